@@ -1,26 +1,173 @@
 use std::sync::Arc;
 
-use arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use arrow::{
+    array::ArrayRef,
+    compute::cast,
+    datatypes::{DataType, Field, FieldRef, Fields, Schema, SchemaRef},
+    error::Result,
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+
+use crate::optimize::optimize_dictionaries;
 
 /// Prepare an arrow Schema for transport over the Arrow Flight protocol
 ///
-/// Converts dictionary types to underlying types due to <https://github.com/apache/arrow-rs/issues/3389>
+/// String dictionaries (`Dictionary(Int32, Utf8)`, used for tags) are preserved and every one of them gets a unique
+/// dictionary ID, so that the client can tell the dictionary batches of different columns apart (see
+/// <https://github.com/apache/arrow-rs/issues/3389>). All other dictionary types are converted to their underlying
+/// types.
+///
+/// Batches sent with this schema must be prepared using [`prepare_batch_for_flight`].
 pub fn prepare_schema_for_flight(schema: SchemaRef) -> SchemaRef {
+    let mut dict_id = 0;
     let fields: Fields = schema
         .fields()
         .iter()
         .map(|field| match field.data_type() {
-            DataType::Dictionary(_, value_type) => Arc::new(
-                Field::new(
-                    field.name(),
-                    value_type.as_ref().clone(),
-                    field.is_nullable(),
-                )
-                .with_metadata(field.metadata().clone()),
-            ),
+            DataType::Dictionary(key_type, value_type) => {
+                if is_string_dictionary(key_type, value_type) {
+                    let dict_field = Field::new_dict(
+                        field.name(),
+                        field.data_type().clone(),
+                        field.is_nullable(),
+                        dict_id,
+                        field.dict_is_ordered().unwrap_or_default(),
+                    )
+                    .with_metadata(field.metadata().clone());
+                    dict_id += 1;
+                    Arc::new(dict_field)
+                } else {
+                    Arc::new(
+                        Field::new(
+                            field.name(),
+                            value_type.as_ref().clone(),
+                            field.is_nullable(),
+                        )
+                        .with_metadata(field.metadata().clone()),
+                    )
+                }
+            }
             _ => Arc::clone(field),
         })
         .collect();
 
     Arc::new(Schema::new(fields).with_metadata(schema.metadata().clone()))
 }
+
+/// Prepare a [`RecordBatch`] for transport over the Arrow Flight protocol using a `schema` that was created by
+/// [`prepare_schema_for_flight`] from the schema of the batch.
+///
+/// String dictionaries are [optimized](optimize_dictionaries) so that only values referenced by this batch are sent,
+/// which matters for sliced batches that otherwise still reference the entire dictionary. Since this always produces
+/// new dictionaries, the dictionaries of every batch are sent to the client. All other dictionaries are converted to
+/// their underlying types.
+pub fn prepare_batch_for_flight(batch: &RecordBatch, schema: SchemaRef) -> Result<RecordBatch> {
+    let batch_schema = batch.schema();
+    if !batch_schema
+        .fields()
+        .iter()
+        .any(|field| matches!(field.data_type(), DataType::Dictionary(_, _)))
+    {
+        return Ok(batch.clone());
+    }
+
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(col, field)| hydrate(col, field))
+        .collect::<Result<Vec<_>>>()?;
+    let batch = RecordBatch::try_new_with_options(
+        schema,
+        columns,
+        &RecordBatchOptions::default().with_row_count(Some(batch.num_rows())),
+    )?;
+
+    optimize_dictionaries(&batch)
+}
+
+/// Convert `col` to the type of the given flight `field` if it is a dictionary that is not sent as such.
+fn hydrate(col: &ArrayRef, field: &FieldRef) -> Result<ArrayRef> {
+    if col.data_type() == field.data_type() {
+        Ok(Arc::clone(col))
+    } else {
+        cast(col, field.data_type())
+    }
+}
+
+fn is_string_dictionary(key_type: &DataType, value_type: &DataType) -> bool {
+    key_type == &DataType::Int32 && value_type == &DataType::Utf8
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{Array, DictionaryArray, Int64Array},
+        datatypes::{Int32Type, Int8Type},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_prepare_for_flight() {
+        let tag_type = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let other_dict_type =
+            DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("t1", tag_type.clone(), true),
+            Field::new("f", DataType::Int64, true),
+            Field::new("t2", tag_type.clone(), true),
+            Field::new("d", other_dict_type, true),
+        ]));
+
+        let flight_schema = prepare_schema_for_flight(Arc::clone(&schema));
+        let fields = flight_schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone(), f.dict_id()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                ("t1", tag_type.clone(), Some(0)),
+                ("f", DataType::Int64, None),
+                ("t2", tag_type, Some(1)),
+                ("d", DataType::Utf8, None),
+            ]
+        );
+
+        let t1: DictionaryArray<Int32Type> = vec!["a", "b", "a", "c"].into_iter().collect();
+        let t2: DictionaryArray<Int32Type> = vec!["x", "x", "y", "z"].into_iter().collect();
+        let d: DictionaryArray<Int8Type> = vec!["u", "v", "u", "v"].into_iter().collect();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(t1) as ArrayRef,
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+                Arc::new(t2),
+                Arc::new(d),
+            ],
+        )
+        .unwrap();
+
+        // only send the dictionary values referenced by the slice
+        let batch = batch.slice(2, 2);
+        let prepared = prepare_batch_for_flight(&batch, Arc::clone(&flight_schema)).unwrap();
+        assert_eq!(prepared.schema(), flight_schema);
+        assert_eq!(prepared.num_rows(), 2);
+
+        let t1 = prepared
+            .column(0)
+            .as_any()
+            .downcast_ref::<DictionaryArray<Int32Type>>()
+            .unwrap();
+        assert_eq!(t1.values().len(), 2);
+        let t2 = prepared
+            .column(2)
+            .as_any()
+            .downcast_ref::<DictionaryArray<Int32Type>>()
+            .unwrap();
+        assert_eq!(t2.values().len(), 2);
+        assert_eq!(prepared.column(3).data_type(), &DataType::Utf8);
+    }
+}
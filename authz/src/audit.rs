@@ -0,0 +1,338 @@
+use std::{
+    fmt::Write,
+    num::NonZeroU64,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use iox_time::TimeProvider;
+use observability_deps::tracing::info;
+use sha2::{Digest, Sha256};
+
+use super::{Action, Authorizer, Error, Permission, Resource};
+
+/// The tracing target of the events emitted by the [`TracingAuditSink`].
+pub const AUDIT_TRACING_TARGET: &str = "authz_audit";
+
+/// The outcome of an authorization decision for a single requested
+/// [`Permission`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditDecision {
+    /// The permission was granted.
+    Granted,
+    /// The permission was denied.
+    Denied,
+    /// No decision could be made, because the token was missing or invalid,
+    /// or could not be verified.
+    Error,
+}
+
+impl AuditDecision {
+    /// A static string representation of the decision.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Granted => "granted",
+            Self::Denied => "denied",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// A record of the authorization decision made for a single requested
+/// [`Permission`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEvent {
+    /// An opaque, stable identifier of the principal making the request,
+    /// derived from a hash of its token.
+    ///
+    /// [`None`] if the request carried no token.
+    pub principal: Option<String>,
+
+    /// The requested permission.
+    pub permission: Permission,
+
+    /// The decision made for [`AuditEvent::permission`].
+    pub decision: AuditDecision,
+
+    /// The time taken by the decorated [`Authorizer`] to make the decision.
+    pub latency: Duration,
+}
+
+/// A destination for [`AuditEvent`] records.
+pub trait AuditSink: std::fmt::Debug + Send + Sync {
+    /// Record `event`.
+    ///
+    /// This is called inline with the authorization request and should not
+    /// block.
+    fn record(&self, event: &AuditEvent);
+}
+
+/// An [`AuditSink`] emitting each [`AuditEvent`] as a structured tracing
+/// event with the [`AUDIT_TRACING_TARGET`] target.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingAuditSink;
+
+impl AuditSink for TracingAuditSink {
+    fn record(&self, event: &AuditEvent) {
+        let Permission::ResourceAction(resource, action) = &event.permission;
+        let (database, table) = match resource {
+            Resource::Database(database) => (database.as_str(), None),
+            Resource::Table { database, table } => (database.as_str(), Some(table.as_str())),
+        };
+
+        info!(
+            target: AUDIT_TRACING_TARGET,
+            principal = event.principal.as_deref().unwrap_or("anonymous"),
+            action = action_str(action),
+            database,
+            table,
+            decision = event.decision.as_str(),
+            latency_us = event.latency.as_micros() as u64,
+            "authz decision"
+        );
+    }
+}
+
+fn action_str(action: &Action) -> &'static str {
+    match action {
+        Action::Create => "create",
+        Action::Delete => "delete",
+        Action::Read => "read",
+        Action::ReadSchema => "read_schema",
+        Action::Write => "write",
+    }
+}
+
+/// Configuration of the sampling performed by an [`AuthorizerAudit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditSamplingConfig {
+    /// Record one in every `sample_every` authorization requests.
+    ///
+    /// A value of 1 records every request.
+    pub sample_every: NonZeroU64,
+
+    /// Record every request that results in a denial or error, regardless
+    /// of [`AuditSamplingConfig::sample_every`].
+    pub always_record_denials: bool,
+}
+
+impl Default for AuditSamplingConfig {
+    fn default() -> Self {
+        Self {
+            sample_every: NonZeroU64::MIN,
+            always_record_denials: true,
+        }
+    }
+}
+
+/// An audit logging decorator over an [`Authorizer`] implementation.
+///
+/// An [`AuditEvent`] is recorded to the [`AuditSink`] for each permission
+/// requested in a sampled call to [`Authorizer::permissions()`]. Raw tokens
+/// are never recorded - the principal is identified by a truncated SHA-256
+/// hash of its token.
+#[derive(Debug)]
+pub struct AuthorizerAudit<T, S = TracingAuditSink> {
+    inner: T,
+    sink: S,
+    config: AuditSamplingConfig,
+    time_provider: Arc<dyn TimeProvider>,
+
+    /// The number of requests made, used to sample requests.
+    requests: AtomicU64,
+}
+
+impl<T, S> AuthorizerAudit<T, S> {
+    /// Record the decisions made by `inner` to `sink`, sampled according to
+    /// `config`.
+    pub fn new(
+        inner: T,
+        sink: S,
+        config: AuditSamplingConfig,
+        time_provider: Arc<dyn TimeProvider>,
+    ) -> Self {
+        Self {
+            inner,
+            sink,
+            config,
+            time_provider,
+            requests: AtomicU64::new(0),
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S> Authorizer for AuthorizerAudit<T, S>
+where
+    T: Authorizer,
+    S: AuditSink,
+{
+    async fn permissions(
+        &self,
+        token: Option<Vec<u8>>,
+        perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        let n = self.requests.fetch_add(1, Ordering::Relaxed);
+        let sampled = n % self.config.sample_every.get() == 0;
+        if !sampled && !self.config.always_record_denials {
+            return self.inner.permissions(token, perms).await;
+        }
+
+        let principal = token.as_deref().map(principal_id);
+
+        let t = self.time_provider.now();
+        let res = self.inner.permissions(token, perms).await;
+        let latency = self
+            .time_provider
+            .now()
+            .checked_duration_since(t)
+            .unwrap_or_default();
+
+        for permission in perms {
+            let decision = match &res {
+                Ok(granted) if granted.contains(permission) => AuditDecision::Granted,
+                Ok(_) | Err(Error::Forbidden) => AuditDecision::Denied,
+                Err(Error::Verification { .. } | Error::InvalidToken | Error::NoToken) => {
+                    AuditDecision::Error
+                }
+            };
+            if !sampled && decision == AuditDecision::Granted {
+                continue;
+            }
+
+            self.sink.record(&AuditEvent {
+                principal: principal.clone(),
+                permission: permission.clone(),
+                decision,
+                latency,
+            });
+        }
+
+        res
+    }
+}
+
+/// Derive a stable principal identifier from `token` that does not reveal
+/// the token itself.
+fn principal_id(token: &[u8]) -> String {
+    Sha256::digest(token)
+        .iter()
+        .take(8)
+        .fold(String::with_capacity(16), |mut s, b| {
+            write!(s, "{b:02x}").expect("write to string cannot fail");
+            s
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use iox_time::{MockProvider, Time};
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::test_util::{perm, ReadOnlyAuthorizer};
+
+    #[derive(Debug, Default)]
+    struct MockSink {
+        events: Mutex<Vec<AuditEvent>>,
+    }
+
+    impl AuditSink for Arc<MockSink> {
+        fn record(&self, event: &AuditEvent) {
+            self.events.lock().push(event.clone());
+        }
+    }
+
+    fn decisions(sink: &MockSink) -> Vec<(Option<String>, Permission, AuditDecision)> {
+        sink.events
+            .lock()
+            .drain(..)
+            .map(|e| (e.principal, e.permission, e.decision))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_audit() {
+        let sink = Arc::new(MockSink::default());
+        let authz = AuthorizerAudit::new(
+            ReadOnlyAuthorizer::default(),
+            Arc::clone(&sink),
+            AuditSamplingConfig::default(),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+        );
+        let principal = Some(principal_id(b"token"));
+
+        let got = authz
+            .permissions(
+                Some(b"token".to_vec()),
+                &[perm(Action::Read), perm(Action::Write)],
+            )
+            .await;
+        assert_matches!(got, Ok(v) if v == [perm(Action::Read)]);
+        assert_eq!(
+            decisions(&sink),
+            [
+                (
+                    principal.clone(),
+                    perm(Action::Read),
+                    AuditDecision::Granted
+                ),
+                (
+                    principal.clone(),
+                    perm(Action::Write),
+                    AuditDecision::Denied
+                ),
+            ]
+        );
+
+        let got = authz.permissions(None, &[perm(Action::Read)]).await;
+        assert_matches!(got, Err(Error::NoToken));
+        assert_eq!(
+            decisions(&sink),
+            [(None, perm(Action::Read), AuditDecision::Error)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audit_sampling() {
+        let sink = Arc::new(MockSink::default());
+        let authz = AuthorizerAudit::new(
+            ReadOnlyAuthorizer::default(),
+            Arc::clone(&sink),
+            AuditSamplingConfig {
+                sample_every: NonZeroU64::new(3).unwrap(),
+                always_record_denials: true,
+            },
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+        );
+
+        for _ in 0..6 {
+            authz
+                .permissions(
+                    Some(b"token".to_vec()),
+                    &[perm(Action::Read), perm(Action::Write)],
+                )
+                .await
+                .unwrap();
+        }
+
+        // Grants are sampled, but every denial is recorded.
+        let got = decisions(&sink);
+        let count = |d| got.iter().filter(|(_, _, v)| *v == d).count();
+        assert_eq!(count(AuditDecision::Granted), 2);
+        assert_eq!(count(AuditDecision::Denied), 6);
+    }
+
+    #[test]
+    fn test_principal_id() {
+        let id = principal_id(b"token");
+        assert_eq!(id.len(), 16);
+        assert_eq!(id, principal_id(b"token"));
+        assert_ne!(id, principal_id(b"other"));
+    }
+}
@@ -35,6 +35,54 @@ pub trait Authorizer: std::fmt::Debug + Send + Sync {
         perms: &[Permission],
     ) -> Result<Vec<Permission>, Error>;
 
+    /// Determine the permissions associated with a request token for each of
+    /// a batch of permission sets, such as one per table accessed by a
+    /// query.
+    ///
+    /// The returned list contains one result per entry in `batch`, in the
+    /// same order: the intersection of the requested permissions and the
+    /// permissions associated with the token, or [`Error::Forbidden`] if
+    /// that intersection is empty. Errors that apply to the token as a whole
+    /// ([`Error::InvalidToken`], [`Error::NoToken`] and
+    /// [`Error::Verification`]) are returned for the batch as a whole.
+    ///
+    /// The default implementation makes a single call to
+    /// [`Authorizer::permissions()`] with the union of all requested
+    /// permissions.
+    async fn authorize_many(
+        &self,
+        token: Option<Vec<u8>>,
+        batch: &[Vec<Permission>],
+    ) -> Result<Vec<Result<Vec<Permission>, Error>>, Error> {
+        let mut union: Vec<Permission> = Vec::new();
+        for perm in batch.iter().flatten() {
+            if !union.contains(perm) {
+                union.push(perm.clone());
+            }
+        }
+
+        let granted = match self.permissions(token, &union).await {
+            Ok(v) => v,
+            Err(Error::Forbidden) => vec![],
+            Err(e) => return Err(e),
+        };
+
+        Ok(batch
+            .iter()
+            .map(|perms| {
+                let perms = perms
+                    .iter()
+                    .filter(|p| granted.contains(p))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if perms.is_empty() {
+                    return Err(Error::Forbidden);
+                }
+                Ok(perms)
+            })
+            .collect())
+    }
+
     /// Make a test request that determines if end-to-end communication
     /// with the service is working.
     ///
@@ -74,6 +122,17 @@ impl<T: Authorizer> Authorizer for Option<T> {
             None => Ok(perms.to_vec()),
         }
     }
+
+    async fn authorize_many(
+        &self,
+        token: Option<Vec<u8>>,
+        batch: &[Vec<Permission>],
+    ) -> Result<Vec<Result<Vec<Permission>, Error>>, Error> {
+        match self {
+            Some(authz) => authz.authorize_many(token, batch).await,
+            None => Ok(batch.iter().map(|perms| Ok(perms.clone())).collect()),
+        }
+    }
 }
 
 #[async_trait]
@@ -85,4 +144,65 @@ impl<T: AsRef<dyn Authorizer> + std::fmt::Debug + Send + Sync> Authorizer for T
     ) -> Result<Vec<Permission>, Error> {
         self.as_ref().permissions(token, perms).await
     }
+
+    async fn authorize_many(
+        &self,
+        token: Option<Vec<u8>>,
+        batch: &[Vec<Permission>],
+    ) -> Result<Vec<Result<Vec<Permission>, Error>>, Error> {
+        self.as_ref().authorize_many(token, batch).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+    use crate::{test_util::ReadOnlyAuthorizer, Action, Resource};
+
+    fn perm(table: &str, action: Action) -> Permission {
+        Permission::ResourceAction(
+            Resource::Table {
+                database: "bananas".to_string(),
+                table: table.to_string(),
+            },
+            action,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_authorize_many() {
+        let authz = ReadOnlyAuthorizer::default();
+        let batch = [
+            vec![perm("cpu", Action::Read)],
+            vec![perm("mem", Action::Read), perm("mem", Action::Write)],
+            vec![perm("disk", Action::Write)],
+            vec![perm("cpu", Action::Read)],
+        ];
+
+        let got = authz
+            .authorize_many(Some(b"token".to_vec()), &batch)
+            .await
+            .unwrap();
+        assert_eq!(got.len(), 4);
+        assert_matches!(&got[0], Ok(v) if *v == [perm("cpu", Action::Read)]);
+        assert_matches!(&got[1], Ok(v) if *v == [perm("mem", Action::Read)]);
+        assert_matches!(&got[2], Err(Error::Forbidden));
+        assert_matches!(&got[3], Ok(v) if *v == [perm("cpu", Action::Read)]);
+
+        // The whole batch is authorized in a single call.
+        assert_eq!(authz.calls(), 1);
+
+        // An entirely denied batch returns a per-item result.
+        let got = authz
+            .authorize_many(Some(b"token".to_vec()), &batch[2..3])
+            .await
+            .unwrap();
+        assert_matches!(got.as_slice(), [Err(Error::Forbidden)]);
+
+        // Token errors apply to the whole batch.
+        let got = authz.authorize_many(None, &batch).await;
+        assert_matches!(got, Err(Error::NoToken));
+    }
 }
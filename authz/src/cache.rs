@@ -0,0 +1,377 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use iox_time::{Time, TimeProvider};
+use metric::{Metric, Registry, U64Counter};
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+
+use super::{Authorizer, Error, Permission};
+
+const AUTHZ_CACHE_METRIC: &str = "authz_cache_requests";
+
+/// A SHA-256 hash of a request token.
+///
+/// Tokens are never retained by the cache in their raw form.
+type TokenHash = [u8; 32];
+
+/// Configuration of an [`AuthorizerCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthorizerCacheConfig {
+    /// The duration an authorization decision is cached for.
+    pub ttl: Duration,
+
+    /// The maximum number of (token, permission) decisions cached.
+    pub max_entries: usize,
+}
+
+impl Default for AuthorizerCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(60),
+            max_entries: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CacheEntry {
+    granted: bool,
+    expires_at: Time,
+}
+
+type CacheKey = (TokenHash, Permission);
+
+/// The cached decisions.
+#[derive(Debug, Default)]
+struct Entries {
+    map: HashMap<CacheKey, CacheEntry>,
+
+    /// The keys in insertion order with their expiry time, which is also the
+    /// order they expire in, as all decisions are cached for the same TTL.
+    ///
+    /// Keys that were evicted or inserted again since are skipped once they
+    /// reach the front.
+    order: VecDeque<(CacheKey, Time)>,
+}
+
+impl Entries {
+    /// Evict expired decisions, and then the oldest decisions until there is
+    /// room for another one.
+    ///
+    /// The cost of each call is amortized by the insertions, as every key in
+    /// [`Self::order`] is only visited once.
+    fn evict(&mut self, now: Time, max_entries: usize) {
+        while let Some((key, expires_at)) = self.order.front() {
+            let current = self.map.get(key).map(|e| e.expires_at);
+            if current == Some(*expires_at) {
+                if *expires_at > now && self.map.len() < max_entries {
+                    break;
+                }
+                self.map.remove(key);
+            }
+            self.order.pop_front();
+        }
+    }
+}
+
+/// A caching decorator over an [`Authorizer`] implementation.
+///
+/// The decision for each (token, [`Permission`]) pair returned by the inner
+/// [`Authorizer`] is cached for [`AuthorizerCacheConfig::ttl`], so repeated
+/// requests with the same token do not call the inner [`Authorizer`] every
+/// time. A request is served from the cache only if a decision for every
+/// requested permission is cached.
+///
+/// Only definitive decisions are cached - [`Error::Verification`],
+/// [`Error::InvalidToken`] and [`Error::NoToken`] errors are always
+/// returned from the inner [`Authorizer`].
+#[derive(Debug)]
+pub struct AuthorizerCache<T> {
+    inner: T,
+    config: AuthorizerCacheConfig,
+    time_provider: Arc<dyn TimeProvider>,
+
+    entries: Mutex<Entries>,
+
+    /// Number of requests served from the cache.
+    hits: U64Counter,
+
+    /// Number of requests passed to the inner [`Authorizer`].
+    misses: U64Counter,
+}
+
+impl<T> AuthorizerCache<T> {
+    /// Cache the decisions made by `inner` according to `config`.
+    pub fn new(
+        registry: &Registry,
+        inner: T,
+        config: AuthorizerCacheConfig,
+        time_provider: Arc<dyn TimeProvider>,
+    ) -> Self {
+        let metric: Metric<U64Counter> = registry.register_metric(
+            AUTHZ_CACHE_METRIC,
+            "number of authz permission checks, by cache hit/miss",
+        );
+
+        Self {
+            inner,
+            config,
+            time_provider,
+            entries: Default::default(),
+            hits: metric.recorder(&[("result", "hit")]),
+            misses: metric.recorder(&[("result", "miss")]),
+        }
+    }
+
+    /// Return the cached decision for each of `perms`, or [`None`] if any
+    /// permission has no unexpired cached decision.
+    fn get(&self, token: &TokenHash, perms: &[Permission]) -> Option<Vec<Permission>> {
+        let now = self.time_provider.now();
+        let entries = self.entries.lock();
+
+        let mut granted = Vec::with_capacity(perms.len());
+        for perm in perms {
+            let entry = entries.map.get(&(*token, perm.clone()))?;
+            if entry.expires_at <= now {
+                return None;
+            }
+            if entry.granted {
+                granted.push(perm.clone());
+            }
+        }
+
+        Some(granted)
+    }
+
+    /// Cache the decision for each of `perms`, granting those contained in
+    /// `granted`.
+    fn insert(&self, token: TokenHash, perms: &[Permission], granted: &[Permission]) {
+        let now = self.time_provider.now();
+        let expires_at = now + self.config.ttl;
+        if self.config.max_entries == 0 {
+            // A max_entries of 0 disables caching.
+            return;
+        }
+        let mut entries = self.entries.lock();
+
+        for perm in perms {
+            entries.evict(now, self.config.max_entries);
+
+            let key = (token, perm.clone());
+            entries.order.push_back((key.clone(), expires_at));
+            entries.map.insert(
+                key,
+                CacheEntry {
+                    granted: granted.contains(perm),
+                    expires_at,
+                },
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl<T> Authorizer for AuthorizerCache<T>
+where
+    T: Authorizer,
+{
+    async fn permissions(
+        &self,
+        token: Option<Vec<u8>>,
+        perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        let token_hash: TokenHash = match &token {
+            Some(t) if !perms.is_empty() => Sha256::digest(t).into(),
+            _ => return self.inner.permissions(token, perms).await,
+        };
+
+        if let Some(granted) = self.get(&token_hash, perms) {
+            self.hits.inc(1);
+            if granted.is_empty() {
+                return Err(Error::Forbidden);
+            }
+            return Ok(granted);
+        }
+
+        self.misses.inc(1);
+        let res = self.inner.permissions(token, perms).await;
+        match &res {
+            Ok(granted) => self.insert(token_hash, perms, granted),
+            Err(Error::Forbidden) => self.insert(token_hash, perms, &[]),
+            Err(Error::Verification { .. } | Error::InvalidToken | Error::NoToken) => {}
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use iox_time::MockProvider;
+    use metric::Attributes;
+
+    use super::*;
+    use crate::{
+        test_util::{perm, ReadOnlyAuthorizer},
+        Action,
+    };
+
+    fn cache_count(metrics: &Registry, result: &'static str) -> u64 {
+        metrics
+            .get_instrument::<Metric<U64Counter>>(AUTHZ_CACHE_METRIC)
+            .expect("failed to read metric")
+            .get_observer(&Attributes::from(&[("result", result)]))
+            .expect("failed to get observer")
+            .fetch()
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let metrics = Registry::default();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let authz = AuthorizerCache::new(
+            &metrics,
+            ReadOnlyAuthorizer::default(),
+            AuthorizerCacheConfig {
+                ttl: Duration::from_secs(10),
+                max_entries: 100,
+            },
+            Arc::clone(&time_provider) as _,
+        );
+        let token = || Some(b"token".to_vec());
+
+        let got = authz
+            .permissions(token(), &[perm(Action::Read), perm(Action::Write)])
+            .await
+            .unwrap();
+        assert_eq!(got, [perm(Action::Read)]);
+        assert_eq!(authz.inner.calls(), 1);
+
+        // Both decisions are served from the cache.
+        let got = authz.permissions(token(), &[perm(Action::Read)]).await;
+        assert_matches!(got, Ok(v) if v == [perm(Action::Read)]);
+        let got = authz.permissions(token(), &[perm(Action::Write)]).await;
+        assert_matches!(got, Err(Error::Forbidden));
+        assert_eq!(authz.inner.calls(), 1);
+
+        // An uncached permission is a miss.
+        let got = authz.permissions(token(), &[perm(Action::Delete)]).await;
+        assert_matches!(got, Err(Error::Forbidden));
+        assert_eq!(authz.inner.calls(), 2);
+
+        // As is a different token.
+        let got = authz
+            .permissions(Some(b"other".to_vec()), &[perm(Action::Read)])
+            .await;
+        assert_matches!(got, Ok(_));
+        assert_eq!(authz.inner.calls(), 3);
+
+        // Invalid tokens are never cached.
+        for _ in 0..2 {
+            let got = authz
+                .permissions(Some(b"bad".to_vec()), &[perm(Action::Read)])
+                .await;
+            assert_matches!(got, Err(Error::InvalidToken));
+        }
+        assert_eq!(authz.inner.calls(), 5);
+
+        // Decisions expire after the TTL.
+        time_provider.inc(Duration::from_secs(10));
+        let got = authz.permissions(token(), &[perm(Action::Read)]).await;
+        assert_matches!(got, Ok(_));
+        assert_eq!(authz.inner.calls(), 6);
+
+        assert_eq!(cache_count(&metrics, "hit"), 2);
+        assert_eq!(cache_count(&metrics, "miss"), 6);
+    }
+
+    #[tokio::test]
+    async fn test_cache_max_entries() {
+        let metrics = Registry::default();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let authz = AuthorizerCache::new(
+            &metrics,
+            ReadOnlyAuthorizer::default(),
+            AuthorizerCacheConfig {
+                ttl: Duration::from_secs(10),
+                max_entries: 2,
+            },
+            Arc::clone(&time_provider) as _,
+        );
+        let request = |token: &'static [u8]| {
+            let authz = &authz;
+            async move {
+                authz
+                    .permissions(Some(token.to_vec()), &[perm(Action::Read)])
+                    .await
+                    .unwrap();
+                authz.inner.calls()
+            }
+        };
+
+        for token in [b"a", b"b", b"c", b"d"] {
+            request(token).await;
+        }
+        assert_eq!(authz.entries.lock().map.len(), 2);
+
+        // The oldest decisions were evicted.
+        assert_eq!(request(b"d").await, 4);
+        assert_eq!(request(b"c").await, 4);
+
+        // Expired decisions are evicted before unexpired ones.
+        time_provider.inc(Duration::from_secs(5));
+        assert_eq!(request(b"a").await, 5);
+        time_provider.inc(Duration::from_secs(5));
+        assert_eq!(request(b"b").await, 6);
+        assert_eq!(request(b"a").await, 6);
+        assert_eq!(authz.entries.lock().map.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_insert_again() {
+        let metrics = Registry::default();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let authz = AuthorizerCache::new(
+            &metrics,
+            ReadOnlyAuthorizer::default(),
+            AuthorizerCacheConfig {
+                ttl: Duration::from_secs(10),
+                max_entries: 3,
+            },
+            Arc::clone(&time_provider) as _,
+        );
+        let token = || Some(b"token".to_vec());
+
+        authz
+            .permissions(token(), &[perm(Action::Read)])
+            .await
+            .unwrap();
+
+        // The request misses because of the uncached permission, so the
+        // decision for the cached one is cached again.
+        time_provider.inc(Duration::from_secs(5));
+        authz
+            .permissions(token(), &[perm(Action::Read), perm(Action::Write)])
+            .await
+            .unwrap();
+        assert_eq!(authz.inner.calls(), 2);
+
+        // The decision is not evicted at its previous expiry.
+        time_provider.inc(Duration::from_secs(5));
+        authz
+            .permissions(Some(b"other".to_vec()), &[perm(Action::Read)])
+            .await
+            .unwrap();
+        authz
+            .permissions(token(), &[perm(Action::Read)])
+            .await
+            .unwrap();
+        assert_eq!(authz.inner.calls(), 3);
+    }
+}
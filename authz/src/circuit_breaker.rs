@@ -0,0 +1,543 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use iox_time::{Time, TimeProvider};
+use metric::{Metric, Registry, U64Counter, U64Gauge};
+use observability_deps::tracing::{info, warn};
+use parking_lot::Mutex;
+
+use super::{Action, Authorizer, Error, Permission};
+
+const AUTHZ_BREAKER_STATE_METRIC: &str = "authz_circuit_breaker_state";
+const AUTHZ_BREAKER_FALLBACK_METRIC: &str = "authz_circuit_breaker_fallback_decisions";
+
+/// The behaviour of an [`AuthorizerCircuitBreaker`] while the inner
+/// [`Authorizer`] is considered unavailable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnavailablePolicy {
+    /// Fail closed - reject all requests with [`Error::Verification`].
+    DenyAll,
+
+    /// Grant the [`Action::Read`] and [`Action::ReadSchema`] permissions of
+    /// any request carrying a token, rejecting all other permissions.
+    AllowReads,
+
+    /// Fail open - grant all permissions of any request carrying a token for
+    /// the given duration after the outage began, and reject all requests
+    /// thereafter.
+    AllowAllFor(Duration),
+}
+
+/// Configuration of an [`AuthorizerCircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// The number of consecutive [`Error::Verification`] errors returned by
+    /// the inner [`Authorizer`] that opens the circuit.
+    pub failure_threshold: usize,
+
+    /// The duration the circuit remains open before a single request is
+    /// allowed through to probe the inner [`Authorizer`].
+    pub open_duration: Duration,
+
+    /// The behaviour while the circuit is open.
+    pub policy: UnavailablePolicy,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(10),
+            policy: UnavailablePolicy::DenyAll,
+        }
+    }
+}
+
+/// The state of the circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Requests are passed to the inner [`Authorizer`], counting the
+    /// consecutive failures.
+    Closed { failures: usize },
+
+    /// Requests are decided by the [`UnavailablePolicy`] until `until`.
+    Open { until: Time },
+
+    /// A single probe request has been passed to the inner [`Authorizer`],
+    /// and all other requests are decided by the [`UnavailablePolicy`].
+    HalfOpen,
+}
+
+impl State {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Closed { .. } => "closed",
+            Self::Open { .. } => "open",
+            Self::HalfOpen => "half_open",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Breaker {
+    state: State,
+
+    /// The time the circuit first opened in the current outage, used by
+    /// [`UnavailablePolicy::AllowAllFor`].
+    outage_start: Option<Time>,
+}
+
+/// A circuit breaking decorator over an [`Authorizer`] implementation,
+/// typically a remote [`IoxAuthorizer`].
+///
+/// After [`CircuitBreakerConfig::failure_threshold`] consecutive
+/// [`Error::Verification`] errors, the circuit opens and requests are no
+/// longer sent to the inner [`Authorizer`]. Instead they are decided by the
+/// configured [`UnavailablePolicy`], giving an outage of the authorization
+/// service a predictable impact. Once
+/// [`CircuitBreakerConfig::open_duration`] has elapsed, a single probe
+/// request is sent to the inner [`Authorizer`], closing the circuit if it
+/// succeeds.
+///
+/// [`IoxAuthorizer`]: crate::IoxAuthorizer
+#[derive(Debug)]
+pub struct AuthorizerCircuitBreaker<T> {
+    inner: T,
+    config: CircuitBreakerConfig,
+    time_provider: Arc<dyn TimeProvider>,
+
+    breaker: Mutex<Breaker>,
+
+    /// A gauge per circuit state, set to 1 for the current state.
+    state_gauges: [(&'static str, U64Gauge); 3],
+
+    /// Number of requests decided by the [`UnavailablePolicy`].
+    fallback_granted: U64Counter,
+    fallback_denied: U64Counter,
+}
+
+impl<T> AuthorizerCircuitBreaker<T> {
+    /// Wrap `inner` in a circuit breaker configured by `config`.
+    pub fn new(
+        registry: &Registry,
+        inner: T,
+        config: CircuitBreakerConfig,
+        time_provider: Arc<dyn TimeProvider>,
+    ) -> Self {
+        let state: Metric<U64Gauge> = registry.register_metric(
+            AUTHZ_BREAKER_STATE_METRIC,
+            "state of the authz circuit breaker, 1 for the current state",
+        );
+        let state_gauges =
+            ["closed", "open", "half_open"].map(|name| (name, state.recorder(&[("state", name)])));
+
+        let fallback: Metric<U64Counter> = registry.register_metric(
+            AUTHZ_BREAKER_FALLBACK_METRIC,
+            "number of authz requests decided by the unavailable policy of an open circuit",
+        );
+
+        let this = Self {
+            inner,
+            config,
+            time_provider,
+            breaker: Mutex::new(Breaker {
+                state: State::Closed { failures: 0 },
+                outage_start: None,
+            }),
+            state_gauges,
+            fallback_granted: fallback.recorder(&[("result", "granted")]),
+            fallback_denied: fallback.recorder(&[("result", "denied")]),
+        };
+        this.set_state_gauges(State::Closed { failures: 0 });
+        this
+    }
+
+    fn set_state_gauges(&self, state: State) {
+        for (name, gauge) in &self.state_gauges {
+            gauge.set(u64::from(*name == state.name()));
+        }
+    }
+
+    /// Returns a [`Permit`] if the request should be sent to the inner
+    /// [`Authorizer`], or [`None`] if it should be decided by the
+    /// [`UnavailablePolicy`].
+    fn try_acquire(&self) -> Option<Permit<'_, T>> {
+        let mut breaker = self.breaker.lock();
+        let probe = match breaker.state {
+            State::Closed { .. } => false,
+            State::Open { until } if self.time_provider.now() >= until => {
+                breaker.state = State::HalfOpen;
+                self.set_state_gauges(breaker.state);
+                true
+            }
+            State::Open { .. } | State::HalfOpen => return None,
+        };
+
+        Some(Permit {
+            breaker: self,
+            probe,
+            recorded: false,
+        })
+    }
+
+    /// Hand out the next probe once a probe request was abandoned before its
+    /// result was recorded, e.g. because the request was cancelled.
+    fn abandon_probe(&self) {
+        let mut breaker = self.breaker.lock();
+        if breaker.state == State::HalfOpen {
+            breaker.state = State::Open {
+                until: self.time_provider.now(),
+            };
+            self.set_state_gauges(breaker.state);
+        }
+    }
+
+    /// Update the circuit state with the result of a request made to the
+    /// inner [`Authorizer`].
+    fn record(&self, available: bool) {
+        let mut breaker = self.breaker.lock();
+        let prev = breaker.state;
+
+        breaker.state = match (prev, available) {
+            (_, true) => {
+                if breaker.outage_start.take().is_some() {
+                    info!("authz service available, closing circuit breaker");
+                }
+                State::Closed { failures: 0 }
+            }
+            (State::Closed { failures }, false) if failures + 1 < self.config.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            (_, false) => {
+                let now = self.time_provider.now();
+                if breaker.outage_start.is_none() {
+                    warn!(
+                        policy=?self.config.policy,
+                        "authz service unavailable, opening circuit breaker"
+                    );
+                    breaker.outage_start = Some(now);
+                }
+                State::Open {
+                    until: now + self.config.open_duration,
+                }
+            }
+        };
+
+        if breaker.state.name() != prev.name() {
+            self.set_state_gauges(breaker.state);
+        }
+    }
+
+    /// Decide a request according to the [`UnavailablePolicy`].
+    fn fallback(&self, perms: &[Permission]) -> Result<Vec<Permission>, Error> {
+        let granted = match self.config.policy {
+            UnavailablePolicy::DenyAll => vec![],
+            UnavailablePolicy::AllowReads => perms
+                .iter()
+                .filter(|p| {
+                    matches!(
+                        p,
+                        Permission::ResourceAction(_, Action::Read | Action::ReadSchema)
+                    )
+                })
+                .cloned()
+                .collect(),
+            UnavailablePolicy::AllowAllFor(d) => {
+                let outage_start = self.breaker.lock().outage_start;
+                match outage_start {
+                    Some(t) if self.time_provider.now() < t + d => perms.to_vec(),
+                    _ => vec![],
+                }
+            }
+        };
+
+        if granted.is_empty() {
+            self.fallback_denied.inc(1);
+            return Err(Error::verification(
+                "authz service unavailable",
+                "circuit breaker open",
+            ));
+        }
+
+        self.fallback_granted.inc(1);
+        Ok(granted)
+    }
+}
+
+/// Permission to send a request to the inner [`Authorizer`] of an
+/// [`AuthorizerCircuitBreaker`].
+///
+/// Dropping a probe permit without [recording](Self::record) its result
+/// (e.g. because the request future was dropped) re-opens the circuit, so the
+/// next request is sent as a new probe instead of the circuit remaining
+/// half-open forever.
+#[derive(Debug)]
+struct Permit<'a, T> {
+    breaker: &'a AuthorizerCircuitBreaker<T>,
+    probe: bool,
+    recorded: bool,
+}
+
+impl<T> Permit<'_, T> {
+    /// Update the circuit state with the result of the request.
+    fn record(mut self, available: bool) {
+        self.recorded = true;
+        self.breaker.record(available);
+    }
+}
+
+impl<T> Drop for Permit<'_, T> {
+    fn drop(&mut self) {
+        if self.probe && !self.recorded {
+            self.breaker.abandon_probe();
+        }
+    }
+}
+
+#[async_trait]
+impl<T> Authorizer for AuthorizerCircuitBreaker<T>
+where
+    T: Authorizer,
+{
+    async fn permissions(
+        &self,
+        token: Option<Vec<u8>>,
+        perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        // A request without a token is never granted by the fallback policy.
+        if token.is_none() {
+            return self.inner.permissions(token, perms).await;
+        }
+
+        let Some(permit) = self.try_acquire() else {
+            return self.fallback(perms);
+        };
+
+        let res = self.inner.permissions(token, perms).await;
+        permit.record(!matches!(res, Err(Error::Verification { .. })));
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use assert_matches::assert_matches;
+    use iox_time::MockProvider;
+    use metric::Attributes;
+
+    use super::*;
+    use crate::test_util::perm;
+
+    /// An [`Authorizer`] granting all permissions while available, counting
+    /// the calls made to it.
+    #[derive(Debug, Default)]
+    struct FlakyAuthorizer {
+        unavailable: AtomicBool,
+        hang: AtomicBool,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Authorizer for FlakyAuthorizer {
+        async fn permissions(
+            &self,
+            _token: Option<Vec<u8>>,
+            perms: &[Permission],
+        ) -> Result<Vec<Permission>, Error> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            if self.hang.load(Ordering::Relaxed) {
+                std::future::pending::<()>().await;
+            }
+            if self.unavailable.load(Ordering::Relaxed) {
+                return Err(Error::verification("unavailable", "connection refused"));
+            }
+            Ok(perms.to_vec())
+        }
+    }
+
+    fn state_gauge(metrics: &Registry, state: &'static str) -> u64 {
+        metrics
+            .get_instrument::<Metric<U64Gauge>>(AUTHZ_BREAKER_STATE_METRIC)
+            .expect("failed to read metric")
+            .get_observer(&Attributes::from(&[("state", state)]))
+            .expect("failed to get observer")
+            .fetch()
+    }
+
+    fn new_breaker(
+        metrics: &Registry,
+        policy: UnavailablePolicy,
+        time_provider: &Arc<MockProvider>,
+    ) -> AuthorizerCircuitBreaker<FlakyAuthorizer> {
+        AuthorizerCircuitBreaker::new(
+            metrics,
+            FlakyAuthorizer::default(),
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_duration: Duration::from_secs(10),
+                policy,
+            },
+            Arc::clone(time_provider) as _,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker() {
+        let metrics = Registry::default();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let authz = new_breaker(&metrics, UnavailablePolicy::DenyAll, &time_provider);
+        let token = || Some(b"token".to_vec());
+        let read = [perm(Action::Read)];
+
+        assert_matches!(authz.permissions(token(), &read).await, Ok(_));
+        assert_eq!(state_gauge(&metrics, "closed"), 1);
+
+        // The circuit opens after two consecutive failures.
+        authz.inner.unavailable.store(true, Ordering::Relaxed);
+        for _ in 0..2 {
+            let got = authz.permissions(token(), &read).await;
+            assert_matches!(got, Err(Error::Verification { .. }));
+        }
+        assert_eq!(authz.inner.calls.load(Ordering::Relaxed), 3);
+        assert_eq!(state_gauge(&metrics, "closed"), 0);
+        assert_eq!(state_gauge(&metrics, "open"), 1);
+
+        // Requests are no longer sent to the inner authorizer.
+        authz.inner.unavailable.store(false, Ordering::Relaxed);
+        let got = authz.permissions(token(), &read).await;
+        assert_matches!(got, Err(Error::Verification { .. }));
+        assert_eq!(authz.inner.calls.load(Ordering::Relaxed), 3);
+
+        // Requests without a token are never decided by the breaker.
+        let got = authz.permissions(None, &read).await;
+        assert_matches!(got, Ok(_));
+        assert_eq!(authz.inner.calls.load(Ordering::Relaxed), 4);
+
+        // Once the open duration has elapsed, a probe request closes the
+        // circuit.
+        time_provider.inc(Duration::from_secs(10));
+        assert_matches!(authz.permissions(token(), &read).await, Ok(_));
+        assert_eq!(authz.inner.calls.load(Ordering::Relaxed), 5);
+        assert_eq!(state_gauge(&metrics, "closed"), 1);
+        assert_eq!(state_gauge(&metrics, "open"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_failed_probe() {
+        let metrics = Registry::default();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let authz = new_breaker(&metrics, UnavailablePolicy::DenyAll, &time_provider);
+        let token = || Some(b"token".to_vec());
+        let read = [perm(Action::Read)];
+
+        authz.inner.unavailable.store(true, Ordering::Relaxed);
+        for _ in 0..2 {
+            let _ = authz.permissions(token(), &read).await;
+        }
+
+        // A failed probe re-opens the circuit immediately.
+        time_provider.inc(Duration::from_secs(10));
+        let got = authz.permissions(token(), &read).await;
+        assert_matches!(got, Err(Error::Verification { .. }));
+        assert_eq!(authz.inner.calls.load(Ordering::Relaxed), 3);
+        assert_eq!(state_gauge(&metrics, "open"), 1);
+
+        let _ = authz.permissions(token(), &read).await;
+        assert_eq!(authz.inner.calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_cancelled_probe() {
+        let metrics = Registry::default();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let authz = new_breaker(&metrics, UnavailablePolicy::DenyAll, &time_provider);
+        let token = || Some(b"token".to_vec());
+        let read = [perm(Action::Read)];
+
+        authz.inner.unavailable.store(true, Ordering::Relaxed);
+        for _ in 0..2 {
+            let _ = authz.permissions(token(), &read).await;
+        }
+
+        // The probe request is cancelled before it completes.
+        time_provider.inc(Duration::from_secs(10));
+        authz.inner.hang.store(true, Ordering::Relaxed);
+        let got =
+            tokio::time::timeout(Duration::from_millis(10), authz.permissions(token(), &read))
+                .await;
+        assert!(got.is_err());
+        assert_eq!(authz.inner.calls.load(Ordering::Relaxed), 3);
+        assert_eq!(state_gauge(&metrics, "open"), 1);
+
+        // The next request is sent as a new probe, closing the circuit.
+        authz.inner.hang.store(false, Ordering::Relaxed);
+        authz.inner.unavailable.store(false, Ordering::Relaxed);
+        assert_matches!(authz.permissions(token(), &read).await, Ok(_));
+        assert_eq!(authz.inner.calls.load(Ordering::Relaxed), 4);
+        assert_eq!(state_gauge(&metrics, "closed"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_policy_allow_reads() {
+        let metrics = Registry::default();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let authz = new_breaker(&metrics, UnavailablePolicy::AllowReads, &time_provider);
+        let token = || Some(b"token".to_vec());
+
+        authz.inner.unavailable.store(true, Ordering::Relaxed);
+        for _ in 0..2 {
+            let _ = authz.permissions(token(), &[]).await;
+        }
+
+        let got = authz
+            .permissions(
+                token(),
+                &[
+                    perm(Action::Read),
+                    perm(Action::Write),
+                    perm(Action::ReadSchema),
+                ],
+            )
+            .await;
+        assert_matches!(got, Ok(v) => {
+            assert_eq!(v, [perm(Action::Read), perm(Action::ReadSchema)]);
+        });
+
+        let got = authz.permissions(token(), &[perm(Action::Write)]).await;
+        assert_matches!(got, Err(Error::Verification { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_policy_allow_all_for() {
+        let metrics = Registry::default();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let authz = new_breaker(
+            &metrics,
+            UnavailablePolicy::AllowAllFor(Duration::from_secs(15)),
+            &time_provider,
+        );
+        let token = || Some(b"token".to_vec());
+        let write = [perm(Action::Write)];
+
+        authz.inner.unavailable.store(true, Ordering::Relaxed);
+        for _ in 0..2 {
+            let _ = authz.permissions(token(), &write).await;
+        }
+        assert_matches!(authz.permissions(token(), &write).await, Ok(_));
+
+        // A failed probe does not extend the fail-open window.
+        time_provider.inc(Duration::from_secs(10));
+        let got = authz.permissions(token(), &write).await;
+        assert_matches!(got, Err(Error::Verification { .. }));
+        assert_matches!(authz.permissions(token(), &write).await, Ok(_));
+
+        time_provider.inc(Duration::from_secs(5));
+        let got = authz.permissions(token(), &write).await;
+        assert_matches!(got, Err(Error::Verification { .. }));
+    }
+}
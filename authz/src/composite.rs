@@ -0,0 +1,280 @@
+//! Combinators composing multiple [`Authorizer`] implementations.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::{Authorizer, Error, Permission};
+
+/// An [`Authorizer`] returning the permissions granted by the first of its
+/// authorizers to grant any of the requested permissions.
+///
+/// Authorizers are consulted in order. If none grant any permissions, the
+/// most severe error is returned, in the order [`Error::Verification`],
+/// [`Error::Forbidden`], [`Error::InvalidToken`] and [`Error::NoToken`] - an
+/// unavailable authorizer is never masked by another denying the request.
+///
+/// This allows, for example, a set of static administrator tokens to be
+/// checked before a remote policy service.
+#[derive(Debug)]
+pub struct FirstMatchAuthorizer {
+    authorizers: Vec<Arc<dyn Authorizer>>,
+}
+
+impl FirstMatchAuthorizer {
+    /// Consult `authorizers` in order, returning the first grant.
+    pub fn new(authorizers: impl IntoIterator<Item = Arc<dyn Authorizer>>) -> Self {
+        Self {
+            authorizers: authorizers.into_iter().collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl Authorizer for FirstMatchAuthorizer {
+    async fn permissions(
+        &self,
+        token: Option<Vec<u8>>,
+        perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        let mut err: Option<Error> = None;
+        for authz in &self.authorizers {
+            match authz.permissions(token.clone(), perms).await {
+                Ok(v) => return Ok(v),
+                Err(e) => {
+                    if err
+                        .as_ref()
+                        .map_or(true, |prev| severity(&e) > severity(prev))
+                    {
+                        err = Some(e);
+                    }
+                }
+            }
+        }
+        Err(err.unwrap_or(Error::Forbidden))
+    }
+}
+
+/// Rank `e` so the most informative error of a [`FirstMatchAuthorizer`] is
+/// returned.
+fn severity(e: &Error) -> u8 {
+    match e {
+        Error::NoToken => 0,
+        Error::InvalidToken => 1,
+        Error::Forbidden => 2,
+        Error::Verification { .. } => 3,
+    }
+}
+
+/// An [`Authorizer`] granting only the permissions granted by all of its
+/// authorizers.
+///
+/// The first error returned by any authorizer is returned, and a request is
+/// [`Error::Forbidden`] if the intersection of the granted permissions is
+/// empty. An [`AllOfAuthorizer`] with no authorizers denies all requests.
+#[derive(Debug)]
+pub struct AllOfAuthorizer {
+    authorizers: Vec<Arc<dyn Authorizer>>,
+}
+
+impl AllOfAuthorizer {
+    /// Require every one of `authorizers` to grant a permission.
+    pub fn new(authorizers: impl IntoIterator<Item = Arc<dyn Authorizer>>) -> Self {
+        Self {
+            authorizers: authorizers.into_iter().collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl Authorizer for AllOfAuthorizer {
+    async fn permissions(
+        &self,
+        token: Option<Vec<u8>>,
+        perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        if self.authorizers.is_empty() {
+            return Err(Error::Forbidden);
+        }
+
+        let mut granted = perms.to_vec();
+        for authz in &self.authorizers {
+            let got = authz.permissions(token.clone(), &granted).await?;
+            granted.retain(|p| got.contains(p));
+            if granted.is_empty() {
+                return Err(Error::Forbidden);
+            }
+        }
+        Ok(granted)
+    }
+}
+
+/// An [`Authorizer`] consulting a `fallback` authorizer only when the
+/// `primary` authorizer is unable to make a decision
+/// ([`Error::Verification`]).
+///
+/// Decisions made by the `primary` authorizer, including denials, are always
+/// returned as-is.
+#[derive(Debug)]
+pub struct FallbackAuthorizer<P, F> {
+    primary: P,
+    fallback: F,
+}
+
+impl<P, F> FallbackAuthorizer<P, F> {
+    /// Consult `fallback` when `primary` is unavailable.
+    pub fn new(primary: P, fallback: F) -> Self {
+        Self { primary, fallback }
+    }
+}
+
+#[async_trait]
+impl<P, F> Authorizer for FallbackAuthorizer<P, F>
+where
+    P: Authorizer,
+    F: Authorizer,
+{
+    async fn permissions(
+        &self,
+        token: Option<Vec<u8>>,
+        perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        match self.primary.permissions(token.clone(), perms).await {
+            Err(Error::Verification { .. }) => self.fallback.permissions(token, perms).await,
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+    use crate::{test_util::perm, Action};
+
+    /// An [`Authorizer`] granting a fixed set of permissions to a single
+    /// token, or returning a fixed error.
+    #[derive(Debug)]
+    enum MockAuthorizer {
+        Grant(&'static [u8], Vec<Permission>),
+        Unavailable,
+    }
+
+    #[async_trait]
+    impl Authorizer for MockAuthorizer {
+        async fn permissions(
+            &self,
+            token: Option<Vec<u8>>,
+            perms: &[Permission],
+        ) -> Result<Vec<Permission>, Error> {
+            let token = token.ok_or(Error::NoToken)?;
+            match self {
+                Self::Unavailable => Err(Error::verification("unavailable", "bananas")),
+                Self::Grant(t, _) if token != *t => Err(Error::InvalidToken),
+                Self::Grant(_, granted) => {
+                    let v = perms
+                        .iter()
+                        .filter(|p| granted.contains(p))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    if v.is_empty() {
+                        return Err(Error::Forbidden);
+                    }
+                    Ok(v)
+                }
+            }
+        }
+    }
+
+    fn admin() -> Arc<dyn Authorizer> {
+        Arc::new(MockAuthorizer::Grant(
+            b"admin",
+            vec![perm(Action::Read), perm(Action::Write)],
+        ))
+    }
+
+    fn reader() -> Arc<dyn Authorizer> {
+        Arc::new(MockAuthorizer::Grant(b"reader", vec![perm(Action::Read)]))
+    }
+
+    fn unavailable() -> Arc<dyn Authorizer> {
+        Arc::new(MockAuthorizer::Unavailable)
+    }
+
+    #[tokio::test]
+    async fn test_first_match() {
+        let authz = FirstMatchAuthorizer::new([admin(), reader()]);
+        let perms = [perm(Action::Read), perm(Action::Write)];
+
+        let got = authz.permissions(Some(b"admin".to_vec()), &perms).await;
+        assert_matches!(got, Ok(v) if v == perms);
+        let got = authz.permissions(Some(b"reader".to_vec()), &perms).await;
+        assert_matches!(got, Ok(v) if v == [perm(Action::Read)]);
+
+        let got = authz
+            .permissions(Some(b"reader".to_vec()), &[perm(Action::Write)])
+            .await;
+        assert_matches!(got, Err(Error::Forbidden));
+        let got = authz.permissions(Some(b"bananas".to_vec()), &perms).await;
+        assert_matches!(got, Err(Error::InvalidToken));
+        let got = authz.permissions(None, &perms).await;
+        assert_matches!(got, Err(Error::NoToken));
+
+        // An unavailable authorizer is not masked by a denial.
+        let authz = FirstMatchAuthorizer::new([admin(), unavailable()]);
+        let got = authz.permissions(Some(b"admin".to_vec()), &perms).await;
+        assert_matches!(got, Ok(_));
+        let got = authz.permissions(Some(b"reader".to_vec()), &perms).await;
+        assert_matches!(got, Err(Error::Verification { .. }));
+
+        let got = FirstMatchAuthorizer::new([])
+            .permissions(Some(b"admin".to_vec()), &perms)
+            .await;
+        assert_matches!(got, Err(Error::Forbidden));
+    }
+
+    #[tokio::test]
+    async fn test_all_of() {
+        let perms = [perm(Action::Read), perm(Action::Write)];
+        let reads_only = || {
+            Arc::new(MockAuthorizer::Grant(b"admin", vec![perm(Action::Read)]))
+                as Arc<dyn Authorizer>
+        };
+
+        let authz = AllOfAuthorizer::new([admin(), reads_only()]);
+        let got = authz.permissions(Some(b"admin".to_vec()), &perms).await;
+        assert_matches!(got, Ok(v) if v == [perm(Action::Read)]);
+        let got = authz
+            .permissions(Some(b"admin".to_vec()), &[perm(Action::Write)])
+            .await;
+        assert_matches!(got, Err(Error::Forbidden));
+
+        let authz = AllOfAuthorizer::new([admin(), unavailable()]);
+        let got = authz.permissions(Some(b"admin".to_vec()), &perms).await;
+        assert_matches!(got, Err(Error::Verification { .. }));
+
+        let got = AllOfAuthorizer::new([])
+            .permissions(Some(b"admin".to_vec()), &perms)
+            .await;
+        assert_matches!(got, Err(Error::Forbidden));
+    }
+
+    #[tokio::test]
+    async fn test_fallback() {
+        let perms = [perm(Action::Read), perm(Action::Write)];
+
+        let authz = FallbackAuthorizer::new(unavailable(), reader());
+        let got = authz.permissions(Some(b"reader".to_vec()), &perms).await;
+        assert_matches!(got, Ok(v) if v == [perm(Action::Read)]);
+
+        // Denials by the primary authorizer are not overridden.
+        let authz = FallbackAuthorizer::new(reader(), admin());
+        let got = authz
+            .permissions(Some(b"reader".to_vec()), &[perm(Action::Write)])
+            .await;
+        assert_matches!(got, Err(Error::Forbidden));
+        let got = authz.permissions(Some(b"admin".to_vec()), &perms).await;
+        assert_matches!(got, Err(Error::InvalidToken));
+    }
+}
@@ -0,0 +1,690 @@
+//! Validation of JWT bearer tokens against a JSON Web Key Set.
+
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use observability_deps::tracing::{debug, warn};
+use parking_lot::RwLock;
+use serde_json::Value;
+use tokio::{sync::Mutex, time::Instant};
+
+use super::{Action, Authorizer, Error, Permission, Resource};
+
+/// The timeout for establishing a connection to the JWKS endpoint.
+const JWKS_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The timeout for a complete JWKS request.
+const JWKS_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Source of the JSON Web Key Set used to validate JWTs.
+#[async_trait]
+pub trait JwksSource: std::fmt::Debug + Send + Sync {
+    /// Fetch the current key set.
+    async fn fetch(&self) -> Result<JwkSet, Error>;
+}
+
+/// A [`JwksSource`] fetching the key set from an HTTP(S) endpoint.
+#[derive(Debug, Clone)]
+pub struct HttpJwksSource {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl HttpJwksSource {
+    /// Fetch the key set from `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .connect_timeout(JWKS_CONNECT_TIMEOUT)
+            .timeout(JWKS_REQUEST_TIMEOUT)
+            .build()
+            .expect("failed to build JWKS HTTP client");
+
+        Self {
+            client,
+            url: url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl JwksSource for HttpJwksSource {
+    async fn fetch(&self) -> Result<JwkSet, Error> {
+        self.client
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| Error::verification("failed to fetch JWKS", e))?
+            .json()
+            .await
+            .map_err(|e| Error::verification("failed to decode JWKS", e))
+    }
+}
+
+/// Configuration of a [`JwtAuthorizer`].
+#[derive(Debug, Clone)]
+pub struct JwtAuthorizerConfig {
+    /// The required `iss` claim, if any.
+    pub issuer: Option<String>,
+
+    /// The required `aud` claim, if any.
+    pub audience: Option<String>,
+
+    /// The name of the claim containing the permissions granted to the
+    /// token.
+    ///
+    /// The claim must be either an array of permission strings, or a single
+    /// string of space-separated permission strings (as used by the OAuth 2.0
+    /// `scope` claim). See [`parse_permission()`] for the permission format.
+    pub permissions_claim: String,
+
+    /// The maximum age of the cached key set before it is re-fetched.
+    pub refresh_interval: Duration,
+
+    /// The minimum duration between key set fetches triggered by tokens
+    /// signed with an unknown key, bounding the fetch rate when presented
+    /// with garbage tokens.
+    pub min_refresh_interval: Duration,
+
+    /// The signing algorithms accepted for keys that do not declare their
+    /// `alg`.
+    ///
+    /// Keys declaring their `alg` only accept tokens signed with that
+    /// algorithm. The `alg` of the token header is never trusted on its own.
+    pub algorithms: Vec<Algorithm>,
+}
+
+impl Default for JwtAuthorizerConfig {
+    fn default() -> Self {
+        Self {
+            issuer: None,
+            audience: None,
+            permissions_claim: "permissions".to_string(),
+            refresh_interval: Duration::from_secs(60 * 60),
+            min_refresh_interval: Duration::from_secs(30),
+            algorithms: vec![Algorithm::RS256, Algorithm::ES256],
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedKeys {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// Authorizer implementation validating JWTs locally, using keys fetched
+/// from a JSON Web Key Set (JWKS) endpoint.
+///
+/// The key set is cached, and re-fetched once it is older than
+/// [`JwtAuthorizerConfig::refresh_interval`] or when a token signed with an
+/// unknown key ID is presented, so signing keys can be rotated without
+/// restarting. Validating a token therefore does not require a network
+/// round trip per request.
+///
+/// The permissions of a token are read from the
+/// [`JwtAuthorizerConfig::permissions_claim`] claim, and may use
+/// [wildcards](crate::WILDCARD) to grant access to many resources.
+#[derive(Debug)]
+pub struct JwtAuthorizer<S = HttpJwksSource> {
+    source: S,
+    config: JwtAuthorizerConfig,
+
+    /// The cached key set, which is never locked across a fetch.
+    keys: RwLock<Option<Arc<CachedKeys>>>,
+
+    /// Held while fetching the key set, so concurrent requests share a
+    /// single fetch.
+    fetch: Mutex<()>,
+}
+
+impl<S> JwtAuthorizer<S>
+where
+    S: JwksSource,
+{
+    /// Construct a new [`JwtAuthorizer`] validating tokens against the keys
+    /// from `source`.
+    pub fn new(source: S, config: JwtAuthorizerConfig) -> Self {
+        Self {
+            source,
+            config,
+            keys: Default::default(),
+            fetch: Default::default(),
+        }
+    }
+
+    /// Return the key with ID `kid`, fetching the key set if the cache is
+    /// stale or does not contain it.
+    ///
+    /// Requests that can be validated with the cached keys never wait for a
+    /// fetch. If the cache is stale, a fetch is only started if none is in
+    /// progress.
+    async fn key(&self, kid: Option<&str>) -> Result<Jwk, Error> {
+        let cached = self.keys.read().clone();
+
+        let (stale, may_refresh, known) = match &cached {
+            Some(c) => (
+                c.fetched_at.elapsed() >= self.config.refresh_interval,
+                c.fetched_at.elapsed() >= self.config.min_refresh_interval,
+                find_key(&c.keys, kid).is_some(),
+            ),
+            None => (true, true, false),
+        };
+
+        let cached = if !known && may_refresh {
+            // The key cannot be found without a fetch, so wait for the fetch
+            // in progress, if any, or start one.
+            let _guard = self.fetch.lock().await;
+            let current = self.keys.read().clone();
+            match (&cached, &current) {
+                // Another request fetched the key set in the meantime.
+                (Some(before), Some(now)) if !Arc::ptr_eq(before, now) => current,
+                (None, Some(_)) => current,
+                _ => self.refresh(current).await?,
+            }
+        } else if stale {
+            match self.fetch.try_lock() {
+                Ok(_guard) => self.refresh(cached).await?,
+                // A fetch is in progress, use the stale keys meanwhile.
+                Err(_) => cached,
+            }
+        } else {
+            cached
+        };
+
+        cached
+            .as_ref()
+            .and_then(|c| find_key(&c.keys, kid))
+            .cloned()
+            .ok_or(Error::InvalidToken)
+    }
+
+    /// Fetch the key set, replacing the `cached` one.
+    ///
+    /// Must be called while holding the fetch lock.
+    async fn refresh(
+        &self,
+        cached: Option<Arc<CachedKeys>>,
+    ) -> Result<Option<Arc<CachedKeys>>, Error> {
+        debug!("fetching JWKS");
+        match self.source.fetch().await {
+            Ok(keys) => {
+                let keys = Arc::new(CachedKeys {
+                    keys,
+                    fetched_at: Instant::now(),
+                });
+                *self.keys.write() = Some(Arc::clone(&keys));
+                Ok(Some(keys))
+            }
+            // Continue using the stale key set until it can be refreshed, if
+            // there is one.
+            Err(e) if cached.is_some() => {
+                warn!(error=%e, "failed to refresh JWKS, using cached keys");
+                Ok(cached)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Return the algorithms accepted for tokens signed with `jwk`.
+    fn algorithms(&self, jwk: &Jwk) -> Result<Vec<Algorithm>, Error> {
+        match jwk.common.key_algorithm {
+            Some(alg) => Algorithm::from_str(&alg.to_string())
+                .map(|alg| vec![alg])
+                .map_err(|e| Error::verification("invalid JWK algorithm", e)),
+            None => Ok(self.config.algorithms.clone()),
+        }
+    }
+}
+
+#[async_trait]
+impl<S> Authorizer for JwtAuthorizer<S>
+where
+    S: JwksSource,
+{
+    async fn permissions(
+        &self,
+        token: Option<Vec<u8>>,
+        requested_perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        let token = token.ok_or(Error::NoToken)?;
+        let token = std::str::from_utf8(&token).map_err(|_| Error::InvalidToken)?;
+
+        let header = decode_header(token).map_err(|_| Error::InvalidToken)?;
+        let jwk = self.key(header.kid.as_deref()).await?;
+
+        // Never trust the algorithm of the (attacker controlled) token
+        // header on its own.
+        let algorithms = self.algorithms(&jwk)?;
+        if !algorithms.contains(&header.alg) {
+            debug!(alg=?header.alg, ?algorithms, "JWT signed with unexpected algorithm");
+            return Err(Error::InvalidToken);
+        }
+        let key = DecodingKey::from_jwk(&jwk).map_err(|e| Error::verification("invalid JWK", e))?;
+
+        let mut validation = Validation::new(header.alg);
+        match &self.config.issuer {
+            Some(iss) => validation.set_issuer(&[iss]),
+            None => validation.iss = None,
+        }
+        match &self.config.audience {
+            Some(aud) => validation.set_audience(&[aud]),
+            None => validation.validate_aud = false,
+        }
+
+        let claims = decode::<HashMap<String, Value>>(token, &key, &validation)
+            .map_err(|e| {
+                debug!(error=%e, "JWT validation failed");
+                Error::InvalidToken
+            })?
+            .claims;
+
+        let granted = match claims.get(&self.config.permissions_claim) {
+            Some(Value::Array(v)) => v
+                .iter()
+                .filter_map(Value::as_str)
+                .filter_map(parse_permission)
+                .collect(),
+            Some(Value::String(v)) => v.split_whitespace().filter_map(parse_permission).collect(),
+            _ => Vec::new(),
+        };
+
+        let intersected_perms = requested_perms
+            .iter()
+            .filter(|req| granted.iter().any(|g: &Permission| g.grants(req)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if intersected_perms.is_empty() {
+            return Err(Error::Forbidden);
+        }
+        Ok(intersected_perms)
+    }
+}
+
+fn find_key<'a>(keys: &'a JwkSet, kid: Option<&str>) -> Option<&'a Jwk> {
+    match kid {
+        Some(kid) => keys.find(kid),
+        // Tokens without a key ID can only be validated against a key set
+        // containing a single key.
+        None if keys.keys.len() == 1 => keys.keys.first(),
+        None => None,
+    }
+}
+
+/// Parse a permission string of the form `<action>:<database>` or
+/// `<action>:<database>/<table>`, returning [`None`] if it is not a
+/// recognised permission.
+///
+/// The action is one of `read_schema`, `read`, `write`, `create` or
+/// `delete`, and the names may use [wildcards](crate::WILDCARD).
+///
+/// ```
+/// use authz::{jwt::parse_permission, Action, Permission, Resource};
+///
+/// assert_eq!(
+///     parse_permission("write:bananas/*"),
+///     Some(Permission::ResourceAction(
+///         Resource::Table {
+///             database: "bananas".to_string(),
+///             table: "*".to_string(),
+///         },
+///         Action::Write,
+///     ))
+/// );
+/// ```
+pub fn parse_permission(s: &str) -> Option<Permission> {
+    let (action, resource) = s.split_once(':')?;
+    let action = match action {
+        "read_schema" => Action::ReadSchema,
+        "read" => Action::Read,
+        "write" => Action::Write,
+        "create" => Action::Create,
+        "delete" => Action::Delete,
+        _ => return None,
+    };
+    let resource = match resource.split_once('/') {
+        Some((database, table)) if !database.is_empty() && !table.is_empty() => Resource::Table {
+            database: database.to_string(),
+            table: table.to_string(),
+        },
+        None if !resource.is_empty() => Resource::Database(resource.to_string()),
+        _ => return None,
+    };
+    Some(Permission::ResourceAction(resource, action))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use assert_matches::assert_matches;
+    use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    use parking_lot::Mutex as SyncMutex;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_util::db_perm;
+
+    /// A [`JwksSource`] returning a configurable set of symmetric keys.
+    #[derive(Debug, Default)]
+    struct MockSource {
+        secrets: SyncMutex<Vec<(&'static str, &'static [u8])>>,
+        fetches: AtomicUsize,
+
+        /// Whether the keys do not declare their `alg`.
+        omit_alg: bool,
+    }
+
+    impl MockSource {
+        fn with_secrets(secrets: Vec<(&'static str, &'static [u8])>) -> Self {
+            Self {
+                secrets: SyncMutex::new(secrets),
+                fetches: Default::default(),
+                omit_alg: false,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl JwksSource for MockSource {
+        async fn fetch(&self) -> Result<JwkSet, Error> {
+            self.fetches.fetch_add(1, Ordering::Relaxed);
+            // Allow concurrent requests to observe the fetch in progress.
+            tokio::task::yield_now().await;
+            let keys = self
+                .secrets
+                .lock()
+                .iter()
+                .map(|(kid, secret)| {
+                    let mut jwk = json!({
+                        "kty": "oct",
+                        "kid": kid,
+                        "alg": "HS256",
+                        "k": BASE64_URL_SAFE_NO_PAD.encode(secret),
+                    });
+                    if self.omit_alg {
+                        jwk.as_object_mut().unwrap().remove("alg");
+                    }
+                    jwk
+                })
+                .collect::<Vec<_>>();
+            Ok(serde_json::from_value(json!({ "keys": keys })).unwrap())
+        }
+    }
+
+    fn token(kid: &str, secret: &[u8], claims: Value) -> Vec<u8> {
+        token_with_alg(Algorithm::HS256, kid, secret, claims)
+    }
+
+    fn token_with_alg(alg: Algorithm, kid: &str, secret: &[u8], claims: Value) -> Vec<u8> {
+        let mut header = Header::new(alg);
+        header.kid = Some(kid.to_string());
+        encode(&header, &claims, &EncodingKey::from_secret(secret))
+            .unwrap()
+            .into_bytes()
+    }
+
+    fn exp() -> u64 {
+        jsonwebtoken::get_current_timestamp() + 3600
+    }
+
+    #[test]
+    fn test_parse_permission() {
+        assert_eq!(
+            parse_permission("read:bananas"),
+            Some(db_perm("bananas", Action::Read))
+        );
+        assert_eq!(
+            parse_permission("read_schema:*"),
+            Some(db_perm("*", Action::ReadSchema))
+        );
+        assert_eq!(
+            parse_permission("delete:bananas/cpu"),
+            Some(Permission::ResourceAction(
+                Resource::Table {
+                    database: "bananas".to_string(),
+                    table: "cpu".to_string()
+                },
+                Action::Delete
+            ))
+        );
+        assert_eq!(parse_permission("read"), None);
+        assert_eq!(parse_permission("read:"), None);
+        assert_eq!(parse_permission("read:bananas/"), None);
+        assert_eq!(parse_permission("eat:bananas"), None);
+    }
+
+    #[tokio::test]
+    async fn test_jwt_authorizer() {
+        let authz = JwtAuthorizer::new(
+            MockSource::with_secrets(vec![("key-1", b"secret-1")]),
+            JwtAuthorizerConfig {
+                issuer: Some("iox".to_string()),
+                ..Default::default()
+            },
+        );
+
+        // Array of permissions
+        let got = authz
+            .permissions(
+                Some(token(
+                    "key-1",
+                    b"secret-1",
+                    json!({"iss": "iox", "exp": exp(), "permissions": ["read:*", "write:bananas"]}),
+                )),
+                &[
+                    db_perm("bananas", Action::Read),
+                    db_perm("bananas", Action::Write),
+                    db_perm("platanos", Action::Write),
+                ],
+            )
+            .await;
+        assert_matches!(got, Ok(v) => {
+            assert_eq!(v, [db_perm("bananas", Action::Read), db_perm("bananas", Action::Write)]);
+        });
+
+        // Space-separated permissions
+        let got = authz
+            .permissions(
+                Some(token(
+                    "key-1",
+                    b"secret-1",
+                    json!({"iss": "iox", "exp": exp(), "permissions": "read:bananas write:bananas"}),
+                )),
+                &[db_perm("bananas", Action::Write)],
+            )
+            .await;
+        assert_matches!(got, Ok(_));
+
+        // Insufficient permissions
+        let got = authz
+            .permissions(
+                Some(token(
+                    "key-1",
+                    b"secret-1",
+                    json!({"iss": "iox", "exp": exp(), "permissions": ["read:bananas"]}),
+                )),
+                &[db_perm("bananas", Action::Write)],
+            )
+            .await;
+        assert_matches!(got, Err(Error::Forbidden));
+
+        // Wrong issuer
+        let got = authz
+            .permissions(
+                Some(token(
+                    "key-1",
+                    b"secret-1",
+                    json!({"iss": "bananas", "exp": exp(), "permissions": ["read:*"]}),
+                )),
+                &[db_perm("bananas", Action::Read)],
+            )
+            .await;
+        assert_matches!(got, Err(Error::InvalidToken));
+
+        // Expired
+        let got = authz
+            .permissions(
+                Some(token(
+                    "key-1",
+                    b"secret-1",
+                    json!({"iss": "iox", "exp": 1, "permissions": ["read:*"]}),
+                )),
+                &[db_perm("bananas", Action::Read)],
+            )
+            .await;
+        assert_matches!(got, Err(Error::InvalidToken));
+
+        // Bad signature
+        let got = authz
+            .permissions(
+                Some(token(
+                    "key-1",
+                    b"bananas",
+                    json!({"iss": "iox", "exp": exp(), "permissions": ["read:*"]}),
+                )),
+                &[db_perm("bananas", Action::Read)],
+            )
+            .await;
+        assert_matches!(got, Err(Error::InvalidToken));
+
+        // Not a JWT
+        let got = authz
+            .permissions(Some(b"UGLY".to_vec()), &[db_perm("bananas", Action::Read)])
+            .await;
+        assert_matches!(got, Err(Error::InvalidToken));
+
+        // The key set was only fetched once.
+        assert_eq!(authz.source.fetches.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_jwt_authorizer_key_rotation() {
+        let authz = JwtAuthorizer::new(
+            MockSource::with_secrets(vec![("key-1", b"secret-1")]),
+            JwtAuthorizerConfig {
+                min_refresh_interval: Duration::ZERO,
+                ..Default::default()
+            },
+        );
+        let claims = json!({"exp": exp(), "permissions": ["read:*"]});
+        let requested = [db_perm("bananas", Action::Read)];
+
+        let got = authz
+            .permissions(
+                Some(token("key-1", b"secret-1", claims.clone())),
+                &requested,
+            )
+            .await;
+        assert_matches!(got, Ok(_));
+        assert_eq!(authz.source.fetches.load(Ordering::Relaxed), 1);
+
+        // Rotate the signing key.
+        *authz.source.secrets.lock() = vec![("key-2", b"secret-2")];
+
+        // A token signed with the new key causes the key set to be re-fetched.
+        let got = authz
+            .permissions(
+                Some(token("key-2", b"secret-2", claims.clone())),
+                &requested,
+            )
+            .await;
+        assert_matches!(got, Ok(_));
+        assert_eq!(authz.source.fetches.load(Ordering::Relaxed), 2);
+
+        // And tokens signed with the old key are no longer valid.
+        let got = authz
+            .permissions(Some(token("key-1", b"secret-1", claims)), &requested)
+            .await;
+        assert_matches!(got, Err(Error::InvalidToken));
+    }
+
+    #[tokio::test]
+    async fn test_jwt_authorizer_algorithm() {
+        let claims = json!({"exp": exp(), "permissions": ["read:*"]});
+        let requested = [db_perm("bananas", Action::Read)];
+
+        // The key declares HS256, so a token claiming HS384 is rejected even
+        // though it is signed with the right secret.
+        let authz = JwtAuthorizer::new(
+            MockSource::with_secrets(vec![("key-1", b"secret-1")]),
+            Default::default(),
+        );
+        let got = authz
+            .permissions(
+                Some(token_with_alg(
+                    Algorithm::HS384,
+                    "key-1",
+                    b"secret-1",
+                    claims.clone(),
+                )),
+                &requested,
+            )
+            .await;
+        assert_matches!(got, Err(Error::InvalidToken));
+
+        // Keys without an `alg` only accept the configured algorithms.
+        let authz = JwtAuthorizer::new(
+            MockSource {
+                omit_alg: true,
+                ..MockSource::with_secrets(vec![("key-1", b"secret-1")])
+            },
+            JwtAuthorizerConfig {
+                algorithms: vec![Algorithm::HS512],
+                ..Default::default()
+            },
+        );
+        let got = authz
+            .permissions(
+                Some(token("key-1", b"secret-1", claims.clone())),
+                &requested,
+            )
+            .await;
+        assert_matches!(got, Err(Error::InvalidToken));
+        let got = authz
+            .permissions(
+                Some(token_with_alg(
+                    Algorithm::HS512,
+                    "key-1",
+                    b"secret-1",
+                    claims,
+                )),
+                &requested,
+            )
+            .await;
+        assert_matches!(got, Ok(_));
+    }
+
+    #[tokio::test]
+    async fn test_jwt_authorizer_single_fetch() {
+        let authz = Arc::new(JwtAuthorizer::new(
+            MockSource::with_secrets(vec![("key-1", b"secret-1")]),
+            Default::default(),
+        ));
+        let claims = json!({"exp": exp(), "permissions": ["read:*"]});
+
+        // Concurrent requests on an empty cache share a single fetch.
+        let requests = (0..10)
+            .map(|_| {
+                let authz = Arc::clone(&authz);
+                let token = token("key-1", b"secret-1", claims.clone());
+                tokio::spawn(async move {
+                    authz
+                        .permissions(Some(token), &[db_perm("bananas", Action::Read)])
+                        .await
+                })
+            })
+            .collect::<Vec<_>>();
+        for request in requests {
+            assert_matches!(request.await.unwrap(), Ok(_));
+        }
+        assert_eq!(authz.source.fetches.load(Ordering::Relaxed), 1);
+    }
+}
@@ -25,18 +25,39 @@ use base64::{prelude::BASE64_STANDARD, Engine};
 use generated_types::influxdata::iox::authz::v1::{self as proto};
 use observability_deps::tracing::warn;
 
+mod audit;
+pub use audit::{
+    AuditDecision, AuditEvent, AuditSamplingConfig, AuditSink, AuthorizerAudit, TracingAuditSink,
+    AUDIT_TRACING_TARGET,
+};
 mod authorizer;
 pub use authorizer::Authorizer;
+mod cache;
+pub use cache::{AuthorizerCache, AuthorizerCacheConfig};
+mod circuit_breaker;
+pub use circuit_breaker::{AuthorizerCircuitBreaker, CircuitBreakerConfig, UnavailablePolicy};
+mod composite;
+pub use composite::{AllOfAuthorizer, FallbackAuthorizer, FirstMatchAuthorizer};
 mod iox_authorizer;
 pub use iox_authorizer::{Error, IoxAuthorizer};
 mod instrumentation;
 pub use instrumentation::AuthorizerInstrumentation;
 mod permission;
-pub use permission::{Action, Permission, Resource};
+pub use permission::{Action, Permission, Resource, WILDCARD};
+#[cfg(feature = "mtls")]
+mod mtls;
+#[cfg(feature = "mtls")]
+pub use mtls::MtlsAuthorizer;
 
 #[cfg(feature = "http")]
 pub mod http;
 
+#[cfg(test)]
+mod test_util;
+
+#[cfg(feature = "jwt")]
+pub mod jwt;
+
 /// Extract a token from an HTTP header or gRPC metadata value.
 pub fn extract_token<T: AsRef<[u8]> + ?Sized>(value: Option<&T>) -> Option<Vec<u8>> {
     let mut parts = value?.as_ref().splitn(2, |&v| v == b' ');
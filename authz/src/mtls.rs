@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use x509_parser::prelude::{FromDer, GeneralName, X509Certificate};
+
+use super::{Error, Permission};
+
+/// Authorizer deriving the principal of a request from the client
+/// certificate of a mutually-authenticated TLS connection, instead of a
+/// bearer token.
+///
+/// The certificate is always taken from the TLS connection of the request
+/// (see [`tonic::Request::peer_certs()`]) and assumed to have been verified
+/// during the TLS handshake. It is never taken from the request metadata,
+/// where a client could present any certificate. For the same reason, this
+/// does not implement [`Authorizer`](crate::Authorizer), whose token is
+/// extracted from the request metadata.
+///
+/// The principals of a certificate are its DNS, URI and email subject
+/// alternative names, and its subject common names. The permissions of a
+/// request are the union of the permissions mapped to each of its
+/// principals.
+#[derive(Debug, Clone, Default)]
+pub struct MtlsAuthorizer {
+    principals: HashMap<String, Vec<Permission>>,
+}
+
+impl MtlsAuthorizer {
+    /// Construct a new [`MtlsAuthorizer`] granting each principal the
+    /// associated set of permissions.
+    ///
+    /// Granted permissions may use [wildcards](crate::WILDCARD) to grant
+    /// access to many resources.
+    pub fn new(principals: impl IntoIterator<Item = (String, Vec<Permission>)>) -> Self {
+        Self {
+            principals: principals.into_iter().collect(),
+        }
+    }
+
+    /// Determine the permissions of the client certificate of `request`.
+    ///
+    /// Returns the subset of `requested_perms` that is granted, like
+    /// [`Authorizer::permissions()`](crate::Authorizer::permissions). If the
+    /// connection of `request` does not use mutual TLS, [`Error::NoToken`] is
+    /// returned.
+    pub fn permissions<T>(
+        &self,
+        request: &tonic::Request<T>,
+        requested_perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        let certs = request.peer_certs();
+        let cert = certs
+            .as_deref()
+            .and_then(|certs| certs.first())
+            .map(|cert| cert.get_ref());
+        self.certificate_permissions(cert, requested_perms)
+    }
+
+    fn certificate_permissions(
+        &self,
+        cert: Option<&[u8]>,
+        requested_perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        let cert = cert.ok_or(Error::NoToken)?;
+        let principals = certificate_principals(cert)?;
+
+        let granted = principals
+            .iter()
+            .filter_map(|p| self.principals.get(p))
+            .flatten()
+            .collect::<Vec<_>>();
+
+        let intersected_perms = requested_perms
+            .iter()
+            .filter(|req| granted.iter().any(|g| g.grants(req)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if intersected_perms.is_empty() {
+            return Err(Error::Forbidden);
+        }
+        Ok(intersected_perms)
+    }
+}
+
+/// Return the principal names of the DER-encoded certificate `der`.
+fn certificate_principals(der: &[u8]) -> Result<Vec<String>, Error> {
+    let (_, cert) = X509Certificate::from_der(der).map_err(|_| Error::InvalidToken)?;
+
+    let mut principals = Vec::new();
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        for name in &san.value.general_names {
+            match name {
+                GeneralName::DNSName(v) | GeneralName::URI(v) | GeneralName::RFC822Name(v) => {
+                    principals.push(v.to_string())
+                }
+                _ => {}
+            }
+        }
+    }
+    principals.extend(
+        cert.subject()
+            .iter_common_name()
+            .filter_map(|cn| cn.as_str().ok())
+            .map(ToString::to_string),
+    );
+
+    Ok(principals)
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use base64::{prelude::BASE64_STANDARD, Engine};
+
+    use super::*;
+    use crate::{test_util::db_perm, Action};
+
+    /// A self-signed certificate with the common name "ingester", and the
+    /// subject alternative names "ingester.iox.svc" (DNS) and
+    /// "spiffe://iox/ingester" (URI).
+    const TEST_CERT: &str = "\
+        MIIBtDCCAVmgAwIBAgIUON+N07wo6eLBgUElJMfCHqdwX10wCgYIKoZIzj0EAwIwEzERMA8GA1UEAwwIaW5nZXN0ZXIw\
+        IBcNMjYxMDE4MDAzNzM2WhgPMjEyNjA5MjQwMDM3MzZaMBMxETAPBgNVBAMMCGluZ2VzdGVyMFkwEwYHKoZIzj0CAQYI\
+        KoZIzj0DAQcDQgAElrF3V4I85cvBnhA6eWG0lr+XChlZEoZQfwKxOu/AuukJ9zuRjScG9G/PpXxas84n3aYG7epB/+52\
+        OFqHuctLiqOBiDCBhTAdBgNVHQ4EFgQUU/hj1bhBhgtiUOmvmy4AFRL6yhwwHwYDVR0jBBgwFoAUU/hj1bhBhgtiUOmv\
+        my4AFRL6yhwwDwYDVR0TAQH/BAUwAwEB/zAyBgNVHREEKzApghBpbmdlc3Rlci5pb3guc3ZjhhVzcGlmZmU6Ly9pb3gv\
+        aW5nZXN0ZXIwCgYIKoZIzj0EAwIDSQAwRgIhAJ5GNkbLHgzYBdQInFdFMaz62s5c0eqdGnyiDWpCzqkNAiEAiK+5Oqf5\
+        lNHjF89ZbpEmSMfEpl28x9HevoSrPtyq43g=";
+
+    fn test_cert() -> Vec<u8> {
+        BASE64_STANDARD.decode(TEST_CERT).unwrap()
+    }
+
+    #[test]
+    fn test_certificate_principals() {
+        assert_eq!(
+            certificate_principals(&test_cert()).unwrap(),
+            ["ingester.iox.svc", "spiffe://iox/ingester", "ingester"]
+        );
+        assert_matches!(certificate_principals(b"UGLY"), Err(Error::InvalidToken));
+    }
+
+    #[test]
+    fn test_mtls_authorizer() {
+        let authz = MtlsAuthorizer::new([
+            (
+                "spiffe://iox/ingester".to_string(),
+                vec![db_perm("*", Action::Write)],
+            ),
+            (
+                "ingester".to_string(),
+                vec![db_perm("bananas", Action::Read)],
+            ),
+            ("querier".to_string(), vec![db_perm("*", Action::Delete)]),
+        ]);
+
+        let got = authz.certificate_permissions(
+            Some(test_cert().as_slice()),
+            &[
+                db_perm("bananas", Action::Write),
+                db_perm("bananas", Action::Read),
+                db_perm("bananas", Action::Delete),
+                db_perm("platanos", Action::Read),
+            ],
+        );
+        assert_matches!(got, Ok(v) => {
+            assert_eq!(v, [db_perm("bananas", Action::Write), db_perm("bananas", Action::Read)]);
+        });
+
+        let got = authz.certificate_permissions(
+            Some(test_cert().as_slice()),
+            &[db_perm("bananas", Action::Delete)],
+        );
+        assert_matches!(got, Err(Error::Forbidden));
+
+        let got = authz.certificate_permissions(
+            Some(b"UGLY".as_slice()),
+            &[db_perm("bananas", Action::Read)],
+        );
+        assert_matches!(got, Err(Error::InvalidToken));
+
+        let got = authz.certificate_permissions(None, &[db_perm("bananas", Action::Read)]);
+        assert_matches!(got, Err(Error::NoToken));
+
+        // a certificate in the request metadata is ignored
+        let mut request = tonic::Request::new(());
+        request.metadata_mut().insert_bin(
+            "authorization-bin",
+            tonic::metadata::MetadataValue::from_bytes(&test_cert()),
+        );
+        request.metadata_mut().insert(
+            "authorization",
+            format!("Bearer {TEST_CERT}").parse().unwrap(),
+        );
+        let got = authz.permissions(&request, &[db_perm("bananas", Action::Read)]);
+        assert_matches!(got, Err(Error::NoToken));
+    }
+}
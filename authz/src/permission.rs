@@ -2,7 +2,7 @@ use super::proto;
 use snafu::Snafu;
 
 /// Action is the type of operation being attempted on a resource.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Action {
     /// The create action is used when a new instance of the resource will
     /// be created.
@@ -61,7 +61,7 @@ pub struct IncompatiblePermissionError {}
 /// authorizer. Not all authorizers neccessarily support all forms of
 /// permission. If an authorizer doesn't support a permission then it
 /// is not an error, the permission will always be denied.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Permission {
     /// ResourceAction is a permission in the form of a reasource and an
     /// action.
@@ -78,6 +78,7 @@ impl TryFrom<proto::Permission> for Permission {
                     proto::resource_action_permission::ResourceType::try_from(ra.resource_type)
                         .map_err(|_| IncompatiblePermissionError {})?,
                     ra.resource_id,
+                    ra.table_name,
                 )?;
                 let a = Action::try_from(
                     proto::resource_action_permission::Action::try_from(ra.action)
@@ -96,7 +97,7 @@ impl TryFrom<Permission> for proto::Permission {
     fn try_from(value: Permission) -> Result<Self, Self::Error> {
         match value {
             Permission::ResourceAction(r, a) => {
-                let (rt, ri) = r.try_into_proto()?;
+                let (rt, ri, tn) = r.try_into_proto()?;
                 let a: proto::resource_action_permission::Action = a.into();
                 Ok(Self {
                     permission_one_of: Some(proto::permission::PermissionOneOf::ResourceAction(
@@ -104,6 +105,7 @@ impl TryFrom<Permission> for proto::Permission {
                             resource_type: rt as i32,
                             resource_id: ri,
                             action: a as i32,
+                            table_name: tn,
                         },
                     )),
                 })
@@ -112,22 +114,82 @@ impl TryFrom<Permission> for proto::Permission {
     }
 }
 
+impl Permission {
+    /// Returns true if holding this permission grants the `requested`
+    /// permission.
+    ///
+    /// A permission grants another if they have the same [`Action`] and the
+    /// [`Resource`] of this permission [matches](Resource::matches) the
+    /// requested resource.
+    pub fn grants(&self, requested: &Self) -> bool {
+        match (self, requested) {
+            (Self::ResourceAction(r, a), Self::ResourceAction(req_r, req_a)) => {
+                a == req_a && r.matches(req_r)
+            }
+        }
+    }
+}
+
+/// The wildcard character that may be used in resource names of granted
+/// permissions.
+///
+/// A name of exactly [`WILDCARD`] matches all names, and a name ending in
+/// [`WILDCARD`] matches all names with the preceding prefix.
+pub const WILDCARD: &str = "*";
+
 /// A resource is the object that a request is trying to access.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Resource {
     /// A database is a named IOx database.
     Database(String),
+    /// A table is a named table within a named IOx database.
+    Table {
+        /// The name of the database containing the table.
+        database: String,
+        /// The name of the table.
+        table: String,
+    },
 }
 
 impl Resource {
+    /// Returns true if a permission granted on this resource extends to the
+    /// `other` resource.
+    ///
+    /// The names of this resource may contain a [`WILDCARD`]. A database
+    /// resource matches the database itself and all tables within it, while
+    /// a table resource matches only tables.
+    pub fn matches(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Database(pattern), Self::Database(db))
+            | (Self::Database(pattern), Self::Table { database: db, .. }) => {
+                name_matches(pattern, db)
+            }
+            (
+                Self::Table {
+                    database: db_pattern,
+                    table: table_pattern,
+                },
+                Self::Table { database, table },
+            ) => name_matches(db_pattern, database) && name_matches(table_pattern, table),
+            (Self::Table { .. }, Self::Database(_)) => false,
+        }
+    }
+
     fn try_from_proto(
         rt: proto::resource_action_permission::ResourceType,
         ri: Option<String>,
+        tn: Option<String>,
     ) -> Result<Self, IncompatiblePermissionError> {
-        match (rt, ri) {
-            (proto::resource_action_permission::ResourceType::Database, Some(s)) => {
+        match (rt, ri, tn) {
+            (proto::resource_action_permission::ResourceType::Database, Some(s), None) => {
                 Ok(Self::Database(s))
             }
+            (proto::resource_action_permission::ResourceType::Table, Some(s), Some(t)) => {
+                Ok(Self::Table {
+                    database: s,
+                    table: t,
+                })
+            }
             _ => Err(IncompatiblePermissionError {}),
         }
     }
@@ -138,6 +200,7 @@ impl Resource {
         (
             proto::resource_action_permission::ResourceType,
             Option<String>,
+            Option<String>,
         ),
         IncompatiblePermissionError,
     > {
@@ -145,11 +208,26 @@ impl Resource {
             Self::Database(s) => Ok((
                 proto::resource_action_permission::ResourceType::Database,
                 Some(s),
+                None,
+            )),
+            Self::Table { database, table } => Ok((
+                proto::resource_action_permission::ResourceType::Table,
+                Some(database),
+                Some(table),
             )),
         }
     }
 }
 
+/// Returns true if `pattern` matches `name`, where `pattern` may contain a
+/// trailing [`WILDCARD`].
+fn name_matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix(WILDCARD) {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,14 +290,37 @@ mod tests {
             Resource::Database("ns1".into()),
             Resource::try_from_proto(
                 proto::resource_action_permission::ResourceType::Database,
-                Some("ns1".into())
+                Some("ns1".into()),
+                None
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            Resource::Table {
+                database: "ns1".into(),
+                table: "cpu".into()
+            },
+            Resource::try_from_proto(
+                proto::resource_action_permission::ResourceType::Table,
+                Some("ns1".into()),
+                Some("cpu".into())
             )
             .unwrap()
         );
+        assert_eq!(
+            IncompatiblePermissionError {},
+            Resource::try_from_proto(
+                proto::resource_action_permission::ResourceType::Table,
+                Some("ns1".into()),
+                None
+            )
+            .unwrap_err()
+        );
         assert_eq!(
             IncompatiblePermissionError {},
             Resource::try_from_proto(
                 proto::resource_action_permission::ResourceType::Database,
+                None,
                 None
             )
             .unwrap_err()
@@ -228,7 +329,8 @@ mod tests {
             IncompatiblePermissionError {},
             Resource::try_from_proto(
                 proto::resource_action_permission::ResourceType::Unspecified,
-                Some("ns1".into())
+                Some("ns1".into()),
+                None
             )
             .unwrap_err()
         );
@@ -239,10 +341,74 @@ mod tests {
         assert_eq!(
             (
                 proto::resource_action_permission::ResourceType::Database,
-                Some("ns1".into())
+                Some("ns1".into()),
+                None
             ),
             Resource::Database("ns1".into()).try_into_proto().unwrap(),
         );
+        assert_eq!(
+            (
+                proto::resource_action_permission::ResourceType::Table,
+                Some("ns1".into()),
+                Some("cpu".into())
+            ),
+            Resource::Table {
+                database: "ns1".into(),
+                table: "cpu".into()
+            }
+            .try_into_proto()
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn resource_matches() {
+        let db = |name: &str| Resource::Database(name.into());
+        let table = |database: &str, table: &str| Resource::Table {
+            database: database.into(),
+            table: table.into(),
+        };
+
+        // Databases match themselves and their tables.
+        assert!(db("ns1").matches(&db("ns1")));
+        assert!(db("ns1").matches(&table("ns1", "cpu")));
+        assert!(!db("ns1").matches(&db("ns2")));
+        assert!(!db("ns1").matches(&table("ns2", "cpu")));
+
+        // Tables only match tables.
+        assert!(table("ns1", "cpu").matches(&table("ns1", "cpu")));
+        assert!(!table("ns1", "cpu").matches(&table("ns1", "mem")));
+        assert!(!table("ns1", "cpu").matches(&table("ns2", "cpu")));
+        assert!(!table("ns1", "cpu").matches(&db("ns1")));
+        assert!(!table("ns1", "*").matches(&db("ns1")));
+
+        // Wildcards
+        assert!(db("*").matches(&db("ns1")));
+        assert!(db("ns*").matches(&table("ns1", "cpu")));
+        assert!(!db("ns*").matches(&db("bananas")));
+        assert!(table("ns1", "*").matches(&table("ns1", "cpu")));
+        assert!(table("ns1", "cpu_*").matches(&table("ns1", "cpu_usage")));
+        assert!(!table("ns1", "cpu_*").matches(&table("ns1", "cpu")));
+        assert!(table("*", "cpu").matches(&table("ns2", "cpu")));
+
+        // Wildcards are only meaningful at the end of a name.
+        assert!(!table("ns1", "*_usage").matches(&table("ns1", "cpu_usage")));
+    }
+
+    #[test]
+    fn permission_grants() {
+        let db_read = Permission::ResourceAction(Resource::Database("ns1".into()), Action::Read);
+        let requested = Permission::ResourceAction(
+            Resource::Table {
+                database: "ns1".into(),
+                table: "cpu".into(),
+            },
+            Action::Read,
+        );
+        assert!(db_read.grants(&requested));
+
+        let db_write = Permission::ResourceAction(Resource::Database("ns1".into()), Action::Write);
+        assert!(!db_write.grants(&requested));
     }
 
     #[test]
@@ -255,6 +421,7 @@ mod tests {
                         resource_type: 1,
                         resource_id: Some("ns2".into()),
                         action: 4,
+                        table_name: None,
                     }
                 ))
             })
@@ -268,6 +435,7 @@ mod tests {
                         resource_type: 0,
                         resource_id: Some("ns2".into()),
                         action: 4,
+                        table_name: None,
                     }
                 ))
             })
@@ -281,6 +449,7 @@ mod tests {
                         resource_type: 1,
                         resource_id: Some("ns2".into()),
                         action: 0,
+                        table_name: None,
                     }
                 ))
             })
@@ -297,6 +466,7 @@ mod tests {
                         resource_type: 1,
                         resource_id: Some("ns3".into()),
                         action: 4,
+                        table_name: None,
                     }
                 ))
             },
@@ -0,0 +1,58 @@
+//! Helpers shared by the tests of the [`Authorizer`] implementations.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+
+use crate::{Action, Authorizer, Error, Permission, Resource};
+
+/// A permission for `action` on the "bananas" database.
+pub(crate) fn perm(action: Action) -> Permission {
+    db_perm("bananas", action)
+}
+
+/// A permission for `action` on `database`.
+pub(crate) fn db_perm(database: &str, action: Action) -> Permission {
+    Permission::ResourceAction(Resource::Database(database.to_string()), action)
+}
+
+/// An [`Authorizer`] granting `Read` and denying everything else, counting
+/// the calls made to it.
+///
+/// Requests without a token fail with [`Error::NoToken`] and requests with the
+/// token `bad` fail with [`Error::InvalidToken`].
+#[derive(Debug, Default)]
+pub(crate) struct ReadOnlyAuthorizer {
+    calls: AtomicUsize,
+}
+
+impl ReadOnlyAuthorizer {
+    /// The number of calls made to this authorizer.
+    pub(crate) fn calls(&self) -> usize {
+        self.calls.load(Ordering::Relaxed)
+    }
+}
+
+#[async_trait]
+impl Authorizer for ReadOnlyAuthorizer {
+    async fn permissions(
+        &self,
+        token: Option<Vec<u8>>,
+        perms: &[Permission],
+    ) -> Result<Vec<Permission>, Error> {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        let token = token.ok_or(Error::NoToken)?;
+        if token == b"bad" {
+            return Err(Error::InvalidToken);
+        }
+        let granted = perms
+            .iter()
+            .filter(|p| matches!(p, Permission::ResourceAction(_, Action::Read)))
+            .cloned()
+            .collect::<Vec<_>>();
+        if granted.is_empty() {
+            return Err(Error::Forbidden);
+        }
+        Ok(granted)
+    }
+}
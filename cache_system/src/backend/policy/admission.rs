@@ -0,0 +1,192 @@
+//! Admission policies for the [LRU](super::lru) cache system.
+//!
+//! An LRU cache admits every new entry and evicts the least recently used entries to make room for it. A single scan
+//! over a large number of entries that are only used once can therefore evict the entire working set. An admission
+//! policy guards the cache against this by rejecting new entries that are less valuable than the entries they would
+//! displace.
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    num::NonZeroUsize,
+};
+
+/// Admission policy of a [`LruPolicy`](super::lru::LruPolicy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AdmissionPolicy {
+    /// Admit all entries.
+    #[default]
+    Always,
+
+    /// Admit new entries based on their access frequency, similar to [TinyLFU].
+    ///
+    /// Reads of all keys of the pool member, including misses, are counted in a compact frequency sketch. Storing the
+    /// value of a missed key does not count as another access. If adding a new entry would exceed the pool limit, the
+    /// entry is only admitted if it was accessed more frequently than each of the least recently used entries that
+    /// would need to be evicted to make room for it. Larger entries therefore need to beat more victims.
+    ///
+    /// Entries of pool members that do not use this policy count as never accessed.
+    ///
+    /// Replacing the value of an existing entry is always admitted.
+    ///
+    /// [TinyLFU]: https://arxiv.org/abs/1512.00727
+    TinyLfu {
+        /// Expected number of distinct keys that are accessed within a sample period, used to size the sketch.
+        ///
+        /// The sketch uses 4 bytes per expected key.
+        expected_keys: NonZeroUsize,
+    },
+}
+
+/// Number of counters (rows of the sketch) per key.
+const DEPTH: usize = 4;
+
+/// Maximum value of a counter.
+const MAX_COUNT: u8 = 15;
+
+/// Seeds to derive the counter index per row.
+const SEEDS: [u64; DEPTH] = [
+    0x9E37_79B9_7F4A_7C15,
+    0xC2B2_AE3D_27D4_EB4F,
+    0x1656_67B1_9E37_79F9,
+    0x85EB_CA77_C2B2_AE63,
+];
+
+/// Count-min sketch that estimates the access frequency of keys.
+///
+/// Counters saturate at 15. To adapt to changing workloads, all counters are halved once the number of recorded
+/// accesses reaches 10 times the width of the sketch.
+pub(crate) struct FrequencySketch {
+    /// Counters, `DEPTH` rows of `width` counters each.
+    table: Vec<u8>,
+
+    /// Width of a row minus one, the width is a power of two.
+    mask: usize,
+
+    /// Number of accesses recorded since the last reset.
+    additions: usize,
+
+    /// Number of accesses after which the counters are halved.
+    sample_size: usize,
+}
+
+impl FrequencySketch {
+    /// Create sketch for given number of expected keys.
+    pub(crate) fn new(expected_keys: NonZeroUsize) -> Self {
+        let width = expected_keys.get().next_power_of_two();
+        Self {
+            table: vec![0; width * DEPTH],
+            mask: width - 1,
+            additions: 0,
+            sample_size: width.saturating_mul(10),
+        }
+    }
+
+    /// Record an access of `k`.
+    pub(crate) fn increment<K>(&mut self, k: &K)
+    where
+        K: Hash + ?Sized,
+    {
+        let h = hash(k);
+        for row in 0..DEPTH {
+            let idx = self.index(h, row);
+            let counter = &mut self.table[idx];
+            *counter = (*counter + 1).min(MAX_COUNT);
+        }
+
+        self.additions += 1;
+        if self.additions >= self.sample_size {
+            self.reset();
+        }
+    }
+
+    /// Estimated number of accesses of `k`.
+    pub(crate) fn estimate<K>(&self, k: &K) -> u8
+    where
+        K: Hash + ?Sized,
+    {
+        let h = hash(k);
+        (0..DEPTH)
+            .map(|row| self.table[self.index(h, row)])
+            .min()
+            .expect("depth is not zero")
+    }
+
+    /// Halve all counters.
+    fn reset(&mut self) {
+        for counter in &mut self.table {
+            *counter /= 2;
+        }
+        self.additions /= 2;
+    }
+
+    fn index(&self, h: u64, row: usize) -> usize {
+        let h = (h ^ SEEDS[row]).wrapping_mul(SEEDS[(row + 1) % DEPTH]);
+        let h = h ^ (h >> 32);
+        row * (self.mask + 1) + (h as usize & self.mask)
+    }
+}
+
+impl std::fmt::Debug for FrequencySketch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrequencySketch")
+            .field("width", &(self.mask + 1))
+            .field("additions", &self.additions)
+            .field("sample_size", &self.sample_size)
+            .finish_non_exhaustive()
+    }
+}
+
+fn hash<K>(k: &K) -> u64
+where
+    K: Hash + ?Sized,
+{
+    let mut hasher = DefaultHasher::new();
+    k.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        let mut sketch = FrequencySketch::new(NonZeroUsize::new(100).unwrap());
+        assert_eq!(sketch.estimate("a"), 0);
+
+        for _ in 0..3 {
+            sketch.increment("a");
+        }
+        sketch.increment("b");
+
+        assert_eq!(sketch.estimate("a"), 3);
+        assert_eq!(sketch.estimate("b"), 1);
+        assert_eq!(sketch.estimate("c"), 0);
+    }
+
+    #[test]
+    fn test_saturation() {
+        let mut sketch = FrequencySketch::new(NonZeroUsize::new(100).unwrap());
+        for _ in 0..100 {
+            sketch.increment("a");
+        }
+        assert_eq!(sketch.estimate("a"), MAX_COUNT);
+    }
+
+    #[test]
+    fn test_aging() {
+        // width 16 => reset after 160 accesses
+        let mut sketch = FrequencySketch::new(NonZeroUsize::new(16).unwrap());
+        for _ in 0..10 {
+            sketch.increment("a");
+        }
+        for i in 0..149 {
+            sketch.increment(&i);
+        }
+        assert!(sketch.estimate("a") >= 10);
+
+        sketch.increment("b");
+        let estimate = sketch.estimate("a");
+        assert!((5..10).contains(&estimate), "{estimate}");
+    }
+}
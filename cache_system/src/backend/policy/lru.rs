@@ -318,7 +318,10 @@ use crate::{
     resource_consumption::{Resource, ResourceEstimator},
 };
 
-use super::{CallbackHandle, ChangeRequest, Subscriber};
+use super::{
+    admission::{AdmissionPolicy, FrequencySketch},
+    CallbackHandle, ChangeRequest, Subscriber,
+};
 
 /// Wrapper around something that can be converted into `u64`
 /// to enable emitting metrics.
@@ -513,6 +516,51 @@ where
         self.shared.current.lock().dec(&s);
     }
 
+    /// Check if a new entry with the given resource consumption and access `frequency` shall be admitted.
+    ///
+    /// The entry is admitted if it fits into the pool or if it was accessed more frequently than each of the least
+    /// recently used entries that would need to be evicted to make room for it.
+    fn admit(&self, consumption: S, frequency: u8) -> bool {
+        let current = self.current();
+        let limit = self.shared.limit.v;
+        if current + consumption <= limit {
+            return true;
+        }
+        let mut needed = current + consumption - limit;
+
+        let members = self.shared.members();
+
+        // limit scope of member iterators, because they contain locks
+        let mut heap: BinaryHeap<EvictionCandidateIter<S>> = members
+            .iter()
+            .map(|(id, member)| EvictionCandidateIter::new(id, member.could_remove()))
+            .collect();
+
+        while let Some(candidate) = heap.pop() {
+            let (candidate, victim) = candidate.next();
+            let Some((_t, s, k)) = victim else {
+                // all iterators are empty
+                break;
+            };
+
+            let member = members
+                .get(candidate.id)
+                .expect("did get this ID from this map");
+            if member.frequency(k.as_ref()) >= frequency {
+                return false;
+            }
+
+            if s >= needed {
+                return true;
+            }
+            needed = needed - s;
+
+            heap.push(candidate);
+        }
+
+        true
+    }
+
     /// Wait for the pool to converge to a steady state.
     ///
     /// This usually means that the background worker that runs the eviction loop is idle.
@@ -553,6 +601,9 @@ where
 
     /// Count resource usage of this specific pool member.
     metric_usage: U64Gauge,
+
+    /// Count entries that were rejected by the admission policy.
+    metric_rejected: U64Counter,
 }
 
 impl<K, V, S> LruPolicy<K, V, S>
@@ -573,6 +624,18 @@ where
         pool: Arc<ResourcePool<S>>,
         id: &'static str,
         resource_estimator: Arc<dyn ResourceEstimator<K = K, V = V, S = S>>,
+    ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
+        Self::new_with_admission(pool, id, resource_estimator, AdmissionPolicy::Always)
+    }
+
+    /// Create new backend w/o any known keys that uses the given [`AdmissionPolicy`] for new entries.
+    ///
+    /// See [`new`](Self::new) for details.
+    pub fn new_with_admission(
+        pool: Arc<ResourcePool<S>>,
+        id: &'static str,
+        resource_estimator: Arc<dyn ResourceEstimator<K = K, V = V, S = S>>,
+        admission: AdmissionPolicy,
     ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
         let metric_count = pool
             .metric_registry
@@ -595,6 +658,20 @@ where
                 "Number of entries that were evicted from a given LRU cache pool member",
             )
             .recorder(&[("pool", pool.name), ("member", id)]);
+        let metric_rejected = pool
+            .metric_registry
+            .register_metric::<U64Counter>(
+                "cache_lru_member_admission_rejected",
+                "Number of new entries that were rejected by the admission policy of a given LRU cache pool member",
+            )
+            .recorder(&[("pool", pool.name), ("member", id)]);
+
+        let sketch = match admission {
+            AdmissionPolicy::Always => None,
+            AdmissionPolicy::TinyLfu { expected_keys } => {
+                Some(Mutex::new(FrequencySketch::new(expected_keys)))
+            }
+        };
 
         move |mut callback_handle| {
             callback_handle.execute_requests(vec![ChangeRequest::ensure_empty()]);
@@ -602,6 +679,7 @@ where
             let member = Arc::new(PoolMemberImpl {
                 id,
                 last_used: Arc::new(Mutex::new(AddressableHeap::new())),
+                sketch,
                 metric_evicted,
                 callback_handle: Mutex::new(callback_handle),
             });
@@ -614,6 +692,7 @@ where
                 resource_estimator,
                 metric_count,
                 metric_usage,
+                metric_rejected,
             }
         }
     }
@@ -649,6 +728,10 @@ where
 
     fn get(&mut self, k: &Self::K, now: Time) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
         trace!(?k, now = now.timestamp_nanos(), "LRU get",);
+        if let Some(sketch) = &self.member.sketch {
+            sketch.lock().increment(k);
+        }
+
         let mut last_used = self.member.last_used.lock();
 
         // update "last used"
@@ -677,6 +760,20 @@ where
             return vec![ChangeRequest::remove(k.clone())];
         }
 
+        // check admission of new entries
+        // Note: the access was already counted by `get`. The pool admission check locks the `last_used` heaps of all
+        //       members, so we must not hold our own lock
+        if let Some(sketch) = &self.member.sketch {
+            let frequency = sketch.lock().estimate(k);
+            let is_new = self.member.last_used.lock().get(k).is_none();
+
+            if is_new && !self.pool.admit(consumption, frequency) {
+                trace!(?k, frequency, "LRU admission rejected");
+                self.metric_rejected.inc(1);
+                return vec![ChangeRequest::remove(k.clone())];
+            }
+        }
+
         {
             let mut last_used = self.member.last_used.lock();
 
@@ -744,6 +841,13 @@ trait PoolMember: Debug + Send + Sync + 'static {
     ///
     /// The keys MUST be a result of [`could_remove`](Self::could_remove), otherwise the downcasting may not work and panic.
     fn remove_keys(&self, keys: Vec<Box<dyn Any>>);
+
+    /// Estimated access frequency of the given key, used by the [`AdmissionPolicy`].
+    ///
+    /// Returns 0 if this member does not track access frequencies.
+    ///
+    /// The key MUST be a result of [`could_remove`](Self::could_remove), otherwise the downcasting may not work and panic.
+    fn frequency(&self, k: &dyn Any) -> u8;
 }
 
 /// The only implementation of [`PoolMember`].
@@ -767,6 +871,9 @@ where
     /// See documentation of [`callback_handle`](Self::callback_handle) for a reasoning about locking.
     last_used: Arc<Mutex<AddressableHeap<K, S, Time>>>,
 
+    /// Access frequency sketch, if the [`AdmissionPolicy`] requires one.
+    sketch: Option<Mutex<FrequencySketch>>,
+
     /// Handle to call back into the [`PolicyBackend`] to evict data.
     ///
     /// # Locking
@@ -817,6 +924,15 @@ where
 
         self.callback_handle.lock().execute_requests(vec![combined]);
     }
+
+    fn frequency(&self, k: &dyn Any) -> u8 {
+        match &self.sketch {
+            Some(sketch) => sketch
+                .lock()
+                .estimate(k.downcast_ref::<K>().expect("wrong type")),
+            None => 0,
+        }
+    }
 }
 
 /// Helper module that wraps the iterator handling for [`PoolMember`]/[`PoolMemberImpl`].
@@ -1069,7 +1185,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashMap, time::Duration};
+    use std::{collections::HashMap, num::NonZeroUsize, time::Duration};
 
     use iox_time::{MockProvider, SystemProvider};
     use metric::{Observation, RawReporter};
@@ -2016,6 +2132,108 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_admission_tinylfu() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let metric_registry = Arc::new(metric::Registry::new());
+        let pool = Arc::new(ResourcePool::new(
+            "pool",
+            TestSize(10),
+            Arc::clone(&metric_registry),
+            &Handle::current(),
+        ));
+        let resource_estimator = Arc::new(TestResourceEstimator {});
+
+        let mut backend = PolicyBackend::hashmap_backed(Arc::clone(&time_provider) as _);
+        backend.add_policy(LruPolicy::new_with_admission(
+            Arc::clone(&pool),
+            "id",
+            Arc::clone(&resource_estimator) as _,
+            AdmissionPolicy::TinyLfu {
+                expected_keys: NonZeroUsize::new(100).unwrap(),
+            },
+        ));
+
+        // set up hot working set
+        for k in ["hot1", "hot2", "hot3"] {
+            time_provider.inc(Duration::from_millis(1));
+            backend.set(String::from(k), 3usize);
+        }
+        for _ in 0..3 {
+            for k in ["hot1", "hot2", "hot3"] {
+                time_provider.inc(Duration::from_millis(1));
+                assert!(backend.get(&String::from(k)).is_some());
+            }
+        }
+        assert_eq!(pool.current().0, 9);
+
+        // a scan does not evict the working set
+        for i in 0..5 {
+            time_provider.inc(Duration::from_millis(1));
+            backend.set(format!("scan{i}"), 2usize);
+        }
+        pool.wait_converged().await;
+        assert_eq!(pool.current().0, 9);
+        assert_inner_backend(
+            &mut backend,
+            [
+                (String::from("hot1"), 3),
+                (String::from("hot2"), 3),
+                (String::from("hot3"), 3),
+            ],
+        );
+        assert_eq!(get_rejected_metric(&metric_registry), 5);
+
+        // entries that fit are always admitted
+        backend.set(String::from("small"), 1usize);
+        pool.wait_converged().await;
+        assert_eq!(pool.current().0, 10);
+        backend.remove(&String::from("small"));
+
+        // an entry that is requested often enough is admitted eventually
+        for _ in 0..3 {
+            time_provider.inc(Duration::from_millis(1));
+            assert!(backend.get(&String::from("new")).is_none());
+            backend.set(String::from("new"), 2usize);
+        }
+        assert_eq!(get_rejected_metric(&metric_registry), 8);
+        time_provider.inc(Duration::from_millis(1));
+        assert!(backend.get(&String::from("new")).is_none());
+        backend.set(String::from("new"), 2usize);
+        pool.wait_converged().await;
+        assert_eq!(get_rejected_metric(&metric_registry), 8);
+        assert_inner_backend(
+            &mut backend,
+            [
+                (String::from("hot2"), 3),
+                (String::from("hot3"), 3),
+                (String::from("new"), 2),
+            ],
+        );
+
+        // overrides are always admitted
+        backend.set(String::from("new"), 4usize);
+        pool.wait_converged().await;
+        assert_eq!(get_rejected_metric(&metric_registry), 8);
+        assert_eq!(backend.get(&String::from("new")), Some(4));
+    }
+
+    fn get_rejected_metric(metric_registry: &metric::Registry) -> u64 {
+        let mut reporter = RawReporter::default();
+        metric_registry.report(&mut reporter);
+        let observation = reporter
+            .metric("cache_lru_member_admission_rejected")
+            .unwrap()
+            .observation(&[("pool", "pool"), ("member", "id")])
+            .unwrap();
+
+        if let Observation::U64Counter(c) = observation {
+            *c
+        } else {
+            panic!("Wrong observation type")
+        }
+    }
+
     #[derive(Debug)]
     struct TestResourceEstimator {}
 
@@ -15,9 +15,13 @@ use parking_lot::{lock_api::ArcReentrantMutexGuard, RawMutex, RawThreadId, Reent
 
 use super::CacheBackend;
 
+pub mod admission;
 pub mod lru;
+pub mod partition_quota;
 pub mod refresh;
+pub mod refresh_ahead;
 pub mod remove_if;
+pub mod stale_while_revalidate;
 pub mod ttl;
 
 #[cfg(test)]
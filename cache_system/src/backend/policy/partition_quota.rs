@@ -0,0 +1,493 @@
+//! Per-partition (e.g. per-namespace) resource quotas.
+use std::{borrow::Cow, collections::HashMap, fmt::Debug, hash::Hash, sync::Arc};
+
+use iox_time::Time;
+use metric::{Attributes, U64Counter, U64Gauge};
+use observability_deps::tracing::trace;
+
+use crate::{
+    addressable_heap::AddressableHeap,
+    resource_consumption::{Resource, ResourceEstimator},
+};
+
+use super::{CallbackHandle, ChangeRequest, Subscriber};
+
+/// Interface to assign cache keys to partitions, e.g. to the namespace they belong to.
+pub trait PartitionProvider: std::fmt::Debug + Send + Sync + 'static {
+    /// Cache key.
+    type K;
+
+    /// Partition of the given key.
+    ///
+    /// The partition of a key MUST NOT change over time.
+    fn partition(&self, k: &Self::K) -> Arc<str>;
+}
+
+/// Cache policy that limits the resource consumption of each partition (e.g. namespace) of a cache.
+///
+/// If adding an entry exceeds the quota of its partition, the least recently used entries of the SAME partition are
+/// evicted immediately until the partition is within its quota again. Entries of other partitions are never evicted
+/// by this policy, so a partition with a lot of data cannot evict the entries of other partitions. Entries that are
+/// larger than the quota of their partition are not cached at all.
+///
+/// This can be combined with the [LRU policy](super::lru::LruPolicy) to also limit the overall resource consumption
+/// of the cache.
+#[derive(Debug)]
+pub struct PartitionQuotaPolicy<K, V, S>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+    S: Resource,
+{
+    /// Cache name, used for metrics.
+    name: &'static str,
+
+    partition_provider: Arc<dyn PartitionProvider<K = K>>,
+    resource_estimator: Arc<dyn ResourceEstimator<K = K, V = V, S = S>>,
+
+    /// Quota for partitions without an explicit quota.
+    default_quota: S,
+
+    /// Explicit quotas for specific partitions.
+    quotas: HashMap<Arc<str>, S>,
+
+    /// Partitions that currently have entries.
+    partitions: HashMap<Arc<str>, PartitionState<K, S>>,
+
+    metric_usage: metric::Metric<U64Gauge>,
+    metric_evicted: metric::Metric<U64Counter>,
+}
+
+impl<K, V, S> PartitionQuotaPolicy<K, V, S>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+    S: Resource,
+{
+    /// Create new policy.
+    ///
+    /// Each partition is limited to `default_quota` unless `quotas` contains an explicit quota for it.
+    ///
+    /// # Panic
+    /// Panics if the inner backend is not empty.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        name: &'static str,
+        partition_provider: Arc<dyn PartitionProvider<K = K>>,
+        resource_estimator: Arc<dyn ResourceEstimator<K = K, V = V, S = S>>,
+        default_quota: S,
+        quotas: HashMap<Arc<str>, S>,
+        metric_registry: &metric::Registry,
+    ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
+        let metric_usage = metric_registry.register_metric::<U64Gauge>(
+            "cache_partition_usage",
+            "Resource usage of a partition (e.g. namespace) of a cache",
+        );
+        let metric_evicted = metric_registry.register_metric::<U64Counter>(
+            "cache_partition_evicted",
+            "Number of entries that were evicted because a partition (e.g. namespace) of a cache exceeded its quota",
+        );
+
+        move |mut callback_handle| {
+            callback_handle.execute_requests(vec![ChangeRequest::ensure_empty()]);
+
+            Self {
+                name,
+                partition_provider,
+                resource_estimator,
+                default_quota,
+                quotas,
+                partitions: HashMap::new(),
+                metric_usage,
+                metric_evicted,
+            }
+        }
+    }
+
+    fn quota(&self, partition: &str) -> S {
+        self.quotas
+            .get(partition)
+            .copied()
+            .unwrap_or(self.default_quota)
+    }
+}
+
+impl<K, V, S> Subscriber for PartitionQuotaPolicy<K, V, S>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+    S: Resource,
+{
+    type K = K;
+    type V = V;
+
+    fn get(&mut self, k: &Self::K, now: Time) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        let partition = self.partition_provider.partition(k);
+        if let Some(state) = self.partitions.get_mut(&partition) {
+            state.last_used.update_order(k, now);
+        }
+
+        vec![]
+    }
+
+    fn set(
+        &mut self,
+        k: &Self::K,
+        v: &Self::V,
+        now: Time,
+    ) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        let partition = self.partition_provider.partition(k);
+        let consumption = self.resource_estimator.consumption(k, v);
+        let quota = self.quota(&partition);
+
+        // check for oversized entries
+        if consumption > quota {
+            trace!(
+                ?k,
+                partition = partition.as_ref(),
+                "entry exceeds partition quota"
+            );
+            return vec![ChangeRequest::remove(k.clone())];
+        }
+
+        let state = self
+            .partitions
+            .entry(Arc::clone(&partition))
+            .or_insert_with(|| {
+                let attributes = Attributes::from([
+                    ("name", Cow::Borrowed(self.name)),
+                    ("partition", Cow::Owned(partition.to_string())),
+                ]);
+                let mut attributes_usage = attributes.clone();
+                attributes_usage.insert("unit", S::unit());
+
+                PartitionState {
+                    usage: S::zero(),
+                    last_used: AddressableHeap::new(),
+                    metric_usage: self.metric_usage.recorder(attributes_usage),
+                    metric_evicted: self.metric_evicted.recorder(attributes),
+                }
+            });
+
+        // Note: in case of an override, the existing "last used" time is kept, because just replacing an existing
+        // value (e.g. via a refresh) should not count as a use.
+        let last_used_t = match state.last_used.remove(k) {
+            Some((consumption_previously, last_used_t_previously)) => {
+                state.usage = state.usage - consumption_previously;
+                last_used_t_previously
+            }
+            None => now,
+        };
+        state.last_used.insert(k.clone(), consumption, last_used_t);
+        state.usage = state.usage + consumption;
+        state.metric_usage.set(state.usage.into());
+
+        // select victims within the same partition, the actual accounting happens when they are removed
+        let mut projected = state.usage;
+        let mut victims = vec![];
+        for (victim, s, _t) in state.last_used.iter() {
+            if projected <= quota {
+                break;
+            }
+            if victim == k {
+                continue;
+            }
+
+            projected = projected - *s;
+            victims.push(ChangeRequest::remove(victim.clone()));
+        }
+
+        if !victims.is_empty() {
+            trace!(
+                partition = partition.as_ref(),
+                n = victims.len(),
+                "evicting cache entries due to partition quota",
+            );
+            state.metric_evicted.inc(victims.len() as u64);
+        }
+
+        victims
+    }
+
+    fn remove(&mut self, k: &Self::K, _now: Time) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        let partition = self.partition_provider.partition(k);
+        if let Some(state) = self.partitions.get_mut(&partition) {
+            if let Some((consumption, _last_used)) = state.last_used.remove(k) {
+                state.usage = state.usage - consumption;
+                state.metric_usage.set(state.usage.into());
+            }
+
+            if state.last_used.is_empty() {
+                self.partitions.remove(&partition);
+            }
+        }
+
+        vec![]
+    }
+}
+
+/// Accounting of a single partition.
+#[derive(Debug)]
+struct PartitionState<K, S>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    S: Resource,
+{
+    /// Current resource usage.
+    usage: S,
+
+    /// Tracks when the entries of this partition were used for the last time.
+    last_used: AddressableHeap<K, S, Time>,
+
+    metric_usage: U64Gauge,
+    metric_evicted: U64Counter,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use iox_time::MockProvider;
+    use metric::{Observation, RawReporter};
+
+    use crate::{
+        backend::{policy::PolicyBackend, CacheBackend},
+        resource_consumption::test_util::TestSize,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    #[should_panic(expected = "inner backend is not empty")]
+    async fn test_panic_inner_not_empty() {
+        let time_provider = Arc::new(MockProvider::new(Time::MIN));
+        let metric_registry = metric::Registry::new();
+        let mut backend = PolicyBackend::hashmap_backed(time_provider);
+        let policy_constructor = PartitionQuotaPolicy::new(
+            "my_cache",
+            Arc::new(TestPartitionProvider {}),
+            Arc::new(TestResourceEstimator {}),
+            TestSize(10),
+            HashMap::new(),
+            &metric_registry,
+        );
+        backend.add_policy(|mut handle| {
+            handle.execute_requests(vec![ChangeRequest::set(String::from("a/1"), 1usize)]);
+            policy_constructor(handle)
+        });
+    }
+
+    #[tokio::test]
+    async fn test_isolation() {
+        let TestState {
+            mut backend,
+            time_provider,
+            metric_registry,
+        } = TestState::new();
+
+        backend.set(String::from("a/1"), 2usize);
+        time_provider.inc(Duration::from_millis(1));
+        backend.set(String::from("b/1"), 3usize);
+        time_provider.inc(Duration::from_millis(1));
+        backend.set(String::from("b/2"), 3usize);
+        time_provider.inc(Duration::from_millis(1));
+        assert_eq!(get_usage(&metric_registry, "a"), 2);
+        assert_eq!(get_usage(&metric_registry, "b"), 6);
+
+        // "b/1" is used, so "b/2" is the least recently used entry of "b"
+        assert_eq!(backend.get(&String::from("b/1")), Some(3));
+        time_provider.inc(Duration::from_millis(1));
+
+        // exceeding the quota of "b" only evicts entries of "b"
+        backend.set(String::from("b/3"), 5usize);
+        assert_eq!(backend.get(&String::from("a/1")), Some(2));
+        assert_eq!(backend.get(&String::from("b/1")), Some(3));
+        assert_eq!(backend.get(&String::from("b/2")), None);
+        assert_eq!(backend.get(&String::from("b/3")), Some(5));
+        assert_eq!(get_usage(&metric_registry, "a"), 2);
+        assert_eq!(get_usage(&metric_registry, "b"), 8);
+        assert_eq!(get_evicted(&metric_registry, "a"), 0);
+        assert_eq!(get_evicted(&metric_registry, "b"), 1);
+
+        // removal is accounted
+        backend.remove(&String::from("b/1"));
+        assert_eq!(get_usage(&metric_registry, "b"), 5);
+    }
+
+    #[tokio::test]
+    async fn test_explicit_quota() {
+        let TestState {
+            mut backend,
+            metric_registry,
+            ..
+        } = TestState::new();
+
+        // "big" has a larger quota
+        backend.set(String::from("big/1"), 15usize);
+        assert_eq!(backend.get(&String::from("big/1")), Some(15));
+        assert_eq!(get_usage(&metric_registry, "big"), 15);
+
+        // oversized entries are not cached
+        backend.set(String::from("a/1"), 11usize);
+        assert_eq!(backend.get(&String::from("a/1")), None);
+    }
+
+    #[tokio::test]
+    async fn test_override() {
+        let TestState {
+            mut backend,
+            time_provider,
+            metric_registry,
+        } = TestState::new();
+
+        backend.set(String::from("a/1"), 4usize);
+        time_provider.inc(Duration::from_millis(1));
+        backend.set(String::from("a/2"), 4usize);
+        time_provider.inc(Duration::from_millis(1));
+
+        // growing an entry evicts others but not the entry itself, even though it is the least recently used one
+        backend.set(String::from("a/1"), 8usize);
+        assert_eq!(backend.get(&String::from("a/1")), Some(8));
+        assert_eq!(backend.get(&String::from("a/2")), None);
+        assert_eq!(get_usage(&metric_registry, "a"), 8);
+
+        // overriding an oversized entry removes the existing entry
+        backend.set(String::from("a/1"), 11usize);
+        assert_eq!(backend.get(&String::from("a/1")), None);
+        assert_eq!(get_usage(&metric_registry, "a"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_generic_backend() {
+        use crate::backend::test_util::test_generic;
+
+        #[derive(Debug)]
+        struct SinglePartitionProvider {}
+
+        impl PartitionProvider for SinglePartitionProvider {
+            type K = u8;
+
+            fn partition(&self, _k: &Self::K) -> Arc<str> {
+                Arc::from("p")
+            }
+        }
+
+        #[derive(Debug)]
+        struct ZeroEstimator {}
+
+        impl ResourceEstimator for ZeroEstimator {
+            type K = u8;
+            type V = String;
+            type S = TestSize;
+
+            fn consumption(&self, _k: &Self::K, _v: &Self::V) -> Self::S {
+                TestSize(0)
+            }
+        }
+
+        test_generic(|| {
+            let time_provider = Arc::new(MockProvider::new(Time::MIN));
+            let metric_registry = metric::Registry::new();
+            let mut backend = PolicyBackend::hashmap_backed(time_provider);
+            backend.add_policy(PartitionQuotaPolicy::new(
+                "my_cache",
+                Arc::new(SinglePartitionProvider {}),
+                Arc::new(ZeroEstimator {}),
+                TestSize(10),
+                HashMap::new(),
+                &metric_registry,
+            ));
+            backend
+        });
+    }
+
+    /// Uses the part of the key before the first `/` as partition.
+    #[derive(Debug)]
+    struct TestPartitionProvider {}
+
+    impl PartitionProvider for TestPartitionProvider {
+        type K = String;
+
+        fn partition(&self, k: &Self::K) -> Arc<str> {
+            Arc::from(k.split('/').next().expect("at least one part"))
+        }
+    }
+
+    #[derive(Debug)]
+    struct TestResourceEstimator {}
+
+    impl ResourceEstimator for TestResourceEstimator {
+        type K = String;
+        type V = usize;
+        type S = TestSize;
+
+        fn consumption(&self, _k: &Self::K, v: &Self::V) -> Self::S {
+            TestSize(*v)
+        }
+    }
+
+    struct TestState {
+        backend: PolicyBackend<String, usize>,
+        time_provider: Arc<MockProvider>,
+        metric_registry: metric::Registry,
+    }
+
+    impl TestState {
+        fn new() -> Self {
+            let time_provider = Arc::new(MockProvider::new(Time::MIN));
+            let metric_registry = metric::Registry::new();
+
+            let mut backend = PolicyBackend::hashmap_backed(Arc::clone(&time_provider) as _);
+            backend.add_policy(PartitionQuotaPolicy::new(
+                "my_cache",
+                Arc::new(TestPartitionProvider {}),
+                Arc::new(TestResourceEstimator {}),
+                TestSize(10),
+                HashMap::from([(Arc::from("big"), TestSize(20))]),
+                &metric_registry,
+            ));
+
+            Self {
+                backend,
+                time_provider,
+                metric_registry,
+            }
+        }
+    }
+
+    fn get_usage(metric_registry: &metric::Registry, partition: &'static str) -> u64 {
+        let mut reporter = RawReporter::default();
+        metric_registry.report(&mut reporter);
+        let observation = reporter
+            .metric("cache_partition_usage")
+            .unwrap()
+            .observation(&[
+                ("name", "my_cache"),
+                ("partition", partition),
+                ("unit", "bytes"),
+            ])
+            .unwrap();
+
+        if let Observation::U64Gauge(c) = observation {
+            *c
+        } else {
+            panic!("Wrong observation type")
+        }
+    }
+
+    fn get_evicted(metric_registry: &metric::Registry, partition: &'static str) -> u64 {
+        let mut reporter = RawReporter::default();
+        metric_registry.report(&mut reporter);
+        let observation = reporter
+            .metric("cache_partition_evicted")
+            .unwrap()
+            .observation(&[("name", "my_cache"), ("partition", partition)])
+            .unwrap();
+
+        if let Observation::U64Counter(c) = observation {
+            *c
+        } else {
+            panic!("Wrong observation type")
+        }
+    }
+}
@@ -0,0 +1,553 @@
+//! Refresh-ahead handling for frequently used entries.
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use iox_time::{Time, TimeProvider};
+use metric::U64Counter;
+use parking_lot::Mutex;
+use tokio::{runtime::Handle, sync::Semaphore};
+use tokio_util::sync::CancellationToken;
+
+use crate::loader::Loader;
+
+use super::{ttl::TtlProvider, CacheBackend, CallbackHandle, ChangeRequest, Subscriber};
+
+/// Cache policy that reloads frequently used ("hot") entries shortly before they expire.
+///
+/// Every entry that has a TTL (as reported by the [`TtlProvider`]) is scheduled for a refresh `refresh_ahead` before
+/// it expires. At that point, the entry is reloaded using the [`Loader`] if it was accessed at least `min_hits` times
+/// since it was last set. Entries that are not hot are left alone and expire normally. The access counter is reset
+/// whenever the entry is set, incl. by the refresh itself, so an entry has to stay hot to be refreshed again.
+///
+/// At most `max_concurrency` refreshes run at the same time. Refreshes that cannot start immediately wait for a free
+/// slot. A pending or running refresh is cancelled if its key is removed or set by someone else in the meantime.
+///
+/// This policy does NOT evict entries. Use the same [`TtlProvider`] for the [TTL policy](super::ttl::TtlPolicy) and
+/// pick a `refresh_ahead` that leaves enough time for the loader to finish before the entry expires, so that hot
+/// entries never incur a synchronous miss.
+#[derive(Debug)]
+pub struct RefreshAheadPolicy<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    time_provider: Arc<dyn TimeProvider>,
+    ttl_provider: Arc<dyn TtlProvider<K = K, V = V>>,
+    refresh_ahead: Duration,
+    min_hits: u64,
+    concurrency: Arc<Semaphore>,
+    loader: Arc<dyn Loader<K = K, V = V, Extra = ()>>,
+    callback_handle: Arc<Mutex<CallbackHandle<K, V>>>,
+    handle: Handle,
+    entries: HashMap<K, EntryState>,
+    metric_refreshed: U64Counter,
+}
+
+impl<K, V> RefreshAheadPolicy<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    /// Create new refresh-ahead policy.
+    ///
+    /// # Panic
+    /// Panics if `max_concurrency` is zero.
+    #[allow(clippy::new_ret_no_self, clippy::too_many_arguments)]
+    pub fn new(
+        time_provider: Arc<dyn TimeProvider>,
+        ttl_provider: Arc<dyn TtlProvider<K = K, V = V>>,
+        refresh_ahead: Duration,
+        min_hits: u64,
+        max_concurrency: usize,
+        loader: Arc<dyn Loader<K = K, V = V, Extra = ()>>,
+        name: &'static str,
+        metric_registry: &metric::Registry,
+        handle: &Handle,
+    ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
+        assert!(max_concurrency > 0, "max_concurrency must be positive");
+
+        let metric_refreshed = metric_registry
+            .register_metric::<U64Counter>(
+                "cache_refresh_ahead",
+                "Number of cache entries that were reloaded ahead of their expiration.",
+            )
+            .recorder(&[("name", name)]);
+
+        // clone handle for callback
+        let handle = handle.clone();
+
+        move |mut callback_handle| {
+            callback_handle.execute_requests(vec![ChangeRequest::ensure_empty()]);
+
+            Self {
+                time_provider,
+                ttl_provider,
+                refresh_ahead,
+                min_hits,
+                concurrency: Arc::new(Semaphore::new(max_concurrency)),
+                loader,
+                callback_handle: Arc::new(Mutex::new(callback_handle)),
+                handle,
+                entries: HashMap::new(),
+                metric_refreshed,
+            }
+        }
+    }
+
+    /// Schedule background refresh for given key at `t_refresh`.
+    ///
+    /// The refresh only happens if the key was accessed at least `min_hits` times until then.
+    fn schedule(&self, k: K, t_refresh: Time, hits: Arc<AtomicU64>) -> ScheduledRefresh {
+        let cancelled = CancellationToken::default();
+
+        let time_provider = Arc::clone(&self.time_provider);
+        let min_hits = self.min_hits;
+        let concurrency = Arc::clone(&self.concurrency);
+        let loader = Arc::clone(&self.loader);
+        let callback_handle = Arc::clone(&self.callback_handle);
+        let metric_refreshed = self.metric_refreshed.clone();
+        let cancelled_captured = cancelled.clone();
+        let fut = async move {
+            time_provider.sleep_until(t_refresh).await;
+
+            if hits.load(Ordering::SeqCst) < min_hits {
+                // entry is not hot, let it expire
+                return;
+            }
+
+            let _permit = concurrency
+                .acquire()
+                .await
+                .expect("semaphore is never closed");
+
+            // some `let`-dance so that rustc does not complain that `&K` is not `Send`
+            let k_for_loader = k.clone();
+            let v = loader.load(k_for_loader, ()).await;
+
+            let mut callback_handle = callback_handle.lock();
+            callback_handle.execute_requests(vec![ChangeRequest::from_fn(|backend| {
+                // Here we have the PolicyBackend implicit lock. There is no way our Subscriber can be
+                // active here, but we need to check if we have been canceled one last time.
+                if cancelled_captured.is_cancelled() {
+                    return;
+                }
+
+                backend.set(k, v);
+            })]);
+
+            // update metric AFTER change request
+            metric_refreshed.inc(1);
+        };
+
+        let cancelled_captured = cancelled.clone();
+        self.handle.spawn(async move {
+            tokio::select! {
+                _ = cancelled_captured.cancelled() => {}
+                _ = fut => {}
+            }
+        });
+
+        ScheduledRefresh { cancelled }
+    }
+}
+
+impl<K, V> Subscriber for RefreshAheadPolicy<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    type K = K;
+    type V = V;
+
+    fn get(&mut self, k: &Self::K, _now: Time) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        if let Some(state) = self.entries.get(k) {
+            state.hits.fetch_add(1, Ordering::SeqCst);
+        }
+
+        vec![]
+    }
+
+    fn set(
+        &mut self,
+        k: &Self::K,
+        v: &Self::V,
+        now: Time,
+    ) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        // replacing or removing the state cancels any scheduled refresh, which would otherwise override the new value
+        let t_refresh = self
+            .ttl_provider
+            .expires_in(k, v)
+            .and_then(|ttl| now.checked_add(ttl.saturating_sub(self.refresh_ahead)));
+        match t_refresh {
+            Some(t_refresh) => {
+                let hits = Arc::new(AtomicU64::new(0));
+                let scheduled = self.schedule(k.clone(), t_refresh, Arc::clone(&hits));
+                self.entries.insert(
+                    k.clone(),
+                    EntryState {
+                        hits,
+                        _scheduled: scheduled,
+                    },
+                );
+            }
+            None => {
+                self.entries.remove(k);
+            }
+        }
+
+        vec![]
+    }
+
+    fn remove(&mut self, k: &Self::K, _now: Time) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        // the removal cancels any scheduled refresh
+        self.entries.remove(k);
+
+        vec![]
+    }
+}
+
+/// State of a cached entry that expires.
+#[derive(Debug)]
+struct EntryState {
+    /// Number of accesses since the entry was set.
+    hits: Arc<AtomicU64>,
+
+    /// Refresh that is scheduled for this entry.
+    _scheduled: ScheduledRefresh,
+}
+
+/// Background refresh of a single entry.
+///
+/// The refresh is cancelled on [`drop`](Drop::drop).
+#[derive(Debug)]
+struct ScheduledRefresh {
+    cancelled: CancellationToken,
+}
+
+impl Drop for ScheduledRefresh {
+    fn drop(&mut self) {
+        self.cancelled.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use iox_time::MockProvider;
+    use metric::{Observation, RawReporter};
+
+    use crate::{
+        backend::{
+            policy::{ttl::ConstantValueTtlProvider, PolicyBackend},
+            CacheBackend,
+        },
+        loader::test_util::TestLoader,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    #[should_panic(expected = "inner backend is not empty")]
+    async fn test_panic_inner_not_empty() {
+        let metric_registry = metric::Registry::new();
+        let time_provider = Arc::new(MockProvider::new(Time::MIN));
+        let loader = Arc::new(TestLoader::<u8, (), String>::default());
+        let mut backend = PolicyBackend::hashmap_backed(Arc::clone(&time_provider) as _);
+        let policy_constructor = RefreshAheadPolicy::new(
+            time_provider,
+            Arc::new(ConstantValueTtlProvider::new(Some(Duration::from_secs(10)))),
+            Duration::from_secs(2),
+            2,
+            1,
+            loader,
+            "my_cache",
+            &metric_registry,
+            &Handle::current(),
+        );
+        backend.add_policy(|mut handle| {
+            handle.execute_requests(vec![ChangeRequest::set(1, String::from("foo"))]);
+            policy_constructor(handle)
+        });
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "max_concurrency must be positive")]
+    async fn test_panic_zero_concurrency() {
+        let metric_registry = metric::Registry::new();
+        let time_provider = Arc::new(MockProvider::new(Time::MIN));
+        let loader = Arc::new(TestLoader::<u8, (), String>::default());
+        let _policy_constructor = RefreshAheadPolicy::new(
+            time_provider,
+            Arc::new(ConstantValueTtlProvider::new(Some(Duration::from_secs(10)))),
+            Duration::from_secs(2),
+            2,
+            0,
+            loader,
+            "my_cache",
+            &metric_registry,
+            &Handle::current(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refresh_hot_entry() {
+        let TestState {
+            mut backend,
+            time_provider,
+            loader,
+            metric_registry,
+        } = TestState::new(1);
+
+        backend.set(1, String::from("a"));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+
+        // not refreshed too early
+        time_provider.inc(Duration::from_millis(7_999));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(get_metric(&metric_registry), 0);
+
+        loader.mock_next(1, String::from("b"));
+        time_provider.inc(Duration::from_millis(1));
+        wait_for_metric(&metric_registry, 1).await;
+        assert_eq!(get_inner(&mut backend, 1), Some(String::from("b")));
+        assert_eq!(loader.loaded(), vec![(1, ())]);
+
+        // hit counter was reset by the refresh, so the entry is not refreshed again
+        assert_eq!(backend.get(&1), Some(String::from("b")));
+        time_provider.inc(Duration::from_secs(8));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(get_metric(&metric_registry), 1);
+        assert_eq!(loader.loaded(), vec![(1, ())]);
+    }
+
+    #[tokio::test]
+    async fn test_cold_entry_is_not_refreshed() {
+        let TestState {
+            mut backend,
+            time_provider,
+            loader,
+            metric_registry,
+        } = TestState::new(1);
+
+        backend.set(1, String::from("a"));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+
+        // the loader would panic if it were called
+        time_provider.inc(Duration::from_secs(8));
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(get_metric(&metric_registry), 0);
+        assert_eq!(loader.loaded(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_concurrency() {
+        let TestState {
+            mut backend,
+            time_provider,
+            loader,
+            metric_registry,
+        } = TestState::new(1);
+
+        backend.set(1, String::from("a"));
+        backend.set(2, String::from("b"));
+        for k in [1, 2] {
+            backend.get(&k);
+            backend.get(&k);
+        }
+
+        let barrier_1 = loader.block_next(1, String::from("c"));
+        let barrier_2 = loader.block_next(2, String::from("d"));
+        time_provider.inc(Duration::from_secs(8));
+        wait_for_loads(&loader, 1).await;
+
+        // only one refresh runs at a time
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(loader.loaded().len(), 1);
+
+        let (first, second) = if loader.loaded()[0].0 == 1 {
+            (barrier_1, barrier_2)
+        } else {
+            (barrier_2, barrier_1)
+        };
+        first.wait().await;
+        wait_for_metric(&metric_registry, 1).await;
+        wait_for_loads(&loader, 2).await;
+        second.wait().await;
+        wait_for_metric(&metric_registry, 2).await;
+
+        assert_eq!(get_inner(&mut backend, 1), Some(String::from("c")));
+        assert_eq!(get_inner(&mut backend, 2), Some(String::from("d")));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_does_not_override_new_entries() {
+        let TestState {
+            mut backend,
+            time_provider,
+            loader,
+            ..
+        } = TestState::new(1);
+
+        backend.set(1, String::from("a"));
+        backend.get(&1);
+        backend.get(&1);
+
+        let barrier = loader.block_next(1, String::from("b"));
+        time_provider.inc(Duration::from_secs(8));
+        wait_for_loads(&loader, 1).await;
+
+        backend.set(1, String::from("c"));
+        wait_for_barrier_released(&barrier).await;
+        assert_eq!(get_inner(&mut backend, 1), Some(String::from("c")));
+    }
+
+    #[tokio::test]
+    async fn test_remove_cancels_refresh() {
+        let TestState {
+            mut backend,
+            time_provider,
+            loader,
+            ..
+        } = TestState::new(1);
+
+        backend.set(1, String::from("a"));
+        backend.get(&1);
+        backend.get(&1);
+
+        let barrier = loader.block_next(1, String::from("b"));
+        time_provider.inc(Duration::from_secs(8));
+        wait_for_loads(&loader, 1).await;
+
+        backend.remove(&1);
+        wait_for_barrier_released(&barrier).await;
+        assert_eq!(get_inner(&mut backend, 1), None);
+    }
+
+    #[tokio::test]
+    async fn test_generic_backend() {
+        use crate::backend::test_util::test_generic;
+
+        test_generic(|| {
+            let time_provider = Arc::new(MockProvider::new(Time::MIN));
+            let metric_registry = metric::Registry::new();
+            let loader = Arc::new(TestLoader::default());
+            let mut backend = PolicyBackend::hashmap_backed(Arc::clone(&time_provider) as _);
+
+            backend.add_policy(RefreshAheadPolicy::new(
+                time_provider,
+                Arc::new(ConstantValueTtlProvider::new(None)),
+                Duration::from_secs(2),
+                2,
+                1,
+                loader,
+                "my_cache",
+                &metric_registry,
+                &Handle::current(),
+            ));
+            backend
+        });
+    }
+
+    struct TestState {
+        backend: PolicyBackend<u8, String>,
+        metric_registry: metric::Registry,
+        time_provider: Arc<MockProvider>,
+        loader: Arc<TestLoader<u8, (), String>>,
+    }
+
+    impl TestState {
+        /// Entries expire after 10s, are refreshed 2s ahead and require 2 hits.
+        fn new(max_concurrency: usize) -> Self {
+            let time_provider = Arc::new(MockProvider::new(Time::MIN));
+            let metric_registry = metric::Registry::new();
+            let loader = Arc::new(TestLoader::default());
+
+            let mut backend = PolicyBackend::hashmap_backed(Arc::clone(&time_provider) as _);
+            backend.add_policy(RefreshAheadPolicy::new(
+                Arc::clone(&time_provider) as _,
+                Arc::new(ConstantValueTtlProvider::new(Some(Duration::from_secs(10)))),
+                Duration::from_secs(2),
+                2,
+                max_concurrency,
+                Arc::clone(&loader) as _,
+                "my_cache",
+                &metric_registry,
+                &Handle::current(),
+            ));
+
+            Self {
+                backend,
+                metric_registry,
+                time_provider,
+                loader,
+            }
+        }
+    }
+
+    fn get_inner(backend: &mut PolicyBackend<u8, String>, k: u8) -> Option<String> {
+        let inner_backend = backend.inner_ref();
+        let inner_backend = inner_backend
+            .as_any()
+            .downcast_ref::<HashMap<u8, String>>()
+            .unwrap();
+        inner_backend.get(&k).cloned()
+    }
+
+    fn get_metric(metric_registry: &metric::Registry) -> u64 {
+        let mut reporter = RawReporter::default();
+        metric_registry.report(&mut reporter);
+        let observation = reporter
+            .metric("cache_refresh_ahead")
+            .unwrap()
+            .observation(&[("name", "my_cache")])
+            .unwrap();
+
+        if let Observation::U64Counter(c) = observation {
+            *c
+        } else {
+            panic!("Wrong observation type")
+        }
+    }
+
+    async fn wait_for_metric(metric_registry: &metric::Registry, expected: u64) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while get_metric(metric_registry) != expected {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("metric not reached");
+    }
+
+    /// Wait until the background refreshes called the loader `n` times.
+    async fn wait_for_loads(loader: &TestLoader<u8, (), String>, n: usize) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while loader.loaded().len() < n {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("refresh not started");
+    }
+
+    /// Wait until the background refresh dropped its reference to the barrier, i.e. it was cancelled.
+    async fn wait_for_barrier_released(barrier: &Arc<tokio::sync::Barrier>) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while Arc::strong_count(barrier) != 1 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("refresh not cancelled");
+    }
+}
@@ -0,0 +1,420 @@
+//! Stale-while-revalidate handling.
+use std::{collections::HashMap, fmt::Debug, hash::Hash, sync::Arc, time::Duration};
+
+use iox_time::Time;
+use metric::U64Counter;
+use parking_lot::Mutex;
+use tokio::{runtime::Handle, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::loader::Loader;
+
+use super::{CacheBackend, CallbackHandle, ChangeRequest, Subscriber};
+
+/// Cache policy that serves stale entries while they are reloaded in the background.
+///
+/// An entry becomes stale `stale_after` after it was last set. The first access to a stale entry returns the stale
+/// value as-is and starts a background reload using the [`Loader`]. Once the reload finishes, the new value replaces
+/// the stale one. This keeps cache accesses from waiting on the loader when entries become outdated.
+///
+/// At most one reload per key runs at any time. A reload is cancelled if its key is removed or set by someone else
+/// in the meantime.
+///
+/// This policy does NOT evict entries. Combine it with a [TTL policy](super::ttl::TtlPolicy) that expires entries
+/// after a duration larger than `stale_after` to bound how long a stale entry that is not accessed is kept.
+#[derive(Debug)]
+pub struct StaleWhileRevalidatePolicy<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    stale_after: Duration,
+    loader: Arc<dyn Loader<K = K, V = V, Extra = ()>>,
+    callback_handle: Arc<Mutex<CallbackHandle<K, V>>>,
+    handle: Handle,
+    entries: HashMap<K, EntryState>,
+    metric_revalidated: U64Counter,
+}
+
+impl<K, V> StaleWhileRevalidatePolicy<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    /// Create new stale-while-revalidate policy.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        stale_after: Duration,
+        loader: Arc<dyn Loader<K = K, V = V, Extra = ()>>,
+        name: &'static str,
+        metric_registry: &metric::Registry,
+        handle: &Handle,
+    ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
+        let metric_revalidated = metric_registry
+            .register_metric::<U64Counter>(
+                "cache_stale_while_revalidate",
+                "Number of background reloads of stale cache entries.",
+            )
+            .recorder(&[("name", name)]);
+
+        // clone handle for callback
+        let handle = handle.clone();
+
+        move |mut callback_handle| {
+            callback_handle.execute_requests(vec![ChangeRequest::ensure_empty()]);
+
+            Self {
+                stale_after,
+                loader,
+                callback_handle: Arc::new(Mutex::new(callback_handle)),
+                handle,
+                entries: HashMap::new(),
+                metric_revalidated,
+            }
+        }
+    }
+
+    /// Start background reload for given key.
+    fn revalidate(&self, k: K) -> RunningRevalidation {
+        let cancelled = CancellationToken::default();
+
+        let loader = Arc::clone(&self.loader);
+        let callback_handle = Arc::clone(&self.callback_handle);
+        let metric_revalidated = self.metric_revalidated.clone();
+        let cancelled_captured = cancelled.clone();
+        let fut = async move {
+            // some `let`-dance so that rustc does not complain that `&K` is not `Send`
+            let k_for_loader = k.clone();
+            let v = loader.load(k_for_loader, ()).await;
+
+            let mut callback_handle = callback_handle.lock();
+            callback_handle.execute_requests(vec![ChangeRequest::from_fn(|backend| {
+                // Here we have the PolicyBackend implicit lock. There is no way our Subscriber can be
+                // active here, but we need to check if we have been canceled one last time.
+                if cancelled_captured.is_cancelled() {
+                    return;
+                }
+
+                backend.set(k, v);
+            })]);
+
+            // update metric AFTER change request
+            metric_revalidated.inc(1);
+        };
+
+        let cancelled_captured = cancelled.clone();
+        let task = self.handle.spawn(async move {
+            tokio::select! {
+                _ = cancelled_captured.cancelled() => {}
+                _ = fut => {}
+            }
+        });
+
+        RunningRevalidation { cancelled, task }
+    }
+}
+
+impl<K, V> Subscriber for StaleWhileRevalidatePolicy<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    type K = K;
+    type V = V;
+
+    fn get(&mut self, k: &Self::K, now: Time) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        let Some(state) = self.entries.get(k) else {
+            return vec![];
+        };
+
+        let is_stale = now
+            .checked_duration_since(state.t_set)
+            .map(|age| age >= self.stale_after)
+            .unwrap_or_default();
+        let is_running = state
+            .running
+            .as_ref()
+            .map(|running| !running.task.is_finished())
+            .unwrap_or_default();
+
+        if is_stale && !is_running {
+            let running = self.revalidate(k.clone());
+            if let Some(state) = self.entries.get_mut(k) {
+                state.running = Some(running);
+            }
+        }
+
+        vec![]
+    }
+
+    fn set(
+        &mut self,
+        k: &Self::K,
+        _v: &Self::V,
+        now: Time,
+    ) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        // replacing the state cancels any running reload, which would otherwise override the new value
+        self.entries.insert(
+            k.clone(),
+            EntryState {
+                t_set: now,
+                running: None,
+            },
+        );
+
+        vec![]
+    }
+
+    fn remove(&mut self, k: &Self::K, _now: Time) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        // the removal cancels any running reload
+        self.entries.remove(k);
+
+        vec![]
+    }
+}
+
+/// State of a cached entry.
+#[derive(Debug)]
+struct EntryState {
+    /// When the entry was set.
+    t_set: Time,
+
+    /// Potentially running background reload.
+    running: Option<RunningRevalidation>,
+}
+
+/// Background reload of a single entry.
+///
+/// The reload is cancelled on [`drop`](Drop::drop).
+#[derive(Debug)]
+struct RunningRevalidation {
+    cancelled: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl Drop for RunningRevalidation {
+    fn drop(&mut self) {
+        self.cancelled.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use iox_time::MockProvider;
+    use metric::{Observation, RawReporter};
+
+    use crate::{
+        backend::{policy::PolicyBackend, CacheBackend},
+        loader::test_util::TestLoader,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    #[should_panic(expected = "inner backend is not empty")]
+    async fn test_panic_inner_not_empty() {
+        let metric_registry = metric::Registry::new();
+        let time_provider = Arc::new(MockProvider::new(Time::MIN));
+        let loader = Arc::new(TestLoader::<u8, (), String>::default());
+        let mut backend = PolicyBackend::hashmap_backed(time_provider);
+        let policy_constructor = StaleWhileRevalidatePolicy::new(
+            Duration::from_secs(1),
+            loader,
+            "my_cache",
+            &metric_registry,
+            &Handle::current(),
+        );
+        backend.add_policy(|mut handle| {
+            handle.execute_requests(vec![ChangeRequest::set(1, String::from("foo"))]);
+            policy_constructor(handle)
+        });
+    }
+
+    #[tokio::test]
+    async fn test_serve_stale_and_revalidate() {
+        let TestState {
+            mut backend,
+            time_provider,
+            loader,
+            metric_registry,
+        } = TestState::new();
+
+        backend.set(1, String::from("a"));
+
+        // fresh entries are not reloaded
+        time_provider.inc(Duration::from_millis(999));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+        assert_eq!(get_metric(&metric_registry), 0);
+
+        // stale entry is served while the reload is running
+        let barrier = loader.block_next(1, String::from("b"));
+        time_provider.inc(Duration::from_millis(1));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+
+        // no second reload is started while one is running (the loader would panic)
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+
+        barrier.wait().await;
+        wait_for_metric(&metric_registry, 1).await;
+        assert_eq!(get_inner(&mut backend, 1), Some(String::from("b")));
+        assert_eq!(loader.loaded(), vec![(1, ())]);
+
+        // reloaded entry is fresh again
+        time_provider.inc(Duration::from_millis(999));
+        assert_eq!(backend.get(&1), Some(String::from("b")));
+        assert_eq!(loader.loaded(), vec![(1, ())]);
+    }
+
+    #[tokio::test]
+    async fn test_revalidation_does_not_override_new_entries() {
+        let TestState {
+            mut backend,
+            time_provider,
+            loader,
+            ..
+        } = TestState::new();
+
+        backend.set(1, String::from("a"));
+
+        let barrier = loader.block_next(1, String::from("b"));
+        time_provider.inc(Duration::from_secs(1));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+        wait_for_load(&loader).await;
+
+        backend.set(1, String::from("c"));
+        wait_for_barrier_released(&barrier).await;
+        assert_eq!(get_inner(&mut backend, 1), Some(String::from("c")));
+    }
+
+    #[tokio::test]
+    async fn test_remove_cancels_loader() {
+        let TestState {
+            mut backend,
+            time_provider,
+            loader,
+            ..
+        } = TestState::new();
+
+        backend.set(1, String::from("a"));
+
+        let barrier = loader.block_next(1, String::from("b"));
+        time_provider.inc(Duration::from_secs(1));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+        wait_for_load(&loader).await;
+
+        backend.remove(&1);
+        wait_for_barrier_released(&barrier).await;
+        assert_eq!(get_inner(&mut backend, 1), None);
+    }
+
+    #[tokio::test]
+    async fn test_generic_backend() {
+        use crate::backend::test_util::test_generic;
+
+        test_generic(|| {
+            let time_provider = Arc::new(MockProvider::new(Time::MIN));
+            let metric_registry = metric::Registry::new();
+            let loader = Arc::new(TestLoader::default());
+            let mut backend = PolicyBackend::hashmap_backed(time_provider);
+
+            backend.add_policy(StaleWhileRevalidatePolicy::new(
+                Duration::MAX,
+                loader,
+                "my_cache",
+                &metric_registry,
+                &Handle::current(),
+            ));
+            backend
+        });
+    }
+
+    struct TestState {
+        backend: PolicyBackend<u8, String>,
+        metric_registry: metric::Registry,
+        time_provider: Arc<MockProvider>,
+        loader: Arc<TestLoader<u8, (), String>>,
+    }
+
+    impl TestState {
+        fn new() -> Self {
+            let time_provider = Arc::new(MockProvider::new(Time::MIN));
+            let metric_registry = metric::Registry::new();
+            let loader = Arc::new(TestLoader::default());
+
+            let mut backend = PolicyBackend::hashmap_backed(Arc::clone(&time_provider) as _);
+            backend.add_policy(StaleWhileRevalidatePolicy::new(
+                Duration::from_secs(1),
+                Arc::clone(&loader) as _,
+                "my_cache",
+                &metric_registry,
+                &Handle::current(),
+            ));
+
+            Self {
+                backend,
+                metric_registry,
+                time_provider,
+                loader,
+            }
+        }
+    }
+
+    fn get_inner(backend: &mut PolicyBackend<u8, String>, k: u8) -> Option<String> {
+        let inner_backend = backend.inner_ref();
+        let inner_backend = inner_backend
+            .as_any()
+            .downcast_ref::<HashMap<u8, String>>()
+            .unwrap();
+        inner_backend.get(&k).cloned()
+    }
+
+    fn get_metric(metric_registry: &metric::Registry) -> u64 {
+        let mut reporter = RawReporter::default();
+        metric_registry.report(&mut reporter);
+        let observation = reporter
+            .metric("cache_stale_while_revalidate")
+            .unwrap()
+            .observation(&[("name", "my_cache")])
+            .unwrap();
+
+        if let Observation::U64Counter(c) = observation {
+            *c
+        } else {
+            panic!("Wrong observation type")
+        }
+    }
+
+    async fn wait_for_metric(metric_registry: &metric::Registry, expected: u64) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while get_metric(metric_registry) != expected {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("metric not reached");
+    }
+
+    /// Wait until the background reload called the loader.
+    async fn wait_for_load(loader: &TestLoader<u8, (), String>) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while loader.loaded().is_empty() {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("reload not started");
+    }
+
+    /// Wait until the background reload dropped its reference to the barrier, i.e. it was cancelled.
+    async fn wait_for_barrier_released(barrier: &Arc<tokio::sync::Barrier>) {
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while Arc::strong_count(barrier) != 1 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("reload not cancelled");
+    }
+}
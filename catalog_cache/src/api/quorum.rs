@@ -5,6 +5,7 @@ use crate::local::CatalogCache;
 use crate::{CacheKey, CacheValue};
 use futures::channel::oneshot;
 use futures::future::{select, Either};
+use futures::stream::FuturesUnordered;
 use futures::{pin_mut, StreamExt};
 use snafu::{ResultExt, Snafu};
 use std::collections::HashMap;
@@ -16,7 +17,7 @@ use tokio_util::sync::CancellationToken;
 #[allow(missing_docs)]
 #[derive(Debug, Snafu)]
 pub enum Error {
-    #[snafu(display("Failed to communicate with any remote replica: {source}"))]
+    #[snafu(display("Failed to replicate write to a quorum of replicas: {source}"))]
     NoRemote { source: ClientError },
 
     #[snafu(display("Write task was aborted"))]
@@ -27,7 +28,7 @@ pub enum Error {
 
     #[snafu(display("Failed to establish a read quorum: {generations:?}"))]
     Quorum {
-        generations: [Result<Option<u64>, ClientError>; 3],
+        generations: Vec<Result<Option<u64>, ClientError>>,
     },
 
     #[snafu(display("Failed to list replica: {source}"))]
@@ -40,11 +41,15 @@ pub enum Error {
 /// Result for [`QuorumCatalogCache`]
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-/// Performs quorum reads and writes across a local [`CatalogCache`] and two [`CatalogCacheClient`]
+/// Performs quorum reads and writes across a local [`CatalogCache`] and any number of [`CatalogCacheClient`]
+///
+/// The local cache counts as one of the nodes, i.e. with four replicas a quorum of three requires the local
+/// cache and two replicas to agree.
 #[derive(Debug)]
 pub struct QuorumCatalogCache {
     local: Arc<CatalogCache>,
-    replicas: Arc<[CatalogCacheClient; 2]>,
+    replicas: Arc<[CatalogCacheClient]>,
+    quorum: usize,
     shutdown: CancellationToken,
 }
 
@@ -55,66 +60,95 @@ impl Drop for QuorumCatalogCache {
 }
 
 impl QuorumCatalogCache {
-    /// Create a new [`QuorumCatalogCache`]
-    pub fn new(local: Arc<CatalogCache>, replicas: Arc<[CatalogCacheClient; 2]>) -> Self {
+    /// Create a new [`QuorumCatalogCache`] that requires a majority of the nodes to agree
+    pub fn new(local: Arc<CatalogCache>, replicas: Arc<[CatalogCacheClient]>) -> Self {
+        let quorum = majority(replicas.len() + 1);
         Self {
             local,
             replicas,
+            quorum,
             shutdown: CancellationToken::new(),
         }
     }
 
+    /// Set the number of nodes, including the local cache, that must agree on reads and writes
+    ///
+    /// # Panics
+    ///
+    /// Panics if `quorum` is not a majority of the nodes, as then reads would not be guaranteed
+    /// to observe the latest successful write
+    pub fn with_quorum(mut self, quorum: usize) -> Self {
+        let nodes = self.replicas.len() + 1;
+        assert!(
+            (majority(nodes)..=nodes).contains(&quorum),
+            "quorum of {quorum} is not a majority of {nodes} nodes"
+        );
+        self.quorum = quorum;
+        self
+    }
+
+    /// The number of nodes, including the local cache, that must agree on reads and writes
+    pub fn quorum(&self) -> usize {
+        self.quorum
+    }
+
     /// Retrieve the given value from the remote cache
     ///
+    /// A replica agrees with the local cache if it has the same or a newer generation, in which
+    /// case the local cache missed a write, e.g. because it has not arrived yet
+    ///
     /// Returns `None` if value is not present in a quorum of replicas
     /// Returns [`Error::Quorum`] if cannot establish a read quorum
     pub async fn get(&self, key: CacheKey) -> Result<Option<CacheValue>> {
         let local = self.local.get(key);
+        let local_generation = local.as_ref().map(|x| x.generation);
+        if self.quorum <= 1 {
+            return Ok(local);
+        }
 
-        let fut1 = self.replicas[0].get(key);
-        let fut2 = self.replicas[1].get(key);
-        pin_mut!(fut1);
-        pin_mut!(fut2);
-
-        match select(fut1, fut2).await {
-            Either::Left((result, fut)) | Either::Right((result, fut)) => match (local, result) {
-                (None, Ok(None)) => Ok(None),
-                (Some(l), Ok(Some(r))) if l.generation <= r.generation => {
-                    // preempt write from remote to local that arrives late
-                    if l.generation < r.generation {
-                        self.local.insert(key, r.clone())?;
+        let mut missing = usize::from(local.is_none());
+        // The number of nodes that agree on each generation returned by a replica
+        let mut found: HashMap<u64, (usize, CacheValue)> = HashMap::new();
+        let mut generations = vec![Ok(local_generation)];
+
+        let mut requests = self
+            .replicas
+            .iter()
+            .map(|r| r.get(key))
+            .collect::<FuturesUnordered<_>>();
+
+        while let Some(result) = requests.next().await {
+            match &result {
+                Ok(None) => {
+                    missing += 1;
+                    if missing >= self.quorum {
+                        return Ok(None);
                     }
-                    Ok(Some(r))
                 }
-                (local, r1) => {
-                    // r1 either failed or did not return anything
-                    let r2 = fut.await;
-                    match (local, r1, r2) {
-                        (None, _, Ok(None)) | (_, Ok(None), Ok(None)) => Ok(None),
-                        (Some(l), _, Ok(Some(r))) if l.generation <= r.generation => {
-                            // preempt write from remote to local that arrives late
-                            if l.generation < r.generation {
-                                self.local.insert(key, r.clone())?;
-                            }
-                            Ok(Some(r))
-                        }
-                        (local, Ok(Some(l)), Ok(Some(r))) if l.generation == r.generation => {
-                            if local.map(|x| x.generation < l.generation).unwrap_or(true) {
-                                self.local.insert(key, l.clone())?;
-                            }
-                            Ok(Some(l))
+                Ok(Some(r)) => {
+                    let (count, value) = found.entry(r.generation).or_insert_with(|| {
+                        let local = local_generation.map(|l| l <= r.generation);
+                        (usize::from(local.unwrap_or_default()), r.clone())
+                    });
+                    *count += 1;
+                    if *count >= self.quorum {
+                        let value = value.clone();
+                        // preempt write from remote to local that arrives late
+                        if local_generation
+                            .map(|l| l < value.generation)
+                            .unwrap_or(true)
+                        {
+                            self.local.insert(key, value.clone())?;
                         }
-                        (l, r1, r2) => Err(Error::Quorum {
-                            generations: [
-                                Ok(l.map(|x| x.generation)),
-                                r1.map(|x| x.map(|x| x.generation)),
-                                r2.map(|x| x.map(|x| x.generation)),
-                            ],
-                        }),
+                        return Ok(Some(value));
                     }
                 }
-            },
+                Err(_) => {}
+            }
+            generations.push(result.map(|x| x.map(|x| x.generation)));
         }
+
+        Err(Error::Quorum { generations })
     }
 
     /// Upsert the given key-value pair
@@ -124,72 +158,103 @@ impl QuorumCatalogCache {
         self.local.insert(key, value.clone())?;
 
         let replicas = Arc::clone(&self.replicas);
+        // The local write counts towards the quorum
+        let required = self.quorum.saturating_sub(1);
         let (sender, receiver) = oneshot::channel();
 
         let fut = async move {
-            let fut1 = replicas[0].put(key, &value);
-            let fut2 = replicas[1].put(key, &value);
-            pin_mut!(fut1);
-            pin_mut!(fut2);
-
-            match select(fut1, fut2).await {
-                Either::Left((r, fut)) | Either::Right((r, fut)) => {
-                    let _ = sender.send(r);
-                    fut.await
+            let mut sender = Some(sender);
+            if required == 0 {
+                if let Some(sender) = sender.take() {
+                    let _ = sender.send(Ok(()));
+                }
+            }
+
+            let mut requests = replicas
+                .iter()
+                .map(|r| r.put(key, &value))
+                .collect::<FuturesUnordered<_>>();
+
+            let mut acks = 0;
+            let mut pending = replicas.len();
+            while let Some(r) = requests.next().await {
+                pending -= 1;
+                match r {
+                    Ok(_) => {
+                        acks += 1;
+                        if acks >= required {
+                            if let Some(sender) = sender.take() {
+                                let _ = sender.send(Ok(()));
+                            }
+                        }
+                    }
+                    Err(source) => {
+                        if acks + pending < required {
+                            if let Some(sender) = sender.take() {
+                                let _ = sender.send(Err(source));
+                            }
+                        }
+                    }
                 }
             }
         };
 
-        // We spawn a tokio task so that we can potentially continue to replicate
-        // to the second replica asynchronously once we receive an ok response
+        // We spawn a tokio task so that we can continue to replicate to the
+        // remaining replicas asynchronously once a quorum has acknowledged the write
         let cancel = self.shutdown.child_token();
         let handle = tokio::spawn(async move {
             let cancelled = cancel.cancelled();
             pin_mut!(fut);
             pin_mut!(cancelled);
-            match select(cancelled, fut).await {
-                Either::Left(_) => Err(Error::Cancelled),
-                Either::Right((Ok(_), _)) => Ok(()),
-                Either::Right((Err(source), _)) => Err(Error::NoRemote { source }),
-            }
+            let _ = select(cancelled, fut).await;
         });
 
         match receiver.await {
-            Ok(Ok(_)) => Ok(()),
-            _ => match handle.await {
-                Ok(r) => r,
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(source)) => Err(Error::NoRemote { source }),
+            // The sender is only dropped without sending if the write was cancelled
+            Err(_) => match handle.await {
+                Ok(()) => Err(Error::Cancelled),
                 Err(source) => Err(Error::Join { source }),
             },
         }
     }
 
-    /// Warm the local cache by performing quorum reads from the other two replicas
+    /// Warm the local cache by performing quorum reads from the replicas
     ///
     /// This method should be called after this server has been participating in the write quorum
     /// for a period of time, e.g. 1 minute. This avoids an issue where a quorum cannot be
     /// established for in-progress writes.
     pub async fn warm(&self) -> Result<()> {
+        let Some((last, others)) = self.replicas.split_last() else {
+            return Ok(());
+        };
+
         // List doesn't return keys in any particular order
         //
-        // We therefore build a hashmap with the keys from one replica and compare
-        // this against those returned by the other
+        // We therefore count the replicas with each generation of each key, only fetching
+        // values from the last replica, and compare this against those returned by it
         //
         // We don't need to consult the local `CatalogCache`, as we only need to insert
         // if a read quorum can be established between the replicas and isn't present locally
-        let mut generations = HashMap::with_capacity(128);
-        let mut list = self.replicas[0].list(Some(0));
-        while let Some(entry) = list.next().await.transpose().context(ListSnafu)? {
-            if let Some(k) = entry.key() {
-                generations.insert(k, entry.generation());
+        let mut generations: HashMap<_, usize> = HashMap::with_capacity(128);
+        for replica in others {
+            let mut list = replica.list(Some(0));
+            while let Some(entry) = list.next().await.transpose().context(ListSnafu)? {
+                if let Some(k) = entry.key() {
+                    *generations.entry((k, entry.generation())).or_default() += 1;
+                }
             }
         }
 
-        let mut list = self.replicas[1].list(None);
+        let mut list = last.list(None);
         while let Some(entry) = list.next().await.transpose().context(ListSnafu)? {
             if let Some(k) = entry.key() {
-                match (generations.get(&k), entry.value()) {
-                    (Some(generation), Some(v)) if *generation == entry.generation() => {
-                        let value = CacheValue::new(v.clone(), *generation);
+                let generation = entry.generation();
+                let count = generations.remove(&(k, generation)).unwrap_or_default() + 1;
+                match entry.value() {
+                    Some(v) if count >= self.quorum => {
+                        let value = CacheValue::new(v.clone(), generation);
                         // In the case that local already has the given version
                         // this will be a no-op
                         self.local.insert(k, value)?;
@@ -198,10 +263,34 @@ impl QuorumCatalogCache {
                 }
             }
         }
+
+        // With more than two replicas, a quorum may have been established without the last replica
+        generations.retain(|_, count| *count >= self.quorum);
+        for replica in others {
+            if generations.is_empty() {
+                break;
+            }
+
+            let mut list = replica.list(None);
+            while let Some(entry) = list.next().await.transpose().context(ListSnafu)? {
+                if let (Some(k), Some(v)) = (entry.key(), entry.value()) {
+                    let generation = entry.generation();
+                    if generations.remove(&(k, generation)).is_some() {
+                        self.local
+                            .insert(k, CacheValue::new(v.clone(), generation))?;
+                    }
+                }
+            }
+        }
         Ok(())
     }
 }
 
+/// The smallest number of nodes that is a majority of `nodes`
+fn majority(nodes: usize) -> usize {
+    nodes / 2 + 1
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,7 +305,7 @@ mod tests {
         let r1 = TestCacheServer::bind_ephemeral();
         let r2 = TestCacheServer::bind_ephemeral();
 
-        let replicas = Arc::new([r1.client(), r2.client()]);
+        let replicas: Arc<[_]> = Arc::new([r1.client(), r2.client()]);
         let quorum = QuorumCatalogCache::new(Arc::clone(&local), Arc::clone(&replicas));
 
         assert_eq!(quorum.get(CacheKey::Table(1)).await.unwrap(), None);
@@ -310,7 +399,7 @@ mod tests {
         let r1 = TestCacheServer::bind_ephemeral();
         let r2 = TestCacheServer::bind_ephemeral();
 
-        let replicas = Arc::new([r1.client(), r2.client()]);
+        let replicas: Arc<[_]> = Arc::new([r1.client(), r2.client()]);
         let quorum = QuorumCatalogCache::new(Arc::clone(&local), Arc::clone(&replicas));
 
         let key = CacheKey::Table(1);
@@ -380,13 +469,103 @@ mod tests {
         assert_eq!(local.get(key).unwrap(), v6);
     }
 
+    #[tokio::test]
+    async fn test_larger_quorum() {
+        let local = Arc::new(CatalogCache::default());
+        let r1 = TestCacheServer::bind_ephemeral();
+        let r2 = TestCacheServer::bind_ephemeral();
+        let r3 = TestCacheServer::bind_ephemeral();
+        let r4 = TestCacheServer::bind_ephemeral();
+
+        let replicas: Arc<[_]> = Arc::new([r1.client(), r2.client(), r3.client(), r4.client()]);
+        let quorum = QuorumCatalogCache::new(Arc::clone(&local), Arc::clone(&replicas));
+        assert_eq!(quorum.quorum(), 3);
+
+        let k1 = CacheKey::Table(1);
+        let k2 = CacheKey::Table(2);
+        let v1 = CacheValue::new("v1".into(), 1);
+        let v2 = CacheValue::new("v2".into(), 2);
+
+        quorum.put(k1, v1.clone()).await.unwrap();
+        assert_eq!(quorum.get(k1).await.unwrap().unwrap(), v1);
+
+        // A single replica with a value is not a quorum
+        r1.cache().insert(k2, v1.clone()).unwrap();
+        assert_eq!(quorum.get(k2).await.unwrap(), None);
+
+        // Local and two replicas form a quorum even though the other two disagree
+        r2.cache().insert(k2, v1.clone()).unwrap();
+        r3.cache().insert(k2, v2.clone()).unwrap();
+        r4.cache().insert(k2, v2.clone()).unwrap();
+        local.insert(k2, v2.clone()).unwrap();
+        assert_eq!(quorum.get(k2).await.unwrap().unwrap(), v2);
+
+        // Can tolerate the loss of two replicas
+        r1.shutdown().await;
+        r2.shutdown().await;
+        quorum.put(k1, v2.clone()).await.unwrap();
+        assert_eq!(quorum.get(k1).await.unwrap().unwrap(), v2);
+
+        // But not three
+        r3.shutdown().await;
+        let v3 = CacheValue::new("v3".into(), 3);
+        let err = quorum.put(k1, v3).await.unwrap_err();
+        assert!(matches!(err, Error::NoRemote { .. }), "{err}");
+        let err = quorum.get(k1).await.unwrap_err();
+        assert!(matches!(err, Error::Quorum { .. }), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_warm_larger_quorum() {
+        let r1 = TestCacheServer::bind_ephemeral();
+        let r2 = TestCacheServer::bind_ephemeral();
+        let r3 = TestCacheServer::bind_ephemeral();
+        let r4 = TestCacheServer::bind_ephemeral();
+
+        let replicas: Arc<[_]> = Arc::new([r1.client(), r2.client(), r3.client(), r4.client()]);
+        let local = Arc::new(CatalogCache::default());
+        let quorum = QuorumCatalogCache::new(Arc::clone(&local), Arc::clone(&replicas));
+
+        let k1 = CacheKey::Table(1);
+        let k2 = CacheKey::Table(2);
+        let k3 = CacheKey::Table(3);
+        let v1 = CacheValue::new("v1".into(), 1);
+
+        // Quorum without the last replica
+        for r in [&r1, &r2, &r3] {
+            r.cache().insert(k1, v1.clone()).unwrap();
+        }
+        // Quorum including the last replica
+        for r in [&r1, &r3, &r4] {
+            r.cache().insert(k2, v1.clone()).unwrap();
+        }
+        // No quorum
+        for r in [&r2, &r4] {
+            r.cache().insert(k3, v1.clone()).unwrap();
+        }
+
+        quorum.warm().await.unwrap();
+
+        let mut entries: Vec<_> = local.list().collect();
+        entries.sort_unstable_by_key(|(k, _)| *k);
+        assert_eq!(entries, vec![(k1, v1.clone()), (k2, v1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "quorum of 2 is not a majority of 5 nodes")]
+    fn test_invalid_quorum() {
+        let client = || CatalogCacheClient::try_new("http://127.0.0.1:1".parse().unwrap()).unwrap();
+        let replicas: Arc<[_]> = Arc::new([client(), client(), client(), client()]);
+        QuorumCatalogCache::new(Arc::default(), replicas).with_quorum(2);
+    }
+
     #[tokio::test]
     async fn test_warm() {
         let local = Arc::new(CatalogCache::default());
         let r1 = TestCacheServer::bind_ephemeral();
         let r2 = TestCacheServer::bind_ephemeral();
 
-        let replicas = Arc::new([r1.client(), r2.client()]);
+        let replicas: Arc<[_]> = Arc::new([r1.client(), r2.client()]);
         let quorum = QuorumCatalogCache::new(local, Arc::clone(&replicas));
 
         let k1 = CacheKey::Table(1);
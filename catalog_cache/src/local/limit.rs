@@ -6,7 +6,7 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 #[derive(Debug)]
 pub(crate) struct MemoryLimiter {
     current: AtomicUsize,
-    limit: usize,
+    limit: AtomicUsize,
 }
 
 impl MemoryLimiter {
@@ -14,13 +14,21 @@ impl MemoryLimiter {
     pub(crate) fn new(limit: usize) -> Self {
         Self {
             current: AtomicUsize::new(0),
-            limit,
+            limit: AtomicUsize::new(limit),
         }
     }
 
+    /// Change the limit to `limit` bytes
+    ///
+    /// Lowering the limit below the reserved bytes does not free anything, but
+    /// further reservations fail until enough bytes are freed
+    pub(crate) fn set_limit(&self, limit: usize) {
+        self.limit.store(limit, Ordering::Relaxed);
+    }
+
     /// Reserve `size` bytes, returning an error if this would exceed the limit
     pub(crate) fn reserve(&self, size: usize) -> Result<()> {
-        let limit = self.limit;
+        let limit = self.limit.load(Ordering::Relaxed);
         let max = limit
             .checked_sub(size)
             .ok_or(Error::TooLarge { size, limit })?;
@@ -78,5 +86,13 @@ mod tests {
         // Protected against overflow
         let err = limiter.reserve(usize::MAX).unwrap_err();
         assert!(matches!(err, Error::TooLarge { .. }), "{err}");
+
+        // Lowering the limit below the reserved bytes
+        limiter.reserve(50).unwrap();
+        limiter.set_limit(40);
+        let err = limiter.reserve(1).unwrap_err().to_string();
+        assert_eq!(err, "Cannot reserve additional 1 bytes for cache containing 50 bytes as would exceed limit of 40 bytes");
+        limiter.free(50);
+        limiter.reserve(40).unwrap();
     }
 }
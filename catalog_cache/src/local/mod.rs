@@ -130,6 +130,17 @@ impl CatalogCache {
         }
     }
 
+    /// Change the memory limit to `limit` bytes
+    ///
+    /// Has no effect on caches created without a limit. Lowering the limit below
+    /// the size of the cache does not evict anything, but new values are rejected
+    /// until enough are evicted by [`CatalogCache::evict_unused`]
+    pub fn set_limit(&self, limit: usize) {
+        if let Some(l) = &self.limit {
+            l.set_limit(limit);
+        }
+    }
+
     /// Returns an iterator over the items in this cache
     pub fn list(&self) -> CacheIterator<'_> {
         CacheIterator(self.map.iter())
@@ -351,5 +362,13 @@ mod tests {
         cache.evict_unused();
 
         cache.insert(k2, CacheValue::new(v_100.clone(), 1)).unwrap();
+
+        // Lower the limit below the current size of 200 bytes
+        cache.set_limit(150);
+        let r = cache.insert(k1, CacheValue::new(v_20.clone(), 2));
+        assert_eq!(r.unwrap_err().to_string(), "Cannot reserve additional 20 bytes for cache containing 200 bytes as would exceed limit of 150 bytes");
+
+        cache.delete(k2).unwrap();
+        cache.insert(k1, CacheValue::new(v_20.clone(), 2)).unwrap();
     }
 }
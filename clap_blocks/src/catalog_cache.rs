@@ -2,8 +2,7 @@
 
 use std::time::Duration;
 
-use itertools::Itertools;
-use snafu::{OptionExt, Snafu};
+use snafu::{ensure, Snafu};
 use url::{Host, Url};
 
 use crate::memory_size::MemorySize;
@@ -20,8 +19,11 @@ pub enum Error {
     #[snafu(display("invalid url: {source}"))]
     InvalidUrl { source: url::ParseError },
 
-    #[snafu(display("Expected exactly two peers"))]
+    #[snafu(display("Expected at least two peers"))]
     InvalidPeers,
+
+    #[snafu(display("quorum of {quorum} is not a majority of {nodes} nodes"))]
+    InvalidQuorum { quorum: usize, nodes: usize },
 }
 
 /// CLI config for catalog configuration
@@ -90,22 +92,46 @@ pub struct CatalogConfig {
         default_value_t = 10
     )]
     pub quorum_fanout: usize,
+
+    /// Number of nodes, including this one, that must agree on reads and writes.
+    ///
+    /// Must be a majority of the nodes, i.e. of the peers plus this node. Defaults to the smallest majority, e.g. 2
+    /// of 3 or 3 of 5 nodes.
+    #[clap(
+        long = "catalog-cache-quorum",
+        env = "INFLUXDB_IOX_CATALOG_CACHE_QUORUM"
+    )]
+    pub quorum: Option<usize>,
 }
 
 impl CatalogConfig {
     /// Return URL of other catalog cache nodes.
-    pub fn peers(&self) -> Result<[Url; 2], Error> {
-        let (peer1, peer2) = self
+    pub fn peers(&self) -> Result<Vec<Url>, Error> {
+        let peers: Vec<_> = self
             .peers
             .iter()
             .filter(|x| match (x.host(), &self.hostname) {
                 (Some(a), Some(r)) => &a != r,
                 _ => true,
             })
-            .collect_tuple()
-            .context(InvalidPeersSnafu)?;
+            .cloned()
+            .collect();
+        ensure!(peers.len() >= 2, InvalidPeersSnafu);
+
+        Ok(peers)
+    }
 
-        Ok([peer1.clone(), peer2.clone()])
+    /// Return the number of nodes, including this one, that must agree on reads and writes.
+    pub fn quorum(&self) -> Result<usize, Error> {
+        let nodes = self.peers()?.len() + 1;
+        let majority = nodes / 2 + 1;
+        let quorum = self.quorum.unwrap_or(majority);
+        ensure!(
+            (majority..=nodes).contains(&quorum),
+            InvalidQuorumSnafu { quorum, nodes }
+        );
+
+        Ok(quorum)
     }
 }
 
@@ -144,11 +170,52 @@ mod tests {
             "--catalog-cache-peers",
             "http://peer1:8080,http://peer2:9090,http://peer3:9091",
         ]);
-        let err = config.peers().unwrap_err();
-        assert!(matches!(err, Error::InvalidPeers), "{err}");
+        let peer3 = Url::parse("http://peer3:9091").unwrap();
+        let peers = config.peers().unwrap();
+        assert_eq!(peers, [peer1.clone(), peer2.clone(), peer3]);
 
         config.hostname = Some(Host::parse("peer3").unwrap());
         let peers = config.peers().unwrap();
         assert_eq!(peers, [peer1.clone(), peer2.clone()]);
+
+        config.hostname = Some(Host::parse("peer1").unwrap());
+        config.peers = vec![peer1.clone(), peer2.clone()];
+        let err = config.peers().unwrap_err();
+        assert!(matches!(err, Error::InvalidPeers), "{err}");
+    }
+
+    #[test]
+    fn test_quorum() {
+        let mut config = CatalogConfig::parse_from([
+            "binary",
+            "--catalog-cache-peers",
+            "http://peer1:8080,http://peer2:9090",
+        ]);
+        assert_eq!(config.quorum().unwrap(), 2);
+
+        config.peers.push(Url::parse("http://peer3:9091").unwrap());
+        config.peers.push(Url::parse("http://peer4:9092").unwrap());
+        assert_eq!(config.quorum().unwrap(), 3);
+
+        config.quorum = Some(5);
+        assert_eq!(config.quorum().unwrap(), 5);
+
+        for quorum in [2, 6] {
+            config.quorum = Some(quorum);
+            let err = config.quorum().unwrap_err();
+            assert!(
+                matches!(err, Error::InvalidQuorum { nodes: 5, .. }),
+                "{err}"
+            );
+        }
+
+        let config = CatalogConfig::parse_from([
+            "binary",
+            "--catalog-cache-peers",
+            "http://peer1:8080,http://peer2:9090,http://peer3:9091,http://peer4:9092",
+            "--catalog-cache-quorum",
+            "4",
+        ]);
+        assert_eq!(config.quorum().unwrap(), 4);
     }
 }
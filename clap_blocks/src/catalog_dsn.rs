@@ -67,6 +67,7 @@ pub struct CatalogDsnConfig {
     ///
     /// Memory (ephemeral, only useful for testing): `memory`
     ///
+    /// The value can also be read from the file given by `INFLUXDB_IOX_CATALOG_DSN_FILE`.
     #[clap(long = "catalog-dsn", env = "INFLUXDB_IOX_CATALOG_DSN", action)]
     pub dsn: Option<String>,
 
@@ -0,0 +1,313 @@
+//! Config file support for all config blocks.
+//!
+//! A TOML config file can be passed via `--config-file` / `INFLUXDB_IOX_CONFIG_FILE`. Its top-level keys are the
+//! names of command line flags (without the leading `--`), e.g.
+//!
+//! ```toml
+//! catalog-cache-peers = ["http://peer1:8080", "http://peer2:8080"]
+//! catalog-cache-gc-interval = "10m"
+//! exec-mem-pool-bytes = "50%"
+//! ```
+//!
+//! Values from the file have the lowest precedence: they are only used for flags that are neither given on the
+//! command line nor via their environment variable, but they take precedence over the default values.
+
+use std::{
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+};
+
+use clap::{parser::ValueSource, Arg, Command};
+use snafu::{ResultExt, Snafu};
+
+use crate::secret_file::resolve_secret_files;
+
+/// Env var providing the config file
+pub const CONFIG_FILE_ENV_NAME: &str = "INFLUXDB_IOX_CONFIG_FILE";
+/// CLI flag for the config file
+pub const CONFIG_FILE_FLAG: &str = "config-file";
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("Could not read config file {}: {source}", path.display()))]
+    ReadFile { path: PathBuf, source: io::Error },
+
+    #[snafu(display("Could not parse config file {}: {source}", path.display()))]
+    ParseFile {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[snafu(display("Unknown key '{key}' in config file {}", path.display()))]
+    UnknownKey { path: PathBuf, key: String },
+
+    #[snafu(display("Invalid value for key '{key}' in config file {}: {reason}", path.display()))]
+    InvalidValue {
+        path: PathBuf,
+        key: String,
+        reason: &'static str,
+    },
+
+    #[snafu(display("{source}"))]
+    SecretFile { source: crate::secret_file::Error },
+}
+
+/// CLI config for the config file.
+///
+/// Flatten this into the top-level config and pass the command line arguments through [`merge_config_file`]
+/// before parsing them.
+#[derive(Debug, Clone, PartialEq, Eq, clap::Parser)]
+pub struct ConfigFileConfig {
+    /// TOML config file.
+    ///
+    /// The keys are the names of the command line flags without the leading `--`. Command line flags and
+    /// environment variables take precedence over the values from the file.
+    #[clap(long = CONFIG_FILE_FLAG, env = CONFIG_FILE_ENV_NAME, action)]
+    pub config_file: Option<PathBuf>,
+}
+
+/// Appends the values of the config file given in `args` (or via [`CONFIG_FILE_ENV_NAME`]) to `args`.
+///
+/// Only flags that were neither given in `args` nor via their environment variable are appended. Only the flags of
+/// `command` itself are considered, not the ones of its subcommands. If `command` has no [`ConfigFileConfig`] or no
+/// config file is given, `args` is returned unchanged.
+///
+/// [Secret files](crate::secret_file) are resolved first, so they take precedence over the config file.
+///
+/// The returned arguments can then be parsed as usual, e.g. via [`clap::Parser::parse_from`].
+pub fn merge_config_file<I, T>(command: &Command, args: I) -> Result<Vec<OsString>, Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut args = resolve_secret_files(command, args).context(SecretFileSnafu)?;
+
+    let Some(config_file_arg) = find_arg(command, CONFIG_FILE_FLAG) else {
+        return Ok(args);
+    };
+
+    // Errors (incl. `--help`) are reported by the actual parsing
+    let Ok(matches) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(args.clone())
+    else {
+        return Ok(args);
+    };
+    let Some(path) = matches
+        .try_get_one::<PathBuf>(config_file_arg.get_id().as_str())
+        .ok()
+        .flatten()
+        .cloned()
+    else {
+        return Ok(args);
+    };
+
+    let content = std::fs::read_to_string(&path).context(ReadFileSnafu { path: &path })?;
+    let table = content
+        .parse::<toml::Table>()
+        .context(ParseFileSnafu { path: &path })?;
+
+    for (key, value) in table {
+        let Some(arg) = find_arg(command, &key) else {
+            return UnknownKeySnafu { path, key }.fail();
+        };
+
+        if matches!(
+            matches.value_source(arg.get_id().as_str()),
+            Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+        ) {
+            continue;
+        }
+
+        if !arg.get_action().takes_values() {
+            match value {
+                toml::Value::Boolean(true) => args.push(format!("--{key}").into()),
+                toml::Value::Boolean(false) => {}
+                _ => {
+                    return InvalidValueSnafu {
+                        path,
+                        key,
+                        reason: "expected a boolean",
+                    }
+                    .fail()
+                }
+            }
+            continue;
+        }
+
+        for value in values(&path, &key, value)? {
+            args.push(format!("--{key}={value}").into());
+        }
+    }
+
+    Ok(args)
+}
+
+/// Finds the argument of `command` with the given long flag.
+fn find_arg<'a>(command: &'a Command, long: &str) -> Option<&'a Arg> {
+    command
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(long))
+}
+
+/// Converts a value of the config file to the values of a command line flag.
+///
+/// Arrays are passed as repeated flags.
+fn values(path: &Path, key: &str, value: toml::Value) -> Result<Vec<String>, Error> {
+    let value = match value {
+        toml::Value::Array(values) => {
+            return values
+                .into_iter()
+                .map(|value| match value {
+                    toml::Value::Array(_) | toml::Value::Table(_) => InvalidValueSnafu {
+                        path,
+                        key,
+                        reason: "nested arrays and tables are not supported",
+                    }
+                    .fail(),
+                    value => Ok(scalar(value)),
+                })
+                .collect();
+        }
+        toml::Value::Table(_) => {
+            return InvalidValueSnafu {
+                path,
+                key,
+                reason: "tables are not supported",
+            }
+            .fail()
+        }
+        value => scalar(value),
+    };
+
+    Ok(vec![value])
+}
+
+/// Formats a scalar value as it would be given on the command line.
+fn scalar(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s,
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use super::*;
+
+    #[derive(Debug, clap::Parser)]
+    struct TestConfig {
+        #[clap(flatten)]
+        config_file: ConfigFileConfig,
+
+        #[clap(long = "name", default_value = "default")]
+        name: String,
+
+        /// Only used by `test_precedence`, as tests share the environment
+        #[clap(long = "region", env = "INFLUXDB_IOX_TEST_CONFIG_FILE_REGION")]
+        region: Option<String>,
+
+        #[clap(long = "peers", value_delimiter = ',')]
+        peers: Vec<String>,
+
+        #[clap(long = "limit")]
+        limit: Option<usize>,
+
+        #[clap(long = "enabled")]
+        enabled: bool,
+    }
+
+    fn parse(args: &[&str]) -> Result<TestConfig, Error> {
+        let args = merge_config_file(&TestConfig::command(), args.iter().copied())?;
+        Ok(TestConfig::parse_from(args))
+    }
+
+    fn config_file(content: &str) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), content).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_no_config_file() {
+        let config = parse(&["binary", "--limit", "1"]).unwrap();
+        assert_eq!(config.config_file.config_file, None);
+        assert_eq!(config.name, "default");
+        assert_eq!(config.limit, Some(1));
+    }
+
+    #[test]
+    fn test_values() {
+        let file = config_file(
+            r#"
+            name = "from-file"
+            peers = ["a", "b"]
+            limit = 10
+            enabled = true
+            "#,
+        );
+        let path = file.path().to_str().unwrap();
+
+        let config = parse(&["binary", "--config-file", path]).unwrap();
+        assert_eq!(config.name, "from-file");
+        assert_eq!(config.peers, vec!["a".to_owned(), "b".to_owned()]);
+        assert_eq!(config.limit, Some(10));
+        assert!(config.enabled);
+    }
+
+    #[test]
+    fn test_precedence() {
+        let file = config_file(
+            r#"
+            limit = 10
+            peers = ["a", "b"]
+            "#,
+        );
+        let path = file.path().to_str().unwrap();
+
+        let config = parse(&["binary", "--config-file", path, "--limit", "1"]).unwrap();
+        assert_eq!(config.limit, Some(1));
+        assert_eq!(config.peers, vec!["a".to_owned(), "b".to_owned()]);
+
+        let file = config_file(r#"region = "from-file""#);
+        let path = file.path().to_str().unwrap();
+        std::env::set_var("INFLUXDB_IOX_TEST_CONFIG_FILE_REGION", "from-env");
+        let config = parse(&["binary", "--config-file", path]).unwrap();
+        std::env::remove_var("INFLUXDB_IOX_TEST_CONFIG_FILE_REGION");
+        assert_eq!(config.region.as_deref(), Some("from-env"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse(&["binary", "--config-file", "/does/not/exist.toml"]).unwrap_err();
+        assert!(matches!(err, Error::ReadFile { .. }), "{err}");
+
+        let file = config_file("limit = ");
+        let path = file.path().to_str().unwrap();
+        let err = parse(&["binary", "--config-file", path]).unwrap_err();
+        assert!(matches!(err, Error::ParseFile { .. }), "{err}");
+
+        let file = config_file("unknown = 1");
+        let path = file.path().to_str().unwrap();
+        let err = parse(&["binary", "--config-file", path]).unwrap_err();
+        assert!(
+            matches!(&err, Error::UnknownKey { key, .. } if key == "unknown"),
+            "{err}"
+        );
+
+        let file = config_file("enabled = 1");
+        let path = file.path().to_str().unwrap();
+        let err = parse(&["binary", "--config-file", path]).unwrap_err();
+        assert!(matches!(err, Error::InvalidValue { .. }), "{err}");
+
+        let file = config_file("[limit]\na = 1");
+        let path = file.path().to_str().unwrap();
+        let err = parse(&["binary", "--config-file", path]).unwrap_err();
+        assert!(matches!(err, Error::InvalidValue { .. }), "{err}");
+    }
+}
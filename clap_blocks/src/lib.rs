@@ -23,15 +23,21 @@ pub mod catalog_cache;
 pub mod catalog_dsn;
 pub mod compactor;
 pub mod compactor_scheduler;
+pub mod config_file;
+pub mod dump_config;
 pub mod garbage_collector;
 pub mod gossip;
 pub mod ingester;
 pub mod ingester_address;
 pub mod memory_size;
 pub mod object_store;
+pub mod object_store_routing;
 pub mod parquet_cache;
 pub mod querier;
+pub mod reloadable;
 pub mod router;
 pub mod run_config;
+pub mod secret_file;
 pub mod single_tenant;
 pub mod socket_addr;
+pub mod tokio_runtime;
@@ -1,6 +1,6 @@
 //! Helper types to express memory size.
 
-use std::{str::FromStr, sync::OnceLock};
+use std::{path::Path, str::FromStr, sync::OnceLock};
 
 use sysinfo::{MemoryRefreshKind, RefreshKind, System};
 
@@ -11,7 +11,7 @@ use sysinfo::{MemoryRefreshKind, RefreshKind, System};
 ///
 /// - **absolute:** just use a non-negative number to specify the absolute bytes, e.g. `1024`
 /// - **relative:** use percentage between 0 and 100 (both inclusive) to specify a relative amount of the totally
-///   available memory size, e.g. `50%`
+///   available memory size, e.g. `50%`, see [`total_mem_bytes`]
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct MemorySize(usize);
 
@@ -58,7 +58,16 @@ impl FromStr for MemorySize {
     }
 }
 
+/// Env var that makes relative memory sizes ignore the cgroup memory limit.
+///
+/// This is an env var and not a CLI flag, because memory sizes are resolved while the CLI flags are parsed.
+pub const IGNORE_CGROUP_MEMORY_LIMIT_ENV_NAME: &str = "INFLUXDB_IOX_IGNORE_CGROUP_MEMORY_LIMIT";
+
 /// Totally available memory size in bytes.
+///
+/// This is the memory limit of the cgroup (v1 or v2) the process runs in (e.g. the limit of its container), or the
+/// total memory of the host if there is no such limit, it is larger than the host memory, or
+/// [`IGNORE_CGROUP_MEMORY_LIMIT_ENV_NAME`] is set to `true`.
 pub fn total_mem_bytes() -> usize {
     // Keep this in a global state so that we only need to inspect the system once during IOx startup.
     static TOTAL_MEM_BYTES: OnceLock<usize> = OnceLock::new();
@@ -67,10 +76,56 @@ pub fn total_mem_bytes() -> usize {
         let sys = System::new_with_specifics(
             RefreshKind::new().with_memory(MemoryRefreshKind::everything()),
         );
-        sys.total_memory() as usize
+        let host = sys.total_memory() as usize;
+
+        let ignore_cgroup = std::env::var(IGNORE_CGROUP_MEMORY_LIMIT_ENV_NAME)
+            .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or_default();
+        if ignore_cgroup {
+            return host;
+        }
+
+        match cgroup_mem_limit_bytes(Path::new("/")) {
+            Some(limit) => limit.min(host),
+            None => host,
+        }
     })
 }
 
+/// Memory limit of the cgroup of this process, with all paths relative to `root`.
+///
+/// Returns `None` if there is no limit or it cannot be determined.
+fn cgroup_mem_limit_bytes(root: &Path) -> Option<usize> {
+    let cgroup_root = root.join("sys/fs/cgroup");
+
+    // cgroup v2: the process is either in the root cgroup of its namespace or the path is listed as `0::<path>`
+    let own_cgroup = std::fs::read_to_string(root.join("proc/self/cgroup"))
+        .ok()
+        .and_then(|s| {
+            s.lines().find_map(|line| {
+                line.strip_prefix("0::")
+                    .map(|p| p.trim_start_matches('/').to_owned())
+            })
+        });
+
+    let mut candidates = own_cgroup
+        .map(|path| cgroup_root.join(path).join("memory.max"))
+        .into_iter()
+        .chain([
+            cgroup_root.join("memory.max"),
+            // cgroup v1
+            cgroup_root.join("memory/memory.limit_in_bytes"),
+        ]);
+
+    // Only the first existing file is used, so that an unlimited cgroup does not fall back to another one
+    let content = candidates.find_map(|path| std::fs::read_to_string(path).ok())?;
+    match content.trim() {
+        "max" => None,
+        // cgroup v1 reports "unlimited" as a very large number, which is capped to the host memory by the caller
+        limit => limit.parse().ok(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,6 +148,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cgroup_v2() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "proc/self/cgroup", "0::/kubepods/pod1\n");
+        write(
+            root.path(),
+            "sys/fs/cgroup/kubepods/pod1/memory.max",
+            "1073741824\n",
+        );
+        write(root.path(), "sys/fs/cgroup/memory.max", "max\n");
+        assert_eq!(cgroup_mem_limit_bytes(root.path()), Some(1073741824));
+
+        // namespaced cgroup
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "proc/self/cgroup", "0::/\n");
+        write(root.path(), "sys/fs/cgroup/memory.max", "2048\n");
+        assert_eq!(cgroup_mem_limit_bytes(root.path()), Some(2048));
+
+        // no limit
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "proc/self/cgroup", "0::/\n");
+        write(root.path(), "sys/fs/cgroup/memory.max", "max\n");
+        write(
+            root.path(),
+            "sys/fs/cgroup/memory/memory.limit_in_bytes",
+            "2048\n",
+        );
+        assert_eq!(cgroup_mem_limit_bytes(root.path()), None);
+    }
+
+    #[test]
+    fn test_cgroup_v1() {
+        let root = tempfile::tempdir().unwrap();
+        write(root.path(), "proc/self/cgroup", "4:memory:/docker/abc\n");
+        write(
+            root.path(),
+            "sys/fs/cgroup/memory/memory.limit_in_bytes",
+            "4096\n",
+        );
+        assert_eq!(cgroup_mem_limit_bytes(root.path()), Some(4096));
+    }
+
+    #[test]
+    fn test_no_cgroup() {
+        let root = tempfile::tempdir().unwrap();
+        assert_eq!(cgroup_mem_limit_bytes(root.path()), None);
+    }
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
     #[track_caller]
     fn assert_ok(s: &'static str, expected: usize) {
         let parsed: MemorySize = s.parse().unwrap();
@@ -3,17 +3,25 @@
 use futures::TryStreamExt;
 use non_empty_string::NonEmptyString;
 use object_store::{
+    limit::LimitStore,
     memory::InMemory,
     path::Path,
     throttle::{ThrottleConfig, ThrottledStore},
     DynObjectStore,
 };
 use observability_deps::tracing::{info, warn};
-use snafu::{ResultExt, Snafu};
-use std::{convert::Infallible, fs, num::NonZeroUsize, path::PathBuf, sync::Arc, time::Duration};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
+use std::{
+    collections::HashMap, convert::Infallible, fs, num::NonZeroUsize, path::PathBuf, sync::Arc,
+    time::Duration,
+};
+use url::Url;
 use uuid::Uuid;
 
-use crate::parquet_cache::ParquetCacheClientConfig;
+use crate::{
+    object_store_routing::{ObjectStoreRoutingConfig, RoutingObjectStore},
+    parquet_cache::ParquetCacheClientConfig,
+};
 
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
@@ -51,6 +59,25 @@ pub enum ParseError {
 
     #[snafu(display("Error configuring Microsoft Azure: {}", source))]
     InvalidAzureConfig { source: object_store::Error },
+
+    #[snafu(display("Error configuring named object store '{}': {}", name, source))]
+    InvalidNamedObjectStore {
+        name: String,
+        source: object_store::Error,
+    },
+
+    #[snafu(display(
+        "URL of named object store '{}' must not contain a path: {}",
+        name,
+        url
+    ))]
+    NamedObjectStoreWithPath { name: String, url: Url },
+
+    #[snafu(display(
+        "Unknown named object store '{}', configure it via --object-store-named",
+        name
+    ))]
+    UnknownNamedObjectStore { name: String },
 }
 
 /// The AWS region to use for Amazon S3 based object storage if none is
@@ -120,6 +147,8 @@ pub struct ObjectStoreConfig {
     /// Prefer the environment variable over the command line flag in shared
     /// environments.
     ///
+    /// The value can also be read from the file given by `AWS_ACCESS_KEY_ID_FILE`.
+    ///
     /// An empty string value is equivalent to omitting the flag.
     /// Note: must refer to std::option::Option explicitly, see <https://github.com/clap-rs/clap/issues/4626>
     #[clap(long = "aws-access-key-id", env = "AWS_ACCESS_KEY_ID", value_parser = parse_optional_string, default_value="", action)]
@@ -134,6 +163,8 @@ pub struct ObjectStoreConfig {
     /// Prefer the environment variable over the command line flag in shared
     /// environments.
     ///
+    /// The value can also be read from the file given by `AWS_SECRET_ACCESS_KEY_FILE`.
+    ///
     /// An empty string value is equivalent to omitting the flag.
     /// Note: must refer to std::option::Option explicitly, see <https://github.com/clap-rs/clap/issues/4626>
     #[clap(long = "aws-secret-access-key", env = "AWS_SECRET_ACCESS_KEY", value_parser = parse_optional_string, default_value = "", action)]
@@ -171,6 +202,8 @@ pub struct ObjectStoreConfig {
     ///
     /// Prefer the environment variable over the command line flag in shared
     /// environments.
+    ///
+    /// The value can also be read from the file given by `AWS_SESSION_TOKEN_FILE`.
     #[clap(long = "aws-session-token", env = "AWS_SESSION_TOKEN", action)]
     pub aws_session_token: Option<String>,
 
@@ -205,6 +238,8 @@ pub struct ObjectStoreConfig {
     ///
     /// Prefer the environment variable over the command line flag in shared
     /// environments.
+    ///
+    /// The value can also be read from the file given by `AZURE_STORAGE_ACCESS_KEY_FILE`.
     #[clap(
         long = "azure-storage-access-key",
         env = "AZURE_STORAGE_ACCESS_KEY",
@@ -224,6 +259,10 @@ pub struct ObjectStoreConfig {
     /// Optional config for the cache client.
     #[clap(flatten)]
     pub cache_config: Option<ParquetCacheClientConfig>,
+
+    /// Config for routing objects to several object stores.
+    #[clap(flatten)]
+    pub routing: ObjectStoreRoutingConfig,
 }
 
 impl ObjectStoreConfig {
@@ -251,6 +290,7 @@ impl ObjectStoreConfig {
             object_store,
             object_store_connection_limit: NonZeroUsize::new(16).unwrap(),
             cache_config: Default::default(),
+            routing: Default::default(),
         }
     }
 }
@@ -441,6 +481,8 @@ pub fn make_object_store(config: &ObjectStoreConfig) -> Result<Arc<DynObjectStor
         },
     };
 
+    let remote_store = make_routing_object_store(config, remote_store)?;
+
     if let Some(cache_config) = &config.cache_config {
         let cache = parquet_cache::make_client(
             cache_config.namespace_addr.clone(),
@@ -453,6 +495,102 @@ pub fn make_object_store(config: &ObjectStoreConfig) -> Result<Arc<DynObjectStor
     }
 }
 
+/// Wrap `default` into a [`RoutingObjectStore`] if named object stores are configured.
+fn make_routing_object_store(
+    config: &ObjectStoreConfig,
+    default: Arc<DynObjectStore>,
+) -> Result<Arc<DynObjectStore>, ParseError> {
+    let routing = &config.routing;
+    if !routing.is_enabled() {
+        return Ok(default);
+    }
+
+    let options = named_object_store_options(config);
+    let mut stores = HashMap::with_capacity(routing.named_stores.len());
+    for named in &routing.named_stores {
+        let (store, path) = object_store::parse_url_opts(&named.url, options.clone())
+            .context(InvalidNamedObjectStoreSnafu { name: &named.name })?;
+        ensure!(
+            path.as_ref().is_empty(),
+            NamedObjectStoreWithPathSnafu {
+                name: &named.name,
+                url: named.url.clone(),
+            }
+        );
+
+        info!(name=%named.name, url=%named.url, "Named Object Store");
+        let store: Arc<DynObjectStore> = Arc::new(LimitStore::new(
+            store,
+            config.object_store_connection_limit.get(),
+        ));
+        stores.insert(named.name.as_str(), store);
+    }
+    let lookup = |name: &str| {
+        stores
+            .get(name)
+            .map(Arc::clone)
+            .context(UnknownNamedObjectStoreSnafu { name })
+    };
+
+    let mut store = RoutingObjectStore::new(default);
+    for route in &routing.namespace_routes {
+        store = store.with_namespace(route.namespace_id, lookup(&route.store)?);
+    }
+    for name in &routing.fallback_stores {
+        store = store.with_fallback(lookup(name)?);
+    }
+
+    info!(%store, "Object store routing enabled");
+    Ok(Arc::new(store))
+}
+
+/// Options of the main object store that also apply to the named object stores.
+///
+/// Options that do not apply to the type of a named store are ignored.
+fn named_object_store_options(config: &ObjectStoreConfig) -> Vec<(&'static str, String)> {
+    let mut options = vec![
+        ("aws_region", config.aws_default_region.clone()),
+        ("allow_http", config.aws_allow_http.to_string()),
+    ];
+
+    let optional = [
+        (
+            "aws_access_key_id",
+            config
+                .aws_access_key_id
+                .as_ref()
+                .map(|v| v.get().to_owned()),
+        ),
+        (
+            "aws_secret_access_key",
+            config
+                .aws_secret_access_key
+                .as_ref()
+                .map(|v| v.get().to_owned()),
+        ),
+        ("aws_endpoint", config.aws_endpoint.clone()),
+        ("aws_session_token", config.aws_session_token.clone()),
+        (
+            "google_service_account",
+            config.google_service_account.clone(),
+        ),
+        (
+            "azure_storage_account_name",
+            config.azure_storage_account.clone(),
+        ),
+        (
+            "azure_storage_account_key",
+            config.azure_storage_access_key.clone(),
+        ),
+    ];
+    options.extend(
+        optional
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| (key, value))),
+    );
+    options
+}
+
 /// The `object_store::signer::Signer` trait is only implemented for AWS currently, so when the AWS
 /// feature is enabled and the configured object store is S3, return a signer.
 #[cfg(feature = "aws")]
@@ -526,6 +664,44 @@ mod tests {
         assert_eq!(&object_store.to_string(), "InMemory")
     }
 
+    #[test]
+    fn named_object_stores() {
+        let config = ObjectStoreConfig::try_parse_from([
+            "server",
+            "--object-store-named",
+            "hot=memory:///,archive=memory:///",
+            "--object-store-namespace-route",
+            "1=hot",
+            "--object-store-fallback",
+            "archive",
+        ])
+        .unwrap();
+
+        let object_store = make_object_store(&config).unwrap();
+        assert_eq!(
+            &object_store.to_string(),
+            "RoutingObjectStore(InMemory, 1: LimitStore(16, InMemory), fallback: LimitStore(16, InMemory))"
+        )
+    }
+
+    #[test]
+    fn unknown_named_object_store() {
+        let config = ObjectStoreConfig::try_parse_from([
+            "server",
+            "--object-store-named",
+            "hot=memory:///",
+            "--object-store-fallback",
+            "archive",
+        ])
+        .unwrap();
+
+        let err = make_object_store(&config).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Unknown named object store 'archive', configure it via --object-store-named"
+        );
+    }
+
     #[test]
     fn default_url_signer_is_none() {
         let config = ObjectStoreConfig::try_parse_from(["server"]).unwrap();
@@ -0,0 +1,539 @@
+//! CLI config for routing objects to several named object stores.
+//!
+//! Besides the main object store (see [`ObjectStoreConfig`](crate::object_store::ObjectStoreConfig)), further
+//! object stores can be configured by name, e.g. a cold archive bucket next to the hot bucket. Objects are routed to
+//! them by
+//!
+//! - **namespace:** all objects of a namespace (i.e. whose path starts with the namespace ID) are stored in the given
+//!   store, and
+//! - **fallback:** objects may have been moved to the given stores, e.g. by a lifecycle job that archives old
+//!   objects. New objects are always written to the store of their namespace or the main store, reads that do not
+//!   find an object there fall back to these stores in the configured order.
+//!
+//! Objects are not routed by their age, as that is only known once the object has been found. Listings return each
+//! location once, preferring the store it is written to over the fallback stores.
+//!
+//! The rest of the system only sees a single [`RoutingObjectStore`].
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    future::Future,
+    ops::Range,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt};
+use object_store::{
+    path::Path, DynObjectStore, GetOptions, GetResult, ListResult, MultipartId, ObjectMeta,
+    ObjectStore, PutMode, PutOptions, PutResult, Result,
+};
+use tokio::io::AsyncWrite;
+use url::Url;
+
+/// An object store given by name, see `--object-store-named`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedObjectStore {
+    /// Name used by the routes.
+    pub name: String,
+
+    /// URL of the store, e.g. `s3://archive-bucket`.
+    pub url: Url,
+}
+
+/// Route all objects of a namespace to a named store, see `--object-store-namespace-route`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceRoute {
+    /// Namespace ID.
+    pub namespace_id: i64,
+
+    /// Name of the store.
+    pub store: String,
+}
+
+/// CLI config for routing objects to several named object stores.
+#[derive(Debug, Clone, Default, PartialEq, Eq, clap::Parser)]
+pub struct ObjectStoreRoutingConfig {
+    /// Additional named object stores, given as `<name>=<url>`, e.g. `archive=s3://archive-bucket`.
+    ///
+    /// The credentials and options of the main object store (e.g. `--aws-access-key-id`) are also used for the named
+    /// stores. Can be provided as a comma-separated list, or on the command line multiple times.
+    #[clap(
+        long = "object-store-named",
+        env = "INFLUXDB_IOX_OBJECT_STORE_NAMED",
+        required = false,
+        value_delimiter = ',',
+        value_parser = parse_named_store,
+    )]
+    pub named_stores: Vec<NamedObjectStore>,
+
+    /// Store all objects of a namespace in a named store, given as `<namespace id>=<name>`.
+    ///
+    /// Can be provided as a comma-separated list, or on the command line multiple times.
+    #[clap(
+        long = "object-store-namespace-route",
+        env = "INFLUXDB_IOX_OBJECT_STORE_NAMESPACE_ROUTES",
+        required = false,
+        value_delimiter = ',',
+        value_parser = parse_namespace_route,
+    )]
+    pub namespace_routes: Vec<NamespaceRoute>,
+
+    /// Named stores that objects may have been moved to, e.g. by a lifecycle job that archives old objects.
+    ///
+    /// Objects are never written to these stores by IOx, but reads of objects that are not found in their store fall
+    /// back to them in the given order. Can be provided as a comma-separated list, or on the command line multiple
+    /// times.
+    #[clap(
+        long = "object-store-fallback",
+        env = "INFLUXDB_IOX_OBJECT_STORE_FALLBACKS",
+        required = false,
+        value_delimiter = ','
+    )]
+    pub fallback_stores: Vec<String>,
+}
+
+impl ObjectStoreRoutingConfig {
+    /// Returns true if any named stores are configured.
+    pub fn is_enabled(&self) -> bool {
+        !self.named_stores.is_empty()
+    }
+}
+
+fn split_pair(s: &str) -> Result<(&str, &str), String> {
+    match s.split_once('=') {
+        Some((k, v)) if !k.is_empty() && !v.is_empty() => Ok((k.trim(), v.trim())),
+        _ => Err(format!("expected '<key>=<value>' but got '{s}'")),
+    }
+}
+
+fn parse_named_store(s: &str) -> Result<NamedObjectStore, String> {
+    let (name, url) = split_pair(s)?;
+    Ok(NamedObjectStore {
+        name: name.to_owned(),
+        url: Url::parse(url).map_err(|e| format!("invalid URL '{url}': {e}"))?,
+    })
+}
+
+fn parse_namespace_route(s: &str) -> Result<NamespaceRoute, String> {
+    let (namespace_id, store) = split_pair(s)?;
+    Ok(NamespaceRoute {
+        namespace_id: namespace_id
+            .parse()
+            .map_err(|e| format!("invalid namespace ID '{namespace_id}': {e}"))?,
+        store: store.to_owned(),
+    })
+}
+
+/// An [`ObjectStore`] that routes objects to several object stores by namespace, falling back to further stores on
+/// reads.
+///
+/// See the [module docs](self) for details.
+#[derive(Debug)]
+pub struct RoutingObjectStore {
+    default: Arc<DynObjectStore>,
+
+    /// Stores keyed by namespace ID, i.e. the first part of the object path.
+    namespaces: HashMap<String, Arc<DynObjectStore>>,
+
+    /// Stores that objects may have been moved to, in the order they are checked.
+    fallbacks: Vec<Arc<DynObjectStore>>,
+}
+
+impl RoutingObjectStore {
+    /// Create a new store that routes all objects to `default`, unless configured otherwise.
+    pub fn new(default: Arc<DynObjectStore>) -> Self {
+        Self {
+            default,
+            namespaces: HashMap::new(),
+            fallbacks: vec![],
+        }
+    }
+
+    /// Store all objects of the namespace in `store`.
+    pub fn with_namespace(mut self, namespace_id: i64, store: Arc<DynObjectStore>) -> Self {
+        self.namespaces.insert(namespace_id.to_string(), store);
+        self
+    }
+
+    /// Fall back to `store` for objects that are not found in their store or the previously added fallbacks.
+    pub fn with_fallback(mut self, store: Arc<DynObjectStore>) -> Self {
+        self.fallbacks.push(store);
+        self
+    }
+
+    /// Store that new objects at `location` are written to.
+    fn store_for(&self, location: &Path) -> &Arc<DynObjectStore> {
+        location
+            .parts()
+            .next()
+            .and_then(|namespace| self.namespaces.get(namespace.as_ref()))
+            .unwrap_or(&self.default)
+    }
+
+    /// Stores that may contain the object at `location`, in the order they should be checked.
+    fn read_stores(&self, location: &Path) -> Vec<&Arc<DynObjectStore>> {
+        dedup(std::iter::once(self.store_for(location)).chain(&self.fallbacks))
+    }
+
+    /// Stores that may contain objects under `prefix`.
+    fn list_stores(&self, prefix: Option<&Path>) -> Vec<&Arc<DynObjectStore>> {
+        match prefix {
+            Some(prefix) if prefix.parts().next().is_some() => self.read_stores(prefix),
+            _ => dedup(
+                std::iter::once(&self.default)
+                    .chain(self.namespaces.values())
+                    .chain(&self.fallbacks),
+            ),
+        }
+    }
+
+    /// Call `f` on the [read stores](Self::read_stores) until the object is found.
+    async fn read<'a, T, F, Fut>(&'a self, location: &Path, f: F) -> Result<T>
+    where
+        F: Fn(&'a Arc<DynObjectStore>) -> Fut + Send,
+        Fut: Future<Output = Result<T>> + Send,
+        T: Send,
+    {
+        let mut stores = self.read_stores(location).into_iter();
+        let mut result = f(stores.next().expect("at least one store")).await;
+        for store in stores {
+            match result {
+                Err(object_store::Error::NotFound { .. }) => result = f(store).await,
+                _ => break,
+            }
+        }
+        result
+    }
+
+    /// Copy an object between different stores.
+    async fn copy_between(&self, from: &Path, to: &Path, mode: PutMode) -> Result<()> {
+        let bytes = self.get(from).await?.bytes().await?;
+        let opts = PutOptions {
+            mode,
+            ..Default::default()
+        };
+        self.store_for(to).put_opts(to, bytes, opts).await?;
+        Ok(())
+    }
+}
+
+/// Removes duplicates, e.g. if the same store is used for several namespaces.
+fn dedup<'a>(
+    stores: impl IntoIterator<Item = &'a Arc<DynObjectStore>>,
+) -> Vec<&'a Arc<DynObjectStore>> {
+    let mut out: Vec<&Arc<DynObjectStore>> = vec![];
+    for store in stores {
+        if !out.iter().any(|s| Arc::ptr_eq(s, store)) {
+            out.push(store);
+        }
+    }
+    out
+}
+
+/// [`GetOptions`] does not implement [`Clone`], but reads may have to be retried against several stores.
+fn clone_get_options(options: &GetOptions) -> GetOptions {
+    GetOptions {
+        if_match: options.if_match.clone(),
+        if_none_match: options.if_none_match.clone(),
+        if_modified_since: options.if_modified_since,
+        if_unmodified_since: options.if_unmodified_since,
+        range: options.range.clone(),
+        version: options.version.clone(),
+        head: options.head,
+    }
+}
+
+impl Display for RoutingObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RoutingObjectStore({}", self.default)?;
+        let mut namespaces: Vec<_> = self.namespaces.iter().collect();
+        namespaces.sort_by_key(|(namespace, _)| *namespace);
+        for (namespace, store) in namespaces {
+            write!(f, ", {namespace}: {store}")?;
+        }
+        for store in &self.fallbacks {
+            write!(f, ", fallback: {store}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[async_trait]
+impl ObjectStore for RoutingObjectStore {
+    async fn put_opts(&self, location: &Path, bytes: Bytes, opts: PutOptions) -> Result<PutResult> {
+        self.store_for(location)
+            .put_opts(location, bytes, opts)
+            .await
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.store_for(location).put_multipart(location).await
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        self.store_for(location)
+            .abort_multipart(location, multipart_id)
+            .await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.read(location, |store| {
+            store.get_opts(location, clone_get_options(&options))
+        })
+        .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.read(location, |store| store.get_range(location, range.clone()))
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.read(location, |store| store.get_ranges(location, ranges))
+            .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.read(location, |store| store.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        let mut stores = self.read_stores(location).into_iter();
+        let mut result = stores
+            .next()
+            .expect("at least one store")
+            .delete(location)
+            .await;
+        for store in stores {
+            match store.delete(location).await {
+                Ok(()) => result = Ok(()),
+                Err(object_store::Error::NotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        result
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let streams: Vec<_> = self
+            .list_stores(prefix)
+            .into_iter()
+            .map(|store| store.list(prefix))
+            .collect();
+        // an object may exist in several stores while it is moved
+        let mut seen = HashSet::new();
+        futures::stream::iter(streams)
+            .flatten()
+            .filter(move |result| {
+                let keep = match result {
+                    Ok(meta) => seen.insert(meta.location.clone()),
+                    Err(_) => true,
+                };
+                futures::future::ready(keep)
+            })
+            .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let mut out = ListResult {
+            common_prefixes: vec![],
+            objects: vec![],
+        };
+        for store in self.list_stores(prefix) {
+            let result = store.list_with_delimiter(prefix).await?;
+            for common_prefix in result.common_prefixes {
+                if !out.common_prefixes.contains(&common_prefix) {
+                    out.common_prefixes.push(common_prefix);
+                }
+            }
+            for object in result.objects {
+                if !out.objects.iter().any(|o| o.location == object.location) {
+                    out.objects.push(object);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let store = self.store_for(to);
+        if Arc::ptr_eq(self.store_for(from), store) {
+            match store.copy(from, to).await {
+                // the source may have been moved to a fallback store
+                Err(object_store::Error::NotFound { .. }) => {}
+                result => return result,
+            }
+        }
+        self.copy_between(from, to, PutMode::Overwrite).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let store = self.store_for(to);
+        if Arc::ptr_eq(self.store_for(from), store) {
+            match store.copy_if_not_exists(from, to).await {
+                // the source may have been moved to a fallback store
+                Err(object_store::Error::NotFound { .. }) => {}
+                result => return result,
+            }
+        }
+        self.copy_between(from, to, PutMode::Create).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use futures::TryStreamExt;
+    use object_store::memory::InMemory;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = ObjectStoreRoutingConfig::parse_from([
+            "server",
+            "--object-store-named",
+            "hot=s3://hot-bucket,archive=s3://archive-bucket",
+            "--object-store-namespace-route",
+            "1=hot",
+            "--object-store-fallback",
+            "archive",
+        ]);
+        assert!(config.is_enabled());
+        assert_eq!(
+            config.named_stores,
+            vec![
+                NamedObjectStore {
+                    name: "hot".to_owned(),
+                    url: Url::parse("s3://hot-bucket").unwrap(),
+                },
+                NamedObjectStore {
+                    name: "archive".to_owned(),
+                    url: Url::parse("s3://archive-bucket").unwrap(),
+                },
+            ]
+        );
+        assert_eq!(
+            config.namespace_routes,
+            vec![NamespaceRoute {
+                namespace_id: 1,
+                store: "hot".to_owned()
+            }]
+        );
+        assert_eq!(config.fallback_stores, vec!["archive".to_owned()]);
+
+        let config = ObjectStoreRoutingConfig::parse_from(["server"]);
+        assert!(!config.is_enabled());
+
+        let err = ObjectStoreRoutingConfig::try_parse_from([
+            "server",
+            "--object-store-namespace-route",
+            "foo=hot",
+        ])
+        .unwrap_err();
+        assert!(
+            err.to_string().contains("invalid namespace ID 'foo'"),
+            "{err}"
+        );
+
+        let err =
+            ObjectStoreRoutingConfig::try_parse_from(["server", "--object-store-named", "hot"])
+                .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("expected '<key>=<value>' but got 'hot'"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_routing() {
+        let default: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        let namespace: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        let archive: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        let store = RoutingObjectStore::new(Arc::clone(&default))
+            .with_namespace(1, Arc::clone(&namespace))
+            .with_fallback(Arc::clone(&archive));
+
+        let p1 = Path::from("1/table/file.parquet");
+        let p2 = Path::from("2/table/file.parquet");
+        let p3 = Path::from("2/table/old.parquet");
+        store.put(&p1, Bytes::from("p1")).await.unwrap();
+        store.put(&p2, Bytes::from("p2")).await.unwrap();
+        archive.put(&p3, Bytes::from("p3")).await.unwrap();
+
+        // written to the store of the namespace
+        namespace.head(&p1).await.unwrap();
+        default.head(&p2).await.unwrap();
+        assert!(default.head(&p1).await.is_err());
+
+        // reads fall back to the archive
+        let bytes = store.get(&p3).await.unwrap().bytes().await.unwrap();
+        assert_eq!(bytes, Bytes::from("p3"));
+        let err = store
+            .head(&Path::from("2/table/missing.parquet"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, object_store::Error::NotFound { .. }), "{err}");
+
+        // lists merge all stores that may hold the prefix
+        let mut paths: Vec<_> = store
+            .list(None)
+            .map_ok(|meta| meta.location)
+            .try_collect()
+            .await
+            .unwrap();
+        paths.sort();
+        assert_eq!(paths, vec![p1.clone(), p2.clone(), p3.clone()]);
+
+        // objects that exist in several stores are listed once
+        archive.put(&p2, Bytes::from("p2")).await.unwrap();
+        let mut paths: Vec<_> = store
+            .list(Some(&Path::from("2")))
+            .map_ok(|meta| meta.location)
+            .try_collect()
+            .await
+            .unwrap();
+        paths.sort();
+        assert_eq!(paths, vec![p2.clone(), p3.clone()]);
+        let result = store
+            .list_with_delimiter(Some(&Path::from("2/table")))
+            .await
+            .unwrap();
+        assert_eq!(result.objects.len(), 2);
+        archive.delete(&p2).await.unwrap();
+
+        let paths: Vec<_> = store
+            .list(Some(&Path::from("1")))
+            .map_ok(|meta| meta.location)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(paths, vec![p1.clone()]);
+
+        // copy between stores
+        let p4 = Path::from("1/table/copy.parquet");
+        store.copy(&p3, &p4).await.unwrap();
+        namespace.head(&p4).await.unwrap();
+        let err = store.copy_if_not_exists(&p2, &p4).await.unwrap_err();
+        assert!(
+            matches!(err, object_store::Error::AlreadyExists { .. }),
+            "{err}"
+        );
+
+        // deletes from all stores
+        store.delete(&p3).await.unwrap();
+        assert!(archive.head(&p3).await.is_err());
+
+        assert_eq!(
+            store.to_string(),
+            "RoutingObjectStore(InMemory, 1: InMemory, fallback: InMemory)"
+        );
+    }
+}
@@ -5,7 +5,12 @@ use crate::{
     memory_size::MemorySize,
     single_tenant::{CONFIG_AUTHZ_ENV_NAME, CONFIG_AUTHZ_FLAG},
 };
-use std::{collections::HashMap, num::NonZeroUsize};
+use executor::CpuSet;
+use std::{
+    collections::HashMap,
+    num::{NonZeroU32, NonZeroUsize},
+    path::PathBuf,
+};
 
 /// CLI config for querier configuration
 #[derive(Debug, Clone, PartialEq, Eq, clap::Parser)]
@@ -24,6 +29,20 @@ pub struct QuerierConfig {
     )]
     pub num_query_threads: Option<NonZeroUsize>,
 
+    /// CPU cores that the query threads are pinned to, e.g. `0-15,32-47`.
+    ///
+    /// Choosing the cores of a single NUMA node improves latency consistency on large multi-socket
+    /// machines. If not specified, query threads are not pinned.
+    #[clap(long = "query-cpus", env = "INFLUXDB_IOX_QUERY_CPUS", action)]
+    pub query_cpus: Option<CpuSet>,
+
+    /// CPU cores that the IO threads (e.g. gRPC and HTTP handling) are pinned to, e.g. `16-19`.
+    ///
+    /// Use together with `--query-cpus` to keep IO and query work on separate cores. If not
+    /// specified, IO threads are not pinned.
+    #[clap(long = "io-cpus", env = "INFLUXDB_IOX_IO_CPUS", action)]
+    pub io_cpus: Option<CpuSet>,
+
     /// Size of memory pool used during query exec, in bytes.
     ///
     /// If queries attempt to allocate more than this many bytes
@@ -38,6 +57,57 @@ pub struct QuerierConfig {
     )]
     pub exec_mem_pool_bytes: MemorySize,
 
+    /// Maximum number of bytes of the query exec memory pool a single query may use.
+    ///
+    /// If a query attempts to allocate more than this many bytes during
+    /// execution, it will error with "ResourcesExhausted", even if the pool
+    /// has capacity remaining. If not specified, a single query may use the
+    /// entire pool.
+    ///
+    /// Can be given as absolute value or in percentage of the total available memory (e.g. `10%`).
+    #[clap(
+        long = "exec-per-query-mem-pool-bytes",
+        env = "INFLUXDB_IOX_EXEC_PER_QUERY_MEM_POOL_BYTES",
+        action
+    )]
+    pub exec_per_query_mem_pool_bytes: Option<MemorySize>,
+
+    /// Directories that queries spill intermediate data (e.g. of large sorts) to once they reach
+    /// their memory limit, instead of failing with "ResourcesExhausted".
+    ///
+    /// Multiple directories can be given separated by commas. If not specified, spilling is
+    /// disabled.
+    #[clap(
+        long = "exec-spill-dirs",
+        env = "INFLUXDB_IOX_EXEC_SPILL_DIRS",
+        required = false,
+        num_args = 0..,
+        value_delimiter = ','
+    )]
+    pub exec_spill_dirs: Vec<PathBuf>,
+
+    /// Prefix within the object store that queries spill large sorts to once they reach their
+    /// memory limit, instead of the `--exec-spill-dirs`.
+    ///
+    /// Spilled objects are removed once they are read, but should be covered by a lifecycle rule
+    /// of the bucket in case the querier stops while spilling. If not specified, sorts do not
+    /// spill to the object store.
+    #[clap(
+        long = "exec-spill-object-store-prefix",
+        env = "INFLUXDB_IOX_EXEC_SPILL_OBJECT_STORE_PREFIX",
+        action
+    )]
+    pub exec_spill_object_store_prefix: Option<String>,
+
+    /// Maximum number of concurrent object store requests for spilling, over all queries.
+    #[clap(
+        long = "exec-spill-object-store-concurrency",
+        env = "INFLUXDB_IOX_EXEC_SPILL_OBJECT_STORE_CONCURRENCY",
+        default_value = "10",
+        action
+    )]
+    pub exec_spill_object_store_concurrency: NonZeroUsize,
+
     /// gRPC address for the router to talk with the ingesters. For
     /// example:
     ///
@@ -88,6 +158,31 @@ pub struct QuerierConfig {
     )]
     pub max_concurrent_queries: usize,
 
+    /// Share the concurrent queries between namespaces.
+    ///
+    /// Queued queries of the same workload class are admitted in proportion to the weights of
+    /// their namespaces (see `--namespace-query-weights`) instead of in order of arrival, so that
+    /// a burst of queries of a single namespace does not starve the queries of all others.
+    #[clap(
+        long = "namespace-fair-query-admission",
+        env = "INFLUXDB_IOX_NAMESPACE_FAIR_QUERY_ADMISSION",
+        action
+    )]
+    pub namespace_fair_query_admission: bool,
+
+    /// Weights of namespaces for `--namespace-fair-query-admission`, as comma separated
+    /// `NAMESPACE:WEIGHT` pairs.
+    ///
+    /// Namespaces that are not listed have a weight of 1.
+    #[clap(
+        long = "namespace-query-weights",
+        env = "INFLUXDB_IOX_NAMESPACE_QUERY_WEIGHTS",
+        default_value = "",
+        value_parser = parse_namespace_query_weights,
+        action
+    )]
+    pub namespace_query_weights: HashMap<String, NonZeroU32>,
+
     /// After how many ingester query errors should the querier enter circuit breaker mode?
     ///
     /// The querier normally contacts the ingester for any unpersisted data during query planning.
@@ -111,6 +206,39 @@ pub struct QuerierConfig {
     )]
     pub ingester_circuit_breaker_threshold: u64,
 
+    /// Maximum number of rows per Flight data message of a query response.
+    ///
+    /// Larger record batches are split. If not specified, record batches are only split by
+    /// `--flight-max-message-bytes`.
+    #[clap(
+        long = "flight-max-rows-per-message",
+        env = "INFLUXDB_IOX_FLIGHT_MAX_ROWS_PER_MESSAGE",
+        action
+    )]
+    pub flight_max_rows_per_message: Option<NonZeroUsize>,
+
+    /// Target maximum size of a Flight data message of a query response, in bytes.
+    #[clap(
+        long = "flight-max-message-bytes",
+        env = "INFLUXDB_IOX_FLIGHT_MAX_MESSAGE_BYTES",
+        default_value = "2097152",  // 2MB
+        action
+    )]
+    pub flight_max_message_bytes: usize,
+
+    /// Maximum number of Flight data messages of a query response that are buffered ahead of
+    /// the client.
+    ///
+    /// If the buffer is full, query execution is paused until the client catches up. Set to 0
+    /// to only produce messages when the client requests them.
+    #[clap(
+        long = "flight-max-buffered-messages",
+        env = "INFLUXDB_IOX_FLIGHT_MAX_BUFFERED_MESSAGES",
+        default_value = "2",
+        action
+    )]
+    pub flight_max_buffered_messages: usize,
+
     /// DataFusion config.
     #[clap(
         long = "datafusion-config",
@@ -161,6 +289,21 @@ fn parse_datafusion_config(
     Ok(out)
 }
 
+fn parse_namespace_query_weights(
+    s: &str,
+) -> Result<HashMap<String, NonZeroU32>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+    // same format as the DataFusion config
+    parse_datafusion_config(s)?
+        .into_iter()
+        .map(|(namespace, weight)| match weight.parse() {
+            Ok(weight) => Ok((namespace, weight)),
+            Err(e) => {
+                Err(format!("Invalid weight '{weight}' of namespace '{namespace}': {e}").into())
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,8 +315,19 @@ mod tests {
         let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
 
         assert_eq!(actual.num_query_threads, None);
+        assert_eq!(actual.query_cpus, None);
+        assert_eq!(actual.io_cpus, None);
+        assert_eq!(actual.exec_per_query_mem_pool_bytes, None);
+        assert!(actual.exec_spill_dirs.is_empty());
+        assert_eq!(actual.exec_spill_object_store_prefix, None);
+        assert_eq!(actual.exec_spill_object_store_concurrency.get(), 10);
         assert!(actual.ingester_addresses.is_empty());
         assert!(actual.datafusion_config.is_empty());
+        assert_eq!(actual.flight_max_rows_per_message, None);
+        assert_eq!(actual.flight_max_message_bytes, 2 * 1024 * 1024);
+        assert_eq!(actual.flight_max_buffered_messages, 2);
+        assert!(!actual.namespace_fair_query_admission);
+        assert!(actual.namespace_query_weights.is_empty());
     }
 
     #[test]
@@ -187,6 +341,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cpus() {
+        let actual =
+            QuerierConfig::try_parse_from(["my_binary", "--query-cpus", "0-3,8", "--io-cpus", "4"])
+                .unwrap();
+
+        assert_eq!(
+            actual.query_cpus,
+            Some(CpuSet::new([0, 1, 2, 3, 8]).unwrap())
+        );
+        assert_eq!(actual.io_cpus, Some(CpuSet::new([4]).unwrap()));
+
+        let actual = QuerierConfig::try_parse_from(["my_binary", "--query-cpus", "3-1"])
+            .unwrap_err()
+            .to_string();
+        assert_contains!(actual, "Invalid CPU range '3-1'");
+    }
+
+    #[test]
+    fn test_per_query_mem_pool_bytes() {
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--exec-per-query-mem-pool-bytes",
+            "1048576",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            actual.exec_per_query_mem_pool_bytes.map(|s| s.bytes()),
+            Some(1048576)
+        );
+    }
+
+    #[test]
+    fn test_spill_dirs() {
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--exec-spill-dirs",
+            "/mnt/spill-0,/mnt/spill-1",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            actual.exec_spill_dirs,
+            [PathBuf::from("/mnt/spill-0"), PathBuf::from("/mnt/spill-1")]
+        );
+    }
+
     #[test]
     fn test_ingester_addresses_list() {
         let querier = QuerierConfig::try_parse_from([
@@ -261,4 +463,29 @@ mod tests {
             "error: invalid value 'foo:bar,baz:1,foo:2' for '--datafusion-config <DATAFUSION_CONFIG>': key 'foo' passed multiple times"
         );
     }
+
+    #[test]
+    fn test_namespace_query_weights() {
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--namespace-fair-query-admission",
+            "--namespace-query-weights=foo:3, bar:1",
+        ])
+        .unwrap();
+
+        assert!(actual.namespace_fair_query_admission);
+        assert_eq!(
+            actual.namespace_query_weights,
+            HashMap::from([
+                (String::from("foo"), NonZeroU32::new(3).unwrap()),
+                (String::from("bar"), NonZeroU32::new(1).unwrap()),
+            ]),
+        );
+
+        let actual =
+            QuerierConfig::try_parse_from(["my_binary", "--namespace-query-weights=foo:0"])
+                .unwrap_err()
+                .to_string();
+        assert_contains!(actual, "Invalid weight '0' of namespace 'foo'");
+    }
 }
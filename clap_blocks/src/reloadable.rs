@@ -0,0 +1,383 @@
+//! Settings that can be changed without restarting a service.
+//!
+//! Most settings are only read once on startup. The ones that are safe to change while a service is running are
+//! wrapped into a [`ReloadableConfig`], which re-reads them when asked to (e.g. on `SIGHUP` or via the
+//! `/debug/reload` HTTP endpoint) and notifies the components using them.
+//!
+//! The [`Tunables`] are applied by [`reload_log_filter`] and [`reload_catalog_cache`].
+
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+use catalog_cache::local::CatalogCache;
+use observability_deps::tracing::{info, warn};
+use snafu::{ResultExt, Snafu};
+use tokio::sync::watch;
+use trogging::{cli::LoggingConfig, LogFilterHandle};
+
+use crate::{
+    catalog_cache::CatalogConfig, config_file::merge_config_file, memory_size::MemorySize,
+};
+
+/// Boxed error returned when loading a config.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum ReloadError {
+    #[snafu(display("Could not load config: {source}"))]
+    Load { source: BoxError },
+}
+
+/// A config that can be reloaded, independent of its type.
+pub trait Reload: Debug + Send + Sync + 'static {
+    /// Load the config again.
+    ///
+    /// Returns `true` if the config changed. If loading fails, the previous config is kept.
+    fn reload(&self) -> Result<bool, ReloadError>;
+}
+
+/// Function that loads a config.
+type LoadFn<T> = Box<dyn Fn() -> Result<T, BoxError> + Send + Sync>;
+
+/// A config of type `T` that can be changed while the service is running.
+///
+/// Components that use the config should not keep a copy of it, but either [get](Self::get) it whenever they need
+/// it or [subscribe](Self::subscribe) to changes.
+pub struct ReloadableConfig<T> {
+    sender: watch::Sender<Arc<T>>,
+    load: LoadFn<T>,
+}
+
+impl<T> Debug for ReloadableConfig<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReloadableConfig")
+            .field("current", &self.sender.borrow())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> ReloadableConfig<T>
+where
+    T: Debug + PartialEq + Send + Sync + 'static,
+{
+    /// Create a new config with the `initial` value, which is reloaded by calling `load`.
+    pub fn new(initial: T, load: impl Fn() -> Result<T, BoxError> + Send + Sync + 'static) -> Self {
+        let (sender, _) = watch::channel(Arc::new(initial));
+        Self {
+            sender,
+            load: Box::new(load),
+        }
+    }
+
+    /// Create a new config that is reloaded by parsing the command line arguments of the process (incl. environment
+    /// variables and the [config file](crate::config_file)) as `P` again and extracting the config from it.
+    pub fn from_args<P>(initial: T, extract: impl Fn(&P) -> T + Send + Sync + 'static) -> Self
+    where
+        P: clap::Parser,
+    {
+        Self::new(initial, move || {
+            let args = merge_config_file(&P::command(), std::env::args_os())?;
+            let config = P::try_parse_from(args)?;
+            Ok(extract(&config))
+        })
+    }
+
+    /// Current value.
+    pub fn get(&self) -> Arc<T> {
+        Arc::clone(&self.sender.borrow())
+    }
+
+    /// Receiver that is notified whenever the value changes.
+    pub fn subscribe(&self) -> watch::Receiver<Arc<T>> {
+        self.sender.subscribe()
+    }
+}
+
+impl<T> Reload for ReloadableConfig<T>
+where
+    T: Debug + PartialEq + Send + Sync + 'static,
+{
+    fn reload(&self) -> Result<bool, ReloadError> {
+        let value = (self.load)().context(LoadSnafu)?;
+        Ok(self.sender.send_if_modified(|current| {
+            if **current == value {
+                return false;
+            }
+            *current = Arc::new(value);
+            true
+        }))
+    }
+}
+
+/// Settings that are safe to change while a service is running.
+///
+/// Settings that a service does not use are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tunables {
+    /// Log filter, see `--log-filter`.
+    pub log_filter: Option<String>,
+
+    /// Size limit of the catalog cache, see `--catalog-cache-size-limit`.
+    pub catalog_cache_size_limit: Option<MemorySize>,
+
+    /// Garbage collection interval of the catalog cache, see `--catalog-cache-gc-interval`.
+    pub catalog_cache_gc_interval: Option<Duration>,
+}
+
+impl Tunables {
+    /// Take the logging settings from `config`.
+    pub fn with_logging(self, config: &LoggingConfig) -> Self {
+        Self {
+            log_filter: config.log_filter.clone(),
+            ..self
+        }
+    }
+
+    /// Take the catalog cache settings from `config`.
+    pub fn with_catalog_cache(self, config: &CatalogConfig) -> Self {
+        Self {
+            catalog_cache_size_limit: Some(config.cache_size_limit),
+            catalog_cache_gc_interval: Some(config.gc_interval),
+            ..self
+        }
+    }
+}
+
+/// Applies [`Tunables::log_filter`] to the logging layer of `handle` whenever it changes.
+///
+/// Runs until the [`ReloadableConfig`] is dropped.
+pub async fn reload_log_filter(
+    mut tunables: watch::Receiver<Arc<Tunables>>,
+    handle: LogFilterHandle,
+) {
+    let mut current = tunables.borrow_and_update().log_filter.clone();
+    while tunables.changed().await.is_ok() {
+        let log_filter = tunables.borrow_and_update().log_filter.clone();
+        if log_filter == current {
+            continue;
+        }
+
+        match handle.set(log_filter.as_deref()) {
+            Ok(()) => info!(?log_filter, "changed log filter"),
+            Err(e) => warn!(%e, ?log_filter, "cannot change log filter"),
+        }
+        current = log_filter;
+    }
+}
+
+/// Applies [`Tunables::catalog_cache_size_limit`] to `cache` and evicts its unused entries every
+/// [`Tunables::catalog_cache_gc_interval`], picking up changes of either.
+///
+/// Runs until the [`ReloadableConfig`] is dropped. This replaces the periodic [`CatalogCache::evict_unused`] of the
+/// service, the GC interval restarts whenever the tunables change.
+pub async fn reload_catalog_cache(
+    mut tunables: watch::Receiver<Arc<Tunables>>,
+    cache: Arc<CatalogCache>,
+) {
+    loop {
+        let (size_limit, gc_interval) = {
+            let tunables = tunables.borrow_and_update();
+            (
+                tunables.catalog_cache_size_limit,
+                tunables.catalog_cache_gc_interval,
+            )
+        };
+        if let Some(size_limit) = size_limit {
+            cache.set_limit(size_limit.bytes());
+        }
+
+        let gc = async {
+            match gc_interval {
+                Some(gc_interval) => tokio::time::sleep(gc_interval).await,
+                None => futures::future::pending().await,
+            }
+        };
+        tokio::select! {
+            _ = gc => cache.evict_unused(),
+            res = tunables.changed() => {
+                if res.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+
+    use bytes::Bytes;
+    use catalog_cache::{CacheKey, CacheValue};
+    use observability_deps::tracing::{self, debug};
+    use trogging::tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn test_reload() {
+        let next = Arc::new(AtomicUsize::new(1));
+        let captured = Arc::clone(&next);
+        let config = ReloadableConfig::new(1, move || Ok(captured.load(Ordering::SeqCst)));
+        let mut receiver = config.subscribe();
+        assert_eq!(*config.get(), 1);
+
+        // unchanged
+        assert!(!config.reload().unwrap());
+        assert!(!receiver.has_changed().unwrap());
+
+        next.store(2, Ordering::SeqCst);
+        assert!(config.reload().unwrap());
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(**receiver.borrow_and_update(), 2);
+        assert_eq!(*config.get(), 2);
+    }
+
+    #[test]
+    fn test_reload_error_keeps_value() {
+        let config = ReloadableConfig::new(1, || Err("broken".into()));
+        let err = config.reload().unwrap_err();
+        assert_eq!(err.to_string(), "Could not load config: broken");
+        assert_eq!(*config.get(), 1);
+    }
+
+    #[test]
+    fn test_tunables() {
+        use clap::Parser;
+
+        let logging = LoggingConfig::parse_from(["binary", "--log-filter", "debug"]);
+        let catalog = CatalogConfig::parse_from([
+            "binary",
+            "--catalog-cache-size-limit",
+            "1024",
+            "--catalog-cache-gc-interval",
+            "1m",
+        ]);
+        let tunables = Tunables::default()
+            .with_logging(&logging)
+            .with_catalog_cache(&catalog);
+        assert_eq!(
+            tunables,
+            Tunables {
+                log_filter: Some("debug".to_owned()),
+                catalog_cache_size_limit: Some("1024".parse().unwrap()),
+                catalog_cache_gc_interval: Some(Duration::from_secs(60)),
+            }
+        );
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct TestWriter(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for TestWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reload_log_filter() {
+        let next = Arc::new(Mutex::new(Tunables::default()));
+        let captured = Arc::clone(&next);
+        let config = ReloadableConfig::new(Tunables::default(), move || {
+            Ok(captured.lock().unwrap().clone())
+        });
+
+        let writer = TestWriter::default();
+        let make_writer = writer.clone();
+        let (layer, handle) = trogging::Builder::new()
+            .with_writer(move || make_writer.clone())
+            .with_ansi(false)
+            .with_log_filter(&Some("info".to_owned()))
+            .build_reloadable()
+            .unwrap();
+        let _guard = tracing::subscriber::set_default(
+            trogging::tracing_subscriber::Registry::default().with(layer),
+        );
+
+        // the current-thread runtime runs the task on this thread, i.e. with the subscriber above
+        let task = tokio::spawn(reload_log_filter(config.subscribe(), handle));
+        tokio::task::yield_now().await;
+        debug!("hidden");
+
+        *next.lock().unwrap() = Tunables {
+            log_filter: Some("debug".to_owned()),
+            ..Default::default()
+        };
+        assert!(config.reload().unwrap());
+        tokio::task::yield_now().await;
+        debug!("shown");
+
+        // unset restores the initial filter
+        *next.lock().unwrap() = Tunables::default();
+        assert!(config.reload().unwrap());
+        tokio::task::yield_now().await;
+        debug!("hidden again");
+
+        drop(config);
+        task.await.unwrap();
+
+        let output = String::from_utf8(writer.0.lock().unwrap().clone()).unwrap();
+        assert!(!output.contains("hidden"), "{output}");
+        assert!(output.contains("shown"), "{output}");
+        assert!(output.contains("changed log filter"), "{output}");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reload_catalog_cache() {
+        let next = Arc::new(Mutex::new(Tunables {
+            catalog_cache_size_limit: Some("100".parse().unwrap()),
+            catalog_cache_gc_interval: Some(Duration::from_secs(60)),
+            ..Default::default()
+        }));
+        let captured = Arc::clone(&next);
+        let config = ReloadableConfig::new(next.lock().unwrap().clone(), move || {
+            Ok(captured.lock().unwrap().clone())
+        });
+
+        let cache = Arc::new(CatalogCache::new(Some(1_000)));
+        let task = tokio::spawn(reload_catalog_cache(config.subscribe(), Arc::clone(&cache)));
+        tokio::task::yield_now().await;
+
+        // size limit applied
+        let value =
+            |len: usize, generation: u64| CacheValue::new(Bytes::from(vec![0; len]), generation);
+        cache.insert(CacheKey::Table(1), value(100, 0)).unwrap();
+        cache.insert(CacheKey::Table(2), value(1, 0)).unwrap_err();
+
+        // unused entries are evicted every GC interval, values start as used
+        tokio::time::sleep(Duration::from_secs(61)).await;
+        assert_eq!(cache.list().count(), 1);
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert_eq!(cache.list().count(), 0);
+
+        // changes are picked up
+        *next.lock().unwrap() = Tunables {
+            catalog_cache_size_limit: Some("200".parse().unwrap()),
+            catalog_cache_gc_interval: Some(Duration::from_secs(600)),
+            ..Default::default()
+        };
+        assert!(config.reload().unwrap());
+        tokio::task::yield_now().await;
+        cache.insert(CacheKey::Table(1), value(200, 1)).unwrap();
+        tokio::time::sleep(Duration::from_secs(1_201)).await;
+        assert_eq!(cache.list().count(), 0);
+
+        // stops once the config is dropped
+        drop(config);
+        task.await.unwrap();
+    }
+}
@@ -10,6 +10,16 @@ pub const DEFAULT_API_BIND_ADDR: &str = "127.0.0.1:8080";
 /// The default bind address for the gRPC.
 pub const DEFAULT_GRPC_BIND_ADDR: &str = "127.0.0.1:8082";
 
+/// Compression algorithm for gRPC responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GrpcCompression {
+    /// No compression.
+    None,
+
+    /// gzip.
+    Gzip,
+}
+
 /// Common config for all `run` commands.
 #[derive(Debug, Clone, clap::Parser)]
 pub struct RunConfig {
@@ -48,6 +58,21 @@ pub struct RunConfig {
     )]
     pub max_http_request_size: usize,
 
+    /// Compression algorithms that may be used for gRPC responses (e.g. Flight query results).
+    ///
+    /// The algorithm is negotiated per call using the `grpc-accept-encoding` header of the request,
+    /// responses to clients that accept none of these algorithms are not compressed.
+    ///
+    /// Use `none` to disable response compression.
+    #[clap(
+        long = "grpc-response-compression",
+        env = "INFLUXDB_IOX_GRPC_RESPONSE_COMPRESSION",
+        default_value = "gzip",
+        value_delimiter = ',',
+        action
+    )]
+    pub(crate) grpc_response_compression: Vec<GrpcCompression>,
+
     /// object store config
     #[clap(flatten)]
     pub(crate) object_store_config: ObjectStoreConfig,
@@ -69,6 +94,15 @@ impl RunConfig {
         &mut self.tracing_config
     }
 
+    /// Get the compression algorithms that are enabled for gRPC responses.
+    pub fn grpc_response_compression(&self) -> Vec<GrpcCompression> {
+        self.grpc_response_compression
+            .iter()
+            .copied()
+            .filter(|compression| *compression != GrpcCompression::None)
+            .collect()
+    }
+
     /// Get a reference to the run config's logging config.
     pub fn logging_config(&self) -> &LoggingConfig {
         &self.logging_config
@@ -101,7 +135,39 @@ impl RunConfig {
             http_bind_address,
             grpc_bind_address,
             max_http_request_size,
+            grpc_response_compression: vec![GrpcCompression::Gzip],
             object_store_config,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_grpc_response_compression() {
+        let config = RunConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(
+            config.grpc_response_compression(),
+            vec![GrpcCompression::Gzip],
+        );
+
+        let config =
+            RunConfig::try_parse_from(["my_binary", "--grpc-response-compression", "gzip,none"])
+                .unwrap();
+        assert_eq!(
+            config.grpc_response_compression(),
+            vec![GrpcCompression::Gzip]
+        );
+
+        let config =
+            RunConfig::try_parse_from(["my_binary", "--grpc-response-compression", "none"])
+                .unwrap();
+        assert_eq!(config.grpc_response_compression(), vec![]);
+
+        RunConfig::try_parse_from(["my_binary", "--grpc-response-compression", "br"]).unwrap_err();
+    }
+}
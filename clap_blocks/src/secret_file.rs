@@ -0,0 +1,225 @@
+//! File-based indirection for secrets.
+//!
+//! Instead of passing a secret via its environment variable, e.g. `AWS_SECRET_ACCESS_KEY`, the path of a file that
+//! contains it can be passed via the same variable with a `_FILE` suffix, e.g.
+//! `AWS_SECRET_ACCESS_KEY_FILE=/var/run/secrets/aws-secret-access-key`. This allows mounting Kubernetes secrets as
+//! files without exposing their values in the environment.
+//!
+//! Only the options listed in [`SECRET_ENV_NAMES`] support this. Command line flags take precedence over the file,
+//! and setting both the environment variable and its `_FILE` variant is an error.
+
+use std::{ffi::OsString, io, path::PathBuf};
+
+use clap::{parser::ValueSource, Command};
+use snafu::{ensure, ResultExt, Snafu};
+
+/// Suffix of the environment variables that provide the path of a secret file.
+pub const SECRET_FILE_SUFFIX: &str = "_FILE";
+
+/// Environment variables of options that can also be read from a secret file.
+pub const SECRET_ENV_NAMES: &[&str] = &[
+    "INFLUXDB_IOX_CATALOG_DSN",
+    "AWS_ACCESS_KEY_ID",
+    "AWS_SECRET_ACCESS_KEY",
+    "AWS_SESSION_TOKEN",
+    "AZURE_STORAGE_ACCESS_KEY",
+];
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("Only one of {env} and {env}{SECRET_FILE_SUFFIX} may be set"))]
+    Ambiguous { env: String },
+
+    #[snafu(display(
+        "Could not read secret file {} given by {env}{SECRET_FILE_SUFFIX}: {source}",
+        path.display()
+    ))]
+    ReadFile {
+        env: String,
+        path: PathBuf,
+        source: io::Error,
+    },
+}
+
+/// Name of the environment variable that provides the secret file for `env`.
+pub fn secret_file_env_name(env: &str) -> String {
+    format!("{env}{SECRET_FILE_SUFFIX}")
+}
+
+/// Whether the value of `env` is read from a secret file.
+pub fn is_secret_file_set(env: &str) -> bool {
+    SECRET_ENV_NAMES.contains(&env) && std::env::var_os(secret_file_env_name(env)).is_some()
+}
+
+/// Appends the contents of the secret files of all options of `command` listed in [`SECRET_ENV_NAMES`] to `args`.
+///
+/// Options that are given in `args` are skipped. Trailing newlines are stripped from the file contents. This is done
+/// by [`merge_config_file`](crate::config_file::merge_config_file), so it does not need to be called separately.
+pub fn resolve_secret_files<I, T>(command: &Command, args: I) -> Result<Vec<OsString>, Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    resolve(command, args, SECRET_ENV_NAMES)
+}
+
+fn resolve<I, T>(
+    command: &Command,
+    args: I,
+    secret_env_names: &[&str],
+) -> Result<Vec<OsString>, Error>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString>,
+{
+    let mut args: Vec<OsString> = args.into_iter().map(Into::into).collect();
+
+    let secret_args: Vec<_> = command
+        .get_arguments()
+        .filter_map(|arg| {
+            let env = arg.get_env()?.to_str()?;
+            let long = arg.get_long()?;
+            secret_env_names.contains(&env).then_some((arg, env, long))
+        })
+        .collect();
+    if secret_args.is_empty() {
+        return Ok(args);
+    }
+
+    // Errors (incl. `--help`) are reported by the actual parsing
+    let Ok(matches) = command
+        .clone()
+        .ignore_errors(true)
+        .try_get_matches_from(args.clone())
+    else {
+        return Ok(args);
+    };
+
+    for (arg, env, long) in secret_args {
+        let Some(path) = std::env::var_os(secret_file_env_name(env)) else {
+            continue;
+        };
+        ensure!(
+            std::env::var_os(env).is_none(),
+            AmbiguousSnafu {
+                env: env.to_owned()
+            }
+        );
+        if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+            continue;
+        }
+
+        let path = PathBuf::from(path);
+        let content = std::fs::read_to_string(&path).context(ReadFileSnafu {
+            env: env.to_owned(),
+            path: &path,
+        })?;
+        let value = content.trim_end_matches(['\r', '\n']);
+        args.push(format!("--{long}={value}").into());
+    }
+
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::{CommandFactory, Parser};
+
+    use super::*;
+
+    // Every test uses its own environment variable, as tests share the environment
+    #[allow(dead_code)]
+    #[derive(Debug, clap::Parser)]
+    struct TestConfig {
+        #[clap(long = "first", env = "INFLUXDB_IOX_TEST_SECRET_FIRST")]
+        first: Option<String>,
+
+        #[clap(long = "second", env = "INFLUXDB_IOX_TEST_SECRET_SECOND")]
+        second: Option<String>,
+
+        #[clap(long = "third", env = "INFLUXDB_IOX_TEST_SECRET_THIRD")]
+        third: Option<String>,
+
+        #[clap(long = "fourth", env = "INFLUXDB_IOX_TEST_SECRET_FOURTH")]
+        fourth: Option<String>,
+
+        #[clap(long = "public", env = "INFLUXDB_IOX_TEST_SECRET_PUBLIC")]
+        public: Option<String>,
+    }
+
+    const TEST_SECRET_ENV_NAMES: &[&str] = &[
+        "INFLUXDB_IOX_TEST_SECRET_FIRST",
+        "INFLUXDB_IOX_TEST_SECRET_SECOND",
+        "INFLUXDB_IOX_TEST_SECRET_THIRD",
+        "INFLUXDB_IOX_TEST_SECRET_FOURTH",
+    ];
+
+    fn parse(args: &[&str]) -> Result<TestConfig, Error> {
+        let args = resolve(
+            &TestConfig::command(),
+            args.iter().copied(),
+            TEST_SECRET_ENV_NAMES,
+        )?;
+        Ok(TestConfig::parse_from(args))
+    }
+
+    fn secret_file(content: &str) -> tempfile::NamedTempFile {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), content).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_secret_file() {
+        let file = secret_file("hunter2\n");
+        std::env::set_var("INFLUXDB_IOX_TEST_SECRET_FIRST_FILE", file.path());
+        let config = parse(&["my_binary"]).unwrap();
+        assert_eq!(config.first.as_deref(), Some("hunter2"));
+
+        // command line takes precedence
+        let config = parse(&["my_binary", "--first", "from-flag"]).unwrap();
+        std::env::remove_var("INFLUXDB_IOX_TEST_SECRET_FIRST_FILE");
+        assert_eq!(config.first.as_deref(), Some("from-flag"));
+    }
+
+    #[test]
+    fn test_not_a_secret() {
+        let file = secret_file("hunter2");
+        std::env::set_var("INFLUXDB_IOX_TEST_SECRET_PUBLIC_FILE", file.path());
+        let config = parse(&["my_binary"]).unwrap();
+        std::env::remove_var("INFLUXDB_IOX_TEST_SECRET_PUBLIC_FILE");
+        assert_eq!(config.public, None);
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        let file = secret_file("hunter2");
+        std::env::set_var("INFLUXDB_IOX_TEST_SECRET_SECOND", "from-env");
+        std::env::set_var("INFLUXDB_IOX_TEST_SECRET_SECOND_FILE", file.path());
+        let err = parse(&["my_binary"]).unwrap_err();
+        std::env::remove_var("INFLUXDB_IOX_TEST_SECRET_SECOND");
+        std::env::remove_var("INFLUXDB_IOX_TEST_SECRET_SECOND_FILE");
+        assert_eq!(
+            err.to_string(),
+            "Only one of INFLUXDB_IOX_TEST_SECRET_SECOND and INFLUXDB_IOX_TEST_SECRET_SECOND_FILE may be set"
+        );
+    }
+
+    #[test]
+    fn test_missing_file() {
+        std::env::set_var("INFLUXDB_IOX_TEST_SECRET_THIRD_FILE", "/does/not/exist");
+        let err = parse(&["my_binary"]).unwrap_err();
+        std::env::remove_var("INFLUXDB_IOX_TEST_SECRET_THIRD_FILE");
+        assert!(matches!(err, Error::ReadFile { .. }), "{err}");
+    }
+
+    #[test]
+    fn test_no_secret_file() {
+        let config = parse(&["my_binary", "--fourth", "from-flag"]).unwrap();
+        assert_eq!(config.fourth.as_deref(), Some("from-flag"));
+
+        let config = parse(&["my_binary"]).unwrap();
+        assert_eq!(config.fourth, None);
+    }
+}
@@ -0,0 +1,209 @@
+//! CLI config for the tokio runtimes.
+//!
+//! Services run two kinds of runtimes: the IO runtime that handles requests (gRPC, HTTP, catalog and object store
+//! calls) and the DataFusion runtimes that execute queries and compactions. Both can be tuned independently, e.g. to
+//! right-size small pods.
+
+use std::num::{NonZeroU32, NonZeroUsize};
+
+use executor::RuntimeSettings;
+
+/// CLI config for the IO runtime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::Parser)]
+pub struct TokioIoConfig {
+    /// Number of worker threads of the IO runtime.
+    ///
+    /// If not specified, defaults to the number of cores on the system.
+    #[clap(
+        long = "io-runtime-num-threads",
+        env = "INFLUXDB_IOX_IO_RUNTIME_NUM_THREADS",
+        action
+    )]
+    pub num_threads: Option<NonZeroUsize>,
+
+    /// Maximum number of threads of the IO runtime that are spawned for blocking operations (e.g. file IO).
+    ///
+    /// If not specified, the tokio default (512) is used.
+    #[clap(
+        long = "io-runtime-max-blocking-threads",
+        env = "INFLUXDB_IOX_IO_RUNTIME_MAX_BLOCKING_THREADS",
+        action
+    )]
+    pub max_blocking_threads: Option<NonZeroUsize>,
+
+    /// Stack size of the threads of the IO runtime, in bytes.
+    ///
+    /// If not specified, the tokio default (2MiB) is used.
+    #[clap(
+        long = "io-runtime-thread-stack-size",
+        env = "INFLUXDB_IOX_IO_RUNTIME_THREAD_STACK_SIZE",
+        action
+    )]
+    pub thread_stack_size: Option<NonZeroUsize>,
+
+    /// Number of scheduler ticks after which the IO runtime polls for external events (e.g. network IO and timers).
+    ///
+    /// Lower values reduce the latency of IO events at the expense of throughput. If not specified, the tokio default
+    /// (61) is used.
+    #[clap(
+        long = "io-runtime-event-interval",
+        env = "INFLUXDB_IOX_IO_RUNTIME_EVENT_INTERVAL",
+        action
+    )]
+    pub event_interval: Option<NonZeroU32>,
+}
+
+impl TokioIoConfig {
+    /// Settings of the IO runtime, apart from its number of worker threads.
+    pub fn runtime_settings(&self) -> RuntimeSettings {
+        RuntimeSettings {
+            max_blocking_threads: self.max_blocking_threads,
+            thread_stack_size: self.thread_stack_size,
+            event_interval: self.event_interval,
+        }
+    }
+
+    /// Builder for the multi-threaded IO runtime with all drivers enabled.
+    pub fn builder(&self) -> tokio::runtime::Builder {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        builder.enable_all();
+        if let Some(num_threads) = self.num_threads {
+            builder.worker_threads(num_threads.get());
+        }
+        self.runtime_settings().apply(&mut builder);
+        builder
+    }
+}
+
+/// CLI config for the DataFusion runtimes.
+///
+/// The settings apply to all DataFusion runtimes of a service (e.g. the ones for queries and for reorganization).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::Parser)]
+pub struct TokioDatafusionConfig {
+    /// Number of worker threads of each DataFusion runtime.
+    ///
+    /// If not specified, the thread count setting of the service is used (e.g. `--num-query-threads`).
+    #[clap(
+        long = "datafusion-runtime-num-threads",
+        env = "INFLUXDB_IOX_DATAFUSION_RUNTIME_NUM_THREADS",
+        action
+    )]
+    pub num_threads: Option<NonZeroUsize>,
+
+    /// Maximum number of threads of each DataFusion runtime that are spawned for blocking operations (e.g.
+    /// spilling to disk).
+    ///
+    /// If not specified, the tokio default (512) is used.
+    #[clap(
+        long = "datafusion-runtime-max-blocking-threads",
+        env = "INFLUXDB_IOX_DATAFUSION_RUNTIME_MAX_BLOCKING_THREADS",
+        action
+    )]
+    pub max_blocking_threads: Option<NonZeroUsize>,
+
+    /// Stack size of the threads of the DataFusion runtimes, in bytes.
+    ///
+    /// Deeply nested query plans may need a larger stack. If not specified, the tokio default (2MiB) is used.
+    #[clap(
+        long = "datafusion-runtime-thread-stack-size",
+        env = "INFLUXDB_IOX_DATAFUSION_RUNTIME_THREAD_STACK_SIZE",
+        action
+    )]
+    pub thread_stack_size: Option<NonZeroUsize>,
+
+    /// Number of scheduler ticks after which the DataFusion runtimes poll for external events (e.g. IO and timers).
+    ///
+    /// If not specified, the tokio default (61) is used.
+    #[clap(
+        long = "datafusion-runtime-event-interval",
+        env = "INFLUXDB_IOX_DATAFUSION_RUNTIME_EVENT_INTERVAL",
+        action
+    )]
+    pub event_interval: Option<NonZeroU32>,
+}
+
+impl TokioDatafusionConfig {
+    /// Settings of the DataFusion runtimes, apart from their number of worker threads.
+    pub fn runtime_settings(&self) -> RuntimeSettings {
+        RuntimeSettings {
+            max_blocking_threads: self.max_blocking_threads,
+            thread_stack_size: self.thread_stack_size,
+            event_interval: self.event_interval,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        let io = TokioIoConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(io, TokioIoConfig::default());
+        assert_eq!(io.runtime_settings(), RuntimeSettings::default());
+
+        let datafusion = TokioDatafusionConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(datafusion, TokioDatafusionConfig::default());
+        assert_eq!(datafusion.runtime_settings(), RuntimeSettings::default());
+    }
+
+    #[test]
+    fn test_io() {
+        let io = TokioIoConfig::try_parse_from([
+            "my_binary",
+            "--io-runtime-num-threads",
+            "2",
+            "--io-runtime-max-blocking-threads",
+            "4",
+            "--io-runtime-thread-stack-size",
+            "4194304",
+            "--io-runtime-event-interval",
+            "31",
+        ])
+        .unwrap();
+        assert_eq!(io.num_threads, NonZeroUsize::new(2));
+        assert_eq!(
+            io.runtime_settings(),
+            RuntimeSettings {
+                max_blocking_threads: NonZeroUsize::new(4),
+                thread_stack_size: NonZeroUsize::new(4194304),
+                event_interval: NonZeroU32::new(31),
+            }
+        );
+
+        let runtime = io.builder().build().unwrap();
+        assert_eq!(runtime.block_on(async { 42 }), 42);
+    }
+
+    #[test]
+    fn test_datafusion() {
+        let datafusion = TokioDatafusionConfig::try_parse_from([
+            "my_binary",
+            "--datafusion-runtime-num-threads",
+            "3",
+            "--datafusion-runtime-thread-stack-size",
+            "8388608",
+        ])
+        .unwrap();
+        assert_eq!(datafusion.num_threads, NonZeroUsize::new(3));
+        assert_eq!(
+            datafusion.runtime_settings(),
+            RuntimeSettings {
+                max_blocking_threads: None,
+                thread_stack_size: NonZeroUsize::new(8388608),
+                event_interval: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let err = TokioIoConfig::try_parse_from(["my_binary", "--io-runtime-num-threads", "0"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("--io-runtime-num-threads"), "{err}");
+    }
+}
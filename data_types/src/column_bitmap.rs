@@ -0,0 +1,227 @@
+//! A bitmap-encoded set of [`ColumnId`].
+
+use croaring::{Portable, Treemap};
+use thiserror::Error;
+
+use crate::{ColumnId, ColumnSet};
+
+/// Errors decoding a serialised [`ColumnBitmap`].
+#[derive(Debug, Error, PartialEq, Eq)]
+#[allow(missing_copy_implementations)]
+pub enum ColumnBitmapError {
+    /// The serialised bytes are not a valid portable roaring bitmap.
+    #[error("invalid serialised column bitmap ({0} bytes)")]
+    InvalidEncoding(usize),
+}
+
+/// A space-efficient encoded set of [`ColumnId`].
+///
+/// Unlike the sorted [`ColumnSet`], membership tests are O(1) and unions and
+/// intersections operate on whole bitmap containers at a time, making this
+/// representation suitable for hot paths that repeatedly test or combine sets
+/// of columns.
+///
+/// [`ColumnBitmap::to_bytes()`] produces a compact serialised form using the
+/// portable roaring format, which is suitable for catalog storage.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ColumnBitmap(Treemap);
+
+impl ColumnBitmap {
+    /// Add the specified [`ColumnId`] to the set, returning `true` if it was
+    /// not already present.
+    pub fn insert(&mut self, id: ColumnId) -> bool {
+        let v = id.get() as u64;
+        if self.0.contains(v) {
+            return false;
+        }
+        self.0.add(v);
+        true
+    }
+
+    /// Remove the specified [`ColumnId`] from the set, if present.
+    pub fn remove(&mut self, id: ColumnId) {
+        self.0.remove(id.get() as u64);
+    }
+
+    /// Return true if the specified [`ColumnId`] is in `self`.
+    pub fn contains(&self, id: ColumnId) -> bool {
+        self.0.contains(id.get() as u64)
+    }
+
+    /// Returns the number of [`ColumnId`] in this set.
+    pub fn len(&self) -> u64 {
+        self.0.cardinality()
+    }
+
+    /// Return `true` if there are no [`ColumnId`] in this set.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return an iterator of all [`ColumnId`] in this set, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = ColumnId> + '_ {
+        self.0.iter().map(|v| ColumnId::new(v as i64))
+    }
+
+    /// Add all the [`ColumnId`] in `other` to `self`.
+    pub fn union(&mut self, other: &Self) {
+        self.0.or_inplace(&other.0)
+    }
+
+    /// Return the set of [`ColumnId`] present in both `self` and `other`.
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self(self.0.and(&other.0))
+    }
+
+    /// Return true if every [`ColumnId`] in `self` is also in `other`.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.0.and(&other.0).cardinality() == self.0.cardinality()
+    }
+
+    /// Reduce the memory usage of this set (trading off immediate CPU time) by
+    /// efficiently re-encoding the set (using run-length encoding).
+    pub fn run_optimise(&mut self) {
+        self.0.run_optimize();
+    }
+
+    /// Serialise this set into its compact, portable binary form.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.serialize::<Portable>()
+    }
+
+    /// Decode a set previously serialised with [`ColumnBitmap::to_bytes()`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ColumnBitmapError> {
+        Treemap::try_deserialize::<Portable>(bytes)
+            .map(Self)
+            .ok_or(ColumnBitmapError::InvalidEncoding(bytes.len()))
+    }
+
+    /// Estimate the memory consumption of this object and its contents.
+    pub fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.0.get_serialized_size_in_bytes::<Portable>()
+    }
+}
+
+impl Extend<ColumnId> for ColumnBitmap {
+    fn extend<T: IntoIterator<Item = ColumnId>>(&mut self, iter: T) {
+        self.0.extend(iter.into_iter().map(|v| v.get() as u64))
+    }
+}
+
+impl FromIterator<ColumnId> for ColumnBitmap {
+    fn from_iter<T: IntoIterator<Item = ColumnId>>(iter: T) -> Self {
+        Self(iter.into_iter().map(|v| v.get() as u64).collect())
+    }
+}
+
+impl From<&ColumnSet> for ColumnBitmap {
+    fn from(set: &ColumnSet) -> Self {
+        set.iter().copied().collect()
+    }
+}
+
+impl From<&ColumnBitmap> for ColumnSet {
+    fn from(set: &ColumnBitmap) -> Self {
+        Self::new(set.iter())
+    }
+}
+
+impl<DB> sqlx::Type<DB> for ColumnBitmap
+where
+    Vec<u8>: sqlx::Type<DB>,
+    DB: sqlx::Database,
+{
+    fn type_info() -> DB::TypeInfo {
+        <Vec<u8> as sqlx::Type<DB>>::type_info()
+    }
+}
+
+impl<'q, DB> sqlx::Encode<'q, DB> for ColumnBitmap
+where
+    DB: sqlx::Database,
+    Vec<u8>: sqlx::Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as sqlx::database::HasArguments<'q>>::ArgumentBuffer,
+    ) -> sqlx::encode::IsNull {
+        <Vec<u8> as sqlx::Encode<'_, DB>>::encode(self.to_bytes(), buf)
+    }
+}
+
+impl<'q, DB> sqlx::Decode<'q, DB> for ColumnBitmap
+where
+    DB: sqlx::Database,
+    Vec<u8>: sqlx::Decode<'q, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::database::HasValueRef<'q>>::ValueRef,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        let bytes = <Vec<u8> as sqlx::Decode<'_, DB>>::decode(value)?;
+        Ok(Self::from_bytes(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitmap(ids: impl IntoIterator<Item = i64>) -> ColumnBitmap {
+        ids.into_iter().map(ColumnId::new).collect()
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let mut a = ColumnBitmap::default();
+        assert!(a.is_empty());
+
+        assert!(a.insert(ColumnId::new(1)));
+        assert!(!a.insert(ColumnId::new(1)));
+        assert!(a.contains(ColumnId::new(1)));
+        assert!(!a.contains(ColumnId::new(2)));
+        assert_eq!(a.len(), 1);
+
+        a.union(&bitmap([2, 3, 42]));
+        assert_eq!(
+            a.iter().collect::<Vec<_>>(),
+            [1, 2, 3, 42].map(ColumnId::new)
+        );
+
+        let b = a.intersection(&bitmap([3, 42, 100]));
+        assert_eq!(b.iter().collect::<Vec<_>>(), [3, 42].map(ColumnId::new));
+        assert!(b.is_subset(&a));
+        assert!(!a.is_subset(&b));
+
+        a.remove(ColumnId::new(42));
+        a.remove(ColumnId::new(1000));
+        assert_eq!(a.len(), 3);
+    }
+
+    #[test]
+    fn test_column_set_round_trip() {
+        let set = ColumnSet::new([7, 1, 3].map(ColumnId::new));
+        let bitmap = ColumnBitmap::from(&set);
+
+        assert_eq!(bitmap.len(), 3);
+        assert!(bitmap.contains(ColumnId::new(3)));
+        assert_eq!(ColumnSet::from(&bitmap), set);
+    }
+
+    #[test]
+    fn test_serialise_round_trip() {
+        let mut bitmap = bitmap((1..1_000).chain([u32::MAX as i64 + 1]));
+        bitmap.run_optimise();
+
+        let bytes = bitmap.to_bytes();
+        // A contiguous run of ids encodes far smaller than an array of them.
+        assert!(bytes.len() < 1_000 * std::mem::size_of::<ColumnId>() / 10);
+
+        let got = ColumnBitmap::from_bytes(&bytes).unwrap();
+        assert_eq!(got, bitmap);
+
+        assert_eq!(
+            ColumnBitmap::from_bytes(&[1, 2, 3]),
+            Err(ColumnBitmapError::InvalidEncoding(3))
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! Per-column statistics recorded in the catalog for a parquet file.
+
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use generated_types::influxdata::iox::column_statistics::v1 as proto;
+use ordered_float::OrderedFloat;
+
+use crate::{ColumnId, StatOverlap, Statistics};
+
+/// A single min/max statistic value of a column.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum StatValue {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(OrderedFloat<f64>),
+    String(String),
+}
+
+impl StatValue {
+    /// Estimate the memory consumption of this object and its contents
+    pub fn size(&self) -> usize {
+        let heap = match self {
+            Self::String(s) => s.capacity(),
+            Self::Bool(_) | Self::I64(_) | Self::U64(_) | Self::F64(_) => 0,
+        };
+        std::mem::size_of_val(self) + heap
+    }
+}
+
+/// Values of differing types are not comparable.
+impl PartialOrd for StatValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Bool(a), Self::Bool(b)) => a.partial_cmp(b),
+            (Self::I64(a), Self::I64(b)) => a.partial_cmp(b),
+            (Self::U64(a), Self::U64(b)) => a.partial_cmp(b),
+            (Self::F64(a), Self::F64(b)) => a.partial_cmp(b),
+            (Self::String(a), Self::String(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+impl From<StatValue> for proto::StatisticValue {
+    fn from(v: StatValue) -> Self {
+        use proto::statistic_value::Value;
+
+        let value = match v {
+            StatValue::Bool(v) => Value::BoolValue(v),
+            StatValue::I64(v) => Value::I64Value(v),
+            StatValue::U64(v) => Value::U64Value(v),
+            StatValue::F64(v) => Value::F64Value(v.into_inner()),
+            StatValue::String(v) => Value::StringValue(v),
+        };
+        Self { value: Some(value) }
+    }
+}
+
+impl From<proto::statistic_value::Value> for StatValue {
+    fn from(v: proto::statistic_value::Value) -> Self {
+        use proto::statistic_value::Value;
+
+        match v {
+            Value::BoolValue(v) => Self::Bool(v),
+            Value::I64Value(v) => Self::I64(v),
+            Value::U64Value(v) => Self::U64(v),
+            Value::F64Value(v) => Self::F64(OrderedFloat(v)),
+            Value::StringValue(v) => Self::String(v),
+        }
+    }
+}
+
+/// The min/max/null-count statistics of a single column within a parquet
+/// file.
+///
+/// Each statistic is optional - an absent value is unknown, and must not be
+/// used to exclude the file from a query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnStatistics {
+    /// The minimum non-null value in the column, if known.
+    pub min: Option<StatValue>,
+    /// The maximum non-null value in the column, if known.
+    pub max: Option<StatValue>,
+    /// The number of null values in the column, if known.
+    pub null_count: Option<u64>,
+}
+
+impl ColumnStatistics {
+    /// Returns information about the overlap between the values in this column
+    /// and the inclusive range `[min, max]`.
+    ///
+    /// If either bound of this column is unknown, or is of a different type to
+    /// the range, [`StatOverlap::Unknown`] is returned.
+    pub fn overlaps(&self, min: &StatValue, max: &StatValue) -> StatOverlap {
+        let (Some(self_min), Some(self_max)) = (&self.min, &self.max) else {
+            return StatOverlap::Unknown;
+        };
+
+        match (self_min.partial_cmp(max), self_max.partial_cmp(min)) {
+            (Some(Ordering::Greater), Some(_)) | (Some(_), Some(Ordering::Less)) => {
+                StatOverlap::Zero
+            }
+            (Some(_), Some(_)) => StatOverlap::NonZero,
+            _ => StatOverlap::Unknown,
+        }
+    }
+
+    /// Returns true if the column may contain `value`.
+    pub fn may_contain(&self, value: &StatValue) -> bool {
+        self.overlaps(value, value) != StatOverlap::Zero
+    }
+
+    /// Estimate the memory consumption of this object and its contents
+    pub fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + [&self.min, &self.max]
+                .into_iter()
+                .flatten()
+                .map(|v| v.size() - std::mem::size_of_val(v))
+                .sum::<usize>()
+    }
+}
+
+impl From<&Statistics> for ColumnStatistics {
+    fn from(stats: &Statistics) -> Self {
+        fn convert<T: Clone>(
+            stats: &crate::StatValues<T>,
+            f: impl Fn(T) -> StatValue,
+        ) -> ColumnStatistics {
+            ColumnStatistics {
+                min: stats.min.clone().map(&f),
+                max: stats.max.clone().map(&f),
+                null_count: stats.null_count,
+            }
+        }
+
+        match stats {
+            Statistics::I64(v) => convert(v, StatValue::I64),
+            Statistics::U64(v) => convert(v, StatValue::U64),
+            Statistics::Bool(v) => convert(v, StatValue::Bool),
+            Statistics::String(v) => convert(v, StatValue::String),
+            Statistics::F64(v) => convert(v, |v| StatValue::F64(OrderedFloat(v))),
+        }
+    }
+}
+
+/// The [`ColumnStatistics`] of the columns within a single parquet file,
+/// keyed by [`ColumnId`].
+///
+/// Columns without an entry have no known statistics.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileColumnStatistics(BTreeMap<ColumnId, ColumnStatistics>);
+
+impl FileColumnStatistics {
+    /// Create a new set of file statistics.
+    ///
+    /// If `columns` contains duplicate [`ColumnId`], the last entry is
+    /// retained.
+    pub fn new<I>(columns: I) -> Self
+    where
+        I: IntoIterator<Item = (ColumnId, ColumnStatistics)>,
+    {
+        Self(columns.into_iter().collect())
+    }
+
+    /// Returns the statistics of the column with `id`, if known.
+    pub fn get(&self, id: ColumnId) -> Option<&ColumnStatistics> {
+        self.0.get(&id)
+    }
+
+    /// Iterate over the columns with known statistics, in [`ColumnId`] order.
+    pub fn iter(&self) -> impl Iterator<Item = (ColumnId, &ColumnStatistics)> + '_ {
+        self.0.iter().map(|(id, stats)| (*id, stats))
+    }
+
+    /// The number of columns with known statistics.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if no column statistics are known.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Estimate the memory consumption of this object and its contents
+    pub fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self
+                .0
+                .values()
+                .map(|v| std::mem::size_of::<ColumnId>() + v.size())
+                .sum::<usize>()
+    }
+}
+
+impl From<&FileColumnStatistics> for proto::ColumnStatistics {
+    fn from(v: &FileColumnStatistics) -> Self {
+        Self {
+            columns: v
+                .iter()
+                .map(|(id, stats)| proto::ColumnStatistic {
+                    column_id: id.get(),
+                    min: stats.min.clone().map(Into::into),
+                    max: stats.max.clone().map(Into::into),
+                    null_count: stats.null_count,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<proto::ColumnStatistics> for FileColumnStatistics {
+    fn from(v: proto::ColumnStatistics) -> Self {
+        Self::new(v.columns.into_iter().map(|c| {
+            let stats = ColumnStatistics {
+                min: c.min.and_then(|v| v.value).map(Into::into),
+                max: c.max.and_then(|v| v.value).map(Into::into),
+                null_count: c.null_count,
+            };
+            (ColumnId::new(c.column_id), stats)
+        }))
+    }
+}
+
+impl<DB> sqlx::Type<DB> for FileColumnStatistics
+where
+    sqlx::types::Json<proto::ColumnStatistics>: sqlx::Type<DB>,
+    DB: sqlx::Database,
+{
+    fn type_info() -> DB::TypeInfo {
+        <sqlx::types::Json<proto::ColumnStatistics> as sqlx::Type<DB>>::type_info()
+    }
+}
+
+impl<'q, DB> sqlx::Encode<'q, DB> for FileColumnStatistics
+where
+    DB: sqlx::Database,
+    sqlx::types::Json<proto::ColumnStatistics>: sqlx::Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as sqlx::database::HasArguments<'q>>::ArgumentBuffer,
+    ) -> sqlx::encode::IsNull {
+        <sqlx::types::Json<proto::ColumnStatistics> as sqlx::Encode<'_, DB>>::encode(
+            sqlx::types::Json(self.into()),
+            buf,
+        )
+    }
+}
+
+impl<'q, DB> sqlx::Decode<'q, DB> for FileColumnStatistics
+where
+    DB: sqlx::Database,
+    sqlx::types::Json<proto::ColumnStatistics>: sqlx::Decode<'q, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::database::HasValueRef<'q>>::ValueRef,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        Ok(
+            <sqlx::types::Json<proto::ColumnStatistics> as sqlx::Decode<'_, DB>>::decode(value)?
+                .0
+                .into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StatValues;
+
+    fn stats(min: StatValue, max: StatValue) -> ColumnStatistics {
+        ColumnStatistics {
+            min: Some(min),
+            max: Some(max),
+            null_count: Some(0),
+        }
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let s = stats(StatValue::I64(10), StatValue::I64(20));
+
+        assert_eq!(
+            s.overlaps(&StatValue::I64(0), &StatValue::I64(9)),
+            StatOverlap::Zero
+        );
+        assert_eq!(
+            s.overlaps(&StatValue::I64(21), &StatValue::I64(30)),
+            StatOverlap::Zero
+        );
+        assert_eq!(
+            s.overlaps(&StatValue::I64(0), &StatValue::I64(10)),
+            StatOverlap::NonZero
+        );
+        assert_eq!(
+            s.overlaps(&StatValue::I64(20), &StatValue::I64(30)),
+            StatOverlap::NonZero
+        );
+
+        // Mismatched types and unknown bounds cannot be used for pruning.
+        assert_eq!(
+            s.overlaps(&StatValue::U64(0), &StatValue::U64(9)),
+            StatOverlap::Unknown
+        );
+        let s = ColumnStatistics { max: None, ..s };
+        assert_eq!(
+            s.overlaps(&StatValue::I64(0), &StatValue::I64(9)),
+            StatOverlap::Unknown
+        );
+    }
+
+    #[test]
+    fn test_may_contain() {
+        let s = stats(
+            StatValue::String("bananas".to_string()),
+            StatValue::String("platanos".to_string()),
+        );
+
+        assert!(s.may_contain(&StatValue::String("bananas".to_string())));
+        assert!(s.may_contain(&StatValue::String("mango".to_string())));
+        assert!(!s.may_contain(&StatValue::String("apple".to_string())));
+        assert!(!s.may_contain(&StatValue::String("quince".to_string())));
+    }
+
+    #[test]
+    fn test_from_statistics() {
+        let got = ColumnStatistics::from(&Statistics::F64(StatValues {
+            min: Some(1.5),
+            max: Some(4.2),
+            total_count: 10,
+            null_count: Some(3),
+            distinct_count: None,
+        }));
+        assert_eq!(
+            got,
+            ColumnStatistics {
+                min: Some(StatValue::F64(OrderedFloat(1.5))),
+                max: Some(StatValue::F64(OrderedFloat(4.2))),
+                null_count: Some(3),
+            }
+        );
+    }
+
+    #[test]
+    fn test_proto_round_trip() {
+        let stats = FileColumnStatistics::new([
+            (
+                ColumnId::new(1),
+                stats(StatValue::Bool(false), StatValue::Bool(true)),
+            ),
+            (
+                ColumnId::new(2),
+                stats(StatValue::I64(-1), StatValue::I64(42)),
+            ),
+            (
+                ColumnId::new(3),
+                stats(StatValue::U64(1), StatValue::U64(42)),
+            ),
+            (
+                ColumnId::new(4),
+                stats(
+                    StatValue::F64(OrderedFloat(-0.5)),
+                    StatValue::F64(OrderedFloat(f64::INFINITY)),
+                ),
+            ),
+            (
+                ColumnId::new(5),
+                stats(
+                    StatValue::String("a".to_string()),
+                    StatValue::String("z".to_string()),
+                ),
+            ),
+            (ColumnId::new(6), ColumnStatistics::default()),
+        ]);
+
+        let proto = proto::ColumnStatistics::from(&stats);
+        assert_eq!(proto.columns.len(), 6);
+        assert_eq!(FileColumnStatistics::from(proto), stats);
+
+        let empty = FileColumnStatistics::default();
+        assert_eq!(
+            FileColumnStatistics::from(proto::ColumnStatistics::from(&empty)),
+            empty
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let stats = FileColumnStatistics::new([(
+            ColumnId::new(42),
+            stats(StatValue::U64(u64::MAX), StatValue::U64(u64::MAX)),
+        )]);
+
+        let json = serde_json::to_string(&proto::ColumnStatistics::from(&stats)).unwrap();
+        let got: proto::ColumnStatistics = serde_json::from_str(&json).unwrap();
+        assert_eq!(FileColumnStatistics::from(got), stats);
+
+        // The column default decodes as empty statistics.
+        let got: proto::ColumnStatistics = serde_json::from_str("{}").unwrap();
+        assert!(FileColumnStatistics::from(got).is_empty());
+    }
+}
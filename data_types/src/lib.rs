@@ -19,6 +19,10 @@ use thiserror::Error;
 // Workaround for "unused crate" lint false positives.
 use workspace_hack as _;
 
+mod column_bitmap;
+pub use column_bitmap::*;
+mod column_statistics;
+pub use column_statistics::*;
 mod columns;
 pub use columns::*;
 mod namespace_name;
@@ -27,6 +31,8 @@ pub mod partition_template;
 use partition_template::*;
 pub mod partition;
 pub use partition::*;
+mod retention;
+pub use retention::*;
 pub mod sequence_number_set;
 pub mod service_limits;
 pub mod snapshot;
@@ -682,6 +688,12 @@ pub struct ParquetFile {
     pub column_set: ColumnSet,
     /// the max of created_at of all L0 files needed for file/chunk ordering for deduplication
     pub max_l0_created_at: Timestamp,
+    /// Per-column min/max/null-count statistics of the data in this file.
+    ///
+    /// Statistics may be absent for some (or all) columns within the
+    /// [`column_set`](Self::column_set), in which case the file cannot be
+    /// pruned by the values of those columns.
+    pub column_stats: FileColumnStatistics,
 }
 
 impl ParquetFile {
@@ -705,6 +717,7 @@ impl ParquetFile {
             created_at: params.created_at,
             column_set: params.column_set,
             max_l0_created_at: params.max_l0_created_at,
+            column_stats: params.column_stats,
         }
     }
 
@@ -718,16 +731,32 @@ impl ParquetFile {
 
         std::mem::size_of_val(self) + hash_id + self.column_set.size()
             - std::mem::size_of_val(&self.column_set)
+            + self.column_stats.size()
+            - std::mem::size_of_val(&self.column_stats)
+    }
+
+    /// Return the inclusive time range of the data in this file
+    pub fn timestamp_min_max(&self) -> TimestampMinMax {
+        TimestampMinMax {
+            min: self.min_time.get(),
+            max: self.max_time.get(),
+        }
     }
 
     /// Return true if the time range overlaps with the time range of the given file
     pub fn overlaps(&self, other: &Self) -> bool {
-        self.min_time <= other.max_time && self.max_time >= other.min_time
+        self.timestamp_min_max()
+            .intersection(&other.timestamp_min_max())
+            .is_some()
     }
 
     /// Return true if the time range of this file overlaps with the given time range
     pub fn overlaps_time_range(&self, min_time: Timestamp, max_time: Timestamp) -> bool {
-        self.min_time <= max_time && self.max_time >= min_time
+        let range = TimestampMinMax {
+            min: min_time.get(),
+            max: max_time.get(),
+        };
+        self.timestamp_min_max().intersection(&range).is_some()
     }
 
     /// Return true if the time range of this file overlaps with any of the given split times.
@@ -744,12 +773,14 @@ impl ParquetFile {
 
     /// Return true if the time range of this file overlaps with any of the given file ranges
     pub fn overlaps_ranges(&self, ranges: &Vec<FileRange>) -> bool {
-        for range in ranges {
-            if self.min_time.get() <= range.max && self.max_time.get() >= range.min {
-                return true;
-            }
-        }
-        false
+        let this = self.timestamp_min_max();
+        ranges.iter().any(|range| {
+            let range = TimestampMinMax {
+                min: range.min,
+                max: range.max,
+            };
+            this.intersection(&range).is_some()
+        })
     }
 
     /// Temporary to aid incremental migration
@@ -832,6 +863,8 @@ pub struct ParquetFileParams {
     pub column_set: ColumnSet,
     /// the max of created_at of all L0 files
     pub max_l0_created_at: Timestamp,
+    /// per-column statistics of the data in this file
+    pub column_stats: FileColumnStatistics,
 }
 
 /// ID of a chunk.
@@ -1730,6 +1763,67 @@ impl TimestampRange {
     pub fn start(&self) -> i64 {
         self.start
     }
+
+    /// Returns true if this range contains no timestamps.
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    /// Returns the range of timestamps contained in both `self` and `other`,
+    /// or [`None`] if they have no timestamps in common.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        (start < end).then_some(Self { start, end })
+    }
+
+    /// Returns the smallest range containing every timestamp in both `self`
+    /// and `other`.
+    ///
+    /// If the ranges are disjoint, the timestamps between them are also
+    /// included. An empty range contributes no timestamps to the hull.
+    pub fn union_hull(&self, other: &Self) -> Self {
+        match (self.is_empty(), other.is_empty()) {
+            (true, _) => *other,
+            (false, true) => *self,
+            (false, false) => Self {
+                start: self.start.min(other.start),
+                end: self.end.max(other.end),
+            },
+        }
+    }
+
+    /// Returns true if every timestamp in `other` is contained within this
+    /// range.
+    ///
+    /// An empty `other` is contained within every range.
+    pub fn contains_range(&self, other: &Self) -> bool {
+        other.is_empty() || (self.start <= other.start && other.end <= self.end)
+    }
+}
+
+/// Renders the range as `[start, end)` using RFC3339 timestamps.
+impl std::fmt::Display for TimestampRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}, {})",
+            iox_time::Time::from_timestamp_nanos(self.start),
+            iox_time::Time::from_timestamp_nanos(self.end)
+        )
+    }
+}
+
+/// Parses the `[start, end)` form rendered by the [`Display`] implementation.
+///
+/// [`Display`]: std::fmt::Display
+impl std::str::FromStr for TimestampRange {
+    type Err = TimestampRangeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = parse_timestamp_bounds(s, ')')?;
+        Ok(Self::new(start, end))
+    }
 }
 
 /// Specifies a min/max timestamp value.
@@ -1760,15 +1854,106 @@ impl TimestampMinMax {
             || (self.min <= range.start && self.max >= range.end)
     }
 
-    /// Returns the union of this range with `other` with the minimum of the `min`s
-    /// and the maximum of the `max`es
-
-    pub fn union(&self, other: &Self) -> Self {
+    /// Returns the smallest range containing every timestamp in both `self`
+    /// and `other`, with the minimum of the `min`s and the maximum of the
+    /// `max`es.
+    pub fn union_hull(&self, other: &Self) -> Self {
         Self {
             min: self.min.min(other.min),
             max: self.max.max(other.max),
         }
     }
+
+    /// Returns the range of timestamps contained in both `self` and `other`,
+    /// or [`None`] if they have no timestamps in common.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let min = self.min.max(other.min);
+        let max = self.max.min(other.max);
+        (min <= max).then_some(Self { min, max })
+    }
+
+    /// Returns true if every timestamp in `other` is contained within this
+    /// range.
+    pub fn contains_range(&self, other: &Self) -> bool {
+        self.min <= other.min && other.max <= self.max
+    }
+}
+
+/// Renders the range as `[min, max]` using RFC3339 timestamps.
+impl std::fmt::Display for TimestampMinMax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{}, {}]",
+            iox_time::Time::from_timestamp_nanos(self.min),
+            iox_time::Time::from_timestamp_nanos(self.max)
+        )
+    }
+}
+
+/// Parses the `[min, max]` form rendered by the [`Display`] implementation.
+///
+/// [`Display`]: std::fmt::Display
+impl std::str::FromStr for TimestampMinMax {
+    type Err = TimestampRangeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (min, max) = parse_timestamp_bounds(s, ']')?;
+        if min > max {
+            return Err(TimestampRangeParseError::Inverted);
+        }
+        Ok(Self { min, max })
+    }
+}
+
+/// Errors parsing a [`TimestampRange`] or [`TimestampMinMax`] from a string.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TimestampRangeParseError {
+    /// The input is not of the form `[a, b)` / `[a, b]`.
+    #[error("expected a range of the form \"[<rfc3339>, <rfc3339>{0}\"")]
+    Format(char),
+
+    /// A bound is not a valid RFC3339 timestamp.
+    #[error("invalid RFC3339 timestamp {value:?}: {reason}")]
+    InvalidTimestamp {
+        /// The bound that failed to parse.
+        value: String,
+        /// The reason it failed to parse.
+        reason: String,
+    },
+
+    /// A bound cannot be represented as nanoseconds since the epoch.
+    #[error("timestamp {0:?} is out of the representable range")]
+    OutOfRange(String),
+
+    /// The minimum of a [`TimestampMinMax`] is greater than the maximum.
+    #[error("range minimum is greater than the maximum")]
+    Inverted,
+}
+
+/// Parse the RFC3339 timestamp bounds of a range of the form `[a, b<close>`
+/// into nanoseconds since the epoch.
+fn parse_timestamp_bounds(s: &str, close: char) -> Result<(i64, i64), TimestampRangeParseError> {
+    let (a, b) = s
+        .trim()
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(close))
+        .and_then(|s| s.split_once(','))
+        .ok_or(TimestampRangeParseError::Format(close))?;
+
+    let parse = |v: &str| {
+        let v = v.trim();
+        iox_time::Time::from_rfc3339(v)
+            .map_err(|e| TimestampRangeParseError::InvalidTimestamp {
+                value: v.to_string(),
+                reason: e.to_string(),
+            })?
+            .date_time()
+            .timestamp_nanos_opt()
+            .ok_or_else(|| TimestampRangeParseError::OutOfRange(v.to_string()))
+    };
+
+    Ok((parse(a)?, parse(b)?))
 }
 
 /// FileRange describes a range of files by the min/max time and the sum of their capacities.
@@ -1786,6 +1971,7 @@ pub struct FileRange {
 mod tests {
     use super::*;
 
+    use assert_matches::assert_matches;
     use std::borrow::Cow;
 
     use ordered_float::OrderedFloat;
@@ -2712,6 +2898,109 @@ mod tests {
         assert!(!TimestampMinMax::new(201, 300).overlaps(range));
     }
 
+    #[test]
+    fn test_timestamp_range_algebra() {
+        let range = TimestampRange::new(100, 200);
+
+        assert_eq!(
+            range.intersection(&TimestampRange::new(150, 300)),
+            Some(TimestampRange::new(150, 200))
+        );
+        assert_eq!(
+            range.intersection(&TimestampRange::new(0, 101)),
+            Some(TimestampRange::new(100, 101))
+        );
+        assert_eq!(range.intersection(&TimestampRange::new(200, 300)), None);
+        assert_eq!(range.intersection(&TimestampRange::new(150, 150)), None);
+
+        assert_eq!(
+            range.union_hull(&TimestampRange::new(300, 400)),
+            TimestampRange::new(100, 400)
+        );
+        assert_eq!(
+            range.union_hull(&TimestampRange::new(0, 150)),
+            TimestampRange::new(0, 200)
+        );
+        // Empty ranges contribute no timestamps.
+        assert_eq!(range.union_hull(&TimestampRange::new(500, 500)), range);
+        assert_eq!(TimestampRange::new(0, 0).union_hull(&range), range);
+
+        assert!(range.contains_range(&range));
+        assert!(range.contains_range(&TimestampRange::new(150, 200)));
+        assert!(range.contains_range(&TimestampRange::new(500, 500)));
+        assert!(!range.contains_range(&TimestampRange::new(150, 201)));
+        assert!(!range.contains_range(&TimestampRange::new(99, 150)));
+
+        assert!(!range.is_empty());
+        assert!(TimestampRange::new(100, 100).is_empty());
+    }
+
+    #[test]
+    fn test_timestamp_min_max_algebra() {
+        let range = TimestampMinMax::new(100, 200);
+
+        assert_eq!(
+            range.intersection(&TimestampMinMax::new(200, 300)),
+            Some(TimestampMinMax::new(200, 200))
+        );
+        assert_eq!(range.intersection(&TimestampMinMax::new(201, 300)), None);
+        assert_eq!(
+            range.union_hull(&TimestampMinMax::new(300, 400)),
+            TimestampMinMax::new(100, 400)
+        );
+
+        assert!(range.contains_range(&range));
+        assert!(range.contains_range(&TimestampMinMax::new(200, 200)));
+        assert!(!range.contains_range(&TimestampMinMax::new(200, 201)));
+    }
+
+    #[test]
+    fn test_timestamp_range_display_from_str() {
+        let range = TimestampRange::new(1_000_000_001, 86_400_000_000_000);
+        let s = range.to_string();
+        assert_eq!(
+            s,
+            "[1970-01-01T00:00:01.000000001+00:00, 1970-01-02T00:00:00+00:00)"
+        );
+        assert_eq!(s.parse::<TimestampRange>().unwrap(), range);
+
+        let all = TimestampRange::new(MIN_NANO_TIME, i64::MAX);
+        assert_eq!(all.to_string().parse::<TimestampRange>().unwrap(), all);
+
+        let range = TimestampMinMax::new(0, 1);
+        let s = range.to_string();
+        assert_eq!(
+            s,
+            "[1970-01-01T00:00:00+00:00, 1970-01-01T00:00:00.000000001+00:00]"
+        );
+        assert_eq!(s.parse::<TimestampMinMax>().unwrap(), range);
+
+        // Other offsets are accepted.
+        assert_eq!(
+            " [1970-01-01T01:00:00+01:00,1970-01-01T00:00:01Z] "
+                .parse::<TimestampMinMax>()
+                .unwrap(),
+            TimestampMinMax::new(0, 1_000_000_000)
+        );
+
+        assert_matches!(
+            "[1970-01-01T00:00:00Z, 1970-01-01T00:00:01Z]".parse::<TimestampRange>(),
+            Err(TimestampRangeParseError::Format(')'))
+        );
+        assert_matches!(
+            "[bananas, 1970-01-01T00:00:01Z]".parse::<TimestampMinMax>(),
+            Err(TimestampRangeParseError::InvalidTimestamp { value, .. }) if value == "bananas"
+        );
+        assert_matches!(
+            "[1970-01-01T00:00:00Z, 3000-01-01T00:00:00Z]".parse::<TimestampMinMax>(),
+            Err(TimestampRangeParseError::OutOfRange(_))
+        );
+        assert_matches!(
+            "[1970-01-01T00:00:01Z, 1970-01-01T00:00:00Z]".parse::<TimestampMinMax>(),
+            Err(TimestampRangeParseError::Inverted)
+        );
+    }
+
     #[test]
     #[should_panic(expected = "expected min (2) <= max (1)")]
     fn test_timestamp_min_max_invalid() {
@@ -7,11 +7,138 @@ use thiserror::Error;
 /// A `RangeInclusive` is a closed interval, covering [1, 64]
 const LENGTH_CONSTRAINT: RangeInclusive<usize> = 1..=64;
 
-/// Allowlist of chars for a [`NamespaceName`] name.
+/// Allowlist of non-alphanumeric chars for a [`NamespaceName`] name.
 ///
 /// '/' | '_' | '-' are utilized by the platforms.
-fn is_allowed(c: char) -> bool {
-    c.is_alphanumeric() || matches!(c, '/' | '_' | '-')
+const DEFAULT_SYMBOLS: &[char] = &['/', '_', '-'];
+
+/// The policy applied by [`NamespaceName::new()`].
+const DEFAULT_POLICY: NamespaceNamePolicy = NamespaceNamePolicy {
+    max_length: *LENGTH_CONSTRAINT.end(),
+    charset: NamespaceNameCharset::Unicode,
+    symbols: Cow::Borrowed(DEFAULT_SYMBOLS),
+    reserved_prefixes: Vec::new(),
+};
+
+/// The alphanumeric characters allowed in a [`NamespaceName`] by a
+/// [`NamespaceNamePolicy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NamespaceNameCharset {
+    /// Any unicode alphanumeric character.
+    #[default]
+    Unicode,
+
+    /// Only ASCII alphanumeric characters.
+    Ascii,
+}
+
+/// Operator-configurable rules a [`NamespaceName`] must satisfy, applied by
+/// [`NamespaceName::new_with_policy()`].
+///
+/// The default policy is the one applied by [`NamespaceName::new()`]: names of
+/// 1 to 64 bytes, containing unicode alphanumeric characters or any of `/`,
+/// `_` and `-`, with no reserved prefixes.
+///
+/// ```rust
+/// # use data_types::{NamespaceName, NamespaceNameCharset, NamespaceNamePolicy};
+/// let policy = NamespaceNamePolicy::default()
+///     .with_max_length(32)
+///     .with_charset(NamespaceNameCharset::Ascii)
+///     .with_reserved_prefix("_internal");
+///
+/// assert!(NamespaceName::new_with_policy("bananas", &policy).is_ok());
+/// assert!(NamespaceName::new_with_policy("_internal_bananas", &policy).is_err());
+/// assert!(NamespaceName::new_with_policy("plátanos", &policy).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceNamePolicy {
+    max_length: usize,
+    charset: NamespaceNameCharset,
+    symbols: Cow<'static, [char]>,
+    reserved_prefixes: Vec<String>,
+}
+
+impl Default for NamespaceNamePolicy {
+    fn default() -> Self {
+        DEFAULT_POLICY
+    }
+}
+
+impl NamespaceNamePolicy {
+    /// Limit names to at most `max_length` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_length` is 0.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        assert!(max_length > 0, "namespace name max length must be non-zero");
+        self.max_length = max_length;
+        self
+    }
+
+    /// Set the alphanumeric characters allowed in names.
+    pub fn with_charset(mut self, charset: NamespaceNameCharset) -> Self {
+        self.charset = charset;
+        self
+    }
+
+    /// Replace the non-alphanumeric characters allowed in names (`/`, `_` and
+    /// `-` by default).
+    pub fn with_symbols(mut self, symbols: impl IntoIterator<Item = char>) -> Self {
+        self.symbols = Cow::Owned(symbols.into_iter().collect());
+        self
+    }
+
+    /// Reject names starting with `prefix`, such as those reserved for
+    /// internal use.
+    pub fn with_reserved_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.reserved_prefixes.push(prefix.into());
+        self
+    }
+
+    /// The maximum length of a name, in bytes.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Validate `name` against this policy.
+    pub fn validate(&self, name: &str) -> Result<(), NamespaceNameError> {
+        if !(1..=self.max_length).contains(&name.len()) {
+            return Err(NamespaceNameError::LengthConstraint {
+                name: name.to_string(),
+                max: self.max_length,
+            });
+        }
+
+        // Validate the name contains only valid characters.
+        if let Some(bad_char_offset) = name.chars().position(|c| !self.is_allowed(c)) {
+            return Err(NamespaceNameError::BadChars {
+                bad_char_offset,
+                name: name.to_string(),
+            });
+        };
+
+        if let Some(prefix) = self
+            .reserved_prefixes
+            .iter()
+            .find(|prefix| name.starts_with(prefix.as_str()))
+        {
+            return Err(NamespaceNameError::ReservedPrefix {
+                name: name.to_string(),
+                prefix: prefix.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn is_allowed(&self, c: char) -> bool {
+        let alphanumeric = match self.charset {
+            NamespaceNameCharset::Unicode => c.is_alphanumeric(),
+            NamespaceNameCharset::Ascii => c.is_ascii_alphanumeric(),
+        };
+        alphanumeric || self.symbols.contains(&c)
+    }
 }
 
 /// Errors returned when attempting to construct a [`NamespaceName`] from an org
@@ -37,11 +164,13 @@ pub enum NamespaceNameError {
         "namespace name {} length must be between {} and {} characters",
         name,
         LENGTH_CONSTRAINT.start(),
-        LENGTH_CONSTRAINT.end()
+        max
     )]
     LengthConstraint {
         /// The user-provided namespace that failed validation.
         name: String,
+        /// The maximum length allowed by the applied policy.
+        max: usize,
     },
 
     /// The provided namespace name contains an unacceptable character.
@@ -58,6 +187,15 @@ pub enum NamespaceNameError {
         /// The user-provided namespace that failed validation.
         name: String,
     },
+
+    /// The provided namespace name starts with a reserved prefix.
+    #[error("namespace name '{name}' uses reserved prefix '{prefix}'")]
+    ReservedPrefix {
+        /// The user-provided namespace that failed validation.
+        name: String,
+        /// The reserved prefix the name starts with.
+        prefix: String,
+    },
 }
 
 /// A correctly formed namespace name.
@@ -86,25 +224,16 @@ pub struct NamespaceName<'a>(Cow<'a, str>);
 impl<'a> NamespaceName<'a> {
     /// Create a new, valid NamespaceName.
     pub fn new<T: Into<Cow<'a, str>>>(name: T) -> Result<Self, NamespaceNameError> {
-        let name: Cow<'a, str> = name.into();
-
-        if !LENGTH_CONSTRAINT.contains(&name.len()) {
-            return Err(NamespaceNameError::LengthConstraint {
-                name: name.to_string(),
-            });
-        }
-
-        // Validate the name contains only valid characters.
-        //
-        // NOTE: If changing these characters, please update the error message
-        // above.
-        if let Some(bad_char_offset) = name.chars().position(|c| !is_allowed(c)) {
-            return Err(NamespaceNameError::BadChars {
-                bad_char_offset,
-                name: name.to_string(),
-            });
-        };
+        Self::new_with_policy(name, &DEFAULT_POLICY)
+    }
 
+    /// Create a new NamespaceName, valid according to `policy`.
+    pub fn new_with_policy<T: Into<Cow<'a, str>>>(
+        name: T,
+        policy: &NamespaceNamePolicy,
+    ) -> Result<Self, NamespaceNameError> {
+        let name: Cow<'a, str> = name.into();
+        policy.validate(&name)?;
         Ok(Self(name))
     }
 
@@ -283,7 +412,7 @@ mod tests {
 
         assert!(matches!(
             got,
-            NamespaceNameError::LengthConstraint { name: _n }
+            NamespaceNameError::LengthConstraint { name: _n, .. }
         ));
     }
 
@@ -294,7 +423,7 @@ mod tests {
 
         assert!(matches!(
             got,
-            NamespaceNameError::LengthConstraint { name: _n }
+            NamespaceNameError::LengthConstraint { name: _n, .. }
         ));
     }
 
@@ -334,6 +463,91 @@ mod tests {
         assert_eq!(got.to_string() , "namespace name 'my_example\'db' contains invalid character, character number 10 is not whitelisted");
     }
 
+    #[test]
+    fn test_default_policy() {
+        let policy = NamespaceNamePolicy::default();
+        assert_eq!(policy.max_length(), 64);
+        policy
+            .validate("my-example-db_with_underscores/and/fwd/slash/AndCaseSensitive")
+            .unwrap();
+        policy.validate("a_ã_京").unwrap();
+        policy.validate("my_example db").unwrap_err();
+        policy.validate(&"A".repeat(65)).unwrap_err();
+    }
+
+    #[test]
+    fn test_policy_max_length() {
+        let policy = NamespaceNamePolicy::default().with_max_length(7);
+
+        NamespaceName::new_with_policy("bananas", &policy).unwrap();
+        let got = NamespaceName::new_with_policy("platanos", &policy).unwrap_err();
+        assert!(matches!(
+            got,
+            NamespaceNameError::LengthConstraint { max: 7, .. }
+        ));
+        assert_eq!(
+            got.to_string(),
+            "namespace name platanos length must be between 1 and 7 characters"
+        );
+
+        // Longer than the default limit.
+        let policy = NamespaceNamePolicy::default().with_max_length(128);
+        NamespaceName::new_with_policy("A".repeat(128), &policy).unwrap();
+    }
+
+    #[test]
+    fn test_policy_charset() {
+        let policy = NamespaceNamePolicy::default().with_charset(NamespaceNameCharset::Ascii);
+
+        NamespaceName::new_with_policy("my_bananas-42/v1", &policy).unwrap();
+        let got = NamespaceName::new_with_policy("a_ã_京", &policy).unwrap_err();
+        assert!(matches!(
+            got,
+            NamespaceNameError::BadChars {
+                bad_char_offset: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_policy_symbols() {
+        let policy = NamespaceNamePolicy::default().with_symbols(['_', '.']);
+
+        NamespaceName::new_with_policy("my_bananas.v1", &policy).unwrap();
+        let got = NamespaceName::new_with_policy("my-bananas", &policy).unwrap_err();
+        assert!(matches!(
+            got,
+            NamespaceNameError::BadChars {
+                bad_char_offset: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_policy_reserved_prefix() {
+        let policy = NamespaceNamePolicy::default()
+            .with_reserved_prefix("_internal")
+            .with_reserved_prefix("system/");
+
+        NamespaceName::new_with_policy("internal", &policy).unwrap();
+        NamespaceName::new_with_policy("my_internal", &policy).unwrap();
+
+        let got = NamespaceName::new_with_policy("_internal_metrics", &policy).unwrap_err();
+        assert_eq!(
+            got.to_string(),
+            "namespace name '_internal_metrics' uses reserved prefix '_internal'"
+        );
+        assert!(matches!(
+            NamespaceName::new_with_policy("system/bananas", &policy),
+            Err(NamespaceNameError::ReservedPrefix { .. })
+        ));
+
+        // The default policy has no reserved prefixes.
+        NamespaceName::new("_internal_metrics").unwrap();
+    }
+
     #[test]
     fn test_ok_chars() {
         let db =
@@ -1,10 +1,15 @@
 //! Types having to do with partitions.
 
 use super::{ColumnsByName, SortKeyIds, TableId, Timestamp};
+use crate::partition_template::{
+    PARTITION_KEY_DELIMITER, PARTITION_KEY_PART_TRUNCATED, PARTITION_KEY_VALUE_EMPTY_STR,
+    PARTITION_KEY_VALUE_NULL_STR,
+};
 
+use percent_encoding::percent_decode_str;
 use schema::sort::SortKey;
 use sha2::Digest;
-use std::{fmt::Display, sync::Arc};
+use std::{borrow::Cow, fmt::Display, sync::Arc};
 use thiserror::Error;
 
 /// Unique ID for a `Partition` during the transition from catalog-assigned sequential
@@ -201,6 +206,66 @@ impl PartitionKey {
     pub fn as_bytes(&self) -> &[u8] {
         self.0.as_bytes()
     }
+
+    /// Iterate over the decoded parts of this key, split on the
+    /// [`PARTITION_KEY_DELIMITER`].
+    ///
+    /// Each part corresponds to the template part at the same position in the
+    /// partition template that generated this key. Invalid UTF-8 sequences in
+    /// a decoded part are replaced with [`char::REPLACEMENT_CHARACTER`].
+    pub fn parts(&self) -> impl Iterator<Item = PartitionKeyPart<'_>> {
+        self.0
+            .split(PARTITION_KEY_DELIMITER)
+            .map(PartitionKeyPart::decode)
+    }
+
+    /// Returns the decoded part at index `n`, or [`None`] if this key has
+    /// fewer parts.
+    ///
+    /// See [`PartitionKey::parts()`].
+    pub fn nth_part(&self, n: usize) -> Option<PartitionKeyPart<'_>> {
+        self.parts().nth(n)
+    }
+}
+
+/// A single decoded part of a [`PartitionKey`].
+///
+/// See the [`partition_template`] module docs for the encoding of partition
+/// keys.
+///
+/// [`partition_template`]: crate::partition_template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitionKeyPart<'a> {
+    /// The row(s) in this partition had no value for this part.
+    Null,
+
+    /// The decoded value of this part, which may be empty.
+    Value(Cow<'a, str>),
+
+    /// The decoded prefix of a value that was truncated to fit within the
+    /// maximum part length.
+    Truncated(Cow<'a, str>),
+}
+
+impl<'a> PartitionKeyPart<'a> {
+    fn decode(part: &'a str) -> Self {
+        match part {
+            PARTITION_KEY_VALUE_NULL_STR => Self::Null,
+            PARTITION_KEY_VALUE_EMPTY_STR => Self::Value(Cow::Borrowed("")),
+            _ => match part.strip_suffix(PARTITION_KEY_PART_TRUNCATED) {
+                Some(prefix) => Self::Truncated(percent_decode_str(prefix).decode_utf8_lossy()),
+                None => Self::Value(percent_decode_str(part).decode_utf8_lossy()),
+            },
+        }
+    }
+
+    /// Returns the decoded value of this part, or [`None`] if it is NULL.
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            Self::Null => None,
+            Self::Value(v) | Self::Truncated(v) => Some(v),
+        }
+    }
 }
 
 impl Display for PartitionKey {
@@ -637,6 +702,66 @@ pub(crate) mod tests {
         }
     }
 
+    #[test]
+    fn test_partition_key_parts() {
+        let key = PartitionKey::from("2023-06-08|is%7Cnot%21ambiguous|!|^|pl%C3%A1t#");
+
+        assert_eq!(
+            key.parts().collect::<Vec<_>>(),
+            [
+                PartitionKeyPart::Value("2023-06-08".into()),
+                PartitionKeyPart::Value("is|not!ambiguous".into()),
+                PartitionKeyPart::Null,
+                PartitionKeyPart::Value("".into()),
+                PartitionKeyPart::Truncated("plát".into()),
+            ]
+        );
+
+        assert_eq!(key.nth_part(1).unwrap().value(), Some("is|not!ambiguous"));
+        assert_eq!(key.nth_part(2).unwrap().value(), None);
+        assert_eq!(key.nth_part(4).unwrap().value(), Some("plát"));
+        assert_eq!(key.nth_part(5), None);
+
+        // A part needing no decoding borrows from the key.
+        assert_matches!(
+            key.nth_part(0),
+            Some(PartitionKeyPart::Value(Cow::Borrowed(_)))
+        );
+
+        // Invalid encodings do not panic.
+        let key = PartitionKey::from("%FF%|%zz");
+        assert_eq!(
+            key.parts().collect::<Vec<_>>(),
+            [
+                PartitionKeyPart::Value("\u{FFFD}%".into()),
+                PartitionKeyPart::Value("%zz".into()),
+            ]
+        );
+    }
+
+    proptest! {
+        /// Keys rendered by a [`PartitionKeyBuilder`] are decoded to the
+        /// original values.
+        ///
+        /// [`PartitionKeyBuilder`]: crate::partition_template::PartitionKeyBuilder
+        #[test]
+        fn prop_partition_key_parts_round_trip(
+            values in prop::collection::vec(proptest::option::of(".{0,16}"), 1..8),
+        ) {
+            let mut builder = crate::partition_template::PartitionKeyBuilder::default();
+            for v in &values {
+                match v {
+                    Some(v) => builder.push_value(v),
+                    None => builder.push_null(),
+                };
+            }
+            let key = PartitionKey::from(builder.into_string());
+
+            let got = key.parts().map(|v| v.value().map(ToString::to_string)).collect::<Vec<_>>();
+            assert_eq!(got, values);
+        }
+    }
+
     #[test]
     fn test_proto_no_id() {
         use generated_types::influxdata::iox::catalog::v1 as proto;
@@ -22,6 +22,16 @@
 //! with a template consisting of a single part: a YYYY-MM-DD representation of
 //! the time row timestamp.
 //!
+//! ## Template Versions
+//!
+//! Templates specify the version of the template format they are written
+//! in, and parts introduced after the initial format (such as a
+//! [`TemplatePart::Bucket`] using [`BucketHashFunction::XxHash64`]) are only
+//! accepted in templates of a version supporting them, up to
+//! [`PARTITION_TEMPLATE_VERSION`]. Validated templates are stored in their
+//! [canonical form](canonicalize), so that templates differing only in their
+//! representation compare and hash equal.
+//!
 //! ## Partition Key Format
 //!
 //! Should a partition template be used that generates a partition key
@@ -36,9 +46,9 @@
 //! It is an invariant that the resulting partition key derived from a given
 //! template has the same number and ordering of parts.
 //!
-//! If the partition key template references a [`TemplatePart::TagValue`] column
-//! that is not present in the row, a single `!` is inserted, indicating a NULL
-//! template key part. If the value of the part is an empty string (""), a `^`
+//! If the partition key template references a [`TemplatePart::TagValue`] or
+//! [`TemplatePart::FieldValue`] column that is not present in the row, a single
+//! `!` is inserted, indicating a NULL template key part. If the value of the part is an empty string (""), a `^`
 //! is inserted to ensure a non-empty partition key is always generated. Like
 //! the `|` key above, any occurrence of these characters in a user-provided
 //! column value is percent encoded.
@@ -47,6 +57,59 @@
 //! [`build_column_values()`] function can be used to obtain the set of
 //! [`TemplatePart::TagValue`] the key was constructed from.
 //!
+//! The [`lint_strftime()`] function can be used to check a
+//! [`TemplatePart::TimeFormat`] for directives that are unsupported, or that
+//! prevent the resulting partition keys from being reversed.
+//!
+//! ## Field Values
+//!
+//! A [`TemplatePart::FieldValue`] renders the value of a field column as a
+//! string (e.g. `42`, `true` or `-1.5`), and is encoded like a tag value. When
+//! reversed with [`build_column_values()`], the rendered string is returned -
+//! the original field type is not recorded in the partition key. Every
+//! distinct field value creates a separate partition, so only low-cardinality
+//! fields (such as an event type or status code) should be used.
+//!
+//! ## Tag Value Transforms
+//!
+//! A [`TemplatePart::TagValuePrefix`] renders at most the first `n` characters
+//! (graphemes) of a tag value, and a [`TemplatePart::TagValueLowercase`]
+//! renders the lowercase form of a tag value, both encoded like a tag value.
+//! These reduce the cardinality of the partitions generated for a tag
+//! without changing the ingested data.
+//!
+//! When reversed with [`build_column_values()`], a prefix part always yields
+//! [`ColumnValue::Prefix`]. The original case of a lowercase part is lost, so
+//! it cannot be matched against the original value and is skipped.
+//!
+//! ## Default Tag Values
+//!
+//! A [`TemplatePart::TagValueWithDefault`] renders the given default value,
+//! encoded like a tag value, instead of the `!` NULL sentinel when a row does
+//! not contain the tag. This avoids collapsing sparsely tagged data into a
+//! single NULL partition.
+//!
+//! Because a row containing the default value as its tag value renders the
+//! same key part as a row without the tag, a key part equal to the default
+//! value is ambiguous and is skipped by [`build_column_values()`].
+//!
+//! ## Time Buckets
+//!
+//! A [`TemplatePart::TimeBucket`] aligns the [`TIME_COLUMN_NAME`] column to
+//! fixed-width buckets of the given duration, aligned to the Unix epoch, and
+//! renders the (inclusive) start of the bucket using [`TIME_BUCKET_FORMAT`]
+//! (e.g. `2023-03-10T12:00:00Z`). When reversed with [`build_column_values()`],
+//! a [`ColumnValue::Datetime`] spanning the whole bucket is returned.
+//!
+//! The bucket duration must be a whole number of seconds within
+//! [`ALLOWED_TIME_BUCKET_SECONDS`].
+//!
+//! ## Table Name
+//!
+//! A [`TemplatePart::TableName`] renders the name of the table the row belongs
+//! to, encoded like a tag value. As the table name is not a column, it is
+//! skipped by [`build_column_values()`].
+//!
 //! ### Value Truncation
 //!
 //! Partition key parts are limited to, at most, 200 bytes in length
@@ -87,10 +150,14 @@
 //! depending on what they consider a character.
 //!
 //! Therefore this implementation takes the conservative approach of never
-//! splitting code-points (for UTF-8 correctness) nor graphemes for simplicity
-//! and compatibility for the consumer. This may be relaxed in the future to
-//! allow splitting graphemes, but by being conservative we give ourselves this
-//! option - we can't easily do the reverse!
+//! splitting code-points (for UTF-8 correctness) nor, by default, graphemes
+//! for simplicity and compatibility for the consumer.
+//!
+//! Templates may opt in to splitting graphemes (see [`TruncationMode`]) by
+//! setting the `truncate_at_code_point` flag, retaining more of the value for
+//! scripts where most graphemes are composed of several multi-byte
+//! code-points. Code-points are never split, so the truncated value remains a
+//! valid prefix for byte-wise comparisons.
 //!
 //! ## Part Limit & Maximum Key Size
 //!
@@ -114,6 +181,11 @@
 //! These characters are defined in [`ENCODED_PARTITION_KEY_CHARS`] and chosen
 //! due to their low likelihood of occurrence in user-provided column values.
 //!
+//! A [`PartitionKeyBuilder`] applies this encoding (and the above value
+//! truncation) when rendering a key into a reusable buffer, and
+//! [`PartitionKey::parts()`] splits and decodes the parts of a key without
+//! requiring the template that generated it.
+//!
 //! ### Reserved Tag Values
 //!
 //! Reserved tag values that cannot be used:
@@ -130,7 +202,7 @@
 //!          TemplatePart::TimeFormat("%Y"),
 //!          TemplatePart::TagValue("a"),
 //!          TemplatePart::TagValue("b"),
-//!          TemplatePart::Bucket("c", 10)
+//!          TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3)
 //!      ]
 //! ```
 //!
@@ -150,16 +222,20 @@
 //! no reserved characters.
 //!
 //! [percent encoded]: https://url.spec.whatwg.org/#percent-encoded-bytes
+//! [`PartitionKey::parts()`]: crate::PartitionKey::parts
 use std::{
     borrow::Cow,
+    collections::HashMap,
     fmt::{Display, Formatter},
-    ops::Range,
+    hash::Hasher,
+    ops::{Range, RangeInclusive},
     sync::Arc,
+    time::Duration,
 };
 
 use chrono::{
     format::{Numeric, StrftimeItems},
-    DateTime, Days, Months, Utc,
+    DateTime, Days, Months, NaiveDateTime, Utc,
 };
 use generated_types::influxdata::iox::partition_template::v1 as proto;
 use murmur3::murmur3_32;
@@ -167,6 +243,11 @@ use once_cell::sync::Lazy;
 use percent_encoding::{percent_decode_str, AsciiSet, CONTROLS};
 use schema::TIME_COLUMN_NAME;
 use thiserror::Error;
+use twox_hash::XxHash64;
+use unicode_segmentation::UnicodeSegmentation;
+
+mod key_builder;
+pub use key_builder::PartitionKeyBuilder;
 
 /// Reasons a user-specified partition template isn't valid.
 #[derive(Debug, Error)]
@@ -176,6 +257,42 @@ pub enum ValidationError {
     #[error("Custom partition template must have at least one part")]
     NoParts,
 
+    /// The partition template specifies a version newer than
+    /// [`PARTITION_TEMPLATE_VERSION`].
+    #[error(
+        "partition template version {0} is not supported, \
+        the latest supported version is {PARTITION_TEMPLATE_VERSION}"
+    )]
+    UnsupportedVersion(u32),
+
+    /// The partition template uses a feature introduced in a later template
+    /// version than the version it specifies.
+    #[error(
+        "partition template part {index} requires template version {required}, \
+        but the template specifies version {specified}"
+    )]
+    VersionTooLow {
+        /// The index of the part requiring a later version.
+        index: usize,
+        /// The minimum template version supporting the part.
+        required: u32,
+        /// The version specified by the template.
+        specified: u32,
+    },
+
+    /// The partition template truncates key parts at code points, but
+    /// specifies an older version than the version supporting it.
+    #[error(
+        "truncating partition key parts at code points requires template version {required}, \
+        but the template specifies version {specified}"
+    )]
+    TruncationVersionTooLow {
+        /// The minimum template version supporting code point truncation.
+        required: u32,
+        /// The version specified by the template.
+        specified: u32,
+    },
+
     /// The partition template exceeded the maximum allowed number of parts.
     #[error(
         "Custom partition template specified {specified} parts. \
@@ -212,16 +329,148 @@ pub enum ValidationError {
     )]
     InvalidNumberOfBuckets(u32),
 
+    /// The partition template defines a [`Bucket`] part, but the provided
+    /// hash function is not recognised.
+    ///
+    /// [`Bucket`]: [`proto::template_part::Part::Bucket`]
+    #[error("unknown bucket hash function in partition template: {0}")]
+    InvalidBucketHashFunction(i32),
+
     /// The partition template defines a [`TagValue`] or [`Bucket`] part
     /// which repeats a tag name used in another [`TagValue`] or [`Bucket`] part.
-    /// This is not allowed
+    /// This is not allowed, except for a single [`TagValue`] part paired with
+    /// a [`Bucket`] part that sets `pair_with_tag_value`.
     ///
     /// [`TagValue`]: [`proto::template_part::Part::TagValue`]
     /// [`Bucket`]: [`proto::template_part::Part::Bucket`]
     #[error("tag name value cannot be repeated in partition template: {0}")]
     RepeatedTagValue(String),
+
+    /// The partition template defines a [`FieldValue`] part, but the provided
+    /// field name is invalid.
+    ///
+    /// [`FieldValue`]: [`proto::template_part::Part::FieldValue`]
+    #[error("invalid field name value in partition template: {0}")]
+    InvalidFieldValue(String),
+
+    /// The partition template defines a [`FieldValue`] part which repeats a
+    /// column name used in another part. This is not allowed.
+    ///
+    /// [`FieldValue`]: [`proto::template_part::Part::FieldValue`]
+    #[error("field name value cannot be repeated in partition template: {0}")]
+    RepeatedFieldValue(String),
+
+    /// The partition template defines more than one [`TableName`] part.
+    ///
+    /// [`TableName`]: [`proto::template_part::Part::TableName`]
+    #[error("table name cannot be repeated in partition template")]
+    RepeatedTableName,
+
+    /// The partition template defines a [`TimeBucket`] part, but the provided
+    /// bucket duration is invalid.
+    ///
+    /// [`TimeBucket`]: [`proto::template_part::Part::TimeBucket`]
+    #[error(
+        "time bucket duration in partition template must be in range \
+        {ALLOWED_TIME_BUCKET_SECONDS:?} seconds, duration specified: {0}"
+    )]
+    InvalidTimeBucket(u64),
+
+    /// The partition template defines a [`TagValuePrefix`] part, but the
+    /// provided prefix length is invalid.
+    ///
+    /// [`TagValuePrefix`]: [`proto::template_part::Part::TagValuePrefix`]
+    #[error(
+        "tag value prefix length in partition template must be in range \
+        {ALLOWED_TAG_VALUE_PREFIX_LENGTHS:?}, length specified: {0}"
+    )]
+    InvalidTagValuePrefixLength(u32),
+
+    /// The partition template defines a [`TagValueWithDefault`] part, but the
+    /// default value exceeds [`PARTITION_KEY_MAX_PART_LEN`] once encoded.
+    ///
+    /// [`TagValueWithDefault`]: [`proto::template_part::Part::TagValueWithDefault`]
+    #[error("default tag value in partition template is too long: {0}")]
+    InvalidTagValueDefault(String),
+}
+
+/// Reasons a partition key is not valid for a given partition template.
+///
+/// See [`validate_partition_key()`].
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum KeyValidationError {
+    /// The partition key has a different number of parts to the template.
+    #[error("partition key has {actual} parts, but the partition template has {expected}")]
+    PartCount {
+        /// The number of parts in the partition template.
+        expected: usize,
+        /// The number of parts in the partition key.
+        actual: usize,
+    },
+
+    /// A partition key part is empty - all parts must render at least one
+    /// character.
+    #[error("partition key part {index} is empty")]
+    EmptyPart {
+        /// The index of the invalid part.
+        index: usize,
+    },
+
+    /// A partition key part exceeds [`PARTITION_KEY_MAX_PART_LEN`].
+    #[error(
+        "partition key part {index} is {len} bytes, exceeding the maximum of \
+        {PARTITION_KEY_MAX_PART_LEN} bytes"
+    )]
+    PartTooLong {
+        /// The index of the invalid part.
+        index: usize,
+        /// The length of the part in bytes.
+        len: usize,
+    },
+
+    /// A partition key part contains characters that should have been
+    /// percent encoded, or an invalid percent encoding.
+    #[error("partition key part {index} is not correctly encoded: {part}")]
+    InvalidEncoding {
+        /// The index of the invalid part.
+        index: usize,
+        /// The invalid part.
+        part: String,
+    },
+
+    /// A bucket partition key part is not a bucket ID in the range of the
+    /// template part's number of buckets.
+    #[error(
+        "partition key part {index} is not a valid bucket ID for {num_buckets} buckets: {part}"
+    )]
+    InvalidBucket {
+        /// The index of the invalid part.
+        index: usize,
+        /// The invalid part.
+        part: String,
+        /// The number of buckets in the template part.
+        num_buckets: u32,
+    },
+
+    /// A time partition key part cannot be parsed using the template part.
+    #[error("partition key part {index} does not match the time template part {template}: {part}")]
+    InvalidTime {
+        /// The index of the invalid part.
+        index: usize,
+        /// The invalid part.
+        part: String,
+        /// A description of the time template part.
+        template: String,
+    },
 }
 
+/// The latest partition template format version supported.
+///
+/// Template features introduced after the initial format (version 0) are only
+/// accepted in templates specifying a version that supports them, allowing
+/// older readers to reject templates they cannot interpret correctly.
+pub const PARTITION_TEMPLATE_VERSION: u32 = 1;
+
 /// The maximum number of template parts a custom partition template may specify, to limit the
 /// amount of space in the catalog used by the custom partition template and the partition keys
 /// created with it.
@@ -269,6 +518,26 @@ pub const ALLOWED_BUCKET_QUANTITIES: Range<u32> = Range {
     end: 100_000,
 };
 
+/// The range of durations, in seconds, allowed for [`TimeBucket`] template
+/// parts.
+///
+/// The upper bound is the largest whole number of seconds representable as
+/// nanoseconds in an [`i64`].
+///
+/// [`TimeBucket`]: [`proto::template_part::Part::TimeBucket`]
+pub const ALLOWED_TIME_BUCKET_SECONDS: RangeInclusive<u64> = 1..=(i64::MAX as u64 / 1_000_000_000);
+
+/// The range of prefix lengths allowed for [`TagValuePrefix`] template parts.
+///
+/// [`TagValuePrefix`]: [`proto::template_part::Part::TagValuePrefix`]
+pub const ALLOWED_TAG_VALUE_PREFIX_LENGTHS: RangeInclusive<u32> =
+    1..=(PARTITION_KEY_MAX_PART_LEN as u32);
+
+/// The format used to render the start of a [`TimeBucket`] in a partition key.
+///
+/// [`TimeBucket`]: [`TemplatePart::TimeBucket`]
+pub const TIME_BUCKET_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
 /// The minimal set of characters that must be encoded during partition key
 /// generation when they form part of a partition key part, in order to be
 /// unambiguously reversible.
@@ -298,9 +567,44 @@ pub enum TemplatePart<'a> {
     /// A bucketing partition part.
     ///
     /// Specifies the name of the tag column used to derive which of the `n`
-    /// buckets the data belongs in, through the mechanism implemented by the
-    /// [`bucket_for_tag_value`] function.
-    Bucket(&'a str, u32),
+    /// buckets the data belongs in, and the [`BucketHashFunction`] used to
+    /// assign tag values to buckets.
+    Bucket(&'a str, u32, BucketHashFunction),
+
+    /// A field-value partition part.
+    ///
+    /// Specifies the name of the field column, the value of which is rendered
+    /// as a string.
+    FieldValue(&'a str),
+
+    /// A table name partition part.
+    ///
+    /// Renders the name of the table the row belongs to.
+    TableName,
+
+    /// A fixed-width time bucketing partition part.
+    ///
+    /// Specifies the width of the buckets the [`TIME_COLUMN_NAME`] column is
+    /// aligned to.
+    TimeBucket(Duration),
+
+    /// A tag-value prefix partition part.
+    ///
+    /// Specifies the name of the tag column, and the number of characters of
+    /// the tag value retained.
+    TagValuePrefix(&'a str, u32),
+
+    /// A lowercase tag-value partition part.
+    ///
+    /// Specifies the name of the tag column, the value of which is rendered in
+    /// lowercase.
+    TagValueLowercase(&'a str),
+
+    /// A tag-value partition part with a default value.
+    ///
+    /// Specifies the name of the tag column, and the value rendered when a row
+    /// does not contain the tag.
+    TagValueWithDefault(&'a str, &'a str),
 }
 
 /// The default partitioning scheme is by each day according to the "time" column.
@@ -311,6 +615,7 @@ pub static PARTITION_BY_DAY_PROTO: Lazy<Arc<proto::PartitionTemplate>> = Lazy::n
                 "%Y-%m-%d".to_owned(),
             )),
         }],
+        ..Default::default()
     })
 });
 
@@ -345,6 +650,157 @@ pub fn bucket_for_tag_value(tag_value: &str, num_buckets: u32) -> u32 {
     (hash & i32::MAX as u32) % num_buckets
 }
 
+/// The hash function used by a [`TemplatePart::Bucket`] to assign tag values
+/// to buckets.
+///
+/// The hash function is recorded in the partition template, so that tag
+/// values are always assigned to the same bucket for the lifetime of the
+/// table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BucketHashFunction {
+    /// The 32 bit murmur3 hash, bucketed as Iceberg would (see
+    /// [`bucket_for_tag_value()`]).
+    #[default]
+    Murmur3,
+
+    /// The 64 bit xxHash (XXH64) hash with a seed of 0, modulo the number of
+    /// buckets.
+    ///
+    /// Useful for matching an existing external sharding scheme.
+    XxHash64,
+}
+
+impl BucketHashFunction {
+    /// Hash bucket the provided tag value to a bucket ID in the range
+    /// `[0,num_buckets)` using this hash function.
+    ///
+    /// # Panics
+    ///
+    /// If `num_buckets` is zero, this will panic (see
+    /// [`bucket_for_tag_value()`]).
+    #[inline(always)]
+    pub fn bucket_for_tag_value(&self, tag_value: &str, num_buckets: u32) -> u32 {
+        match self {
+            Self::Murmur3 => bucket_for_tag_value(tag_value, num_buckets),
+            Self::XxHash64 => {
+                let mut hasher = XxHash64::with_seed(0);
+                hasher.write(tag_value.as_bytes());
+                (hasher.finish() % num_buckets as u64) as u32
+            }
+        }
+    }
+}
+
+impl From<proto::bucket::HashFunction> for BucketHashFunction {
+    fn from(value: proto::bucket::HashFunction) -> Self {
+        match value {
+            proto::bucket::HashFunction::Murmur3 => Self::Murmur3,
+            proto::bucket::HashFunction::Xxhash64 => Self::XxHash64,
+        }
+    }
+}
+
+impl From<BucketHashFunction> for proto::bucket::HashFunction {
+    fn from(value: BucketHashFunction) -> Self {
+        match value {
+            BucketHashFunction::Murmur3 => Self::Murmur3,
+            BucketHashFunction::XxHash64 => Self::Xxhash64,
+        }
+    }
+}
+
+/// The minimum template version supporting
+/// [`proto::PartitionTemplate::truncate_at_code_point`].
+const TRUNCATE_AT_CODE_POINT_VERSION: u32 = 1;
+
+/// Returns the minimum template version supporting `part`.
+fn required_version(part: &proto::template_part::Part) -> u32 {
+    match part {
+        proto::template_part::Part::TagValue(_) | proto::template_part::Part::TimeFormat(_) => 0,
+        proto::template_part::Part::Bucket(bucket) => {
+            if bucket.hash_function() != proto::bucket::HashFunction::Murmur3
+                || bucket.pair_with_tag_value
+            {
+                1
+            } else {
+                0
+            }
+        }
+        proto::template_part::Part::FieldValue(_)
+        | proto::template_part::Part::TableName(_)
+        | proto::template_part::Part::TimeBucket(_)
+        | proto::template_part::Part::TagValuePrefix(_)
+        | proto::template_part::Part::TagValueLowercase(_)
+        | proto::template_part::Part::TagValueWithDefault(_) => 1,
+    }
+}
+
+/// Returns the minimum template version supporting all parts and options of
+/// `template`.
+fn required_template_version(template: &proto::PartitionTemplate) -> u32 {
+    let truncation = if template.truncate_at_code_point {
+        TRUNCATE_AT_CODE_POINT_VERSION
+    } else {
+        0
+    };
+
+    template
+        .parts
+        .iter()
+        .flat_map(|part| part.part.as_ref())
+        .map(required_version)
+        .fold(truncation, u32::max)
+}
+
+/// Return the canonical form of `template`.
+///
+/// Templates that differ only in their representation, and not in the
+/// partition keys they produce, have the same canonical form, and therefore
+/// compare and hash equal. Canonicalisation:
+///
+///   * Removes parts with no value (such as those written by a newer writer
+///     using a part unknown to this reader), which are ignored when
+///     partitioning.
+///   * Raises the version to the minimum version supporting all of the
+///     parts and options of the template. The version is never lowered, as
+///     it records the format the template was written in.
+///
+/// As the template is compared after deserialisation, differences in the
+/// serialised form (such as JSON field ordering) are also irrelevant.
+pub fn canonicalize(mut template: proto::PartitionTemplate) -> proto::PartitionTemplate {
+    template.parts.retain(|part| part.part.is_some());
+    template.version = template.version.max(required_template_version(&template));
+    template
+}
+
+/// The boundaries on which a partition key part exceeding
+/// [`PARTITION_KEY_MAX_PART_LEN`] may be truncated.
+///
+/// See "Value Truncation" in the [module docs](self).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TruncationMode {
+    /// Never split a grapheme, which may be composed of multiple code-points.
+    #[default]
+    Grapheme,
+
+    /// Truncate on any code-point boundary, potentially splitting a grapheme.
+    ///
+    /// Truncated values are valid UTF-8 and remain a byte-wise prefix of the
+    /// original value, but may not be a prefix when compared by "character".
+    CodePoint,
+}
+
+impl TruncationMode {
+    /// Split `s` into the smallest units that are never divided when
+    /// truncating.
+    pub fn segments<'a>(&self, s: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+        match self {
+            Self::Grapheme => Box::new(s.graphemes(true)),
+            Self::CodePoint => Box::new(s.char_indices().map(|(i, c)| &s[i..i + c.len_utf8()])),
+        }
+    }
+}
+
 /// A partition template specified by a namespace record.
 ///
 /// Internally this type is [`None`] when no namespace-level override is
@@ -423,10 +879,29 @@ impl TablePartitionTemplateOverride {
             .map(|part| match part {
                 proto::template_part::Part::TagValue(value) => TemplatePart::TagValue(value),
                 proto::template_part::Part::TimeFormat(fmt) => TemplatePart::TimeFormat(fmt),
-                proto::template_part::Part::Bucket(proto::Bucket {
+                proto::template_part::Part::Bucket(bucket) => TemplatePart::Bucket(
+                    &bucket.tag_name,
+                    bucket.num_buckets,
+                    bucket.hash_function().into(),
+                ),
+                proto::template_part::Part::FieldValue(value) => TemplatePart::FieldValue(value),
+                proto::template_part::Part::TableName(proto::TableName {}) => {
+                    TemplatePart::TableName
+                }
+                proto::template_part::Part::TimeBucket(proto::TimeBucket { duration_seconds }) => {
+                    TemplatePart::TimeBucket(Duration::from_secs(*duration_seconds))
+                }
+                proto::template_part::Part::TagValuePrefix(proto::TagValuePrefix {
                     tag_name,
-                    num_buckets,
-                }) => TemplatePart::Bucket(tag_name, *num_buckets),
+                    length,
+                }) => TemplatePart::TagValuePrefix(tag_name, *length),
+                proto::template_part::Part::TagValueLowercase(value) => {
+                    TemplatePart::TagValueLowercase(value)
+                }
+                proto::template_part::Part::TagValueWithDefault(proto::TagValueWithDefault {
+                    tag_name,
+                    default_value,
+                }) => TemplatePart::TagValueWithDefault(tag_name, default_value),
             })
     }
 
@@ -456,7 +931,38 @@ impl TablePartitionTemplateOverride {
                                         proto::template_part::Part::Bucket(proto::Bucket {
                                             tag_name,
                                             num_buckets: _,
-                                        }) => tag_name.capacity() + std::mem::size_of::<u32>(),
+                                            hash_function: _,
+                                            pair_with_tag_value: _,
+                                        }) => {
+                                            tag_name.capacity()
+                                                + std::mem::size_of::<u32>()
+                                                + std::mem::size_of::<i32>()
+                                                + std::mem::size_of::<bool>()
+                                        }
+                                        proto::template_part::Part::FieldValue(s) => s.capacity(),
+                                        proto::template_part::Part::TableName(
+                                            proto::TableName {},
+                                        ) => 0,
+                                        proto::template_part::Part::TimeBucket(
+                                            proto::TimeBucket {
+                                                duration_seconds: _,
+                                            },
+                                        ) => std::mem::size_of::<u64>(),
+                                        proto::template_part::Part::TagValuePrefix(
+                                            proto::TagValuePrefix {
+                                                tag_name,
+                                                length: _,
+                                            },
+                                        ) => tag_name.capacity() + std::mem::size_of::<u32>(),
+                                        proto::template_part::Part::TagValueLowercase(s) => {
+                                            s.capacity()
+                                        }
+                                        proto::template_part::Part::TagValueWithDefault(
+                                            proto::TagValueWithDefault {
+                                                tag_name,
+                                                default_value,
+                                            },
+                                        ) => tag_name.capacity() + default_value.capacity(),
                                     })
                                     .unwrap_or_default()
                             })
@@ -469,6 +975,329 @@ impl TablePartitionTemplateOverride {
     pub fn as_proto(&self) -> Option<&proto::PartitionTemplate> {
         self.0.as_ref().map(|v| v.inner())
     }
+
+    /// Returns the [`TruncationMode`] used when rendering partition key parts
+    /// for this template.
+    pub fn truncation_mode(&self) -> TruncationMode {
+        match self.as_proto() {
+            Some(proto::PartitionTemplate {
+                truncate_at_code_point: true,
+                ..
+            }) => TruncationMode::CodePoint,
+            _ => TruncationMode::Grapheme,
+        }
+    }
+
+    /// Determine whether partition keys generated by `self` can be safely
+    /// interpreted (see [`build_column_values()`]) using the `other` template.
+    ///
+    /// Keys are compatible when both templates have the same number of parts
+    /// in the same order, each referencing the same columns with the same
+    /// configuration. A [`TemplatePart::TimeBucket`] may be interpreted by a
+    /// wider bucket, as the reversed time range is then a superset of the
+    /// range the key was generated for.
+    pub fn is_compatible_with(&self, other: &Self) -> CompatibilityReport {
+        let this_len = self.len();
+        let other_len = other.len();
+        if this_len != other_len {
+            return CompatibilityReport {
+                incompatibilities: vec![Incompatibility::PartCount {
+                    this: this_len,
+                    other: other_len,
+                }],
+            };
+        }
+
+        let incompatibilities = self
+            .parts()
+            .zip(other.parts())
+            .enumerate()
+            .filter_map(|(index, (this, other))| match (&this, &other) {
+                (TemplatePart::TimeBucket(a), TemplatePart::TimeBucket(b)) if b < a => {
+                    Some(Incompatibility::TimeGranularity {
+                        index,
+                        this: *a,
+                        other: *b,
+                    })
+                }
+                (TemplatePart::TimeBucket(_), TemplatePart::TimeBucket(_)) => None,
+                (TemplatePart::TagValue(a), TemplatePart::TagValue(b))
+                | (TemplatePart::TimeFormat(a), TemplatePart::TimeFormat(b))
+                | (TemplatePart::FieldValue(a), TemplatePart::FieldValue(b))
+                    if a == b =>
+                {
+                    None
+                }
+                (TemplatePart::Bucket(a, n, x), TemplatePart::Bucket(b, m, y))
+                    if a == b && n == m && x == y =>
+                {
+                    None
+                }
+                (TemplatePart::TagValuePrefix(a, n), TemplatePart::TagValuePrefix(b, m))
+                    if a == b && n == m =>
+                {
+                    None
+                }
+                (TemplatePart::TagValueLowercase(a), TemplatePart::TagValueLowercase(b))
+                    if a == b =>
+                {
+                    None
+                }
+                (
+                    TemplatePart::TagValueWithDefault(a, x),
+                    TemplatePart::TagValueWithDefault(b, y),
+                ) if a == b && x == y => None,
+                (TemplatePart::TableName, TemplatePart::TableName) => None,
+                _ => Some(Incompatibility::PartMismatch {
+                    index,
+                    this: format!("{this:?}"),
+                    other: format!("{other:?}"),
+                }),
+            })
+            .collect();
+
+        CompatibilityReport { incompatibilities }
+    }
+
+    /// Estimate the maximum number of partitions this template generates for
+    /// data described by `stats`.
+    ///
+    /// The estimate is an upper bound assuming every combination of column
+    /// values occurs - each column part contributes its distinct count (plus
+    /// one for rows without a value), and each time part the number of
+    /// distinct values rendered across the time range of `stats`. Columns
+    /// without a distinct count estimate are assumed to have a single value.
+    pub fn estimate_max_partitions(&self, stats: &ColumnStats) -> u64 {
+        self.parts()
+            .map(|part| match part {
+                TemplatePart::TagValue(col)
+                | TemplatePart::FieldValue(col)
+                | TemplatePart::TagValuePrefix(col, _)
+                | TemplatePart::TagValueLowercase(col)
+                | TemplatePart::TagValueWithDefault(col, _) => {
+                    stats.distinct_count(col).saturating_add(1)
+                }
+                TemplatePart::Bucket(col, num_buckets, _) => stats
+                    .distinct_count(col)
+                    .min(num_buckets as u64)
+                    .saturating_add(1),
+                TemplatePart::TableName => 1,
+                TemplatePart::TimeFormat(format) => {
+                    strftime_granularity(format).count_intervals(&stats.time_range)
+                }
+                TemplatePart::TimeBucket(width) => {
+                    let width = i64::try_from(width.as_nanos()).unwrap_or(i64::MAX);
+                    TimeGranularity::Fixed(width).count_intervals(&stats.time_range)
+                }
+            })
+            .fold(1_u64, |acc, v| acc.saturating_mul(v))
+    }
+}
+
+/// Estimates describing the data written to a table, used to estimate the
+/// number of partitions a template generates.
+///
+/// See [`TablePartitionTemplateOverride::estimate_max_partitions()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStats {
+    time_range: Range<DateTime<Utc>>,
+    distinct_counts: HashMap<String, u64>,
+}
+
+impl ColumnStats {
+    /// Initialise [`ColumnStats`] for data with timestamps within
+    /// `time_range`.
+    pub fn new(time_range: Range<DateTime<Utc>>) -> Self {
+        Self {
+            time_range,
+            distinct_counts: HashMap::new(),
+        }
+    }
+
+    /// Set the estimated number of distinct values in `column`.
+    pub fn with_distinct_count(mut self, column: impl Into<String>, count: u64) -> Self {
+        self.distinct_counts.insert(column.into(), count);
+        self
+    }
+
+    fn distinct_count(&self, column: &str) -> u64 {
+        self.distinct_counts.get(column).copied().unwrap_or(1)
+    }
+}
+
+/// The finest unit of time a strftime-like format renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeGranularity {
+    /// The format renders the same value for all timestamps.
+    Constant,
+    Years,
+    Months,
+    /// Fixed-width units of the given number of nanoseconds.
+    Fixed(i64),
+}
+
+impl TimeGranularity {
+    /// Returns the finer of `self` and `other`.
+    fn finer(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Fixed(a), Self::Fixed(b)) => Self::Fixed(a.min(b)),
+            (v @ Self::Fixed(_), _) | (_, v @ Self::Fixed(_)) => v,
+            (Self::Months, _) | (_, Self::Months) => Self::Months,
+            (Self::Years, _) | (_, Self::Years) => Self::Years,
+            (Self::Constant, Self::Constant) => Self::Constant,
+        }
+    }
+
+    /// The number of distinct units overlapping `range`.
+    fn count_intervals(&self, range: &Range<DateTime<Utc>>) -> u64 {
+        use chrono::Datelike;
+
+        let begin = range.start;
+        // The inclusive end of the range, treating an empty range as a
+        // single instant.
+        let last = (range.end - chrono::Duration::nanoseconds(1)).max(begin);
+
+        let count = match self {
+            Self::Constant => 0,
+            Self::Years => i128::from(last.year()) - i128::from(begin.year()),
+            Self::Months => {
+                let months = |v: DateTime<Utc>| i128::from(v.year()) * 12 + i128::from(v.month0());
+                months(last) - months(begin)
+            }
+            Self::Fixed(width) => {
+                let nanos = |v: DateTime<Utc>| {
+                    i128::from(v.timestamp()) * 1_000_000_000
+                        + i128::from(v.timestamp_subsec_nanos())
+                };
+                let width = i128::from(*width).max(1);
+                nanos(last).div_euclid(width) - nanos(begin).div_euclid(width)
+            }
+        };
+
+        u64::try_from(count).unwrap_or(u64::MAX).saturating_add(1)
+    }
+}
+
+/// Determine the finest unit of time rendered by the strftime-like `format`.
+///
+/// Directives that do not map to a calendar unit are approximated by a finer
+/// unit, so the resulting estimate remains an upper bound.
+fn strftime_granularity(format: &str) -> TimeGranularity {
+    use chrono::format::{Fixed, Item};
+
+    const SECOND: i64 = 1_000_000_000;
+    const HOUR: i64 = 60 * 60 * SECOND;
+    const DAY: i64 = 24 * HOUR;
+
+    StrftimeItems::new(format)
+        .map(|item| match item {
+            Item::Literal(_) | Item::OwnedLiteral(_) | Item::Space(_) | Item::OwnedSpace(_) => {
+                TimeGranularity::Constant
+            }
+            Item::Numeric(
+                Numeric::Year
+                | Numeric::YearDiv100
+                | Numeric::YearMod100
+                | Numeric::IsoYear
+                | Numeric::IsoYearDiv100
+                | Numeric::IsoYearMod100,
+                _,
+            ) => TimeGranularity::Years,
+            Item::Numeric(Numeric::Month, _)
+            | Item::Fixed(Fixed::ShortMonthName | Fixed::LongMonthName) => TimeGranularity::Months,
+            // Weeks are not aligned to the Unix epoch, so are approximated as
+            // days.
+            Item::Numeric(
+                Numeric::Day
+                | Numeric::Ordinal
+                | Numeric::WeekdayFromMon
+                | Numeric::NumDaysFromSun
+                | Numeric::WeekFromSun
+                | Numeric::WeekFromMon
+                | Numeric::IsoWeek,
+                _,
+            )
+            | Item::Fixed(Fixed::ShortWeekdayName | Fixed::LongWeekdayName) => {
+                TimeGranularity::Fixed(DAY)
+            }
+            Item::Fixed(Fixed::LowerAmPm | Fixed::UpperAmPm) => TimeGranularity::Fixed(12 * HOUR),
+            Item::Numeric(Numeric::Hour | Numeric::Hour12, _) => TimeGranularity::Fixed(HOUR),
+            Item::Numeric(Numeric::Minute, _) => TimeGranularity::Fixed(60 * SECOND),
+            Item::Numeric(Numeric::Second | Numeric::Timestamp, _)
+            | Item::Fixed(Fixed::RFC2822) => TimeGranularity::Fixed(SECOND),
+            Item::Fixed(Fixed::Nanosecond3) => TimeGranularity::Fixed(1_000_000),
+            Item::Fixed(Fixed::Nanosecond6) => TimeGranularity::Fixed(1_000),
+            Item::Fixed(
+                Fixed::TimezoneName
+                | Fixed::TimezoneOffset
+                | Fixed::TimezoneOffsetColon
+                | Fixed::TimezoneOffsetDoubleColon
+                | Fixed::TimezoneOffsetTripleColon
+                | Fixed::TimezoneOffsetZ
+                | Fixed::TimezoneOffsetColonZ,
+            ) => TimeGranularity::Constant,
+            // Anything else (including nanoseconds) is assumed to render a
+            // distinct value for every timestamp.
+            _ => TimeGranularity::Fixed(1),
+        })
+        .fold(TimeGranularity::Constant, TimeGranularity::finer)
+}
+
+/// The result of checking whether partition keys generated by one template can
+/// be interpreted by another.
+///
+/// See [`TablePartitionTemplateOverride::is_compatible_with()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    incompatibilities: Vec<Incompatibility>,
+}
+
+impl CompatibilityReport {
+    /// Returns true if keys can be safely interpreted under the other
+    /// template.
+    pub fn is_compatible(&self) -> bool {
+        self.incompatibilities.is_empty()
+    }
+
+    /// The reasons the templates are not compatible, if any.
+    pub fn incompatibilities(&self) -> &[Incompatibility] {
+        &self.incompatibilities
+    }
+}
+
+/// A reason partition keys generated by one template cannot be interpreted by
+/// another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Incompatibility {
+    /// The templates have a different number of parts.
+    PartCount {
+        /// The number of parts in the template the keys were generated by.
+        this: usize,
+        /// The number of parts in the template interpreting the keys.
+        other: usize,
+    },
+
+    /// The parts at `index` reference different columns, or are configured
+    /// differently.
+    PartMismatch {
+        /// The index of the mismatched part.
+        index: usize,
+        /// A description of the part the keys were generated by.
+        this: String,
+        /// A description of the part interpreting the keys.
+        other: String,
+    },
+
+    /// The [`TemplatePart::TimeBucket`] at `index` interpreting the keys is
+    /// narrower than the bucket the keys were generated by.
+    TimeGranularity {
+        /// The index of the mismatched part.
+        index: usize,
+        /// The bucket width the keys were generated by.
+        this: Duration,
+        /// The bucket width interpreting the keys.
+        other: Duration,
+    },
 }
 
 /// Display the serde_json representation so that the output
@@ -501,11 +1330,14 @@ impl TryFrom<Option<proto::PartitionTemplate>> for TablePartitionTemplateOverrid
 /// duplication.
 mod serialization {
     use super::{
-        ValidationError, ALLOWED_BUCKET_QUANTITIES, MAXIMUM_NUMBER_OF_TEMPLATE_PARTS,
-        TAG_VALUE_KEY_TIME,
+        canonicalize, required_version, ValidationError, ALLOWED_BUCKET_QUANTITIES,
+        ALLOWED_TAG_VALUE_PREFIX_LENGTHS, ALLOWED_TIME_BUCKET_SECONDS, ENCODED_PARTITION_KEY_CHARS,
+        MAXIMUM_NUMBER_OF_TEMPLATE_PARTS, PARTITION_KEY_MAX_PART_LEN, PARTITION_TEMPLATE_VERSION,
+        TAG_VALUE_KEY_TIME, TRUNCATE_AT_CODE_POINT_VERSION,
     };
     use chrono::{format::StrftimeItems, Utc};
     use generated_types::influxdata::iox::partition_template::v1 as proto;
+    use percent_encoding::utf8_percent_encode;
     use std::{collections::HashSet, fmt::Write, sync::Arc};
 
     #[derive(Debug, Clone, PartialEq, Hash)]
@@ -551,11 +1383,41 @@ mod serialization {
                 return Err(ValidationError::TooManyParts { specified });
             }
 
+            // The version must be understood by this reader.
+            let version = partition_template.version;
+            if version > PARTITION_TEMPLATE_VERSION {
+                return Err(ValidationError::UnsupportedVersion(version));
+            }
+            if partition_template.truncate_at_code_point && TRUNCATE_AT_CODE_POINT_VERSION > version
+            {
+                return Err(ValidationError::TruncationVersionTooLow {
+                    required: TRUNCATE_AT_CODE_POINT_VERSION,
+                    specified: version,
+                });
+            }
+
             let mut seen_tags: HashSet<&str> = HashSet::with_capacity(specified);
+            let mut seen_table_name = false;
+
+            // Tags rendered by TagValue parts, and tags of Bucket parts that
+            // may share their tag with a TagValue part.
+            let mut tag_value_tags: HashSet<&str> = HashSet::new();
+            let mut paired_bucket_tags: HashSet<&str> = HashSet::new();
 
             // All time formats must be valid and tag values may not specify any
             // restricted values.
-            for part in &partition_template.parts {
+            for (index, part) in partition_template.parts.iter().enumerate() {
+                // Parts may only use features supported by the template version.
+                if let Some(required) = part.part.as_ref().map(required_version) {
+                    if required > version {
+                        return Err(ValidationError::VersionTooLow {
+                            index,
+                            required,
+                            specified: version,
+                        });
+                    }
+                }
+
                 match &part.part {
                     Some(proto::template_part::Part::TimeFormat(fmt)) => {
                         // Empty is not a valid time format
@@ -599,10 +1461,13 @@ mod serialization {
                         if !seen_tags.insert(value.as_str()) {
                             return Err(ValidationError::RepeatedTagValue(value.into()));
                         }
+                        tag_value_tags.insert(value.as_str());
                     }
                     Some(proto::template_part::Part::Bucket(proto::Bucket {
                         tag_name,
                         num_buckets,
+                        hash_function,
+                        pair_with_tag_value,
                     })) => {
                         if tag_name.is_empty() {
                             return Err(ValidationError::InvalidTagValue(tag_name.into()));
@@ -614,38 +1479,157 @@ mod serialization {
                             )));
                         }
 
-                        if !seen_tags.insert(tag_name.as_str()) {
+                        // A paired bucket is checked against the other parts
+                        // once all the parts have been seen.
+                        let seen = if *pair_with_tag_value {
+                            &mut paired_bucket_tags
+                        } else {
+                            &mut seen_tags
+                        };
+                        if !seen.insert(tag_name.as_str()) {
                             return Err(ValidationError::RepeatedTagValue(tag_name.into()));
                         }
 
                         if !ALLOWED_BUCKET_QUANTITIES.contains(num_buckets) {
                             return Err(ValidationError::InvalidNumberOfBuckets(*num_buckets));
                         }
+
+                        if proto::bucket::HashFunction::try_from(*hash_function).is_err() {
+                            return Err(ValidationError::InvalidBucketHashFunction(*hash_function));
+                        }
                     }
-                    None => {}
-                }
-            }
+                    Some(proto::template_part::Part::FieldValue(value)) => {
+                        // Empty is not a valid field name
+                        if value.is_empty() {
+                            return Err(ValidationError::InvalidFieldValue(value.into()));
+                        }
 
-            Ok(Self(Arc::new(partition_template)))
-        }
-    }
+                        if value == TAG_VALUE_KEY_TIME {
+                            return Err(ValidationError::InvalidFieldValue(format!(
+                                "{TAG_VALUE_KEY_TIME} cannot be used"
+                            )));
+                        }
 
-    impl<DB> sqlx::Type<DB> for Wrapper
-    where
-        sqlx::types::Json<Self>: sqlx::Type<DB>,
-        DB: sqlx::Database,
-    {
-        fn type_info() -> DB::TypeInfo {
-            <sqlx::types::Json<Self> as sqlx::Type<DB>>::type_info()
-        }
-    }
+                        // A column is either a tag or a field, so the name may
+                        // not be used by any other part.
+                        if !seen_tags.insert(value.as_str()) {
+                            return Err(ValidationError::RepeatedFieldValue(value.into()));
+                        }
+                    }
+                    Some(proto::template_part::Part::TableName(proto::TableName {})) => {
+                        if seen_table_name {
+                            return Err(ValidationError::RepeatedTableName);
+                        }
+                        seen_table_name = true;
+                    }
+                    Some(proto::template_part::Part::TagValuePrefix(proto::TagValuePrefix {
+                        tag_name,
+                        length,
+                    })) => {
+                        if tag_name.is_empty() {
+                            return Err(ValidationError::InvalidTagValue(tag_name.into()));
+                        }
 
-    impl<'q, DB> sqlx::Encode<'q, DB> for Wrapper
-    where
-        DB: sqlx::Database,
-        for<'b> sqlx::types::Json<&'b proto::PartitionTemplate>: sqlx::Encode<'q, DB>,
-    {
-        fn encode_by_ref(
+                        if tag_name.contains(TAG_VALUE_KEY_TIME) {
+                            return Err(ValidationError::InvalidTagValue(format!(
+                                "{TAG_VALUE_KEY_TIME} cannot be used"
+                            )));
+                        }
+
+                        if !seen_tags.insert(tag_name.as_str()) {
+                            return Err(ValidationError::RepeatedTagValue(tag_name.into()));
+                        }
+
+                        if !ALLOWED_TAG_VALUE_PREFIX_LENGTHS.contains(length) {
+                            return Err(ValidationError::InvalidTagValuePrefixLength(*length));
+                        }
+                    }
+                    Some(proto::template_part::Part::TagValueLowercase(value)) => {
+                        if value.is_empty() {
+                            return Err(ValidationError::InvalidTagValue(value.into()));
+                        }
+
+                        if value.contains(TAG_VALUE_KEY_TIME) {
+                            return Err(ValidationError::InvalidTagValue(format!(
+                                "{TAG_VALUE_KEY_TIME} cannot be used"
+                            )));
+                        }
+
+                        if !seen_tags.insert(value.as_str()) {
+                            return Err(ValidationError::RepeatedTagValue(value.into()));
+                        }
+                    }
+                    Some(proto::template_part::Part::TagValueWithDefault(
+                        proto::TagValueWithDefault {
+                            tag_name,
+                            default_value,
+                        },
+                    )) => {
+                        if tag_name.is_empty() {
+                            return Err(ValidationError::InvalidTagValue(tag_name.into()));
+                        }
+
+                        if tag_name.contains(TAG_VALUE_KEY_TIME) {
+                            return Err(ValidationError::InvalidTagValue(format!(
+                                "{TAG_VALUE_KEY_TIME} cannot be used"
+                            )));
+                        }
+
+                        if !seen_tags.insert(tag_name.as_str()) {
+                            return Err(ValidationError::RepeatedTagValue(tag_name.into()));
+                        }
+
+                        // The default must never be truncated, otherwise it
+                        // cannot be identified when reversing a key.
+                        let encoded_len =
+                            utf8_percent_encode(default_value, &ENCODED_PARTITION_KEY_CHARS)
+                                .map(str::len)
+                                .sum::<usize>();
+                        if encoded_len > PARTITION_KEY_MAX_PART_LEN {
+                            return Err(ValidationError::InvalidTagValueDefault(
+                                default_value.into(),
+                            ));
+                        }
+                    }
+                    Some(proto::template_part::Part::TimeBucket(proto::TimeBucket {
+                        duration_seconds,
+                    })) => {
+                        if !ALLOWED_TIME_BUCKET_SECONDS.contains(duration_seconds) {
+                            return Err(ValidationError::InvalidTimeBucket(*duration_seconds));
+                        }
+                    }
+                    None => {}
+                }
+            }
+
+            // A paired bucket may only share its tag with a single TagValue
+            // part.
+            for tag_name in paired_bucket_tags {
+                if seen_tags.contains(tag_name) && !tag_value_tags.contains(tag_name) {
+                    return Err(ValidationError::RepeatedTagValue(tag_name.into()));
+                }
+            }
+
+            Ok(Self(Arc::new(canonicalize(partition_template))))
+        }
+    }
+
+    impl<DB> sqlx::Type<DB> for Wrapper
+    where
+        sqlx::types::Json<Self>: sqlx::Type<DB>,
+        DB: sqlx::Database,
+    {
+        fn type_info() -> DB::TypeInfo {
+            <sqlx::types::Json<Self> as sqlx::Type<DB>>::type_info()
+        }
+    }
+
+    impl<'q, DB> sqlx::Encode<'q, DB> for Wrapper
+    where
+        DB: sqlx::Database,
+        for<'b> sqlx::types::Json<&'b proto::PartitionTemplate>: sqlx::Encode<'q, DB>,
+    {
+        fn encode_by_ref(
             &self,
             buf: &mut <DB as sqlx::database::HasArguments<'q>>::ArgumentBuffer,
         ) -> sqlx::encode::IsNull {
@@ -664,13 +1648,12 @@ mod serialization {
         fn decode(
             value: <DB as sqlx::database::HasValueRef<'q>>::ValueRef,
         ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
-            Ok(Self(
+            Ok(Self(Arc::new(canonicalize(
                 <sqlx::types::Json<proto::PartitionTemplate> as sqlx::Decode<'_, DB>>::decode(
                     value,
                 )?
-                .0
-                .into(),
-            ))
+                .0,
+            ))))
         }
     }
 }
@@ -789,14 +1772,369 @@ pub fn build_column_values<'a>(
                     TemplatePart::TimeFormat(format) => {
                         Some((TIME_COLUMN_NAME, parse_part_time_format(value, format)?))
                     }
-                    TemplatePart::Bucket(col_name, num_buckets) => {
+                    TemplatePart::Bucket(col_name, num_buckets, _) => {
                         Some((col_name, parse_part_bucket(value, num_buckets)?))
                     }
+                    TemplatePart::FieldValue(col_name) => {
+                        Some((col_name, parse_part_tag_value(value)?))
+                    }
+                    // The table name is not a column value.
+                    TemplatePart::TableName => None,
+                    TemplatePart::TimeBucket(duration) => {
+                        Some((TIME_COLUMN_NAME, parse_part_time_bucket(value, duration)?))
+                    }
+                    TemplatePart::TagValuePrefix(col_name, _) => {
+                        Some((col_name, parse_part_tag_value_prefix(value)?))
+                    }
+                    // The original case of the value is not recorded in the
+                    // partition key.
+                    TemplatePart::TagValueLowercase(_) => None,
+                    TemplatePart::TagValueWithDefault(col_name, default) => {
+                        match parse_part_tag_value(value)? {
+                            // The tag may have been missing, or had the
+                            // default as its value.
+                            ColumnValue::Identity(v) if v == default => None,
+                            v => Some((col_name, v)),
+                        }
+                    }
                 }
             }
         })
 }
 
+/// Validate that `partition_key` could have been generated by `template`.
+///
+/// This checks the number of parts, that each part is correctly encoded, that
+/// bucket IDs are within the range of the template part, and that time parts
+/// can be parsed using their template part. Unlike [`build_column_values()`],
+/// this never panics for a malformed key.
+pub fn validate_partition_key(
+    template: &TablePartitionTemplateOverride,
+    partition_key: &str,
+) -> Result<(), KeyValidationError> {
+    let expected = template.len();
+    let actual = partition_key.split(PARTITION_KEY_DELIMITER).count();
+    if expected != actual {
+        return Err(KeyValidationError::PartCount { expected, actual });
+    }
+
+    for (index, (template, value)) in template
+        .parts()
+        .zip(partition_key.split(PARTITION_KEY_DELIMITER))
+        .enumerate()
+    {
+        if value.is_empty() {
+            return Err(KeyValidationError::EmptyPart { index });
+        }
+
+        if value.len() > PARTITION_KEY_MAX_PART_LEN {
+            return Err(KeyValidationError::PartTooLong {
+                index,
+                len: value.len(),
+            });
+        }
+
+        let is_column_part = matches!(
+            template,
+            TemplatePart::TagValue(_)
+                | TemplatePart::Bucket(..)
+                | TemplatePart::FieldValue(_)
+                | TemplatePart::TagValuePrefix(..)
+                | TemplatePart::TagValueLowercase(_)
+        );
+        if value == PARTITION_KEY_VALUE_NULL_STR && is_column_part {
+            continue;
+        }
+
+        let decoded =
+            decode_key_part(value).ok_or_else(|| KeyValidationError::InvalidEncoding {
+                index,
+                part: value.to_string(),
+            })?;
+
+        match template {
+            TemplatePart::TagValue(_)
+            | TemplatePart::FieldValue(_)
+            | TemplatePart::TableName
+            | TemplatePart::TagValuePrefix(..)
+            | TemplatePart::TagValueLowercase(_)
+            | TemplatePart::TagValueWithDefault(..) => {}
+            TemplatePart::Bucket(_, num_buckets, _) => {
+                if !decoded
+                    .parse::<u32>()
+                    .map(|id| id < num_buckets)
+                    .unwrap_or_default()
+                {
+                    return Err(KeyValidationError::InvalidBucket {
+                        index,
+                        part: value.to_string(),
+                        num_buckets,
+                    });
+                }
+            }
+            TemplatePart::TimeFormat(format) => {
+                // A truncated time part cannot be parsed.
+                if value.ends_with(PARTITION_KEY_PART_TRUNCATED) {
+                    continue;
+                }
+
+                let mut parsed = chrono::format::Parsed::new();
+                let decoded = match value {
+                    PARTITION_KEY_VALUE_EMPTY_STR => "",
+                    _ => decoded.as_ref(),
+                };
+                if chrono::format::parse(&mut parsed, decoded, StrftimeItems::new(format)).is_err()
+                {
+                    return Err(KeyValidationError::InvalidTime {
+                        index,
+                        part: value.to_string(),
+                        template: format!("{template:?}"),
+                    });
+                }
+            }
+            TemplatePart::TimeBucket(duration) => {
+                let is_aligned = NaiveDateTime::parse_from_str(&decoded, TIME_BUCKET_FORMAT)
+                    .ok()
+                    .and_then(|v| v.and_utc().timestamp_nanos_opt())
+                    .zip(i64::try_from(duration.as_nanos()).ok())
+                    .map(|(ts, width)| width > 0 && ts.rem_euclid(width) == 0)
+                    .unwrap_or_default();
+                if !is_aligned {
+                    return Err(KeyValidationError::InvalidTime {
+                        index,
+                        part: value.to_string(),
+                        template: format!("{template:?}"),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Decode a non-NULL partition key part, returning [`None`] if it contains
+/// characters that should have been encoded, or an invalid encoding.
+///
+/// The empty string sentinel and truncation marker are not removed.
+fn decode_key_part(value: &str) -> Option<Cow<'_, str>> {
+    let bytes = value.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        let is_marker = (b == PARTITION_KEY_VALUE_EMPTY as u8 && value.len() == 1)
+            || (b == PARTITION_KEY_PART_TRUNCATED as u8 && i == bytes.len() - 1);
+        // The bytes of ENCODED_PARTITION_KEY_CHARS, and non-ASCII bytes.
+        let must_encode = !b.is_ascii()
+            || b.is_ascii_control()
+            || (matches!(
+                b as char,
+                PARTITION_KEY_DELIMITER
+                    | PARTITION_KEY_VALUE_NULL
+                    | PARTITION_KEY_VALUE_EMPTY
+                    | PARTITION_KEY_PART_TRUNCATED
+            ) && !is_marker);
+        if must_encode {
+            return None;
+        }
+
+        // Percent signs must be followed by two hex digits.
+        if b == b'%'
+            && !bytes
+                .get(i + 1..i + 3)
+                .map(|v| v.iter().all(u8::is_ascii_hexdigit))
+                .unwrap_or_default()
+        {
+            return None;
+        }
+    }
+
+    percent_decode_str(value).decode_utf8().ok()
+}
+
+/// A diagnostic describing a problem with a strftime-like format used in a
+/// [`TemplatePart::TimeFormat`].
+///
+/// See [`lint_strftime()`].
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum StrftimeDiagnostic {
+    /// The directive is not supported, and cannot be used to render a
+    /// partition key.
+    #[error("unsupported strftime directive: {directive}")]
+    Unsupported {
+        /// The offending directive.
+        directive: String,
+    },
+
+    /// The directive renders a locale-dependent value (such as a month name)
+    /// which is always rendered in English.
+    #[error("locale-dependent strftime directive: {directive}")]
+    LocaleDependent {
+        /// The offending directive.
+        directive: String,
+    },
+
+    /// The directive cannot be reversed by [`build_column_values()`], so
+    /// partitions generated with it cannot be pruned by time.
+    #[error("strftime directive cannot be reversed: {directive}")]
+    NonReversible {
+        /// The offending directive.
+        directive: String,
+    },
+
+    /// The format is missing a directive needed to reverse it with
+    /// [`build_column_values()`], so partitions generated with it cannot be
+    /// pruned by time.
+    #[error("strftime format cannot be reversed without a {missing} directive")]
+    Incomplete {
+        /// The missing directive.
+        missing: &'static str,
+    },
+}
+
+impl StrftimeDiagnostic {
+    /// Returns true if this diagnostic prevents the format from being used in
+    /// a partition template.
+    pub fn is_error(&self) -> bool {
+        matches!(self, Self::Unsupported { .. })
+    }
+}
+
+/// Walk the directives in the strftime-like `format` and return diagnostics
+/// for any that are unsupported, locale-dependent or prevent the resulting
+/// partition keys from being reversed by [`build_column_values()`].
+///
+/// An empty result indicates the format is fully supported and reversible.
+pub fn lint_strftime(format: &str) -> Vec<StrftimeDiagnostic> {
+    use chrono::format::{Fixed, Item};
+
+    let mut diagnostics = Vec::new();
+    let (mut year, mut month, mut day, mut hour, mut am_pm, mut hour12) = Default::default();
+    let (mut minute, mut second, mut fraction) = Default::default();
+
+    'directives: for directive in strftime_directives(format) {
+        // Chrono will panic during timestamp formatting if this formatter
+        // directive is used!
+        if directive == "%#z" {
+            diagnostics.push(StrftimeDiagnostic::Unsupported {
+                directive: directive.to_string(),
+            });
+            continue;
+        }
+
+        if directive.ends_with(['a', 'A', 'b', 'B', 'h', 'c', 'x', 'X', 'r', 'p', 'P']) {
+            diagnostics.push(StrftimeDiagnostic::LocaleDependent {
+                directive: directive.to_string(),
+            });
+        }
+
+        let mut reversible = true;
+        for item in StrftimeItems::new(directive) {
+            match item {
+                Item::Error => {
+                    diagnostics.push(StrftimeDiagnostic::Unsupported {
+                        directive: directive.to_string(),
+                    });
+                    continue 'directives;
+                }
+                Item::Literal(_) | Item::OwnedLiteral(_) | Item::Space(_) | Item::OwnedSpace(_) => {
+                }
+                Item::Numeric(Numeric::Year, _) => year = true,
+                Item::Numeric(Numeric::Month, _) => month = true,
+                Item::Numeric(Numeric::Day, _) => day = true,
+                Item::Numeric(Numeric::Hour, _) => hour = true,
+                Item::Numeric(Numeric::Hour12, _) => hour12 = true,
+                Item::Numeric(Numeric::Minute, _) => minute = true,
+                Item::Numeric(Numeric::Second, _) => second = true,
+                Item::Fixed(Fixed::ShortMonthName | Fixed::LongMonthName) => month = true,
+                Item::Fixed(Fixed::LowerAmPm | Fixed::UpperAmPm) => am_pm = true,
+                Item::Fixed(Fixed::Nanosecond3 | Fixed::Nanosecond6 | Fixed::Nanosecond9) => {
+                    fraction = true
+                }
+                Item::Fixed(
+                    Fixed::ShortWeekdayName
+                    | Fixed::LongWeekdayName
+                    | Fixed::TimezoneName
+                    | Fixed::TimezoneOffset
+                    | Fixed::TimezoneOffsetColon
+                    | Fixed::TimezoneOffsetDoubleColon
+                    | Fixed::TimezoneOffsetTripleColon
+                    | Fixed::TimezoneOffsetZ
+                    | Fixed::TimezoneOffsetColonZ,
+                ) => {}
+                Item::Numeric(..) | Item::Fixed(_) => reversible = false,
+            }
+        }
+
+        if !reversible {
+            diagnostics.push(StrftimeDiagnostic::NonReversible {
+                directive: directive.to_string(),
+            });
+        }
+    }
+
+    // Mirror the requirements of the implicit defaults applied when reversing
+    // the format - each unit requires all coarser units to be present.
+    let hour = hour || (hour12 && am_pm);
+    let missing = if !year {
+        Some("%Y")
+    } else if day && !month {
+        Some("%m")
+    } else if (hour || hour12) && !day {
+        Some("%d")
+    } else if hour12 && !am_pm {
+        Some("%p")
+    } else if minute && !hour {
+        Some("%H")
+    } else if second && !minute {
+        Some("%M")
+    } else if fraction && !second {
+        Some("%S")
+    } else {
+        None
+    };
+    if let Some(missing) = missing {
+        diagnostics.push(StrftimeDiagnostic::Incomplete { missing });
+    }
+
+    diagnostics
+}
+
+/// Split `format` into the individual `%` directives it contains, including
+/// any padding, width or precision modifiers.
+fn strftime_directives(format: &str) -> impl Iterator<Item = &str> {
+    let mut chars = format.char_indices();
+    std::iter::from_fn(move || loop {
+        let (start, c) = chars.next()?;
+        if c != '%' {
+            continue;
+        }
+
+        // Consume any modifiers, followed by the directive character itself.
+        let mut end = format.len();
+        for (i, c) in chars.by_ref() {
+            if c.is_ascii_digit() || matches!(c, '-' | '_' | '0' | '#' | '.' | ':') {
+                continue;
+            }
+            end = i + c.len_utf8();
+            break;
+        }
+
+        // An escaped percent sign is a literal.
+        if &format[start..end] == "%%" {
+            continue;
+        }
+
+        return Some(&format[start..end]);
+    })
+}
+
+fn parse_part_tag_value_prefix(value: &str) -> Option<ColumnValue<'_>> {
+    // Even if not truncated, the value is (at most) a prefix of the tag value.
+    match parse_part_tag_value(value)? {
+        ColumnValue::Identity(v) => Some(ColumnValue::Prefix(v)),
+        v => Some(v),
+    }
+}
+
 fn parse_part_tag_value(value: &str) -> Option<ColumnValue<'_>> {
     // Perform re-mapping of sentinel values.
     let value = match value {
@@ -836,7 +2174,7 @@ fn parse_part_tag_value(value: &str) -> Option<ColumnValue<'_>> {
 }
 
 fn parse_part_time_format(value: &str, format: &str) -> Option<ColumnValue<'static>> {
-    use chrono::format::{parse, Item, Parsed};
+    use chrono::format::{parse, Fixed, Item, Parsed};
 
     let items = StrftimeItems::new(format);
 
@@ -860,16 +2198,40 @@ fn parse_part_time_format(value: &str, format: &str) -> Option<ColumnValue<'stat
                     Numeric::Year => Some(begin + Months::new(12)),
                     Numeric::Month => Some(begin + Months::new(1)),
                     Numeric::Day => Some(begin + Days::new(1)),
+                    Numeric::Hour | Numeric::Hour12 => Some(begin + chrono::Duration::hours(1)),
+                    Numeric::Minute => Some(begin + chrono::Duration::minutes(1)),
+                    Numeric::Second => Some(begin + chrono::Duration::seconds(1)),
                     _ => {
                         // not supported
                         return None;
                     }
                 }
             }
-            Item::Fixed(_) => {
-                // not implemented
-                return None;
-            }
+            Item::Fixed(fixed) => match fixed {
+                Fixed::ShortMonthName | Fixed::LongMonthName => Some(begin + Months::new(1)),
+                Fixed::Nanosecond3 => Some(begin + chrono::Duration::milliseconds(1)),
+                Fixed::Nanosecond6 => Some(begin + chrono::Duration::microseconds(1)),
+                Fixed::Nanosecond9 => Some(begin + chrono::Duration::nanoseconds(1)),
+                // These forms are fully determined by the other parts of the
+                // key (the weekday by the date, the AM/PM marker by a 12 hour
+                // clock, and the timezone is always UTC) so they do not narrow
+                // the range.
+                Fixed::ShortWeekdayName
+                | Fixed::LongWeekdayName
+                | Fixed::LowerAmPm
+                | Fixed::UpperAmPm
+                | Fixed::TimezoneName
+                | Fixed::TimezoneOffset
+                | Fixed::TimezoneOffsetColon
+                | Fixed::TimezoneOffsetDoubleColon
+                | Fixed::TimezoneOffsetTripleColon
+                | Fixed::TimezoneOffsetZ
+                | Fixed::TimezoneOffsetColonZ => None,
+                _ => {
+                    // not supported
+                    return None;
+                }
+            },
         };
 
         end = match (end, item_end) {
@@ -891,6 +2253,15 @@ fn parse_part_time_format(value: &str, format: &str) -> Option<ColumnValue<'stat
     end.map(|end| ColumnValue::Datetime { begin, end })
 }
 
+fn parse_part_time_bucket(value: &str, duration: Duration) -> Option<ColumnValue<'static>> {
+    let begin = NaiveDateTime::parse_from_str(value, TIME_BUCKET_FORMAT)
+        .ok()?
+        .and_utc();
+    let end = begin.checked_add_signed(chrono::Duration::from_std(duration).ok()?)?;
+
+    Some(ColumnValue::Datetime { begin, end })
+}
+
 fn parse_part_bucket(value: &str, num_buckets: u32) -> Option<ColumnValue<'_>> {
     // Parse the bucket ID from the given value string.
     let bucket_id = value
@@ -967,10 +2338,38 @@ pub fn test_table_partition_override(
             let part = match part {
                 TemplatePart::TagValue(value) => proto::template_part::Part::TagValue(value.into()),
                 TemplatePart::TimeFormat(fmt) => proto::template_part::Part::TimeFormat(fmt.into()),
-                TemplatePart::Bucket(value, num_buckets) => {
+                TemplatePart::Bucket(value, num_buckets, hash_function) => {
                     proto::template_part::Part::Bucket(proto::Bucket {
                         tag_name: value.into(),
                         num_buckets,
+                        hash_function: proto::bucket::HashFunction::from(hash_function).into(),
+                        ..Default::default()
+                    })
+                }
+                TemplatePart::FieldValue(value) => {
+                    proto::template_part::Part::FieldValue(value.into())
+                }
+                TemplatePart::TableName => {
+                    proto::template_part::Part::TableName(proto::TableName {})
+                }
+                TemplatePart::TimeBucket(duration) => {
+                    proto::template_part::Part::TimeBucket(proto::TimeBucket {
+                        duration_seconds: duration.as_secs(),
+                    })
+                }
+                TemplatePart::TagValuePrefix(value, length) => {
+                    proto::template_part::Part::TagValuePrefix(proto::TagValuePrefix {
+                        tag_name: value.into(),
+                        length,
+                    })
+                }
+                TemplatePart::TagValueLowercase(value) => {
+                    proto::template_part::Part::TagValueLowercase(value.into())
+                }
+                TemplatePart::TagValueWithDefault(value, default_value) => {
+                    proto::template_part::Part::TagValueWithDefault(proto::TagValueWithDefault {
+                        tag_name: value.into(),
+                        default_value: default_value.into(),
                     })
                 }
             };
@@ -979,7 +2378,10 @@ pub fn test_table_partition_override(
         })
         .collect();
 
-    let proto = proto::PartitionTemplate { parts };
+    let proto = canonicalize(proto::PartitionTemplate {
+        parts,
+        ..Default::default()
+    });
     TablePartitionTemplateOverride(Some(
         serialization::Wrapper::for_testing_possibility_of_invalid_value_in_database(proto),
     ))
@@ -1030,7 +2432,10 @@ mod tests {
 
     #[test]
     fn empty_parts_is_invalid() {
-        let err = serialization::Wrapper::try_from(proto::PartitionTemplate { parts: vec![] });
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![],
+            ..Default::default()
+        });
 
         assert_error!(err, ValidationError::NoParts);
     }
@@ -1067,6 +2472,7 @@ mod tests {
                     part: Some(proto::template_part::Part::TagValue("region".into())),
                 },
             ],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::TooManyParts { specified } if specified == 9);
@@ -1084,6 +2490,7 @@ mod tests {
                     part: Some(proto::template_part::Part::TagValue("bananas".into())),
                 },
             ],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::RepeatedTagValue ( ref specified ) if specified == "bananas");
@@ -1095,15 +2502,18 @@ mod tests {
                     part: Some(proto::template_part::Part::Bucket(proto::Bucket {
                         tag_name: "bananas".into(),
                         num_buckets: 42,
+                        ..Default::default()
                     })),
                 },
                 proto::TemplatePart {
                     part: Some(proto::template_part::Part::Bucket(proto::Bucket {
                         tag_name: "bananas".into(),
                         num_buckets: 42,
+                        ..Default::default()
                     })),
                 },
             ],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::RepeatedTagValue ( ref specified ) if specified == "bananas");
@@ -1118,14 +2528,88 @@ mod tests {
                     part: Some(proto::template_part::Part::Bucket(proto::Bucket {
                         tag_name: "bananas".into(),
                         num_buckets: 42,
+                        ..Default::default()
                     })),
                 },
             ],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::RepeatedTagValue ( ref specified ) if specified == "bananas");
     }
 
+    #[test]
+    fn paired_tag_value_and_bucket() {
+        let tag_value = |name: &str| proto::TemplatePart {
+            part: Some(proto::template_part::Part::TagValue(name.into())),
+        };
+        let paired_bucket = |name: &str| proto::TemplatePart {
+            part: Some(proto::template_part::Part::Bucket(proto::Bucket {
+                tag_name: name.into(),
+                num_buckets: 16,
+                pair_with_tag_value: true,
+                ..Default::default()
+            })),
+        };
+
+        // A TagValue part may share its tag with a paired bucket, in either
+        // order.
+        for parts in [
+            vec![tag_value("region"), paired_bucket("region")],
+            vec![paired_bucket("region"), tag_value("region")],
+        ] {
+            let template = serialization::Wrapper::try_from(proto::PartitionTemplate {
+                parts,
+                version: 1,
+                ..Default::default()
+            })
+            .expect("paired parts should be valid");
+            assert_eq!(template.inner().parts.len(), 2);
+        }
+
+        // Pairing requires template version 1.
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![tag_value("region"), paired_bucket("region")],
+            ..Default::default()
+        });
+        assert_error!(err, ValidationError::VersionTooLow { index: 1, .. });
+
+        // True duplicates are still rejected.
+        for parts in [
+            vec![
+                tag_value("region"),
+                tag_value("region"),
+                paired_bucket("region"),
+            ],
+            vec![
+                tag_value("region"),
+                paired_bucket("region"),
+                paired_bucket("region"),
+            ],
+            vec![
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValueLowercase(
+                        "region".into(),
+                    )),
+                },
+                paired_bucket("region"),
+            ],
+            vec![
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::FieldValue("region".into())),
+                },
+                paired_bucket("region"),
+            ],
+        ] {
+            let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+                parts,
+                version: 1,
+                ..Default::default()
+            });
+            assert_error!(err, ValidationError::RepeatedTagValue(ref specified) if specified == "region");
+        }
+    }
+
     /// Chrono will panic when formatting a timestamp if the "%#z" formatting
     /// directive is used...
     #[test]
@@ -1134,6 +2618,7 @@ mod tests {
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TimeFormat("%#z".into())),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidStrftime(_));
@@ -1143,6 +2628,7 @@ mod tests {
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TimeFormat("%#Z".into())),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidStrftime(_));
@@ -1154,6 +2640,7 @@ mod tests {
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TimeFormat("%3F".into())),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidStrftime(ref format) if format == "%3F");
@@ -1165,6 +2652,7 @@ mod tests {
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TimeFormat("".into())),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidStrftime(ref format) if format.is_empty());
@@ -1178,6 +2666,7 @@ mod tests {
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TagValue("time".into())),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidTagValue(_));
@@ -1189,6 +2678,7 @@ mod tests {
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TagValue("".into())),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidTagValue(ref value) if value.is_empty());
@@ -1203,8 +2693,10 @@ mod tests {
                 part: Some(proto::template_part::Part::Bucket(proto::Bucket {
                     tag_name: "time".into(),
                     num_buckets: 42,
+                    ..Default::default()
                 })),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidTagValue(_));
@@ -1217,8 +2709,10 @@ mod tests {
                 part: Some(proto::template_part::Part::Bucket(proto::Bucket {
                     tag_name: "".into(),
                     num_buckets: 42,
+                    ..Default::default()
                 })),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidTagValue(ref value) if value.is_empty());
@@ -1231,8 +2725,10 @@ mod tests {
                 part: Some(proto::template_part::Part::Bucket(proto::Bucket {
                     tag_name: "arán".into(),
                     num_buckets: 0,
+                    ..Default::default()
                 })),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidNumberOfBuckets(0));
@@ -1247,44 +2743,666 @@ mod tests {
                 part: Some(proto::template_part::Part::Bucket(proto::Bucket {
                     tag_name: "arán".into(),
                     num_buckets: TOO_HIGH,
+                    ..Default::default()
                 })),
             }],
+            ..Default::default()
         });
 
         assert_error!(err, ValidationError::InvalidNumberOfBuckets(TOO_HIGH));
     }
 
-    fn identity(s: &str) -> ColumnValue<'_> {
-        ColumnValue::Identity(s.into())
-    }
+    #[test]
+    fn bucket_unknown_hash_function_is_invalid() {
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![proto::TemplatePart {
+                part: Some(proto::template_part::Part::Bucket(proto::Bucket {
+                    tag_name: "arán".into(),
+                    num_buckets: 10,
+                    hash_function: 42,
+                    ..Default::default()
+                })),
+            }],
+            ..Default::default()
+        });
 
-    fn bucket(bucket_id: u32) -> ColumnValue<'static> {
-        ColumnValue::Bucket(bucket_id)
+        assert_error!(err, ValidationError::InvalidBucketHashFunction(42));
     }
 
-    fn prefix<'a, T>(s: T) -> ColumnValue<'a>
-    where
-        T: Into<Cow<'a, str>>,
-    {
-        ColumnValue::Prefix(s.into())
+    #[test]
+    fn bucket_hash_function_round_trip() {
+        let template = TablePartitionTemplateOverride::try_new(
+            Some(proto::PartitionTemplate {
+                parts: vec![
+                    proto::TemplatePart {
+                        part: Some(proto::template_part::Part::Bucket(proto::Bucket {
+                            tag_name: "a".into(),
+                            num_buckets: 10,
+                            ..Default::default()
+                        })),
+                    },
+                    proto::TemplatePart {
+                        part: Some(proto::template_part::Part::Bucket(proto::Bucket {
+                            tag_name: "b".into(),
+                            num_buckets: 10,
+                            hash_function: proto::bucket::HashFunction::Xxhash64.into(),
+                            ..Default::default()
+                        })),
+                    },
+                ],
+                version: 1,
+                ..Default::default()
+            }),
+            &NamespacePartitionTemplateOverride::default(),
+        )
+        .unwrap();
+
+        assert_matches!(
+            template.parts().collect::<Vec<_>>().as_slice(),
+            [
+                TemplatePart::Bucket("a", 10, BucketHashFunction::Murmur3),
+                TemplatePart::Bucket("b", 10, BucketHashFunction::XxHash64),
+            ]
+        );
     }
 
-    fn year(y: i32) -> ColumnValue<'static> {
-        ColumnValue::Datetime {
-            begin: Utc.with_ymd_and_hms(y, 1, 1, 0, 0, 0).unwrap(),
-            end: Utc.with_ymd_and_hms(y + 1, 1, 1, 0, 0, 0).unwrap(),
-        }
+    /// "time" is a special column already covered by strftime.
+    #[test]
+    fn time_field_value_is_invalid() {
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![proto::TemplatePart {
+                part: Some(proto::template_part::Part::FieldValue("time".into())),
+            }],
+            version: 1,
+            ..Default::default()
+        });
+
+        assert_error!(err, ValidationError::InvalidFieldValue(_));
     }
 
     #[test]
-    fn test_iceberg_string_hash() {
-        assert_eq!(iceberg_hash("iceberg"), 1210000089);
+    fn empty_field_value_is_invalid() {
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![proto::TemplatePart {
+                part: Some(proto::template_part::Part::FieldValue("".into())),
+            }],
+            version: 1,
+            ..Default::default()
+        });
+
+        assert_error!(err, ValidationError::InvalidFieldValue(ref value) if value.is_empty());
     }
 
-    // This is a test fixture designed to catch accidental changes to the
-    // Iceberg-like hash-bucket partitioning behaviour.
-    //
-    // You shouldn't be changing this!
+    #[test]
+    fn field_value_repeating_tag_name_is_invalid() {
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValue("status".into())),
+                },
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::FieldValue("status".into())),
+                },
+            ],
+            version: 1,
+            ..Default::default()
+        });
+
+        assert_error!(err, ValidationError::RepeatedFieldValue(ref value) if value == "status");
+    }
+
+    #[test]
+    fn field_value_is_valid() {
+        let template = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TimeFormat("%Y".into())),
+                },
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::FieldValue("status".into())),
+                },
+            ],
+            version: 1,
+            ..Default::default()
+        })
+        .expect("valid template");
+
+        let template = TablePartitionTemplateOverride(Some(template));
+        assert_matches!(
+            template.parts().collect::<Vec<_>>().as_slice(),
+            [
+                TemplatePart::TimeFormat("%Y"),
+                TemplatePart::FieldValue("status")
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_time_bucket_is_invalid() {
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![proto::TemplatePart {
+                part: Some(proto::template_part::Part::TimeBucket(proto::TimeBucket {
+                    duration_seconds: 0,
+                })),
+            }],
+            version: 1,
+            ..Default::default()
+        });
+
+        assert_error!(err.as_ref(), ValidationError::InvalidTimeBucket(0));
+        assert_eq!(
+            err.unwrap_err().to_string(),
+            "time bucket duration in partition template must be in range \
+            1..=9223372036 seconds, duration specified: 0"
+        );
+    }
+
+    #[test]
+    fn overflowing_time_bucket_is_invalid() {
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![proto::TemplatePart {
+                part: Some(proto::template_part::Part::TimeBucket(proto::TimeBucket {
+                    duration_seconds: u64::MAX,
+                })),
+            }],
+            version: 1,
+            ..Default::default()
+        });
+
+        assert_error!(err, ValidationError::InvalidTimeBucket(u64::MAX));
+    }
+
+    #[test]
+    fn time_bucket_is_valid() {
+        let template = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![proto::TemplatePart {
+                part: Some(proto::template_part::Part::TimeBucket(proto::TimeBucket {
+                    duration_seconds: 6 * 60 * 60,
+                })),
+            }],
+            version: 1,
+            ..Default::default()
+        })
+        .expect("valid template");
+
+        let template = TablePartitionTemplateOverride(Some(template));
+        assert_matches!(
+            template.parts().collect::<Vec<_>>().as_slice(),
+            [TemplatePart::TimeBucket(d)] if *d == Duration::from_secs(6 * 60 * 60)
+        );
+    }
+
+    #[test]
+    fn test_validate_partition_key() {
+        let template = test_table_partition_override(vec![
+            TemplatePart::TimeFormat("%Y-%m-%d"),
+            TemplatePart::TagValue("a"),
+            TemplatePart::Bucket("b", 10, BucketHashFunction::Murmur3),
+            TemplatePart::TimeBucket(Duration::from_secs(60 * 60)),
+        ]);
+
+        for key in [
+            "2023-09-01|bananas|3|2023-09-01T13:00:00Z",
+            "2023-09-01|!|!|2023-09-01T13:00:00Z",
+            "2023-09-01|^|9|2023-09-01T13:00:00Z",
+            "2023-09-01|is%7Cnot%21ambiguous%2510%23|0|2023-09-01T13:00:00Z",
+            "2023-09-01|pl%C3%A1tanos#|0|2023-09-01T13:00:00Z",
+        ] {
+            assert_matches!(validate_partition_key(&template, key), Ok(()), "{key}");
+        }
+
+        assert_matches!(
+            validate_partition_key(&template, "2023-09-01|bananas|3"),
+            Err(KeyValidationError::PartCount {
+                expected: 4,
+                actual: 3
+            })
+        );
+        assert_matches!(
+            validate_partition_key(&template, "2023-09-01||3|2023-09-01T13:00:00Z"),
+            Err(KeyValidationError::EmptyPart { index: 1 })
+        );
+        assert_matches!(
+            validate_partition_key(
+                &template,
+                &format!("2023-09-01|{}|3|2023-09-01T13:00:00Z", "a".repeat(201))
+            ),
+            Err(KeyValidationError::PartTooLong { index: 1, len: 201 })
+        );
+
+        for key in [
+            "2023-09-01|pl\u{e1}tanos|3|2023-09-01T13:00:00Z",
+            "2023-09-01|ba#nanas|3|2023-09-01T13:00:00Z",
+            "2023-09-01|bananas^|3|2023-09-01T13:00:00Z",
+            "2023-09-01|100%|3|2023-09-01T13:00:00Z",
+            "2023-09-01|%zz|3|2023-09-01T13:00:00Z",
+            "2023-09-01|%FF|3|2023-09-01T13:00:00Z",
+        ] {
+            assert_matches!(
+                validate_partition_key(&template, key),
+                Err(KeyValidationError::InvalidEncoding { index: 1, .. }),
+                "{key}"
+            );
+        }
+
+        for key in [
+            "2023-09-01|bananas|10|2023-09-01T13:00:00Z",
+            "2023-09-01|bananas|-1|2023-09-01T13:00:00Z",
+            "2023-09-01|bananas|^|2023-09-01T13:00:00Z",
+        ] {
+            assert_matches!(
+                validate_partition_key(&template, key),
+                Err(KeyValidationError::InvalidBucket {
+                    index: 2,
+                    num_buckets: 10,
+                    ..
+                }),
+                "{key}"
+            );
+        }
+
+        assert_matches!(
+            validate_partition_key(&template, "bananas|bananas|3|2023-09-01T13:00:00Z"),
+            Err(KeyValidationError::InvalidTime { index: 0, .. })
+        );
+        assert_matches!(
+            validate_partition_key(&template, "!|bananas|3|2023-09-01T13:00:00Z"),
+            Err(KeyValidationError::InvalidEncoding { index: 0, .. })
+        );
+        // Not aligned to the bucket width.
+        assert_matches!(
+            validate_partition_key(&template, "2023-09-01|bananas|3|2023-09-01T13:30:00Z"),
+            Err(KeyValidationError::InvalidTime { index: 3, .. })
+        );
+    }
+
+    #[test]
+    fn test_lint_strftime() {
+        for format in [
+            "%Y",
+            "%Y-%m-%d",
+            "%F %T",
+            "%Y-%m-%dT%H:%M:%S%.3f%:z",
+            "100%% %Y",
+        ] {
+            assert!(lint_strftime(format).is_empty(), "{format}");
+        }
+
+        assert_eq!(
+            lint_strftime("%Y-%m-%d %3F"),
+            [StrftimeDiagnostic::Unsupported {
+                directive: "%3F".to_string()
+            }]
+        );
+        assert_eq!(
+            lint_strftime("%Y-%m-%d %#z"),
+            [StrftimeDiagnostic::Unsupported {
+                directive: "%#z".to_string()
+            }]
+        );
+        assert_eq!(
+            lint_strftime("%d %b %Y %I%p"),
+            [
+                StrftimeDiagnostic::LocaleDependent {
+                    directive: "%b".to_string()
+                },
+                StrftimeDiagnostic::LocaleDependent {
+                    directive: "%p".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            lint_strftime("%Y-%j"),
+            [StrftimeDiagnostic::NonReversible {
+                directive: "%j".to_string()
+            }]
+        );
+        assert_eq!(
+            lint_strftime("%y"),
+            [
+                StrftimeDiagnostic::NonReversible {
+                    directive: "%y".to_string()
+                },
+                StrftimeDiagnostic::Incomplete { missing: "%Y" },
+            ]
+        );
+        assert_eq!(
+            lint_strftime("%Y-%m-%d %M"),
+            [StrftimeDiagnostic::Incomplete { missing: "%H" }]
+        );
+        assert_eq!(
+            lint_strftime("%Y-%m-%d %I"),
+            [StrftimeDiagnostic::Incomplete { missing: "%p" }]
+        );
+
+        assert!(lint_strftime("%").iter().any(|d| d.is_error()));
+        assert!(!lint_strftime("%b %Y").iter().any(|d| d.is_error()));
+    }
+
+    #[test]
+    fn test_estimate_max_partitions() {
+        // One week of data.
+        let stats = ColumnStats::new(
+            Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap()
+                ..Utc.with_ymd_and_hms(2023, 1, 8, 0, 0, 0).unwrap(),
+        )
+        .with_distinct_count("region", 4)
+        .with_distinct_count("host", 1_000);
+
+        let estimate = |parts| test_table_partition_override(parts).estimate_max_partitions(&stats);
+
+        // The default template partitions by day.
+        assert_eq!(
+            TablePartitionTemplateOverride::default().estimate_max_partitions(&stats),
+            7
+        );
+
+        assert_eq!(estimate(vec![TemplatePart::TimeFormat("%Y")]), 1);
+        assert_eq!(
+            estimate(vec![TemplatePart::TimeFormat("%Y-%m-%d %H")]),
+            7 * 24
+        );
+        assert_eq!(estimate(vec![TemplatePart::TimeFormat("constant")]), 1);
+        assert_eq!(
+            estimate(vec![TemplatePart::TimeBucket(Duration::from_secs(
+                6 * 60 * 60
+            ))]),
+            7 * 4
+        );
+
+        // Each column contributes its distinct values, plus NULL.
+        assert_eq!(
+            estimate(vec![
+                TemplatePart::TimeFormat("%Y-%m-%d"),
+                TemplatePart::TagValue("region"),
+            ]),
+            7 * 5
+        );
+        assert_eq!(estimate(vec![TemplatePart::TagValue("bananas")]), 2);
+
+        // Buckets bound the cardinality of a column.
+        assert_eq!(
+            estimate(vec![
+                TemplatePart::Bucket("region", 10, BucketHashFunction::Murmur3),
+                TemplatePart::Bucket("host", 10, BucketHashFunction::Murmur3),
+                TemplatePart::TableName,
+            ]),
+            5 * 11
+        );
+
+        // Overflow saturates.
+        let stats = stats.with_distinct_count("host", u64::MAX);
+        assert_eq!(
+            test_table_partition_override(vec![
+                TemplatePart::TagValue("host"),
+                TemplatePart::TagValue("region"),
+            ])
+            .estimate_max_partitions(&stats),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_is_compatible_with() {
+        let template = test_table_partition_override(vec![
+            TemplatePart::TimeFormat("%Y"),
+            TemplatePart::TagValue("a"),
+            TemplatePart::Bucket("b", 10, BucketHashFunction::Murmur3),
+        ]);
+
+        let report = template.is_compatible_with(&template);
+        assert!(report.is_compatible());
+        assert!(report.incompatibilities().is_empty());
+
+        // The default template is compatible with itself.
+        let default = TablePartitionTemplateOverride::default();
+        assert!(default.is_compatible_with(&default).is_compatible());
+
+        // Differing part counts.
+        let report = template.is_compatible_with(&default);
+        assert!(!report.is_compatible());
+        assert_eq!(
+            report.incompatibilities(),
+            [Incompatibility::PartCount { this: 3, other: 1 }]
+        );
+
+        // Differing time formats, tag names and bucket counts.
+        let other = test_table_partition_override(vec![
+            TemplatePart::TimeFormat("%Y-%m"),
+            TemplatePart::TagValue("a"),
+            TemplatePart::Bucket("b", 20, BucketHashFunction::Murmur3),
+        ]);
+        let report = template.is_compatible_with(&other);
+        assert_matches!(
+            report.incompatibilities(),
+            [
+                Incompatibility::PartMismatch { index: 0, .. },
+                Incompatibility::PartMismatch { index: 2, .. },
+            ]
+        );
+
+        // Differing bucket hash functions.
+        let other = test_table_partition_override(vec![
+            TemplatePart::TimeFormat("%Y"),
+            TemplatePart::TagValue("a"),
+            TemplatePart::Bucket("b", 10, BucketHashFunction::XxHash64),
+        ]);
+        let report = template.is_compatible_with(&other);
+        assert_matches!(
+            report.incompatibilities(),
+            [Incompatibility::PartMismatch { index: 2, .. }]
+        );
+
+        // Reordered parts.
+        let other = test_table_partition_override(vec![
+            TemplatePart::TagValue("a"),
+            TemplatePart::TimeFormat("%Y"),
+            TemplatePart::Bucket("b", 10, BucketHashFunction::Murmur3),
+        ]);
+        let report = template.is_compatible_with(&other);
+        assert_matches!(
+            report.incompatibilities(),
+            [
+                Incompatibility::PartMismatch { index: 0, .. },
+                Incompatibility::PartMismatch { index: 1, .. },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_compatible_with_time_bucket() {
+        let hour = Duration::from_secs(60 * 60);
+        let day = Duration::from_secs(24 * 60 * 60);
+
+        let hourly = test_table_partition_override(vec![TemplatePart::TimeBucket(hour)]);
+        let daily = test_table_partition_override(vec![TemplatePart::TimeBucket(day)]);
+
+        // Hourly keys interpreted as daily ranges cover all the data.
+        assert!(hourly.is_compatible_with(&daily).is_compatible());
+
+        // Daily keys interpreted as hourly ranges do not.
+        assert_eq!(
+            daily.is_compatible_with(&hourly).incompatibilities(),
+            [Incompatibility::TimeGranularity {
+                index: 0,
+                this: day,
+                other: hour,
+            }]
+        );
+    }
+
+    #[test]
+    fn tag_value_prefix_length_is_validated() {
+        for length in [0, PARTITION_KEY_MAX_PART_LEN as u32 + 1] {
+            let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+                parts: vec![proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValuePrefix(
+                        proto::TagValuePrefix {
+                            tag_name: "region".into(),
+                            length,
+                        },
+                    )),
+                }],
+                version: 1,
+                ..Default::default()
+            });
+
+            assert_error!(err, ValidationError::InvalidTagValuePrefixLength(l) if l == length);
+        }
+    }
+
+    #[test]
+    fn tag_value_transform_repeating_tag_name_is_invalid() {
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValuePrefix(
+                        proto::TagValuePrefix {
+                            tag_name: "region".into(),
+                            length: 2,
+                        },
+                    )),
+                },
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValueLowercase(
+                        "region".into(),
+                    )),
+                },
+            ],
+            version: 1,
+            ..Default::default()
+        });
+
+        assert_error!(err, ValidationError::RepeatedTagValue(ref value) if value == "region");
+    }
+
+    #[test]
+    fn tag_value_transforms_are_valid() {
+        let template = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValuePrefix(
+                        proto::TagValuePrefix {
+                            tag_name: "region".into(),
+                            length: 2,
+                        },
+                    )),
+                },
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValueLowercase("host".into())),
+                },
+            ],
+            version: 1,
+            ..Default::default()
+        })
+        .expect("valid template");
+
+        let template = TablePartitionTemplateOverride(Some(template));
+        assert_matches!(
+            template.parts().collect::<Vec<_>>().as_slice(),
+            [
+                TemplatePart::TagValuePrefix("region", 2),
+                TemplatePart::TagValueLowercase("host")
+            ]
+        );
+    }
+
+    #[test]
+    fn tag_value_default_is_validated() {
+        let template = |default_value: String| {
+            serialization::Wrapper::try_from(proto::PartitionTemplate {
+                parts: vec![proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValueWithDefault(
+                        proto::TagValueWithDefault {
+                            tag_name: "region".into(),
+                            default_value,
+                        },
+                    )),
+                }],
+                version: 1,
+                ..Default::default()
+            })
+        };
+
+        // Each "|" is encoded as 3 bytes, exceeding the limit.
+        let err = template("|".repeat(PARTITION_KEY_MAX_PART_LEN / 2));
+        assert_error!(err, ValidationError::InvalidTagValueDefault(_));
+
+        let template = template("unknown".into()).expect("valid template");
+        let template = TablePartitionTemplateOverride(Some(template));
+        assert_matches!(
+            template.parts().collect::<Vec<_>>().as_slice(),
+            [TemplatePart::TagValueWithDefault("region", "unknown")]
+        );
+    }
+
+    #[test]
+    fn repeated_table_name_is_invalid() {
+        let table_name = proto::TemplatePart {
+            part: Some(proto::template_part::Part::TableName(proto::TableName {})),
+        };
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![table_name.clone(), table_name],
+            ..Default::default()
+        });
+
+        assert_error!(err, ValidationError::RepeatedTableName);
+    }
+
+    #[test]
+    fn table_name_is_valid() {
+        let template = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TableName(proto::TableName {})),
+                },
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TimeFormat("%Y".into())),
+                },
+            ],
+            version: 1,
+            ..Default::default()
+        })
+        .expect("valid template");
+
+        let template = TablePartitionTemplateOverride(Some(template));
+        assert_matches!(
+            template.parts().collect::<Vec<_>>().as_slice(),
+            [TemplatePart::TableName, TemplatePart::TimeFormat("%Y")]
+        );
+    }
+
+    fn identity(s: &str) -> ColumnValue<'_> {
+        ColumnValue::Identity(s.into())
+    }
+
+    fn bucket(bucket_id: u32) -> ColumnValue<'static> {
+        ColumnValue::Bucket(bucket_id)
+    }
+
+    fn prefix<'a, T>(s: T) -> ColumnValue<'a>
+    where
+        T: Into<Cow<'a, str>>,
+    {
+        ColumnValue::Prefix(s.into())
+    }
+
+    fn year(y: i32) -> ColumnValue<'static> {
+        ColumnValue::Datetime {
+            begin: Utc.with_ymd_and_hms(y, 1, 1, 0, 0, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(y + 1, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_iceberg_string_hash() {
+        assert_eq!(iceberg_hash("iceberg"), 1210000089);
+    }
+
+    // This is a test fixture designed to catch accidental changes to the
+    // Iceberg-like hash-bucket partitioning behaviour.
+    //
+    // You shouldn't be changing this!
     #[test]
     fn test_hash_bucket_fixture() {
         // These are values lifted from the iceberg spark test suite for
@@ -1312,6 +3430,198 @@ mod tests {
         );
     }
 
+    #[test]
+    fn template_version_is_validated() {
+        let xxhash_bucket = proto::TemplatePart {
+            part: Some(proto::template_part::Part::Bucket(proto::Bucket {
+                tag_name: "arán".into(),
+                num_buckets: 10,
+                hash_function: proto::bucket::HashFunction::Xxhash64.into(),
+                ..Default::default()
+            })),
+        };
+
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![proto::TemplatePart {
+                part: Some(proto::template_part::Part::TagValue("region".into())),
+            }],
+            version: PARTITION_TEMPLATE_VERSION + 1,
+            ..Default::default()
+        });
+        assert_error!(err, ValidationError::UnsupportedVersion(v) if v == PARTITION_TEMPLATE_VERSION + 1);
+
+        // The xxHash bucket hash function was introduced in version 1.
+        let err = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![
+                proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValue("region".into())),
+                },
+                xxhash_bucket.clone(),
+            ],
+            ..Default::default()
+        });
+        assert_error!(
+            err,
+            ValidationError::VersionTooLow {
+                index: 1,
+                required: 1,
+                specified: 0
+            }
+        );
+
+        let template = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            parts: vec![xxhash_bucket],
+            version: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(template.inner().version, 1);
+    }
+
+    #[test]
+    fn test_canonical_template_equality() {
+        fn hash_of<T: std::hash::Hash>(v: &T) -> u64 {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            v.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let a: proto::PartitionTemplate = serde_json::from_str(
+            r#"{"parts":[{"tagValue":"region"},{"timeFormat":"%Y"}],"truncateAtCodePoint":true,"version":1}"#,
+        )
+        .unwrap();
+
+        // The same template with differing field ordering and a part unknown
+        // to this reader.
+        let b: proto::PartitionTemplate = serde_json::from_str(
+            r#"{"version":1,"truncateAtCodePoint":true,"parts":[{"tagValue":"region"},{},{"timeFormat":"%Y"}]}"#,
+        )
+        .unwrap();
+        assert_ne!(a, b);
+
+        assert_eq!(canonicalize(a.clone()), canonicalize(b.clone()));
+
+        let a = NamespacePartitionTemplateOverride::try_from(a).unwrap();
+        let b = NamespacePartitionTemplateOverride::try_from(b).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(hash_of(&a), hash_of(&b));
+        assert_eq!(a.as_proto().unwrap().version, 1);
+        assert_eq!(a.as_proto().unwrap().parts.len(), 2);
+    }
+
+    #[test]
+    fn test_version_1_features() {
+        let parts = [
+            proto::template_part::Part::FieldValue("status".into()),
+            proto::template_part::Part::TableName(proto::TableName {}),
+            proto::template_part::Part::TimeBucket(proto::TimeBucket {
+                duration_seconds: 6 * 60 * 60,
+            }),
+            proto::template_part::Part::TagValuePrefix(proto::TagValuePrefix {
+                tag_name: "region".into(),
+                length: 3,
+            }),
+            proto::template_part::Part::TagValueLowercase("region".into()),
+            proto::template_part::Part::TagValueWithDefault(proto::TagValueWithDefault {
+                tag_name: "region".into(),
+                default_value: "unknown".into(),
+            }),
+        ];
+
+        for part in parts {
+            let template = proto::PartitionTemplate {
+                parts: vec![proto::TemplatePart { part: Some(part) }],
+                ..Default::default()
+            };
+
+            let err = serialization::Wrapper::try_from(template.clone());
+            assert_error!(
+                err,
+                ValidationError::VersionTooLow {
+                    index: 0,
+                    required: 1,
+                    specified: 0
+                }
+            );
+
+            // Templates decoded from the catalog are raised to the required
+            // version.
+            assert_eq!(canonicalize(template.clone()).version, 1);
+
+            serialization::Wrapper::try_from(proto::PartitionTemplate {
+                version: 1,
+                ..template
+            })
+            .expect("valid template");
+        }
+    }
+
+    #[test]
+    fn test_truncation_version() {
+        let template = proto::PartitionTemplate {
+            parts: vec![proto::TemplatePart {
+                part: Some(proto::template_part::Part::TagValue("region".into())),
+            }],
+            truncate_at_code_point: true,
+            version: 0,
+        };
+
+        let err = serialization::Wrapper::try_from(template.clone()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "truncating partition key parts at code points requires template version 1, \
+            but the template specifies version 0"
+        );
+        assert_eq!(canonicalize(template.clone()).version, 1);
+
+        let template = serialization::Wrapper::try_from(proto::PartitionTemplate {
+            version: 1,
+            ..template
+        })
+        .expect("valid template");
+        assert_eq!(template.inner().version, 1);
+    }
+
+    #[test]
+    fn test_canonicalize_never_lowers_version() {
+        let template = proto::PartitionTemplate {
+            parts: vec![proto::TemplatePart {
+                part: Some(proto::template_part::Part::TagValue("region".into())),
+            }],
+            version: 1,
+            ..Default::default()
+        };
+        assert_eq!(canonicalize(template).version, 1);
+    }
+
+    // A fixture catching accidental changes to the xxHash bucketing behaviour.
+    #[test]
+    fn test_xxhash64_bucket_fixture() {
+        let f = BucketHashFunction::XxHash64;
+
+        assert_eq!(f.bucket_for_tag_value("abcdefg", 5), 0);
+        assert_eq!(f.bucket_for_tag_value("abc", 128), 25);
+        assert_eq!(f.bucket_for_tag_value("测试", 12), 7);
+        assert_eq!(f.bucket_for_tag_value("", 16), 9);
+        assert_eq!(f.bucket_for_tag_value("bananas", 10), 9);
+        assert_eq!(f.bucket_for_tag_value("plátanos", 100), 39);
+        assert_eq!(f.bucket_for_tag_value("crobhaing bananaí", 1000), 475);
+        assert_eq!(
+            f.bucket_for_tag_value("a long tag value exceeding thirty two bytes in length", 64),
+            20
+        );
+        assert_eq!(
+            f.bucket_for_tag_value("uasmhéid bananaí", u32::MAX),
+            2477760238
+        );
+
+        // The default hash function is the Iceberg-like murmur3 bucketing.
+        assert_eq!(
+            BucketHashFunction::default().bucket_for_tag_value("bananas", 10),
+            bucket_for_tag_value("bananas", 10)
+        );
+    }
+
     /// Test to approximate and show how the tag value maps to the partition key
     /// for the example cases in the mod-doc. The behaviour that renders the key
     /// itself is a combination of this bucket assignment and the render logic.
@@ -1374,7 +3684,7 @@ mod tests {
             TemplatePart::TimeFormat("%Y"),
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 10),
+            TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3),
         ],
         partition_key = "2023|bananas|plátanos|5",
         want = [
@@ -1391,19 +3701,89 @@ mod tests {
             TemplatePart::TimeFormat("%Y"),
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 10),
+            TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3),
         ],
         partition_key = "2023|!|plátanos|!",
         want = [(TIME_COLUMN_NAME, year(2023)), ("b", identity("plátanos")),]
     );
 
+    test_build_column_values!(
+        field_value,
+        template = [
+            TemplatePart::TimeFormat("%Y"),
+            TemplatePart::FieldValue("status"),
+            TemplatePart::FieldValue("code"),
+            TemplatePart::FieldValue("missing"),
+        ],
+        partition_key = "2023|ok%7Cdone|404|!",
+        want = [
+            (TIME_COLUMN_NAME, year(2023)),
+            ("status", identity("ok|done")),
+            ("code", identity("404")),
+        ]
+    );
+
+    test_build_column_values!(
+        time_bucket,
+        template = [
+            TemplatePart::TimeBucket(Duration::from_secs(15 * 60)),
+            TemplatePart::TagValue("a"),
+        ],
+        partition_key = "2023-03-10T13:45:00Z|bananas",
+        want = [
+            (
+                TIME_COLUMN_NAME,
+                ColumnValue::Datetime {
+                    begin: Utc.with_ymd_and_hms(2023, 3, 10, 13, 45, 0).unwrap(),
+                    end: Utc.with_ymd_and_hms(2023, 3, 10, 14, 0, 0).unwrap(),
+                }
+            ),
+            ("a", identity("bananas")),
+        ]
+    );
+
+    test_build_column_values!(
+        tag_value_transforms,
+        template = [
+            TemplatePart::TagValuePrefix("a", 2),
+            TemplatePart::TagValuePrefix("b", 10),
+            TemplatePart::TagValueLowercase("c"),
+            TemplatePart::TagValuePrefix("d", 2),
+        ],
+        partition_key = "eu|us-east|bananas|!",
+        want = [("a", prefix("eu")), ("b", prefix("us-east")),]
+    );
+
+    test_build_column_values!(
+        tag_value_with_default,
+        template = [
+            TemplatePart::TagValueWithDefault("a", "unknown"),
+            TemplatePart::TagValueWithDefault("b", "unknown"),
+            TemplatePart::TagValueWithDefault("c", ""),
+            TemplatePart::TagValueWithDefault("d", "un|known"),
+        ],
+        partition_key = "bananas|unknown|^|un%7Cknown",
+        want = [("a", identity("bananas")),]
+    );
+
+    test_build_column_values!(
+        table_name,
+        template = [
+            TemplatePart::TableName,
+            TemplatePart::TimeFormat("%Y"),
+            TemplatePart::TagValue("a"),
+        ],
+        partition_key = "cpu%7Cmem|2023|bananas",
+        want = [(TIME_COLUMN_NAME, year(2023)), ("a", identity("bananas")),]
+    );
+
     test_build_column_values!(
         module_doc_example_4,
         template = [
             TemplatePart::TimeFormat("%Y"),
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 10),
+            TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3),
         ],
         partition_key = "2023|!|!|!",
         want = [(TIME_COLUMN_NAME, year(2023)),]
@@ -1415,7 +3795,7 @@ mod tests {
             TemplatePart::TimeFormat("%Y"),
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 10),
+            TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3),
         ],
         partition_key = "2023|cat%7Cdog|%21|8",
         want = [
@@ -1432,7 +3812,7 @@ mod tests {
             TemplatePart::TimeFormat("%Y"),
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 10),
+            TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3),
         ],
         partition_key = "2023|%2550|!|9",
         want = [
@@ -1448,7 +3828,7 @@ mod tests {
             TemplatePart::TimeFormat("%Y"),
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 10),
+            TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3),
         ],
         partition_key = "2023|^|!|0",
         want = [
@@ -1464,7 +3844,7 @@ mod tests {
             TemplatePart::TimeFormat("%Y"),
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 10),
+            TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3),
         ],
         partition_key = "2023|BANANAS#|!|!|!",
         want = [(TIME_COLUMN_NAME, year(2023)), ("a", prefix("BANANAS")),]
@@ -1476,7 +3856,7 @@ mod tests {
             TemplatePart::TimeFormat("%Y"),
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 10),
+            TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3),
         ],
         partition_key = "2023|%28%E3%83%8E%E0%B2%A0%E7%9B%8A%E0%B2%A0%29%E3%83%8E%E5%BD%A1%E2%94%BB%E2%94%81%E2%94%BB#|!|!",
         want = [
@@ -1615,12 +3995,84 @@ mod tests {
         )]
     );
 
+    test_build_column_values!(
+        datetime_range_y_m_d_h,
+        template = [TemplatePart::TimeFormat("%Y-%m-%d %H"),],
+        partition_key = "2023-12-31 23",
+        want = [(
+            TIME_COLUMN_NAME,
+            ColumnValue::Datetime {
+                begin: Utc.with_ymd_and_hms(2023, 12, 31, 23, 0, 0).unwrap(),
+                end: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            },
+        )]
+    );
+
+    test_build_column_values!(
+        datetime_range_y_m_d_h_m,
+        template = [TemplatePart::TimeFormat("%Y-%m-%dT%H:%M"),],
+        partition_key = "2023-09-01T13:59",
+        want = [(
+            TIME_COLUMN_NAME,
+            ColumnValue::Datetime {
+                begin: Utc.with_ymd_and_hms(2023, 9, 1, 13, 59, 0).unwrap(),
+                end: Utc.with_ymd_and_hms(2023, 9, 1, 14, 0, 0).unwrap(),
+            },
+        )]
+    );
+
+    test_build_column_values!(
+        datetime_range_y_m_d_h_m_s,
+        template = [TemplatePart::TimeFormat("%F %T"),],
+        partition_key = "2023-09-01 13:14:15",
+        want = [(
+            TIME_COLUMN_NAME,
+            ColumnValue::Datetime {
+                begin: Utc.with_ymd_and_hms(2023, 9, 1, 13, 14, 15).unwrap(),
+                end: Utc.with_ymd_and_hms(2023, 9, 1, 13, 14, 16).unwrap(),
+            },
+        )]
+    );
+
+    test_build_column_values!(
+        datetime_range_fixed_forms,
+        template = [TemplatePart::TimeFormat("%a %d %b %Y %I%p %Z"),],
+        partition_key = "Fri 01 Sep 2023 01PM UTC",
+        want = [(
+            TIME_COLUMN_NAME,
+            ColumnValue::Datetime {
+                begin: Utc.with_ymd_and_hms(2023, 9, 1, 13, 0, 0).unwrap(),
+                end: Utc.with_ymd_and_hms(2023, 9, 1, 14, 0, 0).unwrap(),
+            },
+        )]
+    );
+
+    test_build_column_values!(
+        datetime_range_millis,
+        template = [TemplatePart::TimeFormat("%Y-%m-%d %H:%M:%S%.3f"),],
+        partition_key = "2023-09-01 13:14:15.123",
+        want = [(
+            TIME_COLUMN_NAME,
+            ColumnValue::Datetime {
+                begin: Utc.timestamp_millis_opt(1693574055123).unwrap(),
+                end: Utc.timestamp_millis_opt(1693574055124).unwrap(),
+            },
+        )]
+    );
+
+    test_build_column_values!(
+        datetime_range_minute_without_hour,
+        template = [TemplatePart::TimeFormat("%Y-%m-%d %M"),],
+        partition_key = "2023-09-01 13",
+        want = []
+    );
+
     test_build_column_values!(
         bucket_part_fixture,
         template = [
-            TemplatePart::Bucket("a", 41),
-            TemplatePart::Bucket("b", 91),
-            TemplatePart::Bucket("c", 144)
+            TemplatePart::Bucket("a", 41, BucketHashFunction::Murmur3),
+            TemplatePart::Bucket("b", 91, BucketHashFunction::Murmur3),
+            TemplatePart::Bucket("c", 144, BucketHashFunction::Murmur3)
         ],
         partition_key = "1|2|3",
         want = [("a", bucket(1)), ("b", bucket(2)), ("c", bucket(3)),]
@@ -1630,9 +4082,9 @@ mod tests {
     #[should_panic]
     fn test_build_column_values_bucket_part_out_of_range_panics() {
         let template = [
-            TemplatePart::Bucket("a", 42),
-            TemplatePart::Bucket("b", 42),
-            TemplatePart::Bucket("c", 42),
+            TemplatePart::Bucket("a", 42, BucketHashFunction::Murmur3),
+            TemplatePart::Bucket("b", 42, BucketHashFunction::Murmur3),
+            TemplatePart::Bucket("c", 42, BucketHashFunction::Murmur3),
         ]
         .into_iter()
         .collect::<Vec<_>>();
@@ -1647,9 +4099,9 @@ mod tests {
     #[should_panic]
     fn test_build_column_values_bucket_part_not_u32_panics() {
         let template = [
-            TemplatePart::Bucket("a", 42),
-            TemplatePart::Bucket("b", 42),
-            TemplatePart::Bucket("c", 42),
+            TemplatePart::Bucket("a", 42, BucketHashFunction::Murmur3),
+            TemplatePart::Bucket("b", 42, BucketHashFunction::Murmur3),
+            TemplatePart::Bucket("c", 42, BucketHashFunction::Murmur3),
         ]
         .into_iter()
         .collect::<Vec<_>>();
@@ -1776,6 +4228,7 @@ mod tests {
                 parts: vec![proto::TemplatePart {
                     part: Some(proto::template_part::Part::TimeFormat("year-%Y".into())),
                 }],
+                ..Default::default()
             })
             .unwrap();
         let table_template =
@@ -1791,12 +4244,14 @@ mod tests {
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TagValue("region".into())),
             }],
+            ..Default::default()
         };
         let namespace_template =
             NamespacePartitionTemplateOverride::try_from(proto::PartitionTemplate {
                 parts: vec![proto::TemplatePart {
                     part: Some(proto::template_part::Part::TimeFormat("year-%Y".into())),
                 }],
+                ..Default::default()
             })
             .unwrap();
         let table_template = TablePartitionTemplateOverride::try_new(
@@ -1809,6 +4264,40 @@ mod tests {
         assert_eq!(table_template.0.unwrap().inner(), &custom_table_template);
     }
 
+    #[test]
+    fn truncation_mode() {
+        let ns = NamespacePartitionTemplateOverride::default();
+        let t = TablePartitionTemplateOverride::try_new(None, &ns).unwrap();
+        assert_eq!(t.truncation_mode(), TruncationMode::Grapheme);
+
+        let namespace_template =
+            NamespacePartitionTemplateOverride::try_from(proto::PartitionTemplate {
+                parts: vec![proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValue("region".into())),
+                }],
+                truncate_at_code_point: true,
+                version: 1,
+            })
+            .unwrap();
+
+        // Inherited from the namespace.
+        let t = TablePartitionTemplateOverride::try_new(None, &namespace_template).unwrap();
+        assert_eq!(t.truncation_mode(), TruncationMode::CodePoint);
+
+        // Overridden by the table.
+        let t = TablePartitionTemplateOverride::try_new(
+            Some(proto::PartitionTemplate {
+                parts: vec![proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValue("region".into())),
+                }],
+                ..Default::default()
+            }),
+            &namespace_template,
+        )
+        .unwrap();
+        assert_eq!(t.truncation_mode(), TruncationMode::Grapheme);
+    }
+
     // The JSON representation of the partition template protobuf is stored in the database, so
     // the encode/decode implementations need to be stable if we want to avoid having to
     // migrate the values stored in the database.
@@ -1827,9 +4316,11 @@ mod tests {
                     part: Some(proto::template_part::Part::Bucket(proto::Bucket {
                         tag_name: "bananas".into(),
                         num_buckets: 42,
+                        ..Default::default()
                     })),
                 },
             ],
+            ..Default::default()
         };
         let expected_json_str = "{\"parts\":[\
             {\"tagValue\":\"region\"},\
@@ -1876,6 +4367,7 @@ mod tests {
                 parts: vec![proto::TemplatePart {
                     part: Some(proto::template_part::Part::TagValue(first_string.into())),
                 }],
+                ..Default::default()
             }),
             &NamespacePartitionTemplateOverride::default(),
         )
@@ -1892,6 +4384,7 @@ mod tests {
                 parts: vec![proto::TemplatePart {
                     part: Some(proto::template_part::Part::TagValue(second_string.into())),
                 }],
+                ..Default::default()
             }),
             &NamespacePartitionTemplateOverride::default(),
         )
@@ -1913,6 +4406,7 @@ mod tests {
                         part: Some(proto::template_part::Part::TimeFormat(time_string.into())),
                     },
                 ],
+                ..Default::default()
             }),
             &NamespacePartitionTemplateOverride::default(),
         )
@@ -1932,8 +4426,10 @@ mod tests {
                     part: Some(proto::template_part::Part::Bucket(proto::Bucket {
                         tag_name: second_string.into(),
                         num_buckets: 42,
+                        ..Default::default()
                     })),
                 }],
+                ..Default::default()
             }),
             &NamespacePartitionTemplateOverride::default(),
         )
@@ -0,0 +1,308 @@
+use std::{
+    borrow::Cow,
+    fmt::{Display, Write},
+};
+
+use percent_encoding::utf8_percent_encode;
+
+use super::{
+    TruncationMode, ENCODED_PARTITION_KEY_CHARS, PARTITION_KEY_DELIMITER,
+    PARTITION_KEY_MAX_PART_LEN, PARTITION_KEY_PART_TRUNCATED, PARTITION_KEY_VALUE_EMPTY_STR,
+    PARTITION_KEY_VALUE_NULL_STR,
+};
+
+/// A reusable builder of partition key strings.
+///
+/// Each part is encoded (see the [module docs](super)) directly into an
+/// internal buffer, which is retained across calls to
+/// [`PartitionKeyBuilder::clear()`] so that rendering many keys does not
+/// allocate once the buffer has grown to fit the longest key.
+///
+/// ```
+/// use data_types::partition_template::PartitionKeyBuilder;
+///
+/// let mut builder = PartitionKeyBuilder::default();
+/// builder.push_value("2023").push_value("is|not!ambiguous").push_null();
+/// assert_eq!(builder.as_str(), "2023|is%7Cnot%21ambiguous|!");
+///
+/// builder.clear();
+/// builder.push_value("");
+/// assert_eq!(builder.as_str(), "^");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PartitionKeyBuilder {
+    buf: String,
+    parts: usize,
+    truncation: TruncationMode,
+}
+
+impl PartitionKeyBuilder {
+    /// Initialise a [`PartitionKeyBuilder`] that renders keys into `buf`,
+    /// discarding any existing content but retaining its allocation.
+    pub fn with_buffer(mut buf: String) -> Self {
+        buf.clear();
+        Self {
+            buf,
+            parts: 0,
+            truncation: TruncationMode::default(),
+        }
+    }
+
+    /// Truncate parts exceeding [`PARTITION_KEY_MAX_PART_LEN`] according to
+    /// `mode`, typically the [`TablePartitionTemplateOverride::truncation_mode()`]
+    /// of the template being rendered.
+    ///
+    /// [`TablePartitionTemplateOverride::truncation_mode()`]:
+    ///     super::TablePartitionTemplateOverride::truncation_mode
+    pub fn with_truncation_mode(mut self, mode: TruncationMode) -> Self {
+        self.truncation = mode;
+        self
+    }
+
+    /// Append a part rendering the user-provided `value`, encoding and
+    /// truncating it as necessary.
+    pub fn push_value(&mut self, value: &str) -> &mut Self {
+        self.start_part();
+        self.write_encoded(value);
+        self
+    }
+
+    /// Append a part rendering `value`, encoding and truncating the output as
+    /// necessary.
+    ///
+    /// This only allocates if the rendered output must be encoded or
+    /// truncated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the [`Display`] implementation of `value` fails, in
+    /// which case the part is not appended.
+    pub fn push_display(&mut self, value: impl Display) -> Result<&mut Self, std::fmt::Error> {
+        let reset = self.buf.len();
+        self.start_part();
+        let start = self.buf.len();
+
+        if let Err(e) = write!(self.buf, "{value}") {
+            self.buf.truncate(reset);
+            self.parts -= 1;
+            return Err(e);
+        }
+
+        let rendered = &self.buf[start..];
+        let needs_encoding = rendered.is_empty()
+            || rendered.len() > PARTITION_KEY_MAX_PART_LEN
+            || matches!(
+                Cow::from(utf8_percent_encode(rendered, &ENCODED_PARTITION_KEY_CHARS)),
+                Cow::Owned(_)
+            );
+        if needs_encoding {
+            let rendered = self.buf.split_off(start);
+            self.write_encoded(&rendered);
+        }
+
+        Ok(self)
+    }
+
+    /// Append a NULL part, indicating a missing value.
+    pub fn push_null(&mut self) -> &mut Self {
+        self.start_part();
+        self.buf.push_str(PARTITION_KEY_VALUE_NULL_STR);
+        self
+    }
+
+    /// The number of parts appended since the builder was last cleared.
+    pub fn num_parts(&self) -> usize {
+        self.parts
+    }
+
+    /// The partition key rendered so far.
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    /// Remove all parts, retaining the allocated buffer.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.parts = 0;
+    }
+
+    /// Consume the builder, returning the rendered partition key.
+    pub fn into_string(self) -> String {
+        self.buf
+    }
+
+    fn start_part(&mut self) {
+        if self.parts > 0 {
+            self.buf.push(PARTITION_KEY_DELIMITER);
+        }
+        self.parts += 1;
+    }
+
+    /// Write the encoded form of `value` to the buffer.
+    fn write_encoded(&mut self, value: &str) {
+        if value.is_empty() {
+            self.buf.push_str(PARTITION_KEY_VALUE_EMPTY_STR);
+            return;
+        }
+
+        let start = self.buf.len();
+        self.buf
+            .extend(utf8_percent_encode(value, &ENCODED_PARTITION_KEY_CHARS));
+        if self.buf.len() - start <= PARTITION_KEY_MAX_PART_LEN {
+            return;
+        }
+
+        // This value exceeds the maximum byte length limit and must be
+        // truncated, without splitting a segment, leaving space for the
+        // truncation marker.
+        self.buf.truncate(start);
+        for segment in self.truncation.segments(value) {
+            let before = self.buf.len();
+            self.buf
+                .extend(utf8_percent_encode(segment, &ENCODED_PARTITION_KEY_CHARS));
+            if self.buf.len() - start >= PARTITION_KEY_MAX_PART_LEN {
+                self.buf.truncate(before);
+                break;
+            }
+        }
+        self.buf.push(PARTITION_KEY_PART_TRUNCATED);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::partition_template::{
+        build_column_values, test_table_partition_override, ColumnValue, TemplatePart,
+    };
+
+    #[test]
+    fn test_parts() {
+        let mut builder = PartitionKeyBuilder::default();
+        assert_eq!(builder.as_str(), "");
+        assert_eq!(builder.num_parts(), 0);
+
+        builder
+            .push_value("bananas")
+            .push_null()
+            .push_value("")
+            .push_value("100%#^");
+        assert_eq!(builder.as_str(), "bananas|!|^|100%25%23%5E");
+        assert_eq!(builder.num_parts(), 4);
+
+        builder.clear();
+        assert_eq!(builder.as_str(), "");
+        builder.push_value("platanos");
+        assert_eq!(builder.into_string(), "platanos");
+    }
+
+    #[test]
+    fn test_with_buffer() {
+        let mut buf = String::with_capacity(64);
+        buf.push_str("stale");
+        let ptr = buf.as_ptr();
+
+        let mut builder = PartitionKeyBuilder::with_buffer(buf);
+        builder.push_value("2023").push_value("west");
+        assert_eq!(builder.as_str(), "2023|west");
+
+        // The caller's allocation is used.
+        let buf = builder.into_string();
+        assert_eq!(buf.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_push_display() {
+        let mut builder = PartitionKeyBuilder::default();
+        builder
+            .push_display(42)
+            .unwrap()
+            .push_display("a|b")
+            .unwrap()
+            .push_display("|ab")
+            .unwrap()
+            .push_display("")
+            .unwrap();
+        assert_eq!(builder.as_str(), "42|a%7Cb|%7Cab|^");
+
+        // A failing Display implementation leaves the builder unchanged.
+        struct Failing;
+        impl Display for Failing {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str("partial")?;
+                Err(std::fmt::Error)
+            }
+        }
+        builder.push_display(Failing).unwrap_err();
+        assert_eq!(builder.as_str(), "42|a%7Cb|%7Cab|^");
+        assert_eq!(builder.num_parts(), 4);
+    }
+
+    #[test]
+    fn test_truncation() {
+        let long = "a".repeat(PARTITION_KEY_MAX_PART_LEN + 1);
+        let mut builder = PartitionKeyBuilder::default();
+        builder.push_value(&long);
+        assert_eq!(builder.as_str().len(), PARTITION_KEY_MAX_PART_LEN);
+        assert!(builder.as_str().ends_with(PARTITION_KEY_PART_TRUNCATED));
+
+        // Encoded graphemes are never split.
+        let long = "|".repeat(PARTITION_KEY_MAX_PART_LEN);
+        builder.clear();
+        builder.push_display(&long).unwrap();
+        assert_eq!(builder.as_str(), format!("{}#", "%7C".repeat(66)));
+
+        // A value fitting in the limit is not truncated.
+        let exact = "a".repeat(PARTITION_KEY_MAX_PART_LEN);
+        builder.clear();
+        builder.push_value(&exact[1..]);
+        assert_eq!(builder.as_str(), &exact[1..]);
+    }
+
+    #[test]
+    fn test_truncation_mode() {
+        // The Tamil "ni" grapheme is two 3-byte code-points, each encoded to 9
+        // bytes.
+        let long = format!("ab{}", "நி".repeat(20));
+
+        let mut builder = PartitionKeyBuilder::default();
+        builder.push_value(&long);
+        assert_eq!(builder.as_str().len(), 2 + 10 * 18 + 1);
+        assert!(builder.as_str().ends_with("%E0%AE%A8%E0%AE%BF#"));
+
+        // Splitting the final grapheme retains an additional code-point.
+        let mut builder =
+            PartitionKeyBuilder::default().with_truncation_mode(TruncationMode::CodePoint);
+        builder.push_value(&long);
+        assert_eq!(builder.as_str().len(), 2 + 21 * 9 + 1);
+        assert!(builder.as_str().ends_with("%E0%AE%BF%E0%AE%A8#"));
+
+        // Clearing the builder retains the truncation mode.
+        builder.clear();
+        builder.push_value(&long);
+        assert_eq!(builder.as_str().len(), 2 + 21 * 9 + 1);
+    }
+
+    #[test]
+    fn test_reversible() {
+        let mut builder = PartitionKeyBuilder::default();
+        builder
+            .push_value("is|not!ambiguous%10#")
+            .push_null()
+            .push_value("");
+
+        let template = test_table_partition_override(vec![
+            TemplatePart::TagValue("a"),
+            TemplatePart::TagValue("b"),
+            TemplatePart::TagValue("c"),
+        ]);
+        let got = build_column_values(&template, builder.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            got,
+            [
+                ("a", ColumnValue::Identity("is|not!ambiguous%10#".into())),
+                ("c", ColumnValue::Identity("".into())),
+            ]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Table-level retention policies.
+
+use iox_time::Time;
+use thiserror::Error;
+
+/// Errors constructing a [`TableRetentionPolicy`].
+#[derive(Debug, Error, PartialEq, Eq)]
+#[allow(missing_copy_implementations)]
+pub enum TableRetentionPolicyError {
+    /// The retention period is zero or negative.
+    #[error("retention period must be positive, got {0}ns")]
+    NonPositivePeriod(i64),
+}
+
+/// The retention policy of a table.
+///
+/// A table either inherits the retention period of the namespace it is in,
+/// or overrides it with a policy of its own. Like a
+/// [`TablePartitionTemplateOverride`], a table-level policy always takes
+/// precedence over the namespace configuration, and the namespace
+/// configuration applies only when the table has no policy of its own.
+///
+/// ```
+/// use data_types::TableRetentionPolicy;
+///
+/// let namespace_retention_period_ns = Some(42);
+///
+/// let policy = TableRetentionPolicy::default();
+/// assert_eq!(policy.resolve(namespace_retention_period_ns), Some(42));
+///
+/// let policy = TableRetentionPolicy::try_from_period_ns(24).unwrap();
+/// assert_eq!(policy.resolve(namespace_retention_period_ns), Some(24));
+///
+/// let policy = TableRetentionPolicy::infinite();
+/// assert_eq!(policy.resolve(namespace_retention_period_ns), None);
+/// ```
+///
+/// [`TablePartitionTemplateOverride`]:
+///     crate::partition_template::TablePartitionTemplateOverride
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TableRetentionPolicy(Policy);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+enum Policy {
+    /// Use the retention period of the namespace.
+    #[default]
+    Inherit,
+    /// Never drop data, regardless of the namespace retention period.
+    Infinite,
+    /// Drop data older than this many nanoseconds. Always positive.
+    Period(i64),
+}
+
+impl TableRetentionPolicy {
+    /// A policy that never drops data from the table, regardless of the
+    /// retention period of the namespace.
+    pub fn infinite() -> Self {
+        Self(Policy::Infinite)
+    }
+
+    /// A policy that drops data older than `period_ns` nanoseconds from the
+    /// table, regardless of the retention period of the namespace.
+    pub fn try_from_period_ns(period_ns: i64) -> Result<Self, TableRetentionPolicyError> {
+        if period_ns <= 0 {
+            return Err(TableRetentionPolicyError::NonPositivePeriod(period_ns));
+        }
+        Ok(Self(Policy::Period(period_ns)))
+    }
+
+    /// Returns true if this table uses the retention period of its
+    /// namespace.
+    pub fn is_inherited(&self) -> bool {
+        matches!(self.0, Policy::Inherit)
+    }
+
+    /// Resolve the effective retention period in nanoseconds of the table,
+    /// given the retention period of the namespace it is in.
+    ///
+    /// As for the namespace retention period, [`None`] represents an infinite
+    /// duration (i.e. never drop data).
+    pub fn resolve(&self, namespace_retention_period_ns: Option<i64>) -> Option<i64> {
+        match self.0 {
+            Policy::Inherit => namespace_retention_period_ns,
+            Policy::Infinite => None,
+            Policy::Period(v) => Some(v),
+        }
+    }
+
+    /// Returns the nanosecond timestamp before which data in the table is
+    /// outside of the effective retention period at `now`, or [`None`] if
+    /// data is never dropped.
+    pub fn retention_cutoff(
+        &self,
+        namespace_retention_period_ns: Option<i64>,
+        now: Time,
+    ) -> Option<i64> {
+        self.resolve(namespace_retention_period_ns)
+            .map(|period| now.timestamp_nanos().saturating_sub(period))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let inherit = TableRetentionPolicy::default();
+        assert!(inherit.is_inherited());
+        assert_eq!(inherit.resolve(Some(42)), Some(42));
+        assert_eq!(inherit.resolve(None), None);
+
+        let period = TableRetentionPolicy::try_from_period_ns(24).unwrap();
+        assert!(!period.is_inherited());
+        assert_eq!(period.resolve(Some(42)), Some(24));
+        assert_eq!(period.resolve(None), Some(24));
+
+        let infinite = TableRetentionPolicy::infinite();
+        assert!(!infinite.is_inherited());
+        assert_eq!(infinite.resolve(Some(42)), None);
+        assert_eq!(infinite.resolve(None), None);
+    }
+
+    #[test]
+    fn test_invalid_period() {
+        assert_eq!(
+            TableRetentionPolicy::try_from_period_ns(0),
+            Err(TableRetentionPolicyError::NonPositivePeriod(0))
+        );
+        assert_eq!(
+            TableRetentionPolicy::try_from_period_ns(-1),
+            Err(TableRetentionPolicyError::NonPositivePeriod(-1))
+        );
+    }
+
+    #[test]
+    fn test_retention_cutoff() {
+        let now = Time::from_timestamp_nanos(1_000);
+
+        let policy = TableRetentionPolicy::default();
+        assert_eq!(policy.retention_cutoff(Some(100), now), Some(900));
+        assert_eq!(policy.retention_cutoff(None, now), None);
+
+        let policy = TableRetentionPolicy::try_from_period_ns(10).unwrap();
+        assert_eq!(policy.retention_cutoff(Some(100), now), Some(990));
+
+        let policy = TableRetentionPolicy::infinite();
+        assert_eq!(policy.retention_cutoff(Some(100), now), None);
+
+        // The cutoff saturates rather than overflowing.
+        let policy = TableRetentionPolicy::try_from_period_ns(i64::MAX).unwrap();
+        let now = Time::from_timestamp_nanos(-2);
+        assert_eq!(policy.retention_cutoff(None, now), Some(i64::MIN));
+    }
+}
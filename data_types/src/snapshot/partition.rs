@@ -105,6 +105,8 @@ impl PartitionSnapshot {
                     created_at: file.created_at.0,
                     max_l0_created_at: file.max_l0_created_at.0,
                     column_mask: Some(mask.finish().into()),
+                    column_stats: (!file.column_stats.is_empty())
+                        .then(|| (&file.column_stats).into()),
                 }
             })
             .collect::<Vec<_>>();
@@ -193,6 +195,7 @@ impl PartitionSnapshot {
             created_at: Timestamp(file.created_at),
             column_set,
             max_l0_created_at: Timestamp(file.max_l0_created_at),
+            column_stats: file.column_stats.map(Into::into).unwrap_or_default(),
         })
     }
 
@@ -0,0 +1,214 @@
+//! Sets of CPU cores that threads can be pinned to.
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+
+use snafu::{ensure, ResultExt, Snafu};
+
+/// Maximum CPU index that is supported (exclusive).
+///
+/// This is the size of the kernel CPU mask that is used to pin threads.
+pub const MAX_CPUS: usize = 1024;
+
+/// Error while parsing a [`CpuSet`].
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum CpuSetParseError {
+    #[snafu(display("CPU set is empty"))]
+    Empty,
+
+    #[snafu(display("Cannot parse CPU index '{s}': {source}"))]
+    InvalidIndex {
+        s: String,
+        source: std::num::ParseIntError,
+    },
+
+    #[snafu(display("Invalid CPU range '{s}'"))]
+    InvalidRange { s: String },
+
+    #[snafu(display("CPU index {cpu} is out of range, must be smaller than {MAX_CPUS}"))]
+    OutOfRange { cpu: usize },
+}
+
+/// A non-empty set of CPU cores.
+///
+/// Parsed from and displayed as a comma-separated list of cores and inclusive core ranges, e.g. `0-3,8,10-11`
+/// (the same format as Linux's `cpuset` and `taskset --cpu-list`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuSet {
+    cpus: BTreeSet<usize>,
+}
+
+impl CpuSet {
+    /// Create set from the given core indices.
+    pub fn new(cpus: impl IntoIterator<Item = usize>) -> Result<Self, CpuSetParseError> {
+        let cpus = cpus.into_iter().collect::<BTreeSet<_>>();
+        ensure!(!cpus.is_empty(), EmptySnafu);
+        if let Some(cpu) = cpus.last() {
+            ensure!(*cpu < MAX_CPUS, OutOfRangeSnafu { cpu: *cpu });
+        }
+
+        Ok(Self { cpus })
+    }
+
+    /// Core indices in ascending order.
+    pub fn cpus(&self) -> impl Iterator<Item = usize> + '_ {
+        self.cpus.iter().copied()
+    }
+
+    /// Number of cores in this set.
+    pub fn len(&self) -> usize {
+        self.cpus.len()
+    }
+
+    /// Always `false`, sets are never empty.
+    pub fn is_empty(&self) -> bool {
+        self.cpus.is_empty()
+    }
+
+    /// Pin the current thread to the cores of this set.
+    ///
+    /// The OS scheduler may still move the thread between the cores of this set.
+    #[cfg(target_os = "linux")]
+    pub fn pin_current_thread(&self) -> std::io::Result<()> {
+        // SAFETY: `cpu_set_t` is a plain bit mask for which all-zeros is a valid (empty) value and all indices were
+        // checked against `MAX_CPUS` (== `CPU_SETSIZE`) during construction.
+        let res = unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            for cpu in &self.cpus {
+                libc::CPU_SET(*cpu, &mut set);
+            }
+
+            // on linux sched_setaffinity with pid 0 sets the affinity of the current thread
+            // (as opposed to the current process).
+            libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+        };
+
+        if res == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+
+    /// Pin the current thread to the cores of this set.
+    #[cfg(not(target_os = "linux"))]
+    pub fn pin_current_thread(&self) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "pinning threads to CPU cores is not supported on this platform",
+        ))
+    }
+}
+
+impl FromStr for CpuSet {
+    type Err = CpuSetParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_cpu = |s: &str| {
+            s.trim()
+                .parse::<usize>()
+                .context(InvalidIndexSnafu { s: s.trim() })
+        };
+
+        let mut cpus = BTreeSet::new();
+        for part in s.split(',').filter(|part| !part.trim().is_empty()) {
+            match part.split_once('-') {
+                Some((start, end)) => {
+                    let start = parse_cpu(start)?;
+                    let end = parse_cpu(end)?;
+                    ensure!(start <= end, InvalidRangeSnafu { s: part.trim() });
+                    ensure!(end < MAX_CPUS, OutOfRangeSnafu { cpu: end });
+                    cpus.extend(start..=end);
+                }
+                None => {
+                    cpus.insert(parse_cpu(part)?);
+                }
+            }
+        }
+
+        Self::new(cpus)
+    }
+}
+
+impl Display for CpuSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // collapse consecutive cores into ranges
+        let mut ranges: Vec<(usize, usize)> = vec![];
+        for cpu in self.cpus() {
+            match ranges.last_mut() {
+                Some((_start, end)) if *end + 1 == cpu => *end = cpu,
+                _ => ranges.push((cpu, cpu)),
+            }
+        }
+
+        for (i, (start, end)) in ranges.into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+
+            if start == end {
+                write!(f, "{start}")?;
+            } else {
+                write!(f, "{start}-{end}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_display_roundtrip() {
+        for (s, expected_cpus, expected_display) in [
+            ("0", vec![0], "0"),
+            ("0-3", vec![0, 1, 2, 3], "0-3"),
+            ("0-3,8,10-11", vec![0, 1, 2, 3, 8, 10, 11], "0-3,8,10-11"),
+            (" 3 , 1,2 ,", vec![1, 2, 3], "1-3"),
+            ("5,1-2,2-3", vec![1, 2, 3, 5], "1-3,5"),
+            ("1023", vec![1023], "1023"),
+        ] {
+            let set = CpuSet::from_str(s).unwrap();
+            assert_eq!(set.cpus().collect::<Vec<_>>(), expected_cpus, "{s}");
+            assert_eq!(set.len(), expected_cpus.len());
+            assert_eq!(set.to_string(), expected_display);
+            assert_eq!(CpuSet::from_str(&set.to_string()).unwrap(), set);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (s, expected) in [
+            ("", "CPU set is empty"),
+            (",", "CPU set is empty"),
+            (
+                "a",
+                "Cannot parse CPU index 'a': invalid digit found in string",
+            ),
+            (
+                "1-",
+                "Cannot parse CPU index '': cannot parse integer from empty string",
+            ),
+            ("3-1", "Invalid CPU range '3-1'"),
+            (
+                "1024",
+                "CPU index 1024 is out of range, must be smaller than 1024",
+            ),
+            (
+                "0-5000",
+                "CPU index 5000 is out of range, must be smaller than 1024",
+            ),
+        ] {
+            let err = CpuSet::from_str(s).unwrap_err();
+            assert_eq!(err.to_string(), expected, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_new_empty() {
+        let err = CpuSet::new([]).unwrap_err();
+        assert!(matches!(err, CpuSetParseError::Empty));
+    }
+}
@@ -15,10 +15,16 @@
     unused_crate_dependencies
 )]
 
+mod cpu_set;
+mod runtime_settings;
+
+pub use cpu_set::{CpuSet, CpuSetParseError, MAX_CPUS};
+pub use runtime_settings::RuntimeSettings;
+
 use metric::Registry;
 use snafu::Snafu;
 #[cfg(tokio_unstable)]
-use tokio_metrics_bridge::setup_tokio_metrics;
+use tokio_metrics_bridge::{replace_tokio_metrics, setup_tokio_metrics};
 use tokio_watchdog::WatchdogConfig;
 // Workaround for "unused crate" lint false positives.
 #[cfg(not(tokio_unstable))]
@@ -46,7 +52,7 @@ use futures::{
     ready, Future, FutureExt, TryFutureExt,
 };
 
-use observability_deps::tracing::warn;
+use observability_deps::tracing::{info, warn};
 
 /// Task that can be added to the executor-internal queue.
 ///
@@ -80,6 +86,9 @@ pub enum JobError {
 
     #[snafu(display("Panic: {msg}"))]
     Panic { msg: String },
+
+    #[snafu(display("Job was cancelled"))]
+    Cancelled,
 }
 
 /// Job within the executor.
@@ -114,6 +123,9 @@ impl<T> Future for Job<T> {
         let this = self.project();
         match ready!(this.rx.poll(cx)) {
             Ok(res) => std::task::Poll::Ready(res),
+            Err(_) if this.cancel.is_cancelled() => {
+                std::task::Poll::Ready(Err(JobError::Cancelled))
+            }
             Err(_) => std::task::Poll::Ready(Err(JobError::WorkerGone)),
         }
     }
@@ -134,8 +146,8 @@ impl<T> PinnedDrop for Job<T> {
 pub struct DedicatedExecutor {
     state: Arc<Mutex<State>>,
 
-    /// Number of threads
-    num_threads: NonZeroUsize,
+    /// CPU cores that all threads of this executor are pinned to.
+    cpu_set: Option<CpuSet>,
 
     /// Used for testing.
     ///
@@ -153,13 +165,40 @@ struct State {
     requests: Option<std::sync::mpsc::Sender<Task>>,
 
     /// Receiver side indicating that shutdown is complete.
-    completed_shutdown: Shared<BoxFuture<'static, Result<(), Arc<RecvError>>>>,
+    completed_shutdown: CompletedShutdown,
 
     /// Task counter (uses Arc strong count).
     task_refs: Arc<()>,
 
     /// The inner thread that can be used to join during drop.
     thread: Option<std::thread::JoinHandle<()>>,
+
+    /// Runtimes replaced by [`DedicatedExecutor::resize`] that are still finishing their outstanding tasks.
+    draining: Vec<DrainingRuntime>,
+
+    /// Number of threads of the current runtime.
+    num_threads: NonZeroUsize,
+
+    /// Configuration used to (re-)create the runtime.
+    thread_name: &'static str,
+    runtime_settings: RuntimeSettings,
+    thread_counter: Arc<AtomicUsize>,
+    metric_registry: Arc<Registry>,
+}
+
+/// Receiver side indicating that a runtime completed its shutdown.
+type CompletedShutdown = Shared<BoxFuture<'static, Result<(), Arc<RecvError>>>>;
+
+/// A runtime that no longer accepts new tasks but may still run previously submitted ones.
+struct DrainingRuntime {
+    completed_shutdown: CompletedShutdown,
+    thread: std::thread::JoinHandle<()>,
+}
+
+impl DrainingRuntime {
+    fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
 }
 
 // IMPORTANT: Implement `Drop` for `State`, NOT for `DedicatedExecutor`, because the executor can be cloned and clones
@@ -176,8 +215,11 @@ impl Drop for State {
             warn!("DedicatedExecutor dropped without waiting for worker termination",);
         }
 
-        // join thread but don't care about the results
+        // join threads but don't care about the results
         self.thread.take().expect("not dropped yet").join().ok();
+        for runtime in self.draining.drain(..) {
+            runtime.thread.join().ok();
+        }
     }
 }
 
@@ -187,7 +229,10 @@ const WORKER_PRIORITY: i32 = 10;
 impl std::fmt::Debug for DedicatedExecutor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Avoid taking the mutex in debug formatting
-        write!(f, "DedicatedExecutor")
+        match &self.cpu_set {
+            Some(cpu_set) => write!(f, "DedicatedExecutor(cpus={cpu_set})"),
+            None => write!(f, "DedicatedExecutor"),
+        }
     }
 }
 
@@ -196,6 +241,8 @@ static TESTING_EXECUTOR: Lazy<DedicatedExecutor> = Lazy::new(|| {
     DedicatedExecutor::new_inner(
         "testing",
         NonZeroUsize::new(1).unwrap(),
+        None,
+        RuntimeSettings::default(),
         Arc::new(Registry::default()),
         true,
     )
@@ -223,83 +270,94 @@ impl DedicatedExecutor {
         num_threads: NonZeroUsize,
         metric_registry: Arc<Registry>,
     ) -> Self {
-        Self::new_inner(thread_name, num_threads, metric_registry, false)
+        Self::new_inner(
+            thread_name,
+            num_threads,
+            None,
+            RuntimeSettings::default(),
+            metric_registry,
+            false,
+        )
+    }
+
+    /// Creates a new `DedicatedExecutor` like [`new`](Self::new) but pins all its threads to the given CPU cores.
+    ///
+    /// This keeps query work away from cores that are reserved for other purposes (e.g. the IO runtime) and
+    /// improves latency consistency on large multi-socket machines, e.g. by choosing the cores of a single NUMA node.
+    /// Failing to pin a thread is logged but not fatal.
+    pub fn new_with_cpu_set(
+        thread_name: &'static str,
+        num_threads: NonZeroUsize,
+        cpu_set: CpuSet,
+        metric_registry: Arc<Registry>,
+    ) -> Self {
+        Self::new_inner(
+            thread_name,
+            num_threads,
+            Some(cpu_set),
+            RuntimeSettings::default(),
+            metric_registry,
+            false,
+        )
+    }
+
+    /// Creates a new `DedicatedExecutor` like [`new`](Self::new) but optionally pins all its threads to the given
+    /// CPU cores (see [`new_with_cpu_set`](Self::new_with_cpu_set)) and builds its runtime with the given settings.
+    ///
+    /// The settings are kept when the executor is [resized](Self::resize).
+    pub fn new_with_runtime_settings(
+        thread_name: &'static str,
+        num_threads: NonZeroUsize,
+        cpu_set: Option<CpuSet>,
+        runtime_settings: RuntimeSettings,
+        metric_registry: Arc<Registry>,
+    ) -> Self {
+        Self::new_inner(
+            thread_name,
+            num_threads,
+            cpu_set,
+            runtime_settings,
+            metric_registry,
+            false,
+        )
     }
 
     fn new_inner(
         thread_name: &'static str,
         num_threads: NonZeroUsize,
+        cpu_set: Option<CpuSet>,
+        runtime_settings: RuntimeSettings,
         metric_registry: Arc<Registry>,
         testing: bool,
     ) -> Self {
         let thread_counter = Arc::new(AtomicUsize::new(1));
 
-        let (tx_tasks, rx_tasks) = std::sync::mpsc::channel::<Task>();
-        let (tx_shutdown, rx_shutdown) = tokio::sync::oneshot::channel();
-
-        let thread = std::thread::Builder::new()
-            .name(format!("{thread_name} driver"))
-            .spawn(move || {
-                let runtime = tokio::runtime::Builder::new_multi_thread()
-                    .enable_all()
-                    .thread_name_fn(move || {
-                        format!(
-                            "{} {}",
-                            thread_name,
-                            thread_counter.fetch_add(1, Ordering::SeqCst)
-                        )
-                    })
-                    .worker_threads(num_threads.get())
-                    .on_thread_start(move || set_current_thread_priority(WORKER_PRIORITY))
-                    .build()
-                    .expect("Creating tokio runtime");
-
-                WatchdogConfig::new(runtime.handle(), &metric_registry)
-                    .with_runtime_name(thread_name)
-                    .with_tick_duration(Duration::from_millis(100))
-                    .with_warn_duration(Duration::from_millis(100))
-                    .install();
-
-                #[cfg(tokio_unstable)]
-                setup_tokio_metrics(runtime.metrics(), thread_name, metric_registry);
-                #[cfg(not(tokio_unstable))]
-                let _ = metric_registry;
-
-                runtime.block_on(async move {
-                    // Dropping the tokio runtime only waits for tasks to yield not to complete
-                    //
-                    // We therefore use a RwLock to wait for tasks to complete
-                    let join = Arc::new(tokio::sync::RwLock::new(()));
-
-                    while let Ok(task) = rx_tasks.recv() {
-                        let join = Arc::clone(&join);
-                        let handle = join.read_owned().await;
-
-                        tokio::task::spawn(async move {
-                            task.run().await;
-                            std::mem::drop(handle);
-                        });
-                    }
-
-                    // Wait for all tasks to finish
-                    let _guard = join.write().await;
-
-                    // signal shutdown, but it's OK if the other side is gone
-                    tx_shutdown.send(()).ok();
-                })
-            })
-            .expect("executor setup");
+        let (tx_tasks, completed_shutdown, thread) = spawn_runtime(
+            thread_name,
+            num_threads,
+            cpu_set.clone(),
+            runtime_settings,
+            Arc::clone(&thread_counter),
+            Arc::clone(&metric_registry),
+            false,
+        );
 
         let state = State {
             requests: Some(tx_tasks),
             task_refs: Arc::new(()),
-            completed_shutdown: rx_shutdown.map_err(Arc::new).boxed().shared(),
+            completed_shutdown,
             thread: Some(thread),
+            draining: vec![],
+            num_threads,
+            thread_name,
+            runtime_settings,
+            thread_counter,
+            metric_registry,
         };
 
         Self {
             state: Arc::new(Mutex::new(state)),
-            num_threads,
+            cpu_set,
             testing,
         }
     }
@@ -313,7 +371,66 @@ impl DedicatedExecutor {
 
     /// Number of threads that back this executor.
     pub fn num_threads(&self) -> NonZeroUsize {
-        self.num_threads
+        self.state.lock().num_threads
+    }
+
+    /// CPU cores that the threads of this executor are pinned to, if any.
+    pub fn cpu_set(&self) -> Option<&CpuSet> {
+        self.cpu_set.as_ref()
+    }
+
+    /// Change the number of threads that back this executor.
+    ///
+    /// A new runtime with `num_threads` worker threads is started and all subsequently spawned tasks are run on it.
+    /// The previous runtime stops accepting new tasks and shuts down once its outstanding tasks have completed, so
+    /// running tasks are never interrupted. [`join`](Self::join) waits for both runtimes.
+    ///
+    /// This is a no-op if the number of threads is unchanged or if the executor was shut down.
+    pub fn resize(&self, num_threads: NonZeroUsize) {
+        if self.testing {
+            return;
+        }
+
+        let mut state = self.state.lock();
+        if state.num_threads == num_threads {
+            return;
+        }
+        if state.requests.is_none() {
+            warn!("tried to resize an executor that was shutdown");
+            return;
+        }
+
+        let (tx_tasks, completed_shutdown, thread) = spawn_runtime(
+            state.thread_name,
+            num_threads,
+            self.cpu_set.clone(),
+            state.runtime_settings,
+            Arc::clone(&state.thread_counter),
+            Arc::clone(&state.metric_registry),
+            true,
+        );
+
+        // dropping the old sender makes the old runtime drain and exit
+        state.requests = Some(tx_tasks);
+        let old = DrainingRuntime {
+            completed_shutdown: std::mem::replace(
+                &mut state.completed_shutdown,
+                completed_shutdown,
+            ),
+            thread: state.thread.replace(thread).expect("not dropped yet"),
+        };
+
+        // forget about runtimes that are done by now
+        state.draining.retain(|runtime| !runtime.is_finished());
+        state.draining.push(old);
+
+        info!(
+            thread_name = state.thread_name,
+            from = state.num_threads.get(),
+            to = num_threads.get(),
+            "resized DedicatedExecutor",
+        );
+        state.num_threads = num_threads;
     }
 
     /// Runs the specified Future (and any tasks it spawns) on the
@@ -322,6 +439,28 @@ impl DedicatedExecutor {
     /// Currently all tasks are added to the tokio executor
     /// immediately and compete for the threadpool's resources.
     pub fn spawn<T>(&self, task: T) -> Job<T::Output>
+    where
+        T: Future + Send + 'static,
+        T::Output: Send + 'static,
+    {
+        self.spawn_inner(task, CancellationToken::new())
+    }
+
+    /// Runs the specified Future (and any tasks it spawns) on the
+    /// `DedicatedExecutor` until it completes or `cancel` is cancelled.
+    ///
+    /// This allows a query to promptly abort all its tasks, e.g. when it
+    /// times out, instead of running to completion in the background. A
+    /// cancelled [`Job`] returns [`JobError::Cancelled`].
+    pub fn spawn_with_cancellation<T>(&self, task: T, cancel: &CancellationToken) -> Job<T::Output>
+    where
+        T: Future + Send + 'static,
+        T::Output: Send + 'static,
+    {
+        self.spawn_inner(task, cancel.child_token())
+    }
+
+    fn spawn_inner<T>(&self, task: T, cancel: CancellationToken) -> Job<T::Output>
     where
         T: Future + Send + 'static,
         T::Output: Send + 'static,
@@ -345,7 +484,6 @@ impl DedicatedExecutor {
                 warn!("Spawned task output ignored: receiver dropped")
             }
         });
-        let cancel = CancellationToken::new();
         let mut state = self.state.lock();
         let task = Task {
             fut,
@@ -406,15 +544,133 @@ impl DedicatedExecutor {
 
         self.shutdown();
 
-        // get handle mutex is held
-        let handle = {
+        // get handles mutex is held
+        let handles = {
             let state = self.state.lock();
-            state.completed_shutdown.clone()
+            std::iter::once(state.completed_shutdown.clone())
+                .chain(
+                    state
+                        .draining
+                        .iter()
+                        .map(|runtime| runtime.completed_shutdown.clone()),
+                )
+                .collect::<Vec<_>>()
         };
 
         // wait for completion while not holding the mutex to avoid
         // deadlocks
-        handle.await.expect("Thread died?")
+        for handle in handles {
+            handle.await.expect("Thread died?")
+        }
+    }
+}
+
+/// Spawn a driver thread running a new tokio runtime with `num_threads` worker threads.
+///
+/// The runtime runs tasks received via the returned sender until all senders are dropped, then waits for all tasks
+/// to complete and signals the returned [`CompletedShutdown`].
+///
+/// If `cpu_set` is set, the driver thread and all runtime threads are pinned to these cores.
+///
+/// If `replace_metrics` is set, the runtime metrics replace those of a previous runtime with the same name.
+fn spawn_runtime(
+    thread_name: &'static str,
+    num_threads: NonZeroUsize,
+    cpu_set: Option<CpuSet>,
+    runtime_settings: RuntimeSettings,
+    thread_counter: Arc<AtomicUsize>,
+    metric_registry: Arc<Registry>,
+    replace_metrics: bool,
+) -> (
+    std::sync::mpsc::Sender<Task>,
+    CompletedShutdown,
+    std::thread::JoinHandle<()>,
+) {
+    let (tx_tasks, rx_tasks) = std::sync::mpsc::channel::<Task>();
+    let (tx_shutdown, rx_shutdown) = tokio::sync::oneshot::channel();
+
+    let thread = std::thread::Builder::new()
+        .name(format!("{thread_name} driver"))
+        .spawn(move || {
+            if let Some(cpu_set) = &cpu_set {
+                pin_current_thread(thread_name, cpu_set);
+            }
+
+            let runtime = runtime_settings
+                .apply(&mut tokio::runtime::Builder::new_multi_thread())
+                .enable_all()
+                .thread_name_fn(move || {
+                    format!(
+                        "{} {}",
+                        thread_name,
+                        thread_counter.fetch_add(1, Ordering::SeqCst)
+                    )
+                })
+                .worker_threads(num_threads.get())
+                .on_thread_start(move || {
+                    set_current_thread_priority(WORKER_PRIORITY);
+                    if let Some(cpu_set) = &cpu_set {
+                        pin_current_thread(thread_name, cpu_set);
+                    }
+                })
+                .build()
+                .expect("Creating tokio runtime");
+
+            WatchdogConfig::new(runtime.handle(), &metric_registry)
+                .with_runtime_name(thread_name)
+                .with_tick_duration(Duration::from_millis(100))
+                .with_warn_duration(Duration::from_millis(100))
+                .install();
+
+            #[cfg(tokio_unstable)]
+            if replace_metrics {
+                replace_tokio_metrics(runtime.metrics(), thread_name, metric_registry);
+            } else {
+                setup_tokio_metrics(runtime.metrics(), thread_name, metric_registry);
+            }
+            #[cfg(not(tokio_unstable))]
+            let _ = (metric_registry, replace_metrics);
+
+            runtime.block_on(async move {
+                // Dropping the tokio runtime only waits for tasks to yield not to complete
+                //
+                // We therefore use a RwLock to wait for tasks to complete
+                let join = Arc::new(tokio::sync::RwLock::new(()));
+
+                while let Ok(task) = rx_tasks.recv() {
+                    let join = Arc::clone(&join);
+                    let handle = join.read_owned().await;
+
+                    tokio::task::spawn(async move {
+                        task.run().await;
+                        std::mem::drop(handle);
+                    });
+                }
+
+                // Wait for all tasks to finish
+                let _guard = join.write().await;
+
+                // signal shutdown, but it's OK if the other side is gone
+                tx_shutdown.send(()).ok();
+            })
+        })
+        .expect("executor setup");
+
+    (
+        tx_tasks,
+        rx_shutdown.map_err(Arc::new).boxed().shared(),
+        thread,
+    )
+}
+
+fn pin_current_thread(thread_name: &'static str, cpu_set: &CpuSet) {
+    if let Err(e) = cpu_set.pin_current_thread() {
+        warn!(
+            thread_name,
+            cpus = %cpu_set,
+            %e,
+            "Failed to pin DedicatedExecutor thread to CPU set",
+        );
     }
 }
 
@@ -434,6 +690,7 @@ fn set_current_thread_priority(prio: i32) {
 mod tests {
     use super::*;
     use std::{
+        num::NonZeroU32,
         panic::panic_any,
         sync::{Arc, Barrier},
         time::Duration,
@@ -534,6 +791,84 @@ mod tests {
         exec.join().await;
     }
 
+    #[cfg(target_os = "linux")]
+    fn get_current_thread_cpus() -> Vec<usize> {
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        let res =
+            unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) };
+        assert_eq!(res, 0);
+
+        (0..MAX_CPUS)
+            .filter(|cpu| unsafe { libc::CPU_ISSET(*cpu, &set) })
+            .collect()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn cpu_pinning() {
+        // pick a core that this process is allowed to run on
+        let cpu = get_current_thread_cpus()[0];
+        let cpu_set = CpuSet::new([cpu]).unwrap();
+
+        let exec = DedicatedExecutor::new_with_cpu_set(
+            "Test DedicatedExecutor",
+            NonZeroUsize::new(2).unwrap(),
+            cpu_set.clone(),
+            Arc::new(Registry::default()),
+        );
+        assert_eq!(exec.cpu_set(), Some(&cpu_set));
+        assert_eq!(
+            format!("{exec:?}"),
+            format!("DedicatedExecutor(cpus={cpu})")
+        );
+
+        let dedicated_task = exec.spawn(async move { get_current_thread_cpus() });
+        assert_eq!(dedicated_task.await.unwrap(), vec![cpu]);
+
+        // pinning survives resizing
+        exec.resize(NonZeroUsize::new(1).unwrap());
+        let dedicated_task = exec.spawn(async move { get_current_thread_cpus() });
+        assert_eq!(dedicated_task.await.unwrap(), vec![cpu]);
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn no_cpu_pinning() {
+        let exec = exec();
+        assert_eq!(exec.cpu_set(), None);
+        assert_eq!(format!("{exec:?}"), "DedicatedExecutor");
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn runtime_settings() {
+        let exec = DedicatedExecutor::new_with_runtime_settings(
+            "Test DedicatedExecutor",
+            NonZeroUsize::new(1).unwrap(),
+            None,
+            RuntimeSettings {
+                max_blocking_threads: Some(NonZeroUsize::new(1).unwrap()),
+                thread_stack_size: Some(NonZeroUsize::new(4 * 1024 * 1024).unwrap()),
+                event_interval: Some(NonZeroU32::new(7).unwrap()),
+            },
+            Arc::new(Registry::default()),
+        );
+
+        let dedicated_task =
+            exec.spawn(async move { tokio::task::spawn_blocking(|| 42).await.unwrap() });
+        assert_eq!(dedicated_task.await.unwrap(), 42);
+
+        // settings survive resizing
+        exec.resize(NonZeroUsize::new(2).unwrap());
+        let dedicated_task =
+            exec.spawn(async move { tokio::task::spawn_blocking(|| 11).await.unwrap() });
+        assert_eq!(dedicated_task.await.unwrap(), 11);
+
+        exec.join().await;
+    }
+
     #[tokio::test]
     async fn worker_priority() {
         let exec = exec2();
@@ -745,6 +1080,34 @@ mod tests {
         exec.join().await;
     }
 
+    #[tokio::test]
+    async fn cancellation_token() {
+        let exec = exec();
+        let cancel = CancellationToken::new();
+
+        // create two blocked tasks linked to the same token
+        let barrier1 = Arc::new(AsyncBarrier::new(2));
+        let dedicated_task1 =
+            exec.spawn_with_cancellation(do_work_async(11, Arc::clone(&barrier1)), &cancel);
+        let barrier2 = Arc::new(AsyncBarrier::new(2));
+        let dedicated_task2 =
+            exec.spawn_with_cancellation(do_work_async(22, Arc::clone(&barrier2)), &cancel);
+        assert_eq!(exec.tasks(), 2);
+
+        // dropping one job does not cancel the token
+        drop(dedicated_task1);
+        wait_for_tasks(&exec, 1).await;
+        assert!(!cancel.is_cancelled());
+
+        // cancel remaining task
+        cancel.cancel();
+        let err = dedicated_task2.await.unwrap_err();
+        assert!(matches!(err, JobError::Cancelled), "{err}");
+        wait_for_tasks(&exec, 0).await;
+
+        exec.join().await;
+    }
+
     #[tokio::test]
     async fn detach_receiver() {
         // create empty executor
@@ -777,6 +1140,64 @@ mod tests {
         exec.join().await;
     }
 
+    #[tokio::test]
+    async fn resize() {
+        let exec = exec();
+        assert_eq!(exec.num_threads().get(), 1);
+
+        exec.resize(NonZeroUsize::new(2).unwrap());
+        assert_eq!(exec.num_threads().get(), 2);
+
+        // the two tasks can only both make progress if the new runtime has two threads
+        let barrier = Arc::new(Barrier::new(3));
+        let dedicated_task1 = exec.spawn(do_work(11, Arc::clone(&barrier)));
+        let dedicated_task2 = exec.spawn(do_work(42, Arc::clone(&barrier)));
+        barrier.wait();
+        assert_eq!(dedicated_task1.await.unwrap(), 11);
+        assert_eq!(dedicated_task2.await.unwrap(), 42);
+
+        exec.resize(NonZeroUsize::new(1).unwrap());
+        assert_eq!(exec.num_threads().get(), 1);
+        assert_eq!(exec.spawn(async { 1 }).await.unwrap(), 1);
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn resize_drains_running_tasks() {
+        let barrier = Arc::new(AsyncBarrier::new(2));
+
+        let exec = exec();
+        let dedicated_task = exec.spawn(do_work_async(42, Arc::clone(&barrier)));
+        wait_for_tasks(&exec, 1).await;
+
+        // the running task is not interrupted by the resize
+        exec.resize(NonZeroUsize::new(2).unwrap());
+        assert_eq!(exec.tasks(), 1);
+        assert_eq!(exec.spawn(async { 11 }).await.unwrap(), 11);
+
+        barrier.wait().await;
+        assert_eq!(dedicated_task.await.unwrap(), 42);
+        wait_for_tasks(&exec, 0).await;
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn resize_after_shutdown() {
+        let exec = exec();
+        exec.join().await;
+
+        exec.resize(NonZeroUsize::new(2).unwrap());
+        assert_eq!(exec.num_threads().get(), 1);
+
+        let err = exec.spawn(async { 11 }).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Worker thread gone, executor was likely shut down"
+        );
+    }
+
     /// Wait for the barrier and then return `result`
     async fn do_work(result: usize, barrier: Arc<Barrier>) -> usize {
         barrier.wait();
@@ -0,0 +1,66 @@
+//! Tuning knobs for tokio runtimes.
+use std::num::{NonZeroU32, NonZeroUsize};
+
+/// Settings of a multi-threaded tokio runtime, apart from its number of worker threads.
+///
+/// Settings that are [`None`] keep the tokio defaults.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuntimeSettings {
+    /// Maximum number of threads spawned for blocking operations, see
+    /// [`Builder::max_blocking_threads`](tokio::runtime::Builder::max_blocking_threads).
+    pub max_blocking_threads: Option<NonZeroUsize>,
+
+    /// Stack size of the worker and blocking threads in bytes, see
+    /// [`Builder::thread_stack_size`](tokio::runtime::Builder::thread_stack_size).
+    pub thread_stack_size: Option<NonZeroUsize>,
+
+    /// Number of scheduler ticks after which the runtime polls for external events (e.g. IO and timers), see
+    /// [`Builder::event_interval`](tokio::runtime::Builder::event_interval).
+    pub event_interval: Option<NonZeroU32>,
+}
+
+impl RuntimeSettings {
+    /// Apply the settings to `builder`.
+    pub fn apply<'a>(
+        &self,
+        builder: &'a mut tokio::runtime::Builder,
+    ) -> &'a mut tokio::runtime::Builder {
+        if let Some(max_blocking_threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(max_blocking_threads.get());
+        }
+        if let Some(thread_stack_size) = self.thread_stack_size {
+            builder.thread_stack_size(thread_stack_size.get());
+        }
+        if let Some(event_interval) = self.event_interval {
+            builder.event_interval(event_interval.get());
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let settings = RuntimeSettings {
+            max_blocking_threads: Some(NonZeroUsize::new(1).unwrap()),
+            thread_stack_size: Some(NonZeroUsize::new(4 * 1024 * 1024).unwrap()),
+            event_interval: Some(NonZeroU32::new(7).unwrap()),
+        };
+
+        let runtime = settings
+            .apply(tokio::runtime::Builder::new_multi_thread().worker_threads(1))
+            .build()
+            .unwrap();
+
+        // blocking tasks still complete with a single blocking thread
+        let res = runtime.block_on(async {
+            let a = tokio::task::spawn_blocking(|| 1);
+            let b = tokio::task::spawn_blocking(|| 2);
+            a.await.unwrap() + b.await.unwrap()
+        });
+        assert_eq!(res, 3);
+    }
+}
@@ -1,7 +1,11 @@
 //! IOx FlightSQL Command structures
 
-use std::fmt::Display;
+use std::{fmt::Display, io::Cursor};
 
+use arrow::{
+    ipc::{reader::StreamReader, writer::StreamWriter},
+    record_batch::RecordBatch,
+};
 use arrow_flight::sql::{
     ActionClosePreparedStatementRequest, ActionCreatePreparedStatementRequest, Any,
     CommandGetCatalogs, CommandGetCrossReference, CommandGetDbSchemas, CommandGetExportedKeys,
@@ -10,10 +14,28 @@ use arrow_flight::sql::{
 };
 use bytes::Bytes;
 use prost::Message;
-use snafu::ResultExt;
+use snafu::{ensure, OptionExt, ResultExt};
 
 use crate::error::*;
 
+/// First byte of an encoded [`PreparedStatementHandle`] with bound parameters.
+///
+/// This byte never occurs in UTF-8, so these handles cannot be confused with handles that only
+/// consist of the query text.
+const HANDLE_WITH_PARAMS_MARKER: u8 = 0xFF;
+
+/// Encoding of a [`PreparedStatementHandle`] with bound parameters (after the marker byte).
+#[derive(Clone, PartialEq, prost::Message)]
+struct HandleWithParams {
+    /// The raw SQL query text
+    #[prost(string, tag = "1")]
+    query: String,
+
+    /// Arrow IPC stream that contains a single row of parameter values
+    #[prost(bytes = "bytes", tag = "2")]
+    params: Bytes,
+}
+
 /// Represents a prepared statement "handle". IOx passes all state
 /// required to run the prepared statement back and forth to the
 /// client, so any querier instance can run it
@@ -21,11 +43,17 @@ use crate::error::*;
 pub struct PreparedStatementHandle {
     /// The raw SQL query text
     query: String,
+
+    /// Bound parameter values, encoded as Arrow IPC stream, if any
+    params: Option<Bytes>,
 }
 
 impl PreparedStatementHandle {
     pub fn new(query: String) -> Self {
-        Self { query }
+        Self {
+            query,
+            params: None,
+        }
     }
 
     /// return the query
@@ -33,29 +61,97 @@ impl PreparedStatementHandle {
         self.query.as_ref()
     }
 
+    /// Bind parameter values to this prepared statement.
+    ///
+    /// `params` must contain exactly one row. The `n`-th column provides the
+    /// value for the `$n` placeholder (starting at 1), column names are ignored.
+    pub fn with_params(self, params: &RecordBatch) -> Result<Self> {
+        ensure!(
+            params.num_rows() == 1,
+            InvalidParametersSnafu {
+                num_rows: params.num_rows()
+            }
+        );
+
+        let mut writer = StreamWriter::try_new(vec![], &params.schema())?;
+        writer.write(params)?;
+        let params = writer.into_inner()?;
+
+        Ok(Self {
+            query: self.query,
+            params: Some(Bytes::from(params)),
+        })
+    }
+
+    /// return the bound parameter values, if any
+    pub fn params(&self) -> Result<Option<RecordBatch>> {
+        let Some(params) = &self.params else {
+            return Ok(None);
+        };
+
+        let mut reader = StreamReader::try_new(Cursor::new(params), None)?;
+        let batch = reader
+            .next()
+            .transpose()?
+            .context(InvalidParametersSnafu { num_rows: 0usize })?;
+        Ok(Some(batch))
+    }
+
     fn try_decode(handle: Bytes) -> Result<Self> {
-        // Note: in IOx  handles are the entire decoded query
+        // Note: in IOx  handles are the entire decoded query, optionally
+        // with the bound parameter values
         // It will likely need to get more sophisticated as part of
         // https://github.com/influxdata/influxdb_iox/issues/6699
-        let query = String::from_utf8(handle.to_vec()).context(InvalidHandleSnafu)?;
-        Ok(Self { query })
+        match handle.first() {
+            Some(&HANDLE_WITH_PARAMS_MARKER) => {
+                let HandleWithParams { query, params } =
+                    HandleWithParams::decode(handle.slice(1..))?;
+                Ok(Self {
+                    query,
+                    params: Some(params),
+                })
+            }
+            _ => {
+                let query = String::from_utf8(handle.to_vec()).context(InvalidHandleSnafu)?;
+                Ok(Self {
+                    query,
+                    params: None,
+                })
+            }
+        }
     }
 
     fn encode(self) -> Bytes {
-        Bytes::from(self.query.into_bytes())
+        match self.params {
+            Some(params) => {
+                let msg = HandleWithParams {
+                    query: self.query,
+                    params,
+                };
+                let mut buf = Vec::with_capacity(1 + msg.encoded_len());
+                buf.push(HANDLE_WITH_PARAMS_MARKER);
+                msg.encode(&mut buf)
+                    .expect("Vec<u8> provides capacity as needed");
+                Bytes::from(buf)
+            }
+            None => Bytes::from(self.query.into_bytes()),
+        }
     }
 }
 
 impl Display for PreparedStatementHandle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Pepared({})", self.query)
+        match self.params {
+            Some(_) => write!(f, "Pepared({}, with params)", self.query),
+            None => write!(f, "Pepared({})", self.query),
+        }
     }
 }
 
 /// Encode a PreparedStatementHandle as Bytes
 impl From<PreparedStatementHandle> for Bytes {
     fn from(value: PreparedStatementHandle) -> Self {
-        Self::from(value.query.into_bytes())
+        value.encode()
     }
 }
 
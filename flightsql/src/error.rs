@@ -19,6 +19,12 @@ pub enum Error {
     #[snafu(display("Invalid PreparedStatement handle (invalid UTF-8:) {}", source))]
     InvalidHandle { source: FromUtf8Error },
 
+    #[snafu(display(
+        "Invalid PreparedStatement parameters: expected exactly one row, got {}",
+        num_rows
+    ))]
+    InvalidParameters { num_rows: usize },
+
     #[snafu(display("{}", source))]
     #[snafu(context(false))]
     Flight { source: FlightError },
@@ -3,6 +3,7 @@ use std::sync::Arc;
 
 use arrow::{
     array::{ArrayRef, StringArray},
+    compute::concat_batches,
     datatypes::{DataType, Field, Schema, SchemaRef},
     error::ArrowError,
     ipc::writer::IpcWriteOptions,
@@ -23,12 +24,14 @@ use datafusion::{
     common::ParamValues,
     logical_expr::{LogicalPlan, TableType},
     physical_plan::ExecutionPlan,
+    scalar::ScalarValue,
     sql::TableReference,
 };
 use iox_query::{exec::IOxSessionContext, QueryNamespace};
 use observability_deps::tracing::debug;
 use once_cell::sync::Lazy;
 use prost::Message;
+use snafu::{ensure, OptionExt};
 
 use crate::{error::*, sql_info::iox_sql_info_data, xdbc_type_info::xdbc_type_info_data};
 use crate::{FlightSQLCommand, PreparedStatementHandle};
@@ -56,7 +59,7 @@ impl FlightSQLPlanner {
                 get_schema_for_query(&query, ctx).await
             }
             FlightSQLCommand::CommandPreparedStatementQuery(handle) => {
-                get_schema_for_query(handle.query(), ctx).await
+                get_schema_for_prepared_statement(&handle, ctx).await
             }
             FlightSQLCommand::CommandGetSqlInfo(CommandGetSqlInfo { .. }) => {
                 Ok(iox_sql_info_data().schema())
@@ -110,7 +113,15 @@ impl FlightSQLPlanner {
             FlightSQLCommand::CommandPreparedStatementQuery(handle) => {
                 let query = handle.query();
                 debug!(%query, "Planning FlightSQL prepared query");
-                Ok(ctx.sql_to_physical_plan_with_params(query, params).await?)
+                let plan = ctx.sql_to_prepared_logical_plan(query).await?;
+
+                // parameters bound via `DoPut` take precedence
+                let params = match handle.params()? {
+                    Some(bound) => bind_params(&plan, &bound)?,
+                    None => params.into(),
+                };
+                let plan = plan.with_param_values(params)?;
+                Ok(ctx.create_physical_plan(&plan).await?)
             }
             FlightSQLCommand::CommandGetSqlInfo(cmd) => {
                 debug!(?cmd, "Planning GetSqlInfo query");
@@ -251,18 +262,18 @@ impl FlightSQLPlanner {
             ) => {
                 debug!(%query, "Creating prepared statement");
 
-                // todo run the planner here and actually figure out parameter schemas
-                // see https://github.com/apache/arrow-datafusion/pull/4701
-                let parameter_schema = vec![];
+                let plan = ctx.sql_to_prepared_logical_plan(&query).await?;
+                let parameter_schema = get_parameter_schema(&plan)?;
+                let parameter_schema = encode_schema(&parameter_schema)?;
 
-                let dataset_schema = get_schema_for_query(&query, ctx).await?;
+                let dataset_schema = get_schema_for_plan(plan);
                 let dataset_schema = encode_schema(dataset_schema.as_ref())?;
                 let handle = PreparedStatementHandle::new(query);
 
                 let result = ActionCreatePreparedStatementResult {
                     prepared_statement_handle: Bytes::from(handle),
                     dataset_schema,
-                    parameter_schema: Bytes::from(parameter_schema),
+                    parameter_schema,
                 };
 
                 let msg = Any::pack(&result)?;
@@ -282,6 +293,65 @@ impl FlightSQLPlanner {
             .fail(),
         }
     }
+
+    /// Handles the `DoPut` request for `cmd` with the uploaded `batches` and
+    /// returns bytes for the `app_metadata` of the [`arrow_flight::PutResult`].
+    ///
+    /// Currently this only supports binding parameter values to a prepared
+    /// statement. Since IOx does not keep any state for prepared statements,
+    /// the values are embedded into a new handle that is returned to the
+    /// client, which then uses it for the subsequent `GetFlightInfo` and `DoGet`.
+    pub async fn do_put(
+        namespace_name: impl Into<String> + Send,
+        _database: Arc<dyn QueryNamespace>,
+        cmd: FlightSQLCommand,
+        batches: Vec<RecordBatch>,
+        ctx: &IOxSessionContext,
+    ) -> Result<Bytes> {
+        let namespace_name = namespace_name.into();
+        debug!(%namespace_name, %cmd, "Handling flightsql do_put");
+
+        match cmd {
+            FlightSQLCommand::CommandPreparedStatementQuery(handle) => {
+                let query = handle.query();
+                debug!(%query, "Binding prepared statement parameters");
+
+                let schema = batches
+                    .first()
+                    .map(|batch| batch.schema())
+                    .context(InvalidParametersSnafu { num_rows: 0usize })?;
+                let params = concat_batches(&schema, &batches)?;
+
+                // bind once to report type errors early
+                let plan = ctx.sql_to_prepared_logical_plan(query).await?;
+                let param_values = bind_params(&plan, &params)?;
+                plan.with_param_values(param_values)?;
+
+                let handle = handle.with_params(&params)?;
+                let result = DoPutPreparedStatementResult {
+                    prepared_statement_handle: Some(Bytes::from(handle)),
+                };
+                Ok(result.encode_to_vec().into())
+            }
+            _ => ProtocolSnafu {
+                cmd: format!("{cmd:?}"),
+                method: "DoPut",
+            }
+            .fail(),
+        }
+    }
+}
+
+/// Response to binding parameters to a prepared statement via `DoPut`, sent as
+/// `app_metadata` of the [`arrow_flight::PutResult`].
+///
+/// This mirrors `DoPutPreparedStatementResult` of the FlightSQL protocol, which
+/// is not yet provided by `arrow-flight`.
+#[derive(Clone, PartialEq, Message)]
+struct DoPutPreparedStatementResult {
+    /// Handle that the client shall use for subsequent requests
+    #[prost(bytes = "bytes", optional, tag = "1")]
+    prepared_statement_handle: Option<Bytes>,
 }
 
 /// Return the schema for the specified query
@@ -289,6 +359,75 @@ async fn get_schema_for_query(query: &str, ctx: &IOxSessionContext) -> Result<Sc
     Ok(get_schema_for_plan(ctx.sql_to_logical_plan(query).await?))
 }
 
+/// Return the schema for the specified prepared statement, incl. its bound parameters
+async fn get_schema_for_prepared_statement(
+    handle: &PreparedStatementHandle,
+    ctx: &IOxSessionContext,
+) -> Result<SchemaRef> {
+    let plan = ctx.sql_to_prepared_logical_plan(handle.query()).await?;
+    let plan = match handle.params()? {
+        Some(params) => {
+            let params = bind_params(&plan, &params)?;
+            plan.with_param_values(params)?
+        }
+        None => plan,
+    };
+    Ok(get_schema_for_plan(plan))
+}
+
+/// Return the types of the `$placeholder` parameters of a logical plan, as
+/// sent to FlightSQL clients when creating a prepared statement.
+///
+/// Fields are ordered by their placeholder index (`$1`, `$2`, ...). Parameters
+/// whose type cannot be inferred by the planner are of type [`DataType::Null`].
+fn get_parameter_schema(plan: &LogicalPlan) -> Result<Schema> {
+    let mut params = plan.get_parameter_types()?.into_iter().collect::<Vec<_>>();
+    params.sort_by_cached_key(|(id, _)| {
+        (id.trim_start_matches('$').parse::<usize>().ok(), id.clone())
+    });
+
+    let fields = params
+        .into_iter()
+        .map(|(id, data_type)| Field::new(id, data_type.unwrap_or(DataType::Null), true))
+        .collect::<Vec<_>>();
+    Ok(Schema::new(fields))
+}
+
+/// Convert the single row of parameter values bound to a prepared statement
+/// into values for the `$placeholder` variables of `plan`.
+///
+/// The `n`-th column provides the value for `$n`. Values are cast to the
+/// types that the planner inferred for their placeholders, so that clients
+/// may e.g. send an `Int32` for a placeholder that is compared to an `Int64`
+/// column.
+fn bind_params(plan: &LogicalPlan, params: &RecordBatch) -> Result<ParamValues> {
+    ensure!(
+        params.num_rows() == 1,
+        InvalidParametersSnafu {
+            num_rows: params.num_rows()
+        }
+    );
+
+    let types = plan.get_parameter_types()?;
+
+    let values = params
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(idx, array)| {
+            let value = ScalarValue::try_from_array(array, 0)?;
+            match types.get(&format!("${}", idx + 1)) {
+                Some(Some(data_type)) if &value.data_type() != data_type => {
+                    Ok(value.cast_to(data_type)?)
+                }
+                _ => Ok(value),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(ParamValues::List(values))
+}
+
 /// Return the schema for the specified logical plan
 fn get_schema_for_plan(logical_plan: LogicalPlan) -> SchemaRef {
     // gather real schema, but only
@@ -349,6 +488,10 @@ async fn plan_get_db_schemas(
     let catalog_list = ctx.inner().state().catalog_list();
 
     for catalog_name in catalog_list.catalog_names() {
+        if !matches_catalog(cmd.catalog.as_deref(), &catalog_name) {
+            continue;
+        }
+
         // we just got the catalog name from the catalog_list, so it
         // should always be Some, but avoid unwrap to be safe
         let Some(catalog) = catalog_list.catalog(&catalog_name) else {
@@ -397,10 +540,23 @@ async fn plan_get_primary_keys(
 
 /// Return a list of tables from the DataFusion catalog
 async fn plan_get_tables(ctx: &IOxSessionContext, cmd: CommandGetTables) -> Result<LogicalPlan> {
+    // The builder applies all filters of the command when building the
+    // result. Evaluate them upfront as well, so that we don't resolve
+    // table providers (which may need to load the table from the
+    // catalog) for tables that are filtered out anyways.
+    let catalog_filter = cmd.catalog.clone();
+    let db_schema_filter_pattern = cmd.db_schema_filter_pattern.clone();
+    let table_name_filter_pattern = cmd.table_name_filter_pattern.clone();
+    let table_types = cmd.table_types.clone();
+
     let mut builder = cmd.into_builder();
     let catalog_list = ctx.inner().state().catalog_list();
 
     for catalog_name in catalog_list.catalog_names() {
+        if !matches_catalog(catalog_filter.as_deref(), &catalog_name) {
+            continue;
+        }
+
         // we just got the catalog name from the catalog_list, so it
         // should always be Some, but avoid unwrap to be safe
         let Some(catalog) = catalog_list.catalog(&catalog_name) else {
@@ -411,31 +567,53 @@ async fn plan_get_tables(ctx: &IOxSessionContext, cmd: CommandGetTables) -> Resu
         // "virtual" catalog in DataFusion and thus is not reported
         // directly via the table providers
         // We ensure this list is kept in sync with tests
-        let table_names = vec!["columns", "df_settings", "tables", "views"];
-        for table_name in table_names {
-            let schema_name = "information_schema";
+        let schema_name = "information_schema";
+        let table_type = "VIEW";
+        let information_schema_tables =
+            if matches_pattern(db_schema_filter_pattern.as_deref(), schema_name)
+                && matches_table_type(&table_types, table_type)
+            {
+                vec!["columns", "df_settings", "tables", "views"]
+            } else {
+                vec![]
+            };
+        for table_name in information_schema_tables {
+            if !matches_pattern(table_name_filter_pattern.as_deref(), table_name) {
+                continue;
+            }
+
             let table_ref = TableReference::full(&catalog_name, schema_name, table_name);
 
             let Some(table) = ctx.inner().table(table_ref).await.ok() else {
                 continue;
             };
 
-            let table_type = "VIEW";
             let schema = Schema::from(table.schema());
             builder.append(&catalog_name, schema_name, table_name, table_type, &schema)?;
         }
 
         for schema_name in catalog.schema_names() {
+            if !matches_pattern(db_schema_filter_pattern.as_deref(), &schema_name) {
+                continue;
+            }
+
             let Some(schema) = catalog.schema(&schema_name) else {
                 continue;
             };
 
             for table_name in schema.table_names() {
+                if !matches_pattern(table_name_filter_pattern.as_deref(), &table_name) {
+                    continue;
+                }
+
                 let Some(table) = schema.table(&table_name).await else {
                     continue;
                 };
 
                 let table_type = table_type_name(table.table_type());
+                if !matches_table_type(&table_types, table_type) {
+                    continue;
+                }
 
                 builder.append(
                     &catalog_name,
@@ -462,6 +640,89 @@ fn table_type_name(table_type: TableType) -> &'static str {
     }
 }
 
+/// Returns `true` if `catalog_name` passes the (exact) catalog filter
+/// of a metadata command.
+///
+/// An empty filter only matches objects without a catalog, of which
+/// there are none.
+fn matches_catalog(filter: Option<&str>, catalog_name: &str) -> bool {
+    filter.is_none() || filter == Some(catalog_name)
+}
+
+/// Returns `true` if `table_type` passes the table type filter of
+/// `GetTables`. An empty filter matches all types.
+fn matches_table_type(table_types: &[String], table_type: &str) -> bool {
+    table_types.is_empty() || table_types.iter().any(|t| t == table_type)
+}
+
+/// Returns `true` if `value` matches the SQL `LIKE` pattern of a
+/// metadata command (`%` matches any sequence of characters, `_` a
+/// single character and `\` escapes the next character, see
+/// `SqlSearchStringEscape`). No pattern matches everything.
+///
+/// Runs in `O(pattern * value)` time: on a mismatch only the most recent `%`
+/// is retried with one more character, since any earlier `%` could not match
+/// more than that one does.
+fn matches_pattern(pattern: Option<&str>, value: &str) -> bool {
+    let Some(pattern) = pattern else {
+        return true;
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Token {
+        Any,
+        One,
+        Char(char),
+    }
+
+    let mut tokens = vec![];
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        tokens.push(match c {
+            '%' => Token::Any,
+            '_' => Token::One,
+            // a trailing escape character matches itself
+            '\\' => Token::Char(chars.next().unwrap_or('\\')),
+            c => Token::Char(c),
+        });
+    }
+    let value = value.chars().collect::<Vec<_>>();
+
+    let (mut t, mut v) = (0, 0);
+    // position after the last `%` and the value position it was tried at
+    let mut retry = None;
+    while v < value.len() {
+        match tokens.get(t) {
+            Some(Token::Any) => {
+                t += 1;
+                retry = Some((t, v));
+                continue;
+            }
+            Some(Token::One) => {
+                t += 1;
+                v += 1;
+                continue;
+            }
+            Some(Token::Char(c)) if *c == value[v] => {
+                t += 1;
+                v += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // let the last `%` match one more character
+        let Some((retry_t, retry_v)) = retry else {
+            return false;
+        };
+        t = retry_t;
+        v = retry_v + 1;
+        retry = Some((t, v));
+    }
+
+    tokens[t..].iter().all(|token| *token == Token::Any)
+}
+
 /// Return a `LogicalPlan` for GetTableTypes
 async fn plan_get_table_types(ctx: &IOxSessionContext) -> Result<LogicalPlan> {
     Ok(ctx.batch_to_logical_plan(TABLE_TYPES_RECORD_BATCH.clone())?)
@@ -594,3 +855,86 @@ static GET_XDBC_TYPE_INFO_SCHEMA: Lazy<SchemaRef> = Lazy::new(|| {
         Field::new("interval_precision", DataType::Int32, true),
     ]))
 });
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::Int64Array;
+    use datafusion::logical_expr::LogicalPlanBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_bind_params_num_rows() {
+        let plan = LogicalPlanBuilder::empty(false).build().unwrap();
+
+        for num_rows in [0, 2] {
+            let params = RecordBatch::try_from_iter([(
+                "a",
+                Arc::new(Int64Array::from(vec![1; num_rows])) as ArrayRef,
+            )])
+            .unwrap();
+            let err = bind_params(&plan, &params).unwrap_err();
+            assert!(
+                matches!(err, Error::InvalidParameters { num_rows: n } if n == num_rows),
+                "{err}"
+            );
+        }
+
+        let params =
+            RecordBatch::try_from_iter([("a", Arc::new(Int64Array::from(vec![1])) as ArrayRef)])
+                .unwrap();
+        let ParamValues::List(values) = bind_params(&plan, &params).unwrap() else {
+            panic!("expected a list of values");
+        };
+        assert_eq!(values, [ScalarValue::Int64(Some(1))]);
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern(None, "cpu"));
+
+        for (pattern, value, expected) in [
+            ("", "", true),
+            ("", "cpu", false),
+            ("cpu", "cpu", true),
+            ("cpu", "cpus", false),
+            ("%", "", true),
+            ("%", "cpu", true),
+            ("c%", "cpu", true),
+            ("%u", "cpu", true),
+            ("%p%", "cpu", true),
+            ("%x%", "cpu", false),
+            ("c%u%", "cpu", true),
+            ("c%%u", "cpu", true),
+            ("%pu%pu", "cpupu", true),
+            ("%ab%abc", "abababc", true),
+            ("_pu", "cpu", true),
+            ("___", "cpu", true),
+            ("__", "cpu", false),
+            ("____", "cpu", false),
+            ("c_%", "c", false),
+            ("c\\_u", "c_u", true),
+            ("c\\_u", "cpu", false),
+            ("100\\%", "100%", true),
+            ("100\\%", "1000", false),
+            ("a\\\\b", "a\\b", true),
+            ("a\\", "a\\", true),
+            ("é_", "éa", true),
+        ] {
+            assert_eq!(
+                matches_pattern(Some(pattern), value),
+                expected,
+                "pattern: {pattern:?}, value: {value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_pattern_no_backtracking_blowup() {
+        // exponential with a naive recursive matcher
+        let pattern = "%a".repeat(50) + "b";
+        let value = "a".repeat(1_000);
+        assert!(!matches_pattern(Some(&pattern), &value));
+        assert!(matches_pattern(Some(&pattern), &(value + "b")));
+    }
+}
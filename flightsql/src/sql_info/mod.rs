@@ -115,6 +115,7 @@ static INSTANCE: Lazy<SqlInfoData> = Lazy::new(|| {
     builder.append(SqlInfo::SqlOuterJoinsSupportLevel, 2i32);
     builder.append(SqlInfo::SqlSchemaTerm, "schema");
     builder.append(SqlInfo::SqlProcedureTerm, "procedure");
+    builder.append(SqlInfo::SqlCatalogTerm, "catalog");
     builder.append(SqlInfo::SqlCatalogAtStart, false);
     builder.append(SqlInfo::SqlSchemasSupportedActions, 0i32);
     builder.append(SqlInfo::SqlCatalogsSupportedActions, 0i32);
@@ -287,6 +287,7 @@ mod tests {
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
             max_l0_created_at: Timestamp::new(1),
+            column_stats: Default::default(),
         };
 
         let parquet_file = repos
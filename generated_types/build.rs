@@ -23,6 +23,7 @@ fn main() -> Result<()> {
 /// - `influxdata.iox.catalog.v1.rs`
 /// - `influxdata.iox.compactor.v1.rs`
 /// - `influxdata.iox.delete.v1.rs`
+/// - `influxdata.iox.health.v1.rs`
 /// - `influxdata.iox.ingester.v1.rs`
 /// - `influxdata.iox.namespace.v1.rs`
 /// - `influxdata.iox.object_store.v1.rs`
@@ -39,10 +40,12 @@ fn generate_grpc_types(root: &Path) -> Result<()> {
     let catalog_cache_path = root.join("influxdata/iox/catalog_cache/v1");
     let catalog_v1_path = root.join("influxdata/iox/catalog/v1");
     let catalog_v2_path = root.join("influxdata/iox/catalog/v2");
+    let column_statistics_path = root.join("influxdata/iox/column_statistics/v1");
     let column_type = root.join("influxdata/iox/column_type/v1");
     let compactor_path = root.join("influxdata/iox/compactor/v1");
     let delete_path = root.join("influxdata/iox/delete/v1");
     let gossip_path = root.join("influxdata/iox/gossip/v1");
+    let health_path = root.join("influxdata/iox/health/v1");
     let ingester_path = root.join("influxdata/iox/ingester/v1");
     let namespace_path = root.join("influxdata/iox/namespace/v1");
     let object_store_path = root.join("influxdata/iox/object_store/v1");
@@ -64,6 +67,7 @@ fn generate_grpc_types(root: &Path) -> Result<()> {
         catalog_v1_path.join("partition_identifier.proto"),
         catalog_v1_path.join("service.proto"),
         catalog_v2_path.join("service.proto"),
+        column_statistics_path.join("statistics.proto"),
         column_type.join("type.proto"),
         compactor_path.join("service.proto"),
         delete_path.join("service.proto"),
@@ -72,6 +76,7 @@ fn generate_grpc_types(root: &Path) -> Result<()> {
         gossip_path.join("schema.proto"),
         gossip_path.join("schema_sync.proto"),
         gossip_path.join("sort_keys.proto"),
+        health_path.join("service.proto"),
         ingester_path.join("parquet_metadata.proto"),
         ingester_path.join("persist.proto"),
         ingester_path.join("write.proto"),
@@ -113,7 +118,12 @@ fn generate_grpc_types(root: &Path) -> Result<()> {
             ".influxdata.iox.ingester.v1.IngesterQueryResponseMetadata.unpersisted_partitions",
             ".influxdata.iox.schema.v1.UpsertSchemaRequest.columns",
         ])
-        .type_attribute(".influxdata.iox.partition_template", "#[derive(Hash)]")
+        // Enumerations already derive Hash, so only messages and oneofs need it.
+        .message_attribute(".influxdata.iox.partition_template", "#[derive(Hash)]")
+        .enum_attribute(
+            ".influxdata.iox.partition_template.v1.TemplatePart.part",
+            "#[derive(Hash)]",
+        )
         .bytes([".influxdata.iox.catalog_cache.v1"]);
 
     let descriptor_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("proto_descriptor.bin");
@@ -125,6 +125,19 @@ pub mod influxdata {
             }
         }
 
+        pub mod column_statistics {
+            pub mod v1 {
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/influxdata.iox.column_statistics.v1.rs"
+                ));
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/influxdata.iox.column_statistics.v1.serde.rs"
+                ));
+            }
+        }
+
         pub mod column_type {
             pub mod v1 {
                 include!(concat!(
@@ -213,6 +226,16 @@ pub mod influxdata {
             }
         }
 
+        pub mod health {
+            pub mod v1 {
+                include!(concat!(env!("OUT_DIR"), "/influxdata.iox.health.v1.rs"));
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/influxdata.iox.health.v1.serde.rs"
+                ));
+            }
+        }
+
         pub mod ingester {
             pub mod v1 {
                 include!(concat!(env!("OUT_DIR"), "/influxdata.iox.ingester.v1.rs"));
@@ -653,6 +653,7 @@ impl RemoteImporter {
                 created_at: Timestamp::new(proto_parquet_file.created_at),
                 column_set,
                 max_l0_created_at: Timestamp::new(proto_parquet_file.max_l0_created_at),
+                column_stats: Default::default(),
             }
         } else {
             warn!("Could not read parquet file metadata, reconstructing based on encoded metadata");
@@ -677,6 +678,7 @@ impl RemoteImporter {
                 created_at,
                 column_set,
                 max_l0_created_at: created_at,
+                column_stats: Default::default(),
             }
         };
         debug!(?params, "Created ParquetFileParams");
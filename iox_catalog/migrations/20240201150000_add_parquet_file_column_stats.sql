@@ -0,0 +1,6 @@
+-- Per-column min/max/null-count statistics of the data within each parquet
+-- file, serialised as the JSON form of the
+-- influxdata.iox.column_statistics.v1.ColumnStatistics proto message.
+ALTER TABLE
+    IF EXISTS parquet_file
+    ADD COLUMN column_stats JSONB NOT NULL DEFAULT '{}';
@@ -352,6 +352,7 @@ pub(crate) fn serialize_parquet_file_params(
         created_at: params.created_at.get(),
         column_set: Some(serialize_column_set(&params.column_set)),
         max_l0_created_at: params.max_l0_created_at.get(),
+        column_stats: Some((&params.column_stats).into()),
     }
 }
 
@@ -378,6 +379,7 @@ pub(crate) fn deserialize_parquet_file_params(
         created_at: Timestamp::new(params.created_at),
         column_set: deserialize_column_set(params.column_set.required().ctx("column_set")?),
         max_l0_created_at: Timestamp::new(params.max_l0_created_at),
+        column_stats: params.column_stats.map(Into::into).unwrap_or_default(),
     })
 }
 
@@ -403,6 +405,7 @@ pub(crate) fn serialize_parquet_file(file: ParquetFile) -> proto::ParquetFile {
         created_at: file.created_at.get(),
         column_set: Some(serialize_column_set(&file.column_set)),
         max_l0_created_at: file.max_l0_created_at.get(),
+        column_stats: Some((&file.column_stats).into()),
     }
 }
 
@@ -430,14 +433,15 @@ pub(crate) fn deserialize_parquet_file(file: proto::ParquetFile) -> Result<Parqu
         created_at: Timestamp::new(file.created_at),
         column_set: deserialize_column_set(file.column_set.required().ctx("column_set")?),
         max_l0_created_at: Timestamp::new(file.max_l0_created_at),
+        column_stats: file.column_stats.map(Into::into).unwrap_or_default(),
     })
 }
 
 #[cfg(test)]
 mod tests {
     use data_types::{
-        partition_template::TablePartitionTemplateOverride, CompactionLevel, PartitionHashId,
-        PartitionKey,
+        partition_template::TablePartitionTemplateOverride, ColumnStatistics, CompactionLevel,
+        FileColumnStatistics, PartitionHashId, PartitionKey, StatValue,
     };
 
     use super::*;
@@ -518,6 +522,7 @@ mod tests {
                     parts: vec![proto::TemplatePart {
                         part: Some(proto::template_part::Part::TimeFormat("year-%Y".into())),
                     }],
+                    ..Default::default()
                 },
             )
             .unwrap(),
@@ -540,6 +545,7 @@ mod tests {
                     parts: vec![proto::TemplatePart {
                         part: Some(proto::template_part::Part::TimeFormat("year-%Y".into())),
                     }],
+                    ..Default::default()
                 }),
                 &NamespacePartitionTemplateOverride::const_default(),
             )
@@ -664,6 +670,17 @@ mod tests {
         assert_eq!(set, set2);
     }
 
+    fn test_column_stats() -> FileColumnStatistics {
+        FileColumnStatistics::new([(
+            ColumnId::new(9),
+            ColumnStatistics {
+                min: Some(StatValue::String("a".to_string())),
+                max: Some(StatValue::String("b".to_string())),
+                null_count: Some(12),
+            },
+        )])
+    }
+
     #[test]
     fn test_parquet_file_params_roundtrip() {
         let params = ParquetFileParams {
@@ -680,6 +697,7 @@ mod tests {
             created_at: Timestamp::new(8),
             column_set: ColumnSet::new([ColumnId::new(9), ColumnId::new(10)]),
             max_l0_created_at: Timestamp::new(11),
+            column_stats: test_column_stats(),
         };
         let protobuf = serialize_parquet_file_params(&params);
         let params2 = deserialize_parquet_file_params(protobuf).unwrap();
@@ -704,6 +722,7 @@ mod tests {
             created_at: Timestamp::new(8),
             column_set: ColumnSet::new([ColumnId::new(9), ColumnId::new(10)]),
             max_l0_created_at: Timestamp::new(11),
+            column_stats: test_column_stats(),
         };
         let protobuf = serialize_parquet_file(file.clone());
         let file2 = deserialize_parquet_file(protobuf).unwrap();
@@ -14,9 +14,10 @@ use async_trait::async_trait;
 use data_types::snapshot::table::TableSnapshot;
 use data_types::{
     partition_template::{NamespacePartitionTemplateOverride, TablePartitionTemplateOverride},
-    ColumnId, ColumnType, CompactionLevel, MaxColumnsPerTable, MaxTables, Namespace, NamespaceId,
-    NamespaceName, NamespaceSchema, ObjectStoreId, ParquetFile, ParquetFileId, ParquetFileParams,
-    PartitionId, SortKeyIds, TableId, Timestamp,
+    ColumnId, ColumnStatistics, ColumnType, CompactionLevel, FileColumnStatistics,
+    MaxColumnsPerTable, MaxTables, Namespace, NamespaceId, NamespaceName, NamespaceSchema,
+    ObjectStoreId, ParquetFile, ParquetFileId, ParquetFileParams, PartitionId, SortKeyIds,
+    StatValue, TableId, Timestamp,
 };
 use data_types::{snapshot::partition::PartitionSnapshot, Column, PartitionHashId, PartitionKey};
 use futures::{Future, StreamExt};
@@ -216,6 +217,7 @@ async fn test_namespace(catalog: Arc<dyn Catalog>) {
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TagValue("tag1".into())),
             }],
+            ..Default::default()
         })
         .unwrap();
     let namespace5_name = NamespaceName::new("test_namespace5").unwrap();
@@ -561,6 +563,7 @@ async fn test_table(catalog: Arc<dyn Catalog>) {
                     part: Some(proto::template_part::Part::TagValue("tag2".into())),
                 },
             ],
+            ..Default::default()
         }),
         &namespace2.partition_template,
     )
@@ -610,6 +613,7 @@ async fn test_table(catalog: Arc<dyn Catalog>) {
                     part: Some(proto::template_part::Part::TimeFormat("year-%Y".into())),
                 },
             ],
+            ..Default::default()
         })
         .unwrap();
     let custom_namespace_name = NamespaceName::new("custom_namespace").unwrap();
@@ -1438,9 +1442,30 @@ async fn test_parquet_file(catalog: Arc<dyn Catalog>) {
         object_store_id: ObjectStoreId::new(),
         min_time: Timestamp::new(50),
         max_time: Timestamp::new(60),
+        column_stats: FileColumnStatistics::new([(
+            ColumnId::new(1),
+            ColumnStatistics {
+                min: Some(StatValue::String("bananas".to_string())),
+                max: Some(StatValue::String("platanos".to_string())),
+                null_count: Some(1),
+            },
+        )]),
         ..parquet_file_params.clone()
     };
-    let other_file = repos.parquet_files().create(other_params).await.unwrap();
+    let other_file = repos
+        .parquet_files()
+        .create(other_params.clone())
+        .await
+        .unwrap();
+    assert_eq!(other_file.column_stats, other_params.column_stats);
+
+    // verify the column statistics are persisted
+    let pfg = repos
+        .parquet_files()
+        .get_by_object_store_id(other_file.object_store_id)
+        .await
+        .unwrap();
+    assert_eq!(other_file, pfg.unwrap());
 
     let exist_id = parquet_file.id;
     let non_exist_id = ParquetFileId::new(other_file.id.get() + 10);
@@ -1604,7 +1604,7 @@ RETURNING object_store_id;
             r#"
 SELECT parquet_file.id, namespace_id, parquet_file.table_id, partition_id, partition_hash_id,
        object_store_id, min_time, max_time, parquet_file.to_delete, file_size_bytes, row_count,
-       compaction_level, created_at, column_set, max_l0_created_at
+       compaction_level, created_at, column_set, max_l0_created_at, column_stats
 FROM parquet_file
 WHERE parquet_file.partition_id = ANY($1)
   AND parquet_file.to_delete IS NULL;
@@ -1624,7 +1624,7 @@ WHERE parquet_file.partition_id = ANY($1)
             r#"
 SELECT id, namespace_id, table_id, partition_id, partition_hash_id, object_store_id, min_time,
        max_time, to_delete, file_size_bytes, row_count, compaction_level, created_at, column_set,
-       max_l0_created_at
+       max_l0_created_at, column_stats
 FROM parquet_file
 WHERE object_store_id = $1;
              "#,
@@ -1726,6 +1726,7 @@ where
         created_at,
         column_set,
         max_l0_created_at,
+        column_stats,
     } = parquet_file_params;
 
     let query = sqlx::query_scalar::<_, ParquetFileId>(
@@ -1733,8 +1734,9 @@ where
 INSERT INTO parquet_file (
     table_id, partition_id, partition_hash_id, object_store_id,
     min_time, max_time, file_size_bytes,
-    row_count, compaction_level, created_at, namespace_id, column_set, max_l0_created_at )
-VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13 )
+    row_count, compaction_level, created_at, namespace_id, column_set, max_l0_created_at,
+    column_stats )
+VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14 )
 RETURNING id;
         "#,
     )
@@ -1750,7 +1752,8 @@ RETURNING id;
     .bind(created_at) // $10
     .bind(namespace_id) // $11
     .bind(column_set) // $12
-    .bind(max_l0_created_at); // $13
+    .bind(max_l0_created_at) // $13
+    .bind(column_stats); // $14
 
     let parquet_file_id = query.fetch_one(executor).await.map_err(|e| {
         if is_unique_violation(&e) {
@@ -2411,6 +2414,7 @@ RETURNING id, name, retention_period_ns, max_tables, max_columns_per_table, dele
                         "%Y-%m-%d".to_owned(),
                     )),
                 }],
+                ..Default::default()
             })
             .unwrap();
         let namespace_custom_template = repos
@@ -2473,6 +2477,7 @@ RETURNING id, name, retention_period_ns, max_tables, max_columns_per_table, dele
                         parts: vec![proto::TemplatePart {
                             part: Some(proto::template_part::Part::TimeFormat("year-%Y".into())),
                         }],
+                        ..Default::default()
                     })
                     .unwrap(),
                 ),
@@ -2620,6 +2625,7 @@ RETURNING *;
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TagValue("chemical".into())),
             }],
+            ..Default::default()
         };
         let table_with_template_no_namespace_template = repos
             .tables()
@@ -2673,6 +2679,7 @@ RETURNING *;
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TagValue("vegetable".into())),
             }],
+            ..Default::default()
         };
         let table_with_template_with_namespace_template = repos
             .tables()
@@ -18,10 +18,11 @@ use data_types::{
     partition_template::{
         NamespacePartitionTemplateOverride, TablePartitionTemplateOverride, TemplatePart,
     },
-    Column, ColumnId, ColumnSet, ColumnType, CompactionLevel, MaxColumnsPerTable, MaxTables,
-    Namespace, NamespaceId, NamespaceName, NamespaceServiceProtectionLimitsOverride, ObjectStoreId,
-    ParquetFile, ParquetFileId, ParquetFileParams, Partition, PartitionHashId, PartitionId,
-    PartitionKey, SkippedCompaction, SortKeyIds, Table, TableId, Timestamp,
+    Column, ColumnId, ColumnSet, ColumnType, CompactionLevel, FileColumnStatistics,
+    MaxColumnsPerTable, MaxTables, Namespace, NamespaceId, NamespaceName,
+    NamespaceServiceProtectionLimitsOverride, ObjectStoreId, ParquetFile, ParquetFileId,
+    ParquetFileParams, Partition, PartitionHashId, PartitionId, PartitionKey, SkippedCompaction,
+    SortKeyIds, Table, TableId, Timestamp,
 };
 use iox_time::{SystemProvider, TimeProvider};
 use metric::Registry;
@@ -1212,6 +1213,7 @@ struct ParquetFilePod {
     created_at: Timestamp,
     column_set: Json<Vec<i64>>,
     max_l0_created_at: Timestamp,
+    column_stats: FileColumnStatistics,
 }
 
 impl From<ParquetFilePod> for ParquetFile {
@@ -1232,6 +1234,7 @@ impl From<ParquetFilePod> for ParquetFile {
             created_at: value.created_at,
             column_set: to_column_set(&value.column_set),
             max_l0_created_at: value.max_l0_created_at,
+            column_stats: value.column_stats,
         }
     }
 }
@@ -1308,7 +1311,7 @@ RETURNING object_store_id;
             r#"
 SELECT parquet_file.id, namespace_id, parquet_file.table_id, partition_id, partition_hash_id,
        object_store_id, min_time, max_time, parquet_file.to_delete, file_size_bytes, row_count,
-       compaction_level, created_at, column_set, max_l0_created_at
+       compaction_level, created_at, column_set, max_l0_created_at, column_stats
 FROM parquet_file
 WHERE parquet_file.partition_id IN (SELECT value FROM json_each($1))
   AND parquet_file.to_delete IS NULL;
@@ -1332,7 +1335,7 @@ WHERE parquet_file.partition_id IN (SELECT value FROM json_each($1))
             r#"
 SELECT id, namespace_id, table_id, partition_id, partition_hash_id, object_store_id, min_time,
        max_time, to_delete, file_size_bytes, row_count, compaction_level, created_at, column_set,
-       max_l0_created_at
+       max_l0_created_at, column_stats
 FROM parquet_file
 WHERE object_store_id = $1;
              "#,
@@ -1439,6 +1442,7 @@ where
         created_at,
         column_set,
         max_l0_created_at,
+        column_stats,
     } = parquet_file_params;
 
     let res = sqlx::query_as::<_, ParquetFilePod>(
@@ -1446,12 +1450,13 @@ where
 INSERT INTO parquet_file (
     table_id, partition_id, partition_hash_id, object_store_id,
     min_time, max_time, file_size_bytes,
-    row_count, compaction_level, created_at, namespace_id, column_set, max_l0_created_at )
-VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13 )
+    row_count, compaction_level, created_at, namespace_id, column_set, max_l0_created_at,
+    column_stats )
+VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14 )
 RETURNING
     id, table_id, partition_id, partition_hash_id, object_store_id, min_time, max_time, to_delete,
     file_size_bytes, row_count, compaction_level, created_at, namespace_id, column_set,
-    max_l0_created_at;
+    max_l0_created_at, column_stats;
         "#,
     )
     .bind(table_id) // $1
@@ -1467,6 +1472,7 @@ RETURNING
     .bind(namespace_id) // $11
     .bind(from_column_set(&column_set)) // $12
     .bind(max_l0_created_at) // $13
+    .bind(column_stats) // $14
     .fetch_one(executor)
     .await;
 
@@ -1852,6 +1858,7 @@ RETURNING id, name, retention_period_ns, max_tables, max_columns_per_table, dele
                         "%Y-%m-%d".to_owned(),
                     )),
                 }],
+                ..Default::default()
             })
             .unwrap();
         let namespace_custom_template = repos
@@ -1912,6 +1919,7 @@ RETURNING id, name, retention_period_ns, max_tables, max_columns_per_table, dele
                         parts: vec![proto::TemplatePart {
                             part: Some(proto::template_part::Part::TimeFormat("year-%Y".into())),
                         }],
+                        ..Default::default()
                     })
                     .unwrap(),
                 ),
@@ -2059,6 +2067,7 @@ RETURNING *;
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TagValue("chemical".into())),
             }],
+            ..Default::default()
         };
         let table_with_template_no_namespace_template = repos
             .tables()
@@ -2112,6 +2121,7 @@ RETURNING *;
             parts: vec![proto::TemplatePart {
                 part: Some(proto::template_part::Part::TagValue("vegetable".into())),
             }],
+            ..Default::default()
         };
         let table_with_template_with_namespace_template = repos
             .tables()
@@ -25,6 +25,13 @@ extensions_options! {
         /// This protects against certain highly degenerative plans.
         pub max_dedup_time_split: usize, default = 100
 
+        /// Split de-duplicate operations further into the time ranges where chunks actually overlap, so that the
+        /// time ranges that are only covered by a single chunk are not de-duplicated at all.
+        ///
+        /// Chunks that are only partially overlapped are scanned once per time range. The number of time ranges is
+        /// limited by [`max_dedup_time_split`](Self::max_dedup_time_split).
+        pub dedup_overlap_split: bool, default = false
+
         /// When multiple parquet files are required in a sorted way (e.g. for de-duplication), we have two options:
         ///
         /// 1. **In-mem sorting:** Put them into [`target_partitions`] DataFusion partitions. This limits the fan-out,
@@ -41,6 +48,66 @@ extensions_options! {
         /// [`target_partitions`]: datafusion::common::config::ExecutionOptions::target_partitions
         pub max_parquet_fanout: usize, default = 40
 
+        /// Choose the number of DataFusion partitions that scans of IOx chunks are spread over from the size of the
+        /// scan instead of always using [`target_partitions`].
+        ///
+        /// Each ingester partition and each [`scan_bytes_per_partition`] bytes of parquet files get their own
+        /// DataFusion partition, up to [`target_partitions`]. This spares small queries the overhead of a high
+        /// fan-out, while large queries still use the full parallelism.
+        ///
+        ///
+        /// [`scan_bytes_per_partition`]: Self::scan_bytes_per_partition
+        /// [`target_partitions`]: datafusion::common::config::ExecutionOptions::target_partitions
+        pub adaptive_scan_partitions: bool, default = false
+
+        /// Bytes of parquet files that a single DataFusion partition should scan, see
+        /// [`adaptive_scan_partitions`](Self::adaptive_scan_partitions).
+        pub scan_bytes_per_partition: usize, default = 128 * 1024 * 1024
+
+        /// De-duplicate inputs that are not sorted by their primary key using a hash table instead of sorting them
+        /// first, if none of the inputs are sorted at all or if the input has at most [`hash_dedup_max_rows`] rows.
+        ///
+        ///
+        /// [`hash_dedup_max_rows`]: Self::hash_dedup_max_rows
+        pub hash_dedup: bool, default = false
+
+        /// Maximum estimated number of input rows for which [`hash_dedup`](Self::hash_dedup) is used even if some
+        /// of the inputs are sorted.
+        pub hash_dedup_max_rows: usize, default = 1_000_000
+
+        /// Decide per parquet scan whether filters are evaluated while decoding, so that wide string / tag columns
+        /// that are not filtered on are only decoded for the rows that pass the filters on the other columns.
+        ///
+        /// Scans that only output filtered columns evaluate the filters after decoding instead, since decoding them
+        /// row by row would decode the filtered columns twice.
+        pub late_materialization: bool, default = false
+
+        /// Spill sorts to the object store configured for the executor (see [`ExecutorConfig::spill_object_store`]) once
+        /// they reach the memory limit of their query.
+        ///
+        /// This is enabled for all queries of executors that have a spill object store.
+        ///
+        ///
+        /// [`ExecutorConfig::spill_object_store`]: crate::exec::ExecutorConfig::spill_object_store
+        pub object_store_spill: bool, default = false
+
+        /// Aggregate by time bucket in time order, so that the results of each bucket are emitted as soon as the bucket
+        /// is complete instead of after the entire aggregation has finished.
+        ///
+        /// This sorts the input of aggregations that group by the time column (or a `date_bin` of it) by time and does
+        /// not split them into partial and final aggregations, which reduces their parallelism.
+        pub progressive_aggregation: bool, default = false
+
+        /// Maximum number of series / groups (i.e. distinct group keys) a query may produce, or 0 for no limit.
+        ///
+        /// This is enforced on the output of grouping aggregations, so that queries with a runaway `GROUP BY`
+        /// cardinality fail with [`SeriesLimitExceeded`] before their result is sorted, encoded and sent to the client.
+        /// The aggregation itself still computes all groups first, its memory usage is bounded by the memory pool.
+        ///
+        ///
+        /// [`SeriesLimitExceeded`]: crate::exec::series_limit::SeriesLimitExceeded
+        pub max_series: usize, default = 0
+
         /// Cuttoff date for InfluxQL metadata queries.
         pub influxql_metadata_cutoff: MetadataCutoff, default = MetadataCutoff::Relative(Duration::from_secs(3600 * 24))
     }
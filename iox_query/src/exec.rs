@@ -2,25 +2,32 @@
 //! plans. This is currently implemented using DataFusion, and this
 //! interface abstracts away many of the details
 pub(crate) mod context;
+mod cpu_accounting;
 pub mod field;
 pub mod fieldlist;
 pub mod gapfill;
 mod metrics;
+pub mod namespace_udfs;
 mod non_null_checker;
+pub mod object_store_sort;
+pub mod object_store_spill;
+pub(crate) mod query_memory;
 pub mod query_tracing;
 mod schema_pivot;
+pub mod series_limit;
 pub mod seriesset;
 pub mod sleep;
+mod spill_accounting;
 pub(crate) mod split;
 pub mod stringset;
 use datafusion_util::config::register_iox_object_store;
-use executor::DedicatedExecutor;
+use executor::{CpuSet, DedicatedExecutor, RuntimeSettings};
 use metric::Registry;
 use object_store::DynObjectStore;
 use parquet_file::storage::StorageId;
 mod cross_rt_stream;
 
-use std::{collections::HashMap, fmt::Display, num::NonZeroUsize, sync::Arc};
+use std::{collections::HashMap, fmt::Display, num::NonZeroUsize, path::PathBuf, sync::Arc};
 
 use datafusion::{
     self,
@@ -36,7 +43,13 @@ use datafusion::{
 pub use context::{IOxSessionConfig, IOxSessionContext, SessionContextIOxExt};
 use schema_pivot::SchemaPivotNode;
 
-use crate::exec::metrics::DataFusionMemoryPoolMetricsBridge;
+use crate::exec::{
+    cpu_accounting::NamespaceCpuMetrics,
+    metrics::DataFusionMemoryPoolMetricsBridge,
+    namespace_udfs::NamespaceUdfProvider,
+    object_store_spill::{ObjectStoreSpillConfig, ObjectStoreSpillManager},
+    spill_accounting::SpillMetrics,
+};
 
 use self::{non_null_checker::NonNullCheckerNode, split::StreamSplitNode};
 
@@ -48,6 +61,12 @@ pub struct ExecutorConfig {
     /// Number of threads per thread pool
     pub num_threads: NonZeroUsize,
 
+    /// CPU cores that the threads of both thread pools are pinned to, or [`None`] to not pin them.
+    pub cpu_set: Option<CpuSet>,
+
+    /// Settings of the tokio runtimes backing both thread pools.
+    pub runtime_settings: RuntimeSettings,
+
     /// Target parallelism for query execution
     pub target_query_partitions: NonZeroUsize,
 
@@ -59,16 +78,46 @@ pub struct ExecutorConfig {
 
     /// Memory pool size in bytes.
     pub mem_pool_size: usize,
+
+    /// The default limit on the memory pool bytes a single query may
+    /// reserve, or [`None`] to allow a query to use the entire pool.
+    ///
+    /// May be overridden per query with
+    /// [`IOxSessionConfig::with_query_memory_limit()`], e.g. to apply a
+    /// per-namespace budget.
+    pub per_query_mem_pool_size: Option<usize>,
+
+    /// Directories that operators supporting it (e.g. sorts) spill to instead
+    /// of failing the query once it reaches its memory limit.
+    ///
+    /// If empty, spilling is disabled.
+    pub spill_dirs: Vec<PathBuf>,
+
+    /// Object store that sorts spill to once they reach the memory limit of their query, instead of the
+    /// [`spill_dirs`](Self::spill_dirs).
+    ///
+    /// This allows queriers with small local volumes to complete large sorts.
+    pub spill_object_store: Option<ObjectStoreSpillConfig>,
+
+    /// Provides additional functions for the queries of a namespace, see
+    /// [`IOxSessionConfig::with_namespace`].
+    pub namespace_udfs: Option<Arc<dyn NamespaceUdfProvider>>,
 }
 
 impl ExecutorConfig {
     pub fn testing() -> Self {
         Self {
             num_threads: NonZeroUsize::new(1).unwrap(),
+            cpu_set: None,
+            runtime_settings: RuntimeSettings::default(),
             target_query_partitions: NonZeroUsize::new(1).unwrap(),
             object_stores: HashMap::default(),
             metric_registry: Arc::new(Registry::default()),
             mem_pool_size: TESTING_MEM_POOL_SIZE,
+            per_query_mem_pool_size: None,
+            spill_dirs: vec![],
+            spill_object_store: None,
+            namespace_udfs: None,
         }
     }
 }
@@ -77,8 +126,22 @@ impl Display for ExecutorConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "num_threads={}, target_query_partitions={}, mem_pool_size={}",
-            self.num_threads, self.target_query_partitions, self.mem_pool_size
+            "num_threads={}, cpu_set={}, runtime_settings={:?}, target_query_partitions={}, \
+             mem_pool_size={}, per_query_mem_pool_size={:?}, spill_dirs={:?}, spill_object_store={}",
+            self.num_threads,
+            self.cpu_set
+                .as_ref()
+                .map(|cpu_set| cpu_set.to_string())
+                .unwrap_or_else(|| "none".to_owned()),
+            self.runtime_settings,
+            self.target_query_partitions,
+            self.mem_pool_size,
+            self.per_query_mem_pool_size,
+            self.spill_dirs,
+            self.spill_object_store
+                .as_ref()
+                .map(|spill| format!("{}/{}", spill.store, spill.prefix))
+                .unwrap_or_else(|| "none".to_owned()),
         )
     }
 }
@@ -91,21 +154,30 @@ pub struct DedicatedExecutors {
     /// Executor for running system/reorganization tasks such as
     /// compact
     reorg_exec: DedicatedExecutor,
-
-    /// Number of threads per thread pool
-    num_threads: NonZeroUsize,
 }
 
 impl DedicatedExecutors {
-    pub fn new(num_threads: NonZeroUsize, metric_registry: Arc<Registry>) -> Self {
-        let query_exec =
-            DedicatedExecutor::new("IOx Query", num_threads, Arc::clone(&metric_registry));
-        let reorg_exec = DedicatedExecutor::new("IOx Reorg", num_threads, metric_registry);
+    pub fn new(
+        num_threads: NonZeroUsize,
+        cpu_set: Option<CpuSet>,
+        runtime_settings: RuntimeSettings,
+        metric_registry: Arc<Registry>,
+    ) -> Self {
+        let new_exec = |thread_name: &'static str, metric_registry: Arc<Registry>| {
+            DedicatedExecutor::new_with_runtime_settings(
+                thread_name,
+                num_threads,
+                cpu_set.clone(),
+                runtime_settings,
+                metric_registry,
+            )
+        };
+        let query_exec = new_exec("IOx Query", Arc::clone(&metric_registry));
+        let reorg_exec = new_exec("IOx Reorg", metric_registry);
 
         Self {
             query_exec,
             reorg_exec,
-            num_threads,
         }
     }
 
@@ -113,16 +185,21 @@ impl DedicatedExecutors {
         let query_exec = DedicatedExecutor::new_testing();
         let reorg_exec = DedicatedExecutor::new_testing();
         assert_eq!(query_exec.num_threads(), reorg_exec.num_threads());
-        let num_threads = query_exec.num_threads();
         Self {
             query_exec,
             reorg_exec,
-            num_threads,
         }
     }
 
+    /// Number of threads per thread pool
     pub fn num_threads(&self) -> NonZeroUsize {
-        self.num_threads
+        self.query_exec.num_threads()
+    }
+
+    /// Change the number of threads of both thread pools, see [`DedicatedExecutor::resize`].
+    pub fn resize(&self, num_threads: NonZeroUsize) {
+        self.query_exec.resize(num_threads);
+        self.reorg_exec.resize(num_threads);
     }
 }
 
@@ -139,6 +216,15 @@ pub struct Executor {
     /// The DataFusion [RuntimeEnv] (including memory manager and disk
     /// manager) used for all executions
     runtime: Arc<RuntimeEnv>,
+
+    /// Per-namespace CPU time metrics
+    cpu_metrics: NamespaceCpuMetrics,
+
+    /// Metrics on the data spilled to disk
+    spill_metrics: SpillMetrics,
+
+    /// Spills sorts to an object store, if configured
+    spill_manager: Option<Arc<ObjectStoreSpillManager>>,
 }
 
 impl Display for Executor {
@@ -166,10 +252,16 @@ impl Executor {
     ) -> Self {
         Self::new_with_config(ExecutorConfig {
             num_threads,
+            cpu_set: None,
+            runtime_settings: RuntimeSettings::default(),
             target_query_partitions: num_threads,
             object_stores: HashMap::default(),
             metric_registry,
             mem_pool_size,
+            per_query_mem_pool_size: None,
+            spill_dirs: vec![],
+            spill_object_store: None,
+            namespace_udfs: None,
         })
     }
 
@@ -177,6 +269,8 @@ impl Executor {
     pub fn new_with_config(config: ExecutorConfig) -> Self {
         let executors = Arc::new(DedicatedExecutors::new(
             config.num_threads,
+            config.cpu_set.clone(),
+            config.runtime_settings,
             Arc::clone(&config.metric_registry),
         ));
         Self::new_with_config_and_executors(config, executors)
@@ -200,10 +294,15 @@ impl Executor {
         config: ExecutorConfig,
         executors: Arc<DedicatedExecutors>,
     ) -> Self {
-        assert_eq!(config.num_threads, executors.num_threads);
+        assert_eq!(config.num_threads, executors.num_threads());
 
+        let disk_manager = if config.spill_dirs.is_empty() {
+            DiskManagerConfig::Disabled
+        } else {
+            DiskManagerConfig::NewSpecified(config.spill_dirs.clone())
+        };
         let runtime_config = RuntimeConfig::new()
-            .with_disk_manager(DiskManagerConfig::Disabled)
+            .with_disk_manager(disk_manager)
             .with_memory_limit(config.mem_pool_size, 1.0);
 
         let runtime = Arc::new(RuntimeEnv::new(runtime_config).expect("creating runtime"));
@@ -229,10 +328,20 @@ impl Executor {
             "More than one execution pool created: previously existing instrument"
         );
 
+        let cpu_metrics = NamespaceCpuMetrics::new(&config.metric_registry);
+        let spill_metrics = SpillMetrics::new(&config.metric_registry);
+        let spill_manager = config
+            .spill_object_store
+            .clone()
+            .map(|spill_config| Arc::new(ObjectStoreSpillManager::new(spill_config)));
+
         Self {
             executors,
             config,
             runtime,
+            cpu_metrics,
+            spill_metrics,
+            spill_manager,
         }
     }
 
@@ -241,8 +350,16 @@ impl Executor {
     /// Note that this context (and all its clones) will be shut down once `Executor` is dropped.
     pub fn new_execution_config(&self, executor_type: ExecutorType) -> IOxSessionConfig {
         let exec = self.executor(executor_type).clone();
-        IOxSessionConfig::new(exec, Arc::clone(&self.runtime))
-            .with_target_partitions(self.config.target_query_partitions)
+        IOxSessionConfig::new(
+            exec,
+            Arc::clone(&self.runtime),
+            self.cpu_metrics.clone(),
+            self.spill_metrics.clone(),
+            self.spill_manager.clone(),
+            self.config.namespace_udfs.clone(),
+        )
+        .with_target_partitions(self.config.target_query_partitions)
+        .with_query_memory_limit(self.config.per_query_mem_pool_size)
     }
 
     /// Create a new execution context, suitable for executing a new query or system task
@@ -260,6 +377,13 @@ impl Executor {
         }
     }
 
+    /// Change the number of threads of all execution pools without interrupting running queries.
+    ///
+    /// Note that this does not change [`ExecutorConfig::target_query_partitions`].
+    pub fn resize(&self, num_threads: NonZeroUsize) {
+        self.executors.resize(num_threads);
+    }
+
     /// Initializes shutdown.
     pub fn shutdown(&self) {
         self.executors.query_exec.shutdown();
@@ -400,23 +524,31 @@ mod tests {
         array::{ArrayRef, Int64Array, StringArray},
         datatypes::{DataType, Field, Schema, SchemaRef},
     };
+    use assert_matches::assert_matches;
     use datafusion::{
         datasource::{provider_as_source, MemTable},
         error::DataFusionError,
-        logical_expr::LogicalPlanBuilder,
+        execution::memory_pool::MemoryConsumer,
+        logical_expr::{
+            create_udf, ColumnarValue, LogicalPlanBuilder, ScalarUDF, Signature, Volatility,
+        },
         physical_expr::PhysicalSortExpr,
         physical_plan::{
-            expressions::Column, sorts::sort::SortExec, DisplayAs, ExecutionPlan, RecordBatchStream,
+            expressions::Column, memory::MemoryExec, sorts::sort::SortExec, DisplayAs,
+            ExecutionPlan, RecordBatchStream,
         },
     };
     use futures::{stream::BoxStream, Stream, StreamExt};
     use metric::{Observation, RawReporter};
+    use std::time::Duration;
     use stringset::StringSet;
+    use test_helpers::assert_contains;
     use tokio::sync::Barrier;
 
     use super::*;
     use crate::exec::stringset::StringSetRef;
     use crate::plan::stringset::StringSetPlan;
+    use crate::warnings::QueryWarningKind;
     use arrow::record_batch::RecordBatch;
 
     #[tokio::test]
@@ -625,6 +757,311 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_per_query_memory_limit() {
+        let exec = Executor::new_with_config_and_executors(
+            ExecutorConfig {
+                mem_pool_size: 1000,
+                per_query_mem_pool_size: Some(100),
+                ..ExecutorConfig::testing()
+            },
+            Arc::new(DedicatedExecutors::new_testing()),
+        );
+
+        let ctx = exec.new_context(ExecutorType::Query);
+        let pool = Arc::clone(&ctx.inner().runtime_env().memory_pool);
+        let mut reservation = MemoryConsumer::new("test").register(&pool);
+        reservation.try_grow(100).unwrap();
+        assert_eq!(exec.pool().reserved(), 100);
+
+        let err = reservation.try_grow(1).unwrap_err();
+        assert_matches!(err, DataFusionError::ResourcesExhausted(_));
+
+        // Other queries have their own limit within the shared pool.
+        let other = exec
+            .new_execution_config(ExecutorType::Query)
+            .with_query_memory_limit(Some(500))
+            .build();
+        let pool = Arc::clone(&other.inner().runtime_env().memory_pool);
+        let mut other_reservation = MemoryConsumer::new("test").register(&pool);
+        other_reservation.try_grow(500).unwrap();
+        assert_eq!(exec.pool().reserved(), 600);
+
+        drop(reservation);
+        drop(other_reservation);
+        assert_eq!(exec.pool().reserved(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_per_query_memory_limit_spill() {
+        let spill_dir = test_helpers::tmp_dir().unwrap();
+        let exec = Executor::new_with_config_and_executors(
+            ExecutorConfig {
+                per_query_mem_pool_size: Some(1024 * 1024),
+                spill_dirs: vec![spill_dir.path().to_owned()],
+                ..ExecutorConfig::testing()
+            },
+            Arc::new(DedicatedExecutors::new_testing()),
+        );
+
+        // 20 batches of 64KiB each, exceeding the query memory limit
+        let batches = (0..20)
+            .map(|i| {
+                RecordBatch::try_from_iter(vec![(
+                    "c",
+                    Arc::new(Int64Array::from_iter_values(
+                        (0..8_192).map(|v| (v * 20 + i) as i64).rev(),
+                    )) as ArrayRef,
+                )])
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let schema = batches[0].schema();
+        let input = Arc::new(MemoryExec::try_new(&[batches], Arc::clone(&schema), None).unwrap());
+        let plan = Arc::new(SortExec::new(
+            vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new_with_schema("c", &schema).unwrap()),
+                options: Default::default(),
+            }],
+            input,
+        ));
+
+        let ctx = exec
+            .new_execution_config(ExecutorType::Query)
+            .with_config_option("datafusion.execution.sort_spill_reservation_bytes", "65536")
+            .build();
+        let results = ctx.collect(plan).await.unwrap();
+        assert_eq!(
+            results.iter().map(|b| b.num_rows()).sum::<usize>(),
+            20 * 8_192
+        );
+
+        let (spill_count, spilled_bytes) = read_spills(&exec.config.metric_registry);
+        assert!(spill_count > 0);
+        assert!(spilled_bytes > 0);
+    }
+
+    fn read_spills(registry: &Registry) -> (u64, u64) {
+        let mut reporter = RawReporter::default();
+        registry.report(&mut reporter);
+
+        let read = |name| {
+            let metric = reporter.metric(name).unwrap();
+            match metric.observation(&[]).unwrap() {
+                Observation::U64Counter(v) => *v,
+                _ => panic!("wrong metric type"),
+            }
+        };
+        (read("query_spill_count"), read("query_spilled_bytes"))
+    }
+
+    #[tokio::test]
+    async fn test_namespace_cpu_metrics() {
+        let exec = Executor::new_testing();
+
+        let batch = RecordBatch::try_from_iter(vec![(
+            "c",
+            Arc::new(Int64Array::from_iter_values((0..10_000).rev())) as ArrayRef,
+        )])
+        .unwrap();
+        let schema = batch.schema();
+        let input =
+            Arc::new(MemoryExec::try_new(&[vec![batch]], Arc::clone(&schema), None).unwrap());
+        let plan = Arc::new(SortExec::new(
+            vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new_with_schema("c", &schema).unwrap()),
+                options: Default::default(),
+            }],
+            input,
+        ));
+
+        // not accounted w/o namespace
+        let ctx = exec.new_context(ExecutorType::Query);
+        ctx.collect(Arc::clone(&plan) as _).await.unwrap();
+        assert_eq!(read_namespace_cpu(&exec.config.metric_registry, "ns"), None);
+
+        let ctx = exec
+            .new_execution_config(ExecutorType::Query)
+            .with_namespace("ns")
+            .build();
+        ctx.collect(plan).await.unwrap();
+        let elapsed = read_namespace_cpu(&exec.config.metric_registry, "ns").unwrap();
+        assert!(elapsed > Duration::ZERO);
+        assert_eq!(
+            read_namespace_cpu(&exec.config.metric_registry, "other"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_implicit_cast_warnings() {
+        let exec = Executor::new_testing();
+        let schema = Arc::new(Schema::new(vec![Field::new("c", DataType::Int64, true)]));
+        let table = Arc::new(MemTable::try_new(schema, vec![vec![]]).unwrap());
+
+        let warnings = |sql: &'static str| {
+            let ctx = exec.new_context(ExecutorType::Query);
+            ctx.inner()
+                .register_table("t", Arc::clone(&table) as _)
+                .unwrap();
+            async move {
+                ctx.sql_to_logical_plan(sql).await.unwrap();
+                ctx.warnings().get()
+            }
+        };
+
+        assert_eq!(warnings("SELECT c FROM t").await, vec![]);
+        assert_eq!(warnings("SELECT CAST(c AS VARCHAR) FROM t").await, vec![]);
+
+        let implicit = warnings("SELECT c FROM t WHERE c = '1'").await;
+        assert!(!implicit.is_empty());
+        for warning in implicit {
+            assert_eq!(warning.kind, QueryWarningKind::ImplicitCast);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_settings() {
+        let exec = Executor::new_with_config_and_executors(
+            ExecutorConfig {
+                per_query_mem_pool_size: Some(1000),
+                ..ExecutorConfig::testing()
+            },
+            Arc::new(DedicatedExecutors::new_testing()),
+        );
+
+        let ctx = exec.new_context(ExecutorType::Query);
+        ctx.sql_to_logical_plan(
+            "SET datafusion.execution.batch_size = 100; SET iox.query_memory_limit = 500; SELECT 1",
+        )
+        .await
+        .unwrap();
+        assert_eq!(ctx.inner().copied_config().batch_size(), 100);
+        assert_eq!(ctx.memory_pool().limit(), 500);
+        assert_eq!(
+            ctx.session_settings().get(),
+            vec![
+                (
+                    "datafusion.execution.batch_size".to_owned(),
+                    "100".to_owned()
+                ),
+                ("iox.query_memory_limit".to_owned(), "500".to_owned()),
+            ]
+        );
+
+        // other queries are not affected
+        let ctx = exec.new_context(ExecutorType::Query);
+        assert_ne!(ctx.inner().copied_config().batch_size(), 100);
+        assert_eq!(ctx.memory_pool().limit(), 1000);
+
+        let err = |sql: &'static str| {
+            let ctx = exec.new_context(ExecutorType::Query);
+            async move { ctx.sql_to_logical_plan(sql).await.unwrap_err().to_string() }
+        };
+        assert_contains!(
+            err("SET datafusion.optimizer.max_passes = 1; SELECT 1").await,
+            "'datafusion.optimizer.max_passes' cannot be set per query"
+        );
+        assert_contains!(
+            err("SET iox.query_memory_limit = 2000; SELECT 1").await,
+            "'iox.query_memory_limit' of 2000 bytes exceeds the limit of 1000 bytes"
+        );
+        assert_contains!(
+            err("SET datafusion.execution.batch_size = 0; SELECT 1").await,
+            "'datafusion.execution.batch_size' must be between 1 and 8192, got 0"
+        );
+        assert_contains!(
+            err("SET datafusion.execution.batch_size = 1000000000; SELECT 1").await,
+            "'datafusion.execution.batch_size' must be between 1 and 8192, got 1000000000"
+        );
+        assert_contains!(
+            err("SET datafusion.execution.batch_size = -1; SELECT 1").await,
+            "Invalid value for 'datafusion.execution.batch_size'"
+        );
+        assert_contains!(
+            err("SELECT 1; SELECT 2").await,
+            "Only SET statements may precede the query"
+        );
+        assert_contains!(
+            err("SET datafusion.execution.batch_size = 100").await,
+            "not supported"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namespace_udfs() {
+        #[derive(Debug)]
+        struct TestUdfs;
+
+        impl NamespaceUdfProvider for TestUdfs {
+            fn scalar_udfs(&self, namespace: &str) -> Vec<Arc<ScalarUDF>> {
+                if namespace != "ns" {
+                    return vec![];
+                }
+
+                let identity = |name: &str| {
+                    Arc::new(create_udf(
+                        name,
+                        vec![DataType::Int64],
+                        Arc::new(DataType::Int64),
+                        Volatility::Immutable,
+                        Arc::new(|args: &[ColumnarValue]| Ok(args[0].clone())),
+                    ))
+                };
+                // `date_bin_gapfill` is an IOx function and must not be replaced
+                vec![identity("to_unit"), identity("date_bin_gapfill")]
+            }
+        }
+
+        let exec = Executor::new_with_config_and_executors(
+            ExecutorConfig {
+                namespace_udfs: Some(Arc::new(TestUdfs)),
+                ..ExecutorConfig::testing()
+            },
+            Arc::new(DedicatedExecutors::new_testing()),
+        );
+        let ctx = |namespace: &str| {
+            exec.new_execution_config(ExecutorType::Query)
+                .with_namespace(namespace)
+                .build()
+        };
+
+        let ns = ctx("ns");
+        let batches = ns
+            .collect(
+                ns.sql_to_physical_plan("SELECT to_unit(1) AS x")
+                    .await
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let expected: ArrayRef = Arc::new(Int64Array::from(vec![1]));
+        assert_eq!(batches[0].column(0), &expected);
+        let date_bin_gapfill =
+            Arc::clone(&ns.inner().state().scalar_functions()["date_bin_gapfill"]);
+        assert_ne!(
+            date_bin_gapfill.signature(),
+            &Signature::exact(vec![DataType::Int64], Volatility::Immutable),
+        );
+
+        let other = ctx("other");
+        other
+            .sql_to_physical_plan("SELECT to_unit(1) AS x")
+            .await
+            .unwrap_err();
+    }
+
+    fn read_namespace_cpu(registry: &Registry, namespace: &'static str) -> Option<Duration> {
+        let mut reporter = RawReporter::default();
+        registry.report(&mut reporter);
+
+        let metric = reporter.metric("query_namespace_elapsed_compute").unwrap();
+        match metric.observation(&[("namespace", namespace)])? {
+            Observation::DurationCounter(d) => Some(*d),
+            _ => panic!("wrong metric type"),
+        }
+    }
+
     /// return a set for testing
     fn to_set(strs: &[&str]) -> StringSetRef {
         StringSetRef::new(strs.iter().map(|s| s.to_string()).collect::<StringSet>())
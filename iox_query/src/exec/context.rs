@@ -2,11 +2,15 @@
 //! DataFusion
 
 use super::{
+    cpu_accounting::{CpuAccountedStream, NamespaceCpuMetrics},
     cross_rt_stream::CrossRtStream,
     gapfill::{plan_gap_fill, GapFill},
+    namespace_udfs::{register_namespace_udfs, NamespaceUdfProvider},
     non_null_checker::NonNullCheckerNode,
+    query_memory::QueryMemoryPool,
     seriesset::{series::Either, SeriesSet},
     sleep::SleepNode,
+    spill_accounting::{SpillAccountedStream, SpillMetrics},
     split::StreamSplitNode,
 };
 use crate::{
@@ -14,8 +18,10 @@ use crate::{
     exec::{
         fieldlist::{FieldList, IntoFieldList},
         non_null_checker::NonNullCheckerExec,
+        object_store_spill::ObjectStoreSpillManager,
         query_tracing::TracedStream,
         schema_pivot::{SchemaPivotExec, SchemaPivotNode},
+        series_limit::SeriesCounter,
         seriesset::{
             converter::{GroupGenerator, SeriesSetConverter},
             series::Series,
@@ -30,35 +36,52 @@ use crate::{
         seriesset::{SeriesSetPlan, SeriesSetPlans},
         stringset::StringSetPlan,
     },
+    pruning::PruningStats,
+    session_settings::{SessionSettings, BATCH_SIZE, QUERY_MEMORY_LIMIT, SETTABLE},
+    warnings::{QueryWarning, QueryWarningKind, QueryWarnings},
 };
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
 use datafusion::{
     catalog::CatalogProvider,
-    common::ParamValues,
+    common::{
+        plan_err,
+        tree_node::{TreeNode, VisitRecursion},
+        ParamValues,
+    },
     execution::{
         context::{QueryPlanner, SessionState, TaskContext},
         memory_pool::MemoryPool,
         runtime_env::RuntimeEnv,
     },
-    logical_expr::{LogicalPlan, UserDefinedLogicalNode},
+    logical_expr::{
+        LogicalPlan, SetVariable, Statement as LogicalStatement, UserDefinedLogicalNode,
+    },
+    optimizer::analyzer::{type_coercion::TypeCoercion, AnalyzerRule},
     physical_plan::{
         coalesce_partitions::CoalescePartitionsExec, displayable, stream::RecordBatchStreamAdapter,
         EmptyRecordBatchStream, ExecutionPlan, RecordBatchStream, SendableRecordBatchStream,
     },
     physical_planner::{DefaultPhysicalPlanner, ExtensionPlanner, PhysicalPlanner},
     prelude::*,
+    sql::parser::{DFParser, Statement as SqlStatement},
 };
 use datafusion_util::config::{iox_session_config, DEFAULT_CATALOG};
 use executor::DedicatedExecutor;
 use futures::{Stream, StreamExt, TryStreamExt};
+use metric::DurationCounter;
 use observability_deps::tracing::{debug, warn};
-use query_functions::{register_scalar_functions, selectors::register_selector_aggregates};
+use query_functions::{
+    register_aggregate_functions, register_scalar_functions,
+    selectors::register_selector_aggregates,
+};
 use std::{fmt, num::NonZeroUsize, sync::Arc};
+use tokio_util::sync::CancellationToken;
 use trace::{
     ctx::SpanContext,
     span::{MetaValue, Span, SpanEvent, SpanExt, SpanRecorder},
 };
+use tracker::WorkloadClass;
 
 // Reuse DataFusion error and Result types for this module
 pub use datafusion::error::{DataFusionError, Result};
@@ -181,6 +204,27 @@ pub struct IOxSessionConfig {
 
     /// Span context from which to create spans for this query
     span_ctx: Option<SpanContext>,
+
+    /// Limit on the bytes of the shared memory pool this query may reserve
+    query_mem_limit: Option<usize>,
+
+    /// Namespace the query runs against, used for CPU accounting
+    namespace: Option<Arc<str>>,
+
+    /// Per-namespace CPU time metrics
+    cpu_metrics: NamespaceCpuMetrics,
+
+    /// Metrics on the data spilled to disk
+    spill_metrics: SpillMetrics,
+
+    /// Spills sorts to an object store, if configured
+    spill_manager: Option<Arc<ObjectStoreSpillManager>>,
+
+    /// Provides additional functions for the namespace, if configured
+    namespace_udfs: Option<Arc<dyn NamespaceUdfProvider>>,
+
+    /// Token to cancel the query
+    cancel: CancellationToken,
 }
 
 impl fmt::Debug for IOxSessionConfig {
@@ -190,12 +234,22 @@ impl fmt::Debug for IOxSessionConfig {
 }
 
 impl IOxSessionConfig {
-    pub(super) fn new(exec: DedicatedExecutor, runtime: Arc<RuntimeEnv>) -> Self {
+    pub(super) fn new(
+        exec: DedicatedExecutor,
+        runtime: Arc<RuntimeEnv>,
+        cpu_metrics: NamespaceCpuMetrics,
+        spill_metrics: SpillMetrics,
+        spill_manager: Option<Arc<ObjectStoreSpillManager>>,
+        namespace_udfs: Option<Arc<dyn NamespaceUdfProvider>>,
+    ) -> Self {
         let mut session_config = iox_session_config();
         session_config
             .options_mut()
             .extensions
-            .insert(IoxConfigExt::default());
+            .insert(IoxConfigExt {
+                object_store_spill: spill_manager.is_some(),
+                ..Default::default()
+            });
 
         Self {
             exec,
@@ -203,6 +257,13 @@ impl IOxSessionConfig {
             runtime,
             default_catalog: None,
             span_ctx: None,
+            query_mem_limit: None,
+            namespace: None,
+            cpu_metrics,
+            spill_metrics,
+            spill_manager,
+            namespace_udfs,
+            cancel: CancellationToken::new(),
         }
     }
 
@@ -227,6 +288,46 @@ impl IOxSessionConfig {
         Self { span_ctx, ..self }
     }
 
+    /// Limit the number of bytes of the executor memory pool this query may
+    /// reserve, or [`None`] to allow it to use the entire pool.
+    ///
+    /// A query exceeding its limit fails with
+    /// [`DataFusionError::ResourcesExhausted`], unless spilling is enabled
+    /// (see [`ExecutorConfig::spill_dirs`](super::ExecutorConfig::spill_dirs))
+    /// and all operators exceeding it can spill.
+    pub fn with_query_memory_limit(self, query_mem_limit: Option<usize>) -> Self {
+        Self {
+            query_mem_limit,
+            ..self
+        }
+    }
+
+    /// Set the namespace this query runs against.
+    ///
+    /// The functions provided for this namespace by
+    /// [`ExecutorConfig::namespace_udfs`](super::ExecutorConfig::namespace_udfs) are registered with the resulting
+    /// context.
+    ///
+    /// The DataFusion elapsed compute time of all plans executed by the resulting context is accounted to this
+    /// namespace in the `query_namespace_elapsed_compute` metric. Plans are accounted when their result stream is
+    /// dropped. Plans with multiple output partitions should be executed via [`IOxSessionContext::execute_stream`],
+    /// because executing each partition separately accounts their shared inputs multiple times.
+    pub fn with_namespace(self, namespace: impl Into<Arc<str>>) -> Self {
+        Self {
+            namespace: Some(namespace.into()),
+            ..self
+        }
+    }
+
+    /// Set the token to cancel this query, e.g. when it times out or the client disconnects.
+    ///
+    /// Once cancelled, all tasks that execute the query on the executor are aborted promptly. This drops the
+    /// underlying DataFusion streams and with them their memory reservations. Result streams return an error that
+    /// wraps [`JobError::Cancelled`](executor::JobError::Cancelled), so the caller may release its semaphore permit.
+    pub fn with_cancellation_token(self, cancel: CancellationToken) -> Self {
+        Self { cancel, ..self }
+    }
+
     /// Set DataFusion [config option].
     ///
     /// May be used to set [IOx-specific] option as well.
@@ -253,26 +354,88 @@ impl IOxSessionConfig {
         let recorder = SpanRecorder::new(maybe_span);
 
         // attach span to DataFusion session
-        let session_config = self
+        let mut session_config = self
             .session_config
             .with_extension(Arc::new(recorder.span().cloned()));
+        if let Some(spill_manager) = self.spill_manager {
+            session_config = session_config.with_extension(spill_manager);
+        }
 
-        let state = SessionState::new_with_config_rt(session_config, self.runtime)
+        // Account the reservations of this query (and enforce its own limit,
+        // if any), in addition to the shared pool
+        let shared_pool = Arc::clone(&self.runtime.memory_pool);
+        let memory_pool = Arc::new(match self.query_mem_limit {
+            Some(limit) => QueryMemoryPool::new(shared_pool, limit),
+            None => QueryMemoryPool::new_unlimited(shared_pool),
+        });
+        let runtime = Arc::new(RuntimeEnv {
+            memory_pool: Arc::clone(&memory_pool) as _,
+            disk_manager: Arc::clone(&self.runtime.disk_manager),
+            cache_manager: Arc::clone(&self.runtime.cache_manager),
+            object_store_registry: Arc::clone(&self.runtime.object_store_registry),
+        });
+
+        let max_batch_size = session_config.batch_size();
+        let state = SessionState::new_with_config_rt(session_config, runtime)
             .with_query_planner(Arc::new(IOxQueryPlanner {}));
         let state = register_iox_physical_optimizers(state);
         let state = register_iox_logical_optimizers(state);
 
         let inner = SessionContext::new_with_state(state);
         register_selector_aggregates(&inner);
+        register_aggregate_functions(&inner);
         register_scalar_functions(&inner);
+        if let (Some(namespace_udfs), Some(namespace)) = (&self.namespace_udfs, &self.namespace) {
+            register_namespace_udfs(&inner, namespace_udfs.as_ref(), namespace);
+        }
         if let Some(default_catalog) = self.default_catalog {
             inner.register_catalog(DEFAULT_CATALOG, default_catalog);
         }
 
-        IOxSessionContext::new(inner, self.exec, recorder)
+        let cpu_recorder = self
+            .namespace
+            .map(|namespace| self.cpu_metrics.recorder(&namespace));
+
+        IOxSessionContext::new(
+            inner,
+            self.exec,
+            recorder,
+            cpu_recorder,
+            max_batch_size,
+            QueryState {
+                memory_pool,
+                spill_metrics: Some(self.spill_metrics),
+                pruning_stats: Default::default(),
+                warnings: Default::default(),
+                session_settings: Default::default(),
+                cancel: self.cancel,
+            },
+        )
     }
 }
 
+/// State of a single query, shared by an [`IOxSessionContext`] and its child contexts.
+#[derive(Debug, Clone)]
+pub(crate) struct QueryState {
+    /// Pool accounting the memory reservations of this query
+    memory_pool: Arc<QueryMemoryPool>,
+
+    /// Metrics on the data spilled to disk, if tracked
+    spill_metrics: Option<SpillMetrics>,
+
+    /// Partitions and parquet files pruned while planning this query
+    pruning_stats: Arc<PruningStats>,
+
+    /// Warnings raised while planning this query
+    warnings: Arc<QueryWarnings>,
+
+    /// Settings changed by `SET` statements preceding this query
+    session_settings: Arc<SessionSettings>,
+
+    /// Token to cancel all tasks of this query
+    cancel: CancellationToken,
+}
+
 /// This is an execution context for planning in IOx.  It wraps a
 /// DataFusion execution context with the information needed for planning.
 ///
@@ -298,6 +461,18 @@ pub struct IOxSessionContext {
 
     /// Span context from which to create spans for this query
     recorder: SpanRecorder,
+
+    /// Recorder for the CPU time of the namespace this query runs against, if known
+    cpu_recorder: Option<DurationCounter>,
+
+    /// Batch size configured for this query, which `SET` statements may not exceed
+    max_batch_size: usize,
+
+    /// Workload class of this query, used to prioritize its admission
+    workload_class: WorkloadClass,
+
+    /// State shared with the child contexts
+    query: QueryState,
 }
 
 impl fmt::Debug for IOxSessionContext {
@@ -306,6 +481,10 @@ impl fmt::Debug for IOxSessionContext {
             .field("inner", &"<DataFusion ExecutionContext>")
             .field("exec", &self.exec)
             .field("recorder", &self.recorder)
+            .field("cpu_recorder", &self.cpu_recorder)
+            .field("max_batch_size", &self.max_batch_size)
+            .field("workload_class", &self.workload_class)
+            .field("query", &self.query)
             .finish()
     }
 }
@@ -316,10 +495,31 @@ impl IOxSessionContext {
     /// This is identical to [`Default::default`] but we do NOT implement [`Default`] to make the creation of untracked
     /// contexts more explicit.
     pub fn with_testing() -> Self {
+        let runtime = RuntimeEnv::default();
+        let memory_pool = Arc::new(QueryMemoryPool::new_unlimited(Arc::clone(
+            &runtime.memory_pool,
+        )));
+        let runtime = RuntimeEnv {
+            memory_pool: Arc::clone(&memory_pool) as _,
+            ..runtime
+        };
+
+        let session_config = SessionConfig::new();
         Self {
-            inner: SessionContext::default(),
+            max_batch_size: session_config.batch_size(),
+            inner: SessionContext::new_with_config_rt(session_config, Arc::new(runtime)),
             exec: DedicatedExecutor::new_testing(),
             recorder: SpanRecorder::default(),
+            cpu_recorder: None,
+            workload_class: Default::default(),
+            query: QueryState {
+                memory_pool,
+                spill_metrics: None,
+                pruning_stats: Default::default(),
+                warnings: Default::default(),
+                session_settings: Default::default(),
+                cancel: CancellationToken::new(),
+            },
         }
     }
 
@@ -328,11 +528,18 @@ impl IOxSessionContext {
         inner: SessionContext,
         exec: DedicatedExecutor,
         recorder: SpanRecorder,
+        cpu_recorder: Option<DurationCounter>,
+        max_batch_size: usize,
+        query: QueryState,
     ) -> Self {
         Self {
             inner,
             exec,
             recorder,
+            cpu_recorder,
+            max_batch_size,
+            workload_class: Default::default(),
+            query,
         }
     }
 
@@ -341,6 +548,38 @@ impl IOxSessionContext {
         &self.inner
     }
 
+    /// The highest number of bytes of memory reserved by this query at any
+    /// one time so far.
+    pub fn peak_memory_bytes(&self) -> usize {
+        self.query.memory_pool.peak()
+    }
+
+    /// Pool accounting the memory reservations of this query.
+    pub(crate) fn memory_pool(&self) -> &Arc<QueryMemoryPool> {
+        &self.query.memory_pool
+    }
+
+    /// Partitions and parquet files pruned while planning this query.
+    ///
+    /// Implementations of [`QueryNamespace::chunks`](crate::QueryNamespace::chunks) record what they prune here, so
+    /// that the query log can tell how effective pruning was.
+    pub fn pruning_stats(&self) -> &Arc<PruningStats> {
+        &self.query.pruning_stats
+    }
+
+    /// Warnings raised while planning this query.
+    ///
+    /// Planners record parts of the query that were accepted but probably do not do what the user intended here, so
+    /// that they can be returned to the client.
+    pub fn warnings(&self) -> &Arc<QueryWarnings> {
+        &self.query.warnings
+    }
+
+    /// Settings changed by `SET` statements preceding this query, see [`session_settings`](crate::session_settings).
+    pub fn session_settings(&self) -> &Arc<SessionSettings> {
+        &self.query.session_settings
+    }
+
     /// Plan a SQL statement. This assumes that any tables referenced
     /// in the SQL have been registered with this context. Use
     /// `create_physical_plan` to actually execute the query.
@@ -359,22 +598,110 @@ impl IOxSessionContext {
         params: impl Into<ParamValues> + Send,
     ) -> Result<LogicalPlan> {
         let ctx = self.child_ctx("sql_to_logical_plan");
-        debug!(text=%sql, "planning SQL query");
         let plan = ctx
-            .inner
-            .state()
-            .create_logical_plan(sql)
+            .sql_to_prepared_logical_plan(sql)
             .await?
             .with_param_values(params.into())?;
+        Ok(plan)
+    }
+
+    /// Plan a SQL statement without supplying values to its `$placeholder` variables.
+    ///
+    /// This is used for prepared statements, e.g. to determine the types of their parameters via
+    /// [`LogicalPlan::get_parameter_types`]. Use [`LogicalPlan::with_param_values`] to bind the
+    /// parameters before creating a physical plan.
+    ///
+    /// The statement may be preceded by `SET` statements that change settings for this query, see
+    /// [`session_settings`](crate::session_settings).
+    pub async fn sql_to_prepared_logical_plan(&self, sql: &str) -> Result<LogicalPlan> {
+        let ctx = self.child_ctx("sql_to_prepared_logical_plan");
+        debug!(text=%sql, "planning SQL query");
+        let mut statements = DFParser::parse_sql(sql)?;
+        let Some(statement) = statements.pop_back() else {
+            return plan_err!("No SQL statements were provided in the query string");
+        };
+        for setting in statements {
+            ctx.apply_setting(setting).await?;
+        }
+        let plan = ctx.inner.state().statement_to_plan(statement).await?;
         // ensure the plan does not contain unwanted statements
         let verifier = SQLOptions::new()
             .with_allow_ddl(false) // no CREATE ...
             .with_allow_dml(false) // no INSERT or COPY
             .with_allow_statements(false); // no SET VARIABLE, etc
         verifier.verify_plan(&plan)?;
+        ctx.record_implicit_casts(&plan);
         Ok(plan)
     }
 
+    /// Apply a `SET` statement that precedes the query.
+    async fn apply_setting(&self, statement: SqlStatement) -> Result<()> {
+        let plan = self.inner.state().statement_to_plan(statement).await?;
+        let LogicalPlan::Statement(LogicalStatement::SetVariable(SetVariable {
+            variable,
+            value,
+            ..
+        })) = &plan
+        else {
+            return plan_err!("Only SET statements may precede the query");
+        };
+        if !SETTABLE.contains(&variable.as_str()) {
+            return plan_err!(
+                "'{variable}' cannot be set per query, supported settings are: {}",
+                SETTABLE.join(", ")
+            );
+        }
+
+        if variable == QUERY_MEMORY_LIMIT {
+            let limit = value.parse::<usize>().map_err(|e| {
+                DataFusionError::Plan(format!("Invalid value for '{variable}': {e}"))
+            })?;
+            self.query.memory_pool.set_limit(limit).map_err(|cap| {
+                DataFusionError::Plan(format!(
+                    "'{variable}' of {limit} bytes exceeds the limit of {cap} bytes"
+                ))
+            })?;
+        } else if variable == BATCH_SIZE {
+            let batch_size = value.parse::<usize>().map_err(|e| {
+                DataFusionError::Plan(format!("Invalid value for '{variable}': {e}"))
+            })?;
+            if !(1..=self.max_batch_size).contains(&batch_size) {
+                return plan_err!(
+                    "'{variable}' must be between 1 and {}, got {batch_size}",
+                    self.max_batch_size
+                );
+            }
+            self.inner.execute_logical_plan(plan.clone()).await?;
+        } else {
+            self.inner.execute_logical_plan(plan.clone()).await?;
+        }
+
+        self.query.session_settings.set(variable, value);
+        Ok(())
+    }
+
+    /// Record a warning for every cast that type coercion will add to `plan`.
+    ///
+    /// Type coercion only runs as part of the optimizer, so it is applied to a copy of the plan here. Errors are
+    /// ignored, they are reported once the plan is optimized.
+    fn record_implicit_casts(&self, plan: &LogicalPlan) {
+        let Ok(coerced) =
+            TypeCoercion::new().analyze(plan.clone(), self.inner.state().config_options())
+        else {
+            return;
+        };
+
+        let explicit = casts(plan);
+        for cast in casts(&coerced) {
+            if !explicit.contains(&cast) {
+                self.query.warnings.push(QueryWarning::new(
+                    QueryWarningKind::ImplicitCast,
+                    format!("implicit cast: {cast}"),
+                ));
+            }
+        }
+    }
+
     /// Create a logical plan that reads a single [`RecordBatch`]. Use
     /// `create_physical_plan` to actually execute the query.
     pub fn batch_to_logical_plan(&self, batch: RecordBatch) -> Result<LogicalPlan> {
@@ -474,11 +801,28 @@ impl IOxSessionContext {
             .map(|span| span.child("execute_stream_partitioned"));
 
         let task_context = Arc::new(TaskContext::from(self.inner()));
+        let cpu_recorder = self.cpu_recorder.clone();
+        let spill_metrics = self.query.spill_metrics.clone();
 
         let stream = self
             .run(async move {
                 let stream = physical_plan.execute(partition, task_context)?;
-                Ok(TracedStream::new(stream, span, physical_plan))
+                let stream = TracedStream::new(stream, span, Arc::clone(&physical_plan));
+                let stream: SendableRecordBatchStream = match spill_metrics {
+                    Some(spill_metrics) => Box::pin(SpillAccountedStream::new(
+                        Box::pin(stream),
+                        Arc::clone(&physical_plan),
+                        spill_metrics,
+                    )),
+                    None => Box::pin(stream),
+                };
+                let stream: SendableRecordBatchStream = match cpu_recorder {
+                    Some(cpu_recorder) => {
+                        Box::pin(CpuAccountedStream::new(stream, physical_plan, cpu_recorder))
+                    }
+                    None => stream,
+                };
+                Ok(stream)
             })
             .await?;
         // Wrap the resulting stream into `CrossRtStream`. This is required because polling the DataFusion result stream
@@ -486,7 +830,8 @@ impl IOxSessionContext {
         // dedicated executor because otherwise this may block the top-level tokio/tonic runtime which may lead to
         // requests timetouts (either for new requests, metrics or even for HTTP2 pings on the active connection).
         let schema = stream.schema();
-        let stream = CrossRtStream::new_with_df_error_stream(stream, self.exec.clone());
+        let stream =
+            CrossRtStream::new_with_df_error_stream(stream, self.exec.clone(), &self.query.cancel);
         let stream = RecordBatchStreamAdapter::new(schema, stream);
         Ok(Box::pin(stream))
     }
@@ -511,16 +856,29 @@ impl IOxSessionContext {
         // sort plans by table (measurement) name
         plans.sort_by(|a, b| a.table_name.cmp(&b.table_name));
 
+        // the series of all tables count against the same limit
+        let max_series = self
+            .inner
+            .copied_config()
+            .options()
+            .extensions
+            .get::<IoxConfigExt>()
+            .map(|iox_config| iox_config.max_series)
+            .unwrap_or_default();
+        let series_counter = (max_series > 0).then(|| SeriesCounter::new(max_series));
+
         // Run the plans in parallel
         let ctx = self.child_ctx("to_series_set");
         let exec = self.exec.clone();
+        let cancel = self.query.cancel.clone();
         let data = futures::stream::iter(plans)
             .then(move |plan| {
                 let ctx = ctx.child_ctx("for plan");
                 let exec = exec.clone();
+                let cancel = cancel.clone();
 
                 async move {
-                    let stream = Self::run_inner(exec.clone(), async move {
+                    let stream = Self::run_inner(exec.clone(), &cancel, async move {
                         let SeriesSetPlan {
                             table_name,
                             plan,
@@ -540,7 +898,9 @@ impl IOxSessionContext {
                     })
                     .await?;
 
-                    Ok::<_, DataFusionError>(CrossRtStream::new_with_df_error_stream(stream, exec))
+                    Ok::<_, DataFusionError>(CrossRtStream::new_with_df_error_stream(
+                        stream, exec, &cancel,
+                    ))
                 }
             })
             .try_flatten()
@@ -561,7 +921,14 @@ impl IOxSessionContext {
                     })?;
                 Ok(Some(futures::stream::iter(series).map(Ok)))
             })
-            .try_flatten();
+            .try_flatten()
+            .and_then(move |series| {
+                let res = match &series_counter {
+                    Some(series_counter) => series_counter.add(1).map_err(DataFusionError::from),
+                    None => Ok(()),
+                };
+                futures::future::ready(res.map(|_| series))
+            });
 
         // If we have group columns, sort the results, and create the
         // appropriate groups
@@ -678,20 +1045,26 @@ impl IOxSessionContext {
         Fut: std::future::Future<Output = Result<T>> + Send + 'static,
         T: Send + 'static,
     {
-        Self::run_inner(self.exec.clone(), fut).await
+        Self::run_inner(self.exec.clone(), &self.query.cancel, fut).await
     }
 
-    async fn run_inner<Fut, T>(exec: DedicatedExecutor, fut: Fut) -> Result<T>
+    async fn run_inner<Fut, T>(
+        exec: DedicatedExecutor,
+        cancel: &CancellationToken,
+        fut: Fut,
+    ) -> Result<T>
     where
         Fut: std::future::Future<Output = Result<T>> + Send + 'static,
         T: Send + 'static,
     {
-        exec.spawn(fut).await.unwrap_or_else(|e| {
-            Err(DataFusionError::Context(
-                "Join Error".to_string(),
-                Box::new(DataFusionError::External(Box::new(e))),
-            ))
-        })
+        exec.spawn_with_cancellation(fut, cancel)
+            .await
+            .unwrap_or_else(|e| {
+                Err(DataFusionError::Context(
+                    "Join Error".to_string(),
+                    Box::new(DataFusionError::External(Box::new(e))),
+                ))
+            })
     }
 
     /// Returns a IOxSessionContext with a SpanRecorder that is a child of the current
@@ -700,7 +1073,11 @@ impl IOxSessionContext {
             self.inner.clone(),
             self.exec.clone(),
             self.recorder.child(name),
+            self.cpu_recorder.clone(),
+            self.max_batch_size,
+            self.query.clone(),
         )
+        .with_workload_class(self.workload_class)
     }
 
     /// Record an event on the span recorder
@@ -723,12 +1100,51 @@ impl IOxSessionContext {
         self.recorder.child_span(name)
     }
 
+    /// Token to cancel this query, see [`IOxSessionConfig::with_cancellation_token`].
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.query.cancel
+    }
+
+    /// Set the [`WorkloadClass`] of this query, e.g. as requested by the client.
+    ///
+    /// Child contexts inherit the class. Defaults to [`WorkloadClass::Normal`].
+    pub fn with_workload_class(self, workload_class: WorkloadClass) -> Self {
+        Self {
+            workload_class,
+            ..self
+        }
+    }
+
+    /// Workload class of this query, used to prioritize its admission, see
+    /// [`QueryNamespaceProvider::acquire_semaphore`](crate::QueryNamespaceProvider::acquire_semaphore).
+    pub fn workload_class(&self) -> WorkloadClass {
+        self.workload_class
+    }
+
     /// Number of currently active tasks.
     pub fn tasks(&self) -> usize {
         self.exec.tasks()
     }
 }
 
+/// Returns the casts within the expressions of `plan` (and its inputs).
+fn casts(plan: &LogicalPlan) -> Vec<String> {
+    let mut casts = vec![];
+    plan.apply(&mut |plan| {
+        for expr in plan.expressions() {
+            expr.apply(&mut |expr| {
+                if matches!(expr, Expr::Cast(_) | Expr::TryCast(_)) {
+                    casts.push(expr.to_string());
+                }
+                Ok(VisitRecursion::Continue)
+            })?;
+        }
+        Ok(VisitRecursion::Continue)
+    })
+    .expect("cannot fail");
+    casts
+}
+
 /// Extension trait to pull IOx spans out of DataFusion contexts.
 pub trait SessionContextIOxExt {
     /// Get child span of the current context.
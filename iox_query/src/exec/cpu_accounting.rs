@@ -0,0 +1,97 @@
+//! Accounting of the CPU time that queries spend per namespace.
+use std::{borrow::Cow, sync::Arc, time::Duration};
+
+use arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
+use datafusion::{
+    error::DataFusionError,
+    physical_plan::{ExecutionPlan, RecordBatchStream, SendableRecordBatchStream},
+};
+use futures::StreamExt;
+use metric::{DurationCounter, Metric, Registry};
+
+/// Aggregates the DataFusion "elapsed compute" time of executed plans per namespace.
+///
+/// This is the raw data for fair scheduling decisions and tenant-level cost reporting.
+#[derive(Debug, Clone)]
+pub(crate) struct NamespaceCpuMetrics {
+    elapsed_compute: Metric<DurationCounter>,
+}
+
+impl NamespaceCpuMetrics {
+    pub(crate) fn new(registry: &Registry) -> Self {
+        let elapsed_compute = registry.register_metric(
+            "query_namespace_elapsed_compute",
+            "CPU time spent by DataFusion to execute queries, per namespace",
+        );
+
+        Self { elapsed_compute }
+    }
+
+    /// Get recorder for the given namespace.
+    pub(crate) fn recorder(&self, namespace: &str) -> DurationCounter {
+        self.elapsed_compute
+            .recorder([("namespace", Cow::Owned(namespace.to_owned()))])
+    }
+}
+
+/// Stream wrapper that adds the elapsed compute time of a physical plan to a [`DurationCounter`] when it is dropped.
+pub(crate) struct CpuAccountedStream {
+    inner: SendableRecordBatchStream,
+    physical_plan: Arc<dyn ExecutionPlan>,
+    recorder: DurationCounter,
+}
+
+impl CpuAccountedStream {
+    /// Return a stream that accounts the elapsed compute time of `physical_plan` (incl. its children) to `recorder`
+    /// when dropped.
+    pub(crate) fn new(
+        inner: SendableRecordBatchStream,
+        physical_plan: Arc<dyn ExecutionPlan>,
+        recorder: DurationCounter,
+    ) -> Self {
+        Self {
+            inner,
+            physical_plan,
+            recorder,
+        }
+    }
+}
+
+impl RecordBatchStream for CpuAccountedStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl futures::Stream for CpuAccountedStream {
+    type Item = Result<RecordBatch, DataFusionError>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for CpuAccountedStream {
+    fn drop(&mut self) {
+        let nanos = elapsed_compute_nanos(self.physical_plan.as_ref());
+        self.recorder.inc(Duration::from_nanos(nanos as u64));
+    }
+}
+
+/// Sum of the elapsed compute time of all partitions of `physical_plan` and its children.
+fn elapsed_compute_nanos(physical_plan: &dyn ExecutionPlan) -> usize {
+    let own = physical_plan
+        .metrics()
+        .and_then(|metrics| metrics.elapsed_compute())
+        .unwrap_or_default();
+
+    physical_plan
+        .children()
+        .iter()
+        .map(|child| elapsed_compute_nanos(child.as_ref()))
+        .sum::<usize>()
+        + own
+}
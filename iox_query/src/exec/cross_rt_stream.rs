@@ -12,6 +12,7 @@ use executor::DedicatedExecutor;
 use futures::{future::BoxFuture, ready, FutureExt, Stream, StreamExt};
 use tokio::sync::mpsc::{channel, Sender};
 use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
 
 /// [`Stream`] that is calculated by one tokio runtime but can safely be pulled from another w/o stalling (esp. when the
 /// calculating runtime is CPU-blocked).
@@ -75,7 +76,14 @@ where
     ///
     /// Also receives an executor that actually executes the underlying stream as well as a converter that convets
     /// [`executor::JobError`] to the error type of the stream (so we can send potential crashes/panics).
-    fn new_with_error_stream<S, C>(stream: S, exec: DedicatedExecutor, converter: C) -> Self
+    ///
+    /// The underlying stream is dropped once `cancel` is cancelled.
+    fn new_with_error_stream<S, C>(
+        stream: S,
+        exec: DedicatedExecutor,
+        cancel: &CancellationToken,
+        converter: C,
+    ) -> Self
     where
         S: Stream<Item = Result<X, E>> + Send + 'static,
         C: Fn(executor::JobError) -> E + Send + 'static,
@@ -95,8 +103,9 @@ where
             };
 
             // future for this runtime (likely the tokio/tonic/web driver)
+            let cancel = cancel.clone();
             async move {
-                if let Err(e) = exec.spawn(fut).await {
+                if let Err(e) = exec.spawn_with_cancellation(fut, &cancel).await {
                     let e = converter(e);
 
                     // last message, so we don't care about the receiver side
@@ -113,14 +122,23 @@ where
 {
     /// Create new stream based on an existing stream that transports [`Result`]s w/ [`DataFusionError`]s.
     ///
-    /// Also receives an executor that actually executes the underlying stream.
-    pub fn new_with_df_error_stream<S>(stream: S, exec: DedicatedExecutor) -> Self
+    /// Also receives an executor that actually executes the underlying stream. The underlying stream is dropped once
+    /// `cancel` is cancelled.
+    pub fn new_with_df_error_stream<S>(
+        stream: S,
+        exec: DedicatedExecutor,
+        cancel: &CancellationToken,
+    ) -> Self
     where
         S: Stream<Item = Result<X, DataFusionError>> + Send + 'static,
     {
-        Self::new_with_error_stream(stream, exec, |e| {
+        Self::new_with_error_stream(stream, exec, cancel, |e| {
+            let context = match e {
+                executor::JobError::Cancelled => "Query cancelled",
+                _ => "Join Error (panic)",
+            };
             DataFusionError::Context(
-                "Join Error (panic)".to_string(),
+                context.to_string(),
                 Box::new(DataFusionError::External(e.into())),
             )
         })
@@ -184,6 +202,7 @@ mod tests {
                 Ok(1)
             }),
             exec,
+            &CancellationToken::new(),
             std::convert::identity,
         );
 
@@ -219,6 +238,7 @@ mod tests {
                 Ok(1)
             }),
             exec,
+            &CancellationToken::new(),
             std::convert::identity,
         );
 
@@ -239,6 +259,7 @@ mod tests {
         let mut stream = CrossRtStream::<Result<(), executor::JobError>>::new_with_error_stream(
             futures::stream::once(async { panic!("foo") }),
             exec,
+            &CancellationToken::new(),
             std::convert::identity,
         );
 
@@ -267,6 +288,7 @@ mod tests {
                 Ok(1)
             }),
             exec,
+            &CancellationToken::new(),
             std::convert::identity,
         );
 
@@ -301,6 +323,7 @@ mod tests {
                 unreachable!()
             }),
             exec,
+            &CancellationToken::new(),
             std::convert::identity,
         );
 
@@ -328,6 +351,55 @@ mod tests {
         .unwrap();
     }
 
+    #[tokio::test]
+    async fn test_cancellation_token() {
+        let exec = DedicatedExecutor::new_testing();
+        let cancel = CancellationToken::new();
+        let barrier = Arc::new(tokio::sync::Barrier::new(2));
+        let barrier_captured = Arc::clone(&barrier);
+        let mut stream = CrossRtStream::<Result<u8, executor::JobError>>::new_with_error_stream(
+            futures::stream::once(async move {
+                barrier_captured.wait().await;
+
+                // block forever
+                futures::future::pending::<()>().await;
+
+                // keep barrier Arc alive
+                drop(barrier_captured);
+                unreachable!()
+            }),
+            exec,
+            &cancel,
+            std::convert::identity,
+        );
+
+        let mut f = stream.next();
+
+        // fire up stream
+        ensure_pending(&mut f).await;
+        barrier.wait().await;
+        assert_eq!(Arc::strong_count(&barrier), 2);
+
+        // cancel w/o dropping the stream
+        cancel.cancel();
+        let e = f.await.expect("stream not finished").unwrap_err();
+        assert!(matches!(e, executor::JobError::Cancelled), "{e}");
+        assert!(stream.next().await.is_none());
+
+        // inner stream was dropped
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if Arc::strong_count(&barrier) == 1 {
+                    return;
+                }
+
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+
     #[tokio::test]
     async fn test_inner_future_driven_to_completion_after_stream_ready() {
         let barrier = Arc::new(tokio::sync::Barrier::new(2));
@@ -0,0 +1,70 @@
+//! User-defined functions that are only available to the queries of certain namespaces.
+//!
+//! The functions of [`query_functions`] are registered for every query. Services embedding IOx may additionally
+//! provide functions that only make sense for some namespaces (e.g. customer-specific unit conversions) via a
+//! [`NamespaceUdfProvider`], see [`ExecutorConfig::namespace_udfs`].
+//!
+//!
+//! [`ExecutorConfig::namespace_udfs`]: super::ExecutorConfig::namespace_udfs
+use std::{fmt::Debug, sync::Arc};
+
+use datafusion::{
+    execution::context::SessionContext,
+    logical_expr::{AggregateUDF, ScalarUDF},
+};
+use observability_deps::tracing::warn;
+
+/// Provides the user-defined functions of a namespace.
+///
+/// This is called once for every query that runs against a namespace (see [`IOxSessionConfig::with_namespace`]), so
+/// implementations should be cheap, e.g. by caching the functions per namespace.
+///
+///
+/// [`IOxSessionConfig::with_namespace`]: super::IOxSessionConfig::with_namespace
+pub trait NamespaceUdfProvider: Debug + Send + Sync + 'static {
+    /// Scalar functions available to queries against `namespace`.
+    fn scalar_udfs(&self, _namespace: &str) -> Vec<Arc<ScalarUDF>> {
+        vec![]
+    }
+
+    /// Aggregate functions available to queries against `namespace`.
+    fn aggregate_udfs(&self, _namespace: &str) -> Vec<Arc<AggregateUDF>> {
+        vec![]
+    }
+}
+
+/// Register the functions `provider` returns for `namespace` with `ctx`.
+///
+/// Functions that have the same name as an already registered function are ignored, so that namespaces cannot
+/// change the behavior of the built-in functions.
+pub(super) fn register_namespace_udfs(
+    ctx: &SessionContext,
+    provider: &dyn NamespaceUdfProvider,
+    namespace: &str,
+) {
+    let state = ctx.state();
+
+    for udf in provider.scalar_udfs(namespace) {
+        if state.scalar_functions().contains_key(udf.name()) {
+            warn!(
+                namespace,
+                name = udf.name(),
+                "ignoring UDF that shadows a registered function"
+            );
+            continue;
+        }
+        ctx.register_udf(udf.as_ref().clone());
+    }
+
+    for udaf in provider.aggregate_udfs(namespace) {
+        if state.aggregate_functions().contains_key(udaf.name()) {
+            warn!(
+                namespace,
+                name = udaf.name(),
+                "ignoring UDAF that shadows a registered function"
+            );
+            continue;
+        }
+        ctx.register_udaf(udaf.as_ref().clone());
+    }
+}
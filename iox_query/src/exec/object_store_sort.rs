@@ -0,0 +1,482 @@
+//! Sort that spills to an object store.
+use std::{fmt, sync::Arc};
+
+use arrow::{
+    compute::{concat_batches, lexsort_to_indices, take_record_batch, SortColumn},
+    datatypes::SchemaRef,
+    record_batch::RecordBatch,
+};
+use datafusion::{
+    error::{DataFusionError, Result},
+    execution::{
+        memory_pool::{MemoryConsumer, MemoryReservation},
+        TaskContext,
+    },
+    physical_expr::{EquivalenceProperties, PhysicalSortExpr},
+    physical_plan::{
+        metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet},
+        sorts::sort_preserving_merge::SortPreservingMergeExec,
+        stream::RecordBatchStreamAdapter,
+        DisplayAs, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+        SendableRecordBatchStream, Statistics,
+    },
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use parking_lot::Mutex;
+
+use super::object_store_spill::{ObjectStoreSpillManager, SpilledRun};
+
+/// Sorts its input like DataFusion's [`SortExec`], but spills sorted runs to the object store of the
+/// [`ObjectStoreSpillManager`] registered with the session once the memory limit of the query is reached.
+///
+/// The spilled runs are merged (and read back) by a [`SortPreservingMergeExec`]. If no [`ObjectStoreSpillManager`]
+/// is registered, the sort fails once it runs out of memory.
+///
+///
+/// [`SortExec`]: datafusion::physical_plan::sorts::sort::SortExec
+#[derive(Debug)]
+pub struct ObjectStoreSortExec {
+    input: Arc<dyn ExecutionPlan>,
+    expr: Vec<PhysicalSortExpr>,
+    preserve_partitioning: bool,
+
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl ObjectStoreSortExec {
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        expr: Vec<PhysicalSortExpr>,
+        preserve_partitioning: bool,
+    ) -> Self {
+        Self {
+            input,
+            expr,
+            preserve_partitioning,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    /// Sort expressions.
+    pub fn expr(&self) -> &[PhysicalSortExpr] {
+        &self.expr
+    }
+}
+
+impl DisplayAs for ObjectStoreSortExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                let expr = self
+                    .expr
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                write!(
+                    f,
+                    "ObjectStoreSortExec: expr=[{}], preserve_partitioning=[{}]",
+                    expr, self.preserve_partitioning
+                )
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for ObjectStoreSortExec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        if self.preserve_partitioning {
+            self.input.output_partitioning()
+        } else {
+            Partitioning::UnknownPartitioning(1)
+        }
+    }
+
+    fn required_input_distribution(&self) -> Vec<Distribution> {
+        if self.preserve_partitioning {
+            vec![Distribution::UnspecifiedDistribution]
+        } else {
+            vec![Distribution::SinglePartition]
+        }
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        Some(&self.expr)
+    }
+
+    fn equivalence_properties(&self) -> EquivalenceProperties {
+        EquivalenceProperties::new_with_orderings(self.schema(), &[self.expr.clone()])
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        assert_eq!(children.len(), 1);
+
+        Ok(Arc::new(Self::new(
+            Arc::clone(&children[0]),
+            self.expr.clone(),
+            self.preserve_partitioning,
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let spill_manager = context
+            .session_config()
+            .get_extension::<ObjectStoreSpillManager>();
+        let reservation = MemoryConsumer::new(format!("ObjectStoreSortExec[{partition}]"))
+            .with_can_spill(spill_manager.is_some())
+            .register(context.memory_pool());
+        let sorter = Sorter {
+            schema: self.schema(),
+            expr: self.expr.clone(),
+            batch_size: context.session_config().batch_size(),
+            spill_manager,
+            reservation,
+            spill_count: MetricBuilder::new(&self.metrics).spill_count(partition),
+            spilled_bytes: MetricBuilder::new(&self.metrics).spilled_bytes(partition),
+        };
+
+        let input = self.input.execute(partition, Arc::clone(&context))?;
+        let stream = stream::once(sorter.sort(input, context)).try_flatten();
+        Ok(Box::pin(RecordBatchStreamAdapter::new(
+            self.schema(),
+            stream,
+        )))
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Result<Statistics> {
+        self.input.statistics()
+    }
+}
+
+/// Sorts a single partition.
+struct Sorter {
+    schema: SchemaRef,
+    expr: Vec<PhysicalSortExpr>,
+    batch_size: usize,
+    spill_manager: Option<Arc<ObjectStoreSpillManager>>,
+    reservation: MemoryReservation,
+    spill_count: Count,
+    spilled_bytes: Count,
+}
+
+impl Sorter {
+    async fn sort(
+        mut self,
+        mut input: SendableRecordBatchStream,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let mut buffered = vec![];
+        let mut runs = vec![];
+
+        while let Some(batch) = input.next().await {
+            let batch = batch?;
+            // Sorting the buffered batches copies them, so twice their size is reserved up front to keep the sort
+            // within the memory limit.
+            let size = 2 * batch.get_array_memory_size();
+
+            if let Err(e) = self.reservation.try_grow(size) {
+                let Some(spill_manager) = &self.spill_manager else {
+                    return Err(e);
+                };
+                if buffered.is_empty() {
+                    return Err(e);
+                }
+
+                let sorted = self.sort_batches(std::mem::take(&mut buffered))?;
+                let run = spill_manager
+                    .write(Arc::clone(&self.schema), sorted)
+                    .await?;
+                self.spill_count.add(1);
+                self.spilled_bytes.add(run.size());
+                runs.push(run);
+                self.reservation.free();
+
+                // a single batch must fit into memory
+                self.reservation.try_grow(size)?;
+            }
+
+            buffered.push(batch);
+        }
+
+        let sorted = self.sort_batches(buffered)?;
+        let Some(spill_manager) = self.spill_manager.filter(|_| !runs.is_empty()) else {
+            // all data fit into memory, release it once it was consumed
+            let mut reservation = self.reservation;
+            reservation.resize(reservation.size() / 2);
+            let stream = stream::iter(sorted).map(move |batch| {
+                let _ = &reservation;
+                Ok::<_, DataFusionError>(batch)
+            });
+            return Ok(Box::pin(RecordBatchStreamAdapter::new(self.schema, stream)));
+        };
+
+        let run = spill_manager
+            .write(Arc::clone(&self.schema), sorted)
+            .await?;
+        self.spill_count.add(1);
+        self.spilled_bytes.add(run.size());
+        runs.push(run);
+        drop(self.reservation);
+
+        let runs = Arc::new(SpilledRunsExec::new(self.schema, runs, spill_manager));
+        SortPreservingMergeExec::new(self.expr, runs).execute(0, context)
+    }
+
+    /// Sort `batches` into a single sorted run of batches of the configured size.
+    ///
+    /// This needs up to twice the memory of `batches`, see [`Self::sort`].
+    fn sort_batches(&self, batches: Vec<RecordBatch>) -> Result<Vec<RecordBatch>> {
+        if batches.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let batch = concat_batches(&self.schema, &batches)?;
+        drop(batches);
+
+        let sort_columns = self
+            .expr
+            .iter()
+            .map(|e| {
+                Ok(SortColumn {
+                    values: e.expr.evaluate(&batch)?.into_array(batch.num_rows())?,
+                    options: Some(e.options),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let indices = lexsort_to_indices(&sort_columns, None)?;
+        let sorted = take_record_batch(&batch, &indices)?;
+
+        Ok((0..sorted.num_rows())
+            .step_by(self.batch_size)
+            .map(|offset| sorted.slice(offset, self.batch_size.min(sorted.num_rows() - offset)))
+            .collect())
+    }
+}
+
+/// Reads one [`SpilledRun`] per output partition.
+#[derive(Debug)]
+struct SpilledRunsExec {
+    schema: SchemaRef,
+    runs: Mutex<Vec<Option<SpilledRun>>>,
+    spill_manager: Arc<ObjectStoreSpillManager>,
+}
+
+impl SpilledRunsExec {
+    fn new(
+        schema: SchemaRef,
+        runs: Vec<SpilledRun>,
+        spill_manager: Arc<ObjectStoreSpillManager>,
+    ) -> Self {
+        Self {
+            schema,
+            runs: Mutex::new(runs.into_iter().map(Some).collect()),
+            spill_manager,
+        }
+    }
+}
+
+impl DisplayAs for SpilledRunsExec {
+    fn fmt_as(&self, _t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SpilledRunsExec: runs={}", self.runs.lock().len())
+    }
+}
+
+impl ExecutionPlan for SpilledRunsExec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.runs.lock().len())
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        // only used to be executed directly by `Sorter`
+        None
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        assert!(children.is_empty());
+        Ok(self)
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let run = self
+            .runs
+            .lock()
+            .get_mut(partition)
+            .and_then(Option::take)
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!("spilled run {partition} was already read"))
+            })?;
+        Ok(self.spill_manager.read(run))
+    }
+
+    fn statistics(&self) -> Result<Statistics> {
+        Ok(Statistics::new_unknown(&self.schema))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use arrow::array::{ArrayRef, Int64Array};
+    use datafusion::{
+        execution::runtime_env::{RuntimeConfig, RuntimeEnv},
+        physical_plan::{collect, expressions::Column, memory::MemoryExec},
+        prelude::SessionConfig,
+    };
+    use object_store::{memory::InMemory, ObjectStore};
+
+    use crate::exec::object_store_spill::ObjectStoreSpillConfig;
+
+    use super::*;
+
+    /// 10 batches of 8k unsorted values each, about 640KiB in total.
+    fn input() -> Arc<dyn ExecutionPlan> {
+        let batches = (0..10)
+            .map(|i| {
+                RecordBatch::try_from_iter([(
+                    "v",
+                    Arc::new(Int64Array::from_iter_values(
+                        (0..8_192).map(|v| v * 10 + i).rev(),
+                    )) as ArrayRef,
+                )])
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let schema = batches[0].schema();
+        Arc::new(MemoryExec::try_new(&[batches], schema, None).unwrap())
+    }
+
+    fn sort(input: Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
+        let expr = vec![PhysicalSortExpr {
+            expr: Arc::new(Column::new_with_schema("v", &input.schema()).unwrap()),
+            options: Default::default(),
+        }];
+        Arc::new(ObjectStoreSortExec::new(input, expr, false))
+    }
+
+    fn context(store: Option<Arc<InMemory>>) -> Arc<TaskContext> {
+        let runtime =
+            RuntimeEnv::new(RuntimeConfig::new().with_memory_limit(256 * 1024, 1.0)).unwrap();
+        let mut session_config = SessionConfig::new();
+        if let Some(store) = store {
+            session_config = session_config.with_extension(Arc::new(ObjectStoreSpillManager::new(
+                ObjectStoreSpillConfig {
+                    store,
+                    prefix: "spill".into(),
+                    max_concurrent_requests: NonZeroUsize::new(2).unwrap(),
+                },
+            )));
+        }
+        Arc::new(
+            TaskContext::default()
+                .with_session_config(session_config)
+                .with_runtime(Arc::new(runtime)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_spill() {
+        let store = Arc::new(InMemory::new());
+        let plan = sort(input());
+        let batches = collect(Arc::clone(&plan), context(Some(Arc::clone(&store))))
+            .await
+            .unwrap();
+
+        let values = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap()
+                    .values()
+                    .to_vec()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, (0..81_920).collect::<Vec<_>>());
+
+        let metrics = plan.metrics().unwrap();
+        assert!(metrics.spill_count().unwrap() > 1);
+        assert!(metrics.spilled_bytes().unwrap() > 0);
+
+        // spilled objects are removed once they were read
+        let objects = store.list(None).try_collect::<Vec<_>>().await.unwrap();
+        assert!(objects.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_no_spill_manager() {
+        let err = collect(sort(input()), context(None)).await.unwrap_err();
+        assert!(matches!(err, DataFusionError::ResourcesExhausted(_)));
+    }
+
+    #[tokio::test]
+    async fn test_reserves_sort_headroom() {
+        // a single batch of 128KiB fits into the memory limit, but not together with its sorted copy
+        let batch = RecordBatch::try_from_iter([(
+            "v",
+            Arc::new(Int64Array::from_iter_values((0..16_384).rev())) as ArrayRef,
+        )])
+        .unwrap();
+        let schema = batch.schema();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap());
+
+        let store = Arc::new(InMemory::new());
+        let err = collect(sort(input), context(Some(store)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DataFusionError::ResourcesExhausted(_)));
+    }
+}
@@ -0,0 +1,177 @@
+//! Spilling of intermediate query data to an object store.
+//!
+//! DataFusion operators can only spill to local disk (see [`ExecutorConfig::spill_dirs`]), which queriers with small
+//! local volumes cannot provide for large sorts. The [`ObjectStoreSpillManager`] instead writes sorted runs of
+//! [`RecordBatch`]es below a prefix of an object store and reads them back once they are merged.
+//!
+//!
+//! [`ExecutorConfig::spill_dirs`]: super::ExecutorConfig::spill_dirs
+use std::{fmt, num::NonZeroUsize, sync::Arc};
+
+use arrow::{
+    datatypes::SchemaRef,
+    ipc::{reader::StreamReader, writer::StreamWriter},
+    record_batch::RecordBatch,
+};
+use datafusion::{
+    error::{DataFusionError, Result},
+    physical_plan::{stream::RecordBatchStreamAdapter, SendableRecordBatchStream},
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use object_store::{path::Path, DynObjectStore};
+use observability_deps::tracing::{debug, warn};
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+/// Target size of a single spilled object.
+///
+/// Runs are split into objects of about this size, so that merging many runs only needs to buffer one object per run.
+const TARGET_OBJECT_SIZE: usize = 8 * 1024 * 1024;
+
+/// Configuration of an object store that queries spill to.
+#[derive(Clone)]
+pub struct ObjectStoreSpillConfig {
+    /// The object store.
+    pub store: Arc<DynObjectStore>,
+
+    /// Prefix below which spilled data is written.
+    ///
+    /// Spilled objects are removed once they were read, but objects of queriers that crashed while spilling are
+    /// left behind, so the prefix should be covered by a lifecycle rule of the bucket.
+    pub prefix: Path,
+
+    /// Maximum number of concurrent requests to the object store, over all queries.
+    pub max_concurrent_requests: NonZeroUsize,
+}
+
+impl fmt::Debug for ObjectStoreSpillConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectStoreSpillConfig")
+            .field("store", &self.store.to_string())
+            .field("prefix", &self.prefix)
+            .field("max_concurrent_requests", &self.max_concurrent_requests)
+            .finish()
+    }
+}
+
+/// Writes and reads spilled runs of [`RecordBatch`]es to / from an object store.
+///
+/// This is shared by all queries of an executor and registered as an extension of their session config.
+#[derive(Debug)]
+pub struct ObjectStoreSpillManager {
+    config: ObjectStoreSpillConfig,
+
+    /// Limits the concurrent requests to the object store.
+    semaphore: Semaphore,
+}
+
+impl ObjectStoreSpillManager {
+    pub fn new(config: ObjectStoreSpillConfig) -> Self {
+        let semaphore = Semaphore::new(config.max_concurrent_requests.get());
+        Self { config, semaphore }
+    }
+
+    /// Write `batches` as a single run.
+    ///
+    /// The batches are read back in the same order by [`read`](Self::read).
+    pub async fn write(&self, schema: SchemaRef, batches: Vec<RecordBatch>) -> Result<SpilledRun> {
+        let run_path = self.config.prefix.child(Uuid::new_v4().to_string());
+
+        let mut objects = vec![];
+        let mut size = 0;
+        let mut writer = None;
+        let mut batches = batches.into_iter().peekable();
+        while let Some(batch) = batches.next() {
+            let w = match &mut writer {
+                Some(w) => w,
+                None => writer.insert(StreamWriter::try_new(vec![], &schema)?),
+            };
+            w.write(&batch)?;
+
+            if w.get_ref().len() >= TARGET_OBJECT_SIZE || batches.peek().is_none() {
+                let bytes = writer.take().expect("just set").into_inner()?;
+                let path = run_path.child(format!("{}.arrow", objects.len()));
+                size += bytes.len();
+
+                let _permit = self.acquire().await?;
+                self.config
+                    .store
+                    .put(&path, bytes.into())
+                    .await
+                    .map_err(DataFusionError::ObjectStore)?;
+                objects.push(path);
+            }
+        }
+
+        debug!(%run_path, objects=objects.len(), size, "spilled run to object store");
+
+        Ok(SpilledRun {
+            schema,
+            objects,
+            size,
+        })
+    }
+
+    /// Read the batches of `run`, removing each of its objects once it was read.
+    ///
+    /// Objects that are not read (e.g. because the query was cancelled) are left behind.
+    pub fn read(self: &Arc<Self>, run: SpilledRun) -> SendableRecordBatchStream {
+        let this = Arc::clone(self);
+        let stream = stream::iter(run.objects)
+            .then(move |path| {
+                let this = Arc::clone(&this);
+                async move {
+                    let bytes = {
+                        let _permit = this.acquire().await?;
+                        this.config
+                            .store
+                            .get(&path)
+                            .await
+                            .map_err(DataFusionError::ObjectStore)?
+                            .bytes()
+                            .await
+                            .map_err(DataFusionError::ObjectStore)?
+                    };
+                    this.delete(&path).await;
+
+                    let batches = StreamReader::try_new(std::io::Cursor::new(bytes), None)?
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok::<_, DataFusionError>(stream::iter(batches).map(Ok))
+                }
+            })
+            .try_flatten();
+
+        Box::pin(RecordBatchStreamAdapter::new(run.schema, stream))
+    }
+
+    async fn delete(&self, path: &Path) {
+        let Ok(_permit) = self.acquire().await else {
+            return;
+        };
+        if let Err(e) = self.config.store.delete(path).await {
+            warn!(%path, %e, "cannot remove spilled object");
+        }
+    }
+
+    async fn acquire(&self) -> Result<tokio::sync::SemaphorePermit<'_>> {
+        self.semaphore
+            .acquire()
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))
+    }
+}
+
+/// A sorted run of [`RecordBatch`]es that was spilled by an [`ObjectStoreSpillManager`].
+#[derive(Debug)]
+pub struct SpilledRun {
+    schema: SchemaRef,
+    objects: Vec<Path>,
+    size: usize,
+}
+
+impl SpilledRun {
+    /// Total size of the spilled objects, in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
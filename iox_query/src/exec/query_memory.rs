@@ -0,0 +1,225 @@
+//! Per-query memory limits and accounting.
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use datafusion::{
+    error::{DataFusionError, Result},
+    execution::memory_pool::{MemoryConsumer, MemoryPool, MemoryReservation},
+};
+
+/// A [`MemoryPool`] enforcing a memory limit for a single query, on top of the
+/// limit of the executor-wide pool it allocates from.
+///
+/// All reservations are passed through to the shared `inner` pool, so the
+/// overall process memory limit is still respected. A query reserving more
+/// than its own limit fails with [`DataFusionError::ResourcesExhausted`],
+/// even if the shared pool has capacity remaining.
+///
+/// The peak number of bytes reserved by the query is tracked, see
+/// [`QueryMemoryPool::peak()`].
+#[derive(Debug)]
+pub(crate) struct QueryMemoryPool {
+    inner: Arc<dyn MemoryPool>,
+
+    /// Upper bound for [`limit`](Self::limit), see [`set_limit`](Self::set_limit).
+    cap: usize,
+
+    limit: AtomicUsize,
+
+    /// Bytes reserved by this query.
+    reserved: AtomicUsize,
+
+    /// Highest value of `reserved` observed so far.
+    peak: AtomicUsize,
+}
+
+impl QueryMemoryPool {
+    pub(crate) fn new(inner: Arc<dyn MemoryPool>, limit: usize) -> Self {
+        Self {
+            inner,
+            cap: limit,
+            limit: AtomicUsize::new(limit),
+            reserved: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        }
+    }
+
+    /// Create a pool that only accounts the reservations of a query, without
+    /// limiting them beyond the limit of `inner`.
+    pub(crate) fn new_unlimited(inner: Arc<dyn MemoryPool>) -> Self {
+        Self::new(inner, usize::MAX)
+    }
+
+    /// The highest number of bytes reserved by this query at any one time.
+    pub(crate) fn peak(&self) -> usize {
+        self.peak.load(Ordering::Relaxed)
+    }
+
+    /// The number of bytes this query may reserve.
+    pub(crate) fn limit(&self) -> usize {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    /// Change the limit of this query.
+    ///
+    /// The limit cannot exceed the one the pool was created with, in which case the latter is returned as an error.
+    /// Lowering the limit below the bytes that are already reserved does not fail existing reservations, but all
+    /// further ones.
+    pub(crate) fn set_limit(&self, limit: usize) -> Result<(), usize> {
+        if limit > self.cap {
+            return Err(self.cap);
+        }
+        self.limit.store(limit, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl MemoryPool for QueryMemoryPool {
+    fn register(&self, consumer: &MemoryConsumer) {
+        self.inner.register(consumer)
+    }
+
+    fn unregister(&self, consumer: &MemoryConsumer) {
+        self.inner.unregister(consumer)
+    }
+
+    fn grow(&self, reservation: &MemoryReservation, additional: usize) {
+        self.inner.grow(reservation, additional);
+        let reserved = self.reserved.fetch_add(additional, Ordering::Relaxed);
+        self.peak
+            .fetch_max(reserved.saturating_add(additional), Ordering::Relaxed);
+    }
+
+    fn shrink(&self, reservation: &MemoryReservation, shrink: usize) {
+        self.inner.shrink(reservation, shrink);
+        self.reserved.fetch_sub(shrink, Ordering::Relaxed);
+    }
+
+    fn try_grow(&self, reservation: &MemoryReservation, additional: usize) -> Result<()> {
+        let limit = self.limit();
+        let reserved = self
+            .reserved
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |reserved| {
+                let new = reserved.checked_add(additional)?;
+                (new <= limit).then_some(new)
+            })
+            .map_err(|reserved| {
+                DataFusionError::ResourcesExhausted(format!(
+                    "Failed to allocate additional {} bytes for {} with {} bytes already \
+                     allocated - query memory limit of {} bytes exceeded",
+                    additional,
+                    reservation.consumer().name(),
+                    reserved,
+                    limit,
+                ))
+            })?;
+
+        if let Err(e) = self.inner.try_grow(reservation, additional) {
+            self.reserved.fetch_sub(additional, Ordering::Relaxed);
+            return Err(e);
+        }
+
+        self.peak
+            .fetch_max(reserved + additional, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn reserved(&self) -> usize {
+        self.reserved.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::execution::memory_pool::GreedyMemoryPool;
+
+    use super::*;
+
+    #[test]
+    fn test_query_limit() {
+        let shared = Arc::new(GreedyMemoryPool::new(100)) as Arc<dyn MemoryPool>;
+        let pool = Arc::new(QueryMemoryPool::new(Arc::clone(&shared), 50)) as Arc<dyn MemoryPool>;
+
+        let mut r1 = MemoryConsumer::new("r1").register(&pool);
+        r1.try_grow(40).unwrap();
+        assert_eq!(pool.reserved(), 40);
+        assert_eq!(shared.reserved(), 40);
+
+        // Exceeds the query limit, but not the shared limit.
+        let err = r1.try_grow(20).unwrap_err();
+        assert!(matches!(err, DataFusionError::ResourcesExhausted(_)));
+        assert!(err.to_string().contains("query memory limit of 50 bytes"));
+        assert_eq!(pool.reserved(), 40);
+        assert_eq!(shared.reserved(), 40);
+
+        r1.shrink(30);
+        r1.try_grow(20).unwrap();
+        assert_eq!(pool.reserved(), 30);
+
+        drop(r1);
+        assert_eq!(pool.reserved(), 0);
+        assert_eq!(shared.reserved(), 0);
+    }
+
+    #[test]
+    fn test_peak() {
+        let shared = Arc::new(GreedyMemoryPool::new(100)) as Arc<dyn MemoryPool>;
+        let pool = Arc::new(QueryMemoryPool::new_unlimited(Arc::clone(&shared)));
+        assert_eq!(pool.peak(), 0);
+
+        let dyn_pool = Arc::clone(&pool) as Arc<dyn MemoryPool>;
+        let mut r1 = MemoryConsumer::new("r1").register(&dyn_pool);
+        let mut r2 = MemoryConsumer::new("r2").register(&dyn_pool);
+        r1.try_grow(30).unwrap();
+        r2.grow(20);
+        r1.shrink(30);
+        r2.try_grow(10).unwrap();
+        assert_eq!(pool.reserved(), 30);
+        assert_eq!(pool.peak(), 50);
+
+        // Failed reservations are not accounted.
+        r2.try_grow(80).unwrap_err();
+        drop(r1);
+        drop(r2);
+        assert_eq!(pool.reserved(), 0);
+        assert_eq!(pool.peak(), 50);
+    }
+
+    #[test]
+    fn test_shared_limit() {
+        let shared = Arc::new(GreedyMemoryPool::new(100)) as Arc<dyn MemoryPool>;
+        let mut other = MemoryConsumer::new("other").register(&shared);
+        other.try_grow(80).unwrap();
+
+        let pool = Arc::new(QueryMemoryPool::new(Arc::clone(&shared), 50)) as Arc<dyn MemoryPool>;
+        let mut r1 = MemoryConsumer::new("r1").register(&pool);
+
+        // Within the query limit, but exceeds the shared limit.
+        r1.try_grow(30).unwrap_err();
+        assert_eq!(pool.reserved(), 0);
+        assert_eq!(shared.reserved(), 80);
+    }
+
+    #[test]
+    fn test_set_limit() {
+        let shared = Arc::new(GreedyMemoryPool::new(100)) as Arc<dyn MemoryPool>;
+        let pool = Arc::new(QueryMemoryPool::new(Arc::clone(&shared), 50));
+        assert_eq!(pool.limit(), 50);
+
+        pool.set_limit(20).unwrap();
+        assert_eq!(pool.limit(), 20);
+
+        let dyn_pool = Arc::clone(&pool) as Arc<dyn MemoryPool>;
+        let mut r1 = MemoryConsumer::new("r1").register(&dyn_pool);
+        let err = r1.try_grow(30).unwrap_err();
+        assert!(err.to_string().contains("query memory limit of 20 bytes"));
+
+        // cannot exceed the initial limit
+        assert_eq!(pool.set_limit(60), Err(50));
+        pool.set_limit(50).unwrap();
+        r1.try_grow(30).unwrap();
+    }
+}
@@ -0,0 +1,286 @@
+//! Guardrail on the number of series / groups a query may produce.
+//!
+//! A `GROUP BY` over high-cardinality tags can produce millions of groups. [`SeriesLimitExec`] counts the groups
+//! emitted by a grouping aggregation and fails the query with [`SeriesLimitExceeded`] as soon as the configured limit is
+//! crossed, before the groups are sorted, encoded and sent to the client.
+//!
+//! Note that the limit is checked on the output of the aggregation: a (final) aggregation only emits groups once it
+//! consumed all of its input, so all groups have already been computed at that point. The memory used by the
+//! aggregation itself is bounded by the memory pool of the query, not by this limit.
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use arrow::datatypes::SchemaRef;
+use datafusion::{
+    error::{DataFusionError, Result},
+    execution::TaskContext,
+    physical_plan::{
+        expressions::PhysicalSortExpr, stream::RecordBatchStreamAdapter, DisplayAs,
+        DisplayFormatType, Distribution, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+        Statistics,
+    },
+};
+use futures::StreamExt;
+
+/// Error returned when a query produces more series / groups than allowed.
+///
+/// This is wrapped into [`DataFusionError::External`], use [`SeriesLimitExceeded::find`] to check if a query failed
+/// because of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeriesLimitExceeded {
+    /// The configured limit.
+    pub limit: usize,
+}
+
+impl SeriesLimitExceeded {
+    /// Returns the [`SeriesLimitExceeded`] error that caused `err`, if any.
+    pub fn find(err: &DataFusionError) -> Option<&Self> {
+        match err {
+            DataFusionError::External(e)
+            | DataFusionError::ArrowError(arrow::error::ArrowError::ExternalError(e)) => {
+                Self::find_external(e.as_ref())
+            }
+            DataFusionError::Context(_, e) => Self::find(e),
+            _ => None,
+        }
+    }
+
+    fn find_external(e: &(dyn std::error::Error + Send + Sync + 'static)) -> Option<&Self> {
+        if let Some(e) = e.downcast_ref::<Self>() {
+            Some(e)
+        } else if let Some(e) = e.downcast_ref::<DataFusionError>() {
+            Self::find(e)
+        } else if let Some(e) = e.downcast_ref::<Arc<DataFusionError>>() {
+            // errors that are shared between multiple output partitions, e.g. by `RepartitionExec`
+            Self::find(e)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for SeriesLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "series limit exceeded: query produces more than {} series or groups",
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for SeriesLimitExceeded {}
+
+impl From<SeriesLimitExceeded> for DataFusionError {
+    fn from(e: SeriesLimitExceeded) -> Self {
+        Self::External(Box::new(e))
+    }
+}
+
+/// Counts series / groups against a limit.
+///
+/// The counter is shared by all clones, so that the groups of all DataFusion partitions (or all tables of a storage
+/// gRPC query) count against the same limit.
+#[derive(Debug, Clone)]
+pub struct SeriesCounter {
+    limit: usize,
+    count: Arc<AtomicUsize>,
+}
+
+impl SeriesCounter {
+    /// Create a counter that allows at most `limit` series / groups.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            count: Default::default(),
+        }
+    }
+
+    /// Add `n` new series / groups.
+    ///
+    /// Returns an error if the total count exceeds the limit.
+    pub fn add(&self, n: usize) -> Result<(), SeriesLimitExceeded> {
+        let total = self.count.fetch_add(n, Ordering::Relaxed) + n;
+        if total > self.limit {
+            return Err(SeriesLimitExceeded { limit: self.limit });
+        }
+        Ok(())
+    }
+}
+
+/// Passes through the output of a grouping aggregation and fails once it produced more than `limit` rows (i.e.
+/// distinct group keys) over all its partitions.
+///
+/// The partitions of a final aggregation produce disjoint groups, so their rows are counted against a single limit.
+/// The rows are counted as they are emitted, i.e. after the aggregation computed them.
+///
+/// See [module](self) docs for more details.
+#[derive(Debug)]
+pub struct SeriesLimitExec {
+    input: Arc<dyn ExecutionPlan>,
+    limit: usize,
+    counter: SeriesCounter,
+}
+
+impl SeriesLimitExec {
+    pub fn new(input: Arc<dyn ExecutionPlan>, limit: usize) -> Self {
+        Self {
+            input,
+            limit,
+            counter: SeriesCounter::new(limit),
+        }
+    }
+
+    /// The maximum number of series / groups.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl DisplayAs for SeriesLimitExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                write!(f, "SeriesLimitExec: limit={}", self.limit)
+            }
+        }
+    }
+}
+
+impl ExecutionPlan for SeriesLimitExec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn required_input_distribution(&self) -> Vec<Distribution> {
+        vec![Distribution::UnspecifiedDistribution]
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![true]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        assert_eq!(children.len(), 1);
+
+        Ok(Arc::new(Self::new(Arc::clone(&children[0]), self.limit)))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let counter = self.counter.clone();
+        let stream = self.input.execute(partition, context)?;
+        let stream = RecordBatchStreamAdapter::new(
+            stream.schema(),
+            stream.map(move |batch| {
+                let batch = batch?;
+                counter.add(batch.num_rows())?;
+                Ok::<_, DataFusionError>(batch)
+            }),
+        );
+        Ok(Box::pin(stream))
+    }
+
+    fn statistics(&self) -> Result<Statistics> {
+        self.input.statistics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{ArrayRef, Int64Array},
+        record_batch::RecordBatch,
+    };
+    use datafusion::physical_plan::{collect, memory::MemoryExec};
+
+    use super::*;
+
+    /// Two partitions with two rows each.
+    fn input() -> Arc<dyn ExecutionPlan> {
+        let batch = |values: [i64; 2]| {
+            RecordBatch::try_from_iter([(
+                "v",
+                Arc::new(Int64Array::from_iter_values(values)) as ArrayRef,
+            )])
+            .unwrap()
+        };
+        let partitions = [vec![batch([1, 2])], vec![batch([3, 4])]];
+        let schema = partitions[0][0].schema();
+        Arc::new(MemoryExec::try_new(&partitions, schema, None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_within_limit() {
+        let plan = Arc::new(SeriesLimitExec::new(input(), 4));
+        let batches = collect(plan, Arc::new(TaskContext::default()))
+            .await
+            .unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_limit_exceeded() {
+        // counted over all partitions
+        let plan = Arc::new(SeriesLimitExec::new(input(), 3));
+        let err = collect(plan, Arc::new(TaskContext::default()))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            SeriesLimitExceeded::find(&err),
+            Some(&SeriesLimitExceeded { limit: 3 })
+        );
+        assert_eq!(
+            SeriesLimitExceeded { limit: 3 }.to_string(),
+            "series limit exceeded: query produces more than 3 series or groups"
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        let err = DataFusionError::Context(
+            "Join Error".to_owned(),
+            Box::new(DataFusionError::External(Box::new(Arc::new(
+                DataFusionError::from(SeriesLimitExceeded { limit: 1 }),
+            )))),
+        );
+        assert_eq!(
+            SeriesLimitExceeded::find(&err),
+            Some(&SeriesLimitExceeded { limit: 1 })
+        );
+
+        let err = DataFusionError::Execution("foo".to_owned());
+        assert_eq!(SeriesLimitExceeded::find(&err), None);
+    }
+}
@@ -0,0 +1,107 @@
+//! Accounting of the data that queries spill to disk.
+use std::sync::Arc;
+
+use arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
+use datafusion::{
+    error::DataFusionError,
+    physical_plan::{ExecutionPlan, RecordBatchStream, SendableRecordBatchStream},
+};
+use futures::StreamExt;
+use metric::{Registry, U64Counter};
+
+/// Aggregates the spills of executed plans, e.g. of sorts that exceeded the memory limit of their query.
+#[derive(Debug, Clone)]
+pub(crate) struct SpillMetrics {
+    spill_count: U64Counter,
+    spilled_bytes: U64Counter,
+}
+
+impl SpillMetrics {
+    pub(crate) fn new(registry: &Registry) -> Self {
+        let spill_count = registry
+            .register_metric::<U64Counter>(
+                "query_spill_count",
+                "Number of times DataFusion operators spilled data to disk to stay within the memory limit of their query",
+            )
+            .recorder(&[]);
+        let spilled_bytes = registry
+            .register_metric::<U64Counter>(
+                "query_spilled_bytes",
+                "Number of bytes DataFusion operators spilled to disk to stay within the memory limit of their query",
+            )
+            .recorder(&[]);
+
+        Self {
+            spill_count,
+            spilled_bytes,
+        }
+    }
+}
+
+/// Stream wrapper that adds the spills of a physical plan to [`SpillMetrics`] when it is dropped.
+pub(crate) struct SpillAccountedStream {
+    inner: SendableRecordBatchStream,
+    physical_plan: Arc<dyn ExecutionPlan>,
+    metrics: SpillMetrics,
+}
+
+impl SpillAccountedStream {
+    /// Return a stream that accounts the spills of `physical_plan` (incl. its children) to `metrics` when dropped.
+    pub(crate) fn new(
+        inner: SendableRecordBatchStream,
+        physical_plan: Arc<dyn ExecutionPlan>,
+        metrics: SpillMetrics,
+    ) -> Self {
+        Self {
+            inner,
+            physical_plan,
+            metrics,
+        }
+    }
+}
+
+impl RecordBatchStream for SpillAccountedStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl futures::Stream for SpillAccountedStream {
+    type Item = Result<RecordBatch, DataFusionError>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl Drop for SpillAccountedStream {
+    fn drop(&mut self) {
+        let (count, bytes) = spills(self.physical_plan.as_ref());
+        self.metrics.spill_count.inc(count as u64);
+        self.metrics.spilled_bytes.inc(bytes as u64);
+    }
+}
+
+/// Sum of the spill count and spilled bytes of all partitions of `physical_plan` and its children.
+fn spills(physical_plan: &dyn ExecutionPlan) -> (usize, usize) {
+    let (count, bytes) = physical_plan
+        .metrics()
+        .map(|metrics| {
+            (
+                metrics.spill_count().unwrap_or_default(),
+                metrics.spilled_bytes().unwrap_or_default(),
+            )
+        })
+        .unwrap_or_default();
+
+    physical_plan
+        .children()
+        .iter()
+        .map(|child| spills(child.as_ref()))
+        .fold((count, bytes), |(count, bytes), (c, b)| {
+            (count + c, bytes + b)
+        })
+}
@@ -18,7 +18,7 @@ use futures::TryStreamExt;
 use query_log::{QueryCompletedToken, QueryText, StateReceived};
 use trace::{ctx::SpanContext, span::Span};
 
-use tracker::InstrumentedAsyncOwnedSemaphorePermit;
+use tracker::{InstrumentedAsyncOwnedSemaphorePermit, WorkloadClass};
 // Workaround for "unused crate" lint false positives.
 use workspace_hack as _;
 
@@ -49,8 +49,11 @@ pub mod plan;
 pub mod provider;
 pub mod pruning;
 pub mod query_log;
+pub mod result_cache;
+pub mod session_settings;
 pub mod statistics;
 pub mod util;
+pub mod warnings;
 
 pub use query_functions::group_by::{Aggregate, WindowDuration};
 
@@ -142,6 +145,7 @@ pub trait QueryNamespace: Debug + Send + Sync {
         span_ctx: Option<&SpanContext>,
         query_type: &'static str,
         query_text: QueryText,
+        workload_class: WorkloadClass,
     ) -> QueryCompletedToken<StateReceived>;
 
     /// Returns a new execution context suitable for running queries
@@ -166,7 +170,15 @@ pub trait QueryNamespaceProvider: std::fmt::Debug + Send + Sync + 'static {
     ) -> Option<Arc<dyn QueryNamespace>>;
 
     /// Acquire concurrency-limiting sempahore
-    async fn acquire_semaphore(&self, span: Option<Span>) -> InstrumentedAsyncOwnedSemaphorePermit;
+    ///
+    /// Waiters of a higher [`WorkloadClass`] should be admitted first and waiters of the same class may be admitted
+    /// in proportion to the weight of their namespace, see [`tracker::PriorityAdmissionQueue`].
+    async fn acquire_semaphore(
+        &self,
+        namespace_name: &str,
+        span: Option<Span>,
+        workload_class: WorkloadClass,
+    ) -> InstrumentedAsyncOwnedSemaphorePermit;
 }
 
 /// Raw data of a [`QueryChunk`].
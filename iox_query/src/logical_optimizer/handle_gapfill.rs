@@ -15,6 +15,7 @@ use datafusion::{
     },
     optimizer::{optimizer::ApplyOrder, OptimizerConfig, OptimizerRule},
     prelude::{col, Column, Expr},
+    scalar::ScalarValue,
 };
 use hashbrown::{hash_map, HashMap};
 use query_functions::gapfill::{DATE_BIN_GAPFILL_UDF_NAME, INTERPOLATE_UDF_NAME, LOCF_UDF_NAME};
@@ -171,10 +172,10 @@ fn build_gapfill_node(
     date_bin_gapfill_args: Vec<Expr>,
 ) -> Result<LogicalPlan> {
     match date_bin_gapfill_args.len() {
-        2 | 3 => (),
+        2..=4 => (),
         nargs => {
             return Err(DataFusionError::Plan(format!(
-                "DATE_BIN_GAPFILL expects 2 or 3 arguments, got {nargs}",
+                "DATE_BIN_GAPFILL expects 2 to 4 arguments, got {nargs}",
             )));
         }
     }
@@ -212,6 +213,13 @@ fn build_gapfill_node(
             .map_err(|e| e.context("validate origin"))?;
     }
 
+    // The fill strategy argument applies to all aggregate columns,
+    // unless they are wrapped in a fill function like LOCF().
+    let default_fill_strategy = match args_iter.next() {
+        Some(fill) => parse_fill_strategy(&fill).map_err(|e| e.context("parse fill strategy"))?,
+        None => FillStrategy::Null,
+    };
+
     // Make sure the time output to the gapfill node matches what the
     // aggregate output was.
     let time_column =
@@ -234,7 +242,7 @@ fn build_gapfill_node(
     let fill_behavior = aggr_expr
         .iter()
         .cloned()
-        .map(|e| (e, FillStrategy::Null))
+        .map(|e| (e, default_fill_strategy))
         .collect();
 
     Ok(LogicalPlan::Extension(Extension {
@@ -256,6 +264,22 @@ fn build_gapfill_node(
     }))
 }
 
+fn parse_fill_strategy(e: &Expr) -> Result<FillStrategy> {
+    let Expr::Literal(ScalarValue::Utf8(Some(name))) = e else {
+        return Err(DataFusionError::Plan(
+            "fill strategy argument to DATE_BIN_GAPFILL must be a string literal".to_string(),
+        ));
+    };
+    match name.to_ascii_lowercase().as_str() {
+        "null" => Ok(FillStrategy::Null),
+        "previous" | "locf" => Ok(FillStrategy::PrevNullAsMissing),
+        "linear" => Ok(FillStrategy::LinearInterpolate),
+        _ => Err(DataFusionError::Plan(format!(
+            "unknown fill strategy '{name}' for DATE_BIN_GAPFILL, expected one of 'null', 'previous', 'linear'"
+        ))),
+    }
+}
+
 fn validate_time_range(range: &Range<Bound<Expr>>) -> Result<()> {
     let Range { ref start, ref end } = range;
     let (start, end) = match (start, end) {
@@ -396,6 +420,8 @@ impl TreeNodeRewriter for DateBinGapfillRewriter {
                 if func_def.name() == DATE_BIN_GAPFILL_UDF_NAME =>
             {
                 self.args = Some(args.clone());
+                // DATE_BIN does not take the fill strategy argument
+                let args = args.into_iter().take(3).collect();
                 Ok(Expr::ScalarFunction(ScalarFunction {
                     func_def: ScalarFunctionDefinition::BuiltIn(BuiltinScalarFunction::DateBin),
                     args,
@@ -635,6 +661,17 @@ mod test {
             .call(args))
     }
 
+    fn date_bin_gapfill_with_fill(
+        interval: Expr,
+        time: Expr,
+        origin: Expr,
+        fill: Expr,
+    ) -> Result<Expr> {
+        Ok(query_functions::registry()
+            .udf(DATE_BIN_GAPFILL_UDF_NAME)?
+            .call(vec![interval, time, origin, fill]))
+    }
+
     fn locf(arg: Expr) -> Result<Expr> {
         Ok(query_functions::registry()
             .udf(LOCF_UDF_NAME)?
@@ -1139,6 +1176,75 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn with_fill_strategy() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(table_scan()?)
+            .filter(
+                col("time")
+                    .gt_eq(lit_timestamptz_nano(1000))
+                    .and(col("time").lt(lit_timestamptz_nano(2000))),
+            )?
+            .aggregate(
+                vec![date_bin_gapfill_with_fill(
+                    lit(ScalarValue::IntervalDayTime(Some(60_000))),
+                    col("time"),
+                    lit_timestamptz_nano(0),
+                    lit("linear"),
+                )?],
+                vec![avg(col("temp")), min(col("temp"))],
+            )?
+            .project(vec![
+                col("date_bin_gapfill(IntervalDayTime(\"60000\"),temps.time,TimestampNanosecond(0, None),Utf8(\"linear\"))"),
+                col("AVG(temps.temp)"),
+                locf(col("MIN(temps.temp)"))?,
+            ])?
+            .build()?;
+
+        insta::assert_yaml_snapshot!(
+            format_optimized_plan(&plan)?,
+            @r###"
+        ---
+        - "Projection: date_bin_gapfill(IntervalDayTime(\"60000\"),temps.time,TimestampNanosecond(0, None),Utf8(\"linear\")), AVG(temps.temp), MIN(temps.temp) AS locf(MIN(temps.temp))"
+        - "  GapFill: groupBy=[date_bin_gapfill(IntervalDayTime(\"60000\"),temps.time,TimestampNanosecond(0, None),Utf8(\"linear\"))], aggr=[[INTERPOLATE(AVG(temps.temp)), LOCF(MIN(temps.temp))]], time_column=date_bin_gapfill(IntervalDayTime(\"60000\"),temps.time,TimestampNanosecond(0, None),Utf8(\"linear\")), stride=IntervalDayTime(\"60000\"), range=Included(Literal(TimestampNanosecond(1000, None)))..Excluded(Literal(TimestampNanosecond(2000, None)))"
+        - "    Aggregate: groupBy=[[date_bin(IntervalDayTime(\"60000\"), temps.time, TimestampNanosecond(0, None)) AS date_bin_gapfill(IntervalDayTime(\"60000\"),temps.time,TimestampNanosecond(0, None),Utf8(\"linear\"))]], aggr=[[AVG(temps.temp), MIN(temps.temp)]]"
+        - "      Filter: temps.time >= TimestampNanosecond(1000, None) AND temps.time < TimestampNanosecond(2000, None)"
+        - "        TableScan: temps"
+        "###);
+        Ok(())
+    }
+
+    #[test]
+    fn fill_strategy_errs() -> Result<()> {
+        let plan = |fill: Expr| -> Result<LogicalPlan> {
+            LogicalPlanBuilder::from(table_scan()?)
+                .filter(
+                    col("time")
+                        .gt_eq(lit_timestamptz_nano(1000))
+                        .and(col("time").lt(lit_timestamptz_nano(2000))),
+                )?
+                .aggregate(
+                    vec![date_bin_gapfill_with_fill(
+                        lit(ScalarValue::IntervalDayTime(Some(60_000))),
+                        col("time"),
+                        lit_timestamptz_nano(0),
+                        fill,
+                    )?],
+                    vec![avg(col("temp"))],
+                )?
+                .build()
+        };
+
+        assert_optimizer_err(
+            &plan(lit("cubic"))?,
+            "Error during planning: unknown fill strategy 'cubic' for DATE_BIN_GAPFILL, expected one of 'null', 'previous', 'linear'",
+        );
+        assert_optimizer_err(
+            &plan(col("loc"))?,
+            "Error during planning: fill strategy argument to DATE_BIN_GAPFILL must be a string literal",
+        );
+        Ok(())
+    }
+
     #[test]
     fn scan_filter_not_part_of_projection() {
         let schema = schema();
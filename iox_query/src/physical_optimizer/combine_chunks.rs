@@ -19,7 +19,7 @@ use crate::{
         chunk_extraction::extract_chunks,
         sort::util::{collect_statistics_min_max, sort_by_value_ranges},
     },
-    provider::chunks_to_physical_nodes,
+    provider::{chunks_to_physical_nodes, scan_target_partitions},
 };
 
 /// Collects [`QueryChunk`]s and re-creates a appropriate physical nodes.
@@ -193,12 +193,9 @@ fn combine_plans(
     let union_of_chunks = UnionExec::new(inputs_with_chunks);
 
     if let Some((schema, chunks, output_sort_key)) = extract_chunks(&union_of_chunks) {
-        let union_of_chunks = chunks_to_physical_nodes(
-            &schema,
-            output_sort_key.as_ref(),
-            chunks,
-            config.execution.target_partitions,
-        );
+        let target_partitions = scan_target_partitions(&chunks, config);
+        let union_of_chunks =
+            chunks_to_physical_nodes(&schema, output_sort_key.as_ref(), chunks, target_partitions);
         let Some(union_of_chunks) = union_of_chunks.as_any().downcast_ref::<UnionExec>() else {
             return plan_err!("Expected chunks_to_physical_nodes to produce UnionExec but got {union_of_chunks:?}");
         };
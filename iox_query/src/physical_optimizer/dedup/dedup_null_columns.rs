@@ -11,7 +11,7 @@ use schema::{sort::SortKeyBuilder, TIME_COLUMN_NAME};
 
 use crate::{
     physical_optimizer::chunk_extraction::extract_chunks,
-    provider::{chunks_to_physical_nodes, DeduplicateExec},
+    provider::{chunks_to_physical_nodes, scan_target_partitions, DeduplicateExec},
     util::arrow_sort_key_exprs,
 };
 
@@ -65,11 +65,12 @@ impl PhysicalOptimizerRule for DedupNullColumns {
                 }
 
                 let sort_key = sort_key_builder.build();
+                let target_partitions = scan_target_partitions(&chunks, config);
                 let child = chunks_to_physical_nodes(
                     &schema,
                     (!sort_key.is_empty()).then_some(&sort_key),
                     chunks,
-                    config.execution.target_partitions,
+                    target_partitions,
                 );
 
                 let sort_exprs = arrow_sort_key_exprs(&sort_key, &schema);
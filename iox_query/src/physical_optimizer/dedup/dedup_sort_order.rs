@@ -13,7 +13,7 @@ use schema::{sort::SortKeyBuilder, TIME_COLUMN_NAME};
 
 use crate::{
     physical_optimizer::chunk_extraction::extract_chunks,
-    provider::{chunks_to_physical_nodes, DeduplicateExec},
+    provider::{chunks_to_physical_nodes, scan_target_partitions, DeduplicateExec},
     util::arrow_sort_key_exprs,
     CHUNK_ORDER_COLUMN_NAME,
 };
@@ -127,11 +127,12 @@ impl PhysicalOptimizerRule for DedupSortOrder {
                 }
 
                 let quorum_sort_key = quorum_sort_key_builder.build();
+                let target_partitions = scan_target_partitions(&chunks, config);
                 let child = chunks_to_physical_nodes(
                     &schema,
                     (!quorum_sort_key.is_empty()).then_some(&quorum_sort_key),
                     chunks,
-                    config.execution.target_partitions,
+                    target_partitions,
                 );
 
                 let sort_exprs = arrow_sort_key_exprs(&quorum_sort_key, &schema);
@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    config::ConfigOptions,
+    error::Result,
+    physical_optimizer::PhysicalOptimizerRule,
+    physical_plan::ExecutionPlan,
+};
+
+use crate::{
+    config::IoxConfigExt,
+    provider::{DeduplicateExec, HashDeduplicateExec},
+};
+
+/// Replace [`DeduplicateExec`] with [`HashDeduplicateExec`] if its input would need to be sorted.
+///
+/// [`DeduplicateExec`] requires its input to be sorted by the primary key. If the input is not already sorted (e.g.
+/// because the data comes from many small, unsorted files), this requires a [`SortExec`] that buffers the entire input
+/// anyways. [`HashDeduplicateExec`] also buffers the entire input but does not need to sort it.
+///
+/// The replacement is only performed if [`hash_dedup`] is enabled and either:
+///
+/// - **no sort key:** none of the inputs provide any sort order, or
+/// - **low cardinality:** the input is estimated to have at most [`hash_dedup_max_rows`] rows.
+///
+///
+/// [`hash_dedup`]: IoxConfigExt::hash_dedup
+/// [`hash_dedup_max_rows`]: IoxConfigExt::hash_dedup_max_rows
+/// [`SortExec`]: datafusion::physical_plan::sorts::sort::SortExec
+#[derive(Debug, Default)]
+pub struct HashDedup;
+
+impl PhysicalOptimizerRule for HashDedup {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let iox_config = config
+            .extensions
+            .get::<IoxConfigExt>()
+            .cloned()
+            .unwrap_or_default();
+        if !iox_config.hash_dedup {
+            return Ok(plan);
+        }
+
+        plan.transform_up(&|plan| {
+            let plan_any = plan.as_any();
+
+            if let Some(dedup_exec) = plan_any.downcast_ref::<DeduplicateExec>() {
+                let mut children = dedup_exec.children();
+                assert_eq!(children.len(), 1);
+                let child = children.remove(0);
+
+                let required = dedup_exec
+                    .required_input_ordering()
+                    .remove(0)
+                    .unwrap_or_default();
+                if child
+                    .equivalence_properties()
+                    .ordering_satisfy_requirement(&required)
+                {
+                    // input is already sorted, no need to buffer it
+                    return Ok(Transformed::No(plan));
+                }
+
+                let low_cardinality = child
+                    .statistics()?
+                    .num_rows
+                    .get_value()
+                    .map(|rows| *rows <= iox_config.hash_dedup_max_rows)
+                    .unwrap_or_default();
+                if low_cardinality || !has_sort_order(child.as_ref()) {
+                    return Ok(Transformed::Yes(Arc::new(HashDeduplicateExec::new(
+                        child,
+                        dedup_exec.sort_keys().to_vec(),
+                        dedup_exec.use_chunk_order_col(),
+                    ))));
+                }
+            }
+
+            Ok(Transformed::No(plan))
+        })
+    }
+
+    fn name(&self) -> &str {
+        "hash_dedup"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+/// Returns true if `plan` or any of its inputs produce sorted data.
+fn has_sort_order(plan: &dyn ExecutionPlan) -> bool {
+    plan.output_ordering().is_some()
+        || plan
+            .children()
+            .iter()
+            .any(|child| has_sort_order(child.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use schema::{sort::SortKey, TIME_COLUMN_NAME};
+
+    use crate::{
+        physical_optimizer::{
+            dedup::test_util::{chunk, dedup_plan, dedup_plan_with_chunk_order_col},
+            test_util::OptimizationTest,
+        },
+        QueryChunk,
+    };
+
+    use super::*;
+
+    fn config(hash_dedup: bool) -> ConfigOptions {
+        let mut config = ConfigOptions::default();
+        config.extensions.insert(IoxConfigExt {
+            hash_dedup,
+            hash_dedup_max_rows: 0,
+            ..Default::default()
+        });
+        config
+    }
+
+    #[test]
+    fn test_disabled() {
+        let chunk1 = chunk(1).with_dummy_parquet_file();
+        let chunk2 = chunk(2).with_dummy_parquet_file();
+        let schema = chunk1.schema().clone();
+        let plan = dedup_plan(schema, vec![chunk1, chunk2]);
+        let opt = HashDedup;
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, opt, &config(false)),
+            @r###"
+        ---
+        input:
+          - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+          - "   UnionExec"
+          - "     ParquetExec: file_groups={2 groups: [[1.parquet], [2.parquet]]}, projection=[field, tag1, tag2, time]"
+        output:
+          Ok:
+            - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+            - "   UnionExec"
+            - "     ParquetExec: file_groups={2 groups: [[1.parquet], [2.parquet]]}, projection=[field, tag1, tag2, time]"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_no_sort_key() {
+        let chunk1 = chunk(1).with_dummy_parquet_file();
+        let chunk2 = chunk(2).with_dummy_parquet_file();
+        let schema = chunk1.schema().clone();
+        let plan = dedup_plan_with_chunk_order_col(schema, vec![chunk1, chunk2]);
+        let opt = HashDedup;
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, opt, &config(true)),
+            @r###"
+        ---
+        input:
+          - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+          - "   UnionExec"
+          - "     ParquetExec: file_groups={2 groups: [[1.parquet], [2.parquet]]}, projection=[field, tag1, tag2, time, __chunk_order]"
+        output:
+          Ok:
+            - " HashDeduplicateExec: [tag1,tag2,time]"
+            - "   UnionExec"
+            - "     ParquetExec: file_groups={2 groups: [[1.parquet], [2.parquet]]}, projection=[field, tag1, tag2, time, __chunk_order]"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_sorted_input() {
+        let chunk = chunk(1)
+            .with_dummy_parquet_file()
+            .with_sort_key(SortKey::from_columns([
+                Arc::from("tag1"),
+                Arc::from("tag2"),
+                Arc::from(TIME_COLUMN_NAME),
+            ]));
+        let schema = chunk.schema().clone();
+        let plan = dedup_plan(schema, vec![chunk]);
+        let opt = HashDedup;
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, opt, &config(true)),
+            @r###"
+        ---
+        input:
+          - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+          - "   UnionExec"
+          - "     ParquetExec: file_groups={1 group: [[1.parquet]]}, projection=[field, tag1, tag2, time], output_ordering=[tag1@1 ASC, tag2@2 ASC, time@3 ASC]"
+        output:
+          Ok:
+            - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+            - "   UnionExec"
+            - "     ParquetExec: file_groups={1 group: [[1.parquet]]}, projection=[field, tag1, tag2, time], output_ordering=[tag1@1 ASC, tag2@2 ASC, time@3 ASC]"
+        "###
+        );
+    }
+}
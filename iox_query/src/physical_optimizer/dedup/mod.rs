@@ -2,6 +2,8 @@
 
 pub mod dedup_null_columns;
 pub mod dedup_sort_order;
+pub mod hash_dedup;
+pub mod overlap_split;
 pub mod partition_split;
 pub mod remove_dedup;
 pub mod time_split;
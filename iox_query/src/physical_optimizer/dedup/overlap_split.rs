@@ -0,0 +1,443 @@
+use std::sync::Arc;
+
+use arrow::datatypes::DataType;
+use data_types::TimestampMinMax;
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    config::ConfigOptions,
+    error::Result,
+    logical_expr::Operator,
+    physical_optimizer::PhysicalOptimizerRule,
+    physical_plan::{
+        expressions::{BinaryExpr, Column, Literal},
+        filter::FilterExec,
+        union::UnionExec,
+        ExecutionPlan, PhysicalExpr,
+    },
+    scalar::ScalarValue,
+};
+use observability_deps::tracing::warn;
+use schema::TIME_COLUMN_NAME;
+
+use crate::{
+    config::IoxConfigExt,
+    physical_optimizer::chunk_extraction::extract_chunks,
+    provider::{
+        chunks_to_physical_nodes, overlap::timestamp_min_max, scan_target_partitions,
+        DeduplicateExec,
+    },
+};
+
+/// Split de-duplication operations into the time ranges where chunks overlap.
+///
+/// [`TimeSplit`] only separates chunks that do not overlap at all. In the common append-only case a new chunk only
+/// overlaps the end of an older one though, e.g.:
+///
+/// ```text
+/// chunk 1: |-------------------|
+/// chunk 2:                  |-----|
+///          ^                ^  ^  ^
+///          0               90 100 110
+/// ```
+///
+/// If [`dedup_overlap_split`] is enabled, this scans the chunks once per time range that is covered by the same set
+/// of chunks and de-duplicates each range on its own:
+///
+/// ```text
+/// UnionExec
+///   FilterExec: time >= 0 AND time <= 89
+///     DeduplicateExec
+///       <chunk 1>
+///   FilterExec: time >= 90 AND time <= 100
+///     DeduplicateExec
+///       <chunk 1, chunk 2>
+///   FilterExec: time >= 101 AND time <= 110
+///     DeduplicateExec
+///       <chunk 2>
+/// ```
+///
+/// The de-duplications of ranges that are only covered by a single chunk without primary key duplicates are then
+/// removed by [`RemoveDedup`] and the time filters are pushed into the scans by [`PredicatePushdown`], so that only the
+/// overlapping range flows through the expensive sort-merge-dedup operator.
+///
+/// The split is only performed if at least one range does not need to be de-duplicated.
+///
+///
+/// [`dedup_overlap_split`]: IoxConfigExt::dedup_overlap_split
+/// [`PredicatePushdown`]: crate::physical_optimizer::predicate_pushdown::PredicatePushdown
+/// [`RemoveDedup`]: super::remove_dedup::RemoveDedup
+/// [`TimeSplit`]: super::time_split::TimeSplit
+#[derive(Debug, Default)]
+pub struct OverlapSplit;
+
+impl PhysicalOptimizerRule for OverlapSplit {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let iox_config = config
+            .extensions
+            .get::<IoxConfigExt>()
+            .cloned()
+            .unwrap_or_default();
+        if !iox_config.dedup_overlap_split {
+            return Ok(plan);
+        }
+
+        plan.transform_up(&|plan| {
+            let plan_any = plan.as_any();
+
+            if let Some(dedup_exec) = plan_any.downcast_ref::<DeduplicateExec>() {
+                let mut children = dedup_exec.children();
+                assert_eq!(children.len(), 1);
+                let child = children.remove(0);
+                let Some((schema, chunks, output_sort_key)) = extract_chunks(child.as_ref()) else {
+                    return Ok(Transformed::No(plan));
+                };
+
+                let Some(ranges) = chunks
+                    .iter()
+                    .map(|c| timestamp_min_max(c.as_ref()))
+                    .collect::<Option<Vec<_>>>()
+                else {
+                    return Ok(Transformed::No(plan));
+                };
+                let Ok(time_col) = Column::new_with_schema(TIME_COLUMN_NAME, &schema) else {
+                    return Ok(Transformed::No(plan));
+                };
+                let DataType::Timestamp(_, tz) = schema.field(time_col.index()).data_type() else {
+                    return Ok(Transformed::No(plan));
+                };
+
+                let segments = overlap_segments(&ranges);
+                let needs_dedup = |segment: &Segment| {
+                    segment.chunks.len() > 1
+                        || chunks[segment.chunks[0]].may_contain_pk_duplicates()
+                };
+                if segments.len() < 2 || segments.iter().all(needs_dedup) {
+                    return Ok(Transformed::No(plan));
+                }
+
+                // Protect against degenerative plans
+                if segments.len() > iox_config.max_dedup_time_split {
+                    warn!(
+                        n_segments = segments.len(),
+                        max_dedup_time_split = iox_config.max_dedup_time_split,
+                        "cannot split dedup operation based on time overlaps, too many time ranges"
+                    );
+                    return Ok(Transformed::No(plan));
+                }
+
+                let time_col: Arc<dyn PhysicalExpr> = Arc::new(time_col);
+                let out = UnionExec::new(
+                    segments
+                        .into_iter()
+                        .map(|segment| {
+                            let chunks = segment
+                                .chunks
+                                .iter()
+                                .map(|idx| Arc::clone(&chunks[*idx]))
+                                .collect::<Vec<_>>();
+                            let target_partitions = scan_target_partitions(&chunks, config);
+                            let dedup_exec = Arc::new(DeduplicateExec::new(
+                                chunks_to_physical_nodes(
+                                    &schema,
+                                    output_sort_key.as_ref(),
+                                    chunks,
+                                    target_partitions,
+                                ),
+                                dedup_exec.sort_keys().to_vec(),
+                                dedup_exec.use_chunk_order_col(),
+                            ));
+                            let predicate = time_range_predicate(&time_col, tz, &segment);
+                            FilterExec::try_new(predicate, dedup_exec)
+                                .map(|p| Arc::new(p) as Arc<dyn ExecutionPlan>)
+                        })
+                        .collect::<Result<Vec<_>>>()?,
+                );
+                return Ok(Transformed::Yes(Arc::new(out)));
+            }
+
+            Ok(Transformed::No(plan))
+        })
+    }
+
+    fn name(&self) -> &str {
+        "overlap_split"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+/// A time range and the chunks that cover it.
+#[derive(Debug, PartialEq, Eq)]
+struct Segment {
+    /// Inclusive start of the range.
+    min: i64,
+
+    /// Inclusive end of the range.
+    max: i64,
+
+    /// Indices of the chunks that cover the range.
+    chunks: Vec<usize>,
+}
+
+/// Splits the union of `ranges` into consecutive, non-overlapping segments that are covered by the same ranges.
+///
+/// Segments are ordered by time and times that are not covered by any range are skipped.
+fn overlap_segments(ranges: &[TimestampMinMax]) -> Vec<Segment> {
+    // use exclusive ends, which may not fit into an i64
+    let mut boundaries = ranges
+        .iter()
+        .flat_map(|range| [i128::from(range.min), i128::from(range.max) + 1])
+        .collect::<Vec<_>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut segments: Vec<Segment> = vec![];
+    for window in boundaries.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let chunks = ranges
+            .iter()
+            .enumerate()
+            .filter(|(_, range)| i128::from(range.min) <= start && i128::from(range.max) + 1 >= end)
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        if chunks.is_empty() {
+            continue;
+        }
+
+        let min = start as i64;
+        let max = (end - 1) as i64;
+        match segments.last_mut() {
+            Some(last) if last.chunks == chunks && i128::from(last.max) + 1 == start => {
+                last.max = max;
+            }
+            _ => segments.push(Segment { min, max, chunks }),
+        }
+    }
+
+    segments
+}
+
+/// Predicate `min <= time AND time <= max` for the range of `segment`.
+fn time_range_predicate(
+    time_col: &Arc<dyn PhysicalExpr>,
+    tz: &Option<Arc<str>>,
+    segment: &Segment,
+) -> Arc<dyn PhysicalExpr> {
+    let bound = |op, value| -> Arc<dyn PhysicalExpr> {
+        Arc::new(BinaryExpr::new(
+            Arc::clone(time_col),
+            op,
+            Arc::new(Literal::new(ScalarValue::TimestampNanosecond(
+                Some(value),
+                tz.clone(),
+            ))),
+        ))
+    };
+    Arc::new(BinaryExpr::new(
+        bound(Operator::GtEq, segment.min),
+        Operator::And,
+        bound(Operator::LtEq, segment.max),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        physical_optimizer::{
+            dedup::test_util::{chunk, dedup_plan},
+            test_util::OptimizationTest,
+        },
+        QueryChunk,
+    };
+
+    use super::*;
+
+    fn config(dedup_overlap_split: bool) -> ConfigOptions {
+        let mut config = ConfigOptions::default();
+        config.extensions.insert(IoxConfigExt {
+            dedup_overlap_split,
+            ..Default::default()
+        });
+        config
+    }
+
+    #[test]
+    fn test_overlap_segments() {
+        assert_eq!(overlap_segments(&[]), vec![]);
+        assert_eq!(
+            overlap_segments(&[TimestampMinMax::new(0, 100), TimestampMinMax::new(90, 110)]),
+            vec![
+                Segment {
+                    min: 0,
+                    max: 89,
+                    chunks: vec![0]
+                },
+                Segment {
+                    min: 90,
+                    max: 100,
+                    chunks: vec![0, 1]
+                },
+                Segment {
+                    min: 101,
+                    max: 110,
+                    chunks: vec![1]
+                },
+            ]
+        );
+        // gaps are skipped
+        assert_eq!(
+            overlap_segments(&[
+                TimestampMinMax::new(0, 9),
+                TimestampMinMax::new(10, 19),
+                TimestampMinMax::new(30, i64::MAX),
+            ]),
+            vec![
+                Segment {
+                    min: 0,
+                    max: 9,
+                    chunks: vec![0]
+                },
+                Segment {
+                    min: 10,
+                    max: 19,
+                    chunks: vec![1]
+                },
+                Segment {
+                    min: 30,
+                    max: i64::MAX,
+                    chunks: vec![2]
+                },
+            ]
+        );
+        // ranges that are covered by the same chunks are merged
+        assert_eq!(
+            overlap_segments(&[TimestampMinMax::new(0, 10), TimestampMinMax::new(0, 10)]),
+            vec![Segment {
+                min: 0,
+                max: 10,
+                chunks: vec![0, 1]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_partial_overlap() {
+        let chunk1 = chunk(1).with_timestamp_min_max(0, 100);
+        let chunk2 = chunk(2).with_timestamp_min_max(90, 110);
+        let schema = chunk1.schema().clone();
+        let plan = dedup_plan(schema, vec![chunk1, chunk2]);
+        let opt = OverlapSplit;
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, opt, &config(true)),
+            @r###"
+        ---
+        input:
+          - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+          - "   UnionExec"
+          - "     RecordBatchesExec: chunks=2, projection=[field, tag1, tag2, time]"
+        output:
+          Ok:
+            - " UnionExec"
+            - "   FilterExec: time@3 >= 0 AND time@3 <= 89"
+            - "     DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+            - "       UnionExec"
+            - "         RecordBatchesExec: chunks=1, projection=[field, tag1, tag2, time]"
+            - "   FilterExec: time@3 >= 90 AND time@3 <= 100"
+            - "     DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+            - "       UnionExec"
+            - "         RecordBatchesExec: chunks=2, projection=[field, tag1, tag2, time]"
+            - "   FilterExec: time@3 >= 101 AND time@3 <= 110"
+            - "     DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+            - "       UnionExec"
+            - "         RecordBatchesExec: chunks=1, projection=[field, tag1, tag2, time]"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_disabled() {
+        let chunk1 = chunk(1).with_timestamp_min_max(0, 100);
+        let chunk2 = chunk(2).with_timestamp_min_max(90, 110);
+        let schema = chunk1.schema().clone();
+        let plan = dedup_plan(schema, vec![chunk1, chunk2]);
+        let opt = OverlapSplit;
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, opt, &config(false)),
+            @r###"
+        ---
+        input:
+          - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+          - "   UnionExec"
+          - "     RecordBatchesExec: chunks=2, projection=[field, tag1, tag2, time]"
+        output:
+          Ok:
+            - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+            - "   UnionExec"
+            - "     RecordBatchesExec: chunks=2, projection=[field, tag1, tag2, time]"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_all_ranges_need_dedup() {
+        // the only range that is not overlapped contains duplicates
+        let chunk1 = chunk(1)
+            .with_timestamp_min_max(0, 100)
+            .with_may_contain_pk_duplicates(true);
+        let chunk2 = chunk(2).with_timestamp_min_max(50, 100);
+        let schema = chunk1.schema().clone();
+        let plan = dedup_plan(schema, vec![chunk1, chunk2]);
+        let opt = OverlapSplit;
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, opt, &config(true)),
+            @r###"
+        ---
+        input:
+          - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+          - "   UnionExec"
+          - "     RecordBatchesExec: chunks=2, projection=[field, tag1, tag2, time]"
+        output:
+          Ok:
+            - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+            - "   UnionExec"
+            - "     RecordBatchesExec: chunks=2, projection=[field, tag1, tag2, time]"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_max_split() {
+        let chunk1 = chunk(1).with_timestamp_min_max(0, 100);
+        let chunk2 = chunk(2).with_timestamp_min_max(90, 110);
+        let schema = chunk1.schema().clone();
+        let plan = dedup_plan(schema, vec![chunk1, chunk2]);
+        let opt = OverlapSplit;
+        let mut config = ConfigOptions::default();
+        config.extensions.insert(IoxConfigExt {
+            dedup_overlap_split: true,
+            max_dedup_time_split: 2,
+            ..Default::default()
+        });
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, opt, &config),
+            @r###"
+        ---
+        input:
+          - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+          - "   UnionExec"
+          - "     RecordBatchesExec: chunks=2, projection=[field, tag1, tag2, time]"
+        output:
+          Ok:
+            - " DeduplicateExec: [tag1@1 ASC,tag2@2 ASC,time@3 ASC]"
+            - "   UnionExec"
+            - "     RecordBatchesExec: chunks=2, projection=[field, tag1, tag2, time]"
+        "###
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     config::IoxConfigExt,
     physical_optimizer::chunk_extraction::extract_chunks,
-    provider::{chunks_to_physical_nodes, DeduplicateExec},
+    provider::{chunks_to_physical_nodes, scan_target_partitions, DeduplicateExec},
     QueryChunk,
 };
 use datafusion::{
@@ -77,12 +77,13 @@ impl PhysicalOptimizerRule for PartitionSplit {
                     chunks_by_partition
                         .into_iter()
                         .map(|(_p_id, chunks)| {
+                            let target_partitions = scan_target_partitions(&chunks, config);
                             Arc::new(DeduplicateExec::new(
                                 chunks_to_physical_nodes(
                                     &schema,
                                     output_sort_key.as_ref(),
                                     chunks,
-                                    config.execution.target_partitions,
+                                    target_partitions,
                                 ),
                                 dedup_exec.sort_keys().to_vec(),
                                 dedup_exec.use_chunk_order_col(),
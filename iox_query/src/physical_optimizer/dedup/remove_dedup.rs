@@ -10,7 +10,7 @@ use datafusion::{
 
 use crate::{
     physical_optimizer::chunk_extraction::extract_chunks,
-    provider::{chunks_to_physical_nodes, DeduplicateExec},
+    provider::{chunks_to_physical_nodes, scan_target_partitions, DeduplicateExec},
 };
 
 /// Removes de-duplication operation if there are at most 1 chunks and this chunk does NOT contain primary-key duplicates.
@@ -35,11 +35,12 @@ impl PhysicalOptimizerRule for RemoveDedup {
                 };
 
                 if (chunks.len() < 2) && chunks.iter().all(|c| !c.may_contain_pk_duplicates()) {
+                    let target_partitions = scan_target_partitions(&chunks, config);
                     return Ok(Transformed::Yes(chunks_to_physical_nodes(
                         &schema,
                         output_sort_key.as_ref(),
                         chunks,
-                        config.execution.target_partitions,
+                        target_partitions,
                     )));
                 }
             }
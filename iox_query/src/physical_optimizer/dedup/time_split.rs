@@ -12,7 +12,10 @@ use observability_deps::tracing::warn;
 use crate::{
     config::IoxConfigExt,
     physical_optimizer::chunk_extraction::extract_chunks,
-    provider::{chunks_to_physical_nodes, group_potential_duplicates, DeduplicateExec},
+    provider::{
+        chunks_to_physical_nodes, group_potential_duplicates, scan_target_partitions,
+        DeduplicateExec,
+    },
 };
 
 /// Split de-duplication operations based on time.
@@ -67,12 +70,13 @@ impl PhysicalOptimizerRule for TimeSplit {
                     groups
                         .into_iter()
                         .map(|chunks| {
+                            let target_partitions = scan_target_partitions(&chunks, config);
                             Arc::new(DeduplicateExec::new(
                                 chunks_to_physical_nodes(
                                     &schema,
                                     output_sort_key.as_ref(),
                                     chunks,
-                                    config.execution.target_partitions,
+                                    target_partitions,
                                 ),
                                 dedup_exec.sort_keys().to_vec(),
                                 dedup_exec.use_chunk_order_col(),
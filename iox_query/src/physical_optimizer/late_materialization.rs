@@ -0,0 +1,275 @@
+use std::{collections::HashSet, sync::Arc};
+
+use arrow::datatypes::{DataType, Schema};
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    config::ConfigOptions,
+    datasource::physical_plan::ParquetExec,
+    error::Result,
+    physical_expr::{split_conjunction, utils::collect_columns},
+    physical_optimizer::PhysicalOptimizerRule,
+    physical_plan::{ExecutionPlan, PhysicalExpr},
+};
+
+use crate::config::IoxConfigExt;
+
+use super::predicate_pushdown::conjunction;
+
+/// Defer decoding of wide string / tag columns until the filters on the other columns were applied.
+///
+/// If [`late_materialization`] is enabled, this inspects every [`ParquetExec`] that has a predicate (see
+/// [`PredicatePushdown`]):
+///
+/// - **Outputs a wide column that is not filtered on:** The predicate is evaluated while decoding the file. The parquet reader
+///   then decodes the filtered columns first and the remaining columns only for the rows that pass the predicate.
+///   The parts of the predicate that do not touch wide columns are moved to the front and evaluated first, so that
+///   wide columns that are filtered on are also only decoded for the rows that pass the cheaper filters.
+/// - **Otherwise:** The predicate is only used to prune row groups and pages and evaluated by the filter above the
+///   scan. Evaluating it while decoding would decode the filtered columns twice without saving any work.
+///
+/// This must run after all rules that push predicates and projections into the [`ParquetExec`], since these create
+/// new nodes and drop the settings of this rule.
+///
+///
+/// [`late_materialization`]: IoxConfigExt::late_materialization
+/// [`PredicatePushdown`]: super::predicate_pushdown::PredicatePushdown
+#[derive(Debug, Default)]
+pub struct LateMaterialization;
+
+impl PhysicalOptimizerRule for LateMaterialization {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let enabled = config
+            .extensions
+            .get::<IoxConfigExt>()
+            .map(|iox_config| iox_config.late_materialization)
+            .unwrap_or_default();
+        if !enabled {
+            return Ok(plan);
+        }
+
+        plan.transform_up(&|plan| {
+            let Some(parquet_exec) = plan.as_any().downcast_ref::<ParquetExec>() else {
+                return Ok(Transformed::No(plan));
+            };
+            let Some(predicate) = parquet_exec.predicate() else {
+                return Ok(Transformed::No(plan));
+            };
+
+            let file_schema = &parquet_exec.base_config().file_schema;
+            let filtered = collect_columns(predicate)
+                .into_iter()
+                .map(|col| col.name().to_owned())
+                .collect::<HashSet<_>>();
+            let deferred = parquet_exec.schema().fields().iter().any(|field| {
+                is_wide(field.data_type()) && !filtered.contains(field.name().as_str())
+            });
+
+            let new_exec = if deferred {
+                let (narrow, wide): (Vec<_>, Vec<_>) = split_conjunction(predicate)
+                    .into_iter()
+                    .cloned()
+                    .partition(|expr| !touches_wide_columns(expr, file_schema));
+                let predicate = conjunction(narrow.into_iter().chain(wide)).expect("not empty");
+
+                ParquetExec::new(parquet_exec.base_config().clone(), Some(predicate), None)
+                    .with_pushdown_filters(true)
+                    // keep the order chosen above
+                    .with_reorder_filters(false)
+            } else {
+                ParquetExec::new(
+                    parquet_exec.base_config().clone(),
+                    Some(Arc::clone(predicate)),
+                    None,
+                )
+                .with_pushdown_filters(false)
+            };
+
+            Ok(Transformed::Yes(Arc::new(new_exec)))
+        })
+    }
+
+    fn name(&self) -> &str {
+        "late_materialization"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+/// Returns true if decoding a column of this type is expensive compared to primitive columns.
+fn is_wide(data_type: &DataType) -> bool {
+    match data_type {
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Binary | DataType::LargeBinary => true,
+        DataType::Dictionary(_, value_type) => is_wide(value_type),
+        _ => false,
+    }
+}
+
+/// Returns true if `expr` references any wide column of `schema`.
+///
+/// Columns that are not part of the schema (e.g. partition columns) are treated as wide, so that the filters on
+/// them are not evaluated first.
+fn touches_wide_columns(expr: &Arc<dyn PhysicalExpr>, schema: &Schema) -> bool {
+    collect_columns(expr).iter().any(|col| {
+        schema
+            .field_with_name(col.name())
+            .map(|field| is_wide(field.data_type()))
+            .unwrap_or(true)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{Field, SchemaRef};
+    use datafusion::{
+        datasource::{object_store::ObjectStoreUrl, physical_plan::FileScanConfig},
+        logical_expr::Operator,
+        physical_plan::{
+            expressions::{BinaryExpr, Column, Literal},
+            Statistics,
+        },
+        scalar::ScalarValue,
+    };
+
+    use crate::physical_optimizer::test_util::OptimizationTest;
+
+    use super::*;
+
+    fn config(late_materialization: bool) -> ConfigOptions {
+        let mut config = ConfigOptions::default();
+        config.extensions.insert(IoxConfigExt {
+            late_materialization,
+            ..Default::default()
+        });
+        config
+    }
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("tag", DataType::Utf8, true),
+            Field::new("message", DataType::Utf8, true),
+            Field::new("value", DataType::Int64, true),
+        ]))
+    }
+
+    fn parquet_exec(
+        schema: &SchemaRef,
+        projection: Option<Vec<usize>>,
+        predicate: Option<Arc<dyn PhysicalExpr>>,
+    ) -> Arc<dyn ExecutionPlan> {
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::parse("test://").unwrap(),
+            file_schema: Arc::clone(schema),
+            file_groups: vec![],
+            statistics: Statistics::new_unknown(schema),
+            projection,
+            limit: None,
+            table_partition_cols: vec![],
+            output_ordering: vec![],
+        };
+        Arc::new(ParquetExec::new(base_config, predicate, None))
+    }
+
+    fn eq(schema: &SchemaRef, col: &str, value: ScalarValue) -> Arc<dyn PhysicalExpr> {
+        Arc::new(BinaryExpr::new(
+            Arc::new(Column::new_with_schema(col, schema).unwrap()),
+            Operator::Eq,
+            Arc::new(Literal::new(value)),
+        ))
+    }
+
+    fn predicate(schema: &SchemaRef) -> Arc<dyn PhysicalExpr> {
+        Arc::new(BinaryExpr::new(
+            eq(schema, "tag", ScalarValue::from("a")),
+            Operator::And,
+            eq(schema, "value", ScalarValue::from(1i64)),
+        ))
+    }
+
+    #[test]
+    fn test_is_wide() {
+        assert!(is_wide(&DataType::Utf8));
+        assert!(is_wide(&DataType::Dictionary(
+            Box::new(DataType::Int32),
+            Box::new(DataType::Utf8)
+        )));
+        assert!(!is_wide(&DataType::Int64));
+        assert!(!is_wide(&DataType::Dictionary(
+            Box::new(DataType::Int32),
+            Box::new(DataType::Int64)
+        )));
+    }
+
+    #[test]
+    fn test_filters_on_narrow_columns_first() {
+        let schema = schema();
+        let plan = parquet_exec(&schema, None, Some(predicate(&schema)));
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, LateMaterialization, &config(true)),
+            @r###"
+        ---
+        input:
+          - " ParquetExec: file_groups={0 groups: []}, projection=[tag, message, value], predicate=tag@0 = a AND value@2 = 1, pruning_predicate=tag_min@0 <= a AND a <= tag_max@1 AND value_min@2 <= 1 AND 1 <= value_max@3"
+        output:
+          Ok:
+            - " ParquetExec: file_groups={0 groups: []}, projection=[tag, message, value], predicate=value@2 = 1 AND tag@0 = a, pruning_predicate=value_min@0 <= 1 AND 1 <= value_max@1 AND tag_min@2 <= a AND a <= tag_max@3"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_only_filtered_columns() {
+        let schema = schema();
+        let plan = parquet_exec(&schema, Some(vec![0, 2]), Some(predicate(&schema)));
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, LateMaterialization, &config(true)),
+            @r###"
+        ---
+        input:
+          - " ParquetExec: file_groups={0 groups: []}, projection=[tag, value], predicate=tag@0 = a AND value@2 = 1, pruning_predicate=tag_min@0 <= a AND a <= tag_max@1 AND value_min@2 <= 1 AND 1 <= value_max@3"
+        output:
+          Ok:
+            - " ParquetExec: file_groups={0 groups: []}, projection=[tag, value], predicate=tag@0 = a AND value@2 = 1, pruning_predicate=tag_min@0 <= a AND a <= tag_max@1 AND value_min@2 <= 1 AND 1 <= value_max@3"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_no_predicate() {
+        let schema = schema();
+        let plan = parquet_exec(&schema, None, None);
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, LateMaterialization, &config(true)),
+            @r###"
+        ---
+        input:
+          - " ParquetExec: file_groups={0 groups: []}, projection=[tag, message, value]"
+        output:
+          Ok:
+            - " ParquetExec: file_groups={0 groups: []}, projection=[tag, message, value]"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_disabled() {
+        let schema = schema();
+        let plan = parquet_exec(&schema, None, Some(predicate(&schema)));
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan, LateMaterialization, &config(false)),
+            @r###"
+        ---
+        input:
+          - " ParquetExec: file_groups={0 groups: []}, projection=[tag, message, value], predicate=tag@0 = a AND value@2 = 1, pruning_predicate=tag_min@0 <= a AND a <= tag_max@1 AND value_min@2 <= 1 AND 1 <= value_max@3"
+        output:
+          Ok:
+            - " ParquetExec: file_groups={0 groups: []}, projection=[tag, message, value], predicate=tag@0 = a AND value@2 = 1, pruning_predicate=tag_min@0 <= a AND a <= tag_max@1 AND value_min@2 <= 1 AND 1 <= value_max@3"
+        "###
+        );
+    }
+}
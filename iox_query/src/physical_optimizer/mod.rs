@@ -6,19 +6,29 @@ use self::{
     combine_chunks::CombineChunks,
     dedup::{
         dedup_null_columns::DedupNullColumns, dedup_sort_order::DedupSortOrder,
-        partition_split::PartitionSplit, remove_dedup::RemoveDedup, time_split::TimeSplit,
+        hash_dedup::HashDedup, overlap_split::OverlapSplit, partition_split::PartitionSplit,
+        remove_dedup::RemoveDedup, time_split::TimeSplit,
     },
+    late_materialization::LateMaterialization,
     predicate_pushdown::PredicatePushdown,
+    progressive_aggregation::ProgressiveAggregation,
     projection_pushdown::ProjectionPushdown,
-    sort::{order_union_sorted_inputs::OrderUnionSortedInputs, parquet_sortness::ParquetSortness},
+    series_limit::SeriesLimit,
+    sort::{
+        object_store_sort::ObjectStoreSort, order_union_sorted_inputs::OrderUnionSortedInputs,
+        parquet_sortness::ParquetSortness,
+    },
     union::{nested_union::NestedUnion, one_union::OneUnion},
 };
 
 mod chunk_extraction;
 mod combine_chunks;
 mod dedup;
+mod late_materialization;
 mod predicate_pushdown;
+mod progressive_aggregation;
 mod projection_pushdown;
+mod series_limit;
 mod sort;
 mod union;
 
@@ -35,6 +45,7 @@ pub fn register_iox_physical_optimizers(state: SessionState) -> SessionState {
     let mut optimizers: Vec<Arc<dyn PhysicalOptimizerRule + Sync + Send>> = vec![
         Arc::new(PartitionSplit),
         Arc::new(TimeSplit),
+        Arc::new(OverlapSplit),
         Arc::new(RemoveDedup),
         Arc::new(CombineChunks),
         Arc::new(DedupNullColumns),
@@ -42,15 +53,26 @@ pub fn register_iox_physical_optimizers(state: SessionState) -> SessionState {
         Arc::new(PredicatePushdown),
         Arc::new(ProjectionPushdown),
         Arc::new(ParquetSortness) as _,
+        Arc::new(HashDedup),
         Arc::new(NestedUnion),
         Arc::new(OneUnion),
+        Arc::new(LateMaterialization),
     ];
 
     // Append DataFUsion physical rules to the IOx-specific rules
     optimizers.append(&mut state.physical_optimizers().to_vec());
 
+    // Stream aggregates by time bucket, this must see the aggregation modes chosen by DataFusion
+    optimizers.push(Arc::new(ProgressiveAggregation));
+
     // Add a rule to optimize plan with limit
     optimizers.push(Arc::new(OrderUnionSortedInputs));
 
+    // Enforce the series limit on the final aggregation modes chosen by DataFusion
+    optimizers.push(Arc::new(SeriesLimit));
+
+    // Spill sorts to the object store, this must see all sorts
+    optimizers.push(Arc::new(ObjectStoreSort));
+
     state.with_physical_optimizer_rules(optimizers)
 }
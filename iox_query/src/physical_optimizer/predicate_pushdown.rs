@@ -6,18 +6,23 @@ use datafusion::{
     datasource::physical_plan::ParquetExec,
     error::{DataFusionError, Result},
     logical_expr::Operator,
-    physical_expr::{split_conjunction, utils::collect_columns},
+    physical_expr::{split_conjunction, utils::collect_columns, ScalarFunctionExpr},
     physical_optimizer::PhysicalOptimizerRule,
     physical_plan::{
         empty::EmptyExec,
-        expressions::{BinaryExpr, Column},
+        expressions::{BinaryExpr, CastExpr, Column, Literal},
         filter::FilterExec,
         union::UnionExec,
         ExecutionPlan, PhysicalExpr,
     },
+    scalar::ScalarValue,
 };
+use query_functions::REGEX_MATCH_UDF_NAME;
 
-use crate::provider::DeduplicateExec;
+use crate::{
+    provider::DeduplicateExec,
+    pruning::{regex_prefix_bounds, string_scalar},
+};
 
 /// Push down predicates.
 #[derive(Debug, Default)]
@@ -59,12 +64,14 @@ impl PhysicalOptimizerRule for PredicatePushdown {
                         .predicate()
                         .map(split_conjunction)
                         .unwrap_or_default();
-                    let both = conjunction(
-                        existing
-                            .into_iter()
-                            .chain(split_conjunction(filter_exec.predicate()))
-                            .cloned(),
-                    );
+                    let new = split_conjunction(filter_exec.predicate());
+                    let schema = child_parquet.schema();
+                    let regex_ranges = new
+                        .iter()
+                        .filter_map(|expr| regex_match_range(expr, &schema))
+                        .collect::<Vec<_>>();
+                    let both =
+                        conjunction(existing.into_iter().chain(new).cloned().chain(regex_ranges));
 
                     let new_node = Arc::new(FilterExec::try_new(
                         Arc::clone(filter_exec.predicate()),
@@ -150,7 +157,58 @@ impl TreeNodeRewriter for ColumnCollector {
     }
 }
 
-fn conjunction(
+/// Returns the range of values `expr` can match, if it is an anchored regex match like `tag ~ '^prod-'`.
+///
+/// The range is implied by the regex match, so adding it to the predicate of a [`ParquetExec`] allows to prune row
+/// groups using their min/max statistics without changing the result.
+fn regex_match_range(
+    expr: &Arc<dyn PhysicalExpr>,
+    schema: &arrow::datatypes::Schema,
+) -> Option<Arc<dyn PhysicalExpr>> {
+    let expr_any = expr.as_any();
+    let (column, pattern) = if let Some(binary) = expr_any.downcast_ref::<BinaryExpr>() {
+        if binary.op() != &Operator::RegexMatch {
+            return None;
+        }
+        (binary.left(), binary.right())
+    } else if let Some(fun) = expr_any.downcast_ref::<ScalarFunctionExpr>() {
+        if fun.name() != REGEX_MATCH_UDF_NAME || fun.args().len() != 2 {
+            return None;
+        }
+        (&fun.args()[0], &fun.args()[1])
+    } else {
+        return None;
+    };
+
+    let column = match column.as_any().downcast_ref::<CastExpr>() {
+        Some(cast) => cast.expr(),
+        None => column,
+    };
+    let column = column.as_any().downcast_ref::<Column>()?;
+    let ScalarValue::Utf8(Some(pattern)) = pattern.as_any().downcast_ref::<Literal>()?.value()
+    else {
+        return None;
+    };
+
+    let (lower, upper) = regex_prefix_bounds(pattern)?;
+    let data_type = schema.field(column.index()).data_type();
+    let column: Arc<dyn PhysicalExpr> = Arc::new(column.clone());
+    let bound = |op, value| -> Option<Arc<dyn PhysicalExpr>> {
+        Some(Arc::new(BinaryExpr::new(
+            Arc::clone(&column),
+            op,
+            Arc::new(Literal::new(string_scalar(data_type, value)?)),
+        )))
+    };
+
+    let range = bound(Operator::GtEq, lower)?;
+    match upper {
+        Some(upper) => conjunction([range, bound(Operator::Lt, upper)?]),
+        None => Some(range),
+    }
+}
+
+pub(super) fn conjunction(
     parts: impl IntoIterator<Item = Arc<dyn PhysicalExpr>>,
 ) -> Option<Arc<dyn PhysicalExpr>> {
     parts
@@ -338,6 +396,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parquet_regex() {
+        let schema = schema();
+        let base_config = FileScanConfig {
+            object_store_url: ObjectStoreUrl::parse("test://").unwrap(),
+            file_schema: Arc::clone(&schema),
+            file_groups: vec![],
+            statistics: Statistics::new_unknown(&schema),
+            projection: None,
+            limit: None,
+            table_partition_cols: vec![],
+            output_ordering: vec![],
+        };
+        let plan = Arc::new(
+            FilterExec::try_new(
+                predicate_regex(&schema),
+                Arc::new(ParquetExec::new(base_config, None, None)),
+            )
+            .unwrap(),
+        );
+        let opt = PredicatePushdown;
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new(plan, opt),
+            @r###"
+        ---
+        input:
+          - " FilterExec: tag1@0 ~ ^prod-"
+          - "   ParquetExec: file_groups={0 groups: []}, projection=[tag1, tag2, field]"
+        output:
+          Ok:
+            - " FilterExec: tag1@0 ~ ^prod-"
+            - "   ParquetExec: file_groups={0 groups: []}, projection=[tag1, tag2, field], predicate=tag1@0 ~ ^prod- AND tag1@0 >= prod- AND tag1@0 < prod., pruning_predicate=tag1_max@0 >= prod- AND tag1_min@1 < prod."
+        "###
+        );
+    }
+
     #[test]
     fn test_dedup_no_pushdown() {
         let schema = schema();
@@ -490,6 +584,14 @@ mod tests {
         ))
     }
 
+    fn predicate_regex(schema: &SchemaRef) -> Arc<dyn PhysicalExpr> {
+        Arc::new(BinaryExpr::new(
+            Arc::new(Column::new_with_schema("tag1", schema).unwrap()),
+            Operator::RegexMatch,
+            Arc::new(Literal::new(ScalarValue::from("^prod-"))),
+        ))
+    }
+
     fn predicate_other() -> Arc<dyn PhysicalExpr> {
         Arc::new(Literal::new(ScalarValue::from(true)))
     }
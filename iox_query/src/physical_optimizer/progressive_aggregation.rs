@@ -0,0 +1,276 @@
+use std::sync::Arc;
+
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    config::ConfigOptions,
+    error::Result,
+    physical_expr::{PhysicalSortExpr, ScalarFunctionExpr},
+    physical_optimizer::PhysicalOptimizerRule,
+    physical_plan::{
+        aggregates::{AggregateExec, AggregateMode},
+        coalesce_batches::CoalesceBatchesExec,
+        coalesce_partitions::CoalescePartitionsExec,
+        expressions::Column,
+        repartition::RepartitionExec,
+        sorts::{sort::SortExec, sort_preserving_merge::SortPreservingMergeExec},
+        ExecutionPlan, PhysicalExpr,
+    },
+};
+use schema::TIME_COLUMN_NAME;
+
+use crate::config::IoxConfigExt;
+
+/// Aggregate by time bucket in time order, so that the results of each bucket are emitted as soon as the bucket is
+/// complete instead of after the entire aggregation has finished.
+///
+/// If [`progressive_aggregation`] is enabled, this finds aggregations that group by the time column or by a
+/// `date_bin(..., time, ...)` bucket of it:
+///
+/// ```text
+/// AggregateExec: mode=FinalPartitioned, gby=[date_bin(...)]
+///   RepartitionExec: partitioning=Hash(...)
+///     AggregateExec: mode=Partial, gby=[date_bin(...)]
+///       <input>
+/// ```
+///
+/// and replaces them with a single aggregation over the input sorted by time:
+///
+/// ```text
+/// AggregateExec: mode=Single, gby=[date_bin(...)], ordering_mode=Sorted
+///   SortPreservingMergeExec: [time ASC]
+///     SortExec: expr=[time ASC], preserve_partitioning=[true]
+///       <input>
+/// ```
+///
+/// Since its input is ordered by the bucket, DataFusion's [`AggregateExec`] emits every bucket once it has seen the
+/// first row of the next one. This trades the parallelism of the final aggregation for results that can be rendered
+/// progressively by clients, which is worthwhile for long-running aggregations over long time ranges.
+///
+///
+/// [`progressive_aggregation`]: IoxConfigExt::progressive_aggregation
+#[derive(Debug, Default)]
+pub struct ProgressiveAggregation;
+
+impl PhysicalOptimizerRule for ProgressiveAggregation {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let enabled = config
+            .extensions
+            .get::<IoxConfigExt>()
+            .map(|iox_config| iox_config.progressive_aggregation)
+            .unwrap_or_default();
+        if !enabled {
+            return Ok(plan);
+        }
+
+        plan.transform_up(&|plan| {
+            let Some(agg_exec) = plan.as_any().downcast_ref::<AggregateExec>() else {
+                return Ok(Transformed::No(plan));
+            };
+
+            // find the aggregation that reads the actual input
+            let input_agg_exec = match agg_exec.mode() {
+                AggregateMode::Final | AggregateMode::FinalPartitioned => {
+                    let Some(partial) = find_partial(agg_exec.input()) else {
+                        return Ok(Transformed::No(plan));
+                    };
+                    partial
+                }
+                AggregateMode::Single | AggregateMode::SinglePartitioned => agg_exec,
+                AggregateMode::Partial => {
+                    return Ok(Transformed::No(plan));
+                }
+            };
+
+            let input = input_agg_exec.input();
+            let input_schema = input.schema();
+            let Ok(time_col) = Column::new_with_schema(TIME_COLUMN_NAME, &input_schema) else {
+                return Ok(Transformed::No(plan));
+            };
+            if !input_agg_exec
+                .group_expr()
+                .expr()
+                .iter()
+                .any(|(expr, _name)| is_time_bucket(expr, &time_col))
+            {
+                return Ok(Transformed::No(plan));
+            }
+
+            let sort_exprs = vec![PhysicalSortExpr {
+                expr: Arc::new(time_col),
+                options: Default::default(),
+            }];
+            let sorted_input = sort_by_time(Arc::clone(input), sort_exprs);
+            let new_agg_exec = AggregateExec::try_new(
+                AggregateMode::Single,
+                input_agg_exec.group_expr().clone(),
+                input_agg_exec.aggr_expr().to_vec(),
+                input_agg_exec.filter_expr().to_vec(),
+                input_agg_exec.order_by_expr().to_vec(),
+                sorted_input,
+                input_agg_exec.input_schema(),
+            )?;
+
+            Ok(Transformed::Yes(Arc::new(new_agg_exec)))
+        })
+    }
+
+    fn name(&self) -> &str {
+        "progressive_aggregation"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+/// Find the partial aggregation below a final one, looking through the nodes that DataFusion inserts between them.
+fn find_partial(plan: &Arc<dyn ExecutionPlan>) -> Option<&AggregateExec> {
+    let plan_any = plan.as_any();
+
+    if let Some(agg_exec) = plan_any.downcast_ref::<AggregateExec>() {
+        matches!(agg_exec.mode(), AggregateMode::Partial).then_some(agg_exec)
+    } else if let Some(repartition_exec) = plan_any.downcast_ref::<RepartitionExec>() {
+        find_partial(repartition_exec.input())
+    } else if let Some(coalesce_exec) = plan_any.downcast_ref::<CoalesceBatchesExec>() {
+        find_partial(coalesce_exec.input())
+    } else if let Some(coalesce_exec) = plan_any.downcast_ref::<CoalescePartitionsExec>() {
+        find_partial(coalesce_exec.input())
+    } else {
+        None
+    }
+}
+
+/// Returns true if ordering by `time_col` also orders by `expr`.
+fn is_time_bucket(expr: &Arc<dyn PhysicalExpr>, time_col: &Column) -> bool {
+    let expr_any = expr.as_any();
+
+    if let Some(col) = expr_any.downcast_ref::<Column>() {
+        col == time_col
+    } else if let Some(fun) = expr_any.downcast_ref::<ScalarFunctionExpr>() {
+        // date_bin(stride, source[, origin])
+        fun.name() == "date_bin"
+            && fun
+                .args()
+                .get(1)
+                .and_then(|arg| arg.as_any().downcast_ref::<Column>())
+                .map(|col| col == time_col)
+                .unwrap_or_default()
+    } else {
+        false
+    }
+}
+
+/// Sort `input` by `sort_exprs` into a single partition, unless it is sorted already.
+fn sort_by_time(
+    input: Arc<dyn ExecutionPlan>,
+    sort_exprs: Vec<PhysicalSortExpr>,
+) -> Arc<dyn ExecutionPlan> {
+    let sorted = input
+        .output_ordering()
+        .map(|ordering| ordering.first() == sort_exprs.first())
+        .unwrap_or_default();
+    let input = if sorted {
+        input
+    } else {
+        Arc::new(SortExec::new(sort_exprs.clone(), input).with_preserve_partitioning(true))
+    };
+
+    if input.output_partitioning().partition_count() > 1 {
+        Arc::new(SortPreservingMergeExec::new(sort_exprs, input))
+    } else {
+        input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{ArrayRef, Int64Array, StringArray, TimestampNanosecondArray},
+        record_batch::RecordBatch,
+    };
+    use datafusion::{assert_batches_eq, physical_plan::displayable};
+
+    use crate::exec::{DedicatedExecutors, Executor, ExecutorConfig, ExecutorType};
+
+    use super::*;
+
+    async fn run(enabled: &str, sql: &str) -> (String, Vec<RecordBatch>) {
+        // multiple partitions, so that DataFusion splits the aggregation into partial and final ones
+        let config = ExecutorConfig {
+            target_query_partitions: 4.try_into().unwrap(),
+            ..ExecutorConfig::testing()
+        };
+        let exec = Executor::new_with_config_and_executors(
+            config,
+            Arc::new(DedicatedExecutors::new_testing()),
+        );
+        let ctx = exec
+            .new_execution_config(ExecutorType::Query)
+            .with_config_option("iox.progressive_aggregation", enabled)
+            .build();
+
+        // times are not sorted
+        let batch = RecordBatch::try_from_iter([
+            (
+                "tag",
+                Arc::new(StringArray::from_iter_values(["a", "b", "a", "b", "a"])) as ArrayRef,
+            ),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from_iter_values([
+                    3_000, 1_000, 2_500, 1_500, 4_000,
+                ])) as ArrayRef,
+            ),
+            (
+                "v",
+                Arc::new(Int64Array::from_iter_values([1, 2, 3, 4, 5])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        ctx.inner().register_batch("t", batch).unwrap();
+
+        let plan = ctx.sql_to_physical_plan(sql).await.unwrap();
+        let display = displayable(plan.as_ref()).indent(false).to_string();
+        (display, ctx.collect(plan).await.unwrap())
+    }
+
+    const SQL: &str = "SELECT date_bin(INTERVAL '1 microsecond', time) AS bucket, sum(v) AS v \
+                       FROM t GROUP BY bucket ORDER BY bucket";
+
+    const EXPECTED: &[&str] = &[
+        "+----------------------------+----+",
+        "| bucket                     | v  |",
+        "+----------------------------+----+",
+        "| 1970-01-01T00:00:00.000001 | 6  |",
+        "| 1970-01-01T00:00:00.000002 | 3  |",
+        "| 1970-01-01T00:00:00.000003 | 1  |",
+        "| 1970-01-01T00:00:00.000004 | 5  |",
+        "+----------------------------+----+",
+    ];
+
+    #[tokio::test]
+    async fn test_disabled() {
+        let (plan, batches) = run("false", SQL).await;
+        assert!(plan.contains("mode=Partial"));
+        assert_batches_eq!(EXPECTED, &batches);
+    }
+
+    #[tokio::test]
+    async fn test_progressive() {
+        let (plan, batches) = run("true", SQL).await;
+        assert!(!plan.contains("mode=Partial"));
+        assert!(plan.contains("AggregateExec: mode=Single"));
+        assert!(plan.contains("ordering_mode=Sorted"));
+        assert_batches_eq!(EXPECTED, &batches);
+    }
+
+    #[tokio::test]
+    async fn test_no_time_bucket() {
+        let (plan, _) = run("true", "SELECT tag, sum(v) FROM t GROUP BY tag").await;
+        assert!(plan.contains("mode=Partial"));
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    config::ConfigOptions,
+    error::Result,
+    physical_optimizer::PhysicalOptimizerRule,
+    physical_plan::{
+        aggregates::{AggregateExec, AggregateMode},
+        ExecutionPlan,
+    },
+};
+
+use crate::{config::IoxConfigExt, exec::series_limit::SeriesLimitExec};
+
+/// Enforce [`max_series`] on the output of grouping aggregations.
+///
+/// Every [`AggregateExec`] that produces final groups (i.e. that is not only a partial aggregation) and has a `GROUP
+/// BY` is wrapped into a [`SeriesLimitExec`].
+///
+/// This must run after the DataFusion rules that may change the aggregation modes.
+///
+///
+/// [`max_series`]: IoxConfigExt::max_series
+#[derive(Debug, Default)]
+pub struct SeriesLimit;
+
+impl PhysicalOptimizerRule for SeriesLimit {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let max_series = config
+            .extensions
+            .get::<IoxConfigExt>()
+            .map(|iox_config| iox_config.max_series)
+            .unwrap_or_default();
+        if max_series == 0 {
+            return Ok(plan);
+        }
+
+        plan.transform_up(&|plan| {
+            let plan_any = plan.as_any();
+
+            if let Some(limit_exec) = plan_any.downcast_ref::<SeriesLimitExec>() {
+                // already wrapped, e.g. because this rule ran before
+                let child = Arc::clone(&limit_exec.children()[0]);
+                if child.as_any().is::<SeriesLimitExec>() {
+                    return Ok(Transformed::Yes(child));
+                }
+            } else if let Some(agg_exec) = plan_any.downcast_ref::<AggregateExec>() {
+                let is_final = !matches!(agg_exec.mode(), AggregateMode::Partial);
+                if is_final && !agg_exec.group_expr().expr().is_empty() {
+                    return Ok(Transformed::Yes(Arc::new(SeriesLimitExec::new(
+                        plan, max_series,
+                    ))));
+                }
+            }
+
+            Ok(Transformed::No(plan))
+        })
+    }
+
+    fn name(&self) -> &str {
+        "series_limit"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{ArrayRef, Int64Array, StringArray},
+        record_batch::RecordBatch,
+    };
+    use datafusion::physical_plan::displayable;
+
+    use crate::exec::{series_limit::SeriesLimitExceeded, Executor, ExecutorType};
+
+    use super::*;
+
+    async fn run(max_series: &str, sql: &str) -> (String, Result<Vec<RecordBatch>>) {
+        let exec = Executor::new_testing();
+        let ctx = exec
+            .new_execution_config(ExecutorType::Query)
+            .with_config_option("iox.max_series", max_series)
+            .build();
+
+        let batch = RecordBatch::try_from_iter([
+            (
+                "tag",
+                Arc::new(StringArray::from_iter_values(["a", "b", "c", "a"])) as ArrayRef,
+            ),
+            (
+                "v",
+                Arc::new(Int64Array::from_iter_values(0..4)) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        ctx.inner().register_batch("t", batch).unwrap();
+
+        let plan = ctx.sql_to_physical_plan(sql).await.unwrap();
+        let display = displayable(plan.as_ref()).indent(false).to_string();
+        (display, ctx.collect(plan).await)
+    }
+
+    #[tokio::test]
+    async fn test_disabled() {
+        let (plan, res) = run("0", "SELECT tag, count(*) FROM t GROUP BY tag").await;
+        assert!(!plan.contains("SeriesLimitExec"));
+        assert_eq!(res.unwrap().iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_within_limit() {
+        let (plan, res) = run("3", "SELECT tag, count(*) FROM t GROUP BY tag").await;
+        assert!(plan.contains("SeriesLimitExec: limit=3"));
+        assert_eq!(res.unwrap().iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_limit_exceeded() {
+        let (_, res) = run("2", "SELECT tag, count(*) FROM t GROUP BY tag").await;
+        let err = res.unwrap_err();
+        assert_eq!(
+            SeriesLimitExceeded::find(&err),
+            Some(&SeriesLimitExceeded { limit: 2 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_group_by() {
+        let (plan, res) = run("2", "SELECT count(*) FROM t").await;
+        assert!(!plan.contains("SeriesLimitExec"));
+        assert_eq!(res.unwrap().iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! [`SortExec`]: datafusion::physical_plan::sorts::sort::SortExec
 
+pub mod object_store_sort;
 pub mod order_union_sorted_inputs;
 pub mod parquet_sortness;
 pub mod push_sort_through_union;
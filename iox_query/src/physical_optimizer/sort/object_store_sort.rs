@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use datafusion::{
+    common::tree_node::{Transformed, TreeNode},
+    config::ConfigOptions,
+    error::Result,
+    physical_optimizer::PhysicalOptimizerRule,
+    physical_plan::{sorts::sort::SortExec, ExecutionPlan},
+};
+
+use crate::{config::IoxConfigExt, exec::object_store_sort::ObjectStoreSortExec};
+
+/// Replace [`SortExec`]s with [`ObjectStoreSortExec`]s if [`object_store_spill`] is enabled.
+///
+/// Sorts with a `fetch` (i.e. top-k) only keep `fetch` rows in memory and are left as they are.
+///
+/// This must run after all rules that add sorts.
+///
+///
+/// [`object_store_spill`]: IoxConfigExt::object_store_spill
+#[derive(Debug, Default)]
+pub struct ObjectStoreSort;
+
+impl PhysicalOptimizerRule for ObjectStoreSort {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ConfigOptions,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let enabled = config
+            .extensions
+            .get::<IoxConfigExt>()
+            .map(|iox_config| iox_config.object_store_spill)
+            .unwrap_or_default();
+        if !enabled {
+            return Ok(plan);
+        }
+
+        plan.transform_up(&|plan| {
+            if let Some(sort_exec) = plan.as_any().downcast_ref::<SortExec>() {
+                if sort_exec.fetch().is_none() {
+                    return Ok(Transformed::Yes(Arc::new(ObjectStoreSortExec::new(
+                        Arc::clone(sort_exec.input()),
+                        sort_exec.expr().to_vec(),
+                        sort_exec.preserve_partitioning(),
+                    ))));
+                }
+            }
+
+            Ok(Transformed::No(plan))
+        })
+    }
+
+    fn name(&self) -> &str {
+        "object_store_sort"
+    }
+
+    fn schema_check(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+    use datafusion::{
+        physical_expr::PhysicalSortExpr,
+        physical_plan::{expressions::Column, placeholder_row::PlaceholderRowExec},
+    };
+
+    use crate::physical_optimizer::test_util::OptimizationTest;
+
+    use super::*;
+
+    fn config(object_store_spill: bool) -> ConfigOptions {
+        let mut config = ConfigOptions::default();
+        config.extensions.insert(IoxConfigExt {
+            object_store_spill,
+            ..Default::default()
+        });
+        config
+    }
+
+    fn plan(fetch: Option<usize>) -> Arc<dyn ExecutionPlan> {
+        let schema: SchemaRef =
+            Arc::new(Schema::new(vec![Field::new("col1", DataType::Utf8, true)]));
+        let expr = vec![PhysicalSortExpr {
+            expr: Arc::new(Column::new_with_schema("col1", &schema).unwrap()),
+            options: Default::default(),
+        }];
+        Arc::new(SortExec::new(expr, Arc::new(PlaceholderRowExec::new(schema))).with_fetch(fetch))
+    }
+
+    #[test]
+    fn test_replace_sort() {
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan(None), ObjectStoreSort, &config(true)),
+            @r###"
+        ---
+        input:
+          - " SortExec: expr=[col1@0 ASC]"
+          - "   PlaceholderRowExec"
+        output:
+          Ok:
+            - " ObjectStoreSortExec: expr=[col1@0 ASC], preserve_partitioning=[false]"
+            - "   PlaceholderRowExec"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_disabled() {
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan(None), ObjectStoreSort, &config(false)),
+            @r###"
+        ---
+        input:
+          - " SortExec: expr=[col1@0 ASC]"
+          - "   PlaceholderRowExec"
+        output:
+          Ok:
+            - " SortExec: expr=[col1@0 ASC]"
+            - "   PlaceholderRowExec"
+        "###
+        );
+    }
+
+    #[test]
+    fn test_keep_top_k() {
+        insta::assert_yaml_snapshot!(
+            OptimizationTest::new_with_config(plan(Some(10)), ObjectStoreSort, &config(true)),
+            @r###"
+        ---
+        input:
+          - " SortExec: TopK(fetch=10), expr=[col1@0 ASC]"
+          - "   PlaceholderRowExec"
+        output:
+          Ok:
+            - " SortExec: TopK(fetch=10), expr=[col1@0 ASC]"
+            - "   PlaceholderRowExec"
+        "###
+        );
+    }
+}
@@ -40,8 +40,8 @@ mod physical;
 pub(crate) mod progressive_eval;
 mod record_batch_exec;
 pub use self::overlap::group_potential_duplicates;
-pub use deduplicate::{DeduplicateExec, RecordBatchDeduplicator};
-pub(crate) use physical::{chunks_to_physical_nodes, PartitionedFileExt};
+pub use deduplicate::{DeduplicateExec, HashDeduplicateExec, RecordBatchDeduplicator};
+pub(crate) use physical::{chunks_to_physical_nodes, scan_target_partitions, PartitionedFileExt};
 
 pub(crate) use record_batch_exec::RecordBatchesExec;
 
@@ -233,7 +233,7 @@ impl TableProvider for ChunkTableProvider {
             &schema_with_chunk_order,
             None,
             self.chunks.clone(),
-            ctx.config().target_partitions(),
+            scan_target_partitions(&self.chunks, ctx.config().options()),
         );
 
         // De-dup before doing anything else, because all logical expressions act on de-duplicated data.
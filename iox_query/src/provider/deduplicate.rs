@@ -1,5 +1,6 @@
 //! Implemention of DeduplicateExec operator (resolves primary key conflicts) plumbing and tests
 mod algo;
+mod hash;
 
 use std::{collections::HashSet, fmt, sync::Arc};
 
@@ -10,6 +11,7 @@ use crate::CHUNK_ORDER_COLUMN_NAME;
 
 use self::algo::get_col_name;
 pub use self::algo::RecordBatchDeduplicator;
+pub use self::hash::HashDeduplicateExec;
 use datafusion::physical_expr::EquivalenceProperties;
 use datafusion::{
     error::{DataFusionError, Result},
@@ -0,0 +1,461 @@
+//! Implementation of the hash-based HashDeduplicateExec operator
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use arrow::{
+    array::{Array, ArrayRef, Int64Array, UInt32Array},
+    compute::take,
+    datatypes::SchemaRef,
+    error::ArrowError,
+    record_batch::RecordBatch,
+    row::{RowConverter, SortField},
+};
+use datafusion::{
+    common::downcast_value,
+    error::{DataFusionError, Result},
+    execution::{
+        context::TaskContext,
+        memory_pool::{MemoryConsumer, MemoryReservation},
+    },
+    physical_expr::EquivalenceProperties,
+    physical_plan::{
+        coalesce_batches::concat_batches,
+        expressions::{Column, PhysicalSortExpr},
+        metrics::{ExecutionPlanMetricsSet, MetricsSet, RecordOutput},
+        DisplayAs, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+        SendableRecordBatchStream, Statistics,
+    },
+};
+use datafusion_util::{watch::WatchedTask, AdapterStream};
+use futures::StreamExt;
+use observability_deps::tracing::{debug, trace};
+use tokio::sync::mpsc;
+
+use crate::CHUNK_ORDER_COLUMN_NAME;
+
+use super::{algo::get_col_name, DeduplicateMetrics};
+
+/// # HashDeduplicateExec
+///
+/// Applies the same IOx specific deduplication logic as
+/// [`DeduplicateExec`], but does NOT require its input to be sorted on
+/// the primary key.
+///
+/// Instead, the entire input is buffered and rows are grouped by their
+/// primary key using a hash table. For each group, the value chosen for
+/// each non-key column is the last non-null value, where rows are ordered
+/// by the chunk order column (if used) and then by their position in the
+/// input.
+///
+/// This avoids the sort required by [`DeduplicateExec`], which is
+/// expensive when the input comes unsorted from many small files. The
+/// output is NOT sorted.
+///
+/// [`DeduplicateExec`]: super::DeduplicateExec
+#[derive(Debug)]
+pub struct HashDeduplicateExec {
+    input: Arc<dyn ExecutionPlan>,
+    keys: Vec<PhysicalSortExpr>,
+    use_chunk_order_col: bool,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl HashDeduplicateExec {
+    /// Create a new operator that de-duplicates `input` on the columns of `keys`.
+    ///
+    /// The sort options of `keys` are ignored, they are only accepted so that
+    /// [`DeduplicateExec`] can be converted without further ado.
+    ///
+    /// [`DeduplicateExec`]: super::DeduplicateExec
+    pub fn new(
+        input: Arc<dyn ExecutionPlan>,
+        keys: Vec<PhysicalSortExpr>,
+        use_chunk_order_col: bool,
+    ) -> Self {
+        Self {
+            input,
+            keys,
+            use_chunk_order_col,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    pub fn keys(&self) -> &[PhysicalSortExpr] {
+        &self.keys
+    }
+
+    pub fn use_chunk_order_col(&self) -> bool {
+        self.use_chunk_order_col
+    }
+}
+
+impl ExecutionPlan for HashDeduplicateExec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn maintains_input_order(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn benefits_from_input_partitioning(&self) -> Vec<bool> {
+        vec![false]
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn equivalence_properties(&self) -> EquivalenceProperties {
+        EquivalenceProperties::new(self.schema())
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        assert_eq!(children.len(), 1);
+        let input = Arc::clone(&children[0]);
+        Ok(Arc::new(Self::new(
+            input,
+            self.keys.clone(),
+            self.use_chunk_order_col,
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        trace!(partition, "Start HashDeduplicateExec::execute");
+
+        if partition != 0 {
+            return Err(DataFusionError::Internal(
+                "HashDeduplicateExec only supports a single input stream".to_string(),
+            ));
+        }
+        let deduplicate_metrics = DeduplicateMetrics::new(&self.metrics, partition);
+        let reservation =
+            MemoryConsumer::new("HashDeduplicateExec").register(context.memory_pool());
+        let batch_size = context.session_config().batch_size();
+
+        let schema = self.schema();
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| Column::new_with_schema(get_col_name(key.expr.as_ref()), &schema))
+            .collect::<Result<Vec<_>>>()?;
+        let chunk_order_col = self
+            .use_chunk_order_col
+            .then(|| Column::new_with_schema(CHUNK_ORDER_COLUMN_NAME, &schema))
+            .transpose()?;
+
+        let input_stream = self.input.execute(0, context)?;
+
+        // the deduplication is performed in a separate task which is
+        // then sent via a channel to the output
+        let (tx, rx) = mpsc::channel(1);
+
+        let fut = hash_deduplicate(
+            input_stream,
+            keys,
+            chunk_order_col,
+            batch_size,
+            reservation,
+            tx.clone(),
+            deduplicate_metrics,
+        );
+
+        // A second task watches the output of the worker task and reports errors
+        let handle = WatchedTask::new(fut, vec![tx], "hash deduplicate batches");
+
+        debug!(
+            partition,
+            "End building stream for HashDeduplicateExec::execute"
+        );
+
+        Ok(AdapterStream::adapt(self.schema(), rx, handle))
+    }
+
+    fn required_input_distribution(&self) -> Vec<Distribution> {
+        vec![Distribution::SinglePartition]
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Result<Statistics> {
+        // use a guess from our input but they are NOT exact
+        Ok(self.input.statistics()?.into_inexact())
+    }
+}
+
+impl DisplayAs for HashDeduplicateExec {
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default | DisplayFormatType::Verbose => {
+                let keys: Vec<&str> = self
+                    .keys
+                    .iter()
+                    .map(|e| get_col_name(e.expr.as_ref()))
+                    .collect();
+                write!(f, "HashDeduplicateExec: [{}]", keys.join(","))
+            }
+        }
+    }
+}
+
+async fn hash_deduplicate(
+    mut input_stream: SendableRecordBatchStream,
+    keys: Vec<Column>,
+    chunk_order_col: Option<Column>,
+    batch_size: usize,
+    mut reservation: MemoryReservation,
+    tx: mpsc::Sender<Result<RecordBatch, DataFusionError>>,
+    deduplicate_metrics: DeduplicateMetrics,
+) -> Result<(), DataFusionError> {
+    let DeduplicateMetrics {
+        baseline_metrics,
+        num_dupes,
+    } = deduplicate_metrics;
+
+    // Buffer the entire input, rows with the same key may appear anywhere
+    let schema = input_stream.schema();
+    let mut batches = vec![];
+    while let Some(batch) = input_stream.next().await {
+        let batch = batch?;
+        reservation.try_grow(batch.get_array_memory_size())?;
+        batches.push(batch);
+    }
+
+    let timer = baseline_metrics.elapsed_compute().timer();
+    let input = concat_batches(
+        &schema,
+        &batches,
+        batches.iter().map(|b| b.num_rows()).sum(),
+    )?;
+    drop(batches);
+    let output = deduplicate_batch(&input, &keys, chunk_order_col.as_ref())?;
+    num_dupes.add(input.num_rows() - output.num_rows());
+    drop(input);
+    let output = output.record_output(&baseline_metrics);
+    timer.done();
+
+    let mut offset = 0;
+    while offset < output.num_rows() {
+        let len = batch_size.min(output.num_rows() - offset);
+        tx.send(Ok(output.slice(offset, len)))
+            .await
+            .map_err(|e| ArrowError::from_external_error(Box::new(e)))?;
+        offset += len;
+    }
+
+    Ok(())
+}
+
+/// De-duplicate the rows of `batch` that have the same values in the `keys` columns.
+///
+/// Groups are emitted in the order of their first row in `batch`.
+fn deduplicate_batch(
+    batch: &RecordBatch,
+    keys: &[Column],
+    chunk_order_col: Option<&Column>,
+) -> Result<RecordBatch> {
+    let key_arrays = keys
+        .iter()
+        .map(|key| Arc::clone(batch.column(key.index())))
+        .collect::<Vec<_>>();
+    let converter = RowConverter::new(
+        key_arrays
+            .iter()
+            .map(|array| SortField::new(array.data_type().clone()))
+            .collect(),
+    )?;
+    let rows = converter.convert_columns(&key_arrays)?;
+
+    // assign each row to the group of its key
+    let mut groups = HashMap::new();
+    let row_groups = rows
+        .iter()
+        .map(|row| {
+            let next_group = groups.len();
+            *groups.entry(row).or_insert(next_group)
+        })
+        .collect::<Vec<_>>();
+    let num_groups = groups.len();
+
+    // visit rows from the oldest to the newest, so that later values win
+    let mut row_order = (0..batch.num_rows()).collect::<Vec<_>>();
+    if let Some(chunk_order_col) = chunk_order_col {
+        let chunk_order = batch.column(chunk_order_col.index());
+        let chunk_order = downcast_value!(chunk_order, Int64Array);
+        // stable sort, so rows of the same chunk keep their input order
+        row_order.sort_by_key(|&row| chunk_order.value(row));
+    }
+
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|array| {
+            let mut last_non_null = vec![None; num_groups];
+            for &row in &row_order {
+                if array.is_valid(row) {
+                    last_non_null[row_groups[row]] = Some(row as u32);
+                }
+            }
+            take(array.as_ref(), &UInt32Array::from(last_non_null), None)
+        })
+        .collect::<Result<Vec<ArrayRef>, ArrowError>>()?;
+
+    Ok(RecordBatch::try_new(batch.schema(), columns)?)
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::{
+        array::{Float64Array, StringArray},
+        compute::SortOptions,
+    };
+    use arrow_util::assert_batches_eq;
+    use datafusion::physical_plan::{expressions::col, memory::MemoryExec};
+    use datafusion_util::test_collect;
+
+    use super::*;
+
+    fn keys(names: &[&str], batch: &RecordBatch) -> Vec<PhysicalSortExpr> {
+        names
+            .iter()
+            .map(|name| PhysicalSortExpr {
+                expr: col(name, &batch.schema()).unwrap(),
+                options: SortOptions::default(),
+            })
+            .collect()
+    }
+
+    fn batch(
+        t1: Vec<Option<&str>>,
+        t2: Vec<&str>,
+        f1: Vec<Option<f64>>,
+        f2: Vec<Option<f64>>,
+    ) -> RecordBatch {
+        RecordBatch::try_from_iter_with_nullable(vec![
+            ("t1", Arc::new(StringArray::from(t1)) as ArrayRef, true),
+            ("t2", Arc::new(StringArray::from(t2)) as ArrayRef, true),
+            ("f1", Arc::new(Float64Array::from(f1)) as ArrayRef, true),
+            ("f2", Arc::new(Float64Array::from(f2)) as ArrayRef, true),
+        ])
+        .unwrap()
+    }
+
+    async fn dedupe(
+        input: Vec<RecordBatch>,
+        keys: Vec<PhysicalSortExpr>,
+        use_chunk_order_col: bool,
+    ) -> Vec<RecordBatch> {
+        let schema = input[0].schema();
+        let input = Arc::new(MemoryExec::try_new(&[input], schema, None).unwrap());
+        let exec = Arc::new(HashDeduplicateExec::new(input, keys, use_chunk_order_col));
+        test_collect(exec).await
+    }
+
+    #[tokio::test]
+    async fn test_unsorted_input() {
+        // input (not sorted on t1, t2):
+        // t1 | t2 | f1 | f2
+        // ---+----+----+----
+        //  b | x  | 1  |
+        //  a | x  | 2  |
+        //  b | x  |    | 3
+        //  a | y  | 4  | 5
+        //  a | x  | 6  |
+        //    | x  | 7  |
+        //    | x  |    | 8
+        //
+        // expected output:
+        // t1 | t2 | f1 | f2
+        // ---+----+----+----
+        //  b | x  | 1  | 3
+        //  a | x  | 6  |
+        //  a | y  | 4  | 5
+        //    | x  | 7  | 8
+        let batch1 = batch(
+            vec![Some("b"), Some("a"), Some("b")],
+            vec!["x", "x", "x"],
+            vec![Some(1.0), Some(2.0), None],
+            vec![None, None, Some(3.0)],
+        );
+        let batch2 = batch(
+            vec![Some("a"), Some("a"), None, None],
+            vec!["y", "x", "x", "x"],
+            vec![Some(4.0), Some(6.0), Some(7.0), None],
+            vec![Some(5.0), None, None, Some(8.0)],
+        );
+
+        let keys = keys(&["t1", "t2"], &batch1);
+        let results = dedupe(vec![batch1, batch2], keys, false).await;
+
+        let expected = vec![
+            "+----+----+-----+-----+",
+            "| t1 | t2 | f1  | f2  |",
+            "+----+----+-----+-----+",
+            "| b  | x  | 1.0 | 3.0 |",
+            "| a  | x  | 6.0 |     |",
+            "| a  | y  | 4.0 | 5.0 |",
+            "|    | x  | 7.0 | 8.0 |",
+            "+----+----+-----+-----+",
+        ];
+        assert_batches_eq!(&expected, &results);
+    }
+
+    #[tokio::test]
+    async fn test_chunk_order() {
+        // rows of later chunks win, regardless of their position in the input
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "t1",
+                Arc::new(StringArray::from(vec!["a", "a", "a", "b"])) as ArrayRef,
+            ),
+            (
+                "f1",
+                Arc::new(Float64Array::from(vec![
+                    Some(3.0),
+                    Some(1.0),
+                    Some(2.0),
+                    None,
+                ])) as ArrayRef,
+            ),
+            (
+                CHUNK_ORDER_COLUMN_NAME,
+                Arc::new(Int64Array::from(vec![3, 1, 2, 1])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let keys = keys(&["t1"], &batch);
+        let results = dedupe(vec![batch], keys, true).await;
+
+        let expected = vec![
+            "+----+-----+---------------+",
+            "| t1 | f1  | __chunk_order |",
+            "+----+-----+---------------+",
+            "| a  | 3.0 | 3             |",
+            "| b  |     | 1             |",
+            "+----+-----+---------------+",
+        ];
+        assert_batches_eq!(&expected, &results);
+    }
+}
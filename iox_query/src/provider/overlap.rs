@@ -91,7 +91,8 @@ pub fn group_potential_duplicates(
     groups
 }
 
-fn timestamp_min_max(chunk: &dyn QueryChunk) -> Option<TimestampMinMax> {
+/// Time range of the chunk, if its statistics contain one.
+pub(crate) fn timestamp_min_max(chunk: &dyn QueryChunk) -> Option<TimestampMinMax> {
     let stats = chunk.stats();
     chunk
         .schema()
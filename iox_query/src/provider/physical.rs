@@ -2,11 +2,12 @@
 
 use crate::statistics::build_statistics_for_chunks;
 use crate::{
-    provider::record_batch_exec::RecordBatchesExec, util::arrow_sort_key_exprs, QueryChunk,
-    QueryChunkData, CHUNK_ORDER_COLUMN_NAME,
+    config::IoxConfigExt, provider::record_batch_exec::RecordBatchesExec,
+    util::arrow_sort_key_exprs, QueryChunk, QueryChunkData, CHUNK_ORDER_COLUMN_NAME,
 };
 use arrow::datatypes::{Fields, Schema as ArrowSchema, SchemaRef};
 use datafusion::{
+    config::ConfigOptions,
     datasource::{
         listing::PartitionedFile,
         object_store::ObjectStoreUrl,
@@ -281,6 +282,43 @@ pub fn chunks_to_physical_nodes(
     Arc::new(UnionExec::new(output_nodes))
 }
 
+/// Number of DataFusion partitions that a scan of `chunks` should be spread over, to be passed to
+/// [`chunks_to_physical_nodes`].
+///
+/// This is the configured [`target_partitions`], unless [`IoxConfigExt::adaptive_scan_partitions`] is enabled, in
+/// which case it is derived from the number of ingester partitions and the size of the parquet files in the scan
+/// (but never more than [`target_partitions`]).
+///
+///
+/// [`target_partitions`]: datafusion::common::config::ExecutionOptions::target_partitions
+pub fn scan_target_partitions(chunks: &[Arc<dyn QueryChunk>], config: &ConfigOptions) -> usize {
+    let target_partitions = config.execution.target_partitions;
+    let iox_config = config
+        .extensions
+        .get::<IoxConfigExt>()
+        .cloned()
+        .unwrap_or_default();
+    if !iox_config.adaptive_scan_partitions {
+        return target_partitions;
+    }
+
+    let mut parquet_bytes = 0;
+    let mut ingester_partitions = HashSet::new();
+    for chunk in chunks {
+        match chunk.data() {
+            QueryChunkData::RecordBatches(_) => {
+                ingester_partitions.insert(chunk.partition_id().clone());
+            }
+            QueryChunkData::Parquet(parquet_input) => {
+                parquet_bytes += parquet_input.object_meta.size;
+            }
+        }
+    }
+
+    let parquet_partitions = parquet_bytes.div_ceil(iox_config.scan_bytes_per_partition.max(1));
+    (parquet_partitions + ingester_partitions.len()).clamp(1, target_partitions.max(1))
+}
+
 /// Distribute items from the given iterator into `n` containers.
 ///
 /// This will produce less than `n` containers if the input has less than `n` elements.
@@ -329,6 +367,48 @@ mod tests {
         assert_eq!(distribute(0..3u8, 10), vec![vec![0], vec![1], vec![2]],);
     }
 
+    #[test]
+    fn test_scan_target_partitions() {
+        let parquet = |id, size| {
+            Arc::new(
+                TestChunk::new("table")
+                    .with_id(id)
+                    .with_dummy_parquet_file_and_size(size),
+            ) as Arc<dyn QueryChunk>
+        };
+        let ingester = |id, partition| {
+            Arc::new(
+                TestChunk::new("table")
+                    .with_id(id)
+                    .with_partition(partition),
+            ) as Arc<dyn QueryChunk>
+        };
+        let chunks = vec![
+            parquet(1, 60),
+            parquet(2, 60),
+            ingester(3, 1),
+            ingester(4, 1),
+        ];
+
+        let mut config = ConfigOptions::default();
+        config.execution.target_partitions = 4;
+        assert_eq!(scan_target_partitions(&chunks, &config), 4);
+
+        config.extensions.insert(IoxConfigExt {
+            adaptive_scan_partitions: true,
+            scan_bytes_per_partition: 100,
+            ..Default::default()
+        });
+        // 2 partitions for 120 bytes of parquet files, 1 for the ingester partition
+        assert_eq!(scan_target_partitions(&chunks, &config), 3);
+        assert_eq!(scan_target_partitions(&chunks[..1], &config), 1);
+        assert_eq!(scan_target_partitions(&[], &config), 1);
+
+        // never more than `target_partitions`
+        let chunks = (0..10).map(|id| parquet(id, 1_000)).collect::<Vec<_>>();
+        assert_eq!(scan_target_partitions(&chunks, &config), 4);
+    }
+
     #[test]
     fn test_combine_sort_key() {
         let schema_t1 = SchemaBuilder::new().tag("t1").timestamp().build().unwrap();
@@ -1,23 +1,31 @@
 //! Implementation of statistics based pruning
 
-use crate::QueryChunk;
+use crate::{QueryChunk, QueryChunkData};
 use arrow::{
     array::{ArrayRef, BooleanArray, UInt64Array},
     datatypes::{DataType, SchemaRef},
 };
 use datafusion::{
+    logical_expr::{
+        expr::ScalarFunction,
+        utils::{conjunction, split_conjunction},
+        BinaryExpr, Cast, Operator,
+    },
     physical_expr::execution_props::ExecutionProps,
     physical_optimizer::pruning::PruningStatistics,
     physical_plan::{ColumnStatistics, Statistics},
-    prelude::{col, Column, Expr},
+    prelude::{col, lit, Column, Expr},
     scalar::ScalarValue,
 };
 use datafusion_util::{create_pruning_predicate, lit_timestamptz_nano};
 use observability_deps::tracing::{debug, trace, warn};
-use query_functions::group_by::Aggregate;
+use query_functions::{group_by::Aggregate, REGEX_MATCH_UDF_NAME};
 use schema::{Schema, TIME_COLUMN_NAME};
-use std::collections::HashSet;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
 
 /// Reason why a chunk could not be pruned.
 ///
@@ -69,6 +77,58 @@ pub trait PruningObserver {
     fn could_not_prune(&self, _reason: NotPrunedReason, _chunk: &dyn QueryChunk) {}
 }
 
+/// Number of partitions and parquet files pruned while planning a query.
+///
+/// Shared by all contexts of a query, see
+/// [`IOxSessionContext::pruning_stats`](crate::exec::IOxSessionContext::pruning_stats).
+#[derive(Debug, Default)]
+pub struct PruningStats {
+    partitions_pruned: AtomicU64,
+    parquet_files_pruned: AtomicU64,
+}
+
+impl PruningStats {
+    /// Record that `n` partitions were pruned.
+    pub fn record_partitions_pruned(&self, n: u64) {
+        self.partitions_pruned.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Record that `n` parquet files were pruned.
+    pub fn record_parquet_files_pruned(&self, n: u64) {
+        self.parquet_files_pruned.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Record the chunks that were pruned, given whether each of `chunks` was kept (see [`prune_chunks`]).
+    ///
+    /// A partition counts as pruned if all of its chunks were pruned.
+    pub fn record_pruned_chunks(&self, chunks: &[Arc<dyn QueryChunk>], keep: &[bool]) {
+        assert_eq!(chunks.len(), keep.len());
+
+        let mut partitions = HashMap::new();
+        let mut parquet_files_pruned = 0;
+        for (chunk, &keep) in chunks.iter().zip(keep) {
+            *partitions.entry(chunk.partition_id()).or_insert(false) |= keep;
+            if !keep && matches!(chunk.data(), QueryChunkData::Parquet(_)) {
+                parquet_files_pruned += 1;
+            }
+        }
+        let partitions_pruned = partitions.values().filter(|kept| !**kept).count();
+
+        self.record_partitions_pruned(partitions_pruned as u64);
+        self.record_parquet_files_pruned(parquet_files_pruned);
+    }
+
+    /// Number of partitions pruned so far.
+    pub fn partitions_pruned(&self) -> u64 {
+        self.partitions_pruned.load(Ordering::Relaxed)
+    }
+
+    /// Number of parquet files pruned so far.
+    pub fn parquet_files_pruned(&self) -> u64 {
+        self.parquet_files_pruned.load(Ordering::Relaxed)
+    }
+}
+
 /// Given a Vec of prunable items, returns a possibly smaller set
 /// filtering those where the predicate can be proven to evaluate to
 /// `false` for every single row.
@@ -104,6 +164,8 @@ pub fn prune_summaries(
 ) -> Result<Vec<bool>, NotPrunedReason> {
     trace!(%filter_expr, "Filter_expr of pruning chunks");
 
+    let filter_expr = &replace_regex_matches(table_schema, filter_expr);
+
     // no information about the queries here
     let props = ExecutionProps::new();
     let pruning_predicate =
@@ -218,6 +280,122 @@ fn get_aggregate(stats: &ColumnStatistics, aggregate: Aggregate) -> Option<&Scal
     }
 }
 
+/// Replace anchored regex matches like `tag ~ '^prod-'` in the conjunction `filter_expr` with the range of values
+/// they can match (`tag >= 'prod-' AND tag < 'prod.'`), which can be evaluated using min/max statistics.
+///
+/// The range is implied by the regex match, so a chunk that can be pruned using the range can also be pruned using
+/// the regex match.
+fn replace_regex_matches(table_schema: &Schema, filter_expr: &Expr) -> Expr {
+    conjunction(
+        split_conjunction(filter_expr)
+            .into_iter()
+            .map(|expr| regex_match_range(table_schema, expr).unwrap_or_else(|| expr.clone())),
+    )
+    .expect("at least one conjunct")
+}
+
+/// Returns the range of values `expr` can match, if it is an anchored regex match.
+fn regex_match_range(table_schema: &Schema, expr: &Expr) -> Option<Expr> {
+    let (column, pattern) = match expr {
+        Expr::BinaryExpr(BinaryExpr {
+            left,
+            op: Operator::RegexMatch,
+            right,
+        }) => (left.as_ref(), right.as_ref()),
+        Expr::ScalarFunction(ScalarFunction { func_def, args })
+            if func_def.name() == REGEX_MATCH_UDF_NAME && args.len() == 2 =>
+        {
+            (&args[0], &args[1])
+        }
+        _ => return None,
+    };
+    let column = match column {
+        Expr::Cast(Cast { expr, .. }) => expr.as_ref(),
+        column => column,
+    };
+    let (Expr::Column(column), Expr::Literal(ScalarValue::Utf8(Some(pattern)))) = (column, pattern)
+    else {
+        return None;
+    };
+
+    let (lower, upper) = regex_prefix_bounds(pattern)?;
+    let index = table_schema.find_index_of(&column.name)?;
+    let data_type = table_schema.field(index).1.data_type();
+
+    let column = Expr::Column(column.clone());
+    let range = column.clone().gt_eq(lit(string_scalar(data_type, lower)?));
+    Some(match upper {
+        Some(upper) => range.and(column.lt(lit(string_scalar(data_type, upper)?))),
+        None => range,
+    })
+}
+
+/// Returns the bounds of the strings matched by the regex `pattern`, if it only matches strings with a literal
+/// prefix.
+///
+/// The lower bound is inclusive, the upper bound (if there is any) is exclusive.
+pub(crate) fn regex_prefix_bounds(pattern: &str) -> Option<(String, Option<String>)> {
+    let rest = pattern.strip_prefix('^')?;
+    if rest.contains('|') {
+        // alternatives may not be anchored, e.g. `^foo|bar`
+        return None;
+    }
+
+    let mut prefix = String::new();
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        let literal = match c {
+            '\\' => match chars.peek() {
+                Some(&escaped) if escaped.is_ascii_punctuation() => {
+                    chars.next();
+                    escaped
+                }
+                // character classes like `\d`
+                _ => break,
+            },
+            '.' | '+' | '*' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '^' | '$' => break,
+            c => c,
+        };
+        if matches!(chars.peek(), Some('*' | '?' | '{')) {
+            // the character may be repeated zero times
+            break;
+        }
+        prefix.push(literal);
+    }
+    if prefix.is_empty() {
+        return None;
+    }
+
+    // The smallest string greater than all strings starting with the prefix: increment the last character that
+    // can be incremented and drop the ones after it.
+    let mut upper = prefix.chars().collect::<Vec<_>>();
+    let upper = loop {
+        let Some(last) = upper.pop() else {
+            break None;
+        };
+        if let Some(next) = (last as u32 + 1..=char::MAX as u32).find_map(char::from_u32) {
+            upper.push(next);
+            break Some(upper.into_iter().collect());
+        }
+    };
+
+    Some((prefix, upper))
+}
+
+/// Returns the string `s` as a scalar of `data_type`, if that is a (dictionary-encoded) string type.
+pub(crate) fn string_scalar(data_type: &DataType, s: String) -> Option<ScalarValue> {
+    match data_type {
+        DataType::Utf8 => Some(ScalarValue::Utf8(Some(s))),
+        DataType::Dictionary(key_type, value_type) if value_type.as_ref() == &DataType::Utf8 => {
+            Some(ScalarValue::Dictionary(
+                key_type.clone(),
+                Box::new(ScalarValue::Utf8(Some(s))),
+            ))
+        }
+        _ => None,
+    }
+}
+
 /// Retention time expression, "time > retention_time".
 pub fn retention_expr(retention_time: i64) -> Expr {
     col(TIME_COLUMN_NAME).gt(lit_timestamptz_nano(retention_time))
@@ -435,6 +613,35 @@ mod test {
         merger.build()
     }
 
+    #[test]
+    fn test_pruning_stats() {
+        let chunks = [
+            TestChunk::new("t")
+                .with_partition(1)
+                .with_dummy_parquet_file(),
+            TestChunk::new("t").with_partition(1),
+            TestChunk::new("t")
+                .with_partition(2)
+                .with_dummy_parquet_file(),
+            TestChunk::new("t")
+                .with_partition(2)
+                .with_dummy_parquet_file(),
+            TestChunk::new("t").with_partition(3),
+        ]
+        .into_iter()
+        .map(|c| Arc::new(c) as Arc<dyn QueryChunk>)
+        .collect::<Vec<_>>();
+
+        let stats = PruningStats::default();
+        stats.record_pruned_chunks(&chunks, &[false, true, false, false, false]);
+        assert_eq!(stats.partitions_pruned(), 2);
+        assert_eq!(stats.parquet_files_pruned(), 3);
+
+        stats.record_pruned_chunks(&chunks, &[true; 5]);
+        assert_eq!(stats.partitions_pruned(), 2);
+        assert_eq!(stats.parquet_files_pruned(), 3);
+    }
+
     #[test]
     fn test_pruned_null() {
         test_helpers::maybe_start_logging();
@@ -625,6 +832,97 @@ mod test {
         assert_eq!(result.expect("pruning succeeds"), vec![true, false, false]);
     }
 
+    #[test]
+    fn test_regex_prefix_bounds() {
+        let bounds = |prefix: &str, upper: Option<&str>| {
+            Some((prefix.to_string(), upper.map(|s| s.to_string())))
+        };
+
+        assert_eq!(
+            regex_prefix_bounds("^prod-"),
+            bounds("prod-", Some("prod."))
+        );
+        assert_eq!(
+            regex_prefix_bounds("^prod-.*"),
+            bounds("prod-", Some("prod."))
+        );
+        assert_eq!(regex_prefix_bounds("^prod$"), bounds("prod", Some("proe")));
+        assert_eq!(regex_prefix_bounds("^ab+c"), bounds("ab", Some("ac")));
+        assert_eq!(regex_prefix_bounds("^abc?"), bounds("ab", Some("ac")));
+        assert_eq!(regex_prefix_bounds("^ab*"), bounds("a", Some("b")));
+        assert_eq!(regex_prefix_bounds(r"^a\.b\d"), bounds("a.b", Some("a.c")));
+        assert_eq!(
+            regex_prefix_bounds("^a\u{10FFFF}"),
+            bounds("a\u{10FFFF}", Some("b"))
+        );
+        assert_eq!(
+            regex_prefix_bounds("^\u{10FFFF}"),
+            bounds("\u{10FFFF}", None)
+        );
+
+        // not anchored
+        assert_eq!(regex_prefix_bounds("prod-"), None);
+        // no literal prefix
+        assert_eq!(regex_prefix_bounds("^(?i)prod"), None);
+        assert_eq!(regex_prefix_bounds("^[a-z]"), None);
+        assert_eq!(regex_prefix_bounds("^a?"), None);
+        // alternatives
+        assert_eq!(regex_prefix_bounds("^prod|staging"), None);
+    }
+
+    #[test]
+    fn test_pruned_regex() {
+        test_helpers::maybe_start_logging();
+        // column1 ~ '^prod-' where
+        //   c1: ["a", "z"] --> not pruned
+        //   c2: ["prod-a", "prod-z"] --> not pruned
+        //   c3: ["staging-a", "staging-z"] --> pruned
+        //   c4: ["a", "prod"] --> pruned
+        let chunks = [
+            ("chunk1", "a", "z"),
+            ("chunk2", "prod-a", "prod-z"),
+            ("chunk3", "staging-a", "staging-z"),
+            ("chunk4", "a", "prod"),
+        ]
+        .into_iter()
+        .map(|(name, min, max)| {
+            Arc::new(
+                TestChunk::new(name).with_tag_column_with_nulls_and_full_stats(
+                    "column1",
+                    Some(min),
+                    Some(max),
+                    100,
+                    None,
+                    0,
+                ),
+            ) as Arc<dyn QueryChunk>
+        })
+        .collect::<Vec<_>>();
+        let schema = merge_schema(&chunks);
+
+        let filters = vec![Expr::BinaryExpr(BinaryExpr::new(
+            Box::new(col("column1")),
+            Operator::RegexMatch,
+            Box::new(lit("^prod-")),
+        ))];
+        let result = prune_chunks(&schema, &chunks, &filters);
+        assert_eq!(
+            result.expect("pruning succeeds"),
+            vec![true, true, false, false]
+        );
+
+        // regex match as used by the InfluxRPC API
+        let filters = vec![query_functions::regex_match_expr(
+            col("column1"),
+            "^prod-".to_string(),
+        )];
+        let result = prune_chunks(&schema, &chunks, &filters);
+        assert_eq!(
+            result.expect("pruning succeeds"),
+            vec![true, true, false, false]
+        );
+    }
+
     #[test]
     fn test_pruned_multi_column() {
         test_helpers::maybe_start_logging();
@@ -1,25 +1,59 @@
 //! Ring buffer of queries that have been run with some brief information
 
-use data_types::NamespaceId;
-use datafusion::physical_plan::ExecutionPlan;
+use crate::{
+    exec::{query_memory::QueryMemoryPool, IOxSessionContext},
+    provider::{PartitionedFileExt, RecordBatchesExec},
+    session_settings::DisplaySettings,
+    warnings::QueryWarning,
+};
+use arrow::{
+    array::{
+        ArrayRef, BooleanArray, DictionaryArray, DurationNanosecondArray, Int64Array, StringArray,
+        TimestampNanosecondArray, UInt64Array,
+    },
+    compute::cast,
+    datatypes::{DataType, Field, Int32Type, Schema, SchemaRef, TimeUnit},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+use data_types::{NamespaceId, TransitionPartitionId};
+use datafusion::{
+    datasource::physical_plan::ParquetExec,
+    physical_plan::{displayable, ExecutionPlan},
+};
 use iox_time::{Time, TimeProvider};
+use metric::{Attributes, DurationHistogram, Metric, Registry, U64Counter};
+use object_store::{path::Path, DynObjectStore};
 use observability_deps::tracing::{info, warn};
 use parking_lot::Mutex;
+use parquet::{arrow::ArrowWriter, errors::ParquetError};
+use snafu::{ResultExt, Snafu};
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
     fmt::Debug,
+    num::NonZeroU64,
     sync::{
-        atomic::{self, AtomicBool, AtomicI64, AtomicUsize, Ordering},
+        atomic::{self, AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     time::Duration,
 };
+use tokio::{sync::broadcast, task::JoinHandle, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
 use trace::ctx::TraceId;
+use tracker::WorkloadClass;
 use uuid::Uuid;
 
 /// The query duration used for queries still running.
 const UNCOMPLETED_DURATION: i64 = -1;
 
+/// The output count used for queries that have not finished execution.
+const UNCOMPLETED_COUNT: u64 = u64::MAX;
+
+/// The number of [`QueryLogEvent`]s buffered for each subscriber of the
+/// [`QueryLog`] before the slowest subscriber lags.
+const EVENT_CHANNEL_CAPACITY: usize = 1_024;
+
 /// Information about a single query that was executed
 pub struct QueryLogEntry {
     /// Unique ID.
@@ -34,6 +68,15 @@ pub struct QueryLogEntry {
     /// The type of query
     pub query_type: &'static str,
 
+    /// The query language, derived from the [`query_type`](Self::query_type).
+    pub dialect: QueryDialect,
+
+    /// The kind of statement, derived from the query type and text.
+    pub statement_type: StatementType,
+
+    /// The workload class of the query, used to prioritize its admission
+    pub workload_class: WorkloadClass,
+
     /// The text of the query (SQL for sql queries, pbjson for storage rpc queries)
     pub query_text: QueryText,
 
@@ -43,10 +86,13 @@ pub struct QueryLogEntry {
     /// Time at which the query was run
     pub issue_time: Time,
 
+    /// Duration the query waited for admission before planning started, relative to [`issue_time`](Self::issue_time).
+    queue_duration: AtomicDuration,
+
     /// Duration it took to acquire a semaphore permit, relative to [`issue_time`](Self::issue_time).
     permit_duration: AtomicDuration,
 
-    /// Duration it took to plan the query, relative to [`issue_time`](Self::issue_time) + [`permit_duration`](Self::permit_duration).
+    /// Duration it took to plan the query, relative to [`issue_time`](Self::issue_time) + [`queue_duration`](Self::queue_duration).
     plan_duration: AtomicDuration,
 
     /// Duration it took to execute the query, relative to [`issue_time`](Self::issue_time) +
@@ -59,11 +105,50 @@ pub struct QueryLogEntry {
     /// CPU duration spend for computation.
     compute_duration: AtomicDuration,
 
+    /// Number of rows output by the query.
+    output_rows: AtomicCount,
+
+    /// Number of bytes of query results sent to the client.
+    output_bytes: AtomicCount,
+
+    /// Peak number of bytes of memory reserved by the query.
+    max_memory_bytes: AtomicCount,
+
+    /// Number of partitions considered while planning the query.
+    partitions_total: AtomicCount,
+
+    /// Number of partitions pruned while planning the query.
+    partitions_pruned: AtomicCount,
+
+    /// Number of parquet files considered while planning the query.
+    parquet_files_total: AtomicCount,
+
+    /// Number of parquet files pruned while planning the query.
+    parquet_files_pruned: AtomicCount,
+
+    /// Warnings raised while planning the query.
+    warnings: Mutex<Vec<QueryWarning>>,
+
+    /// Settings changed by `SET` statements preceding the query.
+    session_settings: Mutex<Vec<(String, String)>>,
+
     /// If the query completed successfully
     success: AtomicBool,
 
     /// If the query is currently running (in any state).
     running: AtomicBool,
+
+    /// Why the query was cancelled, if it was.
+    cancellation_reason: Mutex<Option<CancellationReason>>,
+
+    /// Text of the physical plan, see [`QueryLog::with_plan_capture`].
+    plan_text: Mutex<Option<Arc<str>>>,
+
+    /// The current phase of the query.
+    phase: Mutex<QueryPhase>,
+
+    /// If the query was sampled for logging, see [`QueryLog::with_log_sampling`].
+    sampled: bool,
 }
 
 impl Debug for QueryLogEntry {
@@ -73,27 +158,50 @@ impl Debug for QueryLogEntry {
             .field("namespace_id", &self.namespace_id)
             .field("namespace_name", &self.namespace_name)
             .field("query_type", &self.query_type)
+            .field("dialect", &self.dialect)
+            .field("statement_type", &self.statement_type)
+            .field("workload_class", &self.workload_class)
             .field("query_text", &self.query_text.to_string())
             .field("trace_id", &self.trace_id)
             .field("issue_time", &self.issue_time)
+            .field("queue_duration", &self.queue_duration())
             .field("permit_duration", &self.permit_duration())
             .field("plan_duration", &self.plan_duration())
             .field("execute_duration", &self.execute_duration())
             .field("end2end_duration", &self.end2end_duration())
             .field("compute_duration", &self.compute_duration())
+            .field("output_rows", &self.output_rows())
+            .field("output_bytes", &self.output_bytes())
+            .field("max_memory_bytes", &self.max_memory_bytes())
+            .field("partitions_total", &self.partitions_total())
+            .field("partitions_pruned", &self.partitions_pruned())
+            .field("parquet_files_total", &self.parquet_files_total())
+            .field("parquet_files_pruned", &self.parquet_files_pruned())
+            .field("warnings", &self.warnings())
+            .field("session_settings", &self.session_settings())
             .field("success", &self.success())
             .field("running", &self.running())
+            .field("cancellation_reason", &self.cancellation_reason())
+            .field("plan_text", &self.plan_text())
+            .field("phase", &self.phase())
             .finish()
     }
 }
 
 impl QueryLogEntry {
+    /// Duration the query waited for admission before planning started, relative to [`issue_time`](Self::issue_time).
+    ///
+    /// This is `None` for queries that were not [queued](QueryCompletedToken::queued).
+    pub fn queue_duration(&self) -> Option<Duration> {
+        self.queue_duration.get()
+    }
+
     /// Duration it took to acquire a semaphore permit, relative to [`issue_time`](Self::issue_time).
     pub fn permit_duration(&self) -> Option<Duration> {
         self.permit_duration.get()
     }
 
-    /// Duration it took to plan the query, relative to [`issue_time`](Self::issue_time) + [`permit_duration`](Self::permit_duration).
+    /// Duration it took to plan the query, relative to [`issue_time`](Self::issue_time) + [`queue_duration`](Self::queue_duration).
     pub fn plan_duration(&self) -> Option<Duration> {
         self.plan_duration.get()
     }
@@ -114,6 +222,76 @@ impl QueryLogEntry {
         self.compute_duration.get()
     }
 
+    /// Number of rows output by the query, set once it finishes execution.
+    ///
+    /// This is [`None`] if the plan does not report the number of rows it
+    /// output.
+    pub fn output_rows(&self) -> Option<u64> {
+        self.output_rows.get()
+    }
+
+    /// Number of bytes of query results sent to the client, set once the
+    /// query finishes execution.
+    ///
+    /// See [`QueryCompletedToken::add_output_bytes`].
+    pub fn output_bytes(&self) -> Option<u64> {
+        self.output_bytes.get()
+    }
+
+    /// Peak number of bytes of memory reserved from the DataFusion memory
+    /// pool by the query at any one time, set once it finishes execution.
+    pub fn max_memory_bytes(&self) -> Option<u64> {
+        self.max_memory_bytes.get()
+    }
+
+    /// Number of partitions considered by the query, set once it was planned.
+    ///
+    /// This is the number of partitions scanned by the plan plus the
+    /// [pruned](Self::partitions_pruned) ones.
+    pub fn partitions_total(&self) -> Option<u64> {
+        self.partitions_total.get()
+    }
+
+    /// Number of partitions pruned while planning the query, set once it was
+    /// planned.
+    ///
+    /// See [`IOxSessionContext::pruning_stats`].
+    pub fn partitions_pruned(&self) -> Option<u64> {
+        self.partitions_pruned.get()
+    }
+
+    /// Number of parquet files considered by the query, set once it was
+    /// planned.
+    ///
+    /// This is the number of parquet files scanned by the plan plus the
+    /// [pruned](Self::parquet_files_pruned) ones.
+    pub fn parquet_files_total(&self) -> Option<u64> {
+        self.parquet_files_total.get()
+    }
+
+    /// Number of parquet files pruned while planning the query, set once it
+    /// was planned.
+    ///
+    /// See [`IOxSessionContext::pruning_stats`].
+    pub fn parquet_files_pruned(&self) -> Option<u64> {
+        self.parquet_files_pruned.get()
+    }
+
+    /// Warnings raised while planning the query, set once it was planned.
+    ///
+    /// See [`IOxSessionContext::warnings`].
+    pub fn warnings(&self) -> Vec<QueryWarning> {
+        self.warnings.lock().clone()
+    }
+
+    /// Settings changed by `SET` statements preceding the query, as `(variable, value)` pairs, set once it was
+    /// planned.
+    ///
+    /// See [`IOxSessionContext::session_settings`].
+    pub fn session_settings(&self) -> Vec<(String, String)> {
+        self.session_settings.lock().clone()
+    }
+
     /// Returns true if `set_completed` was called with `success=true`
     pub fn success(&self) -> bool {
         self.success.load(Ordering::SeqCst)
@@ -124,6 +302,48 @@ impl QueryLogEntry {
         self.running.load(Ordering::SeqCst)
     }
 
+    /// Why the query was cancelled, or `None` if it was not cancelled.
+    pub fn cancellation_reason(&self) -> Option<CancellationReason> {
+        *self.cancellation_reason.lock()
+    }
+
+    /// The (possibly truncated) text of the physical plan of the query, set
+    /// once it was planned if the [`QueryLog`] captures plans.
+    ///
+    /// See [`QueryLog::with_plan_capture`].
+    pub fn plan_text(&self) -> Option<Arc<str>> {
+        self.plan_text.lock().clone()
+    }
+
+    /// The current phase of the query.
+    pub fn phase(&self) -> QueryPhase {
+        *self.phase.lock()
+    }
+
+    /// Record that the query was cancelled.
+    ///
+    /// Only the first reason is kept, since cancelling a query usually makes it fail in ways that may trigger
+    /// follow-up cancellations.
+    pub fn set_cancelled(&self, reason: CancellationReason) {
+        self.cancellation_reason.lock().get_or_insert(reason);
+    }
+
+    /// Log entry at WARN level as a slow query, see [`QueryLog::with_slow_query_threshold`].
+    fn log_slow(&self, threshold: Duration) {
+        warn!(
+            id=%self.id,
+            namespace_id=self.namespace_id.get(),
+            namespace_name=self.namespace_name.as_ref(),
+            query_type=self.query_type,
+            query_text=%self.query_text,
+            trace_id=self.trace_id.map(|id| format!("{:x}", id.get())),
+            end2end_duration_secs=self.end2end_duration().map(|d| d.as_secs_f64()),
+            threshold_secs=threshold.as_secs_f64(),
+            success=self.success(),
+            "slow_query",
+        )
+    }
+
     /// Log entry.
     pub fn log(&self, when: &'static str) {
         info!(
@@ -132,21 +352,243 @@ impl QueryLogEntry {
             namespace_id=self.namespace_id.get(),
             namespace_name=self.namespace_name.as_ref(),
             query_type=self.query_type,
+            dialect=self.dialect.name(),
+            statement_type=self.statement_type.name(),
+            workload_class=self.workload_class.as_str(),
             query_text=%self.query_text,
             trace_id=self.trace_id.map(|id| format!("{:x}", id.get())),
             issue_time=%self.issue_time,
+            queue_duration_secs=self.queue_duration().map(|d| d.as_secs_f64()),
             plan_duration_secs=self.plan_duration().map(|d| d.as_secs_f64()),
             permit_duration_secs=self.permit_duration().map(|d| d.as_secs_f64()),
             execute_duration_secs=self.execute_duration().map(|d| d.as_secs_f64()),
             end2end_duration_secs=self.end2end_duration().map(|d| d.as_secs_f64()),
             compute_duration_secs=self.compute_duration().map(|d| d.as_secs_f64()),
+            output_rows=self.output_rows(),
+            output_bytes=self.output_bytes(),
+            max_memory_bytes=self.max_memory_bytes(),
+            partitions_total=self.partitions_total(),
+            partitions_pruned=self.partitions_pruned(),
+            parquet_files_total=self.parquet_files_total(),
+            parquet_files_pruned=self.parquet_files_pruned(),
             success=self.success(),
             running=self.running(),
+            cancellation_reason=self.cancellation_reason().map(|r| r.as_str()),
             "query",
         )
     }
 }
 
+/// The query language of a query, see [`QueryLogEntry::dialect`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryDialect {
+    /// SQL.
+    Sql,
+
+    /// InfluxQL.
+    InfluxQl,
+
+    /// A FlightSQL command.
+    FlightSql,
+
+    /// A storage gRPC (InfluxRPC) request.
+    StorageRpc,
+}
+
+impl QueryDialect {
+    /// Derive the dialect from the query type passed to [`QueryLog::push`].
+    ///
+    /// Query types other than those of SQL, InfluxQL and FlightSQL queries
+    /// are the names of storage gRPC methods (e.g. `read_filter`).
+    pub fn from_query_type(query_type: &str) -> Self {
+        match query_type {
+            "sql" => Self::Sql,
+            "influxql" => Self::InfluxQl,
+            "flightsql" => Self::FlightSql,
+            _ => Self::StorageRpc,
+        }
+    }
+
+    /// Name of the dialect.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sql => "sql",
+            Self::InfluxQl => "influxql",
+            Self::FlightSql => "flightsql",
+            Self::StorageRpc => "storage-rpc",
+        }
+    }
+}
+
+impl std::fmt::Display for QueryDialect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The kind of statement of a query, see [`QueryLogEntry::statement_type`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatementType {
+    /// A query reading data, e.g. a `SELECT` statement or a storage gRPC
+    /// `read_*` request.
+    Select,
+
+    /// A query reading metadata, e.g. a `SHOW` statement, a storage gRPC
+    /// `tag_keys` request or a FlightSQL `CommandGet*` command.
+    Show,
+
+    /// An `EXPLAIN` statement.
+    Explain,
+
+    /// Any other statement.
+    Other,
+}
+
+impl StatementType {
+    /// Classify a query of the given `dialect` and `query_type` (see
+    /// [`QueryLog::push`]) by its text.
+    pub fn classify(dialect: QueryDialect, query_type: &str, query_text: &str) -> Self {
+        match dialect {
+            QueryDialect::Sql | QueryDialect::InfluxQl => Self::from_statement(query_text),
+            QueryDialect::FlightSql => match query_text.strip_prefix("CommandStatementQuery") {
+                Some(statement) => Self::from_statement(statement),
+                None if query_text.starts_with("CommandGet") => Self::Show,
+                None => Self::Other,
+            },
+            QueryDialect::StorageRpc if query_type.starts_with("read_") => Self::Select,
+            QueryDialect::StorageRpc => Self::Show,
+        }
+    }
+
+    /// Classify a SQL or InfluxQL statement by its leading keyword.
+    fn from_statement(statement: &str) -> Self {
+        let statement = statement.trim_start_matches(|c: char| c.is_whitespace() || c == '(');
+        let keyword = statement
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .next()
+            .unwrap_or_default();
+
+        if keyword.eq_ignore_ascii_case("select") || keyword.eq_ignore_ascii_case("with") {
+            Self::Select
+        } else if keyword.eq_ignore_ascii_case("show") {
+            Self::Show
+        } else if keyword.eq_ignore_ascii_case("explain") {
+            Self::Explain
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Name of the statement type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Select => "select",
+            Self::Show => "show",
+            Self::Explain => "explain",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl std::fmt::Display for StatementType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The phase of a query, see [`QueryLogEntry::phase`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryPhase {
+    /// The query has been received, see [`StateReceived`].
+    ///
+    /// Queries that were [queued](QueryPhase::Queued) return to this phase
+    /// once admitted.
+    Received,
+
+    /// The query waits for admission before planning, see [`StateQueued`].
+    Queued,
+
+    /// The query has been planned, see [`StatePlanned`].
+    Planned,
+
+    /// The query has been issued a permit to execute, see [`StatePermit`].
+    Permit,
+
+    /// The query ended before completing execution.
+    Cancel,
+
+    /// The query completed successfully.
+    Success,
+
+    /// The query failed during execution.
+    Fail,
+}
+
+impl QueryPhase {
+    /// Name of the phase.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Received => "received",
+            Self::Queued => "queued",
+            Self::Planned => "planned",
+            Self::Permit => "permit",
+            Self::Cancel => "cancel",
+            Self::Success => "success",
+            Self::Fail => "fail",
+        }
+    }
+
+    /// Returns true if the query has finished in this phase.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Cancel | Self::Success | Self::Fail)
+    }
+}
+
+impl std::fmt::Display for QueryPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A state transition of a [`QueryLogEntry`], see [`QueryLog::subscribe`].
+#[derive(Debug, Clone)]
+pub struct QueryLogEvent {
+    /// The phase the query transitioned to.
+    ///
+    /// This is captured at the time of the transition, whereas the phase of
+    /// the [`entry`](Self::entry) may have since advanced.
+    pub phase: QueryPhase,
+
+    /// The entry of the query.
+    pub entry: Arc<QueryLogEntry>,
+}
+
+/// Reason why a query was cancelled, see [`QueryLogEntry::set_cancelled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancellationReason {
+    /// The deadline of the request (e.g. the `grpc-timeout`) passed before the query finished.
+    Timeout,
+
+    /// The query was cancelled on request, see [`QueryLog::cancel`].
+    Requested,
+}
+
+impl CancellationReason {
+    /// Name of the reason, used for logging.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Requested => "requested",
+        }
+    }
+}
+
+impl std::fmt::Display for CancellationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 /// Snapshot of the entries the [`QueryLog`].
 #[derive(Debug)]
 pub struct QueryLogEntries {
@@ -156,18 +598,319 @@ pub struct QueryLogEntries {
     /// Maximum number of entries
     pub max_size: usize,
 
-    /// Number of evicted entries due to the "max size" constraint.
+    /// Maximum age of entries, if any.
+    pub max_age: Option<Duration>,
+
+    /// Number of evicted entries due to the "max size" or "max age" constraints.
     pub evicted: usize,
 }
 
+impl QueryLogEntries {
+    /// Arrow schema of [`to_record_batch`](Self::to_record_batch).
+    ///
+    /// Durations are nanosecond columns that are null while the respective
+    /// phase has not completed. The dialect, statement type and phase are
+    /// dictionary-encoded.
+    pub fn schema() -> SchemaRef {
+        let duration = |name| Field::new(name, DataType::Duration(TimeUnit::Nanosecond), true);
+        let dictionary =
+            || DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("namespace_id", DataType::Int64, false),
+            Field::new("namespace_name", DataType::Utf8, false),
+            Field::new("query_type", DataType::Utf8, false),
+            Field::new("dialect", dictionary(), false),
+            Field::new("statement_type", dictionary(), false),
+            Field::new("workload_class", DataType::Utf8, false),
+            Field::new("query_text", DataType::Utf8, false),
+            Field::new("trace_id", DataType::Utf8, true),
+            Field::new(
+                "issue_time",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+            duration("queue_duration"),
+            duration("plan_duration"),
+            duration("permit_duration"),
+            duration("execute_duration"),
+            duration("end2end_duration"),
+            duration("compute_duration"),
+            Field::new("output_rows", DataType::UInt64, true),
+            Field::new("output_bytes", DataType::UInt64, true),
+            Field::new("max_memory_bytes", DataType::UInt64, true),
+            Field::new("partitions_total", DataType::UInt64, true),
+            Field::new("partitions_pruned", DataType::UInt64, true),
+            Field::new("parquet_files_total", DataType::UInt64, true),
+            Field::new("parquet_files_pruned", DataType::UInt64, true),
+            Field::new("success", DataType::Boolean, false),
+            Field::new("running", DataType::Boolean, false),
+            Field::new("cancellation_reason", DataType::Utf8, true),
+            Field::new("session_settings", DataType::Utf8, true),
+            Field::new("plan", DataType::Utf8, true),
+            Field::new("phase", dictionary(), false),
+        ]))
+    }
+
+    /// Convert the entries into a [`RecordBatch`] with one row per entry, in
+    /// the order they were issued. See [`schema`](Self::schema).
+    pub fn to_record_batch(&self) -> Result<RecordBatch, ArrowError> {
+        let entries = &self.entries;
+        let durations = |f: fn(&QueryLogEntry) -> Option<Duration>| -> ArrayRef {
+            Arc::new(
+                entries
+                    .iter()
+                    .map(|e| f(e).map(|d| d.as_nanos() as i64))
+                    .collect::<DurationNanosecondArray>(),
+            )
+        };
+        let counts = |f: fn(&QueryLogEntry) -> Option<u64>| -> ArrayRef {
+            Arc::new(entries.iter().map(|e| f(e)).collect::<UInt64Array>())
+        };
+
+        RecordBatch::try_new(
+            Self::schema(),
+            vec![
+                Arc::new(StringArray::from_iter_values(
+                    entries.iter().map(|e| e.id.to_string()),
+                )),
+                Arc::new(Int64Array::from_iter_values(
+                    entries.iter().map(|e| e.namespace_id.get()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    entries.iter().map(|e| e.namespace_name.as_ref()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    entries.iter().map(|e| e.query_type),
+                )),
+                Arc::new(
+                    entries
+                        .iter()
+                        .map(|e| e.dialect.name())
+                        .collect::<DictionaryArray<Int32Type>>(),
+                ),
+                Arc::new(
+                    entries
+                        .iter()
+                        .map(|e| e.statement_type.name())
+                        .collect::<DictionaryArray<Int32Type>>(),
+                ),
+                Arc::new(StringArray::from_iter_values(
+                    entries.iter().map(|e| e.workload_class.as_str()),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    entries.iter().map(|e| e.query_text.to_string()),
+                )),
+                Arc::new(
+                    entries
+                        .iter()
+                        .map(|e| e.trace_id.map(|id| format!("{:x}", id.get())))
+                        .collect::<StringArray>(),
+                ),
+                Arc::new(TimestampNanosecondArray::from_iter_values(
+                    entries.iter().map(|e| e.issue_time.timestamp_nanos()),
+                )),
+                durations(QueryLogEntry::queue_duration),
+                durations(QueryLogEntry::plan_duration),
+                durations(QueryLogEntry::permit_duration),
+                durations(QueryLogEntry::execute_duration),
+                durations(QueryLogEntry::end2end_duration),
+                durations(QueryLogEntry::compute_duration),
+                counts(QueryLogEntry::output_rows),
+                counts(QueryLogEntry::output_bytes),
+                counts(QueryLogEntry::max_memory_bytes),
+                counts(QueryLogEntry::partitions_total),
+                counts(QueryLogEntry::partitions_pruned),
+                counts(QueryLogEntry::parquet_files_total),
+                counts(QueryLogEntry::parquet_files_pruned),
+                Arc::new(
+                    entries
+                        .iter()
+                        .map(|e| Some(e.success()))
+                        .collect::<BooleanArray>(),
+                ),
+                Arc::new(
+                    entries
+                        .iter()
+                        .map(|e| Some(e.running()))
+                        .collect::<BooleanArray>(),
+                ),
+                Arc::new(
+                    entries
+                        .iter()
+                        .map(|e| e.cancellation_reason().map(|r| r.as_str()))
+                        .collect::<StringArray>(),
+                ),
+                Arc::new(
+                    entries
+                        .iter()
+                        .map(|e| {
+                            let settings = e.session_settings();
+                            (!settings.is_empty()).then(|| DisplaySettings(&settings).to_string())
+                        })
+                        .collect::<StringArray>(),
+                ),
+                Arc::new(
+                    entries
+                        .iter()
+                        .map(|e| e.plan_text())
+                        .collect::<StringArray>(),
+                ),
+                Arc::new(
+                    entries
+                        .iter()
+                        .map(|e| e.phase().name())
+                        .collect::<DictionaryArray<Int32Type>>(),
+                ),
+            ],
+        )
+    }
+}
+
+/// Detection of slow queries, see [`QueryLog::with_slow_query_threshold`].
+#[derive(Debug)]
+struct SlowQueries {
+    /// Minimum end-to-end duration of a slow query.
+    threshold: Duration,
+
+    /// Number of slow queries, per query type.
+    count: Metric<U64Counter>,
+}
+
+impl SlowQueries {
+    /// Log and count the query of `entry` if it was slow.
+    fn observe(&self, entry: &QueryLogEntry) {
+        if entry.end2end_duration().is_some_and(|d| d > self.threshold) {
+            entry.log_slow(self.threshold);
+            self.count
+                .recorder(&[("query_type", entry.query_type)])
+                .inc(1);
+        }
+    }
+}
+
+/// Latency distributions of the phases of queries, see
+/// [`QueryLog::with_phase_metrics`].
+#[derive(Debug)]
+struct PhaseMetrics {
+    queue: Metric<DurationHistogram>,
+    plan: Metric<DurationHistogram>,
+    permit: Metric<DurationHistogram>,
+    execute: Metric<DurationHistogram>,
+    end2end: Metric<DurationHistogram>,
+}
+
+impl PhaseMetrics {
+    fn new(registry: &Registry) -> Self {
+        Self {
+            queue: registry.register_metric(
+                "query_log_queue_duration",
+                "Time queries waited for admission before planning started",
+            ),
+            plan: registry.register_metric("query_log_plan_duration", "Time taken to plan queries"),
+            permit: registry.register_metric(
+                "query_log_permit_duration",
+                "Time queries waited for a semaphore permit to execute",
+            ),
+            execute: registry.register_metric(
+                "query_log_execute_duration",
+                "Time taken to execute queries",
+            ),
+            end2end: registry.register_metric(
+                "query_log_end2end_duration",
+                "Time from receiving queries until they ended",
+            ),
+        }
+    }
+
+    /// Record the durations of all phases the query of `entry` completed,
+    /// once it ended.
+    fn observe(&self, entry: &QueryLogEntry) {
+        let attributes = Attributes::from(&[
+            ("query_type", entry.query_type),
+            ("outcome", entry.phase().name()),
+        ]);
+
+        for (metric, duration) in [
+            (&self.queue, entry.queue_duration()),
+            (&self.plan, entry.plan_duration()),
+            (&self.permit, entry.permit_duration()),
+            (&self.execute, entry.execute_duration()),
+            (&self.end2end, entry.end2end_duration()),
+        ] {
+            if let Some(duration) = duration {
+                metric.recorder(attributes.clone()).record(duration);
+            }
+        }
+    }
+}
+
+/// Errors persisting evicted entries, see [`QueryLog::persist_evicted`].
+#[derive(Debug, Snafu)]
+pub enum PersistError {
+    #[snafu(display("Failed to convert query log entries to Arrow: {source}"))]
+    ToArrow { source: ArrowError },
+
+    #[snafu(display("Failed to encode query log entries as Parquet: {source}"))]
+    Encode { source: ParquetError },
+
+    #[snafu(display("Failed to upload query log entries to {path}: {source}"))]
+    Upload {
+        path: Path,
+        source: object_store::Error,
+    },
+}
+
+/// Persistence of evicted entries, see [`QueryLog::with_persistence`].
+#[derive(Debug)]
+struct Persistence {
+    store: Arc<DynObjectStore>,
+    prefix: Path,
+
+    /// Maximum number of entries in `pending`.
+    max_pending: usize,
+
+    /// Evicted entries that have not been persisted yet.
+    pending: Mutex<VecDeque<Arc<QueryLogEntry>>>,
+
+    /// Number of entries dropped from `pending` without being persisted.
+    dropped: U64Counter,
+}
+
+impl Persistence {
+    /// Drop the oldest entries of `pending` beyond `max_pending`.
+    fn truncate(&self, pending: &mut VecDeque<Arc<QueryLogEntry>>) {
+        let n = pending.len().saturating_sub(self.max_pending);
+        if n > 0 {
+            pending.drain(..n);
+            self.dropped.inc(n as u64);
+        }
+    }
+}
+
+/// Entries and cancellation tokens of the planned queries that have not ended
+/// yet, keyed by [`QueryLogEntry::id`]. See [`QueryLog::cancel`].
+type CancellationRegistry = Arc<Mutex<HashMap<Uuid, (Arc<QueryLogEntry>, CancellationToken)>>>;
+
 /// Stores a fixed number `QueryExecutions` -- handles locking
 /// internally so can be shared across multiple
 pub struct QueryLog {
     log: Mutex<VecDeque<Arc<QueryLogEntry>>>,
     max_size: usize,
+    max_age: Option<Duration>,
     evicted: AtomicUsize,
     time_provider: Arc<dyn TimeProvider>,
     id_gen: IDGen,
+    events: broadcast::Sender<QueryLogEvent>,
+    slow_queries: Option<Arc<SlowQueries>>,
+    phase_metrics: Option<Arc<PhaseMetrics>>,
+    cancellations: CancellationRegistry,
+    log_sampling: Option<NonZeroU64>,
+    log_sample_counter: AtomicU64,
+    plan_capture: Option<usize>,
+    redactor: Option<Redactor>,
+    persistence: Option<Persistence>,
 }
 
 impl QueryLog {
@@ -185,10 +928,241 @@ impl QueryLog {
         Self {
             log: Mutex::new(VecDeque::with_capacity(max_size)),
             max_size,
+            max_age: None,
             evicted: AtomicUsize::new(0),
             time_provider,
             id_gen,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            slow_queries: None,
+            phase_metrics: None,
+            cancellations: Default::default(),
+            log_sampling: None,
+            log_sample_counter: AtomicU64::new(0),
+            plan_capture: None,
+            redactor: None,
+            persistence: None,
+        }
+    }
+
+    /// Evict entries issued more than `max_age` ago, in addition to the
+    /// `max_size` limit.
+    ///
+    /// Expired entries are evicted when a query is pushed and when the
+    /// entries are read, so the log never returns an expired entry.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Log queries whose end-to-end duration exceeds `threshold` at WARN
+    /// level with a dedicated `slow_query` message, once they end.
+    ///
+    /// Slow queries are counted in the `query_log_slow_queries` metric of
+    /// `metric_registry`, per query type.
+    pub fn with_slow_query_threshold(
+        mut self,
+        threshold: Duration,
+        metric_registry: &Registry,
+    ) -> Self {
+        let count = metric_registry.register_metric(
+            "query_log_slow_queries",
+            "Number of queries whose end-to-end duration exceeded the slow query threshold",
+        );
+        self.slow_queries = Some(Arc::new(SlowQueries { threshold, count }));
+        self
+    }
+
+    /// Record the queue, plan, permit wait, execute and end-to-end durations of
+    /// queries into [`DurationHistogram`]s of `metric_registry` once they end,
+    /// with the query type and outcome (the final [`QueryPhase`]) as
+    /// attributes.
+    ///
+    /// Durations of phases that a query did not complete are not recorded.
+    pub fn with_phase_metrics(mut self, metric_registry: &Registry) -> Self {
+        self.phase_metrics = Some(Arc::new(PhaseMetrics::new(metric_registry)));
+        self
+    }
+
+    /// Only emit the INFO log lines of 1 in `n` queries.
+    ///
+    /// Queries that do not complete successfully are always logged once they
+    /// end. Sampling does not affect which entries are kept in the log.
+    pub fn with_log_sampling(mut self, n: NonZeroU64) -> Self {
+        self.log_sampling = Some(n);
+        self
+    }
+
+    /// Store the text of the physical plan of queries on their entries once
+    /// they are planned, truncated to `max_len` bytes.
+    ///
+    /// See [`QueryLogEntry::plan_text`].
+    pub fn with_plan_capture(mut self, max_len: usize) -> Self {
+        self.plan_capture = Some(max_len);
+        self
+    }
+
+    /// Apply `redactor` to the text of queries (and their plans, see
+    /// [`with_plan_capture`](Self::with_plan_capture)) before they are
+    /// logged or stored, e.g. [`Redactor::string_literals`].
+    ///
+    /// The dialect and statement type are derived from the original text.
+    pub fn with_redaction(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Keep the entries evicted from the log, so that they can be persisted
+    /// as Parquet files under `prefix` in `store`.
+    ///
+    /// Evicted entries are only written once [`persist_evicted`] is called,
+    /// either explicitly or periodically by [`persist_periodically`]. Entries
+    /// of queries that are still running when evicted are persisted in their
+    /// state at that time.
+    ///
+    /// At most `max_pending` entries are kept until they are written, e.g.
+    /// while the store is unavailable. Beyond that, the oldest entries are
+    /// dropped and counted in the `query_log_persistence_dropped_entries`
+    /// metric of `metric_registry`.
+    ///
+    /// [`persist_evicted`]: Self::persist_evicted
+    /// [`persist_periodically`]: Self::persist_periodically
+    pub fn with_persistence(
+        mut self,
+        store: Arc<DynObjectStore>,
+        prefix: Path,
+        max_pending: usize,
+        metric_registry: &Registry,
+    ) -> Self {
+        let dropped = metric_registry
+            .register_metric::<U64Counter>(
+                "query_log_persistence_dropped_entries",
+                "Number of evicted query log entries that were dropped without being persisted",
+            )
+            .recorder(&[]);
+        self.persistence = Some(Persistence {
+            store,
+            prefix,
+            max_pending,
+            pending: Default::default(),
+            dropped,
+        });
+        self
+    }
+
+    /// Write the entries evicted since the last call as a single Parquet
+    /// file, returning its path.
+    ///
+    /// The file has the [schema](QueryLogEntries::schema) of
+    /// [`QueryLogEntries::to_record_batch`], except that durations are stored
+    /// as nanosecond integers. Returns `None` if persistence is not enabled
+    /// or no entries were evicted. If the upload fails, the entries are kept
+    /// for the next attempt.
+    pub async fn persist_evicted(&self) -> Result<Option<Path>, PersistError> {
+        let Some(persistence) = &self.persistence else {
+            return Ok(None);
+        };
+
+        let entries = std::mem::take(&mut *persistence.pending.lock());
+        if entries.is_empty() {
+            return Ok(None);
         }
+        let entries = QueryLogEntries {
+            entries,
+            max_size: self.max_size,
+            max_age: self.max_age,
+            evicted: self.evicted.load(Ordering::SeqCst),
+        };
+
+        let path = persistence.prefix.child(format!(
+            "{}-{}.parquet",
+            self.time_provider.now().timestamp_nanos(),
+            Uuid::new_v4()
+        ));
+        let res = async {
+            let data = encode_parquet(&entries)?;
+            persistence
+                .store
+                .put(&path, data.into())
+                .await
+                .context(UploadSnafu { path: path.clone() })
+        }
+        .await;
+
+        if let Err(e) = res {
+            // Retry with the next call, keeping the eviction order
+            let mut pending = persistence.pending.lock();
+            for entry in entries.entries.into_iter().rev() {
+                pending.push_front(entry);
+            }
+            persistence.truncate(&mut pending);
+            return Err(e);
+        }
+
+        Ok(Some(path))
+    }
+
+    /// Spawn a task that calls [`persist_evicted`](Self::persist_evicted)
+    /// every `interval`, until the log is dropped.
+    pub fn persist_periodically(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let log = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            loop {
+                ticker.tick().await;
+                let Some(log) = log.upgrade() else {
+                    return;
+                };
+                if let Err(e) = log.persist_evicted().await {
+                    warn!(%e, "failed to persist evicted query log entries");
+                }
+            }
+        })
+    }
+
+    /// Decide if the next query is sampled for logging.
+    fn sample_log(&self) -> bool {
+        match self.log_sampling {
+            Some(n) => self.log_sample_counter.fetch_add(1, Ordering::Relaxed) % n.get() == 0,
+            None => true,
+        }
+    }
+
+    /// Subscribe to the state transitions of all queries pushed to this log
+    /// from now on.
+    ///
+    /// An event is emitted when a query is received, each time it advances
+    /// to the next phase, and once it finishes (when the end-to-end duration
+    /// of the entry is set). Events are delivered regardless of the size of
+    /// the ring buffer.
+    ///
+    /// Events are buffered in a bounded channel: a subscriber falling too far
+    /// behind observes a [`RecvError::Lagged`] error reporting the number of
+    /// skipped events. Slow subscribers never block query execution.
+    ///
+    /// [`RecvError::Lagged`]: broadcast::error::RecvError::Lagged
+    pub fn subscribe(&self) -> broadcast::Receiver<QueryLogEvent> {
+        self.events.subscribe()
+    }
+
+    /// Cancel the query with the given [`QueryLogEntry::id`], aborting its
+    /// execution.
+    ///
+    /// Only queries that have been planned (see [`QueryCompletedToken::planned`])
+    /// but have not ended yet can be cancelled. Returns `false` if there is no
+    /// such query.
+    ///
+    /// The entry of the query records [`CancellationReason::Requested`] and
+    /// ends in [`QueryPhase::Cancel`].
+    pub fn cancel(&self, id: Uuid) -> bool {
+        let Some((entry, cancel)) = self.cancellations.lock().get(&id).cloned() else {
+            return false;
+        };
+
+        entry.set_cancelled(CancellationReason::Requested);
+        cancel.cancel();
+        true
     }
 
     pub fn push(
@@ -198,29 +1172,64 @@ impl QueryLog {
         query_type: &'static str,
         query_text: QueryText,
         trace_id: Option<TraceId>,
+        workload_class: WorkloadClass,
     ) -> QueryCompletedToken<StateReceived> {
+        let dialect = QueryDialect::from_query_type(query_type);
+        let text = query_text.to_string();
+        let statement_type = StatementType::classify(dialect, query_type, &text);
+        let query_text: QueryText = match &self.redactor {
+            Some(redactor) => Box::new(redactor.redact(&text)),
+            None => query_text,
+        };
         let entry = Arc::new(QueryLogEntry {
             id: (self.id_gen)(),
             namespace_id,
             namespace_name,
             query_type,
+            dialect,
+            statement_type,
+            workload_class,
             query_text,
             trace_id,
             issue_time: self.time_provider.now(),
+            queue_duration: Default::default(),
             permit_duration: Default::default(),
             plan_duration: Default::default(),
             execute_duration: Default::default(),
             end2end_duration: Default::default(),
             compute_duration: Default::default(),
+            output_rows: Default::default(),
+            output_bytes: Default::default(),
+            max_memory_bytes: Default::default(),
+            partitions_total: Default::default(),
+            partitions_pruned: Default::default(),
+            parquet_files_total: Default::default(),
+            parquet_files_pruned: Default::default(),
+            warnings: Default::default(),
+            session_settings: Default::default(),
             success: atomic::AtomicBool::new(false),
             running: atomic::AtomicBool::new(true),
+            cancellation_reason: Default::default(),
+            plan_text: Default::default(),
+            phase: Mutex::new(QueryPhase::Received),
+            sampled: self.sample_log(),
         });
-        entry.log("start");
+        if entry.sampled {
+            entry.log("start");
+        }
         let token = QueryCompletedToken {
             entry: Some(Arc::clone(&entry)),
             time_provider: Arc::clone(&self.time_provider),
-            state: Default::default(),
+            events: self.events.clone(),
+            slow_queries: self.slow_queries.clone(),
+            phase_metrics: self.phase_metrics.clone(),
+            cancellations: Arc::clone(&self.cancellations),
+            state: StateReceived {
+                plan_capture: self.plan_capture,
+                redactor: self.redactor.clone(),
+            },
         };
+        token.emit(QueryPhase::Received);
 
         if self.max_size == 0 {
             return token;
@@ -230,32 +1239,66 @@ impl QueryLog {
 
         // enforce limit
         while log.len() > self.max_size {
-            log.pop_front();
-            self.evicted.fetch_add(1, Ordering::SeqCst);
+            self.evict(log.pop_front());
         }
+        self.evict_expired(&mut log);
 
         log.push_back(Arc::clone(&entry));
         token
     }
 
     pub fn entries(&self) -> QueryLogEntries {
-        let log = self.log.lock();
+        let mut log = self.log.lock();
+        self.evict_expired(&mut log);
         QueryLogEntries {
             entries: log.clone(),
             max_size: self.max_size,
+            max_age: self.max_age,
             evicted: self.evicted.load(Ordering::SeqCst),
         }
     }
-}
 
-impl Debug for QueryLog {
+    /// Evict entries older than `max_age`, if set.
+    fn evict_expired(&self, log: &mut VecDeque<Arc<QueryLogEntry>>) {
+        let Some(max_age) = self.max_age else {
+            return;
+        };
+        let Some(cutoff) = self.time_provider.now().checked_sub(max_age) else {
+            return;
+        };
+
+        // Entries are pushed in issue order, so the oldest are at the front.
+        while log.front().is_some_and(|e| e.issue_time < cutoff) {
+            self.evict(log.pop_front());
+        }
+    }
+
+    /// Account an entry that was removed from the log.
+    fn evict(&self, entry: Option<Arc<QueryLogEntry>>) {
+        let Some(entry) = entry else {
+            return;
+        };
+
+        self.evicted.fetch_add(1, Ordering::SeqCst);
+        if let Some(persistence) = &self.persistence {
+            let mut pending = persistence.pending.lock();
+            pending.push_back(entry);
+            persistence.truncate(&mut pending);
+        }
+    }
+}
+
+impl Debug for QueryLog {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("QueryLog")
             .field("log", &self.log)
             .field("max_size", &self.max_size)
+            .field("max_age", &self.max_age)
             .field("evicted", &self.evicted)
             .field("time_provider", &self.time_provider)
             .field("id_gen", &"<ID_GEN>")
+            .field("subscribers", &self.events.receiver_count())
+            .field("persistence", &self.persistence)
             .finish()
     }
 }
@@ -269,8 +1312,32 @@ impl Debug for QueryLog {
 /// - The concurrency-limiting semaphore has NOT yet issued a permit.
 /// - The query is not planned.
 /// - The query has not been executed.
-#[derive(Debug, Clone, Copy, Default)]
-pub struct StateReceived;
+#[derive(Debug, Clone, Default)]
+pub struct StateReceived {
+    /// Maximum length of the captured plan text, if plans are captured.
+    ///
+    /// See [`QueryLog::with_plan_capture`].
+    plan_capture: Option<usize>,
+
+    /// Redaction of the captured plan text, see [`QueryLog::with_redaction`].
+    redactor: Option<Redactor>,
+}
+
+/// State of [`QueryCompletedToken`].
+///
+/// # Done
+/// - The query has been received (and potentially authenticated) by the server.
+///
+/// # To Do
+/// - The query has NOT yet been admitted by admission control.
+/// - The concurrency-limiting semaphore has NOT yet issued a permit.
+/// - The query is not planned.
+/// - The query has not been executed.
+#[derive(Debug, Clone)]
+pub struct StateQueued {
+    /// State to return to once admitted.
+    received: StateReceived,
+}
 
 /// State of [`QueryCompletedToken`].
 ///
@@ -286,6 +1353,9 @@ pub struct StateReceived;
 pub struct StatePlanned {
     /// Physical execution plan.
     plan: Arc<dyn ExecutionPlan>,
+
+    /// Pool accounting the memory reservations of the query.
+    memory_pool: Arc<QueryMemoryPool>,
 }
 
 /// State of [`QueryCompletedToken`].
@@ -300,6 +1370,12 @@ pub struct StatePlanned {
 pub struct StatePermit {
     /// Physical execution plan.
     plan: Arc<dyn ExecutionPlan>,
+
+    /// Pool accounting the memory reservations of the query.
+    memory_pool: Arc<QueryMemoryPool>,
+
+    /// Number of bytes of query results sent to the client so far.
+    output_bytes: u64,
 }
 
 /// A `QueryCompletedToken` is returned by `record_query` implementations of
@@ -315,6 +1391,18 @@ pub struct QueryCompletedToken<S> {
     /// Time provider
     time_provider: Arc<dyn TimeProvider>,
 
+    /// Subscribers to state transitions.
+    events: broadcast::Sender<QueryLogEvent>,
+
+    /// Slow query detection, if enabled.
+    slow_queries: Option<Arc<SlowQueries>>,
+
+    /// Phase duration metrics, if enabled.
+    phase_metrics: Option<Arc<PhaseMetrics>>,
+
+    /// Cancellation tokens of running queries.
+    cancellations: CancellationRegistry,
+
     /// Current state.
     state: S,
 }
@@ -324,22 +1412,125 @@ impl<S> QueryCompletedToken<S> {
     pub fn entry(&self) -> &Arc<QueryLogEntry> {
         self.entry.as_ref().expect("valid state")
     }
+
+    /// Transition the entry to `phase`, notifying subscribers.
+    fn emit(&self, phase: QueryPhase) {
+        let entry = self.entry.as_ref().expect("valid state");
+        *entry.phase.lock() = phase;
+
+        // There may be no subscribers, in which case the event is dropped.
+        let _ = self.events.send(QueryLogEvent {
+            phase,
+            entry: Arc::clone(entry),
+        });
+    }
 }
 
 impl QueryCompletedToken<StateReceived> {
+    /// Record that this query waits for admission (e.g. by a concurrency
+    /// limit) before planning can start.
+    ///
+    /// Once [admitted](QueryCompletedToken::admitted), the time since
+    /// [`issue_time`](QueryLogEntry::issue_time) is recorded as the
+    /// [queue duration](QueryLogEntry::queue_duration), rather than as part of
+    /// the plan duration.
+    pub fn queued(mut self) -> QueryCompletedToken<StateQueued> {
+        let entry = self.entry.take().expect("valid state");
+
+        let token = QueryCompletedToken {
+            entry: Some(entry),
+            time_provider: Arc::clone(&self.time_provider),
+            events: self.events.clone(),
+            slow_queries: self.slow_queries.clone(),
+            phase_metrics: self.phase_metrics.clone(),
+            cancellations: Arc::clone(&self.cancellations),
+            state: StateQueued {
+                received: self.state.clone(),
+            },
+        };
+        token.emit(QueryPhase::Queued);
+        token
+    }
+
     /// Record that this query got planned.
-    pub fn planned(mut self, plan: Arc<dyn ExecutionPlan>) -> QueryCompletedToken<StatePlanned> {
+    ///
+    /// The query is expected to be executed within `ctx`, so that its memory
+    /// usage can be recorded and it can be cancelled via [`QueryLog::cancel`].
+    pub fn planned(
+        mut self,
+        ctx: &IOxSessionContext,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> QueryCompletedToken<StatePlanned> {
         let entry = self.entry.take().expect("valid state");
 
         let now = self.time_provider.now();
-        let origin = entry.issue_time;
+        let origin = entry.issue_time + entry.queue_duration().unwrap_or_default();
         entry.plan_duration.set_relative(origin, now);
 
-        QueryCompletedToken {
+        let scanned = ScannedData::collect(plan.as_ref());
+        let pruning_stats = ctx.pruning_stats();
+        let partitions_pruned = pruning_stats.partitions_pruned();
+        let parquet_files_pruned = pruning_stats.parquet_files_pruned();
+        entry
+            .partitions_total
+            .set(scanned.partitions.len() as u64 + partitions_pruned);
+        entry.partitions_pruned.set(partitions_pruned);
+        entry
+            .parquet_files_total
+            .set(scanned.parquet_files + parquet_files_pruned);
+        entry.parquet_files_pruned.set(parquet_files_pruned);
+        *entry.warnings.lock() = ctx.warnings().get();
+        *entry.session_settings.lock() = ctx.session_settings().get();
+
+        self.cancellations.lock().insert(
+            entry.id,
+            (Arc::clone(&entry), ctx.cancellation_token().clone()),
+        );
+
+        if let Some(max_len) = self.state.plan_capture {
+            let mut text = displayable(plan.as_ref()).indent(false).to_string();
+            if let Some(redactor) = &self.state.redactor {
+                text = redactor.redact(&text);
+            }
+            *entry.plan_text.lock() = Some(truncate(text, max_len).into());
+        }
+
+        let token = QueryCompletedToken {
             entry: Some(entry),
             time_provider: Arc::clone(&self.time_provider),
-            state: StatePlanned { plan },
-        }
+            events: self.events.clone(),
+            slow_queries: self.slow_queries.clone(),
+            phase_metrics: self.phase_metrics.clone(),
+            cancellations: Arc::clone(&self.cancellations),
+            state: StatePlanned {
+                plan,
+                memory_pool: Arc::clone(ctx.memory_pool()),
+            },
+        };
+        token.emit(QueryPhase::Planned);
+        token
+    }
+}
+
+impl QueryCompletedToken<StateQueued> {
+    /// Record that this query was admitted, so planning can start.
+    pub fn admitted(mut self) -> QueryCompletedToken<StateReceived> {
+        let entry = self.entry.take().expect("valid state");
+
+        let now = self.time_provider.now();
+        entry.queue_duration.set_relative(entry.issue_time, now);
+
+        let token = QueryCompletedToken {
+            entry: Some(entry),
+            time_provider: Arc::clone(&self.time_provider),
+            events: self.events.clone(),
+            slow_queries: self.slow_queries.clone(),
+            phase_metrics: self.phase_metrics.clone(),
+            cancellations: Arc::clone(&self.cancellations),
+            state: self.state.received.clone(),
+        };
+        token.emit(QueryPhase::Received);
+        token
     }
 }
 
@@ -349,38 +1540,54 @@ impl QueryCompletedToken<StatePlanned> {
         let entry = self.entry.take().expect("valid state");
 
         let now = self.time_provider.now();
-        let origin = entry.issue_time + entry.plan_duration().expect("valid state");
+        let origin = entry.issue_time
+            + entry.queue_duration().unwrap_or_default()
+            + entry.plan_duration().expect("valid state");
         entry.permit_duration.set_relative(origin, now);
 
-        QueryCompletedToken {
+        let token = QueryCompletedToken {
             entry: Some(entry),
             time_provider: Arc::clone(&self.time_provider),
+            events: self.events.clone(),
+            slow_queries: self.slow_queries.clone(),
+            phase_metrics: self.phase_metrics.clone(),
+            cancellations: Arc::clone(&self.cancellations),
             state: StatePermit {
                 plan: Arc::clone(&self.state.plan),
+                memory_pool: Arc::clone(&self.state.memory_pool),
+                output_bytes: 0,
             },
-        }
+        };
+        token.emit(QueryPhase::Permit);
+        token
     }
 }
 
 impl QueryCompletedToken<StatePermit> {
+    /// Record that `bytes` of query results were sent to the client.
+    pub fn add_output_bytes(&mut self, bytes: u64) {
+        self.state.output_bytes = self.state.output_bytes.saturating_add(bytes);
+    }
+
     /// Record that this query completed successfully
     pub fn success(self) {
         let entry = self.entry.as_ref().expect("valid state");
         entry.success.store(true, Ordering::SeqCst);
 
-        self.finish()
+        self.finish(QueryPhase::Success)
     }
 
     /// Record that the query finished execution with an error.
     pub fn fail(self) {
-        self.finish()
+        self.finish(QueryPhase::Fail)
     }
 
-    fn finish(&self) {
+    fn finish(&self, phase: QueryPhase) {
         let entry = self.entry.as_ref().expect("valid state");
 
         let now = self.time_provider.now();
         let origin = entry.issue_time
+            + entry.queue_duration().unwrap_or_default()
             + entry.permit_duration().expect("valid state")
             + entry.plan_duration().expect("valid state");
         entry.execute_duration.set_relative(origin, now);
@@ -388,17 +1595,52 @@ impl QueryCompletedToken<StatePermit> {
         entry
             .compute_duration
             .set_absolute(collect_compute_duration(self.state.plan.as_ref()));
+        if let Some(rows) = collect_output_rows(self.state.plan.as_ref()) {
+            entry.output_rows.set(rows);
+        }
+        entry.output_bytes.set(self.state.output_bytes);
+        entry
+            .max_memory_bytes
+            .set(self.state.memory_pool.peak() as u64);
+
+        // A query failing because it was cancelled ends as cancelled.
+        let phase = match phase {
+            QueryPhase::Fail if entry.cancellation_reason().is_some() => QueryPhase::Cancel,
+            phase => phase,
+        };
+
+        // Subscribers are notified once the token is dropped, after the
+        // end-to-end duration is set.
+        *entry.phase.lock() = phase;
     }
 }
 
 impl<S> Drop for QueryCompletedToken<S> {
     fn drop(&mut self) {
-        if let Some(entry) = self.entry.take() {
+        if let Some(entry) = self.entry.as_ref() {
             let now = self.time_provider.now();
             entry.end2end_duration.set_relative(entry.issue_time, now);
             entry.running.store(false, Ordering::SeqCst);
-
-            entry.log("end");
+            self.cancellations.lock().remove(&entry.id);
+
+            // A query dropped before completing execution was cancelled.
+            let phase = entry.phase();
+            let phase = if phase.is_final() {
+                phase
+            } else {
+                QueryPhase::Cancel
+            };
+            self.emit(phase);
+
+            if entry.sampled || phase != QueryPhase::Success {
+                entry.log("end");
+            }
+            if let Some(slow_queries) = &self.slow_queries {
+                slow_queries.observe(entry);
+            }
+            if let Some(phase_metrics) = &self.phase_metrics {
+                phase_metrics.observe(entry);
+            }
         }
     }
 }
@@ -411,6 +1653,64 @@ pub type QueryText = Box<dyn std::fmt::Display + Send + Sync>;
 /// Method that generated [`Uuid`]s.
 pub type IDGen = Box<dyn Fn() -> Uuid + Send + Sync>;
 
+/// Function that removes sensitive data from query and plan texts before
+/// they are logged or stored, see [`QueryLog::with_redaction`].
+#[derive(Clone)]
+pub struct Redactor(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl Redactor {
+    /// Create a redactor from a function mapping the original text to its
+    /// redacted form.
+    pub fn new(f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(f))
+    }
+
+    /// A redactor replacing all single-quoted string literals with `?`.
+    pub fn string_literals() -> Self {
+        Self::new(redact_string_literals)
+    }
+
+    /// Redact `text`.
+    pub fn redact(&self, text: &str) -> String {
+        (self.0)(text)
+    }
+}
+
+impl Debug for Redactor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Redactor").field(&"<FN>").finish()
+    }
+}
+
+/// Replace all single-quoted string literals in `text` with `?`.
+///
+/// Quotes within literals are escaped by doubling them (`'it''s'`), as in
+/// SQL and InfluxQL. An unterminated literal extends to the end of `text`.
+fn redact_string_literals(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\'' {
+            redacted.push(c);
+            continue;
+        }
+
+        redacted.push('?');
+        while let Some(c) = chars.next() {
+            if c == '\'' {
+                if chars.peek() == Some(&'\'') {
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
+    redacted
+}
+
 struct AtomicDuration(AtomicI64);
 
 impl AtomicDuration {
@@ -443,6 +1743,134 @@ impl Default for AtomicDuration {
     }
 }
 
+/// An optional count, [`None`] until the query finishes execution.
+struct AtomicCount(AtomicU64);
+
+impl AtomicCount {
+    fn get(&self) -> Option<u64> {
+        match self.0.load(Ordering::Relaxed) {
+            UNCOMPLETED_COUNT => None,
+            v => Some(v),
+        }
+    }
+
+    fn set(&self, v: u64) {
+        // Saturate below the sentinel.
+        self.0
+            .store(v.min(UNCOMPLETED_COUNT - 1), Ordering::Relaxed);
+    }
+}
+
+impl Default for AtomicCount {
+    fn default() -> Self {
+        Self(AtomicU64::new(UNCOMPLETED_COUNT))
+    }
+}
+
+/// Collect the number of rows output by an [`ExecutionPlan`].
+///
+/// Falls back to the child of nodes that do not report their output rows, if
+/// they have exactly one.
+fn collect_output_rows(plan: &dyn ExecutionPlan) -> Option<u64> {
+    if let Some(rows) = plan.metrics().and_then(|m| m.output_rows()) {
+        return Some(rows as u64);
+    }
+
+    match plan.children().as_slice() {
+        [child] => collect_output_rows(child.as_ref()),
+        _ => None,
+    }
+}
+
+/// Partitions and parquet files read by the IOx scan nodes of a plan.
+#[derive(Debug, Default)]
+struct ScannedData {
+    partitions: HashSet<TransitionPartitionId>,
+    parquet_files: u64,
+}
+
+impl ScannedData {
+    fn collect(plan: &dyn ExecutionPlan) -> Self {
+        let mut scanned = Self::default();
+        scanned.visit(plan);
+        scanned
+    }
+
+    fn visit(&mut self, plan: &dyn ExecutionPlan) {
+        let plan_any = plan.as_any();
+
+        if let Some(record_batches_exec) = plan_any.downcast_ref::<RecordBatchesExec>() {
+            self.partitions.extend(
+                record_batches_exec
+                    .chunks()
+                    .map(|chunk| chunk.partition_id().clone()),
+            );
+        } else if let Some(parquet_exec) = plan_any.downcast_ref::<ParquetExec>() {
+            let files = parquet_exec.base_config().file_groups.iter().flatten();
+            for ext in files.filter_map(|file| {
+                file.extensions
+                    .as_ref()
+                    .and_then(|any| any.downcast_ref::<PartitionedFileExt>())
+            }) {
+                self.partitions.insert(ext.chunk.partition_id().clone());
+                self.parquet_files += 1;
+            }
+        }
+
+        for child in plan.children() {
+            self.visit(child.as_ref());
+        }
+    }
+}
+
+/// Encode `entries` as a Parquet file.
+///
+/// Parquet does not support Arrow durations, so these are stored as
+/// nanosecond integers.
+fn encode_parquet(entries: &QueryLogEntries) -> Result<Vec<u8>, PersistError> {
+    let batch = entries.to_record_batch().context(ToArrowSnafu)?;
+
+    let (fields, columns): (Vec<_>, Vec<_>) = batch
+        .schema()
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, column)| match field.data_type() {
+            DataType::Duration(_) => Ok((
+                Field::new(field.name(), DataType::Int64, field.is_nullable()),
+                cast(column, &DataType::Int64)?,
+            )),
+            _ => Ok((field.as_ref().clone(), Arc::clone(column))),
+        })
+        .collect::<Result<Vec<_>, ArrowError>>()
+        .context(ToArrowSnafu)?
+        .into_iter()
+        .unzip();
+    let batch =
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).context(ToArrowSnafu)?;
+
+    let mut data = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut data, batch.schema(), None).context(EncodeSnafu)?;
+    writer.write(&batch).context(EncodeSnafu)?;
+    writer.close().context(EncodeSnafu)?;
+
+    Ok(data)
+}
+
+/// Truncate `text` to `max_len` bytes (at a character boundary), marking
+/// truncated text with a trailing ellipsis.
+fn truncate(mut text: String, max_len: usize) -> String {
+    if text.len() > max_len {
+        let mut len = max_len;
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        text.truncate(len);
+        text.push('…');
+    }
+    text
+}
+
 /// Collect compute duration from [`ExecutionPlan`].
 fn collect_compute_duration(plan: &dyn ExecutionPlan) -> Duration {
     let mut total = Duration::ZERO;
@@ -462,7 +1890,7 @@ fn collect_compute_duration(plan: &dyn ExecutionPlan) -> Duration {
 
 #[cfg(test)]
 mod test_super {
-    use datafusion::error::DataFusionError;
+    use datafusion::{error::DataFusionError, execution::memory_pool::MemoryConsumer};
     use std::sync::atomic::AtomicU64;
 
     use datafusion::physical_plan::{
@@ -470,9 +1898,13 @@ mod test_super {
         DisplayAs, Metric,
     };
     use iox_time::MockProvider;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
     use test_helpers::tracing::TracingCapture;
 
     use super::*;
+    use crate::{
+        provider::chunks_to_physical_nodes, test::TestChunk, warnings::QueryWarningKind, QueryChunk,
+    };
 
     #[test]
     fn test_token_end2end_success() {
@@ -492,8 +1924,9 @@ mod test_super {
         assert_eq!(entry.end2end_duration(), None,);
         assert_eq!(entry.compute_duration(), None,);
 
+        let ctx = IOxSessionContext::with_testing();
         time_provider.inc(Duration::from_millis(1));
-        let token = token.planned(plan());
+        let token = token.planned(&ctx, plan());
 
         assert!(!entry.success());
         assert!(entry.running());
@@ -515,6 +1948,14 @@ mod test_super {
         assert_eq!(entry.compute_duration(), None,);
 
         time_provider.inc(Duration::from_millis(100));
+        let mut reservation =
+            MemoryConsumer::new("test").register(&ctx.inner().runtime_env().memory_pool);
+        reservation.grow(2_048);
+        reservation.shrink(1_024);
+        drop(reservation);
+        let mut token = token;
+        token.add_output_bytes(1_000);
+        token.add_output_bytes(24);
         token.success();
 
         assert!(entry.success());
@@ -524,12 +1965,15 @@ mod test_super {
         assert_eq!(entry.execute_duration(), Some(Duration::from_millis(100)),);
         assert_eq!(entry.end2end_duration(), Some(Duration::from_millis(111)),);
         assert_eq!(entry.compute_duration(), Some(Duration::from_millis(1_337)),);
+        assert_eq!(entry.output_rows(), Some(42));
+        assert_eq!(entry.output_bytes(), Some(1_024));
+        assert_eq!(entry.max_memory_bytes(), Some(2_048));
 
         assert_eq!(
             capture.to_string().trim(),
             [
-                r#"level = INFO; message = query; when = "start"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; success = false; running = true;"#,
-                r#"level = INFO; message = query; when = "end"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; plan_duration_secs = 0.001; permit_duration_secs = 0.01; execute_duration_secs = 0.1; end2end_duration_secs = 0.111; compute_duration_secs = 1.337; success = true; running = false;"#,
+                r#"level = INFO; message = query; when = "start"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; dialect = "sql"; statement_type = "select"; workload_class = "normal"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; success = false; running = true;"#,
+                r#"level = INFO; message = query; when = "end"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; dialect = "sql"; statement_type = "select"; workload_class = "normal"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; plan_duration_secs = 0.001; permit_duration_secs = 0.01; execute_duration_secs = 0.1; end2end_duration_secs = 0.111; compute_duration_secs = 1.337; output_rows = 42; output_bytes = 1024; max_memory_bytes = 2048; partitions_total = 0; partitions_pruned = 0; parquet_files_total = 0; parquet_files_pruned = 0; success = true; running = false;"#,
             ].join(" \n")
         );
     }
@@ -544,8 +1988,9 @@ mod test_super {
             entry,
         } = Test::default();
 
+        let ctx = IOxSessionContext::with_testing();
         time_provider.inc(Duration::from_millis(1));
-        let token = token.planned(plan());
+        let token = token.planned(&ctx, plan());
         time_provider.inc(Duration::from_millis(10));
         let token = token.permit();
         time_provider.inc(Duration::from_millis(100));
@@ -558,12 +2003,15 @@ mod test_super {
         assert_eq!(entry.execute_duration(), Some(Duration::from_millis(100)),);
         assert_eq!(entry.end2end_duration(), Some(Duration::from_millis(111)),);
         assert_eq!(entry.compute_duration(), Some(Duration::from_millis(1_337)),);
+        assert_eq!(entry.output_rows(), Some(42));
+        assert_eq!(entry.output_bytes(), Some(0));
+        assert_eq!(entry.max_memory_bytes(), Some(0));
 
         assert_eq!(
             capture.to_string().trim(),
             [
-                r#"level = INFO; message = query; when = "start"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; success = false; running = true;"#,
-                r#"level = INFO; message = query; when = "end"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; plan_duration_secs = 0.001; permit_duration_secs = 0.01; execute_duration_secs = 0.1; end2end_duration_secs = 0.111; compute_duration_secs = 1.337; success = false; running = false;"#,
+                r#"level = INFO; message = query; when = "start"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; dialect = "sql"; statement_type = "select"; workload_class = "normal"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; success = false; running = true;"#,
+                r#"level = INFO; message = query; when = "end"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; dialect = "sql"; statement_type = "select"; workload_class = "normal"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; plan_duration_secs = 0.001; permit_duration_secs = 0.01; execute_duration_secs = 0.1; end2end_duration_secs = 0.111; compute_duration_secs = 1.337; output_rows = 42; output_bytes = 0; max_memory_bytes = 0; partitions_total = 0; partitions_pruned = 0; parquet_files_total = 0; parquet_files_pruned = 0; success = false; running = false;"#,
             ].join(" \n")
         );
     }
@@ -592,12 +2040,862 @@ mod test_super {
         assert_eq!(
             capture.to_string().trim(),
             [
-                r#"level = INFO; message = query; when = "start"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; success = false; running = true;"#,
-                r#"level = INFO; message = query; when = "end"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; end2end_duration_secs = 0.1; success = false; running = false;"#,
+                r#"level = INFO; message = query; when = "start"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; dialect = "sql"; statement_type = "select"; workload_class = "normal"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; success = false; running = true;"#,
+                r#"level = INFO; message = query; when = "end"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; dialect = "sql"; statement_type = "select"; workload_class = "normal"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; end2end_duration_secs = 0.1; success = false; running = false;"#,
             ].join(" \n")
         );
     }
 
+    #[test]
+    fn test_token_cancelled() {
+        let capture = TracingCapture::new();
+
+        let Test {
+            time_provider,
+            token,
+            entry,
+        } = Test::default();
+
+        assert_eq!(entry.cancellation_reason(), None);
+
+        time_provider.inc(Duration::from_millis(100));
+        entry.set_cancelled(CancellationReason::Timeout);
+        drop(token);
+
+        assert!(!entry.success());
+        assert!(!entry.running());
+        assert_eq!(
+            entry.cancellation_reason(),
+            Some(CancellationReason::Timeout)
+        );
+
+        assert_eq!(
+            capture.to_string().trim(),
+            [
+                r#"level = INFO; message = query; when = "start"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; dialect = "sql"; statement_type = "select"; workload_class = "normal"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; success = false; running = true;"#,
+                r#"level = INFO; message = query; when = "end"; id = 00000000-0000-0000-0000-000000000001; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; dialect = "sql"; statement_type = "select"; workload_class = "normal"; query_text = SELECT 1; issue_time = 1970-01-01T00:00:00.100+00:00; end2end_duration_secs = 0.1; success = false; running = false; cancellation_reason = "timeout";"#,
+            ].join(" \n")
+        );
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100).unwrap()));
+        let log = QueryLog::new(0, Arc::clone(&time_provider) as _);
+        let push = || {
+            log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new("SELECT 1"),
+                None,
+                WorkloadClass::Normal,
+            )
+        };
+
+        let ctx = IOxSessionContext::with_testing();
+
+        // The receipt of a query pushed before subscribing is not observed.
+        let early = push();
+
+        let mut rx = log.subscribe();
+        let mut next_phase = |id: Uuid| {
+            let event = rx.try_recv().expect("should have event");
+            assert_eq!(event.entry.id, id);
+            event.phase
+        };
+
+        let token = push();
+        let entry = Arc::clone(token.entry());
+        assert_eq!(entry.phase(), QueryPhase::Received);
+        assert_eq!(next_phase(entry.id), QueryPhase::Received);
+
+        let token = token.planned(&ctx, plan());
+        assert_eq!(next_phase(entry.id), QueryPhase::Planned);
+
+        let token = token.permit();
+        assert_eq!(next_phase(entry.id), QueryPhase::Permit);
+
+        time_provider.inc(Duration::from_millis(100));
+        token.success();
+        assert_eq!(next_phase(entry.id), QueryPhase::Success);
+        assert_eq!(entry.phase(), QueryPhase::Success);
+        assert_eq!(entry.end2end_duration(), Some(Duration::from_millis(100)));
+
+        // A query dropped before completing is cancelled.
+        let token = push();
+        let entry = Arc::clone(token.entry());
+        assert_eq!(next_phase(entry.id), QueryPhase::Received);
+        drop(token.planned(&ctx, plan()));
+        assert_eq!(next_phase(entry.id), QueryPhase::Planned);
+        assert_eq!(next_phase(entry.id), QueryPhase::Cancel);
+        assert_eq!(entry.phase(), QueryPhase::Cancel);
+
+        // Later transitions of the query pushed before subscribing are.
+        let id = early.entry().id;
+        early.planned(&ctx, plan()).permit().fail();
+        assert_eq!(next_phase(id), QueryPhase::Planned);
+        assert_eq!(next_phase(id), QueryPhase::Permit);
+        assert_eq!(next_phase(id), QueryPhase::Fail);
+
+        assert_matches::assert_matches!(rx.try_recv(), Err(broadcast::error::TryRecvError::Empty));
+    }
+
+    #[tokio::test]
+    async fn test_persist_evicted() {
+        let store = Arc::new(object_store::memory::InMemory::new());
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100).unwrap()));
+        let id_counter = AtomicU64::new(1);
+        let log = QueryLog::new_with_id_gen(
+            1,
+            Arc::clone(&time_provider) as _,
+            Box::new(move || Uuid::from_u128(id_counter.fetch_add(1, Ordering::SeqCst) as _)),
+        )
+        .with_persistence(
+            Arc::clone(&store) as _,
+            Path::from("query_log"),
+            10,
+            &metric::Registry::new(),
+        );
+        let push = |text: &'static str| {
+            drop(log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new(text),
+                None,
+                WorkloadClass::Normal,
+            ))
+        };
+
+        push("SELECT 1");
+        push("SELECT 2");
+        assert_eq!(log.persist_evicted().await.unwrap(), None);
+
+        push("SELECT 3");
+        push("SELECT 4");
+        assert_eq!(log.entries().evicted, 2);
+
+        let path = log.persist_evicted().await.unwrap().unwrap();
+        assert!(path.as_ref().starts_with("query_log/"));
+        assert!(path.as_ref().ends_with(".parquet"));
+        assert_eq!(log.persist_evicted().await.unwrap(), None);
+
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        let batches = ParquetRecordBatchReaderBuilder::try_new(data)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let batch = &batches[0];
+        let schema = batch.schema();
+        assert_eq!(
+            schema
+                .field_with_name("end2end_duration")
+                .unwrap()
+                .data_type(),
+            &DataType::Int64
+        );
+
+        let projection = ["id", "query_text", "phase"].map(|name| schema.index_of(name).unwrap());
+        arrow_util::assert_batches_eq!(
+            [
+                "+--------------------------------------+------------+--------+",
+                "| id                                   | query_text | phase  |",
+                "+--------------------------------------+------------+--------+",
+                "| 00000000-0000-0000-0000-000000000001 | SELECT 1   | cancel |",
+                "| 00000000-0000-0000-0000-000000000002 | SELECT 2   | cancel |",
+                "+--------------------------------------+------------+--------+",
+            ],
+            &[batch.project(&projection).unwrap()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_persist_evicted_bounded() {
+        let store = Arc::new(object_store::memory::InMemory::new());
+        let metric_registry = metric::Registry::new();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100).unwrap()));
+        let id_counter = AtomicU64::new(1);
+        let log = QueryLog::new_with_id_gen(
+            1,
+            Arc::clone(&time_provider) as _,
+            Box::new(move || Uuid::from_u128(id_counter.fetch_add(1, Ordering::SeqCst) as _)),
+        )
+        .with_persistence(
+            Arc::clone(&store) as _,
+            Path::from("query_log"),
+            2,
+            &metric_registry,
+        );
+        for text in ["SELECT 1", "SELECT 2", "SELECT 3", "SELECT 4", "SELECT 5"] {
+            drop(log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new(text),
+                None,
+                WorkloadClass::Normal,
+            ));
+        }
+        assert_eq!(log.entries().evicted, 4);
+
+        let dropped = metric_registry
+            .get_instrument::<Metric<U64Counter>>("query_log_persistence_dropped_entries")
+            .unwrap()
+            .get_observer(&metric::Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        assert_eq!(dropped, 2);
+
+        // only the newest entries are persisted
+        let path = log.persist_evicted().await.unwrap().unwrap();
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        let batches = ParquetRecordBatchReaderBuilder::try_new(data)
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let batch = &batches[0];
+        let projection = [batch.schema().index_of("query_text").unwrap()];
+        arrow_util::assert_batches_eq!(
+            [
+                "+------------+",
+                "| query_text |",
+                "+------------+",
+                "| SELECT 3   |",
+                "| SELECT 4   |",
+                "+------------+",
+            ],
+            &[batch.project(&projection).unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_pruning_stats() {
+        let Test {
+            time_provider: _,
+            token,
+            entry,
+        } = Test::default();
+
+        assert_eq!(entry.partitions_total(), None);
+        assert_eq!(entry.parquet_files_total(), None);
+
+        let chunks = [
+            TestChunk::new("t")
+                .with_id(1)
+                .with_partition(1)
+                .with_dummy_parquet_file(),
+            TestChunk::new("t")
+                .with_id(2)
+                .with_partition(1)
+                .with_dummy_parquet_file(),
+            TestChunk::new("t")
+                .with_id(3)
+                .with_partition(2)
+                .with_dummy_parquet_file(),
+            TestChunk::new("t").with_id(4).with_partition(3),
+        ]
+        .map(|chunk| Arc::new(chunk) as Arc<dyn QueryChunk>);
+        let schema = chunks[0].schema().as_arrow();
+        let plan = chunks_to_physical_nodes(&schema, None, chunks.into(), 2);
+
+        let ctx = IOxSessionContext::with_testing();
+        let pruning_stats = Arc::clone(ctx.child_ctx("chunks").pruning_stats());
+        pruning_stats.record_partitions_pruned(2);
+        pruning_stats.record_parquet_files_pruned(3);
+        pruning_stats.record_parquet_files_pruned(2);
+
+        let token = token.planned(&ctx, plan);
+        assert_eq!(entry.partitions_total(), Some(5));
+        assert_eq!(entry.partitions_pruned(), Some(2));
+        assert_eq!(entry.parquet_files_total(), Some(8));
+        assert_eq!(entry.parquet_files_pruned(), Some(5));
+
+        token.permit().success();
+    }
+
+    #[test]
+    fn test_warnings() {
+        let Test {
+            time_provider: _,
+            token,
+            entry,
+        } = Test::default();
+
+        assert_eq!(entry.warnings(), vec![]);
+
+        let ctx = IOxSessionContext::with_testing();
+        let warning = QueryWarning::new(QueryWarningKind::MissingColumn, "column 'foo'");
+        ctx.child_ctx("plan").warnings().push(warning.clone());
+
+        let token = token.planned(&ctx, plan());
+        assert_eq!(entry.warnings(), vec![warning]);
+
+        token.permit().success();
+    }
+
+    #[test]
+    fn test_session_settings() {
+        let Test {
+            time_provider: _,
+            token,
+            entry,
+        } = Test::default();
+
+        assert_eq!(entry.session_settings(), vec![]);
+
+        let ctx = IOxSessionContext::with_testing();
+        ctx.child_ctx("plan")
+            .session_settings()
+            .set("datafusion.execution.time_zone", "+01:00");
+
+        let token = token.planned(&ctx, plan());
+        assert_eq!(
+            entry.session_settings(),
+            vec![(
+                "datafusion.execution.time_zone".to_owned(),
+                "+01:00".to_owned()
+            )]
+        );
+
+        token.permit().success();
+    }
+
+    #[test]
+    fn test_queued() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100).unwrap()));
+        let log = QueryLog::new(10, Arc::clone(&time_provider) as _);
+        let push = || {
+            log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new("SELECT 1"),
+                None,
+                WorkloadClass::Normal,
+            )
+        };
+        let ctx = IOxSessionContext::with_testing();
+        let mut rx = log.subscribe();
+
+        let token = push();
+        let entry = Arc::clone(token.entry());
+        time_provider.inc(Duration::from_millis(5));
+        let token = token.queued();
+        assert_eq!(entry.phase(), QueryPhase::Queued);
+        time_provider.inc(Duration::from_millis(10));
+        let token = token.admitted();
+        assert_eq!(entry.queue_duration(), Some(Duration::from_millis(15)));
+        time_provider.inc(Duration::from_millis(1));
+        let token = token.planned(&ctx, plan());
+        time_provider.inc(Duration::from_millis(10));
+        let token = token.permit();
+        time_provider.inc(Duration::from_millis(100));
+        token.success();
+
+        assert_eq!(entry.queue_duration(), Some(Duration::from_millis(15)));
+        assert_eq!(entry.plan_duration(), Some(Duration::from_millis(1)));
+        assert_eq!(entry.permit_duration(), Some(Duration::from_millis(10)));
+        assert_eq!(entry.execute_duration(), Some(Duration::from_millis(100)));
+        assert_eq!(entry.end2end_duration(), Some(Duration::from_millis(126)));
+
+        let phases = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|event| event.phase)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            phases,
+            [
+                QueryPhase::Received,
+                QueryPhase::Queued,
+                QueryPhase::Received,
+                QueryPhase::Planned,
+                QueryPhase::Permit,
+                QueryPhase::Success,
+            ]
+        );
+
+        // A query dropped while waiting for admission is cancelled.
+        let token = push();
+        let entry = Arc::clone(token.entry());
+        time_provider.inc(Duration::from_millis(5));
+        drop(token.queued());
+        assert_eq!(entry.phase(), QueryPhase::Cancel);
+        assert_eq!(entry.queue_duration(), None);
+        assert_eq!(entry.end2end_duration(), Some(Duration::from_millis(5)));
+    }
+
+    #[test]
+    fn test_max_age() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100).unwrap()));
+        let log = QueryLog::new(10, Arc::clone(&time_provider) as _)
+            .with_max_age(Duration::from_secs(60));
+        let push = |text: &'static str| {
+            log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new(text),
+                None,
+                WorkloadClass::Normal,
+            )
+        };
+        let texts = |entries: &QueryLogEntries| {
+            entries
+                .entries
+                .iter()
+                .map(|e| e.query_text.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        push("a");
+        time_provider.inc(Duration::from_secs(30));
+        push("b");
+
+        let entries = log.entries();
+        assert_eq!(texts(&entries), ["a", "b"]);
+        assert_eq!(entries.max_age, Some(Duration::from_secs(60)));
+        assert_eq!(entries.evicted, 0);
+
+        // "a" expires without further pushes.
+        time_provider.inc(Duration::from_secs(31));
+        let entries = log.entries();
+        assert_eq!(texts(&entries), ["b"]);
+        assert_eq!(entries.evicted, 1);
+
+        // "b" expires on push.
+        time_provider.inc(Duration::from_secs(30));
+        push("c");
+        let entries = log.entries();
+        assert_eq!(texts(&entries), ["c"]);
+        assert_eq!(entries.evicted, 2);
+    }
+
+    #[test]
+    fn test_slow_query_threshold() {
+        let capture = TracingCapture::new();
+        let metric_registry = metric::Registry::new();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100).unwrap()));
+        let id_counter = AtomicU64::new(1);
+        let log = QueryLog::new_with_id_gen(
+            10,
+            Arc::clone(&time_provider) as _,
+            Box::new(move || Uuid::from_u128(id_counter.fetch_add(1, Ordering::SeqCst) as _)),
+        )
+        .with_slow_query_threshold(Duration::from_millis(100), &metric_registry);
+        let push = || {
+            log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new("SELECT 1"),
+                None,
+                WorkloadClass::Normal,
+            )
+        };
+
+        // Queries up to the threshold are not slow.
+        let token = push();
+        time_provider.inc(Duration::from_millis(100));
+        drop(token);
+
+        let token = push();
+        time_provider.inc(Duration::from_millis(250));
+        drop(token);
+
+        let warnings = capture
+            .to_string()
+            .lines()
+            .filter(|l| l.starts_with("level = WARN"))
+            .map(|l| l.trim().to_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            [
+                r#"level = WARN; message = slow_query; id = 00000000-0000-0000-0000-000000000002; namespace_id = 1; namespace_name = "ns"; query_type = "sql"; query_text = SELECT 1; end2end_duration_secs = 0.25; threshold_secs = 0.1; success = false;"#,
+            ]
+        );
+
+        let count = metric_registry
+            .get_instrument::<Metric<U64Counter>>("query_log_slow_queries")
+            .unwrap()
+            .get_observer(&metric::Attributes::from(&[("query_type", "sql")]))
+            .unwrap()
+            .fetch();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_phase_metrics() {
+        let metric_registry = metric::Registry::new();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100).unwrap()));
+        let log =
+            QueryLog::new(10, Arc::clone(&time_provider) as _).with_phase_metrics(&metric_registry);
+        let push = || {
+            log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new("SELECT 1"),
+                None,
+                WorkloadClass::Normal,
+            )
+        };
+        let ctx = IOxSessionContext::with_testing();
+
+        let token = push();
+        time_provider.inc(Duration::from_millis(1));
+        let token = token.planned(&ctx, plan());
+        time_provider.inc(Duration::from_millis(10));
+        let token = token.permit();
+        time_provider.inc(Duration::from_millis(100));
+        token.success();
+
+        // Cancelled before being planned.
+        let token = push();
+        time_provider.inc(Duration::from_millis(5));
+        drop(token);
+
+        let histogram = |name, outcome| {
+            metric_registry
+                .get_instrument::<Metric<DurationHistogram>>(name)
+                .unwrap()
+                .get_observer(&Attributes::from(&[
+                    ("query_type", "sql"),
+                    ("outcome", outcome),
+                ]))
+                .map(|h| h.fetch())
+        };
+
+        let success = |name| histogram(name, "success").unwrap();
+        assert_eq!(
+            success("query_log_plan_duration").total,
+            Duration::from_millis(1)
+        );
+        assert_eq!(
+            success("query_log_permit_duration").total,
+            Duration::from_millis(10)
+        );
+        assert_eq!(
+            success("query_log_execute_duration").total,
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            success("query_log_end2end_duration").total,
+            Duration::from_millis(111)
+        );
+
+        let cancel = histogram("query_log_end2end_duration", "cancel").unwrap();
+        assert_eq!(cancel.sample_count(), 1);
+        assert_eq!(cancel.total, Duration::from_millis(5));
+        assert!(histogram("query_log_plan_duration", "cancel").is_none());
+    }
+
+    #[test]
+    fn test_log_sampling() {
+        let capture = TracingCapture::new();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100).unwrap()));
+        let id_counter = AtomicU64::new(1);
+        let log = QueryLog::new_with_id_gen(
+            10,
+            Arc::clone(&time_provider) as _,
+            Box::new(move || Uuid::from_u128(id_counter.fetch_add(1, Ordering::SeqCst) as _)),
+        )
+        .with_log_sampling(NonZeroU64::new(2).unwrap());
+        let push = || {
+            log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new("SELECT 1"),
+                None,
+                WorkloadClass::Normal,
+            )
+        };
+        let ctx = IOxSessionContext::with_testing();
+        let succeed = |token: QueryCompletedToken<StateReceived>| {
+            token.planned(&ctx, plan()).permit().success();
+        };
+
+        succeed(push());
+        succeed(push());
+        succeed(push());
+        push().planned(&ctx, plan()).permit().fail();
+        drop(push());
+
+        // Every entry is kept.
+        assert_eq!(log.entries().entries.len(), 5);
+
+        let logged = capture
+            .to_string()
+            .lines()
+            .map(|l| {
+                let when = if l.contains(r#"when = "start""#) {
+                    "start"
+                } else {
+                    "end"
+                };
+                let id = l.split("id = ").nth(1).unwrap();
+                let id = Uuid::parse_str(&id[..36]).unwrap().as_u128();
+                (id, when)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            logged,
+            [
+                (1, "start"),
+                (1, "end"),
+                (3, "start"),
+                (3, "end"),
+                (4, "end"),
+                (5, "start"),
+                (5, "end"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_capture() {
+        let time_provider = Arc::new(MockProvider::new(Time::MIN));
+        let ctx = IOxSessionContext::with_testing();
+        let planned = |log: QueryLog| {
+            let token = log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new("SELECT 1"),
+                None,
+                WorkloadClass::Normal,
+            );
+            let entry = Arc::clone(token.entry());
+            assert_eq!(entry.plan_text(), None);
+
+            drop(token.planned(&ctx, plan()));
+            entry.plan_text().map(|t| t.to_string())
+        };
+
+        let log = QueryLog::new(1, Arc::clone(&time_provider) as _);
+        assert_eq!(planned(log), None);
+
+        let log = QueryLog::new(1, Arc::clone(&time_provider) as _).with_plan_capture(1_024);
+        assert_eq!(planned(log).as_deref(), Some("TestExec\n"));
+
+        let log = QueryLog::new(1, Arc::clone(&time_provider) as _).with_plan_capture(4);
+        assert_eq!(planned(log).as_deref(), Some("Test…"));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("abc".to_owned(), 3), "abc");
+        assert_eq!(truncate("abc".to_owned(), 2), "ab…");
+        assert_eq!(truncate("aé".to_owned(), 2), "a…");
+        assert_eq!(truncate("aé".to_owned(), 0), "…");
+    }
+
+    #[test]
+    fn test_statement_type() {
+        use QueryDialect::*;
+        use StatementType::*;
+
+        let classify = |query_type, query_text| {
+            let dialect = QueryDialect::from_query_type(query_type);
+            (
+                dialect,
+                StatementType::classify(dialect, query_type, query_text),
+            )
+        };
+
+        assert_eq!(classify("sql", "SELECT 1"), (Sql, Select));
+        assert_eq!(classify("sql", "  (select 1)"), (Sql, Select));
+        assert_eq!(
+            classify("sql", "WITH t AS (SELECT 1) SELECT * FROM t"),
+            (Sql, Select)
+        );
+        assert_eq!(classify("sql", "explain SELECT 1"), (Sql, Explain));
+        assert_eq!(classify("sql", "SHOW TABLES"), (Sql, Show));
+        assert_eq!(classify("sql", "SET x = 1"), (Sql, Other));
+        assert_eq!(classify("sql", ""), (Sql, Other));
+        assert_eq!(classify("influxql", "SHOW MEASUREMENTS"), (InfluxQl, Show));
+        assert_eq!(
+            classify("influxql", "SELECT * FROM cpu"),
+            (InfluxQl, Select)
+        );
+        assert_eq!(
+            classify("flightsql", "CommandStatementQuerySELECT 1"),
+            (FlightSql, Select)
+        );
+        assert_eq!(
+            classify("flightsql", "CommandGetCatalogs"),
+            (FlightSql, Show)
+        );
+        assert_eq!(
+            classify("flightsql", "CommandPreparedStatementQuery[1, 2]"),
+            (FlightSql, Other)
+        );
+        assert_eq!(classify("read_filter", "{}"), (StorageRpc, Select));
+        assert_eq!(classify("tag_keys", "{}"), (StorageRpc, Show));
+    }
+
+    #[test]
+    fn test_redaction() {
+        let capture = TracingCapture::new();
+        let log = QueryLog::new(1, Arc::new(MockProvider::new(Time::MIN)))
+            .with_redaction(Redactor::string_literals())
+            .with_plan_capture(1_024);
+        let ctx = IOxSessionContext::with_testing();
+
+        let token = log.push(
+            NamespaceId::new(1),
+            Arc::from("ns"),
+            "sql",
+            Box::new("SELECT * FROM t WHERE password = 'hunter2'"),
+            None,
+            WorkloadClass::Normal,
+        );
+        let entry = Arc::clone(token.entry());
+        assert_eq!(
+            entry.query_text.to_string(),
+            "SELECT * FROM t WHERE password = ?"
+        );
+        assert_eq!(entry.statement_type, StatementType::Select);
+
+        token.planned(&ctx, plan()).permit().success();
+        assert!(!capture.to_string().contains("hunter2"));
+
+        // The plan text is redacted as well.
+        let log = QueryLog::new(1, Arc::new(MockProvider::new(Time::MIN)))
+            .with_redaction(Redactor::new(|text| text.replace("Exec", "")))
+            .with_plan_capture(1_024);
+        let token = log.push(
+            NamespaceId::new(1),
+            Arc::from("ns"),
+            "sql",
+            Box::new("SELECT 1"),
+            None,
+            WorkloadClass::Normal,
+        );
+        let entry = Arc::clone(token.entry());
+        drop(token.planned(&ctx, plan()));
+        assert_eq!(entry.plan_text().as_deref(), Some("Test\n"));
+    }
+
+    #[test]
+    fn test_redact_string_literals() {
+        assert_eq!(redact_string_literals(""), "");
+        assert_eq!(redact_string_literals("SELECT 1"), "SELECT 1");
+        assert_eq!(
+            redact_string_literals("WHERE a = 'x' AND b = 'y'"),
+            "WHERE a = ? AND b = ?"
+        );
+        assert_eq!(
+            redact_string_literals("a = 'it''s' OR b = ''"),
+            "a = ? OR b = ?"
+        );
+        assert_eq!(redact_string_literals("\"ident\" = 'ü"), "\"ident\" = ?");
+    }
+
+    #[test]
+    fn test_cancel() {
+        let log = QueryLog::new(10, Arc::new(MockProvider::new(Time::MIN)));
+        let push = || {
+            log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new("SELECT 1"),
+                None,
+                WorkloadClass::Normal,
+            )
+        };
+
+        let other_ctx = IOxSessionContext::with_testing();
+        let other_token = push();
+        let other_entry = Arc::clone(other_token.entry());
+        let other_token = other_token.planned(&other_ctx, plan()).permit();
+
+        let ctx = IOxSessionContext::with_testing();
+        let token = push();
+        let entry = Arc::clone(token.entry());
+
+        // Queries can only be cancelled once planned.
+        assert!(!log.cancel(entry.id));
+        let token = token.planned(&ctx, plan()).permit();
+
+        assert!(log.cancel(entry.id));
+        assert!(ctx.cancellation_token().is_cancelled());
+        assert_eq!(
+            entry.cancellation_reason(),
+            Some(CancellationReason::Requested)
+        );
+
+        // The aborted execution fails, but the query ends as cancelled.
+        token.fail();
+        assert_eq!(entry.phase(), QueryPhase::Cancel);
+        assert!(!entry.running());
+        assert!(!log.cancel(entry.id));
+
+        // Other queries are unaffected.
+        assert!(!other_ctx.cancellation_token().is_cancelled());
+        other_token.success();
+        assert_eq!(other_entry.phase(), QueryPhase::Success);
+        assert_eq!(other_entry.cancellation_reason(), None);
+    }
+
+    #[test]
+    fn test_to_record_batch() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_millis(100).unwrap()));
+        let id_counter = AtomicU64::new(1);
+        let log = QueryLog::new_with_id_gen(
+            10,
+            Arc::clone(&time_provider) as _,
+            Box::new(move || Uuid::from_u128(id_counter.fetch_add(1, Ordering::SeqCst) as _)),
+        );
+        let push = || {
+            log.push(
+                NamespaceId::new(1),
+                Arc::from("ns"),
+                "sql",
+                Box::new("SELECT 1"),
+                None,
+                WorkloadClass::Normal,
+            )
+        };
+
+        let token = push();
+        time_provider.inc(Duration::from_millis(100));
+        drop(token);
+        let _running = push();
+
+        let batch = log.entries().to_record_batch().unwrap();
+        assert_eq!(batch.schema(), QueryLogEntries::schema());
+        assert_eq!(batch.num_rows(), 2);
+
+        let schema = batch.schema();
+        let projection = [
+            "id",
+            "phase",
+            "issue_time",
+            "end2end_duration",
+            "success",
+            "running",
+        ]
+        .map(|name| schema.index_of(name).unwrap());
+        arrow_util::assert_batches_eq!(
+            [
+                "+--------------------------------------+----------+--------------------------+------------------+---------+---------+",
+                "| id                                   | phase    | issue_time               | end2end_duration | success | running |",
+                "+--------------------------------------+----------+--------------------------+------------------+---------+---------+",
+                "| 00000000-0000-0000-0000-000000000001 | cancel   | 1970-01-01T00:00:00.100Z | 100ms            | false   | false   |",
+                "| 00000000-0000-0000-0000-000000000002 | received | 1970-01-01T00:00:00.200Z |                  | false   | true    |",
+                "+--------------------------------------+----------+--------------------------+------------------+---------+---------+",
+            ],
+            &[batch.project(&projection).unwrap()]
+        );
+    }
+
     struct Test {
         time_provider: Arc<MockProvider>,
         token: QueryCompletedToken<StateReceived>,
@@ -621,6 +2919,7 @@ mod test_super {
                 "sql",
                 Box::new("SELECT 1"),
                 None,
+                WorkloadClass::Normal,
             );
 
             let entry = Arc::clone(token.entry());
@@ -644,9 +2943,9 @@ mod test_super {
         fn fmt_as(
             &self,
             _t: datafusion::physical_plan::DisplayFormatType,
-            _f: &mut std::fmt::Formatter<'_>,
+            f: &mut std::fmt::Formatter<'_>,
         ) -> std::fmt::Result {
-            unimplemented!()
+            write!(f, "TestExec")
         }
     }
 
@@ -698,6 +2997,10 @@ mod test_super {
             t.add_duration(Duration::from_millis(1_337));
             metrics.push(Arc::new(Metric::new(MetricValue::ElapsedCompute(t), None)));
 
+            let rows = datafusion::physical_plan::metrics::Count::new();
+            rows.add(42);
+            metrics.push(Arc::new(Metric::new(MetricValue::OutputRows(rows), None)));
+
             Some(metrics)
         }
     }
@@ -0,0 +1,448 @@
+//! Cache of the final results of deterministic queries.
+//!
+//! Dashboards tend to re-issue identical queries every few seconds. As long as
+//! the catalog state of the tables they read does not change, the results of
+//! such queries can be served from memory instead of being re-executed.
+//!
+//! The cache is opt-in: callers compute the [`PlanFingerprint`] of the logical
+//! plan and session config of a query and the [`CatalogGenerations`] of the
+//! namespace and tables it reads, and [look up](ResultCache::get) or
+//! [store](ResultCache::insert) results with them.
+
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, HashMap, VecDeque},
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use arrow::record_batch::RecordBatch;
+use data_types::{NamespaceId, TableId};
+use datafusion::{
+    common::tree_node::{TreeNode, VisitRecursion},
+    config::ConfigOptions,
+    logical_expr::{expr::ScalarFunction, LogicalPlan, ScalarFunctionDefinition, Volatility},
+    prelude::Expr,
+};
+use metric::{Metric, Registry, U64Counter};
+use observability_deps::tracing::debug;
+use parking_lot::Mutex;
+
+/// Fingerprint of a deterministic [`LogicalPlan`] and the session settings
+/// that affect its results.
+///
+/// Two queries with equal fingerprints produce the same results when run
+/// against the same catalog state. Fingerprints are compared by their plans
+/// and settings, the hash only speeds up the lookup, so hash collisions never
+/// return the results of another query.
+#[derive(Clone)]
+pub struct PlanFingerprint {
+    hash: u64,
+    plan: Arc<LogicalPlan>,
+    time_zone: Option<String>,
+    batch_size: usize,
+}
+
+impl PlanFingerprint {
+    /// Compute the fingerprint of `plan`, run with `config`.
+    ///
+    /// Returns `None` if the results of `plan` may differ between runs, e.g.
+    /// because it calls `now()` or `random()`. This must therefore be called
+    /// on the plan before it is optimized, since optimization may replace such
+    /// calls with their value at planning time.
+    pub fn new(plan: &LogicalPlan, config: &ConfigOptions) -> Option<Self> {
+        if !is_deterministic(plan) {
+            return None;
+        }
+
+        let time_zone = config.execution.time_zone.clone();
+        let batch_size = config.execution.batch_size;
+
+        let mut hasher = DefaultHasher::new();
+        plan.hash(&mut hasher);
+        time_zone.hash(&mut hasher);
+        batch_size.hash(&mut hasher);
+
+        Some(Self {
+            hash: hasher.finish(),
+            plan: Arc::new(plan.clone()),
+            time_zone,
+            batch_size,
+        })
+    }
+}
+
+impl PartialEq for PlanFingerprint {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash
+            && self.time_zone == other.time_zone
+            && self.batch_size == other.batch_size
+            && (Arc::ptr_eq(&self.plan, &other.plan) || self.plan == other.plan)
+    }
+}
+
+impl Eq for PlanFingerprint {}
+
+impl Hash for PlanFingerprint {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash.hash(state);
+    }
+}
+
+impl fmt::Debug for PlanFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the plan may be large
+        f.debug_struct("PlanFingerprint")
+            .field("hash", &format_args!("{:016x}", self.hash))
+            .field("time_zone", &self.time_zone)
+            .field("batch_size", &self.batch_size)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns true if all expressions of `plan` (and its inputs) are immutable.
+fn is_deterministic(plan: &LogicalPlan) -> bool {
+    let mut deterministic = true;
+    plan.apply(&mut |plan| {
+        for expr in plan.expressions() {
+            expr.apply(&mut |expr| {
+                if let Expr::ScalarFunction(ScalarFunction { func_def, .. }) = expr {
+                    let volatility = match func_def {
+                        ScalarFunctionDefinition::BuiltIn(fun) => Some(fun.volatility()),
+                        ScalarFunctionDefinition::UDF(udf) => Some(udf.signature().volatility),
+                        ScalarFunctionDefinition::Name(_) => None,
+                    };
+                    if volatility != Some(Volatility::Immutable) {
+                        deterministic = false;
+                        return Ok(VisitRecursion::Stop);
+                    }
+                }
+                Ok(VisitRecursion::Continue)
+            })?;
+        }
+
+        Ok(if deterministic {
+            VisitRecursion::Continue
+        } else {
+            VisitRecursion::Stop
+        })
+    })
+    .expect("cannot fail");
+    deterministic
+}
+
+/// The catalog state a query result was computed from.
+///
+/// A cached result is only served for the same generations of the namespace
+/// and all the tables the query reads.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CatalogGenerations {
+    namespace: u64,
+    tables: BTreeMap<TableId, u64>,
+}
+
+impl CatalogGenerations {
+    /// Create the state for the given generation of the namespace.
+    pub fn new(namespace_generation: u64) -> Self {
+        Self {
+            namespace: namespace_generation,
+            tables: BTreeMap::new(),
+        }
+    }
+
+    /// Add the generation of a table read by the query.
+    pub fn with_table(mut self, table_id: TableId, generation: u64) -> Self {
+        self.tables.insert(table_id, generation);
+        self
+    }
+}
+
+/// Key of a [`ResultCache`] entry.
+type CacheKey = (NamespaceId, PlanFingerprint);
+
+#[derive(Debug)]
+struct CachedResult {
+    generations: CatalogGenerations,
+    batches: Vec<RecordBatch>,
+    size: usize,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<CacheKey, CachedResult>,
+
+    /// Keys in insertion order, the oldest first.
+    order: VecDeque<CacheKey>,
+
+    /// Total size of the cached results, in bytes.
+    size: usize,
+}
+
+impl State {
+    fn remove(&mut self, key: &CacheKey) -> Option<CachedResult> {
+        let removed = self.entries.remove(key)?;
+        self.order.retain(|k| k != key);
+        self.size -= removed.size;
+        Some(removed)
+    }
+}
+
+/// Cache of the final [`RecordBatch`]es of deterministic queries, keyed on
+/// their [`PlanFingerprint`] and invalidated when the [`CatalogGenerations`]
+/// they were computed from change.
+///
+/// Once the cached results exceed the configured size, the oldest ones are
+/// evicted.
+#[derive(Debug)]
+pub struct ResultCache {
+    max_bytes: usize,
+    state: Mutex<State>,
+    requests: Metric<U64Counter>,
+}
+
+impl ResultCache {
+    /// Create a cache holding up to `max_bytes` of results.
+    pub fn new(max_bytes: usize, metric_registry: &Registry) -> Self {
+        let requests = metric_registry.register_metric(
+            "query_result_cache_requests",
+            "Number of query result cache lookups, by result",
+        );
+
+        Self {
+            max_bytes,
+            state: Default::default(),
+            requests,
+        }
+    }
+
+    /// Return the cached results of the query with `fingerprint`, if they
+    /// were computed from the same `generations`.
+    ///
+    /// Results computed from other generations are removed.
+    pub fn get(
+        &self,
+        namespace_id: NamespaceId,
+        fingerprint: &PlanFingerprint,
+        generations: &CatalogGenerations,
+    ) -> Option<Vec<RecordBatch>> {
+        let key = (namespace_id, fingerprint.clone());
+        let mut state = self.state.lock();
+
+        let result = match state.entries.get(&key) {
+            Some(cached) if &cached.generations == generations => Some(cached.batches.clone()),
+            Some(_) => {
+                debug!(
+                    namespace_id = namespace_id.get(),
+                    ?fingerprint,
+                    "invalidating cached query result"
+                );
+                state.remove(&key);
+                self.record("invalidated");
+                return None;
+            }
+            None => None,
+        };
+
+        self.record(if result.is_some() { "hit" } else { "miss" });
+        result
+    }
+
+    /// Cache the results of the query with `fingerprint`, computed from
+    /// `generations`.
+    ///
+    /// Results larger than the cache are not cached.
+    pub fn insert(
+        &self,
+        namespace_id: NamespaceId,
+        fingerprint: PlanFingerprint,
+        generations: CatalogGenerations,
+        batches: Vec<RecordBatch>,
+    ) {
+        let size = batches
+            .iter()
+            .map(|batch| batch.get_array_memory_size())
+            .sum::<usize>();
+        if size > self.max_bytes {
+            return;
+        }
+
+        let key = (namespace_id, fingerprint);
+        let mut state = self.state.lock();
+        state.remove(&key);
+
+        while state.size + size > self.max_bytes {
+            let oldest = state.order.front().cloned().expect("size is accounted");
+            state.remove(&oldest);
+        }
+
+        state.entries.insert(
+            key.clone(),
+            CachedResult {
+                generations,
+                batches,
+                size,
+            },
+        );
+        state.order.push_back(key);
+        state.size += size;
+    }
+
+    /// Remove all cached results of the namespace.
+    pub fn invalidate_namespace(&self, namespace_id: NamespaceId) {
+        let mut state = self.state.lock();
+        let keys = state
+            .order
+            .iter()
+            .filter(|(ns, _)| *ns == namespace_id)
+            .cloned()
+            .collect::<Vec<_>>();
+        for key in keys {
+            state.remove(&key);
+        }
+    }
+
+    /// Total size of the cached results, in bytes.
+    pub fn size(&self) -> usize {
+        self.state.lock().size
+    }
+
+    fn record(&self, result: &'static str) {
+        self.requests.recorder(&[("result", result)]).inc(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{ArrayRef, Int64Array};
+    use datafusion::{
+        logical_expr::LogicalPlanBuilder,
+        prelude::{lit, now, random},
+    };
+    use metric::Attributes;
+
+    use super::*;
+
+    fn plan(expr: Expr) -> LogicalPlan {
+        LogicalPlanBuilder::empty(true)
+            .project([expr])
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn batch(n: usize) -> RecordBatch {
+        RecordBatch::try_from_iter([(
+            "v",
+            Arc::new(Int64Array::from_iter_values(0..n as i64)) as ArrayRef,
+        )])
+        .unwrap()
+    }
+
+    fn requests(registry: &Registry, result: &'static str) -> u64 {
+        let metric = registry
+            .get_instrument::<Metric<U64Counter>>("query_result_cache_requests")
+            .unwrap();
+        metric
+            .get_observer(&Attributes::from(&[("result", result)]))
+            .map(|counter| counter.fetch())
+            .unwrap_or_default()
+    }
+
+    fn fingerprint(expr: Expr) -> PlanFingerprint {
+        PlanFingerprint::new(&plan(expr), &ConfigOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let config = ConfigOptions::default();
+        let a = fingerprint(lit(1));
+        assert_eq!(
+            PlanFingerprint::new(&plan(lit(1)), &config),
+            Some(a.clone())
+        );
+        assert_ne!(
+            PlanFingerprint::new(&plan(lit(2)), &config),
+            Some(a.clone())
+        );
+
+        assert_eq!(PlanFingerprint::new(&plan(now()), &config), None);
+        assert_eq!(PlanFingerprint::new(&plan(random()), &config), None);
+
+        // settings that change the results are part of the fingerprint
+        let mut config = ConfigOptions::default();
+        config.execution.time_zone = Some("+01:00".to_owned());
+        assert_ne!(
+            PlanFingerprint::new(&plan(lit(1)), &config),
+            Some(a.clone())
+        );
+
+        let mut config = ConfigOptions::default();
+        config.execution.batch_size = 100;
+        assert_ne!(PlanFingerprint::new(&plan(lit(1)), &config), Some(a));
+    }
+
+    #[test]
+    fn test_fingerprint_hash_collision() {
+        let a = fingerprint(lit(1));
+        let b = PlanFingerprint {
+            hash: a.hash,
+            ..fingerprint(lit(2))
+        };
+        assert_ne!(a, b);
+
+        let registry = Registry::default();
+        let cache = ResultCache::new(usize::MAX, &registry);
+        let ns = NamespaceId::new(1);
+        let generations = CatalogGenerations::new(1);
+        cache.insert(ns, a.clone(), generations.clone(), vec![batch(1)]);
+        assert_eq!(cache.get(ns, &b, &generations), None);
+        assert_eq!(cache.get(ns, &a, &generations), Some(vec![batch(1)]));
+    }
+
+    #[test]
+    fn test_cache() {
+        let registry = Registry::default();
+        let batch_size = batch(100).get_array_memory_size();
+        let cache = ResultCache::new(2 * batch_size, &registry);
+
+        let ns = NamespaceId::new(1);
+        let table = TableId::new(1);
+        let a = fingerprint(lit(1));
+        let b = fingerprint(lit(2));
+        let c = fingerprint(lit(3));
+        let gen1 = CatalogGenerations::new(1).with_table(table, 1);
+        let gen2 = CatalogGenerations::new(1).with_table(table, 2);
+
+        assert_eq!(cache.get(ns, &a, &gen1), None);
+        assert_eq!(requests(&registry, "miss"), 1);
+
+        cache.insert(ns, a.clone(), gen1.clone(), vec![batch(100)]);
+        assert_eq!(cache.get(ns, &a, &gen1), Some(vec![batch(100)]));
+        assert_eq!(cache.get(NamespaceId::new(2), &a, &gen1), None);
+        assert_eq!(requests(&registry, "hit"), 1);
+        assert_eq!(requests(&registry, "miss"), 2);
+
+        // a newer table generation invalidates the result
+        assert_eq!(cache.get(ns, &a, &gen2), None);
+        assert_eq!(cache.get(ns, &a, &gen1), None);
+        assert_eq!(requests(&registry, "invalidated"), 1);
+        assert_eq!(cache.size(), 0);
+
+        // the oldest results are evicted once the cache is full
+        cache.insert(ns, a.clone(), gen2.clone(), vec![batch(100)]);
+        cache.insert(ns, b.clone(), gen2.clone(), vec![batch(100)]);
+        cache.insert(ns, c.clone(), gen2.clone(), vec![batch(100)]);
+        assert_eq!(cache.size(), 2 * batch_size);
+        assert_eq!(cache.get(ns, &a, &gen2), None);
+        assert!(cache.get(ns, &b, &gen2).is_some());
+        assert!(cache.get(ns, &c, &gen2).is_some());
+
+        // results larger than the cache are not cached
+        cache.insert(ns, a.clone(), gen2.clone(), vec![batch(1_000)]);
+        assert_eq!(cache.get(ns, &a, &gen2), None);
+
+        cache.invalidate_namespace(ns);
+        assert_eq!(cache.size(), 0);
+        assert_eq!(cache.get(ns, &b, &gen2), None);
+    }
+}
@@ -0,0 +1,108 @@
+//! Per-query settings changed by `SET` statements.
+//!
+//! A SQL query may be preceded by `SET` statements that change some settings for this query only, e.g.
+//!
+//! ```sql
+//! SET datafusion.execution.time_zone = '+01:00';
+//! SET iox.query_memory_limit = 104857600;
+//! SELECT ...
+//! ```
+//!
+//! Only the [settings listed here](SETTABLE) may be changed. The settings are recorded in the query log, so that the
+//! query can be reproduced.
+use std::fmt;
+
+use parking_lot::Mutex;
+
+/// Number of rows per record batch.
+///
+/// This may only lower the batch size that applies to the query otherwise, so that a query cannot allocate
+/// arbitrarily large batches.
+pub const BATCH_SIZE: &str = "datafusion.execution.batch_size";
+
+/// Limit on the bytes of memory the query may reserve.
+///
+/// This may only lower the limit that applies to the query otherwise, see
+/// [`IOxSessionConfig::with_query_memory_limit`](crate::exec::IOxSessionConfig::with_query_memory_limit).
+pub const QUERY_MEMORY_LIMIT: &str = "iox.query_memory_limit";
+
+/// Settings that may be changed for a single query.
+pub const SETTABLE: &[&str] = &[
+    BATCH_SIZE,
+    "datafusion.execution.time_zone",
+    QUERY_MEMORY_LIMIT,
+];
+
+/// Settings changed for a query.
+///
+/// Shared by all contexts of a query, see
+/// [`IOxSessionContext::session_settings`](crate::exec::IOxSessionContext::session_settings).
+#[derive(Debug, Default)]
+pub struct SessionSettings {
+    settings: Mutex<Vec<(String, String)>>,
+}
+
+impl SessionSettings {
+    /// Record that `variable` was set to `value`, replacing a previous value.
+    pub(crate) fn set(&self, variable: &str, value: &str) {
+        let mut settings = self.settings.lock();
+        match settings.iter_mut().find(|(v, _)| v == variable) {
+            Some((_, old)) => *old = value.to_owned(),
+            None => settings.push((variable.to_owned(), value.to_owned())),
+        }
+    }
+
+    /// Settings changed so far as `(variable, value)` pairs, in the order they were first set.
+    pub fn get(&self) -> Vec<(String, String)> {
+        self.settings.lock().clone()
+    }
+}
+
+/// Formats settings as the `SET` statements that reproduce them.
+#[derive(Debug)]
+pub struct DisplaySettings<'a>(pub &'a [(String, String)]);
+
+impl<'a> fmt::Display for DisplaySettings<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (variable, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "SET {variable} = '{}';", value.replace('\'', "''"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let settings = SessionSettings::default();
+        assert_eq!(settings.get(), vec![]);
+
+        settings.set("a", "1");
+        settings.set("b", "2");
+        settings.set("a", "3");
+        let settings = settings.get();
+        assert_eq!(
+            settings,
+            vec![
+                ("a".to_owned(), "3".to_owned()),
+                ("b".to_owned(), "2".to_owned())
+            ]
+        );
+        assert_eq!(
+            DisplaySettings(&settings).to_string(),
+            "SET a = '3'; SET b = '2';"
+        );
+    }
+
+    #[test]
+    fn test_display_quotes() {
+        let settings = [("a".to_owned(), "it's".to_owned())];
+        assert_eq!(DisplaySettings(&settings).to_string(), "SET a = 'it''s';");
+    }
+}
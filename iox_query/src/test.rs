@@ -50,9 +50,13 @@ use std::{
     fmt,
     num::NonZeroU64,
     sync::Arc,
+    time::Duration,
 };
 use trace::{ctx::SpanContext, span::Span};
-use tracker::{AsyncSemaphoreMetrics, InstrumentedAsyncOwnedSemaphorePermit};
+use tracker::{
+    AsyncSemaphoreMetrics, InstrumentedAsyncOwnedSemaphorePermit, PriorityAdmissionQueue,
+    WorkloadClass,
+};
 
 #[derive(Debug)]
 pub struct TestDatabaseStore {
@@ -60,6 +64,7 @@ pub struct TestDatabaseStore {
     executor: Arc<Executor>,
     pub metric_registry: Arc<metric::Registry>,
     pub query_semaphore: Arc<tracker::InstrumentedAsyncSemaphore>,
+    pub query_queue: Arc<PriorityAdmissionQueue>,
 }
 
 impl TestDatabaseStore {
@@ -73,11 +78,19 @@ impl TestDatabaseStore {
             &metric_registry,
             &[("semaphore", "query_execution")],
         ));
+        let query_semaphore = Arc::new(semaphore_metrics.new_semaphore(semaphore_size));
+        let query_queue = Arc::new(PriorityAdmissionQueue::new(
+            &metric_registry,
+            &[("semaphore", "query_execution")],
+            Arc::clone(&query_semaphore),
+            Duration::from_secs(10),
+        ));
         Self {
             databases: Mutex::new(BTreeMap::new()),
             executor: Arc::new(Executor::new_testing()),
             metric_registry,
-            query_semaphore: Arc::new(semaphore_metrics.new_semaphore(semaphore_size)),
+            query_semaphore,
+            query_queue,
         }
     }
 
@@ -114,9 +127,14 @@ impl QueryNamespaceProvider for TestDatabaseStore {
         databases.get(name).cloned().map(|ns| ns as _)
     }
 
-    async fn acquire_semaphore(&self, span: Option<Span>) -> InstrumentedAsyncOwnedSemaphorePermit {
-        Arc::clone(&self.query_semaphore)
-            .acquire_owned(span)
+    async fn acquire_semaphore(
+        &self,
+        namespace_name: &str,
+        span: Option<Span>,
+        workload_class: WorkloadClass,
+    ) -> InstrumentedAsyncOwnedSemaphorePermit {
+        self.query_queue
+            .acquire_owned_for_namespace(workload_class, namespace_name, span)
             .await
             .unwrap()
     }
@@ -201,30 +219,37 @@ impl QueryNamespace for TestDatabase {
         table_name: &str,
         filters: &[Expr],
         _projection: Option<&Vec<usize>>,
-        _ctx: IOxSessionContext,
+        ctx: IOxSessionContext,
     ) -> Result<Vec<Arc<dyn QueryChunk>>, DataFusionError> {
         // save last predicate
         *self.chunks_predicate.lock() = filters.to_vec();
 
         let partitions = self.partitions.lock().clone();
-        Ok(partitions
+        let chunks = partitions
             .values()
             .flat_map(|x| x.values())
             // filter by table
             .filter(|c| c.table_name == table_name)
-            // only keep chunks if their statistics overlap
-            .filter(|c| {
-                prune_chunks(
-                    c.schema(),
-                    &[Arc::clone(*c) as Arc<dyn QueryChunk>],
-                    filters,
-                )
-                .ok()
-                .map(|res| res[0])
-                .unwrap_or(true)
-            })
             .map(|x| Arc::clone(x) as Arc<dyn QueryChunk>)
-            .collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        // only keep chunks if their statistics overlap
+        let keep = chunks
+            .iter()
+            .map(|c| {
+                prune_chunks(c.schema(), &[Arc::clone(c)], filters)
+                    .ok()
+                    .map(|res| res[0])
+                    .unwrap_or(true)
+            })
+            .collect::<Vec<_>>();
+        ctx.pruning_stats().record_pruned_chunks(&chunks, &keep);
+
+        Ok(chunks
+            .into_iter()
+            .zip(keep)
+            .filter_map(|(c, keep)| keep.then_some(c))
+            .collect())
     }
 
     fn retention_time_ns(&self) -> Option<i64> {
@@ -236,6 +261,7 @@ impl QueryNamespace for TestDatabase {
         span_ctx: Option<&SpanContext>,
         query_type: &'static str,
         query_text: QueryText,
+        workload_class: WorkloadClass,
     ) -> QueryCompletedToken<StateReceived> {
         QueryLog::new(0, Arc::new(SystemProvider::new())).push(
             NamespaceId::new(1),
@@ -243,6 +269,7 @@ impl QueryNamespace for TestDatabase {
             query_type,
             query_text,
             span_ctx.map(|s| s.trace_id),
+            workload_class,
         )
     }
 
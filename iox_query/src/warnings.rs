@@ -0,0 +1,135 @@
+//! Warnings that are raised while planning a query.
+//!
+//! Unlike errors, warnings do not fail the query. They point out parts of the query that were accepted but probably
+//! do not do what the user intended, e.g. columns that do not exist and are therefore treated as `NULL`. Warnings are
+//! recorded in the query log and returned to the client together with the results.
+use std::fmt;
+
+use parking_lot::Mutex;
+
+/// Maximum number of warnings kept per query.
+///
+/// Further warnings are dropped, so that a query that references many missing columns cannot blow up the query log
+/// or the response metadata.
+const MAX_WARNINGS: usize = 100;
+
+/// Kind of a [`QueryWarning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryWarningKind {
+    /// The query uses syntax that is still accepted but deprecated.
+    DeprecatedSyntax,
+
+    /// The query references a column that does not exist, which is treated as `NULL`.
+    MissingColumn,
+
+    /// A value was implicitly cast to another type.
+    ImplicitCast,
+}
+
+impl QueryWarningKind {
+    /// Name of the kind, as returned to clients.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DeprecatedSyntax => "deprecated_syntax",
+            Self::MissingColumn => "missing_column",
+            Self::ImplicitCast => "implicit_cast",
+        }
+    }
+}
+
+impl fmt::Display for QueryWarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A warning raised while planning a query.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryWarning {
+    /// Kind of the warning.
+    pub kind: QueryWarningKind,
+
+    /// Human-readable description.
+    pub message: String,
+}
+
+impl QueryWarning {
+    pub fn new(kind: QueryWarningKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for QueryWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+/// Warnings raised while planning a query.
+///
+/// Shared by all contexts of a query, see
+/// [`IOxSessionContext::warnings`](crate::exec::IOxSessionContext::warnings).
+#[derive(Debug, Default)]
+pub struct QueryWarnings {
+    warnings: Mutex<Vec<QueryWarning>>,
+}
+
+impl QueryWarnings {
+    /// Record a warning.
+    ///
+    /// Duplicates of already recorded warnings are ignored.
+    pub fn push(&self, warning: QueryWarning) {
+        let mut warnings = self.warnings.lock();
+        if warnings.len() < MAX_WARNINGS && !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+
+    /// Warnings recorded so far, in the order they were raised.
+    pub fn get(&self) -> Vec<QueryWarning> {
+        self.warnings.lock().clone()
+    }
+
+    /// Returns true if no warnings were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.warnings.lock().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dedup() {
+        let warnings = QueryWarnings::default();
+        assert!(warnings.is_empty());
+
+        warnings.push(QueryWarning::new(QueryWarningKind::MissingColumn, "a"));
+        warnings.push(QueryWarning::new(QueryWarningKind::ImplicitCast, "a"));
+        warnings.push(QueryWarning::new(QueryWarningKind::MissingColumn, "a"));
+
+        assert_eq!(
+            warnings.get(),
+            vec![
+                QueryWarning::new(QueryWarningKind::MissingColumn, "a"),
+                QueryWarning::new(QueryWarningKind::ImplicitCast, "a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_limit() {
+        let warnings = QueryWarnings::default();
+        for i in 0..(MAX_WARNINGS + 10) {
+            warnings.push(QueryWarning::new(
+                QueryWarningKind::MissingColumn,
+                i.to_string(),
+            ));
+        }
+        assert_eq!(warnings.get().len(), MAX_WARNINGS);
+    }
+}
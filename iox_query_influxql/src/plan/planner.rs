@@ -80,6 +80,7 @@ use iox_query::config::{IoxConfigExt, MetadataCutoff};
 use iox_query::exec::gapfill::{FillStrategy, GapFill, GapFillParams};
 use iox_query::exec::IOxSessionContext;
 use iox_query::logical_optimizer::range_predicate::find_time_range;
+use iox_query::warnings::{QueryWarning, QueryWarningKind};
 use itertools::Itertools;
 use observability_deps::tracing::debug;
 use query_functions::{
@@ -519,6 +520,10 @@ impl<'a> InfluxQLToLogicalPlan<'a> {
     }
 
     fn rewrite_select_statement(&self, select: SelectStatement) -> Result<SelectQuery> {
+        // the rewriter splits the time range from the condition, so check the original one
+        if let Some(where_clause) = &select.condition {
+            self.warn_deprecated_time_refs(where_clause);
+        }
         rewrite_statement(self.s, &select)
     }
 
@@ -1893,7 +1898,13 @@ impl<'a> InfluxQLToLogicalPlan<'a> {
                                 None => column,
                             }
                         }
-                        _ => Expr::Literal(ScalarValue::Null),
+                        _ => {
+                            self.iox_ctx.warnings().push(QueryWarning::new(
+                                QueryWarningKind::MissingColumn,
+                                format!("column '{name}' does not exist, treated as NULL"),
+                            ));
+                            Expr::Literal(ScalarValue::Null)
+                        }
                     },
                 })
             }
@@ -2247,6 +2258,30 @@ impl<'a> InfluxQLToLogicalPlan<'a> {
         .build()
     }
 
+    /// Record a warning for every reference to the time column of `where_clause` that does not use the lowercase
+    /// name.
+    ///
+    /// These are still accepted for compatibility with InfluxDB 1.x, see [`expr_to_df_expr`](Self::expr_to_df_expr).
+    fn warn_deprecated_time_refs(&self, where_clause: &WhereClause) {
+        walk_expression(where_clause, &mut |e| match e {
+            Expression::Arithmetic(e) => walk_expr(e, &mut |e| {
+                if let IQLExpr::VarRef(VarRef { name, .. }) = e {
+                    if name.eq_ignore_ascii_case("time") && name.as_str() != "time" {
+                        self.iox_ctx.warnings().push(QueryWarning::new(
+                            QueryWarningKind::DeprecatedSyntax,
+                            format!(
+                                "time column referenced as '{}', use 'time' instead",
+                                name.as_str()
+                            ),
+                        ));
+                    }
+                }
+                ControlFlow::<()>::Continue(())
+            }),
+            _ => ControlFlow::<()>::Continue(()),
+        });
+    }
+
     /// Generate a logical plan that filters the existing plan based on the
     /// InfluxQL [`WhereClause`] of a `SHOW` statement.
     fn plan_where_clause(
@@ -2258,6 +2293,10 @@ impl<'a> InfluxQLToLogicalPlan<'a> {
     ) -> Result<LogicalPlan> {
         let start_time = Timestamp::from(self.s.execution_props().query_execution_start_time);
 
+        if let Some(where_clause) = condition {
+            self.warn_deprecated_time_refs(where_clause);
+        }
+
         let (cond, time_range) = condition
             .as_ref()
             .map(|where_clause| {
@@ -3407,6 +3446,10 @@ mod test {
     use schema::SchemaBuilder;
 
     fn logical_plan(sql: &str) -> Result<LogicalPlan> {
+        logical_plan_with_ctx(sql, &IOxSessionContext::with_testing())
+    }
+
+    fn logical_plan_with_ctx(sql: &str, iox_ctx: &IOxSessionContext) -> Result<LogicalPlan> {
         let mut statements = parse_statements(sql).unwrap();
         let mut sp = MockSchemaProvider::default();
         sp.add_schemas(vec![
@@ -3448,8 +3491,7 @@ mod test {
                 .unwrap(),
         ]);
 
-        let iox_ctx = IOxSessionContext::with_testing();
-        let planner = InfluxQLToLogicalPlan::new(&sp, &iox_ctx);
+        let planner = InfluxQLToLogicalPlan::new(&sp, iox_ctx);
 
         planner.statement_to_plan(statements.pop().unwrap())
     }
@@ -3463,6 +3505,17 @@ mod test {
             .map(|s| serde_json::from_str(s).unwrap())
     }
 
+    fn warnings(sql: &str) -> Vec<String> {
+        let iox_ctx = IOxSessionContext::with_testing();
+        logical_plan_with_ctx(sql, &iox_ctx).unwrap();
+        iox_ctx
+            .warnings()
+            .get()
+            .into_iter()
+            .map(|w| w.to_string())
+            .collect()
+    }
+
     fn plan(sql: impl Into<String>) -> String {
         let result = logical_plan(&sql.into());
         match result {
@@ -3538,6 +3591,21 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_warnings() {
+        assert_eq!(
+            warnings("SELECT f64_field FROM data WHERE foo = 'a' AND time > 0"),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            warnings("SELECT f64_field FROM data WHERE non_existent = 'a' AND TIME > 0"),
+            vec![
+                "deprecated_syntax: time column referenced as 'TIME', use 'time' instead",
+                "missing_column: column 'non_existent' does not exist, treated as NULL",
+            ]
+        );
+    }
+
     /// Verify the list of unsupported statements.
     ///
     /// It is expected certain statements will be unsupported, indefinitely.
@@ -88,6 +88,8 @@ impl TestCatalog {
         let exec = Arc::new(Executor::new_with_config_and_executors(
             ExecutorConfig {
                 num_threads: exec.num_threads(),
+                cpu_set: None,
+                runtime_settings: Default::default(),
                 target_query_partitions,
                 object_stores: HashMap::from([(
                     parquet_store.id(),
@@ -95,6 +97,10 @@ impl TestCatalog {
                 )]),
                 metric_registry: Arc::clone(&metric_registry),
                 mem_pool_size: 1024 * 1024 * 1024,
+                per_query_mem_pool_size: None,
+                spill_dirs: vec![],
+                spill_object_store: None,
+                namespace_udfs: None,
             },
             exec,
         ));
@@ -602,6 +608,7 @@ impl TestPartition {
             compaction_level,
             column_set,
             max_l0_created_at: Timestamp::new(max_l0_created_at),
+            column_stats: Default::default(),
         };
 
         let mut repos = self.catalog.catalog.repositories();
@@ -0,0 +1,345 @@
+//! Health of the individual components (subsystems) of a server.
+//!
+//! Components (e.g. catalog connectivity, WAL replay) register themselves with a [`HealthRegistry`] and update their
+//! [`ComponentState`]. The aggregate readiness of all required components is reported as the overall serving status
+//! of the standard gRPC health service and via `/health`, the per-component details are served by the
+//! `influxdata.iox.health.v1.ComponentHealthService`.
+use std::{collections::BTreeMap, sync::Arc, time::Instant};
+
+use generated_types::influxdata::iox::health::v1 as proto;
+use observability_deps::tracing::{info, warn};
+use parking_lot::Mutex;
+use tokio::{sync::watch, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+use tonic_health::{server::HealthReporter, ServingStatus};
+
+/// Component name for catalog connectivity.
+pub const COMPONENT_CATALOG: &str = "catalog";
+
+/// Component name for object store reachability.
+pub const COMPONENT_OBJECT_STORE: &str = "object_store";
+
+/// Component name for cache warmup.
+pub const COMPONENT_CACHE_WARMUP: &str = "cache_warmup";
+
+/// Component name for WAL replay.
+pub const COMPONENT_WAL_REPLAY: &str = "wal_replay";
+
+/// State of a single component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentState {
+    /// The component is initializing.
+    Starting,
+
+    /// The component is fully functional.
+    Ready,
+
+    /// The component works, but with reduced functionality or performance.
+    Degraded,
+
+    /// The component does not work.
+    Failed,
+}
+
+impl ComponentState {
+    /// Returns `true` if a required component in this state allows the server to serve requests.
+    pub fn is_ready(&self) -> bool {
+        match self {
+            Self::Ready | Self::Degraded => true,
+            Self::Starting | Self::Failed => false,
+        }
+    }
+}
+
+impl From<ComponentState> for proto::ComponentState {
+    fn from(state: ComponentState) -> Self {
+        match state {
+            ComponentState::Starting => Self::Starting,
+            ComponentState::Ready => Self::Ready,
+            ComponentState::Degraded => Self::Degraded,
+            ComponentState::Failed => Self::Failed,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Component {
+    state: ComponentState,
+    message: Option<String>,
+    required: bool,
+    since: Instant,
+}
+
+#[derive(Debug)]
+struct Inner {
+    components: Mutex<BTreeMap<String, Component>>,
+    ready_tx: watch::Sender<bool>,
+}
+
+impl Inner {
+    /// Recompute the aggregate readiness, must be called after every change.
+    fn update_ready(&self, components: &BTreeMap<String, Component>) {
+        let ready = components
+            .values()
+            .all(|c| !c.required || c.state.is_ready());
+        self.ready_tx.send_if_modified(|current| {
+            let modified = *current != ready;
+            *current = ready;
+            modified
+        });
+    }
+}
+
+/// Registry of component states, cheap to clone.
+#[derive(Debug, Clone)]
+pub struct HealthRegistry {
+    inner: Arc<Inner>,
+}
+
+impl Default for HealthRegistry {
+    fn default() -> Self {
+        // no components registered => ready
+        let (ready_tx, _ready_rx) = watch::channel(true);
+
+        Self {
+            inner: Arc::new(Inner {
+                components: Default::default(),
+                ready_tx,
+            }),
+        }
+    }
+}
+
+impl HealthRegistry {
+    /// Register a new component, starting in [`ComponentState::Starting`].
+    ///
+    /// If `required` is set, the server is only ready when this component is ready (or degraded).
+    ///
+    /// # Panics
+    /// Panics if a component with the same name is already registered.
+    pub fn register(&self, name: impl Into<String>, required: bool) -> ComponentHealth {
+        let name = name.into();
+
+        let mut components = self.inner.components.lock();
+        let existing = components.insert(
+            name.clone(),
+            Component {
+                state: ComponentState::Starting,
+                message: None,
+                required,
+                since: Instant::now(),
+            },
+        );
+        assert!(existing.is_none(), "component '{name}' registered twice");
+        self.inner.update_ready(&components);
+
+        ComponentHealth {
+            name,
+            inner: Arc::clone(&self.inner),
+        }
+    }
+
+    /// Returns `true` if all required components are ready (or degraded).
+    pub fn is_ready(&self) -> bool {
+        *self.inner.ready_tx.borrow()
+    }
+
+    /// Subscribe to changes of the aggregate readiness.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.inner.ready_tx.subscribe()
+    }
+
+    /// Get the detailed state of all components, ordered by name.
+    pub fn snapshot(&self) -> proto::GetComponentHealthResponse {
+        let components = self.inner.components.lock();
+
+        proto::GetComponentHealthResponse {
+            ready: self.is_ready(),
+            components: components
+                .iter()
+                .map(|(name, c)| proto::ComponentHealth {
+                    name: name.clone(),
+                    state: proto::ComponentState::from(c.state).into(),
+                    message: c.message.clone(),
+                    required: c.required,
+                    state_age_ns: c.since.elapsed().as_nanos() as u64,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Handle to update the state of a single component, see [`HealthRegistry::register`].
+#[derive(Debug)]
+pub struct ComponentHealth {
+    name: String,
+    inner: Arc<Inner>,
+}
+
+impl ComponentHealth {
+    /// Component name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Set the state of this component, clearing any message.
+    pub fn set_state(&self, state: ComponentState) {
+        self.update(state, None)
+    }
+
+    /// Set the state of this component with a message explaining it, e.g. an error.
+    pub fn set_state_with_message(&self, state: ComponentState, message: impl Into<String>) {
+        self.update(state, Some(message.into()))
+    }
+
+    fn update(&self, state: ComponentState, message: Option<String>) {
+        let mut components = self.inner.components.lock();
+        let component = components
+            .get_mut(&self.name)
+            .expect("components are never removed");
+
+        if component.state != state {
+            match state {
+                ComponentState::Starting | ComponentState::Ready => {
+                    info!(component=%self.name, ?state, ?message, "component state changed")
+                }
+                ComponentState::Degraded | ComponentState::Failed => {
+                    warn!(component=%self.name, ?state, ?message, "component state changed")
+                }
+            }
+            component.since = Instant::now();
+        }
+        component.state = state;
+        component.message = message;
+
+        self.inner.update_ready(&components);
+    }
+}
+
+/// Mirror the aggregate readiness of `registry` into the overall serving status (empty service name) of the standard
+/// gRPC health service until `shutdown` is triggered.
+pub fn spawn_health_reporter(
+    registry: HealthRegistry,
+    mut reporter: HealthReporter,
+    shutdown: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ready_rx = registry.subscribe();
+
+        loop {
+            let ready = *ready_rx.borrow_and_update();
+            let status = if ready {
+                ServingStatus::Serving
+            } else {
+                ServingStatus::NotServing
+            };
+            reporter.set_service_status("", status).await;
+
+            tokio::select! {
+                _ = shutdown.cancelled() => return,
+                res = ready_rx.changed() => {
+                    if res.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Serves the component details of a [`HealthRegistry`].
+#[derive(Debug)]
+struct ComponentHealthService {
+    registry: HealthRegistry,
+}
+
+#[tonic::async_trait]
+impl proto::component_health_service_server::ComponentHealthService for ComponentHealthService {
+    async fn get_component_health(
+        &self,
+        _request: tonic::Request<proto::GetComponentHealthRequest>,
+    ) -> Result<tonic::Response<proto::GetComponentHealthResponse>, tonic::Status> {
+        Ok(tonic::Response::new(self.registry.snapshot()))
+    }
+}
+
+/// Create the gRPC service that serves the component details of `registry`.
+pub fn make_server(
+    registry: HealthRegistry,
+) -> proto::component_health_service_server::ComponentHealthServiceServer<
+    impl proto::component_health_service_server::ComponentHealthService,
+> {
+    proto::component_health_service_server::ComponentHealthServiceServer::new(
+        ComponentHealthService { registry },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_readiness() {
+        let registry = HealthRegistry::default();
+        assert!(registry.is_ready());
+
+        let catalog = registry.register(COMPONENT_CATALOG, true);
+        let cache = registry.register(COMPONENT_CACHE_WARMUP, false);
+        assert!(!registry.is_ready());
+
+        // optional components don't matter
+        cache.set_state(ComponentState::Failed);
+        assert!(!registry.is_ready());
+
+        catalog.set_state(ComponentState::Ready);
+        assert!(registry.is_ready());
+
+        catalog.set_state_with_message(ComponentState::Degraded, "slow");
+        assert!(registry.is_ready());
+
+        catalog.set_state_with_message(ComponentState::Failed, "connection refused");
+        assert!(!registry.is_ready());
+
+        let snapshot = registry.snapshot();
+        assert!(!snapshot.ready);
+        let states = snapshot
+            .components
+            .iter()
+            .map(|c| (c.name.as_str(), c.state(), c.message.as_deref(), c.required))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            states,
+            vec![
+                ("cache_warmup", proto::ComponentState::Failed, None, false),
+                (
+                    "catalog",
+                    proto::ComponentState::Failed,
+                    Some("connection refused"),
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "component 'catalog' registered twice")]
+    fn test_register_twice() {
+        let registry = HealthRegistry::default();
+        let _a = registry.register(COMPONENT_CATALOG, true);
+        let _b = registry.register(COMPONENT_CATALOG, true);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe() {
+        let registry = HealthRegistry::default();
+        let mut rx = registry.subscribe();
+
+        let wal = registry.register(COMPONENT_WAL_REPLAY, true);
+        rx.changed().await.unwrap();
+        assert!(!*rx.borrow_and_update());
+
+        wal.set_state(ComponentState::Ready);
+        rx.changed().await.unwrap();
+        assert!(*rx.borrow_and_update());
+    }
+}
@@ -7,7 +7,7 @@ use hyper::{
     server::conn::{AddrIncoming, AddrStream},
     Body, Method, Request, Response,
 };
-use observability_deps::tracing::{debug, error};
+use observability_deps::tracing::{debug, error, info};
 use serde::Deserialize;
 use snafu::Snafu;
 use tokio_util::sync::CancellationToken;
@@ -70,6 +70,14 @@ pub enum ApplicationError {
 
     #[snafu(display("Route error from run mode: {}", e))]
     RunModeRouteError { e: Box<dyn HttpApiErrorSource> },
+
+    #[snafu(display("Server has no reloadable config"))]
+    NotReloadable,
+
+    #[snafu(display("Reload error: {}", source))]
+    Reload {
+        source: clap_blocks::reloadable::ReloadError,
+    },
 }
 
 impl HttpApiErrorSource for ApplicationError {
@@ -85,6 +93,8 @@ impl HttpApiErrorSource for ApplicationError {
             #[cfg(feature = "heappy")]
             e @ Self::HeappyError { .. } => e.internal_error(),
             Self::RunModeRouteError { e } => e.to_http_api_error(),
+            e @ Self::NotReloadable => e.not_found(),
+            e @ Self::Reload { .. } => e.invalid(),
         }
     }
 }
@@ -139,6 +149,7 @@ async fn route_request(
         (Method::GET, "/debug/pprof") => pprof_home(req).await,
         (Method::GET, "/debug/pprof/profile") => pprof_profile(req).await,
         (Method::GET, "/debug/pprof/allocs") => pprof_heappy_profile(req).await,
+        (Method::POST, "/debug/reload") => reload(server_type.as_ref()),
         _ => server_type
             .route_http_request(req)
             .await
@@ -164,7 +175,11 @@ async fn route_request(
 }
 
 fn health(server_type: &dyn ServerType) -> Response<Body> {
-    match server_type.is_healthy() {
+    let ready = server_type
+        .health_registry()
+        .map_or(true, |registry| registry.is_ready());
+
+    match server_type.is_healthy() && ready {
         true => {
             let response_body = "OK";
             Response::new(Body::from(response_body.to_string()))
@@ -185,6 +200,19 @@ fn handle_metrics(server_type: &dyn ServerType) -> Result<Response<Body>, Applic
     Ok(Response::new(Body::from(body)))
 }
 
+fn reload(server_type: &dyn ServerType) -> Result<Response<Body>, ApplicationError> {
+    use snafu::ResultExt;
+
+    let config = server_type
+        .reloadable_config()
+        .ok_or(ApplicationError::NotReloadable)?;
+    let changed = config.reload().context(ReloadSnafu)?;
+    info!(?server_type, changed, "reloaded config");
+
+    let response_body = if changed { "changed" } else { "unchanged" };
+    Ok(Response::new(Body::from(response_body)))
+}
+
 async fn pprof_home(req: Request<Body>) -> Result<Response<Body>, ApplicationError> {
     let default_host = HeaderValue::from_static("localhost");
     let host = req
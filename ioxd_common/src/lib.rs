@@ -14,6 +14,7 @@
 // Workaround for "unused crate" lint false positives.
 use workspace_hack as _;
 
+pub mod health;
 pub mod http;
 pub mod rpc;
 pub mod server_type;
@@ -100,6 +101,34 @@ pub async fn wait_for_signal() {
     let _ = tokio::signal::ctrl_c().await;
 }
 
+/// On unix platforms SIGHUP reloads the [reloadable config](ServerType::reloadable_config)
+/// This method never returns
+#[cfg(unix)]
+async fn reload_on_signal(server_type: Arc<dyn ServerType>) {
+    use observability_deps::tracing::warn;
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut hup = signal(SignalKind::hangup()).expect("failed to register signal handler");
+
+    while hup.recv().await.is_some() {
+        info!(?server_type, "Received SIGHUP");
+        let Some(config) = server_type.reloadable_config() else {
+            warn!(?server_type, "server has no reloadable config");
+            continue;
+        };
+        match config.reload() {
+            Ok(changed) => info!(?server_type, changed, "reloaded config"),
+            Err(error) => error!(%error, ?server_type, "failed to reload config"),
+        }
+    }
+    futures::future::pending().await
+}
+
+#[cfg(windows)]
+/// There is no equivalent of SIGHUP, the config can only be reloaded via HTTP
+async fn reload_on_signal(_server_type: Arc<dyn ServerType>) {
+    futures::future::pending().await
+}
+
 pub async fn grpc_listener(addr: SocketAddr) -> Result<tokio::net::TcpListener> {
     let listener = tokio::net::TcpListener::bind(addr)
         .await
@@ -147,6 +176,8 @@ pub async fn serve(
                 .traces_jaeger_debug_name,
         );
 
+    let grpc_response_compression = common_state.run_config().grpc_response_compression();
+
     // Construct and start up gRPC server
     let captured_server_type = Arc::clone(&server_type);
     let captured_shutdown = frontend_shutdown.clone();
@@ -158,6 +189,7 @@ pub async fn serve(
                 grpc_listener,
                 captured_server_type,
                 captured_trace_header_parser,
+                &grpc_response_compression,
                 captured_shutdown,
             )
             .await?
@@ -195,6 +227,9 @@ pub async fn serve(
     // Get IOx background worker join handle
     let server_handle = Arc::clone(&server_type).join().fuse();
 
+    // Reload the config on SIGHUP until the server shuts down
+    let reload_handle = tokio::spawn(reload_on_signal(Arc::clone(&server_type)));
+
     // Shutdown signal
     let signal = wait_for_signal().fuse();
 
@@ -284,6 +319,7 @@ pub async fn serve(
         server_type.shutdown(frontend_shutdown.clone())
     }
     info!(?server_type, "frontend shutdown completed");
+    reload_handle.abort();
 
     if !server_handle.is_terminated() {
         server_handle.await;
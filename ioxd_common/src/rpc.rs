@@ -1,9 +1,10 @@
 use std::any::Any;
 use std::sync::Arc;
 
+use clap_blocks::run_config::GrpcCompression;
 use tokio::net::TcpListener;
 use tokio_util::sync::CancellationToken;
-use tonic::{body::BoxBody, transport::NamedService, Code};
+use tonic::{body::BoxBody, codec::CompressionEncoding, transport::NamedService, Code};
 use tonic_health::server::HealthReporter;
 use trace_http::ctx::TraceHeaderParser;
 
@@ -14,10 +15,20 @@ pub fn service_name<S: NamedService>(_: &S) -> &'static str {
     S::NAME
 }
 
+/// Returns the tonic encoding for the given compression algorithm, or
+/// `None` if responses should not be compressed.
+pub fn compression_encoding(compression: GrpcCompression) -> Option<CompressionEncoding> {
+    match compression {
+        GrpcCompression::None => None,
+        GrpcCompression::Gzip => Some(CompressionEncoding::Gzip),
+    }
+}
+
 #[derive(Debug)]
 pub struct RpcBuilderInput {
     pub socket: TcpListener,
     pub trace_header_parser: TraceHeaderParser,
+    pub compression: Vec<CompressionEncoding>,
     pub shutdown: CancellationToken,
 }
 
@@ -25,6 +36,7 @@ pub struct RpcBuilderInput {
 pub struct RpcBuilder<T> {
     pub inner: T,
     pub health_reporter: HealthReporter,
+    pub compression: Vec<CompressionEncoding>,
     pub shutdown: CancellationToken,
     pub socket: TcpListener,
 }
@@ -48,6 +60,7 @@ macro_rules! add_service {
                 let RpcBuilder {
                     mut inner,
                     mut health_reporter,
+                    compression,
                     shutdown,
                     socket,
                 } = $builder;
@@ -63,6 +76,7 @@ macro_rules! add_service {
                 RpcBuilder {
                     inner,
                     health_reporter,
+                    compression,
                     shutdown,
                     socket,
                 }
@@ -71,6 +85,27 @@ macro_rules! add_service {
     };
 }
 
+/// Adds a gRPC service to the builder like [`add_service!`], but
+/// enables response compression using the encodings that were
+/// configured for the server.
+///
+/// The encoding is negotiated per call using the `grpc-accept-encoding`
+/// header of the request. This should be used for services that send
+/// large responses, e.g. Flight or reflection.
+#[macro_export]
+macro_rules! add_compressed_service {
+    ($builder:ident, $svc:expr) => {
+        $crate::add_compressed_service!($builder, $svc, Serving)
+    };
+    ($builder:ident, $svc:expr, $status:ident) => {
+        let compressed_service = $builder
+            .compression
+            .iter()
+            .fold($svc, |service, encoding| service.send_compressed(*encoding));
+        $crate::add_service!($builder, compressed_service, $status);
+    };
+}
+
 /// Creates a [`RpcBuilder`] from [`RpcBuilderInput`].
 ///
 /// The resulting builder can be used w/ [`add_service`]. After adding all services it should
@@ -84,11 +119,18 @@ macro_rules! setup_builder {
         let RpcBuilderInput {
             socket,
             trace_header_parser,
+            compression,
             shutdown,
         } = $input;
 
         let (health_reporter, health_service) =
             $crate::reexport::tonic_health::server::health_reporter();
+        let health_registry = $server_type.health_registry().unwrap_or_default();
+        $crate::health::spawn_health_reporter(
+            health_registry.clone(),
+            health_reporter.clone(),
+            shutdown.clone(),
+        );
         let reflection_service = $crate::reexport::tonic_reflection::server::Builder::configure()
             .register_encoded_file_descriptor_set(
                 $crate::reexport::generated_types::FILE_DESCRIPTOR_SET,
@@ -117,12 +159,14 @@ macro_rules! setup_builder {
         let builder = RpcBuilder {
             inner: builder,
             health_reporter,
+            compression,
             shutdown,
             socket,
         };
 
         add_service!(builder, health_service);
-        add_service!(builder, reflection_service);
+        add_service!(builder, $crate::health::make_server(health_registry));
+        $crate::add_compressed_service!(builder, reflection_service);
         add_service!(
             builder,
             $crate::reexport::service_grpc_testing::make_server()
@@ -181,11 +225,17 @@ pub async fn serve(
     socket: TcpListener,
     server_type: Arc<dyn ServerType>,
     trace_header_parser: TraceHeaderParser,
+    compression: &[GrpcCompression],
     shutdown: CancellationToken,
 ) -> Result<(), RpcError> {
     let builder_input = RpcBuilderInput {
         socket,
         trace_header_parser,
+        compression: compression
+            .iter()
+            .copied()
+            .filter_map(compression_encoding)
+            .collect(),
         shutdown,
     };
 
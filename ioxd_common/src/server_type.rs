@@ -3,6 +3,7 @@ mod common_state;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use clap_blocks::reloadable::Reload;
 use hyper::{Body, Request, Response};
 use metric::Registry;
 use snafu::Snafu;
@@ -12,7 +13,7 @@ use trace::TraceCollector;
 pub use common_state::{CommonServerState, CommonServerStateError};
 use trace_http::metrics::{MetricFamily, RequestMetrics};
 
-use crate::{http::error::HttpApiErrorSource, rpc::RpcBuilderInput};
+use crate::{health::HealthRegistry, http::error::HttpApiErrorSource, rpc::RpcBuilderInput};
 
 #[derive(Debug, Snafu)]
 pub enum RpcError {
@@ -83,4 +84,22 @@ pub trait ServerType: std::fmt::Debug + Send + Sync + 'static {
     fn is_healthy(&self) -> bool {
         true
     }
+
+    /// Registry of the component states of this server, if any.
+    ///
+    /// The aggregate readiness of the registered components is reported via
+    /// the gRPC health service and `/health`.
+    fn health_registry(&self) -> Option<HealthRegistry> {
+        None
+    }
+
+    /// Settings of this server that can be changed while it is running, if any.
+    ///
+    /// These are reloaded on `SIGHUP` and via `POST /debug/reload`. The server
+    /// applies the changes by subscribing to them, e.g. via
+    /// [`reload_log_filter`](clap_blocks::reloadable::reload_log_filter) and
+    /// [`reload_catalog_cache`](clap_blocks::reloadable::reload_catalog_cache).
+    fn reloadable_config(&self) -> Option<Arc<dyn Reload>> {
+        None
+    }
 }
@@ -18,8 +18,7 @@ fuzz_target!(|data: &[u8]| {
         if let Ok((batches, stats)) = converter.finish() {
             let mut total_rows = 0;
 
-            let mut partitions: HashMap<_, HashMap<String, MutableBatch>> =
-                HashMap::default();
+            let mut partitions: HashMap<_, HashMap<String, MutableBatch>> = HashMap::default();
 
             for (table_name, mutable_batch) in &batches {
                 assert!(
@@ -35,7 +34,8 @@ fuzz_target!(|data: &[u8]| {
                 );
 
                 for (partition_key, partition_payload) in
-                    PartitionWrite::partition(&mutable_batch, &table_partition_template).unwrap()
+                    PartitionWrite::partition(&mutable_batch, table_name, &table_partition_template)
+                        .unwrap()
                 {
                     let partition = partitions.entry(partition_key).or_default();
 
@@ -0,0 +1,187 @@
+//! Structured panic reports ("crash reports") for post-mortem analysis.
+//!
+//! Log aggregation usually only keeps the panic message and location. A [`CrashReport`] additionally contains the full
+//! backtrace, the thread name and build information. Reports are passed to a [`CrashReportSink`], e.g. a
+//! [`CrashReportDirectory`] that writes one JSON file per panic that can later be collected by operators.
+use std::{
+    backtrace::Backtrace,
+    collections::BTreeMap,
+    fmt::Debug,
+    fs,
+    io::Write,
+    panic::PanicInfo,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use iox_time::{SystemProvider, Time, TimeProvider};
+use serde::{Deserialize, Serialize};
+
+use crate::{message, PanicType};
+
+/// Error returned by a [`CrashReportSink`].
+pub type CrashReportError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Information about a single panic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// Panic classification, see the `type` attribute of the `thread_panic_count` metric.
+    pub panic_type: String,
+
+    /// Panic message, if the payload was a string.
+    pub message: Option<String>,
+
+    /// Source file of the panic.
+    pub file: Option<String>,
+
+    /// Line of the panic.
+    pub line: Option<u32>,
+
+    /// Column of the panic.
+    pub column: Option<u32>,
+
+    /// Rendered backtrace of the panicking thread.
+    pub backtrace: String,
+
+    /// Name of the panicking thread, if it has one.
+    pub thread_name: Option<String>,
+
+    /// ID of the tokio task that panicked, if known.
+    ///
+    /// This is only available if built with `tokio_unstable`.
+    #[serde(default)]
+    pub task_id: Option<String>,
+
+    /// Time of the panic, as RFC 3339 timestamp.
+    pub timestamp: String,
+
+    /// ID of the process.
+    pub pid: u32,
+
+    /// Build information (e.g. version and git revision) as provided by the application.
+    pub build_info: BTreeMap<String, String>,
+}
+
+impl CrashReport {
+    /// Gather report for the given panic.
+    ///
+    /// Must be called from the panicking thread, otherwise backtrace and thread name are wrong.
+    pub(crate) fn from_panic(
+        info: &PanicInfo<'_>,
+        panic_type: PanicType,
+        task_id: Option<String>,
+        build_info: &BTreeMap<String, String>,
+    ) -> Self {
+        let location = info.location();
+
+        Self {
+            panic_type: panic_type.name().to_owned(),
+            message: message(info).map(ToOwned::to_owned),
+            file: location.map(|l| l.file().to_owned()),
+            line: location.map(|l| l.line()),
+            column: location.map(|l| l.column()),
+            backtrace: Backtrace::force_capture().to_string(),
+            thread_name: std::thread::current().name().map(ToOwned::to_owned),
+            task_id,
+            timestamp: SystemProvider::new().now().to_rfc3339(),
+            pid: std::process::id(),
+            build_info: build_info.clone(),
+        }
+    }
+}
+
+/// Destination for [`CrashReport`]s.
+///
+/// This is called from within the panic hook, so implementations must not panic and should be reasonably fast. They
+/// cannot rely on an async runtime being available (or not being the very thing that panics), so uploads to an
+/// object store should rather be done by collecting the files written by [`CrashReportDirectory`].
+pub trait CrashReportSink: Debug + Send + Sync + 'static {
+    /// Persist the given report.
+    fn write(&self, report: &CrashReport) -> Result<(), CrashReportError>;
+}
+
+/// Writes every [`CrashReport`] as a JSON file into a directory.
+///
+/// Files are named `panic-<timestamp>-<pid>-<seq>.json` and are first written under a temporary name and then renamed,
+/// so a collector never sees partially written reports. The directory is created if it does not exist.
+#[derive(Debug)]
+pub struct CrashReportDirectory {
+    path: PathBuf,
+    seq: AtomicU64,
+}
+
+impl CrashReportDirectory {
+    /// Create sink that writes to the given directory.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            seq: AtomicU64::new(0),
+        }
+    }
+}
+
+impl CrashReportSink for CrashReportDirectory {
+    fn write(&self, report: &CrashReport) -> Result<(), CrashReportError> {
+        fs::create_dir_all(&self.path)?;
+
+        let timestamp = Time::from_rfc3339(&report.timestamp)?.timestamp_nanos();
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        let name = format!("panic-{timestamp}-{}-{seq}.json", report.pid);
+        let path = self.path.join(&name);
+        let tmp_path = self.path.join(format!(".{name}.tmp"));
+
+        let mut file = fs::File::create(&tmp_path)?;
+        serde_json::to_writer_pretty(&mut file, report)?;
+        file.write_all(b"\n")?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crashes");
+        let sink = CrashReportDirectory::new(&path);
+
+        let report = CrashReport {
+            panic_type: "unknown".to_owned(),
+            message: Some("it's bananas".to_owned()),
+            file: Some("src/lib.rs".to_owned()),
+            line: Some(1),
+            column: Some(2),
+            backtrace: "<backtrace>".to_owned(),
+            thread_name: Some("main".to_owned()),
+            task_id: None,
+            timestamp: "1970-01-01T00:00:01+00:00".to_owned(),
+            pid: 42,
+            build_info: BTreeMap::from([("version".to_owned(), "1.2.3".to_owned())]),
+        };
+        sink.write(&report).unwrap();
+        sink.write(&report).unwrap();
+
+        let mut names = fs::read_dir(&path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "panic-1000000000-42-0.json".to_owned(),
+                "panic-1000000000-42-1.json".to_owned(),
+            ]
+        );
+
+        let read: CrashReport =
+            serde_json::from_slice(&fs::read(path.join(&names[0])).unwrap()).unwrap();
+        assert_eq!(read, report);
+    }
+}
@@ -0,0 +1,88 @@
+//! Configurable process termination on panic, see [`FatalPanics`].
+use std::{panic, time::Duration};
+
+/// How the process is terminated after a panic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExitMode {
+    /// Call [`std::process::exit`] with the configured exit code.
+    #[default]
+    Exit,
+
+    /// Call [`std::process::abort`], which raises `SIGABRT` and produces a core dump if the environment is configured
+    /// for it. The exit code is ignored in this mode.
+    Abort,
+}
+
+/// Builder that installs a panic hook which terminates the process after calling the existing panic hook, if any.
+///
+/// The defaults (exit code 1, no grace period, [`ExitMode::Exit`]) are the same as [`make_panics_fatal`].
+///
+/// [`make_panics_fatal`]: crate::make_panics_fatal
+#[derive(Debug, Clone, Copy)]
+pub struct FatalPanics {
+    exit_code: i32,
+    grace_period: Duration,
+    mode: ExitMode,
+}
+
+impl Default for FatalPanics {
+    fn default() -> Self {
+        Self {
+            exit_code: 1,
+            grace_period: Duration::ZERO,
+            mode: ExitMode::Exit,
+        }
+    }
+}
+
+impl FatalPanics {
+    /// Create builder with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Exit code used in [`ExitMode::Exit`].
+    pub fn with_exit_code(self, exit_code: i32) -> Self {
+        Self { exit_code, ..self }
+    }
+
+    /// Time to wait between the panic and the termination of the process.
+    ///
+    /// Only the panicking thread is blocked, so this gives background tasks (e.g. trace and metric exporters) a chance
+    /// to flush their data.
+    pub fn with_grace_period(self, grace_period: Duration) -> Self {
+        Self {
+            grace_period,
+            ..self
+        }
+    }
+
+    /// Set how the process is terminated.
+    pub fn with_mode(self, mode: ExitMode) -> Self {
+        Self { mode, ..self }
+    }
+
+    /// Install the panic hook.
+    pub fn install(self) {
+        let existing = panic::take_hook();
+
+        panic::set_hook(Box::new(move |info| {
+            // Call the existing panic hook.
+            existing(info);
+
+            self.terminate();
+        }));
+    }
+
+    /// Terminate the process according to this configuration.
+    pub(crate) fn terminate(&self) -> ! {
+        if !self.grace_period.is_zero() {
+            std::thread::sleep(self.grace_period);
+        }
+
+        match self.mode {
+            ExitMode::Exit => std::process::exit(self.exit_code),
+            ExitMode::Abort => std::process::abort(),
+        }
+    }
+}
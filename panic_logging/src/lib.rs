@@ -15,11 +15,31 @@
 // Workaround for "unused crate" lint false positives.
 use workspace_hack as _;
 
-use std::{collections::HashMap, fmt, panic, sync::Arc};
-
-use metric::U64Counter;
+mod crash_report;
+mod fatal;
+mod rate_limit;
+pub mod test_util;
+mod threshold;
+
+pub use crash_report::{CrashReport, CrashReportDirectory, CrashReportError, CrashReportSink};
+pub use fatal::{ExitMode, FatalPanics};
+pub use threshold::PanicThreshold;
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt, panic,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use metric::{Metric, U64Counter};
 use observability_deps::tracing::{error, warn};
 use panic::PanicInfo;
+use rate_limit::{Decision, PanicKey, PanicRateLimiter};
+// Workaround for "unused crate" lint false positives.
+#[cfg(not(tokio_unstable))]
+use tokio as _;
 
 type PanicFunctionPtr = Arc<Box<dyn Fn(&PanicInfo<'_>) + Sync + Send + 'static>>;
 
@@ -40,7 +60,7 @@ pub struct SendPanicsToTracing {
 
 impl SendPanicsToTracing {
     pub fn new() -> Self {
-        Self::new_inner(None)
+        Self::builder().build()
     }
 
     /// Configure this panic handler to emit a panic count metric.
@@ -48,36 +68,174 @@ impl SendPanicsToTracing {
     /// The metric is named `thread_panic_count_total` and is incremented each
     /// time the panic handler is invoked.
     pub fn new_with_metrics(metrics: &metric::Registry) -> Self {
-        let metrics = Metrics::new(metrics);
-        Self::new_inner(Some(metrics))
+        Self::builder().with_metrics(metrics).build()
     }
 
-    fn new_inner(metrics: Option<Metrics>) -> Self {
+    /// Create a builder to configure additional behavior of the panic hook.
+    pub fn builder() -> SendPanicsToTracingBuilder {
+        SendPanicsToTracingBuilder::default()
+    }
+
+    fn new_inner(config: SendPanicsToTracingBuilder) -> Self {
+        let SendPanicsToTracingBuilder {
+            mut metrics,
+            thread_name_attribute,
+            crash_reports,
+            build_info,
+            rate_limit,
+            thresholds,
+        } = config;
+        if let Some(metrics) = &mut metrics {
+            metrics.thread_name_attribute = thread_name_attribute;
+        }
+        let rate_limiter = rate_limit.map(PanicRateLimiter::new);
+
         let current_panic_hook: PanicFunctionPtr = Arc::new(panic::take_hook());
         let old_panic_hook = Some(Arc::clone(&current_panic_hook));
         panic::set_hook(Box::new(move |info| {
             let panic_type = PanicType::classify(info);
+            let thread = std::thread::current();
+            let thread_name = thread.name();
+            let task_id = task_id();
+            let now = Instant::now();
             if let Some(metrics) = &metrics {
-                metrics.inc(panic_type);
+                metrics.inc(panic_type, thread_name);
             }
 
             let location = info.location();
-            error!(
-                panic_type = panic_type.name(),
-                panic_message = message(info),
-                panic_file = location.map(|l| l.file()),
-                panic_line = location.map(|l| l.line()),
-                panic_column = location.map(|l| l.column()),
-                "Thread panic",
-            );
+            let decision = match &rate_limiter {
+                Some(rate_limiter) => rate_limiter.check(PanicKey::new(info), now),
+                None => Decision::Report { suppressed: 0 },
+            };
+            match decision {
+                Decision::Report { suppressed: 0 } | Decision::Suppress => {}
+                Decision::Report { suppressed } => {
+                    error!(
+                        panic_type = panic_type.name(),
+                        panic_message = message(info),
+                        panic_file = location.map(|l| l.file()),
+                        panic_line = location.map(|l| l.line()),
+                        panic_column = location.map(|l| l.column()),
+                        thread_name,
+                        task_id,
+                        suppressed,
+                        "Suppressed identical thread panics",
+                    );
+                }
+            }
+
+            if decision != Decision::Suppress {
+                if let Some(sink) = &crash_reports {
+                    let report =
+                        CrashReport::from_panic(info, panic_type, task_id.clone(), &build_info);
+                    if let Err(e) = sink.write(&report) {
+                        warn!(%e, "Cannot write crash report");
+                    }
+                }
+
+                error!(
+                    panic_type = panic_type.name(),
+                    panic_message = message(info),
+                    panic_file = location.map(|l| l.file()),
+                    panic_line = location.map(|l| l.line()),
+                    panic_column = location.map(|l| l.column()),
+                    thread_name,
+                    task_id,
+                    "Thread panic",
+                );
+            }
 
             current_panic_hook(info);
+
+            // record in all policies, the counts must not depend on the order
+            let mut exceeded = None;
+            for threshold in &thresholds {
+                if threshold.record(panic_type, now) {
+                    exceeded.get_or_insert(threshold);
+                }
+            }
+            if let Some(threshold) = exceeded {
+                threshold.terminate();
+            }
         }));
 
         Self { old_panic_hook }
     }
 }
 
+/// Builder for [`SendPanicsToTracing`].
+#[derive(Debug, Default)]
+pub struct SendPanicsToTracingBuilder {
+    metrics: Option<Metrics>,
+    thread_name_attribute: bool,
+    crash_reports: Option<Arc<dyn CrashReportSink>>,
+    build_info: BTreeMap<String, String>,
+    rate_limit: Option<Duration>,
+    thresholds: Vec<PanicThreshold>,
+}
+
+impl SendPanicsToTracingBuilder {
+    /// Emit a panic count metric, see [`SendPanicsToTracing::new_with_metrics`].
+    pub fn with_metrics(self, metrics: &metric::Registry) -> Self {
+        Self {
+            metrics: Some(Metrics::new(metrics)),
+            ..self
+        }
+    }
+
+    /// Add the name of the panicking thread as `thread_name` attribute to the panic count metric.
+    ///
+    /// Threads without a name are counted as `unnamed`. Only use this if thread names have a bounded cardinality, e.g.
+    /// if all runtimes use a fixed thread name. Has no effect without [`with_metrics`](Self::with_metrics).
+    pub fn with_thread_name_attribute(self) -> Self {
+        Self {
+            thread_name_attribute: true,
+            ..self
+        }
+    }
+
+    /// Write a [`CrashReport`] for every panic to the given sink, e.g. a [`CrashReportDirectory`].
+    pub fn with_crash_reports(self, sink: Arc<dyn CrashReportSink>) -> Self {
+        Self {
+            crash_reports: Some(sink),
+            ..self
+        }
+    }
+
+    /// Add build information (e.g. `version` or `git_hash`) to every [`CrashReport`].
+    pub fn with_build_info(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.build_info.insert(key.into(), value.into());
+        self
+    }
+
+    /// Report identical panics (same message and location) at most once per `interval`.
+    ///
+    /// Repeated panics, e.g. of a retried task, are neither logged nor written as [`CrashReport`]. Instead, the next
+    /// reported panic after the interval is preceded by an event with the number of suppressed panics. The panic count
+    /// metric and the previously installed panic hook are still called for every panic.
+    pub fn with_rate_limit(self, interval: Duration) -> Self {
+        Self {
+            rate_limit: Some(interval),
+            ..self
+        }
+    }
+
+    /// Terminate the process once the given threshold is reached.
+    ///
+    /// This can be called multiple times, e.g. to use a lower threshold for a specific [`PanicType`]. All panics are
+    /// counted, including those suppressed by [`with_rate_limit`](Self::with_rate_limit). The process is terminated
+    /// after the panic was reported and the previously installed panic hook was called.
+    pub fn with_panic_threshold(mut self, threshold: PanicThreshold) -> Self {
+        self.thresholds.push(threshold);
+        self
+    }
+
+    /// Install the panic hook.
+    pub fn build(self) -> SendPanicsToTracing {
+        SendPanicsToTracing::new_inner(self)
+    }
+}
+
 // can't derive because the function pointer doesn't implement Debug
 impl fmt::Debug for SendPanicsToTracing {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -120,23 +278,16 @@ impl Drop for SendPanicsToTracing {
 
 /// Ensure panics are fatal events by exiting the process with an exit code of
 /// 1 after calling the existing panic handler, if any.
+///
+/// Use [`FatalPanics`] to configure the exit code, a grace period, or to abort the process instead.
 pub fn make_panics_fatal() {
-    let existing = panic::take_hook();
-
-    panic::set_hook(Box::new(move |info| {
-        // Call the existing panic hook.
-        existing(info);
-        // Exit the process.
-        //
-        // NOTE: execution may not reach this point if another hook
-        // kills the process first.
-        std::process::exit(1);
-    }));
+    FatalPanics::default().install();
 }
 
-/// Panic type
+/// Panic type, used as `type` attribute of the panic count metric.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum PanicType {
+#[non_exhaustive]
+pub enum PanicType {
     /// Counter for unknown panics.
     Unknown,
 
@@ -144,23 +295,71 @@ enum PanicType {
     ///
     /// These are likely caused due too overly large string columns in Arrow.
     OffsetOverflow,
+
+    /// Counter for unwrapped Arrow compute kernel errors.
+    ArrowCompute,
+
+    /// Counter for panics that re-raise a failed tokio task, e.g. by unwrapping a `JoinError`.
+    JoinError,
+
+    /// Counter for failed allocations, e.g. "capacity overflow".
+    ///
+    /// Note that most allocation failures abort the process instead of panicking.
+    AllocationFailure,
+
+    /// Counter for out-of-bounds slice and array accesses.
+    IndexOutOfBounds,
 }
 
 impl PanicType {
     fn all() -> &'static [Self] {
-        &[Self::Unknown, Self::OffsetOverflow]
+        &[
+            Self::Unknown,
+            Self::OffsetOverflow,
+            Self::ArrowCompute,
+            Self::JoinError,
+            Self::AllocationFailure,
+            Self::IndexOutOfBounds,
+        ]
     }
 
-    fn name(&self) -> &'static str {
+    /// Name of this type as used in metrics and logs.
+    pub fn name(&self) -> &'static str {
         match self {
             Self::Unknown => "unknown",
             Self::OffsetOverflow => "offset_overflow",
+            Self::ArrowCompute => "arrow_compute",
+            Self::JoinError => "join_error",
+            Self::AllocationFailure => "allocation_failure",
+            Self::IndexOutOfBounds => "index_out_of_bounds",
         }
     }
 
     fn classify(panic_info: &PanicInfo<'_>) -> Self {
-        match message(panic_info) {
-            Some("offset overflow" | "offset") => Self::OffsetOverflow,
+        Self::classify_message(message(panic_info))
+    }
+
+    fn classify_message(message: Option<&str>) -> Self {
+        let Some(message) = message else {
+            return Self::Unknown;
+        };
+
+        match message {
+            "offset overflow" | "offset" => Self::OffsetOverflow,
+            "capacity overflow" => Self::AllocationFailure,
+            m if m.starts_with("memory allocation of") => Self::AllocationFailure,
+            m if m.starts_with("index out of bounds")
+                || m.contains("out of range for slice of length") =>
+            {
+                Self::IndexOutOfBounds
+            }
+            m if m.contains("JoinError::")
+                || (m.starts_with("task ")
+                    && (m.contains(" panicked") || m.ends_with(" was cancelled"))) =>
+            {
+                Self::JoinError
+            }
+            m if m.contains("ComputeError") || m.starts_with("Arrow error:") => Self::ArrowCompute,
             _ => Self::Unknown,
         }
     }
@@ -176,11 +375,29 @@ fn message<'a>(panic_info: &'a PanicInfo<'a>) -> Option<&'a str> {
         .or(payload_any.downcast_ref::<String>().map(|s| s.as_str()))
 }
 
+/// ID of the current tokio task, if the panic happened within a task.
+///
+/// This requires `tokio_unstable`.
+#[cfg(tokio_unstable)]
+fn task_id() -> Option<String> {
+    tokio::task::try_id().map(|id| id.to_string())
+}
+
+#[cfg(not(tokio_unstable))]
+fn task_id() -> Option<String> {
+    None
+}
+
 /// Metrics used for panics.
 #[derive(Debug)]
 struct Metrics {
+    metric: Metric<U64Counter>,
+
     /// Counter for different panic types.
     counters: HashMap<PanicType, U64Counter>,
+
+    /// Add the `thread_name` attribute.
+    thread_name_attribute: bool,
 }
 
 impl Metrics {
@@ -195,10 +412,23 @@ impl Metrics {
                 .iter()
                 .map(|t| (*t, metric.recorder(&[("type", t.name())])))
                 .collect(),
+            metric,
+            thread_name_attribute: false,
         }
     }
 
-    fn inc(&self, panic_type: PanicType) {
+    fn inc(&self, panic_type: PanicType, thread_name: Option<&str>) {
+        if self.thread_name_attribute {
+            let thread_name = thread_name.unwrap_or("unnamed").to_owned();
+            self.metric
+                .recorder([
+                    ("type", Cow::Borrowed(panic_type.name())),
+                    ("thread_name", Cow::Owned(thread_name)),
+                ])
+                .inc(1);
+            return;
+        }
+
         self.counters
             .get(&panic_type)
             .expect("all types covered")
@@ -237,12 +467,15 @@ mod tests {
         assert_count(&metrics, "unknown", 0);
 
         let capture2 = Arc::clone(&capture);
-        std::thread::spawn(move || {
-            capture2.register_in_current_thread();
-            panic!("it's bananas");
-        })
-        .join()
-        .expect_err("wat");
+        std::thread::Builder::new()
+            .name("bananas".to_owned())
+            .spawn(move || {
+                capture2.register_in_current_thread();
+                panic!("it's bananas");
+            })
+            .unwrap()
+            .join()
+            .expect_err("wat");
 
         let capture2 = Arc::clone(&capture);
         std::thread::spawn(move || {
@@ -283,10 +516,81 @@ mod tests {
 
         assert_eq!(
             capture.to_string(),
-            "level = ERROR; message = Thread panic; panic_type = \"unknown\"; panic_message = \"it's bananas\"; panic_file = \"panic_logging/src/lib.rs\"; panic_line = 242; panic_column = 13; \n\
-             level = ERROR; message = Thread panic; panic_type = \"offset_overflow\"; panic_message = \"offset\"; panic_file = \"panic_logging/src/lib.rs\"; panic_line = 250; panic_column = 13; \n\
-             level = ERROR; message = Thread panic; panic_type = \"offset_overflow\"; panic_message = \"offset overflow\"; panic_file = \"panic_logging/src/lib.rs\"; panic_line = 259; panic_column = 13; \n\
-             level = ERROR; message = Thread panic; panic_type = \"unknown\"; panic_file = \"panic_logging/src/lib.rs\"; panic_line = 267; panic_column = 13; "
+            "level = ERROR; message = Thread panic; panic_type = \"unknown\"; panic_message = \"it's bananas\"; panic_file = \"panic_logging/src/lib.rs\"; panic_line = 474; panic_column = 17; thread_name = \"bananas\"; \n\
+             level = ERROR; message = Thread panic; panic_type = \"offset_overflow\"; panic_message = \"offset\"; panic_file = \"panic_logging/src/lib.rs\"; panic_line = 483; panic_column = 13; \n\
+             level = ERROR; message = Thread panic; panic_type = \"offset_overflow\"; panic_message = \"offset overflow\"; panic_file = \"panic_logging/src/lib.rs\"; panic_line = 492; panic_column = 13; \n\
+             level = ERROR; message = Thread panic; panic_type = \"unknown\"; panic_file = \"panic_logging/src/lib.rs\"; panic_line = 500; panic_column = 13; "
         );
     }
+
+    #[test]
+    fn test_thread_name_attribute() {
+        let registry = metric::Registry::default();
+        let mut metrics = Metrics::new(&registry);
+        metrics.thread_name_attribute = true;
+
+        metrics.inc(PanicType::Unknown, Some("io"));
+        metrics.inc(PanicType::Unknown, Some("io"));
+        metrics.inc(PanicType::OffsetOverflow, None);
+
+        let metric = registry
+            .get_instrument::<Metric<U64Counter>>("thread_panic_count")
+            .expect("failed to read metric");
+        let get = |t: &'static str, thread_name: &'static str| {
+            metric
+                .get_observer(&Attributes::from(&[
+                    ("type", t),
+                    ("thread_name", thread_name),
+                ]))
+                .expect("failed to get observer")
+                .fetch()
+        };
+        assert_eq!(get("unknown", "io"), 2);
+        assert_eq!(get("offset_overflow", "unnamed"), 1);
+    }
+
+    #[test]
+    fn test_classify() {
+        let cases = [
+            (None, PanicType::Unknown),
+            (Some("it's bananas"), PanicType::Unknown),
+            (Some("offset"), PanicType::OffsetOverflow),
+            (Some("offset overflow"), PanicType::OffsetOverflow),
+            (
+                Some("called `Result::unwrap()` on an `Err` value: ComputeError(\"Overflow happened\")"),
+                PanicType::ArrowCompute,
+            ),
+            (
+                Some("Arrow error: Compute error: Overflow happened"),
+                PanicType::ArrowCompute,
+            ),
+            (
+                Some("called `Result::unwrap()` on an `Err` value: JoinError::Panic(Id(12), ...)"),
+                PanicType::JoinError,
+            ),
+            (Some("task 12 panicked"), PanicType::JoinError),
+            (Some("task 12 was cancelled"), PanicType::JoinError),
+            (Some("capacity overflow"), PanicType::AllocationFailure),
+            (
+                Some("memory allocation of 1024 bytes failed"),
+                PanicType::AllocationFailure,
+            ),
+            (
+                Some("index out of bounds: the len is 3 but the index is 5"),
+                PanicType::IndexOutOfBounds,
+            ),
+            (
+                Some("range end index 5 out of range for slice of length 3"),
+                PanicType::IndexOutOfBounds,
+            ),
+        ];
+
+        for (message, expected) in cases {
+            assert_eq!(
+                PanicType::classify_message(message),
+                expected,
+                "message: {message:?}"
+            );
+        }
+    }
 }
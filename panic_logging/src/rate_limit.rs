@@ -0,0 +1,187 @@
+//! Deduplication of identical panics, see [`SendPanicsToTracingBuilder::with_rate_limit`].
+//!
+//! [`SendPanicsToTracingBuilder::with_rate_limit`]: crate::SendPanicsToTracingBuilder::with_rate_limit
+use std::{
+    collections::HashMap,
+    panic::PanicInfo,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::message;
+
+/// Maximum number of distinct panics that are tracked.
+///
+/// Panics with unique messages (e.g. containing IDs) must not grow the state unboundedly. If the limit is reached,
+/// untracked panics are always reported.
+const MAX_ENTRIES: usize = 1_000;
+
+/// Identifies "identical" panics.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct PanicKey {
+    message: Option<String>,
+    location: Option<(String, u32, u32)>,
+}
+
+impl PanicKey {
+    pub(crate) fn new(info: &PanicInfo<'_>) -> Self {
+        Self {
+            message: message(info).map(ToOwned::to_owned),
+            location: info
+                .location()
+                .map(|l| (l.file().to_owned(), l.line(), l.column())),
+        }
+    }
+}
+
+/// Decision of [`PanicRateLimiter::check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Decision {
+    /// Report the panic.
+    ///
+    /// `suppressed` is the number of identical panics that were not reported since the last report.
+    Report { suppressed: u64 },
+
+    /// Do not report the panic.
+    Suppress,
+}
+
+#[derive(Debug)]
+struct Entry {
+    last_reported: Instant,
+    suppressed: u64,
+}
+
+/// Reports every distinct panic at most once per interval.
+#[derive(Debug)]
+pub(crate) struct PanicRateLimiter {
+    interval: Duration,
+    entries: Mutex<HashMap<PanicKey, Entry>>,
+}
+
+impl PanicRateLimiter {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            entries: Default::default(),
+        }
+    }
+
+    pub(crate) fn check(&self, key: PanicKey, now: Instant) -> Decision {
+        // we are within the panic hook, so don't panic on a poisoned lock
+        let mut entries = self
+            .entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(entry) = entries.get_mut(&key) {
+            if now.saturating_duration_since(entry.last_reported) < self.interval {
+                entry.suppressed += 1;
+                return Decision::Suppress;
+            }
+
+            let suppressed = std::mem::take(&mut entry.suppressed);
+            entry.last_reported = now;
+            return Decision::Report { suppressed };
+        }
+
+        if entries.len() >= MAX_ENTRIES {
+            // forget entries that would be reported anyways, losing their suppression counts
+            let interval = self.interval;
+            entries
+                .retain(|_k, entry| now.saturating_duration_since(entry.last_reported) < interval);
+        }
+        if entries.len() < MAX_ENTRIES {
+            entries.insert(
+                key,
+                Entry {
+                    last_reported: now,
+                    suppressed: 0,
+                },
+            );
+        }
+
+        Decision::Report { suppressed: 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(message: &str) -> PanicKey {
+        PanicKey {
+            message: Some(message.to_owned()),
+            location: Some(("src/lib.rs".to_owned(), 1, 2)),
+        }
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let limiter = PanicRateLimiter::new(Duration::from_secs(10));
+        let t0 = Instant::now();
+
+        assert_eq!(
+            limiter.check(key("a"), t0),
+            Decision::Report { suppressed: 0 }
+        );
+        assert_eq!(limiter.check(key("a"), t0), Decision::Suppress);
+        assert_eq!(
+            limiter.check(key("a"), t0 + Duration::from_secs(9)),
+            Decision::Suppress
+        );
+
+        // different message or location is not identical
+        assert_eq!(
+            limiter.check(key("b"), t0 + Duration::from_secs(9)),
+            Decision::Report { suppressed: 0 }
+        );
+        let other_location = PanicKey {
+            location: None,
+            ..key("a")
+        };
+        assert_eq!(
+            limiter.check(other_location, t0 + Duration::from_secs(9)),
+            Decision::Report { suppressed: 0 }
+        );
+
+        // interval passed
+        assert_eq!(
+            limiter.check(key("a"), t0 + Duration::from_secs(10)),
+            Decision::Report { suppressed: 2 }
+        );
+        assert_eq!(
+            limiter.check(key("a"), t0 + Duration::from_secs(20)),
+            Decision::Report { suppressed: 0 }
+        );
+    }
+
+    #[test]
+    fn test_max_entries() {
+        let limiter = PanicRateLimiter::new(Duration::from_secs(10));
+        let t0 = Instant::now();
+
+        for i in 0..MAX_ENTRIES {
+            limiter.check(key(&i.to_string()), t0);
+        }
+
+        // untracked panics are always reported
+        let t1 = t0 + Duration::from_secs(1);
+        assert_eq!(
+            limiter.check(key("x"), t1),
+            Decision::Report { suppressed: 0 }
+        );
+        assert_eq!(
+            limiter.check(key("x"), t1),
+            Decision::Report { suppressed: 0 }
+        );
+
+        // old entries are evicted
+        let t2 = t0 + Duration::from_secs(10);
+        assert_eq!(
+            limiter.check(key("x"), t2),
+            Decision::Report { suppressed: 0 }
+        );
+        assert_eq!(limiter.check(key("x"), t2), Decision::Suppress);
+    }
+}
@@ -0,0 +1,145 @@
+//! Test utilities, see [`PanicCapture`].
+use std::{
+    cell::RefCell,
+    panic::{self, PanicInfo},
+    sync::{Arc, Mutex, Once, Weak},
+};
+
+use crate::{message, PanicType};
+
+/// Panic recorded by [`PanicCapture`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedPanic {
+    /// Classification of the panic.
+    pub panic_type: PanicType,
+
+    /// Panic message, if the payload was a string.
+    pub message: Option<String>,
+
+    /// Source file of the panic.
+    pub file: Option<String>,
+
+    /// Line of the panic.
+    pub line: Option<u32>,
+
+    /// Name of the panicking thread, if it has one.
+    pub thread_name: Option<String>,
+}
+
+impl CapturedPanic {
+    fn new(info: &PanicInfo<'_>) -> Self {
+        Self {
+            panic_type: PanicType::classify(info),
+            message: message(info).map(ToOwned::to_owned),
+            file: info.location().map(|l| l.file().to_owned()),
+            line: info.location().map(|l| l.line()),
+            thread_name: std::thread::current().name().map(ToOwned::to_owned),
+        }
+    }
+}
+
+type Panics = Mutex<Vec<CapturedPanic>>;
+
+thread_local! {
+    /// Captures registered in the current thread, the most recent one receives the panics.
+    static CAPTURES: RefCell<Vec<Weak<Panics>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Guards the installation of the dispatching panic hook.
+static INSTALL_HOOK: Once = Once::new();
+
+/// Records the panics of the threads it is registered in, without replacing the process-wide panic hook per test.
+///
+/// Upon first use, a panic hook is installed for the rest of the process. This hook passes panics of registered
+/// threads to the [`PanicCapture`] and then chains to the previously installed hook, so that e.g. the default hook
+/// still prints them. Since nothing is installed or uninstalled per test, tests using this can run in parallel.
+///
+/// Note that installing [`SendPanicsToTracing`](crate::SendPanicsToTracing) within the same test binary may still
+/// replace the process-wide hook.
+#[derive(Debug)]
+pub struct PanicCapture {
+    panics: Arc<Panics>,
+}
+
+impl PanicCapture {
+    /// Create a new capture and register it in the current thread.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        INSTALL_HOOK.call_once(|| {
+            let previous_hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                capture(info);
+                previous_hook(info);
+            }));
+        });
+
+        let this = Self {
+            panics: Default::default(),
+        };
+        this.register_in_current_thread();
+        this
+    }
+
+    /// Registers capture in current thread, e.g. in a thread or blocking task spawned by the test.
+    ///
+    /// The registration ends when the capture is dropped.
+    pub fn register_in_current_thread(&self) {
+        CAPTURES.with(|captures| {
+            let mut captures = captures.borrow_mut();
+            captures.retain(|c| c.strong_count() > 0);
+            captures.push(Arc::downgrade(&self.panics));
+        });
+    }
+
+    /// All panics captured so far, in order.
+    pub fn panics(&self) -> Vec<CapturedPanic> {
+        self.panics.lock().expect("not poisoned").clone()
+    }
+
+    /// Assert that exactly `n` panics were captured.
+    #[track_caller]
+    pub fn assert_count(&self, n: usize) {
+        let panics = self.panics();
+        assert_eq!(panics.len(), n, "unexpected number of panics: {panics:#?}");
+    }
+
+    /// Assert that the captured panics have the given types, in order.
+    #[track_caller]
+    pub fn assert_panic_types(&self, expected: &[PanicType]) {
+        let actual = self
+            .panics()
+            .into_iter()
+            .map(|p| p.panic_type)
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    /// Assert that any captured panic message contains `needle`.
+    #[track_caller]
+    pub fn assert_message_contains(&self, needle: &str) {
+        let panics = self.panics();
+        assert!(
+            panics
+                .iter()
+                .any(|p| p.message.as_deref().is_some_and(|m| m.contains(needle))),
+            "no panic message contains '{needle}': {panics:#?}"
+        );
+    }
+}
+
+/// Pass panic to the capture registered in the current thread, if any.
+fn capture(info: &PanicInfo<'_>) {
+    // the thread may be shutting down
+    let capture = CAPTURES
+        .try_with(|captures| captures.borrow().iter().rev().find_map(Weak::upgrade))
+        .ok()
+        .flatten();
+
+    if let Some(panics) = capture {
+        // we are within the panic hook, so don't panic on a poisoned lock
+        panics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(CapturedPanic::new(info));
+    }
+}
@@ -0,0 +1,124 @@
+//! Terminate the process after too many panics, see [`PanicThreshold`].
+use std::{
+    collections::VecDeque,
+    num::NonZeroUsize,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use observability_deps::tracing::error;
+
+use crate::{FatalPanics, PanicType};
+
+/// Policy that terminates the process once `max_panics` panics were observed within a sliding time window.
+///
+/// Some panics (e.g. [`PanicType::OffsetOverflow`]) are caught and turned into errors, so the process would otherwise
+/// continue to run in a potentially bad state. Install this policy via
+/// [`SendPanicsToTracingBuilder::with_panic_threshold`](crate::SendPanicsToTracingBuilder::with_panic_threshold).
+#[derive(Debug)]
+pub struct PanicThreshold {
+    max_panics: NonZeroUsize,
+    window: Duration,
+    panic_type: Option<PanicType>,
+    exit: FatalPanics,
+
+    /// Time of the most recent panics, at most `max_panics` entries.
+    panics: Mutex<VecDeque<Instant>>,
+}
+
+impl PanicThreshold {
+    /// Terminate the process on the `max_panics`-th panic within `window`.
+    ///
+    /// All panic types are counted and the process is terminated using the defaults of [`FatalPanics`].
+    pub fn new(max_panics: NonZeroUsize, window: Duration) -> Self {
+        Self {
+            max_panics,
+            window,
+            panic_type: None,
+            exit: FatalPanics::default(),
+            panics: Default::default(),
+        }
+    }
+
+    /// Only count panics of the given type.
+    pub fn with_panic_type(self, panic_type: PanicType) -> Self {
+        Self {
+            panic_type: Some(panic_type),
+            ..self
+        }
+    }
+
+    /// Configure how the process is terminated, e.g. to allow exporters to flush or to abort the process.
+    pub fn with_exit(self, exit: FatalPanics) -> Self {
+        Self { exit, ..self }
+    }
+
+    /// Record a panic, returns `true` if the threshold was reached.
+    pub(crate) fn record(&self, panic_type: PanicType, now: Instant) -> bool {
+        if self.panic_type.is_some_and(|t| t != panic_type) {
+            return false;
+        }
+
+        // we are within the panic hook, so don't panic on a poisoned lock
+        let mut panics = self
+            .panics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        while panics
+            .front()
+            .is_some_and(|t| now.saturating_duration_since(*t) >= self.window)
+        {
+            panics.pop_front();
+        }
+        if panics.len() == self.max_panics.get() {
+            panics.pop_front();
+        }
+        panics.push_back(now);
+
+        panics.len() == self.max_panics.get()
+    }
+
+    /// Terminate the process.
+    pub(crate) fn terminate(&self) -> ! {
+        error!(
+            max_panics = self.max_panics.get(),
+            window_secs = self.window.as_secs_f64(),
+            panic_type = self.panic_type.map(|t| t.name()),
+            "Panic threshold reached, terminating process",
+        );
+        self.exit.terminate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window() {
+        let threshold = PanicThreshold::new(NonZeroUsize::new(3).unwrap(), Duration::from_secs(10));
+        let t0 = Instant::now();
+
+        assert!(!threshold.record(PanicType::Unknown, t0));
+        assert!(!threshold.record(PanicType::OffsetOverflow, t0 + Duration::from_secs(5)));
+
+        // first panic left the window
+        assert!(!threshold.record(PanicType::Unknown, t0 + Duration::from_secs(10)));
+
+        assert!(threshold.record(PanicType::Unknown, t0 + Duration::from_secs(11)));
+        assert!(threshold.record(PanicType::Unknown, t0 + Duration::from_secs(12)));
+    }
+
+    #[test]
+    fn test_panic_type() {
+        let threshold = PanicThreshold::new(NonZeroUsize::new(2).unwrap(), Duration::from_secs(10))
+            .with_panic_type(PanicType::OffsetOverflow);
+        let t0 = Instant::now();
+
+        assert!(!threshold.record(PanicType::OffsetOverflow, t0));
+        assert!(!threshold.record(PanicType::Unknown, t0));
+        assert!(!threshold.record(PanicType::Unknown, t0));
+        assert!(threshold.record(PanicType::OffsetOverflow, t0));
+    }
+}
@@ -0,0 +1,59 @@
+use std::panic::catch_unwind;
+
+use panic_logging::{test_util::PanicCapture, PanicType};
+
+#[test]
+fn test_capture_current_thread() {
+    let capture = PanicCapture::new();
+    capture.assert_count(0);
+
+    catch_unwind(|| panic!("offset overflow")).expect_err("should panic");
+    catch_unwind(|| panic!("it's {}", "bananas")).expect_err("should panic");
+
+    capture.assert_count(2);
+    capture.assert_panic_types(&[PanicType::OffsetOverflow, PanicType::Unknown]);
+    capture.assert_message_contains("bananas");
+
+    let panics = capture.panics();
+    assert_eq!(panics[0].message.as_deref(), Some("offset overflow"));
+    assert_eq!(
+        panics[0].file.as_deref(),
+        Some("panic_logging/tests/capture.rs")
+    );
+}
+
+#[test]
+fn test_capture_spawned_thread() {
+    let capture = PanicCapture::new();
+
+    std::thread::scope(|s| {
+        // not registered
+        s.spawn(|| panic!("other thread"))
+            .join()
+            .expect_err("should panic");
+
+        s.spawn(|| {
+            capture.register_in_current_thread();
+            panic!("registered thread");
+        })
+        .join()
+        .expect_err("should panic");
+    });
+
+    capture.assert_count(1);
+    capture.assert_message_contains("registered thread");
+}
+
+#[test]
+fn test_capture_dropped() {
+    let outer = PanicCapture::new();
+
+    let inner = PanicCapture::new();
+    catch_unwind(|| panic!("inner")).expect_err("should panic");
+    drop(inner);
+
+    catch_unwind(|| panic!("outer")).expect_err("should panic");
+
+    outer.assert_count(1);
+    outer.assert_message_contains("outer");
+}
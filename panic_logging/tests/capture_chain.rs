@@ -0,0 +1,22 @@
+//! Separate test binary, so the hook below is installed before the [`PanicCapture`] hook.
+use std::{
+    panic::{catch_unwind, set_hook},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use panic_logging::test_util::PanicCapture;
+
+static PREVIOUS_HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn test_capture_chains_previous_hook() {
+    set_hook(Box::new(|_| {
+        PREVIOUS_HOOK_CALLS.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    let capture = PanicCapture::new();
+    catch_unwind(|| panic!("captured")).expect_err("should panic");
+
+    capture.assert_count(1);
+    assert_eq!(PREVIOUS_HOOK_CALLS.load(Ordering::SeqCst), 1);
+}
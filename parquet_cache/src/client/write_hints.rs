@@ -149,6 +149,7 @@ mod test {
             created_at: Timestamp::new(1234),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
             max_l0_created_at: Timestamp::new(1234),
+            column_stats: Default::default(),
         }
     }
 
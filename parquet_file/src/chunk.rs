@@ -82,9 +82,6 @@ impl ParquetChunk {
 
     /// return time range
     pub fn timestamp_min_max(&self) -> TimestampMinMax {
-        TimestampMinMax {
-            min: self.parquet_file.min_time.get(),
-            max: self.parquet_file.max_time.get(),
-        }
+        self.parquet_file.timestamp_min_max()
     }
 }
@@ -89,9 +89,10 @@
 use base64::{prelude::BASE64_STANDARD, Engine};
 use bytes::Bytes;
 use data_types::{
-    ColumnId, ColumnSet, ColumnSummary, CompactionLevel, CompactionLevelProtoError, InfluxDbType,
-    NamespaceId, ObjectStoreId, ParquetFileParams, PartitionHashId, PartitionId, PartitionKey,
-    StatValues, Statistics, TableId, Timestamp,
+    ColumnId, ColumnSet, ColumnStatistics, ColumnSummary, CompactionLevel,
+    CompactionLevelProtoError, FileColumnStatistics, InfluxDbType, NamespaceId, ObjectStoreId,
+    ParquetFileParams, PartitionHashId, PartitionId, PartitionKey, StatValues, Statistics, TableId,
+    Timestamp,
 };
 use generated_types::influxdata::iox::ingester::v1 as proto;
 use iox_time::Time;
@@ -469,6 +470,12 @@ impl IoxMetadata {
             .read_statistics(&schema)
             .expect("invalid statistics");
         let columns: Vec<_> = stats.iter().map(|v| column_id_map(&v.name)).collect();
+        let column_stats = FileColumnStatistics::new(
+            columns
+                .iter()
+                .zip(&stats)
+                .map(|(id, summary)| (*id, ColumnStatistics::from(&summary.stats))),
+        );
         let time_summary = stats
             .into_iter()
             .find(|v| v.name == TIME_COLUMN_NAME)
@@ -501,6 +508,7 @@ impl IoxMetadata {
             created_at: Timestamp::from(self.creation_timestamp),
             column_set: ColumnSet::new(columns),
             max_l0_created_at: Timestamp::from(self.max_l0_created_at),
+            column_stats,
         }
     }
 
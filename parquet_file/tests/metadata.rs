@@ -1,12 +1,13 @@
 use std::{collections::HashMap, sync::Arc};
 
 use arrow::{
-    array::{ArrayRef, StringArray, TimestampNanosecondArray},
+    array::{ArrayRef, StringArray, TimestampNanosecondArray, UInt64Array},
     record_batch::RecordBatch,
 };
 use data_types::{
-    ColumnId, CompactionLevel, NamespaceId, ObjectStoreId, PartitionHashId, PartitionId,
-    PartitionKey, TableId, Timestamp, TransitionPartitionId,
+    ColumnId, ColumnStatistics, ColumnType, CompactionLevel, NamespaceId, ObjectStoreId,
+    PartitionHashId, PartitionId, PartitionKey, StatValue, Statistics, TableId, Timestamp,
+    TransitionPartitionId,
 };
 use datafusion_util::{unbounded_memory_pool, MemoryStream};
 use iox_time::Time;
@@ -437,6 +438,106 @@ async fn test_derive_parquet_file_params() {
     assert_eq!(catalog_data.min_time, Timestamp::new(1646917692000000000));
     assert_eq!(catalog_data.max_time, Timestamp::new(1653311292000000000));
     assert_eq!(catalog_data.max_l0_created_at, Timestamp::new(1234));
+
+    // The per-column statistics are derived from the parquet metadata.
+    assert_eq!(catalog_data.column_stats.len(), 2);
+    assert_eq!(
+        catalog_data.column_stats.get(ColumnId::new(1)),
+        Some(&ColumnStatistics {
+            min: Some(StatValue::String("bananas".to_string())),
+            max: Some(StatValue::String("platanos".to_string())),
+            null_count: Some(0),
+        })
+    );
+    assert_eq!(
+        catalog_data.column_stats.get(ColumnId::new(2)),
+        Some(&ColumnStatistics {
+            min: Some(StatValue::I64(1646917692000000000)),
+            max: Some(StatValue::I64(1653311292000000000)),
+            null_count: Some(0),
+        })
+    );
+}
+
+// Unsigned integer fields must be persisted without a lossy conversion to
+// signed integers, retaining values above i64::MAX.
+#[tokio::test]
+async fn test_u64_field_round_trip() {
+    let data = vec![
+        Arc::new(UInt64Array::from(vec![0, u64::MAX, 42])) as ArrayRef,
+        to_timestamp_array(&[1, 2, 3]),
+    ];
+
+    let table_id = TableId::new(3);
+    let partition_key = PartitionKey::from("potato");
+    let partition_hash_id = PartitionHashId::new(table_id, &partition_key);
+    let partition_id = TransitionPartitionId::Deterministic(partition_hash_id.clone());
+
+    let meta = IoxMetadata {
+        object_store_id: ObjectStoreId::new(),
+        creation_timestamp: Time::from_timestamp_nanos(1234),
+        namespace_id: NamespaceId::new(1),
+        namespace_name: "bananas".into(),
+        table_id,
+        table_name: "platanos".into(),
+        partition_key,
+        compaction_level: CompactionLevel::FileNonOverlapped,
+        sort_key: None,
+        max_l0_created_at: Time::from_timestamp_nanos(1234),
+    };
+
+    let schema = SchemaBuilder::new()
+        .influx_field("count", InfluxFieldType::UInteger)
+        .timestamp()
+        .build()
+        .expect("could not create schema")
+        .as_arrow();
+
+    let batch = RecordBatch::try_new(schema, data).unwrap();
+    let stream = Box::pin(MemoryStream::new(vec![batch]));
+
+    let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::default());
+    let storage = ParquetStorage::new(object_store, StorageId::from("iox"));
+
+    let (iox_parquet_meta, file_size) = storage
+        .upload(stream, &partition_id, &meta, unbounded_memory_pool())
+        .await
+        .expect("failed to serialize & persist record batch");
+
+    // The column is read back with its unsigned type and full range.
+    let decoded = iox_parquet_meta.decode().unwrap();
+    let schema = decoded.read_schema().unwrap();
+    let (influx_type, _) = schema.field_by_name("count").unwrap();
+    assert_eq!(ColumnType::from(influx_type), ColumnType::U64);
+
+    let stats = decoded.read_statistics(&schema).unwrap();
+    let summary = stats.iter().find(|v| v.name == "count").unwrap();
+    match &summary.stats {
+        Statistics::U64(v) => {
+            assert_eq!(v.min, Some(0));
+            assert_eq!(v.max, Some(u64::MAX));
+        }
+        v => panic!("unexpected statistics type: {v:?}"),
+    }
+
+    let column_id_map: HashMap<String, ColumnId> = HashMap::from([
+        ("count".into(), ColumnId::new(1)),
+        ("time".into(), ColumnId::new(2)),
+    ]);
+    let catalog_data = meta.to_parquet_file(
+        PartitionId::new(1),
+        Some(partition_hash_id),
+        file_size,
+        &iox_parquet_meta,
+        |name| *column_id_map.get(name).unwrap(),
+    );
+    assert_eq!(
+        catalog_data
+            .column_stats
+            .get(ColumnId::new(1))
+            .and_then(|v| v.max.clone()),
+        Some(StatValue::U64(u64::MAX))
+    );
 }
 
 fn to_string_array(strs: &[&str]) -> ArrayRef {
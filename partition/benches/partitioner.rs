@@ -59,6 +59,7 @@ fn partitioner_benchmarks(c: &mut Criterion) {
             part: Some(proto::template_part::Part::Bucket(Bucket {
                 tag_name: "env".to_string(),
                 num_buckets: 100,
+                ..Default::default()
             })),
         }],
         "test_fixtures/lineproto/prometheus.lp",
@@ -111,6 +112,7 @@ fn partitioner_benchmarks(c: &mut Criterion) {
             part: Some(proto::template_part::Part::Bucket(Bucket {
                 tag_name: "host".to_string(),
                 num_buckets: 100,
+                ..Default::default()
             })),
         }],
         "test_fixtures/lineproto/metrics.lp",
@@ -163,6 +165,7 @@ fn partitioner_benchmarks(c: &mut Criterion) {
             part: Some(proto::template_part::Part::Bucket(Bucket {
                 tag_name: "location".to_string(),
                 num_buckets: 100,
+                ..Default::default()
             })),
         }],
         "test_fixtures/lineproto/temperature.lp",
@@ -183,6 +186,7 @@ fn bench(
     let partition_template = TablePartitionTemplateOverride::try_new(
         Some(proto::PartitionTemplate {
             parts: partition_template,
+            ..Default::default()
         }),
         &Default::default(),
     )
@@ -216,7 +220,7 @@ fn bench(
                 || mutable_batch_input.clone(),
                 |input| {
                     for batch in input {
-                        partition_batch(&batch, &partition_template).for_each(drop);
+                        partition_batch(&batch, "table", &partition_template).for_each(drop);
                     }
                 },
                 BatchSize::NumIterations(1),
@@ -233,7 +237,7 @@ fn bench(
                 || record_batch_input.clone(),
                 |input| {
                     for batch in input {
-                        partition_batch(&batch, &partition_template).for_each(drop);
+                        partition_batch(&batch, "table", &partition_template).for_each(drop);
                     }
                 },
                 BatchSize::NumIterations(1),
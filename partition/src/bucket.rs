@@ -1,15 +1,17 @@
-use data_types::partition_template;
+use data_types::partition_template::BucketHashFunction;
 
 #[derive(Debug)]
 pub(super) struct BucketHasher {
     num_buckets: u32,
+    hash_function: BucketHashFunction,
     last_assigned_bucket: Option<u32>,
 }
 
 impl BucketHasher {
-    pub(super) fn new(num_buckets: u32) -> Self {
+    pub(super) fn new(num_buckets: u32, hash_function: BucketHashFunction) -> Self {
         Self {
             num_buckets,
+            hash_function,
             last_assigned_bucket: None,
         }
     }
@@ -17,7 +19,9 @@ impl BucketHasher {
     /// Assign a bucket for the provided `tag_value` using the [`BucketHasher`]s
     /// configuration.
     pub(super) fn assign_bucket(&mut self, tag_value: &str) -> u32 {
-        let bucket = partition_template::bucket_for_tag_value(tag_value, self.num_buckets);
+        let bucket = self
+            .hash_function
+            .bucket_for_tag_value(tag_value, self.num_buckets);
         self.last_assigned_bucket = Some(bucket);
         bucket
     }
@@ -34,7 +38,7 @@ mod tests {
 
     #[test]
     fn test_last_assigned_bucket() {
-        let mut bucketer = BucketHasher::new(10);
+        let mut bucketer = BucketHasher::new(10, BucketHashFunction::Murmur3);
         assert_eq!(bucketer.last_assigned_bucket, None);
 
         assert_eq!(bucketer.assign_bucket("foo"), 6);
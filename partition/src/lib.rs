@@ -6,13 +6,15 @@
 mod bucket;
 mod filter;
 mod strftime;
+mod time_bucket;
 mod traits;
+mod transform;
 
 use std::{borrow::Cow, num::NonZeroUsize, ops::Range};
 
 use data_types::{
     partition_template::{
-        TablePartitionTemplateOverride, TemplatePart, ENCODED_PARTITION_KEY_CHARS,
+        TablePartitionTemplateOverride, TemplatePart, TruncationMode, ENCODED_PARTITION_KEY_CHARS,
         MAXIMUM_NUMBER_OF_TEMPLATE_PARTS, PARTITION_KEY_DELIMITER, PARTITION_KEY_MAX_PART_LEN,
         PARTITION_KEY_PART_TRUNCATED, PARTITION_KEY_VALUE_EMPTY_STR, PARTITION_KEY_VALUE_NULL_STR,
     },
@@ -22,10 +24,12 @@ use hashbrown::HashMap;
 use mutable_batch::{MutableBatch, WritePayload};
 use percent_encoding::utf8_percent_encode;
 use thiserror::Error;
-use unicode_segmentation::UnicodeSegmentation;
 
 pub use self::traits::{Batch, PartitioningColumn, TimeColumnError};
-use self::{bucket::BucketHasher, strftime::StrftimeFormatter};
+use self::{
+    bucket::BucketHasher, strftime::StrftimeFormatter, time_bucket::TimeBucketer,
+    transform::TagTransform,
+};
 
 /// An error generating a partition key for a row.
 #[allow(missing_copy_implementations)]
@@ -41,6 +45,11 @@ pub enum PartitionKeyError {
     #[error("tag value partitioner does not accept input columns of type {0}")]
     TagValueNotTag(String),
 
+    /// The partition template defines a [`Template::FieldValue`] part, but the
+    /// column type is not a field.
+    #[error("field value partitioner does not accept input columns of type {0}")]
+    FieldValueNotField(String),
+
     /// A "catch all" error for when a formatter returns [`std::fmt::Error`],
     /// which contains no context.
     #[error("partition key generation error")]
@@ -48,8 +57,12 @@ pub enum PartitionKeyError {
 }
 
 /// Returns an iterator identifying consecutive ranges for a given partition key
+///
+/// `table_name` is the name of the table `batch` belongs to, rendered by
+/// [`TemplatePart::TableName`] parts.
 pub fn partition_batch<'a, T>(
     batch: &'a T,
+    table_name: &'a str,
     template: &'a TablePartitionTemplateOverride,
 ) -> impl Iterator<Item = (Result<String, PartitionKeyError>, Range<usize>)> + 'a
 where
@@ -63,7 +76,12 @@ where
         );
     }
 
-    range_encode(partition_keys(batch, template.parts()))
+    range_encode(partition_keys(
+        batch,
+        table_name,
+        template.truncation_mode(),
+        template.parts(),
+    ))
 }
 
 /// A [`TablePartitionTemplateOverride`] is made up of one of more
@@ -79,11 +97,22 @@ where
 #[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 enum Template<'a, T: PartitioningColumn> {
-    TagValue(&'a T, Option<&'a T::TagIdentityKey>),
+    /// A tag column, an optional transformation applied to its values, and
+    /// the (encoded) key part rendered for rows without a value.
+    TagValue(
+        &'a T,
+        Option<TagTransform>,
+        Cow<'a, str>,
+        Option<&'a T::TagIdentityKey>,
+    ),
     TimeFormat(&'a [i64], StrftimeFormatter<'a>),
     Bucket(&'a T, BucketHasher, Option<&'a T::TagIdentityKey>),
+    FieldValue(&'a T, Option<String>),
+    /// A constant key part, such as the table name.
+    Literal(Cow<'a, str>),
+    TimeBucket(&'a [i64], TimeBucketer),
 
-    /// This batch is missing a partitioning tag column.
+    /// This batch is missing a partitioning tag or field column.
     MissingTag,
 }
 
@@ -91,14 +120,16 @@ impl<'a, T> Template<'a, T>
 where
     T: PartitioningColumn,
 {
-    /// Renders this template to `out` for the row `idx`.
+    /// Renders this template to `out` for the row `idx`, truncating values
+    /// according to `truncation`.
     fn fmt_row<W: std::fmt::Write>(
         &mut self,
         out: &mut W,
         idx: usize,
+        truncation: TruncationMode,
     ) -> Result<(), PartitionKeyError> {
         match self {
-            Template::TagValue(col, last_key) if col.is_valid(idx) => {
+            Template::TagValue(col, transform, _, last_key) if col.is_valid(idx) => {
                 let this_key = col
                     .get_tag_identity_key(idx)
                     .ok_or_else(|| PartitionKeyError::TagValueNotTag(col.type_description()))?;
@@ -107,9 +138,15 @@ where
                 // potentially different key.
                 *last_key = Some(this_key);
 
-                out.write_str(encode_key_part(col.get_tag_value(this_key).unwrap()).as_ref())?
+                let value = col.get_tag_value(this_key).unwrap();
+                match transform {
+                    Some(transform) => out
+                        .write_str(encode_key_part(&transform.apply(value), truncation).as_ref())?,
+                    None => out.write_str(encode_key_part(value, truncation).as_ref())?,
+                }
             }
             Template::TimeFormat(t, fmt) => fmt.render(t[idx], out)?,
+            Template::TimeBucket(t, bucketer) => bucketer.render(t[idx], out)?,
             Template::Bucket(col, bucketer, last_key) if col.is_valid(idx) => {
                 let this_key = col
                     .get_tag_identity_key(idx)
@@ -125,11 +162,11 @@ where
             }
             // Either a tag that has no value for this given row index, or the
             // batch does not contain this tag at all.
-            Template::TagValue(_, last_key) => {
+            Template::TagValue(_, _, null_value, last_key) => {
                 // This row doesn't have a tag value, which should be carried
                 // forwards to be checked against the next row.
                 *last_key = None;
-                out.write_str(PARTITION_KEY_VALUE_NULL_STR)?
+                out.write_str(null_value)?
             }
             // Either a tag that has no value for this given row index, or the
             // batch does not contain this tag at all.
@@ -139,6 +176,23 @@ where
                 *last_key = None;
                 out.write_str(PARTITION_KEY_VALUE_NULL_STR)?
             }
+            Template::FieldValue(col, last_value) if col.is_valid(idx) => {
+                let this_value = col
+                    .get_field_value(idx)
+                    .ok_or_else(|| PartitionKeyError::FieldValueNotField(col.type_description()))?;
+
+                out.write_str(encode_key_part(&this_value, truncation).as_ref())?;
+
+                // Update the "is identical" tracking value for this new,
+                // potentially different value.
+                *last_value = Some(this_value.into_owned());
+            }
+            // A field that has no value for this given row index.
+            Template::FieldValue(_, last_value) => {
+                *last_value = None;
+                out.write_str(PARTITION_KEY_VALUE_NULL_STR)?
+            }
+            Template::Literal(value) => out.write_str(value)?,
             Template::MissingTag => out.write_str(PARTITION_KEY_VALUE_NULL_STR)?,
         }
 
@@ -149,7 +203,9 @@ where
     /// identical to the last generated key.
     fn is_identical(&mut self, idx: usize) -> bool {
         match self {
-            Template::TagValue(col, last_key) if col.is_valid(idx) => {
+            Template::TagValue(col, _, _, last_key) if col.is_valid(idx) => {
+                // Identical tag values always render identically, regardless
+                // of any transformation.
                 let this_key = match col.get_tag_identity_key(idx) {
                     Some(key) => key,
                     // This is an error, but for the purposes of identical checks,
@@ -204,11 +260,28 @@ where
                     None => false,
                 }
             }
+            Template::FieldValue(col, last_value) if col.is_valid(idx) => {
+                // Field values are not dictionary encoded, so the rendered
+                // values are compared. An invalid column type is treated as
+                // not identical, causing the error to be raised when
+                // formatting is attempted.
+                match (col.get_field_value(idx), last_value) {
+                    (Some(this_value), Some(last_value)) => this_value == last_value.as_str(),
+                    _ => false,
+                }
+            }
             // The last row did not contain this key, and neither does this.
-            Template::TagValue(_, None) | Template::Bucket(_, _, None) => true,
+            Template::TagValue(_, _, _, None)
+            | Template::Bucket(_, _, None)
+            | Template::FieldValue(_, None) => true,
             // The last row did contain a key, but this one does not (therefore
             // it differs).
-            Template::TagValue(_, Some(_)) | Template::Bucket(_, _, Some(_)) => false,
+            Template::TagValue(_, _, _, Some(_))
+            | Template::Bucket(_, _, Some(_))
+            | Template::FieldValue(_, Some(_)) => false,
+            Template::TimeBucket(t, bucketer) => bucketer.equals_last(t[idx]),
+            // A constant is the same for all rows of a batch.
+            Template::Literal(_) => true,
             // The batch does not contain this tag at all - it always matches
             // with the previous row.
             Template::MissingTag => true,
@@ -216,7 +289,7 @@ where
     }
 }
 
-fn encode_key_part(s: &str) -> Cow<'_, str> {
+fn encode_key_part(s: &str, truncation: TruncationMode) -> Cow<'_, str> {
     // Encode reserved characters and non-ascii characters.
     let as_str: Cow<'_, str> = utf8_percent_encode(s, &ENCODED_PARTITION_KEY_CHARS).into();
 
@@ -228,9 +301,9 @@ fn encode_key_part(s: &str) -> Cow<'_, str> {
             // truncated.
             //
             // Truncation of unicode strings can be tricky - this implementation
-            // avoids splitting unicode code-points nor graphemes. See the
-            // partition_template module docs in data_types before altering
-            // this.
+            // never splits unicode code-points, and avoids splitting graphemes
+            // unless the template opts in. See the partition_template module
+            // docs in data_types before altering this.
 
             // Preallocate the string to hold the long partition key part.
             let mut buf = String::with_capacity(PARTITION_KEY_MAX_PART_LEN);
@@ -239,14 +312,15 @@ fn encode_key_part(s: &str) -> Cow<'_, str> {
             // fortunately this is an uncommon path.
             //
             // Walk the string, encoding each grapheme (which includes spaces)
-            // individually, tracking the total length of the encoded string.
-            // Once it hits 199 bytes, stop and append a #.
+            // or code-point individually, tracking the total length of the
+            // encoded string. Once it hits 199 bytes, stop and append a #.
 
             let mut bytes = 0;
-            s.graphemes(true)
+            truncation
+                .segments(s)
                 .map(|v| Cow::from(utf8_percent_encode(v, &ENCODED_PARTITION_KEY_CHARS)))
                 .take_while(|v| {
-                    bytes += v.len(); // Byte length of encoded grapheme
+                    bytes += v.len(); // Byte length of encoded segment
                     bytes < PARTITION_KEY_MAX_PART_LEN
                 })
                 .for_each(|v| buf.push_str(v.as_ref()));
@@ -261,13 +335,16 @@ fn encode_key_part(s: &str) -> Cow<'_, str> {
     }
 }
 
-/// Returns an iterator of partition keys for the given table batch.
+/// Returns an iterator of partition keys for the given table batch, truncating
+/// key parts according to `truncation`.
 ///
 /// This function performs deduplication on returned keys; the returned iterator
 /// yields [`Some`] containing the partition key string when a new key is
 /// generated, and [`None`] when the generated key would equal the last key.
 fn partition_keys<'a, T>(
     batch: &'a T,
+    table_name: &'a str,
+    truncation: TruncationMode,
     template_parts: impl Iterator<Item = TemplatePart<'a>>,
 ) -> impl Iterator<Item = Option<Result<String, PartitionKeyError>>> + 'a
 where
@@ -279,16 +356,56 @@ where
     // Convert TemplatePart into an ordered array of Template
     let mut template = template_parts
         .map(|v| match v {
-            TemplatePart::TagValue(col_name) => batch
-                .column(col_name)
-                .map_or_else(|| Template::MissingTag, |v| Template::TagValue(v, None)),
-            TemplatePart::TimeFormat(fmt) => {
-                Template::TimeFormat(time, StrftimeFormatter::new(fmt))
+            TemplatePart::TagValue(col_name) => batch.column(col_name).map_or_else(
+                || Template::MissingTag,
+                |v| Template::TagValue(v, None, Cow::Borrowed(PARTITION_KEY_VALUE_NULL_STR), None),
+            ),
+            TemplatePart::TimeFormat(fmt) => Template::TimeFormat(
+                time,
+                StrftimeFormatter::new(fmt).with_truncation(truncation),
+            ),
+            TemplatePart::Bucket(col_name, num_buckets, hash_function) => {
+                batch.column(col_name).map_or_else(
+                    || Template::MissingTag,
+                    |v| Template::Bucket(v, BucketHasher::new(num_buckets, hash_function), None),
+                )
             }
-            TemplatePart::Bucket(col_name, num_buckets) => batch.column(col_name).map_or_else(
+            TemplatePart::FieldValue(col_name) => batch
+                .column(col_name)
+                .map_or_else(|| Template::MissingTag, |v| Template::FieldValue(v, None)),
+            TemplatePart::TableName => Template::Literal(encode_key_part(table_name, truncation)),
+            TemplatePart::TimeBucket(width) => Template::TimeBucket(time, TimeBucketer::new(width)),
+            TemplatePart::TagValuePrefix(col_name, n) => batch.column(col_name).map_or_else(
                 || Template::MissingTag,
-                |v| Template::Bucket(v, BucketHasher::new(num_buckets), None),
+                |v| {
+                    Template::TagValue(
+                        v,
+                        Some(TagTransform::Prefix(n)),
+                        Cow::Borrowed(PARTITION_KEY_VALUE_NULL_STR),
+                        None,
+                    )
+                },
             ),
+            TemplatePart::TagValueLowercase(col_name) => batch.column(col_name).map_or_else(
+                || Template::MissingTag,
+                |v| {
+                    Template::TagValue(
+                        v,
+                        Some(TagTransform::Lowercase),
+                        Cow::Borrowed(PARTITION_KEY_VALUE_NULL_STR),
+                        None,
+                    )
+                },
+            ),
+            TemplatePart::TagValueWithDefault(col_name, default) => {
+                let default = encode_key_part(default, truncation);
+                match batch.column(col_name) {
+                    Some(v) => Template::TagValue(v, None, default, None),
+                    // The batch does not contain this tag at all, so the
+                    // default is rendered for every row.
+                    None => Template::Literal(default),
+                }
+            }
         })
         .collect::<Vec<_>>();
 
@@ -305,7 +422,12 @@ where
     //
     // Row 0 is guaranteed to exist, otherwise attempting to read the time
     // column above would have caused a panic (no rows -> no time column).
-    let first = std::iter::once(Some(evaluate_template(&mut template, &mut last_len, 0)));
+    let first = std::iter::once(Some(evaluate_template(
+        &mut template,
+        truncation,
+        &mut last_len,
+        0,
+    )));
 
     // The subsequent rows in a batch may generate the same key, and therefore a
     // dedupe check is used before allocating & populating the partition key.
@@ -316,7 +438,12 @@ where
             return None;
         }
 
-        Some(evaluate_template(&mut template, &mut last_len, idx))
+        Some(evaluate_template(
+            &mut template,
+            truncation,
+            &mut last_len,
+            idx,
+        ))
     });
 
     first.chain(rest)
@@ -329,6 +456,7 @@ where
 /// This method panics if `idx` exceeds the number of rows in the batch.
 fn evaluate_template<T: PartitioningColumn>(
     template: &mut [Template<'_, T>],
+    truncation: TruncationMode,
     last_len: &mut usize,
     idx: usize,
 ) -> Result<String, PartitionKeyError> {
@@ -338,7 +466,7 @@ fn evaluate_template<T: PartitioningColumn>(
     // Evaluate each template part for this row
     for (col_idx, col) in template.iter_mut().enumerate() {
         // Evaluate the formatter for this template part against the row.
-        col.fmt_row(&mut buf, idx)?;
+        col.fmt_row(&mut buf, idx, truncation)?;
 
         // If this isn't the last element in the template, insert a field
         // delimiter.
@@ -487,16 +615,18 @@ impl<'a> PartitionWrite<'a> {
     }
 
     /// Create a collection of [`PartitionWrite`] indexed by partition key
-    /// from a [`MutableBatch`] and [`TablePartitionTemplateOverride`]
+    /// from a [`MutableBatch`] of the table `table_name` and
+    /// [`TablePartitionTemplateOverride`]
     pub fn partition(
         batch: &'a MutableBatch,
+        table_name: &str,
         partition_template: &TablePartitionTemplateOverride,
     ) -> Result<HashMap<PartitionKey, Self>, PartitionWriteError> {
         use hashbrown::hash_map::Entry;
         let time = batch.time_column()?;
 
         let mut partition_ranges = HashMap::new();
-        for (partition, range) in partition_batch(batch, partition_template) {
+        for (partition, range) in partition_batch(batch, table_name, partition_template) {
             let row_count = NonZeroUsize::new(range.end - range.start).unwrap();
             let (min_timestamp, max_timestamp) = min_max_time(&time[range.clone()]);
 
@@ -541,27 +671,32 @@ fn min_max_time(col: &[i64]) -> (i64, i64) {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::{collections::HashMap, time::Duration};
 
     use super::*;
 
     use assert_matches::assert_matches;
     use chrono::{format::StrftimeItems, DateTime, Datelike, Days, TimeZone, Utc};
     use data_types::partition_template::{
-        build_column_values, test_table_partition_override, ColumnValue,
+        build_column_values, test_table_partition_override, BucketHashFunction, ColumnValue,
+        PartitionKeyBuilder,
     };
+    use generated_types::influxdata::iox::partition_template::v1 as proto;
     use mutable_batch::{writer::Writer, MutableBatch};
     use proptest::{prelude::*, prop_compose, proptest, strategy::Strategy};
     use rand::prelude::*;
     use schema::{Projection, TIME_COLUMN_NAME};
     use test_helpers::assert_error;
 
+    /// The table name used for partitioning in tests.
+    const TABLE_NAME: &str = "platanos";
+
     #[test]
     fn return_err_if_no_time_column() {
         let batch = MutableBatch::new();
         let table_partition_template = Default::default();
         assert_error!(
-            PartitionWrite::partition(&batch, &table_partition_template),
+            PartitionWrite::partition(&batch, TABLE_NAME, &table_partition_template),
             PartitionWriteError::TimeColumn(TimeColumnError::NotFound),
         );
     }
@@ -615,7 +750,7 @@ mod tests {
         template_parts: impl Iterator<Item = TemplatePart<'a>>,
     ) -> Result<Vec<String>, PartitionKeyError> {
         let mut last_ret = None;
-        partition_keys(batch, template_parts)
+        partition_keys(batch, TABLE_NAME, TruncationMode::default(), template_parts)
             .map(|v| match v {
                 Some(this) => {
                     last_ret = Some(this.clone());
@@ -644,10 +779,15 @@ mod tests {
 
         let template_parts =
             TablePartitionTemplateOverride::try_new(None, &Default::default()).unwrap();
-        let keys: Vec<_> = partition_keys(&batch, template_parts.parts())
-            .map(|v| v.expect("non-identical consecutive keys"))
-            .collect::<Result<Vec<_>, _>>()
-            .unwrap();
+        let keys: Vec<_> = partition_keys(
+            &batch,
+            TABLE_NAME,
+            TruncationMode::default(),
+            template_parts.parts(),
+        )
+        .map(|v| v.expect("non-identical consecutive keys"))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
 
         assert_eq!(keys, vec!["1970-01-01".to_string()])
     }
@@ -663,7 +803,7 @@ mod tests {
             TemplatePart::TagValue("bananas"),
         ]);
 
-        let keys: Vec<_> = partition_batch(&batch, &template_parts).collect::<Vec<_>>();
+        let keys: Vec<_> = partition_batch(&batch, TABLE_NAME, &template_parts).collect::<Vec<_>>();
         assert_eq!(keys, vec![])
     }
 
@@ -738,16 +878,21 @@ mod tests {
         let template_parts = [
             TemplatePart::TimeFormat("%Y-%m-%d %H:%M:%S"),
             TemplatePart::TagValue("region"),
-            TemplatePart::Bucket("device", 10),
+            TemplatePart::Bucket("device", 10, BucketHashFunction::Murmur3),
             TemplatePart::TagValue("bananas"), // column not present
         ];
 
         writer.commit();
 
-        let keys: Vec<_> = partition_keys(&batch, template_parts.clone().into_iter())
-            .map(|v| v.expect("non-identical consecutive keys"))
-            .collect::<Result<Vec<_>, _>>()
-            .unwrap();
+        let keys: Vec<_> = partition_keys(
+            &batch,
+            TABLE_NAME,
+            TruncationMode::default(),
+            template_parts.clone().into_iter(),
+        )
+        .map(|v| v.expect("non-identical consecutive keys"))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
 
         assert_eq!(
             keys,
@@ -762,10 +907,15 @@ mod tests {
 
         let record_batch = batch.to_arrow(Projection::All).unwrap();
 
-        let keys: Vec<_> = partition_keys(&record_batch, template_parts.into_iter())
-            .map(|v| v.expect("non-identical consecutive keys"))
-            .collect::<Result<Vec<_>, _>>()
-            .unwrap();
+        let keys: Vec<_> = partition_keys(
+            &record_batch,
+            TABLE_NAME,
+            TruncationMode::default(),
+            template_parts.into_iter(),
+        )
+        .map(|v| v.expect("non-identical consecutive keys"))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
 
         assert_eq!(
             keys,
@@ -781,7 +931,7 @@ mod tests {
 
     #[test]
     fn test_bucket_fixture() {
-        let mut bucketer = BucketHasher::new(10);
+        let mut bucketer = BucketHasher::new(10, BucketHashFunction::Murmur3);
         assert_eq!(bucketer.assign_bucket("foo"), 6);
         assert_eq!(bucketer.last_assigned_bucket(), Some(6));
         assert_eq!(bucketer.assign_bucket("bat"), 5);
@@ -834,13 +984,18 @@ mod tests {
         let template_parts = [
             TemplatePart::TimeFormat("%Y-%m-%d %H:%M:%S"),
             TemplatePart::TagValue("region"),
-            TemplatePart::Bucket("device", 10),
+            TemplatePart::Bucket("device", 10, BucketHashFunction::Murmur3),
             TemplatePart::TagValue("bananas"), // column not present
         ];
 
         writer.commit();
 
-        let mut iter = partition_keys(&batch, template_parts.into_iter());
+        let mut iter = partition_keys(
+            &batch,
+            TABLE_NAME,
+            TruncationMode::default(),
+            template_parts.into_iter(),
+        );
 
         assert_eq!(
             iter.next().unwrap(),
@@ -905,7 +1060,11 @@ mod tests {
             )
             .unwrap();
 
-        let template_parts = [TemplatePart::Bucket("region", 10)];
+        let template_parts = [TemplatePart::Bucket(
+            "region",
+            10,
+            BucketHashFunction::Murmur3,
+        )];
 
         writer.commit();
 
@@ -913,6 +1072,302 @@ mod tests {
         assert_matches::assert_matches!(got, Err(PartitionKeyError::TagValueNotTag(_)));
     }
 
+    #[test]
+    fn test_field_value() {
+        let mut batch = MutableBatch::new();
+        let mut writer = Writer::new(&mut batch, 6);
+
+        writer
+            .write_time("time", vec![1, 2, 3, 4, 5, 6].into_iter())
+            .unwrap();
+
+        writer
+            .write_string(
+                "status",
+                Some(&[0b00111011]),
+                vec!["ok", "ok", "failed|retry", "ok", "ok"].into_iter(),
+            )
+            .unwrap();
+
+        writer
+            .write_i64(
+                "code",
+                Some(&[0b00111111]),
+                vec![200, 200, 200, 500, 500, 500].into_iter(),
+            )
+            .unwrap();
+
+        writer
+            .write_bool("cached", Some(&[0b00100000]), vec![true].into_iter())
+            .unwrap();
+
+        let template_parts = [
+            TemplatePart::FieldValue("status"),
+            TemplatePart::FieldValue("code"),
+            TemplatePart::FieldValue("cached"),
+            TemplatePart::FieldValue("bananas"), // column not present
+        ];
+
+        writer.commit();
+
+        let want = vec![
+            "ok|200|!|!".to_string(),
+            "ok|200|!|!".to_string(),
+            "!|200|!|!".to_string(),
+            "failed%7Cretry|500|!|!".to_string(),
+            "ok|500|!|!".to_string(),
+            "ok|500|true|!".to_string(),
+        ];
+
+        let keys = generate_denormalised_keys(&batch, template_parts.clone().into_iter()).unwrap();
+        assert_eq!(keys, want);
+
+        let record_batch = batch.to_arrow(Projection::All).unwrap();
+        let keys = generate_denormalised_keys(&record_batch, template_parts.into_iter()).unwrap();
+        assert_eq!(keys, want);
+
+        // Reverse the encoding.
+        let template = test_table_partition_override(vec![
+            TemplatePart::FieldValue("status"),
+            TemplatePart::FieldValue("code"),
+            TemplatePart::FieldValue("cached"),
+            TemplatePart::FieldValue("bananas"),
+        ]);
+        let reversed = build_column_values(&template, &keys[3]).collect::<Vec<_>>();
+        assert_eq!(
+            reversed,
+            vec![
+                ("status", identity("failed|retry")),
+                ("code", identity("500"))
+            ]
+        );
+    }
+
+    #[test]
+    fn field_value_on_tags_errors() {
+        let mut batch = MutableBatch::new();
+        let mut writer = Writer::new(&mut batch, 2);
+
+        writer.write_time("time", vec![1, 2].into_iter()).unwrap();
+
+        writer
+            .write_tag(
+                "region",
+                Some(&[0b00000011]),
+                vec!["west", "east"].into_iter(),
+            )
+            .unwrap();
+
+        let template_parts = [TemplatePart::FieldValue("region")];
+
+        writer.commit();
+
+        let got: Result<Vec<_>, _> = generate_denormalised_keys(&batch, template_parts.into_iter());
+        assert_matches::assert_matches!(got, Err(PartitionKeyError::FieldValueNotField(_)));
+    }
+
+    #[test]
+    fn test_table_name() {
+        let mut batch = MutableBatch::new();
+        let mut writer = Writer::new(&mut batch, 3);
+
+        writer
+            .write_time("time", vec![1, 2, 3].into_iter())
+            .unwrap();
+
+        writer
+            .write_tag(
+                "region",
+                Some(&[0b00000111]),
+                vec!["west", "west", "east"].into_iter(),
+            )
+            .unwrap();
+
+        writer.commit();
+
+        let template_parts = [TemplatePart::TableName, TemplatePart::TagValue("region")];
+
+        // The table name is encoded like any other key part.
+        let keys: Vec<_> = partition_keys(
+            &batch,
+            "cpu|mem",
+            TruncationMode::default(),
+            template_parts.clone().into_iter(),
+        )
+        .map(Option::transpose)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+        assert_eq!(
+            keys,
+            vec![
+                Some("cpu%7Cmem|west".to_string()),
+                None,
+                Some("cpu%7Cmem|east".to_string()),
+            ]
+        );
+
+        let record_batch = batch.to_arrow(Projection::All).unwrap();
+        let keys =
+            generate_denormalised_keys(&record_batch, template_parts.clone().into_iter()).unwrap();
+        assert_eq!(
+            keys,
+            vec!["platanos|west", "platanos|west", "platanos|east"]
+        );
+
+        // The table name is not a column, so it is skipped when reversing.
+        let template = test_table_partition_override(template_parts.to_vec());
+        let reversed = build_column_values(&template, &keys[2]).collect::<Vec<_>>();
+        assert_eq!(reversed, vec![("region", identity("east"))]);
+    }
+
+    #[test]
+    fn test_tag_value_transforms() {
+        let mut batch = MutableBatch::new();
+        let mut writer = Writer::new(&mut batch, 4);
+
+        writer
+            .write_time("time", vec![1, 2, 3, 4].into_iter())
+            .unwrap();
+
+        writer
+            .write_tag(
+                "region",
+                Some(&[0b00001111]),
+                vec!["eu-west", "eu-east", "us|west", "US-EAST"].into_iter(),
+            )
+            .unwrap();
+
+        writer
+            .write_tag(
+                "host",
+                Some(&[0b00001011]),
+                vec!["Bananas", "BANANAS", "Platanos"].into_iter(),
+            )
+            .unwrap();
+
+        writer.commit();
+
+        let template_parts = [
+            TemplatePart::TagValuePrefix("region", 3),
+            TemplatePart::TagValueLowercase("host"),
+            TemplatePart::TagValueLowercase("region"),
+        ];
+
+        let want = vec![
+            "eu-|bananas|eu-west".to_string(),
+            "eu-|bananas|eu-east".to_string(),
+            "us%7C|!|us%7Cwest".to_string(),
+            "US-|platanos|us-east".to_string(),
+        ];
+
+        let keys = generate_denormalised_keys(&batch, template_parts.clone().into_iter()).unwrap();
+        assert_eq!(keys, want);
+
+        let record_batch = batch.to_arrow(Projection::All).unwrap();
+        let keys = generate_denormalised_keys(&record_batch, template_parts.into_iter()).unwrap();
+        assert_eq!(keys, want);
+
+        // Reversing a prefix yields a prefix, and lowercase parts are skipped.
+        let template = test_table_partition_override(vec![
+            TemplatePart::TagValuePrefix("region", 3),
+            TemplatePart::TagValueLowercase("host"),
+        ]);
+        let reversed = build_column_values(&template, "eu-|bananas").collect::<Vec<_>>();
+        assert_eq!(reversed, vec![("region", prefix("eu-"))]);
+    }
+
+    #[test]
+    fn test_tag_value_with_default() {
+        let mut batch = MutableBatch::new();
+        let mut writer = Writer::new(&mut batch, 3);
+
+        writer
+            .write_time("time", vec![1, 2, 3].into_iter())
+            .unwrap();
+
+        writer
+            .write_tag(
+                "region",
+                Some(&[0b00000101]),
+                vec!["west", "unknown"].into_iter(),
+            )
+            .unwrap();
+
+        writer.commit();
+
+        let template_parts = [
+            TemplatePart::TagValueWithDefault("region", "unknown"),
+            TemplatePart::TagValueWithDefault("bananas", "no|bananas"), // column not present
+            TemplatePart::TagValue("platanos"),                         // column not present
+        ];
+
+        let want = vec![
+            "west|no%7Cbananas|!".to_string(),
+            "unknown|no%7Cbananas|!".to_string(),
+            "unknown|no%7Cbananas|!".to_string(),
+        ];
+
+        let keys = generate_denormalised_keys(&batch, template_parts.clone().into_iter()).unwrap();
+        assert_eq!(keys, want);
+
+        let record_batch = batch.to_arrow(Projection::All).unwrap();
+        let keys =
+            generate_denormalised_keys(&record_batch, template_parts.clone().into_iter()).unwrap();
+        assert_eq!(keys, want);
+
+        // A key part matching the default is ambiguous, and not reversed.
+        let template = test_table_partition_override(template_parts.to_vec());
+        let reversed = build_column_values(&template, &keys[0]).collect::<Vec<_>>();
+        assert_eq!(reversed, vec![("region", identity("west"))]);
+        let reversed = build_column_values(&template, &keys[1]).collect::<Vec<_>>();
+        assert!(reversed.is_empty());
+    }
+
+    #[test]
+    fn test_time_bucket() {
+        const HOUR: i64 = 60 * 60 * 1_000_000_000;
+
+        let mut batch = MutableBatch::new();
+        let mut writer = Writer::new(&mut batch, 4);
+
+        writer
+            .write_time("time", vec![0, 5 * HOUR, 6 * HOUR, 13 * HOUR].into_iter())
+            .unwrap();
+
+        writer.commit();
+
+        let template_parts = [TemplatePart::TimeBucket(Duration::from_secs(6 * 60 * 60))];
+
+        let want = vec![
+            "1970-01-01T00:00:00Z".to_string(),
+            "1970-01-01T00:00:00Z".to_string(),
+            "1970-01-01T06:00:00Z".to_string(),
+            "1970-01-01T12:00:00Z".to_string(),
+        ];
+
+        let keys = generate_denormalised_keys(&batch, template_parts.clone().into_iter()).unwrap();
+        assert_eq!(keys, want);
+
+        let record_batch = batch.to_arrow(Projection::All).unwrap();
+        let keys =
+            generate_denormalised_keys(&record_batch, template_parts.clone().into_iter()).unwrap();
+        assert_eq!(keys, want);
+
+        // Reverse the encoding.
+        let template = test_table_partition_override(template_parts.to_vec());
+        let reversed = build_column_values(&template, &keys[2]).collect::<Vec<_>>();
+        assert_eq!(
+            reversed,
+            vec![(
+                TIME_COLUMN_NAME,
+                ColumnValue::Datetime {
+                    begin: Utc.timestamp_nanos(6 * HOUR),
+                    end: Utc.timestamp_nanos(12 * HOUR),
+                }
+            )]
+        );
+    }
+
     fn identity<'a, T>(s: T) -> ColumnValue<'a>
     where
         T: Into<Cow<'a, str>>,
@@ -1003,7 +1458,7 @@ mod tests {
             TemplatePart::TimeFormat("%Y"),
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 5),
+            TemplatePart::Bucket("c", 5, BucketHashFunction::Murmur3),
         ],
         tags = [
             ("a", "bananas"),
@@ -1045,7 +1500,7 @@ mod tests {
 
     test_partition_key!(
         single_bucket_template_tag_not_present,
-        template = [TemplatePart::Bucket("a", 10)],
+        template = [TemplatePart::Bucket("a", 10, BucketHashFunction::Murmur3)],
         tags = [("b", "bananas")],
         want_key = "!",
         want_reversed_tags = []
@@ -1061,18 +1516,30 @@ mod tests {
 
     test_partition_key!(
         single_bucket_template_tag_empty,
-        template = [TemplatePart::Bucket("a", 10)],
+        template = [TemplatePart::Bucket("a", 10, BucketHashFunction::Murmur3)],
         tags = [("a", "")],
         want_key = "0",
         want_reversed_tags = [("a", bucket(0))]
     );
 
+    // A tag rendered both as a value and a bucket.
+    test_partition_key!(
+        paired_tag_value_and_bucket,
+        template = [
+            TemplatePart::TagValue("a"),
+            TemplatePart::Bucket("a", 10, BucketHashFunction::Murmur3)
+        ],
+        tags = [("a", "bananas")],
+        want_key = "bananas|1",
+        want_reversed_tags = [("a", identity("bananas")), ("a", bucket(1))]
+    );
+
     test_partition_key!(
         missing_tag,
         template = [
             TemplatePart::TagValue("a"),
             TemplatePart::TagValue("b"),
-            TemplatePart::Bucket("c", 10)
+            TemplatePart::Bucket("c", 10, BucketHashFunction::Murmur3)
         ],
         tags = [("a", "bananas")],
         want_key = "bananas|!|!",
@@ -1344,6 +1811,46 @@ mod tests {
         want_reversed_tags = [("a", identity(format!("{}நி", "A".repeat(182))))]
     );
 
+    /// Templates opting in to code-point truncation may split a grapheme,
+    /// retaining a byte-wise prefix of the value.
+    #[test]
+    fn test_truncate_at_code_point() {
+        let mut batch = MutableBatch::new();
+        let mut writer = Writer::new(&mut batch, 1);
+
+        let value = format!("{}நிbananas", "A".repeat(182));
+        writer.write_time("time", vec![1].into_iter()).unwrap();
+        writer
+            .write_tag("a", Some(&[0b00000001]), vec![value.as_str()].into_iter())
+            .unwrap();
+        writer.commit();
+
+        let template = TablePartitionTemplateOverride::try_new(
+            Some(proto::PartitionTemplate {
+                parts: vec![proto::TemplatePart {
+                    part: Some(proto::template_part::Part::TagValue("a".into())),
+                }],
+                truncate_at_code_point: true,
+                version: 1,
+            }),
+            &Default::default(),
+        )
+        .unwrap();
+
+        let keys = partition_batch(&batch, TABLE_NAME, &template)
+            .map(|(key, _)| key.unwrap())
+            .collect::<Vec<_>>();
+
+        // Only the first code-point of the "நி" grapheme fits within the
+        // limit (see truncate_within_grapheme_1 for the default behaviour).
+        let want_prefix = format!("{}ந", "A".repeat(182));
+        assert_eq!(keys, [format!("{}%E0%AE%A8#", "A".repeat(182))]);
+        assert!(value.as_bytes().starts_with(want_prefix.as_bytes()));
+
+        let reversed = build_column_values(&template, &keys[0]).collect::<Vec<_>>();
+        assert_eq!(reversed, [("a", prefix(want_prefix))]);
+    }
+
     /// A test using an invalid strftime format string.
     #[test]
     fn test_invalid_strftime() {
@@ -1361,9 +1868,14 @@ mod tests {
             .collect::<Vec<_>>();
         let template = test_table_partition_override(template);
 
-        let ret = partition_keys(&batch, template.parts())
-            .map(|v| v.expect("non-identical consecutive keys"))
-            .collect::<Result<Vec<_>, _>>();
+        let ret = partition_keys(
+            &batch,
+            TABLE_NAME,
+            TruncationMode::default(),
+            template.parts(),
+        )
+        .map(|v| v.expect("non-identical consecutive keys"))
+        .collect::<Result<Vec<_>, _>>();
 
         assert_matches!(ret, Err(PartitionKeyError::InvalidStrftime));
     }
@@ -1379,7 +1891,7 @@ mod tests {
                 .collect(),
         );
 
-        let _ = partition_batch(&MutableBatch::new(), &template);
+        let _ = partition_batch(&MutableBatch::new(), TABLE_NAME, &template);
     }
 
     // These values are arbitrarily chosen when building an input to the
@@ -1387,7 +1899,7 @@ mod tests {
 
     // Arbitrary tag names are selected from this set of candidates (to ensure
     // there's always some overlap, rather than truly random strings).
-    const TEST_TAG_NAME_SET: &[&str] = &["A", "B", "C", "D", "E", "F"];
+    const TEST_TAG_NAME_SET: &[&str] = &["A", "B", "C", "D", "E", "F", "G"];
 
     // Arbitrary template parts are selected from this set.
     const TEST_TEMPLATE_PARTS: &[TemplatePart<'static>] = &[
@@ -1404,9 +1916,10 @@ mod tests {
         TemplatePart::TagValue("my_tag"),
         TemplatePart::TagValue("my|tag"),
         TemplatePart::TagValue("%%%%|!!!!|"),
-        TemplatePart::Bucket("D", 10),
-        TemplatePart::Bucket("E", 100),
-        TemplatePart::Bucket("F", 1000),
+        TemplatePart::Bucket("D", 10, BucketHashFunction::Murmur3),
+        TemplatePart::Bucket("E", 100, BucketHashFunction::Murmur3),
+        TemplatePart::Bucket("F", 1000, BucketHashFunction::Murmur3),
+        TemplatePart::Bucket("G", 1000, BucketHashFunction::XxHash64),
     ];
 
     prop_compose! {
@@ -1538,14 +2051,14 @@ mod tests {
                     let end = begin + Days::new(1);
                     Some((TIME_COLUMN_NAME, ExpectedColumnValue::TSRange(begin, end)))
                 }
-                TemplatePart::Bucket(col_name, num_buckets) if tag_values.contains_key(col_name) => {
+                TemplatePart::Bucket(col_name, num_buckets, hash_function) if tag_values.contains_key(col_name) => {
                     // Hash-bucketing is not fully-reversible from value to
                     // tag-name (intentionally so, it makes it much simpler to
                     // implement).
                     //
                     // The test must assign buckets as they are when the
                     // partition key is rendered.
-                    let want_bucket = BucketHasher::new(num_buckets).assign_bucket(tag_values.get(col_name).unwrap());
+                    let want_bucket = BucketHasher::new(num_buckets, hash_function).assign_bucket(tag_values.get(col_name).unwrap());
                     Some((col_name, ExpectedColumnValue::Bucket(want_bucket)))
                 }
                 _ => None,
@@ -1596,6 +2109,23 @@ mod tests {
             }
         }
 
+        /// A property test that asserts [`PartitionKeyBuilder`] renders
+        /// values identically to the partitioner.
+        #[test]
+        fn prop_key_builder_equivalence(values in prop::collection::vec(any::<String>(), 1..5)) {
+            let mut builder = PartitionKeyBuilder::default();
+            for v in &values {
+                builder.push_value(v);
+            }
+
+            let want = values
+                .iter()
+                .map(|v| encode_key_part(v.as_str(), TruncationMode::default()))
+                .collect::<Vec<_>>()
+                .join(&PARTITION_KEY_DELIMITER.to_string());
+            assert_eq!(builder.as_str(), want);
+        }
+
         /// A property test that asserts the partitioner tolerates (does not
         /// panic) randomised, potentially invalid strftime formatter strings.
         #[test]
@@ -1632,7 +2162,7 @@ mod tests {
                 .unwrap();
 
             writer.commit();
-            let ret = partition_keys(&batch, template.parts())
+            let ret = partition_keys(&batch, TABLE_NAME, TruncationMode::default(), template.parts())
                 .map(|v| v.expect("non-identical consecutive keys"))
                 .collect::<Result<Vec<_>, _>>();
 
@@ -1672,7 +2202,7 @@ mod tests {
             writer.commit();
 
             let fmt = StrftimeItems::new(format);
-            let iter = partition_batch(&batch, &template);
+            let iter = partition_batch(&batch, TABLE_NAME, &template);
 
             let mut observed_rows = 0;
 
@@ -1,6 +1,7 @@
 use std::fmt::Write;
 
 use chrono::{format::StrftimeItems, TimeZone, Utc};
+use data_types::partition_template::TruncationMode;
 
 use crate::PartitionKeyError;
 
@@ -158,6 +159,10 @@ pub(super) struct StrftimeFormatter<'a> {
     /// MUST always track the last timestamp given to
     /// [`StrftimeFormatter::render()`].
     last_ts: Option<i64>,
+
+    /// The truncation applied to rendered values exceeding the maximum key
+    /// part length.
+    truncation: TruncationMode,
 }
 
 impl<'a> StrftimeFormatter<'a> {
@@ -182,9 +187,17 @@ impl<'a> StrftimeFormatter<'a> {
             is_ymd_format: is_default_format,
             values: RingBuffer::default(),
             last_ts: None,
+            truncation: TruncationMode::default(),
         }
     }
 
+    /// Truncate rendered values exceeding the maximum key part length
+    /// according to `truncation`.
+    pub(super) fn with_truncation(mut self, truncation: TruncationMode) -> Self {
+        self.truncation = truncation;
+        self
+    }
+
     /// Format `timestamp` to the format spec provided during initialisation,
     /// writing the result to `out`.
     pub(super) fn render<W>(&mut self, timestamp: i64, mut out: W) -> Result<(), PartitionKeyError>
@@ -232,7 +245,7 @@ impl<'a> StrftimeFormatter<'a> {
         };
 
         // Encode any reserved characters in this new string.
-        buf.1 = encode_key_part(&buf.1).to_string();
+        buf.1 = encode_key_part(&buf.1, self.truncation).to_string();
 
         // Render this new value to the caller's buffer
         out.write_str(&buf.1)?;
@@ -398,7 +411,7 @@ mod tests {
                     Utc.timestamp_nanos(ts)
                         .format_with_items(items.clone())
                 );
-                let control = encode_key_part(&control);
+                let control = encode_key_part(&control, TruncationMode::default());
 
                 // Generate the test string.
                 let mut test = String::new();
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use chrono::{format::StrftimeItems, TimeZone, Utc};
+use data_types::partition_template::TIME_BUCKET_FORMAT;
+
+use crate::PartitionKeyError;
+
+/// Aligns timestamps to fixed-width buckets, rendering the start of the bucket
+/// each timestamp falls into.
+#[derive(Debug)]
+pub(super) struct TimeBucketer {
+    /// The width of each bucket in nanoseconds, always greater than 0.
+    width: i64,
+
+    /// The start of the last rendered bucket.
+    last_bucket: Option<i64>,
+}
+
+impl TimeBucketer {
+    /// Initialise a new [`TimeBucketer`] aligning timestamps to buckets of
+    /// `width`.
+    ///
+    /// Widths that do not fit in an [`i64`] of nanoseconds are saturated, and
+    /// a zero `width` is treated as 1 nanosecond.
+    pub(super) fn new(width: Duration) -> Self {
+        let width = i64::try_from(width.as_nanos()).unwrap_or(i64::MAX).max(1);
+
+        Self {
+            width,
+            last_bucket: None,
+        }
+    }
+
+    /// Returns the start of the bucket `timestamp` falls into.
+    fn bucket_start(&self, timestamp: i64) -> i64 {
+        timestamp.saturating_sub(timestamp.rem_euclid(self.width))
+    }
+
+    /// Render the start of the bucket `timestamp` falls into to `out`.
+    pub(super) fn render<W>(&mut self, timestamp: i64, mut out: W) -> Result<(), PartitionKeyError>
+    where
+        W: std::fmt::Write,
+    {
+        let start = self.bucket_start(timestamp);
+        self.last_bucket = Some(start);
+
+        write!(
+            out,
+            "{}",
+            Utc.timestamp_nanos(start)
+                .format_with_items(StrftimeItems::new(TIME_BUCKET_FORMAT))
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns true if `timestamp` falls into the same bucket as the last
+    /// rendered timestamp.
+    pub(super) fn equals_last(&self, timestamp: i64) -> bool {
+        self.last_bucket
+            .map(|v| v == self.bucket_start(timestamp))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: i64 = 60 * 1_000_000_000;
+
+    #[test]
+    fn test_render() {
+        let mut bucketer = TimeBucketer::new(Duration::from_secs(15 * 60));
+
+        let mut buf = String::new();
+        bucketer.render(44 * MINUTE, &mut buf).unwrap();
+        assert_eq!(buf, "1970-01-01T00:30:00Z");
+
+        buf.clear();
+        bucketer.render(45 * MINUTE, &mut buf).unwrap();
+        assert_eq!(buf, "1970-01-01T00:45:00Z");
+    }
+
+    #[test]
+    fn test_negative_timestamps() {
+        let mut bucketer = TimeBucketer::new(Duration::from_secs(60 * 60));
+
+        let mut buf = String::new();
+        bucketer.render(-1, &mut buf).unwrap();
+        assert_eq!(buf, "1969-12-31T23:00:00Z");
+    }
+
+    #[test]
+    fn test_equals_last() {
+        let mut bucketer = TimeBucketer::new(Duration::from_secs(15 * 60));
+        assert!(!bucketer.equals_last(0));
+
+        bucketer.render(MINUTE, String::new()).unwrap();
+        assert!(bucketer.equals_last(0));
+        assert!(bucketer.equals_last(15 * MINUTE - 1));
+        assert!(!bucketer.equals_last(15 * MINUTE));
+        assert!(!bucketer.equals_last(-1));
+    }
+}
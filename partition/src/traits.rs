@@ -1,6 +1,8 @@
 mod mutable_batch;
 mod record_batch;
 
+use std::borrow::Cow;
+
 use thiserror::Error;
 
 /// An error accessing the time column of a batch.
@@ -36,6 +38,12 @@ pub trait PartitioningColumn: std::fmt::Debug {
     /// Get the value of the tag that has the given identity
     fn get_tag_value<'a>(&'a self, tag_identity_key: &'a Self::TagIdentityKey) -> Option<&'a str>;
 
+    /// Render the field value at the given row index as a string.
+    ///
+    /// Returns [`None`] if this column is not a field column. The return value
+    /// is only valid if `is_valid(idx)` for the same `idx` returns true.
+    fn get_field_value(&self, idx: usize) -> Option<Cow<'_, str>>;
+
     /// A string describing this column's data type; used in error messages
     fn type_description(&self) -> String;
 }
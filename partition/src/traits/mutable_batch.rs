@@ -4,6 +4,7 @@ use mutable_batch::{
     MutableBatch,
 };
 use schema::TIME_COLUMN_NAME;
+use std::borrow::Cow;
 
 impl PartitioningColumn for MutableBatchColumn {
     type TagIdentityKey = i32;
@@ -31,6 +32,18 @@ impl PartitioningColumn for MutableBatchColumn {
         }
     }
 
+    fn get_field_value(&self, idx: usize) -> Option<Cow<'_, str>> {
+        debug_assert!(PartitioningColumn::is_valid(self, idx));
+        match self.data() {
+            ColumnData::F64(col_data, _) => Some(Cow::Owned(col_data[idx].to_string())),
+            ColumnData::I64(col_data, _) => Some(Cow::Owned(col_data[idx].to_string())),
+            ColumnData::U64(col_data, _) => Some(Cow::Owned(col_data[idx].to_string())),
+            ColumnData::Bool(col_data, _) => Some(Cow::Owned(col_data.get(idx).to_string())),
+            ColumnData::String(col_data, _) => col_data.get(idx).map(Cow::Borrowed),
+            ColumnData::Tag(_, _, _) => None,
+        }
+    }
+
     fn type_description(&self) -> String {
         self.influx_type().to_string()
     }
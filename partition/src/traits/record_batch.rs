@@ -1,11 +1,14 @@
 use super::{Batch, PartitioningColumn, TimeColumnError};
 use arrow::{
-    array::{Array, DictionaryArray, StringArray, TimestampNanosecondArray},
+    array::{
+        Array, BooleanArray, DictionaryArray, Float64Array, Int64Array, StringArray,
+        TimestampNanosecondArray, UInt64Array,
+    },
     datatypes::{DataType, Int32Type},
     record_batch::RecordBatch,
 };
 use schema::TIME_COLUMN_NAME;
-use std::sync::Arc;
+use std::{borrow::Cow, sync::Arc};
 
 impl PartitioningColumn for Arc<dyn Array> {
     type TagIdentityKey = str;
@@ -50,6 +53,29 @@ impl PartitioningColumn for Arc<dyn Array> {
         Some(tag_identity_key)
     }
 
+    fn get_field_value(&self, idx: usize) -> Option<Cow<'_, str>> {
+        debug_assert!(PartitioningColumn::is_valid(self, idx));
+        let any = self.as_any();
+        match self.data_type() {
+            DataType::Float64 => any
+                .downcast_ref::<Float64Array>()
+                .map(|col_data| Cow::Owned(col_data.value(idx).to_string())),
+            DataType::Int64 => any
+                .downcast_ref::<Int64Array>()
+                .map(|col_data| Cow::Owned(col_data.value(idx).to_string())),
+            DataType::UInt64 => any
+                .downcast_ref::<UInt64Array>()
+                .map(|col_data| Cow::Owned(col_data.value(idx).to_string())),
+            DataType::Boolean => any
+                .downcast_ref::<BooleanArray>()
+                .map(|col_data| Cow::Owned(col_data.value(idx).to_string())),
+            DataType::Utf8 => any
+                .downcast_ref::<StringArray>()
+                .map(|col_data| Cow::Borrowed(col_data.value(idx))),
+            _ => None,
+        }
+    }
+
     fn type_description(&self) -> String {
         self.data_type().to_string()
     }
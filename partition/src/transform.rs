@@ -0,0 +1,65 @@
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A transformation applied to a tag value before it is rendered into a
+/// partition key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TagTransform {
+    /// Retain at most the first `n` graphemes of the tag value.
+    Prefix(u32),
+
+    /// Convert the tag value to lowercase.
+    Lowercase,
+}
+
+impl TagTransform {
+    /// Apply this transformation to `value`, only allocating if the value
+    /// changes.
+    pub(super) fn apply<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Prefix(n) => {
+                // Never split a grapheme, for the same reasons as key part
+                // truncation.
+                let end = value
+                    .grapheme_indices(true)
+                    .nth(*n as usize)
+                    .map(|(idx, _)| idx)
+                    .unwrap_or(value.len());
+                Cow::Borrowed(&value[..end])
+            }
+            Self::Lowercase if value.chars().any(char::is_uppercase) => {
+                Cow::Owned(value.to_lowercase())
+            }
+            Self::Lowercase => Cow::Borrowed(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix() {
+        let t = TagTransform::Prefix(2);
+        assert_eq!(t.apply("bananas"), "ba");
+        assert_eq!(t.apply("b"), "b");
+        assert_eq!(t.apply(""), "");
+
+        // Graphemes are not split.
+        assert_eq!(t.apply("pla\u{0301}tanos"), "pl");
+        assert_eq!(
+            TagTransform::Prefix(3).apply("pla\u{0301}tanos"),
+            "pla\u{0301}"
+        );
+    }
+
+    #[test]
+    fn test_lowercase() {
+        let t = TagTransform::Lowercase;
+        assert_eq!(t.apply("BaNaNaS"), "bananas");
+        assert_eq!(t.apply("PLÁTANOS"), "plátanos");
+        assert!(matches!(t.apply("bananas"), Cow::Borrowed("bananas")));
+    }
+}
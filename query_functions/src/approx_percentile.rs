@@ -0,0 +1,292 @@
+//! Approximate percentile aggregate function.
+//!
+//! Computing exact percentiles requires buffering (and sorting) every value
+//! of a group, which is too expensive for large series. Instead,
+//! `approx_percentile(field, p)` summarises the values of each partition in a
+//! [t-digest] sketch of bounded size, merges the sketches of all partitions,
+//! and estimates the percentile `p` (between 0 and 1) from the merged sketch.
+//! The sketch is the one DataFusion's `approx_percentile_cont` uses:
+//!
+//! ```sql
+//! SELECT location, approx_percentile(temp, 0.99)
+//! FROM temps
+//! GROUP BY location
+//! ```
+//!
+//! [t-digest]: https://arxiv.org/abs/1902.04023
+use std::sync::Arc;
+
+use arrow::{
+    array::{Array, ArrayRef, Float64Array},
+    compute::cast,
+    datatypes::{DataType, Field},
+};
+use datafusion::{
+    common::{downcast_value, DataFusionError, Result, ScalarValue},
+    logical_expr::{
+        Accumulator, AccumulatorFactoryFunction, AggregateUDF, ReturnTypeFunction, Signature,
+        StateTypeFunction, TypeSignature, Volatility,
+    },
+    physical_expr::{
+        expressions::{ApproxPercentileCont, Column, Literal},
+        AggregateExpr,
+    },
+};
+use once_cell::sync::Lazy;
+
+/// The name of the approx_percentile UDAF given to DataFusion.
+pub const APPROX_PERCENTILE_UDAF_NAME: &str = "approx_percentile";
+
+/// Number of state fields of a serialised t-digest.
+const TDIGEST_STATE_LEN: usize = 6;
+
+/// Index of the value count within the state fields of a serialised t-digest.
+const TDIGEST_STATE_COUNT: usize = 2;
+
+/// Definition of the `approx_percentile` user-defined aggregate function.
+pub(crate) static APPROX_PERCENTILE_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    let signature = Signature::one_of(
+        [DataType::Int64, DataType::UInt64, DataType::Float64]
+            .into_iter()
+            .map(|dt| TypeSignature::Exact(vec![dt, DataType::Float64]))
+            .collect(),
+        Volatility::Immutable,
+    );
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+    let accumulator: AccumulatorFactoryFunction =
+        Arc::new(|_| Ok(Box::<ApproxPercentileAccumulator>::default()));
+    let state_type: StateTypeFunction = Arc::new(|_| Ok(Arc::new(state_type())));
+
+    Arc::new(AggregateUDF::new(
+        APPROX_PERCENTILE_UDAF_NAME,
+        &signature,
+        &return_type,
+        &accumulator,
+        &state_type,
+    ))
+});
+
+/// The intermediate state: the fields of the t-digest followed by the
+/// percentile.
+fn state_type() -> Vec<DataType> {
+    vec![
+        // max size
+        DataType::UInt64,
+        // sum
+        DataType::Float64,
+        // count
+        DataType::Float64,
+        // max
+        DataType::Float64,
+        // min
+        DataType::Float64,
+        // centroids
+        DataType::List(Arc::new(Field::new("item", DataType::Float64, true))),
+        // percentile
+        DataType::Float64,
+    ]
+}
+
+/// Create the t-digest accumulator of DataFusion's `approx_percentile_cont`
+/// for `percentile`.
+fn digest_accumulator(percentile: f64) -> Result<Box<dyn Accumulator>> {
+    ApproxPercentileCont::new(
+        vec![
+            Arc::new(Column::new("value", 0)),
+            Arc::new(Literal::new(ScalarValue::Float64(Some(percentile)))),
+        ],
+        APPROX_PERCENTILE_UDAF_NAME,
+        DataType::Float64,
+    )?
+    .create_accumulator()
+}
+
+#[derive(Debug, Default)]
+struct ApproxPercentileAccumulator {
+    /// The t-digest accumulator, created once the percentile is known as it
+    /// is fixed at construction.
+    digest: Option<Box<dyn Accumulator>>,
+    percentile: Option<f64>,
+}
+
+impl ApproxPercentileAccumulator {
+    fn set_percentile(&mut self, array: &ArrayRef) -> Result<()> {
+        if self.percentile.is_some() {
+            return Ok(());
+        }
+        let Some(p) = downcast_value!(array, Float64Array).iter().flatten().next() else {
+            return Ok(());
+        };
+
+        if !(0.0..=1.0).contains(&p) {
+            return Err(DataFusionError::Plan(format!(
+                "{APPROX_PERCENTILE_UDAF_NAME} percentile must be between 0 and 1, got {p}"
+            )));
+        }
+        self.digest = Some(digest_accumulator(p)?);
+        self.percentile = Some(p);
+        Ok(())
+    }
+}
+
+impl Accumulator for ApproxPercentileAccumulator {
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        assert_eq!(values.len(), 2);
+
+        self.set_percentile(&values[1])?;
+        let Some(digest) = &mut self.digest else {
+            return Ok(());
+        };
+
+        // The t-digest accumulator does not skip nulls, nor NaNs.
+        let array = cast(&values[0], &DataType::Float64)?;
+        let array = downcast_value!(array, Float64Array)
+            .iter()
+            .flatten()
+            .filter(|v| !v.is_nan())
+            .map(Some)
+            .collect::<Float64Array>();
+        digest.update_batch(&[Arc::new(array)])
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let Some(digest) = &self.digest else {
+            return Ok(ScalarValue::Float64(None));
+        };
+
+        // The t-digest accumulator fails to evaluate without any values.
+        if digest.state()?[TDIGEST_STATE_COUNT] == ScalarValue::Float64(Some(0.0)) {
+            return Ok(ScalarValue::Float64(None));
+        }
+        digest.evaluate()
+    }
+
+    fn size(&self) -> usize {
+        std::mem::size_of_val(self) + self.digest.as_ref().map_or(0, |d| d.size())
+    }
+
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let mut state = match &self.digest {
+            Some(digest) => digest.state()?,
+            // The state of an empty digest does not depend on the percentile.
+            None => digest_accumulator(0.0)?.state()?,
+        };
+        state.push(ScalarValue::Float64(self.percentile));
+        Ok(state)
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        assert_eq!(states.len(), TDIGEST_STATE_LEN + 1);
+
+        self.set_percentile(&states[TDIGEST_STATE_LEN])?;
+        match &mut self.digest {
+            Some(digest) => digest.merge_batch(&states[..TDIGEST_STATE_LEN]),
+            // Without a percentile, none of the states hold any values.
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::{
+        array::{Int64Array, StringArray},
+        datatypes::Schema,
+        record_batch::RecordBatch,
+    };
+    use datafusion::{
+        assert_batches_eq, common::assert_contains, datasource::MemTable, prelude::SessionContext,
+    };
+
+    use super::*;
+
+    /// Register a table `t` with the values 1..=1000, spread over 4
+    /// partitions so that the sketches of the partitions are merged.
+    fn context() -> SessionContext {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("tag", DataType::Utf8, false),
+            Field::new("v", DataType::Int64, true),
+        ]));
+        let partitions = (0..4)
+            .map(|p| {
+                let values = (1..=1000).filter(|v| v % 4 == p).collect::<Vec<i64>>();
+                let tags = values
+                    .iter()
+                    .map(|v| if v % 2 == 0 { "even" } else { "odd" })
+                    .collect::<Vec<_>>();
+                vec![RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![
+                        Arc::new(StringArray::from(tags)),
+                        Arc::new(Int64Array::from(values)),
+                    ],
+                )
+                .unwrap()]
+            })
+            .collect();
+
+        let ctx = SessionContext::new();
+        ctx.register_udaf(APPROX_PERCENTILE_UDAF.as_ref().clone());
+        ctx.register_table(
+            "t",
+            Arc::new(MemTable::try_new(schema, partitions).unwrap()),
+        )
+        .unwrap();
+        ctx
+    }
+
+    async fn query(sql: &str) -> Result<Vec<RecordBatch>> {
+        context().sql(sql).await?.collect().await
+    }
+
+    fn approx(batches: &[RecordBatch], row: usize) -> f64 {
+        let col = batches[0].column_by_name("p").unwrap();
+        col.as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap()
+            .value(row)
+    }
+
+    #[tokio::test]
+    async fn test_approx_percentile() {
+        let batches = query("SELECT approx_percentile(v, 0.5) AS p FROM t")
+            .await
+            .unwrap();
+        assert!((approx(&batches, 0) - 500.0).abs() < 10.0);
+
+        let batches = query("SELECT approx_percentile(v, 0.99) AS p FROM t")
+            .await
+            .unwrap();
+        assert!((approx(&batches, 0) - 990.0).abs() < 10.0);
+
+        let batches = query(
+            "SELECT tag, approx_percentile(v, 0) AS p FROM t \
+             GROUP BY tag ORDER BY tag",
+        )
+        .await
+        .unwrap();
+        // even
+        assert!((approx(&batches, 0) - 2.0).abs() < 10.0);
+        // odd
+        assert!((approx(&batches, 1) - 1.0).abs() < 10.0);
+    }
+
+    #[tokio::test]
+    async fn test_approx_percentile_no_values() {
+        let batches = query("SELECT approx_percentile(v, 0.5) AS p FROM t WHERE v > 1000")
+            .await
+            .unwrap();
+        assert_batches_eq!(&["+---+", "| p |", "+---+", "|   |", "+---+"], &batches);
+    }
+
+    #[tokio::test]
+    async fn test_approx_percentile_invalid_percentile() {
+        let err = query("SELECT approx_percentile(v, 1.5) FROM t")
+            .await
+            .unwrap_err();
+        assert_contains!(
+            err.to_string(),
+            "approx_percentile percentile must be between 0 and 1, got 1.5"
+        );
+    }
+}
@@ -14,6 +14,21 @@
 //! GROUP BY LOCATION, MINUTE
 //! ```
 //!
+//! `DATE_BIN_GAPFILL` takes an optional fourth argument that names the
+//! strategy used to fill aggregate columns that are not wrapped in `LOCF` or
+//! `INTERPOLATE`: `'null'` (the default), `'previous'` (or `'locf'`), or
+//! `'linear'`:
+//!
+//! ```sql
+//! SELECT
+//!   location,
+//!   DATE_BIN_GAPFILL(INTERVAL '1 minute', time, '1970-01-01T00:00:00Z', 'linear') AS minute,
+//!   AVG(temp)
+//! FROM temps
+//! WHERE time > NOW() - INTERVAL '6 hours' AND time < NOW()
+//! GROUP BY LOCATION, MINUTE
+//! ```
+//!
 //! The functions `DATE_BIN_GAPFILL`, `LOCF`, and `INTERPOLATE` are special,
 //! in that they don't have normal implementations, but instead
 //! are transformed by logical optimizer rule `HandleGapFill` to
@@ -64,13 +79,26 @@ impl ScalarUDFImpl for DateBinGapFillUDF {
 }
 
 /// (Non-)Implementation of date_bin_gapfill.
-/// This function takes arguments identical to `date_bin()` but
-/// works in conjunction with the logical optimizer rule
-/// `HandleGapFill` to fill gaps in time series data.
+/// This function takes arguments identical to `date_bin()`, plus
+/// an optional fill strategy, but works in conjunction with the
+/// logical optimizer rule `HandleGapFill` to fill gaps in time series data.
 pub(crate) static DATE_BIN_GAPFILL: Lazy<Arc<ScalarUDF>> = Lazy::new(|| {
     // DATE_BIN_GAPFILL should have the same signature as DATE_BIN,
     // so that just adding _GAPFILL can turn a query into a gap-filling query.
     let mut signatures = BuiltinScalarFunction::DateBin.signature();
+    // After the origin, the name of the fill strategy may be given.
+    if let TypeSignature::OneOf(ref mut sigs) = signatures.type_signature {
+        let with_fill: Vec<_> = sigs
+            .iter()
+            .filter_map(|sig| match sig {
+                TypeSignature::Exact(types) if types.len() == 3 => Some(TypeSignature::Exact(
+                    types.iter().cloned().chain([DataType::Utf8]).collect(),
+                )),
+                _ => None,
+            })
+            .collect();
+        sigs.extend(with_fill);
+    }
     // We don't want this to be optimized away before we can give a helpful error message
     signatures.volatility = Volatility::Volatile;
 
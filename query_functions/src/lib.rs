@@ -24,6 +24,9 @@ use datafusion::{
 use group_by::WindowDuration;
 use window::EncodedWindowDuration;
 
+/// Approximate percentiles
+mod approx_percentile;
+
 pub mod coalesce_struct;
 
 /// Grouping by structs
@@ -47,6 +50,7 @@ pub mod gapfill;
 mod registry;
 mod to_timestamp;
 
+pub use crate::approx_percentile::APPROX_PERCENTILE_UDAF_NAME;
 pub use crate::regex::clean_non_meta_escapes;
 pub use crate::regex::REGEX_MATCH_UDF_NAME;
 pub use crate::regex::REGEX_NOT_MATCH_UDF_NAME;
@@ -116,6 +120,12 @@ pub fn register_scalar_functions(ctx: &SessionContext) {
     }
 }
 
+/// registers aggregate functions so they can be invoked via SQL
+pub fn register_aggregate_functions(ctx: &SessionContext) {
+    let udaf = registry().udaf(APPROX_PERCENTILE_UDAF_NAME).unwrap();
+    ctx.register_udaf(udaf.as_ref().clone())
+}
+
 #[cfg(test)]
 mod test {
     use arrow::{
@@ -7,7 +7,7 @@ use datafusion::{
 };
 use once_cell::sync::Lazy;
 
-use crate::{gapfill, regex, sleep, to_timestamp, window};
+use crate::{approx_percentile, gapfill, regex, sleep, to_timestamp, window};
 
 static REGISTRY: Lazy<IOxFunctionRegistry> = Lazy::new(IOxFunctionRegistry::new);
 
@@ -55,9 +55,14 @@ impl FunctionRegistry for IOxFunctionRegistry {
     }
 
     fn udaf(&self, name: &str) -> DataFusionResult<Arc<AggregateUDF>> {
-        Err(DataFusionError::Plan(format!(
-            "IOx FunctionRegistry does not contain user defined aggregate function '{name}'"
-        )))
+        match name {
+            approx_percentile::APPROX_PERCENTILE_UDAF_NAME => {
+                Ok(approx_percentile::APPROX_PERCENTILE_UDAF.clone())
+            }
+            _ => Err(DataFusionError::Plan(format!(
+                "IOx FunctionRegistry does not contain user defined aggregate function '{name}'"
+            ))),
+        }
     }
 
     fn udwf(&self, name: &str) -> DataFusionResult<Arc<WindowUDF>> {
@@ -59,6 +59,7 @@ pub fn datafusion_error_to_tonic_code(e: &DataFusionError) -> tonic::Code {
                 match e {
                     executor::JobError::WorkerGone => tonic::Code::Unavailable,
                     executor::JobError::Panic { .. } => tonic::Code::Internal,
+                    executor::JobError::Cancelled => tonic::Code::Cancelled,
                 }
             } else {
                 // All other, unclassified cases are signalled as "internal error" to the user since they cannot do
@@ -134,6 +135,10 @@ mod test {
             DataFusionError::External(Box::new(executor::JobError::WorkerGone)),
             tonic::Code::Unavailable,
         );
+        do_transl_test(
+            DataFusionError::External(Box::new(executor::JobError::Cancelled)),
+            tonic::Code::Cancelled,
+        );
         do_transl_test(
             DataFusionError::Context(
                 "ctx".into(),
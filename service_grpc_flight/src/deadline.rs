@@ -0,0 +1,243 @@
+//! Per-RPC deadlines.
+//!
+//! gRPC clients may send their deadline as a relative timeout via the `grpc-timeout` header. Once the deadline passed,
+//! the client gave up on the request, so there is no point in planning or executing the query any further.
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{stream::BoxStream, Stream, StreamExt};
+use snafu::{OptionExt, ResultExt, Snafu};
+use tokio::time::{Instant, Sleep};
+use tonic::metadata::MetadataMap;
+
+/// Name of the gRPC header that contains the timeout of the request.
+pub(crate) const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+
+/// Maximum number of digits of the `grpc-timeout` value, see [gRPC over HTTP2].
+///
+/// [gRPC over HTTP2]: https://github.com/grpc/grpc/blob/master/doc/PROTOCOL-HTTP2.md
+const MAX_TIMEOUT_DIGITS: usize = 8;
+
+#[derive(Debug, Snafu)]
+pub enum Error {
+    #[snafu(display("header value is not ASCII: {}", source))]
+    NotAscii {
+        source: tonic::metadata::errors::ToStrError,
+    },
+
+    #[snafu(display(
+        "expected at most {} digits followed by a unit: '{}'",
+        MAX_TIMEOUT_DIGITS,
+        value
+    ))]
+    InvalidFormat { value: String },
+
+    #[snafu(display("unknown unit '{}'", unit))]
+    UnknownUnit { unit: char },
+}
+
+/// Get the timeout of the request from the `grpc-timeout` header, if any.
+pub(crate) fn get_timeout(metadata: &MetadataMap) -> Result<Option<Duration>, Error> {
+    metadata
+        .get(GRPC_TIMEOUT_HEADER)
+        .map(|value| parse_timeout(value.to_str().context(NotAsciiSnafu)?))
+        .transpose()
+}
+
+/// Parse a `grpc-timeout` value, e.g. `100m` for 100 milliseconds.
+fn parse_timeout(value: &str) -> Result<Duration, Error> {
+    let (digits, unit) = value
+        .char_indices()
+        .last()
+        .map(|(idx, unit)| (&value[..idx], unit))
+        .context(InvalidFormatSnafu { value })?;
+
+    if digits.is_empty()
+        || digits.len() > MAX_TIMEOUT_DIGITS
+        || !digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        return InvalidFormatSnafu { value }.fail();
+    }
+    let amount: u64 = digits.parse().expect("checked digits");
+
+    let timeout = match unit {
+        'H' => Duration::from_secs(amount * 60 * 60),
+        'M' => Duration::from_secs(amount * 60),
+        'S' => Duration::from_secs(amount),
+        'm' => Duration::from_millis(amount),
+        'u' => Duration::from_micros(amount),
+        'n' => Duration::from_nanos(amount),
+        unit => return UnknownUnitSnafu { unit }.fail(),
+    };
+    Ok(timeout)
+}
+
+/// Run `fut` until it completes or `deadline` passes, in which case `None` is returned.
+pub(crate) async fn run_until<F>(deadline: Option<Instant>, fut: F) -> Option<F::Output>
+where
+    F: Future,
+{
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, fut).await.ok(),
+        None => Some(fut.await),
+    }
+}
+
+/// Wrap `stream` so that it ends with the error created by `on_expired` once `deadline` passed.
+///
+/// The inner stream is not polled anymore after the deadline, but it is only dropped together with the returned
+/// stream.
+pub(crate) fn with_deadline<S, T, E, F>(
+    stream: S,
+    deadline: Option<Instant>,
+    on_expired: F,
+) -> BoxStream<'static, Result<T, E>>
+where
+    S: Stream<Item = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+    F: FnOnce() -> E + Send + Unpin + 'static,
+{
+    let Some(deadline) = deadline else {
+        return stream.boxed();
+    };
+
+    DeadlineStream {
+        inner: stream.boxed(),
+        sleep: Box::pin(tokio::time::sleep_until(deadline)),
+        on_expired: Some(on_expired),
+        done: false,
+    }
+    .boxed()
+}
+
+/// Stream returned by [`with_deadline`].
+struct DeadlineStream<T, E, F> {
+    inner: BoxStream<'static, Result<T, E>>,
+    sleep: Pin<Box<Sleep>>,
+    on_expired: Option<F>,
+    done: bool,
+}
+
+impl<T, E, F> Stream for DeadlineStream<T, E, F>
+where
+    F: FnOnce() -> E + Unpin,
+{
+    type Item = Result<T, E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        if self.sleep.as_mut().poll(cx).is_ready() {
+            self.done = true;
+            let on_expired = self.on_expired.take().expect("only called once");
+            return Poll::Ready(Some(Err(on_expired())));
+        }
+
+        let res = futures::ready!(self.inner.poll_next_unpin(cx));
+        if res.is_none() {
+            self.done = true;
+        }
+        Poll::Ready(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use tonic::metadata::MetadataValue;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("1H").unwrap(), Duration::from_secs(3_600));
+        assert_eq!(parse_timeout("2M").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_timeout("3S").unwrap(), Duration::from_secs(3));
+        assert_eq!(parse_timeout("100m").unwrap(), Duration::from_millis(100));
+        assert_eq!(parse_timeout("5u").unwrap(), Duration::from_micros(5));
+        assert_eq!(
+            parse_timeout("99999999n").unwrap(),
+            Duration::from_nanos(99_999_999)
+        );
+
+        assert_eq!(
+            parse_timeout("100").unwrap_err().to_string(),
+            "expected at most 8 digits followed by a unit: '100'",
+        );
+        assert_eq!(
+            parse_timeout("123456789m").unwrap_err().to_string(),
+            "expected at most 8 digits followed by a unit: '123456789m'",
+        );
+        assert_eq!(
+            parse_timeout("m").unwrap_err().to_string(),
+            "expected at most 8 digits followed by a unit: 'm'",
+        );
+        assert_eq!(
+            parse_timeout("-1m").unwrap_err().to_string(),
+            "expected at most 8 digits followed by a unit: '-1m'",
+        );
+        assert_eq!(
+            parse_timeout("10x").unwrap_err().to_string(),
+            "unknown unit 'x'",
+        );
+    }
+
+    #[test]
+    fn test_get_timeout() {
+        let mut metadata = MetadataMap::new();
+        assert_eq!(get_timeout(&metadata).unwrap(), None);
+
+        metadata.insert(GRPC_TIMEOUT_HEADER, MetadataValue::from_static("10S"));
+        assert_eq!(
+            get_timeout(&metadata).unwrap(),
+            Some(Duration::from_secs(10))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_until() {
+        assert_eq!(run_until(None, async { 1 }).await, Some(1));
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        assert_eq!(run_until(Some(deadline), async { 1 }).await, Some(1));
+
+        let deadline = Instant::now() + Duration::from_millis(10);
+        assert_eq!(
+            run_until(Some(deadline), futures::future::pending::<()>()).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_deadline() {
+        // no deadline
+        let stream = futures::stream::iter([Ok::<_, &str>(1), Ok(2)]);
+        let res = with_deadline(stream, None, || "expired")
+            .try_collect::<Vec<_>>()
+            .await;
+        assert_eq!(res, Ok(vec![1, 2]));
+
+        // deadline in the future
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let stream = futures::stream::iter([Ok::<_, &str>(1), Ok(2)]);
+        let res = with_deadline(stream, Some(deadline), || "expired")
+            .try_collect::<Vec<_>>()
+            .await;
+        assert_eq!(res, Ok(vec![1, 2]));
+
+        // deadline passes while waiting for the inner stream
+        let deadline = Instant::now() + Duration::from_millis(10);
+        let stream = futures::stream::iter([Ok::<_, &str>(1)]).chain(futures::stream::pending());
+        let res = with_deadline(stream, Some(deadline), || "expired")
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(res, vec![Ok(1), Err("expired")]);
+    }
+}
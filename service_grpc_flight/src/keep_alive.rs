@@ -93,8 +93,7 @@
 //!
 //! - `None`: This is the implicit default.
 //! - `Schema`: Sent before any other data to describe the schema of the stream.
-//! - `DictionaryBatch`: Encodes dictionary data. This is not used in practice at the moment because dictionaries are
-//!   always hydrated.
+//! - `DictionaryBatch`: Encodes dictionary data. Must be sent before a `RecordBatch` that references the dictionary.
 //! - `RecordBatch`: Content of a `RecordBatch` w/o schema information.
 //! - `Tensor`, `SparseTensor`: Irrelevant for us.
 //!
@@ -108,9 +107,12 @@
 //! # The Actual Workaround
 //! So we send actual empty `RecordBatch`es instead. These are encoded as `RecordBatch` messages w/o a schema (see
 //! section above). The schema is sent separately right at the start of the stream. The arrow-rs implementation does
-//! that for us and also ensures that the schema is adjusted for dictionary hydration. So we just inspect the data
-//! stream and wait for that schema (the upstream implementation will always send this without any blocking / wait
-//! time / actual `RecordBatch` data).
+//! that for us. So we just inspect the data stream and wait for that schema (the upstream implementation will always
+//! send this without any blocking / wait time / actual `RecordBatch` data).
+//!
+//! If the schema contains dictionary-encoded columns, the empty `RecordBatch` is preceded by (empty) `DictionaryBatch`
+//! messages, because the client cannot decode a batch without its dictionaries. This replaces the dictionaries on the
+//! client side, which is fine because the dictionaries are resent with every "real" `RecordBatch`.
 //!
 //!
 //! [Apache Flight]: https://arrow.apache.org/docs/format/Flight.html
@@ -132,7 +134,7 @@ use std::{
 };
 
 use arrow::{
-    datatypes::{DataType, Schema, SchemaRef},
+    datatypes::{Schema, SchemaRef},
     ipc::writer::{DictionaryTracker, IpcDataGenerator, IpcWriteOptions},
     record_batch::RecordBatch,
 };
@@ -172,28 +174,27 @@ where
             loop {
                 tokio::select! {
                     _ = state.ticker.tick() => {
-                        let Some(data) = build_empty_batch_msg(state.schema.as_ref()) else {
+                        let Some(data) = build_empty_batch_msgs(state.schema.as_ref()) else {
                             continue;
                         };
                         info!("stream keep-alive");
-                        return Some((Ok(data), state));
+                        return Some((data.into_iter().map(Ok).collect::<Vec<_>>(), state));
                     }
                     res = state.inner.next() => {
                         // peek at content to detect schema transmission
                         if let Some(Ok(data)) = &res {
                             if let Some(schema) = decode_schema(data) {
-                                if check_schema(&schema) {
-                                    state.schema = Some(Arc::new(schema));
-                                }
+                                state.schema = Some(Arc::new(schema));
                             }
                         }
 
                         state.ticker.reset();
-                        return res.map(|res| (res, state));
+                        return res.map(|res| (vec![res], state));
                     }
                 }
             }
         })
+        .flat_map(futures::stream::iter)
         .boxed();
 
         Self { inner }
@@ -239,26 +240,10 @@ fn decode_schema(data: &FlightData) -> Option<Schema> {
     Schema::try_from(data).ok()
 }
 
-/// Check that the [`Schema`] that we've [decoded](decode_schema) is sensible.
-///
-/// Returns `true` if the [`Schema`] is OK. Will log a warning and return `false` if there is a problem.
-fn check_schema(schema: &Schema) -> bool {
-    schema.fields().iter().all(|field| match field.data_type() {
-        DataType::Dictionary(_, _) => {
-            warn!(
-                field = field.name(),
-                "arrow IPC schema still contains dictionary, should have been hydrated by now",
-            );
-            false
-        }
-        _ => true,
-    })
-}
-
-/// Encode an empty [`RecordBatch`] as a message.
+/// Encode an empty [`RecordBatch`] as messages, i.e. the dictionaries (if any) followed by the batch itself.
 ///
 /// This must only be sent AFTER a [`Schema`] was transmitted.
-fn build_empty_batch_msg(schema: Option<&SchemaRef>) -> Option<FlightData> {
+fn build_empty_batch_msgs(schema: Option<&SchemaRef>) -> Option<Vec<FlightData>> {
     let Some(schema) = schema else {
         warn!("cannot send keep-alive because no schema was transmitted yet",);
         return None;
@@ -268,21 +253,25 @@ fn build_empty_batch_msg(schema: Option<&SchemaRef>) -> Option<FlightData> {
     let data_gen = IpcDataGenerator::default();
     let mut dictionary_tracker = DictionaryTracker::new(true);
     let write_options = IpcWriteOptions::default();
-    let batch_data = match data_gen.encoded_batch(&batch, &mut dictionary_tracker, &write_options) {
-        Ok((dicts_data, batch_data)) => {
-            assert!(dicts_data.is_empty());
-            batch_data
-        }
-        Err(e) => {
-            warn!(
-                %e,
-                "cannot encode empty batch",
-            );
-            return None;
-        }
-    };
+    let (dicts_data, batch_data) =
+        match data_gen.encoded_batch(&batch, &mut dictionary_tracker, &write_options) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!(
+                    %e,
+                    "cannot encode empty batch",
+                );
+                return None;
+            }
+        };
 
-    Some(batch_data.into())
+    Some(
+        dicts_data
+            .into_iter()
+            .chain(std::iter::once(batch_data))
+            .map(FlightData::from)
+            .collect(),
+    )
 }
 
 #[cfg(test)]
@@ -307,10 +296,16 @@ pub(crate) mod test_util {
 
 #[cfg(test)]
 mod tests {
-    use arrow::{array::Int64Array, datatypes::Field};
+    use arrow::{
+        array::{DictionaryArray, Int64Array},
+        datatypes::{DataType, Field, Int32Type},
+    };
     use arrow_flight::{
-        decode::FlightRecordBatchStream, encode::FlightDataEncoderBuilder, error::FlightError,
+        decode::FlightRecordBatchStream,
+        encode::{DictionaryHandling, FlightDataEncoderBuilder},
+        error::FlightError,
     };
+    use arrow_util::flight::{prepare_batch_for_flight, prepare_schema_for_flight};
     use datafusion::assert_batches_eq;
     use futures::TryStreamExt;
     use test_helpers::maybe_start_logging;
@@ -336,23 +331,46 @@ mod tests {
         let s = FlightRecordBatchStream::new_from_flight_data(s);
         let batches: Vec<_> = s.try_collect().await.unwrap();
         assert_batches_eq!(
-            ["+---+", "| f |", "+---+", "| 1 |", "| 2 |", "| 3 |", "| 4 |", "| 5 |", "+---+"],
+            [
+                "+---+---+",
+                "| f | t |",
+                "+---+---+",
+                "| 1 | a |",
+                "| 2 | b |",
+                "| 3 | a |",
+                "| 4 | c |",
+                "| 5 | c |",
+                "+---+---+",
+            ],
             &batches
         );
     }
 
     /// Creates a stream like the query processing would do.
     fn make_query_result_stream() -> (BatchStream, SchemaRef) {
-        let schema = Arc::new(Schema::new(vec![Field::new("f", DataType::Int64, false)]));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("f", DataType::Int64, false),
+            Field::new(
+                "t",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                false,
+            ),
+        ]));
 
         let batch_1 = RecordBatch::try_new(
             Arc::clone(&schema),
-            vec![Arc::new(Int64Array::from(vec![1, 2, 3]))],
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3])),
+                Arc::new(DictionaryArray::<Int32Type>::from_iter(["a", "b", "a"])),
+            ],
         )
         .unwrap();
         let batch_2 = RecordBatch::try_new(
             Arc::clone(&schema),
-            vec![Arc::new(Int64Array::from(vec![4, 5]))],
+            vec![
+                Arc::new(Int64Array::from(vec![4, 5])),
+                Arc::new(DictionaryArray::<Int32Type>::from_iter(["c", "c"])),
+            ],
         )
         .unwrap();
 
@@ -364,8 +382,18 @@ mod tests {
     ///
     /// This stream will -- as in prod -- send the [`Schema`] data even when there are no [`RecordBatch`]es yet.
     fn make_flight_data_stream(s: BatchStream, schema: SchemaRef) -> FlightStream {
+        let schema = prepare_schema_for_flight(schema);
+        let schema_captured = Arc::clone(&schema);
+        let s = s.map(move |res| {
+            res.and_then(|batch| {
+                prepare_batch_for_flight(&batch, Arc::clone(&schema_captured))
+                    .map_err(FlightError::Arrow)
+            })
+        });
+
         FlightDataEncoderBuilder::new()
             .with_schema(schema)
+            .with_dictionary_handling(DictionaryHandling::Resend)
             .build(s)
             .boxed()
     }
@@ -22,35 +22,48 @@ use tower_trailer::{HeaderMap, Trailers};
 // Workaround for "unused crate" lint false positives.
 use workspace_hack as _;
 
+mod deadline;
 mod keep_alive;
 mod planner;
 mod request;
+mod response;
+mod write;
+
+pub use response::{
+    FlightResponseConfig, DEFAULT_MAX_BUFFERED_MESSAGES, DEFAULT_MAX_MESSAGE_BYTES,
+};
+pub use write::{FlightWriteHandler, WriteAck, WriteError, WRITE_ACK_INTERVAL_BATCHES};
 
 use arrow::error::ArrowError;
 use arrow_flight::{
-    encode::FlightDataEncoderBuilder,
+    decode::FlightRecordBatchStream,
+    encode::{DictionaryHandling, FlightDataEncoderBuilder},
     error::FlightError,
     flight_descriptor::DescriptorType,
     flight_service_server::{FlightService as Flight, FlightServiceServer as FlightServer},
     Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
     HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
 };
+use arrow_util::flight::{prepare_batch_for_flight, prepare_schema_for_flight};
 use authz::{extract_token, Authorizer};
-use data_types::NamespaceNameError;
+use data_types::{NamespaceName, NamespaceNameError};
 use datafusion::{error::DataFusionError, physical_plan::ExecutionPlan};
 use flightsql::FlightSQLCommand;
 use futures::{ready, stream::BoxStream, Stream, StreamExt, TryStreamExt};
 use generated_types::influxdata::iox::querier::v1 as proto;
 use iox_query::{
     exec::IOxSessionContext,
-    query_log::{QueryCompletedToken, QueryLogEntry, StatePermit, StatePlanned},
+    query_log::{
+        CancellationReason, QueryCompletedToken, QueryLogEntry, StatePermit, StatePlanned,
+    },
+    warnings::QueryWarning,
     QueryNamespaceProvider,
 };
 use observability_deps::tracing::{debug, info, warn};
 use prost::Message;
 use request::{IoxGetRequest, RunQuery};
 use service_common::datafusion_error_to_tonic_code;
-use snafu::{OptionExt, ResultExt, Snafu};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::{
     fmt::Debug,
     pin::Pin,
@@ -64,7 +77,10 @@ use tonic::{
 };
 use trace::{ctx::SpanContext, span::SpanExt};
 use trace_http::ctx::{RequestLogContext, RequestLogContextExt};
-use tracker::InstrumentedAsyncOwnedSemaphorePermit;
+use tracker::{
+    InstrumentedAsyncOwnedSemaphorePermit, InstrumentedAsyncSemaphore, UnknownWorkloadClass,
+    WorkloadClass,
+};
 
 /// The supported names of the grpc header that contain the target database
 /// for FlightSQL requests.
@@ -78,6 +94,11 @@ const IOX_FLIGHT_SQL_DATABASE_REQUEST_HEADERS: [&str; 4] = [
     "iox-namespace-name", // deprecated
 ];
 
+/// The name of the grpc header that contains the [`WorkloadClass`] of a query, used to prioritize its admission.
+///
+/// Queries without this header are of class [`WorkloadClass::Normal`].
+const IOX_FLIGHT_WORKLOAD_CLASS_REQUEST_HEADER: &str = "iox-workload-class";
+
 /// Trailer that describes the duration (in seconds) for which a query was queued due to concurrency limits.
 const IOX_FLIGHT_QUEUE_DURATION_RESPONSE_TRAILER: &str = "x-influxdata-queue-duration-seconds";
 
@@ -92,9 +113,24 @@ const IOX_FLIGHT_EXECUTION_DURATION_RESPONSE_TRAILER: &str =
 /// Trailer that describes the duration (in seconds) the CPU(s) took to compute the results.
 const IOX_FLIGHT_COMPUTE_DURATION_RESPONSE_TRAILER: &str = "x-influxdata-compute-duration-seconds";
 
+/// Trailer that lists the warnings raised while planning a query, as a JSON array of objects with a `kind` and a
+/// `message`.
+///
+/// Only set if there are any warnings.
+const IOX_FLIGHT_WARNINGS_RESPONSE_TRAILER: &str = "x-influxdata-warnings";
+
 /// In which interval should the `DoGet` stream send empty messages as keep alive markers?
 const DO_GET_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(5);
 
+/// Maximum number of rows accepted by a `DoPut` request.
+///
+/// `DoPut` is only used to bind parameter values to prepared statements, which take exactly one row.
+const DO_PUT_MAX_ROWS: usize = 1;
+
+/// Maximum number of record batches accepted by a `DoPut` request, so that clients cannot make the server buffer an
+/// unbounded number of empty batches.
+const DO_PUT_MAX_BATCHES: usize = 16;
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -107,6 +143,36 @@ pub enum Error {
     #[snafu(display("Invalid handshake. No payload provided"))]
     InvalidHandshake {},
 
+    #[snafu(display("Invalid DoPut request. No flight descriptor provided"))]
+    NoFlightDescriptor,
+
+    #[snafu(display(
+        "Invalid DoPut request. Expected at most {} rows in at most {} batches",
+        max_rows,
+        max_batches
+    ))]
+    DoPutTooLarge { max_rows: usize, max_batches: usize },
+
+    #[snafu(display(
+        "Invalid DoExchange request. Expected a flight descriptor with path [database, table]"
+    ))]
+    InvalidWriteDescriptor,
+
+    #[snafu(display("Writes are not supported by this server"))]
+    WritesNotSupported,
+
+    #[snafu(display(
+        "Error writing to table '{}' in database '{}': {}",
+        table_name,
+        namespace_name,
+        source
+    ))]
+    Write {
+        namespace_name: String,
+        table_name: String,
+        source: WriteError,
+    },
+
     #[snafu(display("Database '{}' not found", namespace_name))]
     DatabaseNotFound { namespace_name: String },
 
@@ -139,6 +205,21 @@ pub enum Error {
     #[snafu(display("Invalid database name: {}", source))]
     InvalidDatabaseName { source: NamespaceNameError },
 
+    #[snafu(display("Invalid 'iox-workload-class' header in request: {}", source))]
+    InvalidWorkloadClassHeader { source: UnknownWorkloadClass },
+
+    #[snafu(display("Invalid 'grpc-timeout' header in request: {}", source))]
+    InvalidTimeoutHeader { source: deadline::Error },
+
+    #[snafu(display(
+        "Deadline exceeded while planning query for namespace {}",
+        namespace_name
+    ))]
+    DeadlineExceeded {
+        namespace_name: String,
+        query: String,
+    },
+
     #[snafu(display("Failed to optimize record batch: {}", source))]
     Optimize { source: ArrowError },
 
@@ -185,9 +266,16 @@ impl From<Error> for tonic::Status {
             Error::DatabaseNotFound { .. }
             | Error::InvalidTicket { .. }
             | Error::InvalidHandshake { .. }
+            | Error::NoFlightDescriptor
+            | Error::DoPutTooLarge { .. }
+            | Error::InvalidWriteDescriptor
+            | Error::WritesNotSupported
             | Error::Unauthenticated { .. }
             | Error::PermissionDenied { .. }
             | Error::InvalidDatabaseName { .. }
+            | Error::InvalidWorkloadClassHeader { .. }
+            | Error::InvalidTimeoutHeader { .. }
+            | Error::DeadlineExceeded { .. }
             | Error::Query { .. } => info!(e=%err, %namespace, %query, msg),
             Error::Optimize { .. }
             | Error::EncodeSchema { .. }
@@ -199,6 +287,7 @@ impl From<Error> for tonic::Status {
             | Error::InternalCreatingTicket { .. }
             | Error::UnsupportedMessageType { .. }
             | Error::FlightSQL { .. }
+            | Error::Write { .. }
             | Error::Authz { .. } => {
                 warn!(e=%err, %namespace, %query, msg)
             }
@@ -217,17 +306,27 @@ impl Error {
             Self::DatabaseNotFound { .. } => tonic::Code::NotFound,
             Self::InvalidTicket { .. }
             | Self::InvalidHandshake { .. }
+            | Self::NoFlightDescriptor
+            | Self::DoPutTooLarge { .. }
+            | Self::InvalidWriteDescriptor
             | Self::Deserialization { .. }
             | Self::TooManyFlightSQLDatabases { .. }
             | Self::NoFlightSQLDatabase
             | Self::InvalidDatabaseHeader { .. }
-            | Self::InvalidDatabaseName { .. } => tonic::Code::InvalidArgument,
+            | Self::InvalidDatabaseName { .. }
+            | Self::InvalidWorkloadClassHeader { .. }
+            | Self::InvalidTimeoutHeader { .. } => tonic::Code::InvalidArgument,
+            Self::DeadlineExceeded { .. } => tonic::Code::DeadlineExceeded,
             Self::Planning { source, .. } | Self::Query { source, .. } => {
                 datafusion_error_to_tonic_code(&source)
             }
-            Self::UnsupportedMessageType { .. } => tonic::Code::Unimplemented,
+            Self::UnsupportedMessageType { .. } | Self::WritesNotSupported => {
+                tonic::Code::Unimplemented
+            }
+            Self::Write { source, .. } => source.code(),
             Self::FlightSQL { source } => match source {
                 flightsql::Error::InvalidHandle { .. }
+                | flightsql::Error::InvalidParameters { .. }
                 | flightsql::Error::Decode { .. }
                 | flightsql::Error::Protocol { .. }
                 | flightsql::Error::UnsupportedMessageType { .. } => tonic::Code::InvalidArgument,
@@ -256,10 +355,16 @@ impl Error {
             Error::InvalidTicket { .. }
             | Error::InternalCreatingTicket { .. }
             | Error::InvalidHandshake {}
+            | Error::NoFlightDescriptor
+            | Error::DoPutTooLarge { .. }
+            | Error::InvalidWriteDescriptor
+            | Error::WritesNotSupported
             | Error::TooManyFlightSQLDatabases { .. }
             | Error::NoFlightSQLDatabase
             | Error::InvalidDatabaseHeader { .. }
             | Error::InvalidDatabaseName { .. }
+            | Error::InvalidWorkloadClassHeader { .. }
+            | Error::InvalidTimeoutHeader { .. }
             | Error::Optimize { .. }
             | Error::EncodeSchema { .. }
             | Error::FlightSQL { .. }
@@ -269,8 +374,10 @@ impl Error {
             | Error::PermissionDenied
             | Error::Authz { .. } => "<unknown>",
             Error::DatabaseNotFound { namespace_name } => namespace_name,
+            Error::DeadlineExceeded { namespace_name, .. } => namespace_name,
             Error::Query { namespace_name, .. } => namespace_name,
             Error::Planning { namespace_name, .. } => namespace_name,
+            Error::Write { namespace_name, .. } => namespace_name,
         }
     }
 
@@ -280,10 +387,16 @@ impl Error {
             Error::InvalidTicket { .. }
             | Error::InternalCreatingTicket { .. }
             | Error::InvalidHandshake {}
+            | Error::NoFlightDescriptor
+            | Error::DoPutTooLarge { .. }
+            | Error::InvalidWriteDescriptor
+            | Error::WritesNotSupported
             | Error::TooManyFlightSQLDatabases { .. }
             | Error::NoFlightSQLDatabase
             | Error::InvalidDatabaseHeader { .. }
             | Error::InvalidDatabaseName { .. }
+            | Error::InvalidWorkloadClassHeader { .. }
+            | Error::InvalidTimeoutHeader { .. }
             | Error::Optimize { .. }
             | Error::EncodeSchema { .. }
             | Error::FlightSQL { .. }
@@ -292,8 +405,10 @@ impl Error {
             | Error::Unauthenticated
             | Error::PermissionDenied
             | Error::Authz { .. }
-            | Error::DatabaseNotFound { .. } => "NONE",
+            | Error::DatabaseNotFound { .. }
+            | Error::Write { .. } => "NONE",
             Error::Query { query, .. } => query,
+            Error::DeadlineExceeded { query, .. } => query,
             Error::Planning { query, .. } => query,
         }
     }
@@ -433,7 +548,7 @@ type TonicStream<T> = Pin<Box<dyn Stream<Item = Result<T, tonic::Status>> + Send
 ///       ┃                                                  ┃
 /// ```
 ///
-/// ## FlightSQL Prepared Statement (no bind parameters like $1, etc)
+/// ## FlightSQL Prepared Statement
 ///
 /// To run a prepared query, via FlightSQL, the client undertakes a
 /// few more steps:
@@ -483,6 +598,32 @@ type TonicStream<T> = Pin<Box<dyn Stream<Item = Result<T, tonic::Status>> + Send
 ///     7 ┃◀ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ━ ┃
 /// ```
 ///
+/// ## FlightSQL Prepared Statement with bind parameters ($1, etc)
+///
+/// The `ActionCreatePreparedStatementResponse` describes the types of
+/// the parameters in its `parameter_schema`. Before step 4, the client
+/// binds values to these parameters:
+///
+/// 1. Call the `DoPut` method with the handle in a
+/// `CommandPreparedStatementQuery` FlightSQL structure in the
+/// [`FlightDescriptor`] and a single record batch with exactly one row
+/// that contains the values for `$1`, `$2`, ... in its columns.
+///
+/// 2. Receive a `PutResult` whose `app_metadata` contains a
+/// `DoPutPreparedStatementResult` with a new handle. Since IOx is
+/// stateless, this handle contains the bound values.
+///
+/// 3. Continue with step 4 above using the new handle.
+///
+/// # Streaming writes
+///
+/// If the server was created via [`make_server_with_writes`], clients
+/// may write record batches using `DoExchange`. The first message
+/// contains a [`FlightDescriptor`] with path `[database, table]`. The
+/// server echoes the schema accepted by the [`FlightWriteHandler`] and
+/// periodically acknowledges the written data with a [`WriteAck`]. See
+/// the `write` module for the full protocol.
+///
 /// [Arrow Flight]: https://arrow.apache.org/docs/format/Flight.html
 /// [Arrow FlightSQL]: https://arrow.apache.org/docs/format/FlightSql.html
 #[derive(Debug)]
@@ -492,6 +633,25 @@ where
 {
     server: Arc<S>,
     authz: Option<Arc<dyn Authorizer>>,
+    write_handler: Option<Arc<dyn FlightWriteHandler>>,
+    response_config: FlightResponseConfig,
+    planning_semaphore: Option<Arc<InstrumentedAsyncSemaphore>>,
+}
+
+/// Optional configuration of the Flight service, see [`make_server_with_config`].
+#[derive(Debug, Clone, Default)]
+pub struct FlightServiceConfig {
+    /// Accept streaming writes via `DoExchange`, see [`FlightWriteHandler`].
+    pub write_handler: Option<Arc<dyn FlightWriteHandler>>,
+
+    /// Chunking and buffering of query results.
+    pub response: FlightResponseConfig,
+
+    /// Limits the number of queries that are planned concurrently.
+    ///
+    /// Queries wait for a permit before planning starts, which is recorded as their
+    /// [queue duration](QueryLogEntry::queue_duration). The permit is released once the query is planned.
+    pub planning_semaphore: Option<Arc<InstrumentedAsyncSemaphore>>,
 }
 
 pub fn make_server<S>(
@@ -501,7 +661,51 @@ pub fn make_server<S>(
 where
     S: QueryNamespaceProvider,
 {
-    FlightServer::new(FlightService { server, authz })
+    make_server_with_config(server, authz, FlightServiceConfig::default())
+}
+
+/// Create a server that also accepts streaming writes via `DoExchange`,
+/// see [`FlightWriteHandler`].
+pub fn make_server_with_writes<S>(
+    server: Arc<S>,
+    authz: Option<Arc<dyn Authorizer>>,
+    write_handler: Arc<dyn FlightWriteHandler>,
+) -> FlightServer<impl Flight>
+where
+    S: QueryNamespaceProvider,
+{
+    make_server_with_config(
+        server,
+        authz,
+        FlightServiceConfig {
+            write_handler: Some(write_handler),
+            ..Default::default()
+        },
+    )
+}
+
+/// Create a server with the given [`FlightServiceConfig`].
+pub fn make_server_with_config<S>(
+    server: Arc<S>,
+    authz: Option<Arc<dyn Authorizer>>,
+    config: FlightServiceConfig,
+) -> FlightServer<impl Flight>
+where
+    S: QueryNamespaceProvider,
+{
+    let FlightServiceConfig {
+        write_handler,
+        response,
+        planning_semaphore,
+    } = config;
+
+    FlightServer::new(FlightService {
+        server,
+        authz,
+        write_handler,
+        response_config: response,
+        planning_semaphore,
+    })
 }
 
 impl<S> FlightService<S>
@@ -510,10 +714,12 @@ where
 {
     /// Implementation of the `DoGet` method
     async fn run_do_get(
-        server: Arc<S>,
+        &self,
         span_ctx: Option<SpanContext>,
         external_span_ctx: Option<RequestLogContext>,
         request: IoxGetRequest,
+        workload_class: WorkloadClass,
+        deadline: Option<tokio::time::Instant>,
         log_entry: &mut Option<Arc<QueryLogEntry>>,
     ) -> Result<TonicStream<FlightData>, tonic::Status> {
         let IoxGetRequest {
@@ -523,6 +729,7 @@ where
             is_debug,
         } = request;
         let namespace_name = database.as_str();
+        let server = Arc::clone(&self.server);
 
         let db = server
             .db(
@@ -538,10 +745,32 @@ where
             external_span_ctx.as_ref().map(RequestLogContext::ctx),
             query.variant(),
             Box::new(query.to_string()),
+            workload_class,
         );
 
         *log_entry = Some(Arc::clone(query_completed_token.entry()));
 
+        let (query_completed_token, planning_permit) = match &self.planning_semaphore {
+            Some(planning_semaphore) => {
+                let query_completed_token = query_completed_token.queued();
+                let permit_fut = planning_semaphore
+                    .acquire_owned(span_ctx.child_span("query planning semaphore"));
+                let Some(permit) = deadline::run_until(deadline, permit_fut).await else {
+                    query_completed_token
+                        .entry()
+                        .set_cancelled(CancellationReason::Timeout);
+                    return Err(Error::DeadlineExceeded {
+                        namespace_name: namespace_name.to_string(),
+                        query: query.to_string(),
+                    }
+                    .into());
+                };
+                let permit = permit.expect("planning semaphore is never closed");
+                (query_completed_token.admitted(), Some(permit))
+            }
+            None => (query_completed_token, None),
+        };
+
         // Log after we acquire the permit and are about to start execution
         info!(
             %namespace_name,
@@ -551,31 +780,39 @@ where
             "DoGet request",
         );
 
-        let ctx = db.new_query_context(span_ctx);
-        let physical_plan = match &query {
-            RunQuery::Sql(sql_query) => Planner::new(&ctx)
-                .sql(sql_query, params)
-                .await
-                .with_context(|_| PlanningSnafu {
-                    namespace_name,
-                    query: query.to_string(),
-                })?,
-            RunQuery::InfluxQL(sql_query) => Planner::new(&ctx)
-                .influxql(sql_query, params)
-                .await
-                .with_context(|_| PlanningSnafu {
-                namespace_name,
+        let ctx = db
+            .new_query_context(span_ctx)
+            .with_workload_class(workload_class);
+        let planner = Planner::new(&ctx);
+        let plan_fut = async {
+            match &query {
+                RunQuery::Sql(sql_query) => planner.sql(sql_query, params).await,
+                RunQuery::InfluxQL(sql_query) => planner.influxql(sql_query, params).await,
+                RunQuery::FlightSQL(msg) => {
+                    planner
+                        .flight_sql_do_get(namespace_name, db, msg.clone(), params)
+                        .await
+                }
+            }
+        };
+        let Some(physical_plan) = deadline::run_until(deadline, plan_fut).await else {
+            // the client gave up, stop any work that was already started
+            ctx.cancellation_token().cancel();
+            query_completed_token
+                .entry()
+                .set_cancelled(CancellationReason::Timeout);
+            return Err(Error::DeadlineExceeded {
+                namespace_name: namespace_name.to_string(),
                 query: query.to_string(),
-            })?,
-            RunQuery::FlightSQL(msg) => Planner::new(&ctx)
-                .flight_sql_do_get(namespace_name, db, msg.clone(), params)
-                .await
-                .with_context(|_| PlanningSnafu {
-                    namespace_name,
-                    query: query.to_string(),
-                })?,
+            }
+            .into());
         };
-        let query_completed_token = query_completed_token.planned(Arc::clone(&physical_plan));
+        let physical_plan = physical_plan.with_context(|_| PlanningSnafu {
+            namespace_name,
+            query: query.to_string(),
+        })?;
+        let query_completed_token = query_completed_token.planned(&ctx, Arc::clone(&physical_plan));
+        drop(planning_permit);
 
         let output = GetStream::new(
             server,
@@ -584,6 +821,10 @@ where
             namespace_name.to_string(),
             &query,
             query_completed_token,
+            GetStreamOptions {
+                deadline,
+                response_config: self.response_config,
+            },
         )
         .await?;
 
@@ -639,6 +880,10 @@ where
         let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
         let authz_token = get_flight_authz(request.metadata());
         let debug_header = has_debug_header(request.metadata());
+        let workload_class = get_workload_class(request.metadata())?;
+        let deadline = deadline::get_timeout(request.metadata())
+            .context(InvalidTimeoutHeaderSnafu)?
+            .map(|timeout| tokio::time::Instant::now() + timeout);
         let ticket = request.into_inner();
 
         // attempt to decode ticket
@@ -668,16 +913,17 @@ where
         // Also note that due to the keep alive mechanism, we cannot send any headers back because they might come
         // after a keep alive message and therefore aren't headers. gRPC metadata can only be sent at the very beginning
         // (headers) or at the very end (trailers). We shall use trailers.
-        let server = Arc::clone(&self.server);
         let mut log_entry = None;
-        let response = Self::run_do_get(
-            server,
-            span_ctx,
-            external_span_ctx.clone(),
-            request.clone(),
-            &mut log_entry,
-        )
-        .await;
+        let response = self
+            .run_do_get(
+                span_ctx,
+                external_span_ctx.clone(),
+                request.clone(),
+                workload_class,
+                deadline,
+                &mut log_entry,
+            )
+            .await;
 
         if let Err(e) = &response {
             info!(
@@ -829,13 +1075,90 @@ where
         Ok(tonic::Response::new(flight_info))
     }
 
+    /// Handles `DoPut` RPC requests. The [`FlightDescriptor`] of the
+    /// first message contains a FlightSQL command, encoded as a binary
+    /// ProtoBuf message, and the data is a stream of record batches.
+    ///
+    /// see [`FlightService`] for more details.
     async fn do_put(
         &self,
-        _request: Request<Streaming<FlightData>>,
+        request: Request<Streaming<FlightData>>,
     ) -> Result<Response<Self::DoPutStream>, tonic::Status> {
-        info!("Handling flightsql do_put body");
+        let external_span_ctx: Option<RequestLogContext> = request.extensions().get().cloned();
+        let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
+        let trace = external_span_ctx.format_jaeger();
+        let is_debug = has_debug_header(request.metadata());
+
+        let namespace_name = get_flightsql_namespace(request.metadata())?;
+        let authz_token = get_flight_authz(request.metadata());
+        let mut stream = request.into_inner();
+
+        // the first message carries the descriptor (and potentially the schema)
+        let first = stream.message().await?.context(NoFlightDescriptorSnafu)?;
+        let flight_descriptor = first
+            .flight_descriptor
+            .clone()
+            .context(NoFlightDescriptorSnafu)?;
+
+        // extract the FlightSQL message
+        let cmd = cmd_from_descriptor(flight_descriptor)?;
+        info!(%namespace_name, %cmd, %trace, "DoPut request");
+
+        let perms = flightsql_permissions(&namespace_name, &cmd);
+        self.authz
+            .permissions(authz_token, &perms)
+            .await
+            .map_err(Error::from)?;
 
-        Err(tonic::Status::unimplemented("Not yet implemented: do_put"))
+        let mut batch_stream = FlightRecordBatchStream::new_from_flight_data(
+            futures::stream::once(async move { Ok(first) })
+                .chain(stream)
+                .map_err(FlightError::Tonic),
+        );
+        let mut batches = vec![];
+        let mut num_rows = 0;
+        while let Some(batch) = batch_stream
+            .try_next()
+            .await
+            .map_err(flightsql::Error::from)
+            .context(FlightSQLSnafu)?
+        {
+            num_rows += batch.num_rows();
+            ensure!(
+                num_rows <= DO_PUT_MAX_ROWS && batches.len() < DO_PUT_MAX_BATCHES,
+                DoPutTooLargeSnafu {
+                    max_rows: DO_PUT_MAX_ROWS,
+                    max_batches: DO_PUT_MAX_BATCHES,
+                }
+            );
+            batches.push(batch);
+        }
+
+        let db = self
+            .server
+            .db(
+                &namespace_name,
+                span_ctx.child_span("get namespace"),
+                is_debug,
+            )
+            .await
+            .context(DatabaseNotFoundSnafu {
+                namespace_name: &namespace_name,
+            })?;
+
+        let ctx = db.new_query_context(span_ctx);
+        let app_metadata = Planner::new(&ctx)
+            .flight_sql_do_put(&namespace_name, db, cmd.clone(), batches)
+            .await
+            .context(PlanningSnafu {
+                namespace_name: &namespace_name,
+                query: format!("{cmd:?}"),
+            })?;
+
+        let result = PutResult { app_metadata };
+        let stream = futures::stream::iter([Ok(result)]);
+
+        Ok(Response::new(stream.boxed()))
     }
 
     async fn do_action(
@@ -901,16 +1224,81 @@ where
         ))
     }
 
+    /// Handles `DoExchange` RPC requests, which are used for streaming
+    /// writes of record batches.
+    ///
+    /// See [`FlightService`] for more details.
     async fn do_exchange(
         &self,
-        _request: Request<Streaming<FlightData>>,
+        request: Request<Streaming<FlightData>>,
     ) -> Result<Response<Self::DoExchangeStream>, tonic::Status> {
-        Err(tonic::Status::unimplemented(
-            "Not yet implemented: do_exchange",
-        ))
+        let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
+        let authz_token = get_flight_authz(request.metadata());
+        let write_handler = self
+            .write_handler
+            .as_ref()
+            .map(Arc::clone)
+            .context(WritesNotSupportedSnafu)?;
+        let mut stream = request.into_inner();
+
+        // the first message carries the descriptor (and potentially the schema)
+        let first = stream.message().await?.context(NoFlightDescriptorSnafu)?;
+        let flight_descriptor = first
+            .flight_descriptor
+            .clone()
+            .context(NoFlightDescriptorSnafu)?;
+        let (namespace_name, table_name) = write_target_from_descriptor(flight_descriptor)?;
+        info!(%namespace_name, %table_name, "DoExchange write request");
+
+        let perms = vec![authz::Permission::ResourceAction(
+            authz::Resource::Database(namespace_name.clone()),
+            authz::Action::Write,
+        )];
+        self.authz
+            .permissions(authz_token, &perms)
+            .await
+            .map_err(Error::from)?;
+
+        let input = futures::stream::once(async move { Ok(first) })
+            .chain(stream)
+            .map_err(FlightError::Tonic);
+        let output = write::write_stream(
+            write_handler,
+            namespace_name.clone(),
+            table_name.clone(),
+            span_ctx,
+            input,
+        )
+        .map_err(move |source| {
+            tonic::Status::from(Error::Write {
+                namespace_name: namespace_name.clone(),
+                table_name: table_name.clone(),
+                source,
+            })
+        });
+
+        Ok(Response::new(output.boxed()))
     }
 }
 
+/// Extracts the database (namespace) and table name of a `DoExchange`
+/// write from the path of a [`FlightDescriptor`].
+fn write_target_from_descriptor(flight_descriptor: FlightDescriptor) -> Result<(String, String)> {
+    ensure!(
+        flight_descriptor.r#type() == DescriptorType::Path,
+        InvalidWriteDescriptorSnafu
+    );
+
+    let [namespace_name, table_name]: [String; 2] = flight_descriptor
+        .path
+        .try_into()
+        .map_err(|_| Error::InvalidWriteDescriptor)?;
+    ensure!(!table_name.is_empty(), InvalidWriteDescriptorSnafu);
+    NamespaceName::new(namespace_name.as_str()).context(InvalidDatabaseNameSnafu)?;
+
+    Ok((namespace_name, table_name))
+}
+
 /// Extracts an encoded Protobuf message from a [`FlightDescriptor`],
 /// as used in FlightSQL.
 fn cmd_from_descriptor(flight_descriptor: FlightDescriptor) -> Result<FlightSQLCommand> {
@@ -1000,12 +1388,32 @@ fn has_debug_header(metadata: &MetadataMap) -> bool {
         .unwrap_or_default()
 }
 
+/// Retrieve the [`WorkloadClass`] of the request.
+fn get_workload_class(metadata: &MetadataMap) -> Result<WorkloadClass> {
+    match metadata.get(IOX_FLIGHT_WORKLOAD_CLASS_REQUEST_HEADER) {
+        Some(class) => String::from_utf8_lossy(class.as_bytes())
+            .parse()
+            .context(InvalidWorkloadClassHeaderSnafu),
+        None => Ok(WorkloadClass::default()),
+    }
+}
+
 struct PermitAndToken {
     #[allow(dead_code)]
     permit: InstrumentedAsyncOwnedSemaphorePermit,
     query_completed_token: QueryCompletedToken<StatePermit>,
 }
 
+/// Options of a [`GetStream`].
+#[derive(Debug, Clone, Copy)]
+struct GetStreamOptions {
+    /// Point in time at which the client gives up on the query, if any.
+    deadline: Option<tokio::time::Instant>,
+
+    /// Shape of the response messages.
+    response_config: FlightResponseConfig,
+}
+
 /// Wrapper over a FlightDataEncodeStream that adds IOx specific
 /// metadata and records completion
 struct GetStream {
@@ -1022,13 +1430,21 @@ impl GetStream {
         namespace_name: String,
         query: &RunQuery,
         query_completed_token: QueryCompletedToken<StatePlanned>,
+        options: GetStreamOptions,
     ) -> Result<Self, tonic::Status>
     where
         S: QueryNamespaceProvider,
     {
+        let GetStreamOptions {
+            deadline,
+            response_config,
+        } = options;
         let app_metadata = proto::AppMetadata {};
 
-        let schema = physical_plan.schema();
+        // dictionaries are sent as is, see `prepare_schema_for_flight`
+        let schema = prepare_schema_for_flight(physical_plan.schema());
+        let cancel = ctx.cancellation_token().clone();
+        let log_entry = Arc::clone(query_completed_token.entry());
 
         let query_results = ctx
             .execute_stream(Arc::clone(&physical_plan))
@@ -1046,8 +1462,11 @@ impl GetStream {
         let permit_state: Arc<Mutex<Option<PermitAndToken>>> = Default::default();
         let permit_state_captured = Arc::clone(&permit_state);
         let permit_span = ctx.child_span("query rate limit semaphore");
+        let workload_class = ctx.workload_class();
         let query_results = futures::stream::once(async move {
-            let permit = server.acquire_semaphore(permit_span).await;
+            let permit = server
+                .acquire_semaphore(&namespace_name, permit_span, workload_class)
+                .await;
             let query_completed_token = query_completed_token.permit();
             *permit_state_captured.lock().expect("not poisened") = Some(PermitAndToken {
                 permit,
@@ -1057,14 +1476,40 @@ impl GetStream {
         })
         .flatten();
 
+        // split large batches
+        let query_results =
+            response::split_batches(query_results, response_config.max_rows_per_message);
+
+        // compact the dictionaries of every (split) batch
+        let flight_schema = Arc::clone(&schema);
+        let query_results = query_results.and_then(move |batch| {
+            let res = prepare_batch_for_flight(&batch, Arc::clone(&flight_schema))
+                .map_err(FlightError::Arrow);
+            futures::future::ready(res)
+        });
+
         // setup encoding stream
         let encoded = FlightDataEncoderBuilder::new()
             .with_schema(schema)
+            .with_dictionary_handling(DictionaryHandling::Resend)
             .with_metadata(app_metadata.encode_to_vec().into())
+            .with_max_flight_data_size(response_config.max_message_bytes)
             .build(query_results);
 
+        // bounded read-ahead, the query is paused if the client is slow
+        let encoded = response::buffered(encoded, response_config.max_buffered_messages);
+
         // keep-alive
-        let inner = KeepAliveStream::new(encoded, DO_GET_KEEP_ALIVE_INTERVAL).boxed();
+        let inner = KeepAliveStream::new(encoded, DO_GET_KEEP_ALIVE_INTERVAL);
+
+        // stop once the client gave up, this also covers waiting for the semaphore
+        let inner = deadline::with_deadline(inner, deadline, move || {
+            cancel.cancel();
+            log_entry.set_cancelled(CancellationReason::Timeout);
+            FlightError::Tonic(tonic::Status::deadline_exceeded(
+                "Deadline exceeded while executing query",
+            ))
+        });
 
         Ok(Self {
             inner,
@@ -1105,6 +1550,10 @@ impl Stream for GetStream {
                     }
                 }
                 Some(Ok(data)) => {
+                    if let Some(state) = self.permit_state.lock().expect("not poisened").as_mut() {
+                        let bytes = data.data_header.len() + data.data_body.len();
+                        state.query_completed_token.add_output_bytes(bytes as u64);
+                    }
                     return Poll::Ready(Some(Ok(data)));
                 }
                 Some(Err(e)) => {
@@ -1135,6 +1584,34 @@ impl QueryResponseMetadata {
         );
     }
 
+    fn write_trailer_warnings(md: &mut HeaderMap, warnings: &[QueryWarning]) {
+        if warnings.is_empty() {
+            return;
+        }
+
+        let json = serde_json::Value::Array(
+            warnings
+                .iter()
+                .map(|w| {
+                    serde_json::json!({
+                        "kind": w.kind.as_str(),
+                        "message": w.message,
+                    })
+                })
+                .collect(),
+        )
+        .to_string();
+
+        match json.parse() {
+            Ok(value) => {
+                md.insert(IOX_FLIGHT_WARNINGS_RESPONSE_TRAILER, value);
+            }
+            Err(e) => {
+                warn!(%e, "cannot encode query warnings as trailer");
+            }
+        }
+    }
+
     fn write_trailers(&self, md: &mut HeaderMap) {
         let Some(log_entry) = &self.log_entry else {
             return;
@@ -1160,6 +1637,7 @@ impl QueryResponseMetadata {
             IOX_FLIGHT_COMPUTE_DURATION_RESPONSE_TRAILER,
             log_entry.compute_duration(),
         );
+        Self::write_trailer_warnings(md, &log_entry.warnings());
     }
 }
 
@@ -1174,6 +1652,7 @@ mod tests {
     use test_helpers::maybe_start_logging;
     use tokio::pin;
     use tonic::metadata::{MetadataKey, MetadataValue};
+    use tracker::AsyncSemaphoreMetrics;
 
     use super::*;
 
@@ -1204,6 +1683,9 @@ mod tests {
         let service = FlightService {
             server: Arc::clone(&test_storage),
             authz: Option::<Arc<dyn Authorizer>>::None,
+            write_handler: None,
+            response_config: Default::default(),
+            planning_semaphore: None,
         };
         let ticket = Ticket {
             ticket: br#"{"namespace_name": "my_db", "sql_query": "SELECT 1;"}"#
@@ -1325,6 +1807,61 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_planning_semaphore() {
+        let test_storage = Arc::new(TestDatabaseStore::new());
+        test_storage.db_or_create("my_db").await;
+
+        let planning_semaphore =
+            Arc::new(Arc::new(AsyncSemaphoreMetrics::new_unregistered()).new_semaphore(1));
+        let service = FlightService {
+            server: Arc::clone(&test_storage),
+            authz: Option::<Arc<dyn Authorizer>>::None,
+            write_handler: None,
+            response_config: Default::default(),
+            planning_semaphore: Some(Arc::clone(&planning_semaphore)),
+        };
+        let ticket = Ticket {
+            ticket: br#"{"namespace_name": "my_db", "sql_query": "SELECT 1;"}"#
+                .to_vec()
+                .into(),
+        };
+
+        // planning waits for a permit
+        let permit = planning_semaphore.acquire_owned(None).await.unwrap();
+        let fut = service.do_get(tonic::Request::new(ticket));
+        pin!(fut);
+        assert_fut_pending(&mut fut).await;
+        assert_eq!(planning_semaphore.holders_pending(), 1);
+
+        drop(permit);
+        let mut streaming_resp = fut.await.unwrap().into_inner();
+
+        // the permit is released once the query is planned
+        assert_eq!(planning_semaphore.holders_acquired(), 0);
+        streaming_resp.next().await.unwrap().unwrap(); // schema (planning)
+        streaming_resp.next().await.unwrap().unwrap(); // record batch (execution)
+    }
+
+    #[test]
+    fn test_get_workload_class() {
+        let mut metadata = MetadataMap::new();
+        assert_eq!(
+            get_workload_class(&metadata).unwrap(),
+            WorkloadClass::Normal
+        );
+
+        metadata.insert("iox-workload-class", "interactive".parse().unwrap());
+        assert_eq!(
+            get_workload_class(&metadata).unwrap(),
+            WorkloadClass::Interactive
+        );
+
+        metadata.insert("iox-workload-class", "foo".parse().unwrap());
+        let status = tonic::Status::from(get_workload_class(&metadata).unwrap_err());
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
     /// Assert that given future is pending.
     ///
     /// This will try to poll the future a bit to ensure that it is not stuck in tokios task preemption.
@@ -1382,6 +1919,9 @@ mod tests {
         let svc = FlightService {
             server: Arc::clone(&test_storage),
             authz: Some(Arc::new(MockAuthorizer {})),
+            write_handler: None,
+            response_config: Default::default(),
+            planning_semaphore: None,
         };
 
         async fn assert_code(
@@ -1490,6 +2030,9 @@ mod tests {
         let svc = FlightService {
             server: Arc::clone(&test_storage),
             authz: Some(Arc::new(MockAuthorizer {})),
+            write_handler: None,
+            response_config: Default::default(),
+            planning_semaphore: None,
         };
 
         async fn assert_code(
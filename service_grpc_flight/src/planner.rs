@@ -3,7 +3,9 @@ use std::sync::Arc;
 
 use bytes::Bytes;
 use datafusion::{
-    arrow::datatypes::SchemaRef, error::DataFusionError, physical_plan::ExecutionPlan,
+    arrow::{datatypes::SchemaRef, record_batch::RecordBatch},
+    error::DataFusionError,
+    physical_plan::ExecutionPlan,
 };
 use flightsql::{FlightSQLCommand, FlightSQLPlanner};
 use iox_query::{exec::IOxSessionContext, frontend::sql::SqlQueryPlanner, QueryNamespace};
@@ -95,6 +97,23 @@ impl Planner {
             .map_err(DataFusionError::from)
     }
 
+    /// Handles a `DoPut` FlightSQL message, as described on
+    /// [`FlightSQLPlanner::do_put`], on a separate threadpool
+    pub(crate) async fn flight_sql_do_put(
+        &self,
+        namespace_name: impl Into<String> + Send,
+        namespace: Arc<dyn QueryNamespace>,
+        cmd: FlightSQLCommand,
+        batches: Vec<RecordBatch>,
+    ) -> Result<Bytes> {
+        let namespace_name = namespace_name.into();
+        let ctx = self.ctx.child_ctx("planner flight_sql_do_put");
+
+        FlightSQLPlanner::do_put(namespace_name, namespace, cmd, batches, &ctx)
+            .await
+            .map_err(DataFusionError::from)
+    }
+
     /// Returns the [`SchemaRef`] to be included in the response to a
     /// `GetFlightInfo` FlightSQL message as described on
     /// [`FlightSQLPlanner::get_schema`], on a separate threadpool.
@@ -0,0 +1,261 @@
+//! Chunking and buffering of query result streams.
+//!
+//! Query results are sent as a stream of [`FlightData`] messages. To keep the memory usage of the querier bounded
+//! even for clients on slow links:
+//!
+//! - record batches are split into messages of at most [`FlightResponseConfig::max_rows_per_message`] rows and
+//!   roughly [`FlightResponseConfig::max_message_bytes`] bytes
+//! - at most [`FlightResponseConfig::max_buffered_messages`] encoded messages are produced ahead of the client. If
+//!   the buffer is full, the query execution is paused until the client catches up (backpressure).
+use std::{
+    future::Future,
+    num::NonZeroUsize,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use arrow::record_batch::RecordBatch;
+use arrow_flight::FlightData;
+use futures::{stream::BoxStream, Stream, StreamExt, TryStreamExt};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Default for [`FlightResponseConfig::max_message_bytes`], same as the Arrow Flight encoder.
+pub const DEFAULT_MAX_MESSAGE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Default for [`FlightResponseConfig::max_buffered_messages`].
+pub const DEFAULT_MAX_BUFFERED_MESSAGES: usize = 2;
+
+/// Configuration of the query result stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlightResponseConfig {
+    /// Maximum number of rows per [`FlightData`] message, unlimited if `None`.
+    pub max_rows_per_message: Option<NonZeroUsize>,
+
+    /// Target maximum size of the data of a single [`FlightData`] message.
+    ///
+    /// This is a soft limit, a single row may still exceed it.
+    pub max_message_bytes: usize,
+
+    /// Maximum number of encoded messages that are buffered ahead of the client.
+    ///
+    /// If set to 0, messages are only produced when the client polls the next one.
+    pub max_buffered_messages: usize,
+}
+
+impl Default for FlightResponseConfig {
+    fn default() -> Self {
+        Self {
+            max_rows_per_message: None,
+            max_message_bytes: DEFAULT_MAX_MESSAGE_BYTES,
+            max_buffered_messages: DEFAULT_MAX_BUFFERED_MESSAGES,
+        }
+    }
+}
+
+/// Split record batches into batches of at most `max_rows` rows.
+pub(crate) fn split_batches<S, E>(
+    stream: S,
+    max_rows: Option<NonZeroUsize>,
+) -> BoxStream<'static, Result<RecordBatch, E>>
+where
+    S: Stream<Item = Result<RecordBatch, E>> + Send + 'static,
+    E: Send + 'static,
+{
+    let Some(max_rows) = max_rows else {
+        return stream.boxed();
+    };
+
+    stream
+        .map_ok(move |batch| futures::stream::iter(split_batch(batch, max_rows).map(Ok)))
+        .try_flatten()
+        .boxed()
+}
+
+/// Split a single record batch into slices of at most `max_rows` rows.
+///
+/// Slicing is zero-copy. Empty batches are passed through unchanged.
+fn split_batch(
+    batch: RecordBatch,
+    max_rows: NonZeroUsize,
+) -> impl Iterator<Item = RecordBatch> + Send {
+    let max_rows = max_rows.get();
+    let num_rows = batch.num_rows();
+    let num_slices = num_rows.div_ceil(max_rows).max(1);
+
+    (0..num_slices).map(move |i| {
+        let offset = i * max_rows;
+        batch.slice(offset, max_rows.min(num_rows - offset))
+    })
+}
+
+/// Produce up to `capacity` items of `stream` ahead of the consumer.
+///
+/// The inner stream is driven by a separate task that is aborted when the returned stream is dropped. If the buffer is
+/// full, the inner stream is not polled until the consumer catches up. If the task panics, the returned stream ends
+/// with an internal error instead of looking like a complete response.
+pub(crate) fn buffered<S, E>(
+    stream: S,
+    capacity: usize,
+) -> BoxStream<'static, Result<FlightData, E>>
+where
+    S: Stream<Item = Result<FlightData, E>> + Send + 'static,
+    E: From<tonic::Status> + Send + 'static,
+{
+    if capacity == 0 {
+        return stream.boxed();
+    }
+
+    let (tx, rx) = mpsc::channel(capacity);
+    let task = tokio::spawn(async move {
+        let mut stream = std::pin::pin!(stream);
+        while let Some(item) = stream.next().await {
+            if tx.send(item).await.is_err() {
+                // receiver gone
+                return;
+            }
+        }
+    });
+
+    BufferedStream {
+        rx,
+        task: Some(task),
+    }
+    .boxed()
+}
+
+/// Receiving end of [`buffered`].
+#[derive(Debug)]
+struct BufferedStream<T> {
+    rx: mpsc::Receiver<T>,
+
+    /// Producer task, `None` once it was joined.
+    task: Option<JoinHandle<()>>,
+}
+
+impl<T> Drop for BufferedStream<T> {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+impl<E> Stream for BufferedStream<Result<FlightData, E>>
+where
+    E: From<tonic::Status>,
+{
+    type Item = Result<FlightData, E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(item) = ready!(self.rx.poll_recv(cx)) {
+            return Poll::Ready(Some(item));
+        }
+
+        // the producer finished, but it may have done so by panicking
+        let Some(task) = self.task.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let res = ready!(Pin::new(task).poll(cx));
+        self.task = None;
+        match res {
+            Ok(()) => Poll::Ready(None),
+            Err(e) => Poll::Ready(Some(Err(tonic::Status::internal(format!(
+                "query result stream failed: {e}"
+            ))
+            .into()))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+    };
+
+    use super::*;
+
+    fn batch(rows: i64) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int64, false)]));
+        RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int64Array::from_iter_values(0..rows))],
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_split_batches() {
+        let input = futures::stream::iter([batch(7), batch(0), batch(3)].map(Ok::<_, ()>));
+
+        let rows = split_batches(input, NonZeroUsize::new(3))
+            .map_ok(|batch| batch.num_rows())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(rows, vec![3, 3, 1, 0, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_split_batches_unlimited() {
+        let input = futures::stream::iter([batch(7)].map(Ok::<_, ()>));
+
+        let rows = split_batches(input, None)
+            .map_ok(|batch| batch.num_rows())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        assert_eq!(rows, vec![7]);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_backpressure() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let produced_captured = Arc::clone(&produced);
+        let input = futures::stream::iter(0..10).map(move |_| {
+            produced_captured.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, tonic::Status>(FlightData::new())
+        });
+
+        let mut stream = buffered(input, 2);
+
+        // give the producer a chance to run ahead
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        // 2 messages in the channel + 1 that waits for capacity
+        assert!(produced.load(Ordering::SeqCst) <= 3);
+
+        let mut n = 0;
+        while let Some(res) = stream.next().await {
+            res.unwrap();
+            n += 1;
+        }
+        assert_eq!(n, 10);
+        assert_eq!(produced.load(Ordering::SeqCst), 10);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_panic() {
+        let input = futures::stream::iter(0..10).map(|i| {
+            if i == 3 {
+                panic!("producer failed");
+            }
+            Ok::<_, tonic::Status>(FlightData::new())
+        });
+
+        let res = buffered(input, 2).collect::<Vec<_>>().await;
+
+        assert_eq!(res.len(), 4);
+        assert!(res[..3].iter().all(|res| res.is_ok()));
+        let err = res[3].as_ref().unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Internal);
+        assert!(err.message().contains("panicked"), "{err}");
+    }
+}
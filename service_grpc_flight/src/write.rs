@@ -0,0 +1,344 @@
+//! Streaming writes of Arrow record batches via the Flight `DoExchange` RPC.
+//!
+//! # Protocol
+//! The client opens a `DoExchange` stream and sends:
+//!
+//! 1. A [`FlightData`] message that contains a [`FlightDescriptor`] of type `PATH` with two elements: the database
+//!    (namespace) name and the table name. The same message (or the next one) carries the Arrow schema of the data.
+//! 2. Any number of record batches.
+//!
+//! The server responds with:
+//!
+//! 1. The schema that was accepted by the [`FlightWriteHandler`] for every schema sent by the client ("schema
+//!    negotiation"). The handler may reject a schema or return a compatible one (e.g. with coerced column types).
+//! 2. A [`WriteAck`] in the `app_metadata` of an otherwise empty [`FlightData`] message every
+//!    [`WRITE_ACK_INTERVAL_BATCHES`] batches as well as once the client closed its side of the stream.
+//!
+//! Batches are only acknowledged after they were accepted by the [`FlightWriteHandler`], so a client may use the acks
+//! to bound the amount of unacknowledged data in flight.
+//!
+//! [`FlightDescriptor`]: arrow_flight::FlightDescriptor
+use std::{fmt::Debug, sync::Arc};
+
+use arrow::{datatypes::SchemaRef, ipc::writer::IpcWriteOptions, record_batch::RecordBatch};
+use arrow_flight::{
+    decode::{DecodedPayload, FlightDataDecoder},
+    error::FlightError,
+    FlightData, SchemaAsIpc,
+};
+use async_trait::async_trait;
+use futures::{stream::BoxStream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use trace::ctx::SpanContext;
+
+/// Number of record batches after which the server acknowledges the write progress.
+pub const WRITE_ACK_INTERVAL_BATCHES: u64 = 16;
+
+/// Errors returned by a [`FlightWriteHandler`].
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+#[allow(missing_docs)]
+pub enum WriteError {
+    #[snafu(display("Database '{}' not found", namespace_name))]
+    NamespaceNotFound { namespace_name: String },
+
+    #[snafu(display("Invalid write: {}", message))]
+    InvalidData { message: String },
+
+    #[snafu(display("Error decoding write stream: {}", source))]
+    Decode { source: FlightError },
+
+    #[snafu(display("Write failed: {}", source))]
+    Internal {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl WriteError {
+    /// Converts this error into the appropriate tonic (gRPC) status code.
+    pub(crate) fn code(&self) -> tonic::Code {
+        match self {
+            Self::NamespaceNotFound { .. } => tonic::Code::NotFound,
+            Self::InvalidData { .. } | Self::Decode { .. } => tonic::Code::InvalidArgument,
+            Self::Internal { .. } => tonic::Code::Internal,
+        }
+    }
+}
+
+/// Sink for record batches that are written via Flight.
+#[async_trait]
+pub trait FlightWriteHandler: Debug + Send + Sync + 'static {
+    /// Check the schema of a write stream before any data is accepted.
+    ///
+    /// Returns the schema that the handler accepts, which is sent back to the client. All batches passed to
+    /// [`write`](Self::write) afterwards use the schema that was sent by the client.
+    async fn negotiate_schema(
+        &self,
+        namespace_name: &str,
+        table_name: &str,
+        schema: SchemaRef,
+    ) -> Result<SchemaRef, WriteError>;
+
+    /// Write a single record batch.
+    async fn write(
+        &self,
+        namespace_name: &str,
+        table_name: &str,
+        batch: RecordBatch,
+        span_ctx: Option<SpanContext>,
+    ) -> Result<(), WriteError>;
+}
+
+/// Write progress, sent as JSON in the `app_metadata` of [`FlightData`] messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WriteAck {
+    /// Total number of record batches that were written so far.
+    pub batches: u64,
+
+    /// Total number of rows that were written so far.
+    pub rows: u64,
+}
+
+impl From<WriteAck> for FlightData {
+    fn from(ack: WriteAck) -> Self {
+        let app_metadata = serde_json::to_vec(&ack).expect("ack serialization cannot fail");
+        Self::new().with_app_metadata(app_metadata)
+    }
+}
+
+/// State of a single write stream.
+#[derive(Debug)]
+struct WriteState {
+    handler: Arc<dyn FlightWriteHandler>,
+    namespace_name: String,
+    table_name: String,
+    span_ctx: Option<SpanContext>,
+    decoder: FlightDataDecoder,
+    ack: WriteAck,
+    batches_since_ack: u64,
+    done: bool,
+}
+
+impl WriteState {
+    /// Process input until the next response message is due.
+    async fn advance(&mut self) -> Result<Option<FlightData>, WriteError> {
+        if self.done {
+            return Ok(None);
+        }
+
+        loop {
+            let Some(data) = self.decoder.next().await else {
+                // client is done, send final ack
+                self.done = true;
+                return Ok(Some(self.ack.into()));
+            };
+            let data = data.context(DecodeSnafu)?;
+
+            match data.payload {
+                DecodedPayload::None => {}
+                DecodedPayload::Schema(schema) => {
+                    let schema = self
+                        .handler
+                        .negotiate_schema(&self.namespace_name, &self.table_name, schema)
+                        .await?;
+                    return Ok(Some(
+                        SchemaAsIpc::new(&schema, &IpcWriteOptions::default()).into(),
+                    ));
+                }
+                DecodedPayload::RecordBatch(batch) => {
+                    let rows = batch.num_rows() as u64;
+                    self.handler
+                        .write(
+                            &self.namespace_name,
+                            &self.table_name,
+                            batch,
+                            self.span_ctx.clone(),
+                        )
+                        .await?;
+
+                    self.ack.batches += 1;
+                    self.ack.rows += rows;
+                    self.batches_since_ack += 1;
+                    if self.batches_since_ack >= WRITE_ACK_INTERVAL_BATCHES {
+                        self.batches_since_ack = 0;
+                        return Ok(Some(self.ack.into()));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pass the record batches of the given [`FlightData`] stream to the handler and return the response stream that
+/// contains the negotiated schemas and the acknowledgements, see [module docs](self).
+pub(crate) fn write_stream<S>(
+    handler: Arc<dyn FlightWriteHandler>,
+    namespace_name: String,
+    table_name: String,
+    span_ctx: Option<SpanContext>,
+    input: S,
+) -> BoxStream<'static, Result<FlightData, WriteError>>
+where
+    S: Stream<Item = Result<FlightData, FlightError>> + Send + 'static,
+{
+    let state = WriteState {
+        handler,
+        namespace_name,
+        table_name,
+        span_ctx,
+        decoder: FlightDataDecoder::new(input),
+        ack: WriteAck::default(),
+        batches_since_ack: 0,
+        done: false,
+    };
+
+    futures::stream::try_unfold(state, |mut state| async move {
+        state.advance().await.map(|msg| msg.map(|msg| (msg, state)))
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+    };
+    use arrow_flight::encode::FlightDataEncoderBuilder;
+    use futures::TryStreamExt;
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct MockHandler {
+        writes: Mutex<Vec<(String, String, usize)>>,
+    }
+
+    #[async_trait]
+    impl FlightWriteHandler for MockHandler {
+        async fn negotiate_schema(
+            &self,
+            _namespace_name: &str,
+            _table_name: &str,
+            schema: SchemaRef,
+        ) -> Result<SchemaRef, WriteError> {
+            if schema.fields().is_empty() {
+                return Err(WriteError::InvalidData {
+                    message: "no columns".to_owned(),
+                });
+            }
+            Ok(schema)
+        }
+
+        async fn write(
+            &self,
+            namespace_name: &str,
+            table_name: &str,
+            batch: RecordBatch,
+            _span_ctx: Option<SpanContext>,
+        ) -> Result<(), WriteError> {
+            self.writes.lock().unwrap().push((
+                namespace_name.to_owned(),
+                table_name.to_owned(),
+                batch.num_rows(),
+            ));
+            Ok(())
+        }
+    }
+
+    fn batch(rows: i64) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("x", DataType::Int64, false)]));
+        RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int64Array::from_iter_values(0..rows))],
+        )
+        .unwrap()
+    }
+
+    async fn encode(batches: Vec<RecordBatch>) -> Vec<Result<FlightData, FlightError>> {
+        FlightDataEncoderBuilder::new()
+            .build(futures::stream::iter(batches.into_iter().map(Ok)))
+            .collect()
+            .await
+    }
+
+    async fn run(
+        handler: Arc<MockHandler>,
+        input: Vec<Result<FlightData, FlightError>>,
+    ) -> Result<Vec<FlightData>, WriteError> {
+        write_stream(
+            handler,
+            "ns".to_owned(),
+            "table".to_owned(),
+            None,
+            futures::stream::iter(input),
+        )
+        .try_collect()
+        .await
+    }
+
+    fn ack(data: &FlightData) -> WriteAck {
+        serde_json::from_slice(&data.app_metadata).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_write_stream() {
+        let handler = Arc::new(MockHandler::default());
+        let n = WRITE_ACK_INTERVAL_BATCHES + 2;
+        let batches = (0..n).map(|_| batch(3)).collect::<Vec<_>>();
+
+        let response = run(Arc::clone(&handler), encode(batches).await)
+            .await
+            .unwrap();
+
+        // schema, periodic ack, final ack
+        assert_eq!(response.len(), 3);
+        assert!(!response[0].data_header.is_empty());
+        assert!(response[0].app_metadata.is_empty());
+        assert_eq!(
+            ack(&response[1]),
+            WriteAck {
+                batches: WRITE_ACK_INTERVAL_BATCHES,
+                rows: WRITE_ACK_INTERVAL_BATCHES * 3,
+            }
+        );
+        assert_eq!(
+            ack(&response[2]),
+            WriteAck {
+                batches: n,
+                rows: n * 3
+            }
+        );
+
+        let writes = handler.writes.lock().unwrap();
+        assert_eq!(writes.len(), n as usize);
+        assert_eq!(writes[0], ("ns".to_owned(), "table".to_owned(), 3));
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_empty() {
+        let handler = Arc::new(MockHandler::default());
+
+        let response = run(Arc::clone(&handler), vec![]).await.unwrap();
+
+        assert_eq!(response.len(), 1);
+        assert_eq!(ack(&response[0]), WriteAck::default());
+    }
+
+    #[tokio::test]
+    async fn test_write_stream_schema_rejected() {
+        let handler = Arc::new(MockHandler::default());
+        let schema = SchemaAsIpc::new(&Schema::empty(), &IpcWriteOptions::default()).into();
+
+        let err = run(Arc::clone(&handler), vec![Ok(schema)])
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Invalid write: no columns");
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        assert!(handler.writes.lock().unwrap().is_empty());
+    }
+}
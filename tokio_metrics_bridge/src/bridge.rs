@@ -43,6 +43,23 @@ pub fn setup_tokio_metrics(
     }
 }
 
+/// Register metric instrumentation for given tokio runtime, replacing any runtime with the same name.
+///
+/// This can be used when a runtime is replaced by a new one (e.g. to change its number of worker threads) and the
+/// metrics should continue to be reported under the same name.
+pub fn replace_tokio_metrics(
+    runtime_metrics: RuntimeMetrics,
+    runtime_name: &'static str,
+    registry: Arc<Registry>,
+) {
+    let dispatcher = registry.register_instrument("tokio", TokioInstrumentDispatcher::default);
+    let instrument = TokioInstrument::new(runtime_metrics, runtime_name);
+    dispatcher
+        .instruments
+        .write()
+        .insert(runtime_name, instrument);
+}
+
 /// Dispatcher from a single [`Instrument`] to the per-runtime [`TokioInstrument`]. This is used so we can use a
 /// predictable instrument name and so that we only need to emit the same metric name/type/description once.
 #[derive(Debug, Clone, Default)]
@@ -309,6 +326,18 @@ mod tests {
         setup_tokio_metrics(rt.metrics(), "foo", registry);
     }
 
+    #[test]
+    fn test_replace() {
+        let registry = Arc::new(Registry::default());
+        let rt1 = Runtime::new().unwrap();
+        let rt2 = Runtime::new().unwrap();
+        setup_tokio_metrics(rt1.metrics(), "foo", Arc::clone(&registry));
+        replace_tokio_metrics(rt2.metrics(), "foo", Arc::clone(&registry));
+
+        // replacing an unknown runtime registers it
+        replace_tokio_metrics(rt2.metrics(), "bar", registry);
+    }
+
     /// Test that runtimes are scoped per [`Registry`] and are NOT inserted into some global static state.
     ///
     /// If there would be a global state, this would panic like [`test_panic_register_twice`].
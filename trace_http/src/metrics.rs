@@ -1,8 +1,10 @@
 use crate::classify::Classification;
+use bytes::Buf;
 use hashbrown::HashMap;
-use http::Method;
+use http::{HeaderMap, Method};
 use metric::{Attributes, DurationHistogram, Metric, ResultMetric, U64Counter};
 use parking_lot::{MappedMutexGuard, Mutex, MutexGuard};
+use std::io::IoSlice;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -64,6 +66,9 @@ impl RequestMetrics {
             path: Some(request.uri().path().to_string()),
             method: Some(request.method().clone()),
             classification: None,
+            response_encoding: None,
+            response_bytes: 0,
+            response_messages: Some(MessageSizes::default()),
         }
     }
 
@@ -107,6 +112,79 @@ impl RequestMetrics {
     }
 }
 
+/// Maps the `grpc-encoding` header to a metric attribute value.
+///
+/// Unknown encodings are grouped together to bound the metric cardinality.
+fn grpc_encoding(headers: &HeaderMap) -> &'static str {
+    match headers.get("grpc-encoding").map(|v| v.as_bytes()) {
+        None | Some(b"identity") => "identity",
+        Some(b"gzip") => "gzip",
+        Some(b"zstd") => "zstd",
+        Some(b"deflate") => "deflate",
+        Some(_) => "other",
+    }
+}
+
+/// Sums up the uncompressed sizes of the gRPC messages of a response body.
+///
+/// Each message is prefixed with a compressed flag and its length. Messages are compressed individually, so the
+/// uncompressed size of a gzip compressed message is given by its gzip trailer (the last 4 bytes).
+#[derive(Debug, Default)]
+struct MessageSizes {
+    /// The prefix of the current message, while it is read.
+    prefix: [u8; 5],
+    prefix_len: usize,
+
+    /// Whether the current message is compressed.
+    compressed: bool,
+
+    /// Bytes of the current message that have not been read yet.
+    remaining: usize,
+
+    /// The last 4 bytes of the current message read so far, in little endian.
+    trailer: u32,
+
+    /// Uncompressed size of all messages read so far.
+    raw_bytes: u64,
+}
+
+impl MessageSizes {
+    const PREFIX_LEN: usize = 5;
+
+    fn push(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.remaining == 0 {
+                let n = (Self::PREFIX_LEN - self.prefix_len).min(data.len());
+                self.prefix[self.prefix_len..self.prefix_len + n].copy_from_slice(&data[..n]);
+                self.prefix_len += n;
+                data = &data[n..];
+
+                if self.prefix_len == Self::PREFIX_LEN {
+                    self.prefix_len = 0;
+                    self.compressed = self.prefix[0] == 1;
+                    self.trailer = 0;
+                    self.remaining =
+                        u32::from_be_bytes(self.prefix[1..].try_into().expect("4 bytes")) as usize;
+                    if !self.compressed {
+                        self.raw_bytes += self.remaining as u64;
+                    }
+                }
+            } else {
+                let n = self.remaining.min(data.len());
+                for b in &data[n.saturating_sub(4)..n] {
+                    self.trailer = (self.trailer >> 8) | (u32::from(*b) << 24);
+                }
+                self.remaining -= n;
+                data = &data[n..];
+
+                if self.remaining == 0 && self.compressed {
+                    self.raw_bytes += u64::from(self.trailer);
+                }
+            }
+        }
+    }
+}
+
 fn truncate_path(path: &str, segments: Option<usize>) -> String {
     let search = || {
         let s = segments?;
@@ -134,6 +212,28 @@ struct Metrics {
 
     /// Latency distribution of non-aborted requests
     request_duration: ResultMetric<DurationHistogram>,
+
+    /// Response body bytes by response encoding, only for [`MetricFamily::GrpcServer`]
+    response_bytes: Option<ResponseBytes>,
+}
+
+/// Response body bytes as sent and before compression, the `encoding` attribute is added per request.
+#[derive(Debug)]
+struct ResponseBytes {
+    sent: Metric<U64Counter>,
+    raw: Metric<U64Counter>,
+    attributes: Attributes,
+}
+
+impl ResponseBytes {
+    fn record(&self, encoding: &'static str, sent: u64, raw: Option<u64>) {
+        let mut attributes = self.attributes.clone();
+        attributes.insert("encoding", encoding);
+        if let Some(raw) = raw {
+            self.raw.recorder(attributes.clone()).inc(raw);
+        }
+        self.sent.recorder(attributes).inc(sent);
+    }
 }
 
 impl Metrics {
@@ -159,6 +259,18 @@ impl Metrics {
         let count = ResultMetric::new(&counter, attributes.clone());
         let duration = ResultMetric::new(&duration, attributes.clone());
 
+        let response_bytes = matches!(family, MetricFamily::GrpcServer).then(|| ResponseBytes {
+            sent: registry.register_metric(
+                "grpc_response_bytes",
+                "accumulated response body bytes sent, by response compression",
+            ),
+            raw: registry.register_metric(
+                "grpc_response_raw_bytes",
+                "accumulated response message bytes before compression, by response compression",
+            ),
+            attributes: attributes.clone(),
+        });
+
         attributes.insert("status", "aborted");
         let aborted_count = counter.recorder(attributes);
 
@@ -166,6 +278,7 @@ impl Metrics {
             request_count: count,
             request_duration: duration,
             aborted_count,
+            response_bytes,
         }
     }
 }
@@ -178,9 +291,40 @@ pub(crate) struct MetricsRecorder {
     path: Option<String>,
     method: Option<Method>,
     classification: Option<Classification>,
+    response_encoding: Option<&'static str>,
+    response_bytes: u64,
+
+    /// Uncompressed response message sizes, `None` if they cannot be determined.
+    response_messages: Option<MessageSizes>,
 }
 
 impl MetricsRecorder {
+    /// Sets the encoding (i.e. compression) of the response body based on the
+    /// `grpc-encoding` header of the response.
+    pub(crate) fn set_response_encoding(&mut self, headers: &HeaderMap) {
+        self.response_encoding = Some(grpc_encoding(headers));
+    }
+
+    /// Adds a chunk of the response body.
+    pub(crate) fn add_response_data(&mut self, data: &impl Buf) {
+        self.response_bytes += data.remaining() as u64;
+
+        let Some(messages) = &mut self.response_messages else {
+            return;
+        };
+        let mut slices = [IoSlice::new(&[]); 16];
+        let n = data.chunks_vectored(&mut slices);
+        let slices = &slices[..n];
+        if slices.iter().map(|s| s.len()).sum::<usize>() != data.remaining() {
+            // too fragmented to read
+            self.response_messages = None;
+            return;
+        }
+        for slice in slices {
+            messages.push(slice);
+        }
+    }
+
     /// Sets the classification of this request if not already set
     pub(crate) fn set_classification(&mut self, classification: Classification) {
         if matches!(classification, Classification::PathNotFound) {
@@ -205,6 +349,18 @@ impl Drop for MetricsRecorder {
             .metrics
             .request_metrics(self.path.take(), self.method.take());
 
+        if let (Some(response_bytes), Some(encoding)) =
+            (&metrics.response_bytes, self.response_encoding)
+        {
+            // only the sizes of uncompressed and gzip compressed messages are known
+            let raw = self
+                .response_messages
+                .as_ref()
+                .filter(|_| matches!(encoding, "identity" | "gzip"))
+                .map(|m| m.raw_bytes);
+            response_bytes.record(encoding, self.response_bytes, raw);
+        }
+
         let duration = self.start_instant.elapsed();
         match self.classification {
             Some(Classification::Ok) => {
@@ -247,4 +403,98 @@ mod tests {
         assert_eq!(truncate_path("/a/b/c/d/e/f/", None), "/a/b/c/d/e/f/");
         assert_eq!(truncate_path("/v1/p/", Some(2)), "/v1/p/");
     }
+
+    #[test]
+    fn test_grpc_encoding() {
+        for (header, expected) in [
+            (None, "identity"),
+            (Some("identity"), "identity"),
+            (Some("gzip"), "gzip"),
+            (Some("zstd"), "zstd"),
+            (Some("snappy"), "other"),
+        ] {
+            let mut headers = HeaderMap::new();
+            if let Some(header) = header {
+                headers.insert("grpc-encoding", header.parse().unwrap());
+            }
+            assert_eq!(grpc_encoding(&headers), expected);
+        }
+    }
+
+    /// A gRPC message with the given compressed flag and payload.
+    fn message(compressed: bool, payload: &[u8]) -> Vec<u8> {
+        let mut message = vec![u8::from(compressed)];
+        message.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        message.extend_from_slice(payload);
+        message
+    }
+
+    /// A payload that looks like a gzip compressed message of `size` bytes.
+    fn gzip_payload(size: u32) -> Vec<u8> {
+        let mut payload = vec![0x1f, 0x8b, 0, 0];
+        payload.extend_from_slice(&size.to_le_bytes());
+        payload
+    }
+
+    #[test]
+    fn test_message_sizes() {
+        let body = [
+            message(false, b"hello"),
+            message(true, &gzip_payload(1000)),
+            message(false, b""),
+            message(true, &gzip_payload(7)),
+        ]
+        .concat();
+
+        for chunk_size in [1, 2, 3, 5, 7, body.len()] {
+            let mut sizes = MessageSizes::default();
+            for chunk in body.chunks(chunk_size) {
+                sizes.push(chunk);
+            }
+            assert_eq!(sizes.raw_bytes, 1012, "{chunk_size}");
+        }
+    }
+
+    #[test]
+    fn test_grpc_response_bytes() {
+        let registry = Arc::new(metric::Registry::new());
+        let metrics = Arc::new(RequestMetrics::new(
+            Arc::clone(&registry),
+            MetricFamily::GrpcServer,
+        ));
+        let request = http::Request::builder()
+            .uri("/arrow.flight.protocol.FlightService/DoGet")
+            .body(())
+            .unwrap();
+
+        for (encoding, body) in [
+            ("gzip", message(true, &gzip_payload(100))),
+            ("gzip", message(true, &gzip_payload(50))),
+            ("identity", message(false, b"hi")),
+        ] {
+            let mut recorder = metrics.recorder(&request);
+            let mut headers = HeaderMap::new();
+            headers.insert("grpc-encoding", encoding.parse().unwrap());
+            recorder.set_response_encoding(&headers);
+            recorder.add_response_data(&bytes::Bytes::from(body));
+            recorder.set_classification(Classification::Ok);
+        }
+
+        let sent = registry
+            .get_instrument::<Metric<U64Counter>>("grpc_response_bytes")
+            .unwrap();
+        let raw = registry
+            .get_instrument::<Metric<U64Counter>>("grpc_response_raw_bytes")
+            .unwrap();
+        for (encoding, expected_sent, expected_raw) in [("gzip", 26, 150), ("identity", 7, 2)] {
+            let attributes = Attributes::from(&[
+                ("path", "/arrow.flight.protocol.FlightService/DoGet"),
+                ("encoding", encoding),
+            ]);
+            let observation = sent.get_observer(&attributes).unwrap().fetch();
+            assert_eq!(observation, expected_sent, "{encoding}");
+            let observation = raw.get_observer(&attributes).unwrap().fetch();
+            assert_eq!(observation, expected_raw, "{encoding}");
+        }
+    }
 }
@@ -229,6 +229,8 @@ where
 
         match result {
             Ok(mut response) => {
+                metrics_recorder.set_response_encoding(response.headers());
+
                 // add trace-id header to the response, if we have one
                 let projected = self.as_mut().project();
                 let request_ctx = projected.request_ctx.take();
@@ -314,6 +316,7 @@ impl<B: http_body::Body> http_body::Body for TracedBody<B> {
         match &result {
             Ok(body) => {
                 let size = body.remaining() as i64;
+                metrics_recorder.add_response_data(body);
                 match projected.inner.is_end_stream() {
                     true => {
                         metrics_recorder.set_classification(Classification::Ok);
@@ -0,0 +1,273 @@
+//! Weighted fair sharing of a queue between classes of waiters.
+use std::{
+    collections::{BTreeMap, VecDeque},
+    num::NonZeroU32,
+    sync::Arc,
+};
+
+/// The virtual time a class with a weight of 1 is charged for each selected waiter.
+///
+/// A class with weight `w` is charged `STRIDE_BASE / w`.
+const STRIDE_BASE: u64 = 1 << 32;
+
+/// The scheduling state of a single class of waiters.
+#[derive(Debug)]
+struct ClassState<W> {
+    /// The virtual time charged for each waiter selected from this class.
+    stride: u64,
+
+    /// The virtual time at which the next waiter of this class is due.
+    pass: u64,
+
+    waiters: VecDeque<W>,
+}
+
+/// A waiter selected by [`FairQueue::pop`].
+#[derive(Debug)]
+pub(crate) struct Grant<W> {
+    pub(crate) class: Arc<str>,
+
+    /// The virtual time before the waiter was selected, see [`FairQueue::refund`].
+    pub(crate) prev_virtual_time: u64,
+
+    pub(crate) waiter: W,
+}
+
+/// Weighted fair (stride) scheduling of waiters of type `W` between classes (such as namespaces).
+///
+/// Each class is charged virtual time inversely proportional to its weight for each selected waiter, and the next
+/// waiter is the first waiter of the class that has been charged the least. A class that starts waiting is charged at
+/// least the current virtual time, so idle classes do not accumulate credit to burst with later. Waiters of the same
+/// class are selected in FIFO order, so a queue with a single class is a plain FIFO queue.
+#[derive(Debug)]
+pub(crate) struct FairQueue<W> {
+    /// The `pass` of the most recently charged class.
+    virtual_time: u64,
+
+    /// Classes that are waiting, or that have been charged beyond the current virtual time.
+    ///
+    /// Ordered so ties are broken deterministically.
+    classes: BTreeMap<Arc<str>, ClassState<W>>,
+}
+
+impl<W> Default for FairQueue<W> {
+    fn default() -> Self {
+        Self {
+            virtual_time: 0,
+            classes: Default::default(),
+        }
+    }
+}
+
+impl<W> FairQueue<W> {
+    /// Returns true if no waiters are queued.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.classes.values().all(|c| c.waiters.is_empty())
+    }
+
+    fn class_mut(&mut self, class: &str, weight: NonZeroU32) -> &mut ClassState<W> {
+        let virtual_time = self.virtual_time;
+        let state = self
+            .classes
+            .entry(Arc::from(class))
+            .or_insert_with(|| ClassState {
+                stride: STRIDE_BASE / u64::from(weight.get()),
+                pass: virtual_time,
+                waiters: VecDeque::new(),
+            });
+
+        // idle classes do not accumulate credit
+        if state.waiters.is_empty() {
+            state.pass = state.pass.max(virtual_time);
+        }
+
+        state
+    }
+
+    /// Queue `waiter` of `class`.
+    pub(crate) fn push(&mut self, class: &str, weight: NonZeroU32, waiter: W) {
+        self.class_mut(class, weight).waiters.push_back(waiter);
+    }
+
+    /// Charge `class` as if a waiter was selected, for a waiter that did not need to queue.
+    pub(crate) fn charge(&mut self, class: &str, weight: NonZeroU32) {
+        let state = self.class_mut(class, weight);
+        let pass = state.pass;
+        state.pass += state.stride;
+        self.virtual_time = pass;
+    }
+
+    /// The waiter that is selected next.
+    pub(crate) fn peek(&self) -> Option<&W> {
+        self.next().and_then(|(_, c)| c.waiters.front())
+    }
+
+    fn next(&self) -> Option<(&Arc<str>, &ClassState<W>)> {
+        self.classes
+            .iter()
+            .filter(|(_, c)| !c.waiters.is_empty())
+            .min_by_key(|(_, c)| c.pass)
+    }
+
+    /// Select the next waiter, charging its class for it.
+    ///
+    /// If the waiter can no longer be served, the charge should be reverted with [`Self::refund`] before calling this
+    /// method again.
+    pub(crate) fn pop(&mut self) -> Option<Grant<W>> {
+        let grant = self
+            .next()
+            .map(|(class, _)| Arc::clone(class))
+            .map(|class| {
+                let state = self.classes.get_mut(&class).expect("class was just found");
+                let waiter = state.waiters.pop_front().expect("class has waiters");
+                let prev_virtual_time = self.virtual_time;
+                self.virtual_time = state.pass;
+                state.pass += state.stride;
+
+                Grant {
+                    class,
+                    prev_virtual_time,
+                    waiter,
+                }
+            });
+
+        // forget idle classes that are owed no credit
+        let virtual_time = self.virtual_time;
+        self.classes
+            .retain(|_, c| !c.waiters.is_empty() || c.pass > virtual_time);
+
+        grant
+    }
+
+    /// Revert the charge of a [`Grant`] of a waiter that could not be served.
+    pub(crate) fn refund(&mut self, class: &str, prev_virtual_time: u64) {
+        if let Some(state) = self.classes.get_mut(class) {
+            state.pass -= state.stride;
+        }
+        self.virtual_time = prev_virtual_time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn weight(w: u32) -> NonZeroU32 {
+        NonZeroU32::new(w).unwrap()
+    }
+
+    /// Queue the `waiters` (class, id) pairs and return the order in which they are selected.
+    fn pop_order(
+        weights: &[(&str, u32)],
+        waiters: &[(&'static str, usize)],
+    ) -> Vec<(&'static str, usize)> {
+        let weights = weights.iter().copied().collect::<HashMap<_, _>>();
+        let mut queue = FairQueue::default();
+        for &(class, id) in waiters {
+            let w = weight(weights.get(class).copied().unwrap_or(1));
+            queue.push(class, w, (class, id));
+        }
+
+        let got = std::iter::from_fn(|| queue.pop().map(|g| g.waiter)).collect();
+        assert!(queue.is_empty());
+        assert!(queue.peek().is_none());
+        got
+    }
+
+    #[test]
+    fn test_equal_weights() {
+        let got = pop_order(
+            &[],
+            &[("a", 0), ("a", 1), ("a", 2), ("a", 3), ("b", 0), ("b", 1)],
+        );
+        assert_eq!(
+            got,
+            [("a", 0), ("b", 0), ("a", 1), ("b", 1), ("a", 2), ("a", 3)]
+        );
+    }
+
+    #[test]
+    fn test_weighted() {
+        let waiters = (0..6)
+            .map(|i| ("a", i))
+            .chain((0..3).map(|i| ("b", i)))
+            .collect::<Vec<_>>();
+        let got = pop_order(&[("a", 2)], &waiters);
+        assert_eq!(
+            got,
+            [
+                ("a", 0),
+                ("b", 0),
+                ("a", 1),
+                ("a", 2),
+                ("b", 1),
+                ("a", 3),
+                ("a", 4),
+                ("b", 2),
+                ("a", 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_burst_does_not_starve_others() {
+        // a burst of the noisy class, followed by two other classes
+        let waiters = (0..6)
+            .map(|i| ("noisy", i))
+            .chain([("a", 0), ("a", 1), ("b", 0)])
+            .collect::<Vec<_>>();
+        let got = pop_order(&[("a", 2)], &waiters);
+        assert_eq!(
+            got,
+            [
+                ("a", 0),
+                ("b", 0),
+                ("noisy", 0),
+                ("a", 1),
+                ("noisy", 1),
+                ("noisy", 2),
+                ("noisy", 3),
+                ("noisy", 4),
+                ("noisy", 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_credit_for_idle_classes() {
+        let mut queue = FairQueue::default();
+
+        // class "a" is served many times while "b" is idle
+        for _ in 0..10 {
+            queue.charge("a", weight(1));
+        }
+        for i in 0..3 {
+            queue.push("a", weight(1), ("a", i));
+        }
+        assert_eq!(queue.pop().unwrap().waiter, ("a", 0));
+
+        // "b" does not get to catch up on the waiters it did not have
+        queue.push("b", weight(1), ("b", 0));
+        queue.push("b", weight(1), ("b", 1));
+
+        let got = std::iter::from_fn(|| queue.pop().map(|g| g.waiter)).collect::<Vec<_>>();
+        assert_eq!(got, [("b", 0), ("a", 1), ("b", 1), ("a", 2)]);
+    }
+
+    #[test]
+    fn test_refund() {
+        let mut queue = FairQueue::default();
+        queue.push("a", weight(1), ("a", 0));
+        queue.push("a", weight(1), ("a", 1));
+        queue.push("b", weight(1), ("b", 0));
+
+        // "a" is not charged for a waiter that could not be served
+        let grant = queue.pop().unwrap();
+        assert_eq!(grant.waiter, ("a", 0));
+        queue.refund(&grant.class, grant.prev_virtual_time);
+
+        assert_eq!(queue.peek(), Some(&("a", 1)));
+    }
+}
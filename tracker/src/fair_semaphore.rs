@@ -0,0 +1,439 @@
+//! A semaphore sharing its permits fairly between classes of waiters.
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    num::NonZeroU32,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use metric::{Attributes, DurationHistogram, Metric, U64Counter, U64Gauge};
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+use trace::span::{Span, SpanRecorder};
+
+use crate::fair_queue::{FairQueue, Grant};
+
+/// Metrics of a [`FairSemaphore`], faceted by waiter class.
+#[derive(Debug)]
+struct FairSemaphoreMetrics {
+    attributes: Attributes,
+    permits_total: U64Gauge,
+    holders_acquired: Metric<U64Gauge>,
+    holders_pending: Metric<U64Gauge>,
+    holders_cancelled_while_pending: Metric<U64Counter>,
+    acquire_duration: Metric<DurationHistogram>,
+}
+
+impl FairSemaphoreMetrics {
+    fn new(registry: &metric::Registry, attributes: Attributes) -> Self {
+        let permits_total = registry
+            .register_metric::<U64Gauge>(
+                "iox_fair_semaphore_permits_total",
+                "Number of total permits",
+            )
+            .recorder(attributes.clone());
+        let holders_acquired = registry.register_metric(
+            "iox_fair_semaphore_holders_acquired",
+            "Number of currently acquired permits, by waiter class",
+        );
+        let holders_pending = registry.register_metric(
+            "iox_fair_semaphore_holders_pending",
+            "Number of waiters pending a permit, by waiter class",
+        );
+        let holders_cancelled_while_pending = registry.register_metric(
+            "iox_fair_semaphore_holders_cancelled_while_pending",
+            "Number of waiters that were cancelled while pending a permit, by waiter class",
+        );
+        let acquire_duration = registry.register_metric(
+            "iox_fair_semaphore_acquire_duration",
+            "Duration it takes to acquire a permit, by waiter class",
+        );
+
+        Self {
+            attributes,
+            permits_total,
+            holders_acquired,
+            holders_pending,
+            holders_cancelled_while_pending,
+            acquire_duration,
+        }
+    }
+
+    fn class_attributes(&self, class: &str) -> Attributes {
+        let mut attributes = self.attributes.clone();
+        attributes.insert("class", Cow::Owned(class.to_string()));
+        attributes
+    }
+}
+
+/// Weighted fair scheduling of the permits of a [`FairSemaphore`] between
+/// classes of waiters of type `W`, see [`FairQueue`].
+#[derive(Debug)]
+struct Scheduler<W> {
+    available: usize,
+    queue: FairQueue<W>,
+}
+
+impl<W> Scheduler<W> {
+    fn new(permits: usize) -> Self {
+        Self {
+            available: permits,
+            queue: FairQueue::default(),
+        }
+    }
+
+    /// Take a permit for `class` if one is free and no other waiter is
+    /// pending, charging `class` for it, or otherwise enqueue `waiter`.
+    ///
+    /// Returns the waiter back if a permit was taken.
+    fn acquire_or_enqueue(&mut self, class: &str, weight: NonZeroU32, waiter: W) -> Option<W> {
+        if self.available > 0 && self.queue.is_empty() {
+            self.available -= 1;
+            self.queue.charge(class, weight);
+            return Some(waiter);
+        }
+
+        self.queue.push(class, weight, waiter);
+        None
+    }
+
+    /// Return a permit, returning the waiter it should be granted to (if
+    /// any) and charging its class for it.
+    ///
+    /// If the waiter can no longer accept the permit, the charge must be
+    /// reverted with [`FairQueue::refund()`] before calling this method again.
+    fn release(&mut self) -> Option<Grant<W>> {
+        let grant = self.queue.pop();
+        if grant.is_none() {
+            self.available += 1;
+        }
+        grant
+    }
+}
+
+/// A semaphore that shares its permits between classes of waiters (such as
+/// namespaces or workload classes) in proportion to their weights, instead of
+/// in FIFO order.
+///
+/// Waiters of the same class are granted permits in FIFO order. When a
+/// permit is released it is granted to the class that has received the
+/// fewest permits relative to its weight, so a burst of waiters of one class
+/// does not starve the waiters of other classes.
+#[derive(Debug)]
+pub struct FairSemaphore {
+    scheduler: Mutex<Scheduler<oneshot::Sender<FairSemaphorePermit>>>,
+    permits: usize,
+
+    /// Configured class weights. Classes that are not configured have a
+    /// weight of 1.
+    weights: HashMap<String, NonZeroU32>,
+
+    metrics: FairSemaphoreMetrics,
+}
+
+impl FairSemaphore {
+    /// Create a new [`FairSemaphore`] with `permits` permits, shared between
+    /// classes according to `weights`.
+    ///
+    /// Metrics are registered in `registry` with the given `attributes`.
+    pub fn new(
+        registry: &metric::Registry,
+        attributes: impl Into<Attributes>,
+        permits: usize,
+        weights: impl IntoIterator<Item = (String, NonZeroU32)>,
+    ) -> Self {
+        let metrics = FairSemaphoreMetrics::new(registry, attributes.into());
+        metrics.permits_total.inc(permits as u64);
+
+        Self {
+            scheduler: Mutex::new(Scheduler::new(permits)),
+            permits,
+            weights: weights.into_iter().collect(),
+            metrics,
+        }
+    }
+
+    /// Return the total number of permits (available + already acquired).
+    pub fn total_permits(&self) -> usize {
+        self.permits
+    }
+
+    /// Acquire a single permit on behalf of `class`.
+    pub async fn acquire_owned(
+        self: &Arc<Self>,
+        class: &str,
+        span: Option<Span>,
+    ) -> FairSemaphorePermit {
+        let t_start = Instant::now();
+        let attributes = self.metrics.class_attributes(class);
+        let weight = self.weights.get(class).copied().unwrap_or(NonZeroU32::MIN);
+
+        let (tx, rx) = oneshot::channel();
+        let enqueued = self
+            .scheduler
+            .lock()
+            .acquire_or_enqueue(class, weight, tx)
+            .is_none();
+
+        let mut permit = if enqueued {
+            let pending = PendingGuard::new(&self.metrics, attributes.clone());
+            let permit = rx
+                .await
+                .expect("waiters are granted a permit before being removed");
+            pending.completed();
+            permit
+        } else {
+            FairSemaphorePermit {
+                semaphore: Some(Arc::clone(self)),
+                acquired: Default::default(),
+                acquire_duration: Duration::ZERO,
+                span_recorder: SpanRecorder::new(None),
+            }
+        };
+
+        let acquire_duration = t_start.elapsed();
+        self.metrics
+            .acquire_duration
+            .recorder(attributes.clone())
+            .record(acquire_duration);
+
+        let acquired = self.metrics.holders_acquired.recorder(attributes);
+        acquired.inc(1);
+
+        let mut span_recorder = SpanRecorder::new(span);
+        span_recorder.ok("acquired");
+
+        permit.acquired = acquired;
+        permit.acquire_duration = acquire_duration;
+        permit.span_recorder = span_recorder;
+        permit
+    }
+
+    /// Return a permit, granting it to the next waiter if any.
+    fn release(self: &Arc<Self>) {
+        let mut scheduler = self.scheduler.lock();
+        while let Some(grant) = scheduler.release() {
+            let permit = FairSemaphorePermit {
+                semaphore: Some(Arc::clone(self)),
+                acquired: Default::default(),
+                acquire_duration: Duration::ZERO,
+                span_recorder: SpanRecorder::new(None),
+            };
+
+            match grant.waiter.send(permit) {
+                Ok(()) => return,
+                Err(mut permit) => {
+                    // The waiter was cancelled - disarm the permit (so that
+                    // dropping it doesn't re-enter this method) and grant it
+                    // to the next waiter instead.
+                    permit.semaphore = None;
+                    scheduler
+                        .queue
+                        .refund(&grant.class, grant.prev_virtual_time);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for FairSemaphore {
+    fn drop(&mut self) {
+        self.metrics.permits_total.dec(self.permits as u64);
+    }
+}
+
+/// Tracks a pending waiter in the metrics of a [`FairSemaphore`].
+#[derive(Debug)]
+struct PendingGuard<'a> {
+    metrics: &'a FairSemaphoreMetrics,
+    attributes: Attributes,
+    completed: bool,
+}
+
+impl<'a> PendingGuard<'a> {
+    fn new(metrics: &'a FairSemaphoreMetrics, attributes: Attributes) -> Self {
+        metrics.holders_pending.recorder(attributes.clone()).inc(1);
+        Self {
+            metrics,
+            attributes,
+            completed: false,
+        }
+    }
+
+    fn completed(mut self) {
+        self.completed = true;
+    }
+}
+
+impl<'a> Drop for PendingGuard<'a> {
+    fn drop(&mut self) {
+        self.metrics
+            .holders_pending
+            .recorder(self.attributes.clone())
+            .dec(1);
+        if !self.completed {
+            self.metrics
+                .holders_cancelled_while_pending
+                .recorder(self.attributes.clone())
+                .inc(1);
+        }
+    }
+}
+
+/// A permit acquired from a [`FairSemaphore`], returned to the semaphore when
+/// dropped.
+#[derive(Debug)]
+pub struct FairSemaphorePermit {
+    /// The semaphore the permit is returned to, or [`None`] for a permit that
+    /// was never handed out.
+    semaphore: Option<Arc<FairSemaphore>>,
+
+    /// The acquired-holders gauge of the class of the permit holder.
+    acquired: U64Gauge,
+
+    /// The time it took to acquire this permit.
+    acquire_duration: Duration,
+
+    /// Span recorder for the entire semaphore interaction.
+    ///
+    /// No direct interaction, will be exported during drop (aka the end of the span will be set).
+    #[allow(dead_code)]
+    span_recorder: SpanRecorder,
+}
+
+impl FairSemaphorePermit {
+    /// The time it took to acquire this permit.
+    pub fn acquire_duration(&self) -> Duration {
+        self.acquire_duration
+    }
+}
+
+impl Drop for FairSemaphorePermit {
+    fn drop(&mut self) {
+        if let Some(semaphore) = self.semaphore.take() {
+            self.acquired.dec(1);
+            semaphore.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metric::Registry;
+    use test_helpers::timeout::FutureTimeout;
+
+    use super::*;
+
+    fn weight(w: u32) -> NonZeroU32 {
+        NonZeroU32::new(w).unwrap()
+    }
+
+    #[test]
+    fn test_scheduler_no_credit_for_idle_classes() {
+        let mut scheduler = Scheduler::new(1);
+
+        // Class "a" is granted many permits while "b" is idle.
+        for i in 0..10 {
+            assert!(scheduler.acquire_or_enqueue("a", weight(1), i).is_some());
+            assert!(scheduler.release().is_none());
+        }
+
+        assert!(scheduler.acquire_or_enqueue("a", weight(1), 10).is_some());
+        for i in 11..14 {
+            assert!(scheduler.acquire_or_enqueue("a", weight(1), i).is_none());
+        }
+        assert!(scheduler.acquire_or_enqueue("b", weight(1), 100).is_none());
+
+        // "b" does not get to catch up on the permits granted to "a" while
+        // it was idle.
+        let got = std::iter::from_fn(|| scheduler.release().map(|g| g.waiter))
+            .take(4)
+            .collect::<Vec<_>>();
+        assert_eq!(got, [100, 11, 12, 13]);
+    }
+
+    #[tokio::test]
+    async fn test_fair_semaphore() {
+        let registry = Registry::new();
+        let semaphore = Arc::new(FairSemaphore::new(
+            &registry,
+            &[("semaphore", "test")],
+            1,
+            [("a".to_string(), weight(1))],
+        ));
+        assert_eq!(semaphore.total_permits(), 1);
+
+        let permit = semaphore
+            .acquire_owned("a", None)
+            .with_timeout_panic(Duration::from_secs(5))
+            .await;
+
+        let s = Arc::clone(&semaphore);
+        let waiter_a = tokio::spawn(async move { s.acquire_owned("a", None).await });
+        let s = Arc::clone(&semaphore);
+        let cancelled = tokio::spawn(async move { s.acquire_owned("b", None).await });
+        let s = Arc::clone(&semaphore);
+        let waiter_b = tokio::spawn(async move { s.acquire_owned("b", None).await });
+
+        // Wait for all waiters to be enqueued.
+        async {
+            while pending(&registry, "a") + pending(&registry, "b") < 3 {
+                tokio::task::yield_now().await;
+            }
+        }
+        .with_timeout_panic(Duration::from_secs(5))
+        .await;
+
+        cancelled.abort();
+        assert!(cancelled.await.unwrap_err().is_cancelled());
+        assert_eq!(pending(&registry, "b"), 1);
+
+        // "b" is granted the permit first, skipping the cancelled waiter.
+        drop(permit);
+        let permit = waiter_b
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(pending(&registry, "b"), 0);
+        assert!(!waiter_a.is_finished());
+
+        drop(permit);
+        let permit = waiter_a
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(pending(&registry, "a"), 0);
+        assert_eq!(acquired(&registry, "a"), 1);
+        assert_eq!(acquired(&registry, "b"), 0);
+
+        drop(permit);
+        assert_eq!(acquired(&registry, "a"), 0);
+
+        // The permit is available again.
+        semaphore
+            .acquire_owned("c", None)
+            .with_timeout_panic(Duration::from_secs(5))
+            .await;
+    }
+
+    fn gauge(registry: &Registry, name: &'static str, class: &'static str) -> u64 {
+        registry
+            .get_instrument::<Metric<U64Gauge>>(name)
+            .unwrap()
+            .get_observer(&Attributes::from(&[
+                ("semaphore", "test"),
+                ("class", class),
+            ]))
+            .map(|o| o.fetch())
+            .unwrap_or_default()
+    }
+
+    fn pending(registry: &Registry, class: &'static str) -> u64 {
+        gauge(registry, "iox_fair_semaphore_holders_pending", class)
+    }
+
+    fn acquired(registry: &Registry, class: &'static str) -> u64 {
+        gauge(registry, "iox_fair_semaphore_holders_acquired", class)
+    }
+}
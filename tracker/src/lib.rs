@@ -16,10 +16,15 @@ use workspace_hack as _;
 
 mod async_semaphore;
 mod disk_metric;
+mod fair_queue;
+mod fair_semaphore;
 mod lock;
+mod priority_queue;
 mod task;
 
 pub use async_semaphore::*;
 pub use disk_metric::*;
+pub use fair_semaphore::{FairSemaphore, FairSemaphorePermit};
 pub use lock::*;
+pub use priority_queue::{PriorityAdmissionQueue, UnknownWorkloadClass, WorkloadClass};
 pub use task::*;
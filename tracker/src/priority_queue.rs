@@ -0,0 +1,778 @@
+//! Priority lanes in front of a semaphore.
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::Display,
+    num::NonZeroU32,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use metric::{Attributes, DurationHistogram, Metric, U64Counter, U64Gauge};
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+use trace::span::Span;
+
+use crate::{
+    fair_queue::FairQueue, AcquireError, InstrumentedAsyncOwnedSemaphorePermit,
+    InstrumentedAsyncSemaphore,
+};
+
+/// Workload class of a query, used to prioritize its admission.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WorkloadClass {
+    /// Latency-sensitive queries, e.g. dashboards or admin/system queries.
+    Interactive,
+
+    /// Regular queries.
+    #[default]
+    Normal,
+
+    /// Throughput-oriented queries, e.g. exports or long scans.
+    Batch,
+}
+
+impl WorkloadClass {
+    /// All classes, from highest to lowest priority.
+    pub const ALL: [Self; 3] = [Self::Interactive, Self::Normal, Self::Batch];
+
+    /// String representation.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Interactive => "interactive",
+            Self::Normal => "normal",
+            Self::Batch => "batch",
+        }
+    }
+
+    /// Index of the lane, lower lanes are served first.
+    fn lane(self) -> usize {
+        self as usize
+    }
+}
+
+impl Display for WorkloadClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for WorkloadClass {
+    type Err = UnknownWorkloadClass;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|class| class.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| UnknownWorkloadClass(s.to_owned()))
+    }
+}
+
+/// Error returned when parsing an unknown [`WorkloadClass`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownWorkloadClass(String);
+
+impl Display for UnknownWorkloadClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown workload class '{}', expected one of: interactive, normal, batch",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for UnknownWorkloadClass {}
+
+/// Metrics of a [`PriorityAdmissionQueue`], faceted by workload class.
+#[derive(Debug)]
+struct PriorityAdmissionQueueMetrics {
+    attributes: Attributes,
+    holders_pending: Metric<U64Gauge>,
+    holders_promoted: Metric<U64Counter>,
+    wait_duration: Metric<DurationHistogram>,
+}
+
+impl PriorityAdmissionQueueMetrics {
+    fn new(registry: &metric::Registry, attributes: Attributes) -> Self {
+        let holders_pending = registry.register_metric(
+            "iox_priority_queue_holders_pending",
+            "Number of waiters queued in front of the semaphore, by workload class",
+        );
+        let holders_promoted = registry.register_metric(
+            "iox_priority_queue_holders_promoted",
+            "Number of waiters that were admitted ahead of higher workload classes because they waited too long, by workload class",
+        );
+        let wait_duration = registry.register_metric(
+            "iox_priority_queue_wait_duration",
+            "Duration a waiter is queued in front of the semaphore, by workload class",
+        );
+
+        Self {
+            attributes,
+            holders_pending,
+            holders_promoted,
+            wait_duration,
+        }
+    }
+
+    fn class_attributes(&self, class: WorkloadClass) -> Attributes {
+        let mut attributes = self.attributes.clone();
+        attributes.insert("class", Cow::Borrowed(class.as_str()));
+        attributes
+    }
+}
+
+/// A waiter selected by [`Lanes::pop`].
+#[derive(Debug)]
+struct Next<W> {
+    class: WorkloadClass,
+
+    /// The waiter was selected ahead of waiters of a higher workload class because it waited too long.
+    promoted: bool,
+
+    waiter: W,
+}
+
+/// Lanes of waiters, one per [`WorkloadClass`].
+///
+/// Each lane is shared between namespaces in proportion to their weights, see [`FairQueue`]. Waiters of the same
+/// namespace are selected in FIFO order, so a lane with a single namespace is a plain FIFO queue.
+#[derive(Debug)]
+struct Lanes<W> {
+    lanes: [FairQueue<(Instant, W)>; WorkloadClass::ALL.len()],
+}
+
+impl<W> Lanes<W> {
+    fn new() -> Self {
+        Self {
+            lanes: Default::default(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lanes.iter().all(|lane| lane.is_empty())
+    }
+
+    fn push(
+        &mut self,
+        class: WorkloadClass,
+        namespace: &str,
+        weight: NonZeroU32,
+        enqueued: Instant,
+        waiter: W,
+    ) {
+        self.lanes[class.lane()].push(namespace, weight, (enqueued, waiter));
+    }
+
+    /// Select the next waiter.
+    ///
+    /// This is the waiter that has been waiting the longest among those that waited at least `max_wait`, or otherwise
+    /// the first waiter of the highest-priority class.
+    fn pop(&mut self, now: Instant, max_wait: Duration) -> Option<Next<W>> {
+        let highest = self.lanes.iter().position(|lane| !lane.is_empty())?;
+
+        let starved = self
+            .lanes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, lane)| lane.peek().map(|(enqueued, _)| (idx, *enqueued)))
+            .filter(|(_, enqueued)| now.saturating_duration_since(*enqueued) >= max_wait)
+            .min_by_key(|(_, enqueued)| *enqueued)
+            .map(|(idx, _)| idx);
+
+        let idx = starved.unwrap_or(highest);
+        let (_enqueued, waiter) = self.lanes[idx].pop().expect("lane is not empty").waiter;
+
+        Some(Next {
+            class: WorkloadClass::ALL[idx],
+            promoted: idx != highest,
+            waiter,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct QueueState {
+    /// A waiter is currently allowed to wait for the semaphore.
+    head_taken: bool,
+
+    lanes: Lanes<oneshot::Sender<Head>>,
+}
+
+/// Priority-aware queue in front of an [`InstrumentedAsyncSemaphore`].
+///
+/// Only a single waiter -- the head of the queue -- waits for the semaphore at a time. All other waiters queue in
+/// one lane per [`WorkloadClass`]. Once the head acquires a permit, the next head is selected from the
+/// highest-priority non-empty lane, so [interactive](WorkloadClass::Interactive) queries are not stuck behind long
+/// [batch](WorkloadClass::Batch) queries.
+///
+/// By default, waiters of the same lane are admitted in FIFO order. If [namespace
+/// weights](Self::with_namespace_weights) are set, the waiters of a lane are instead admitted in proportion to the
+/// weights of their namespaces, so that a burst of queries of a single namespace does not starve the queries of all
+/// other namespaces.
+///
+/// To prevent starvation, a waiter that has been queued for at least `max_wait` is selected ahead of higher-priority
+/// waiters. Among multiple such waiters, the one that has been queued the longest is selected first.
+#[derive(Debug)]
+pub struct PriorityAdmissionQueue {
+    semaphore: Arc<InstrumentedAsyncSemaphore>,
+    max_wait: Duration,
+    state: Mutex<QueueState>,
+    metrics: PriorityAdmissionQueueMetrics,
+
+    /// Weights of namespaces, or [`None`] if lanes are not shared between namespaces.
+    namespace_weights: Option<HashMap<String, NonZeroU32>>,
+}
+
+impl PriorityAdmissionQueue {
+    /// Create a new queue in front of `semaphore`.
+    ///
+    /// Metrics are registered in `registry` with the given `attributes`.
+    pub fn new(
+        registry: &metric::Registry,
+        attributes: impl Into<Attributes>,
+        semaphore: Arc<InstrumentedAsyncSemaphore>,
+        max_wait: Duration,
+    ) -> Self {
+        Self {
+            semaphore,
+            max_wait,
+            state: Mutex::new(QueueState {
+                head_taken: false,
+                lanes: Lanes::new(),
+            }),
+            metrics: PriorityAdmissionQueueMetrics::new(registry, attributes.into()),
+            namespace_weights: None,
+        }
+    }
+
+    /// Share the lanes between namespaces according to `weights`.
+    ///
+    /// Namespaces that are not part of `weights` have a weight of 1.
+    pub fn with_namespace_weights(
+        mut self,
+        weights: impl IntoIterator<Item = (String, NonZeroU32)>,
+    ) -> Self {
+        self.namespace_weights = Some(weights.into_iter().collect());
+        self
+    }
+
+    /// The semaphore behind this queue.
+    pub fn semaphore(&self) -> &Arc<InstrumentedAsyncSemaphore> {
+        &self.semaphore
+    }
+
+    /// Acquire a single permit of the underlying semaphore on behalf of a query of the given `class`.
+    pub async fn acquire_owned(
+        self: &Arc<Self>,
+        class: WorkloadClass,
+        span: Option<Span>,
+    ) -> Result<InstrumentedAsyncOwnedSemaphorePermit, AcquireError> {
+        self.acquire_owned_for_namespace(class, "", span).await
+    }
+
+    /// Acquire a single permit of the underlying semaphore on behalf of a query of the given `class` and
+    /// `namespace`.
+    ///
+    /// The namespace is ignored unless [namespace weights](Self::with_namespace_weights) are set.
+    pub async fn acquire_owned_for_namespace(
+        self: &Arc<Self>,
+        class: WorkloadClass,
+        namespace: &str,
+        span: Option<Span>,
+    ) -> Result<InstrumentedAsyncOwnedSemaphorePermit, AcquireError> {
+        let t_start = Instant::now();
+        let attributes = self.metrics.class_attributes(class);
+        let (namespace, weight) = match &self.namespace_weights {
+            Some(weights) => (
+                namespace,
+                weights.get(namespace).copied().unwrap_or(NonZeroU32::MIN),
+            ),
+            None => ("", NonZeroU32::MIN),
+        };
+
+        let rx = {
+            let mut state = self.state.lock();
+            if !state.head_taken && state.lanes.is_empty() {
+                state.head_taken = true;
+                None
+            } else {
+                let (tx, rx) = oneshot::channel();
+                state.lanes.push(class, namespace, weight, t_start, tx);
+                Some(rx)
+            }
+        };
+
+        let head = match rx {
+            None => Head {
+                queue: Some(Arc::clone(self)),
+            },
+            Some(rx) => {
+                let _pending =
+                    PendingGuard::new(self.metrics.holders_pending.recorder(attributes.clone()));
+                rx.await.expect("waiters are selected before being removed")
+            }
+        };
+
+        self.metrics
+            .wait_duration
+            .recorder(attributes)
+            .record(t_start.elapsed());
+
+        let permit = Arc::clone(&self.semaphore).acquire_owned(span).await;
+
+        // let the next waiter queue for the semaphore
+        drop(head);
+
+        permit
+    }
+
+    /// Pass the head of the queue to the next waiter, if any.
+    fn pass_head(self: &Arc<Self>) {
+        let mut state = self.state.lock();
+        while let Some(next) = state.lanes.pop(Instant::now(), self.max_wait) {
+            let head = Head {
+                queue: Some(Arc::clone(self)),
+            };
+
+            match next.waiter.send(head) {
+                Ok(()) => {
+                    if next.promoted {
+                        self.metrics
+                            .holders_promoted
+                            .recorder(self.metrics.class_attributes(next.class))
+                            .inc(1);
+                    }
+                    return;
+                }
+                Err(mut head) => {
+                    // The waiter was cancelled - disarm the head (so that dropping it doesn't re-enter this method)
+                    // and pass it to the next waiter instead.
+                    head.queue = None;
+                }
+            }
+        }
+
+        state.head_taken = false;
+    }
+}
+
+/// Tracks a queued waiter in the pending gauge, also if the waiter is cancelled.
+#[derive(Debug)]
+struct PendingGuard(U64Gauge);
+
+impl PendingGuard {
+    fn new(gauge: U64Gauge) -> Self {
+        gauge.inc(1);
+        Self(gauge)
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.dec(1);
+    }
+}
+
+/// Marks the waiter that is allowed to wait for the semaphore, passed on to the next waiter when dropped.
+#[derive(Debug)]
+struct Head {
+    /// The queue, or [`None`] for a head that was never handed out.
+    queue: Option<Arc<PriorityAdmissionQueue>>,
+}
+
+impl Drop for Head {
+    fn drop(&mut self) {
+        if let Some(queue) = self.queue.take() {
+            queue.pass_head();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metric::Registry;
+    use test_helpers::timeout::FutureTimeout;
+
+    use crate::AsyncSemaphoreMetrics;
+
+    use super::*;
+
+    #[test]
+    fn test_workload_class_parse() {
+        for class in WorkloadClass::ALL {
+            assert_eq!(class.as_str().parse::<WorkloadClass>().unwrap(), class);
+        }
+        assert_eq!(
+            "Batch".parse::<WorkloadClass>().unwrap(),
+            WorkloadClass::Batch
+        );
+        assert_eq!(
+            "foo".parse::<WorkloadClass>().unwrap_err().to_string(),
+            "unknown workload class 'foo', expected one of: interactive, normal, batch",
+        );
+    }
+
+    #[test]
+    fn test_lanes_priority() {
+        let t0 = Instant::now();
+        let mut lanes = Lanes::new();
+        lanes.push(WorkloadClass::Batch, "ns", NonZeroU32::MIN, t0, 0);
+        lanes.push(WorkloadClass::Normal, "ns", NonZeroU32::MIN, t0, 1);
+        lanes.push(WorkloadClass::Interactive, "ns", NonZeroU32::MIN, t0, 2);
+        lanes.push(WorkloadClass::Batch, "ns", NonZeroU32::MIN, t0, 3);
+        lanes.push(WorkloadClass::Interactive, "ns", NonZeroU32::MIN, t0, 4);
+
+        let got = std::iter::from_fn(|| lanes.pop(t0, Duration::from_secs(10)))
+            .map(|next| {
+                assert!(!next.promoted);
+                (next.class, next.waiter)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            got,
+            [
+                (WorkloadClass::Interactive, 2),
+                (WorkloadClass::Interactive, 4),
+                (WorkloadClass::Normal, 1),
+                (WorkloadClass::Batch, 0),
+                (WorkloadClass::Batch, 3),
+            ]
+        );
+        assert!(lanes.is_empty());
+    }
+
+    #[test]
+    fn test_lanes_starvation() {
+        let t0 = Instant::now();
+        let max_wait = Duration::from_secs(10);
+        let mut lanes = Lanes::new();
+        lanes.push(WorkloadClass::Batch, "ns", NonZeroU32::MIN, t0, 0);
+        lanes.push(
+            WorkloadClass::Normal,
+            "ns",
+            NonZeroU32::MIN,
+            t0 + Duration::from_secs(1),
+            1,
+        );
+        lanes.push(
+            WorkloadClass::Interactive,
+            "ns",
+            NonZeroU32::MIN,
+            t0 + Duration::from_secs(2),
+            2,
+        );
+        lanes.push(
+            WorkloadClass::Interactive,
+            "ns",
+            NonZeroU32::MIN,
+            t0 + Duration::from_secs(3),
+            3,
+        );
+
+        // nobody is starved yet
+        let next = lanes.pop(t0 + Duration::from_secs(5), max_wait).unwrap();
+        assert_eq!(
+            (next.class, next.promoted, next.waiter),
+            (WorkloadClass::Interactive, false, 2)
+        );
+
+        // batch and normal waiters are starved, the one that waited longer goes first
+        let now = t0 + Duration::from_secs(11);
+        let next = lanes.pop(now, max_wait).unwrap();
+        assert_eq!(
+            (next.class, next.promoted, next.waiter),
+            (WorkloadClass::Batch, true, 0)
+        );
+        let next = lanes.pop(now, max_wait).unwrap();
+        assert_eq!(
+            (next.class, next.promoted, next.waiter),
+            (WorkloadClass::Normal, true, 1)
+        );
+        let next = lanes.pop(now, max_wait).unwrap();
+        assert_eq!(
+            (next.class, next.promoted, next.waiter),
+            (WorkloadClass::Interactive, false, 3)
+        );
+        assert!(lanes.pop(now, max_wait).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_priority_admission_queue() {
+        let registry = Registry::new();
+        let semaphore_metrics = Arc::new(AsyncSemaphoreMetrics::new(
+            &registry,
+            &[("semaphore", "test")],
+        ));
+        let queue = Arc::new(PriorityAdmissionQueue::new(
+            &registry,
+            &[("semaphore", "test")],
+            Arc::new(semaphore_metrics.new_semaphore(1)),
+            Duration::from_secs(3600),
+        ));
+
+        let permit = queue
+            .acquire_owned(WorkloadClass::Batch, None)
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        // the head of the queue waits for the semaphore
+        let q = Arc::clone(&queue);
+        let head = tokio::spawn(async move { q.acquire_owned(WorkloadClass::Batch, None).await });
+        async {
+            while queue.semaphore().holders_pending() < 1 {
+                tokio::task::yield_now().await;
+            }
+        }
+        .with_timeout_panic(Duration::from_secs(5))
+        .await;
+
+        let q = Arc::clone(&queue);
+        let waiter_batch =
+            tokio::spawn(async move { q.acquire_owned(WorkloadClass::Batch, None).await });
+        let q = Arc::clone(&queue);
+        let cancelled =
+            tokio::spawn(async move { q.acquire_owned(WorkloadClass::Interactive, None).await });
+        let q = Arc::clone(&queue);
+        let waiter_interactive =
+            tokio::spawn(async move { q.acquire_owned(WorkloadClass::Interactive, None).await });
+
+        // Wait for all waiters to be enqueued.
+        async {
+            while pending(&registry, "batch") + pending(&registry, "interactive") < 3 {
+                tokio::task::yield_now().await;
+            }
+        }
+        .with_timeout_panic(Duration::from_secs(5))
+        .await;
+
+        cancelled.abort();
+        assert!(cancelled.await.unwrap_err().is_cancelled());
+        assert_eq!(pending(&registry, "interactive"), 1);
+
+        // the head gets the permit, the interactive waiter becomes the new head, skipping the cancelled waiter
+        drop(permit);
+        let permit = head
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+        async {
+            while pending(&registry, "interactive") > 0 {
+                tokio::task::yield_now().await;
+            }
+        }
+        .with_timeout_panic(Duration::from_secs(5))
+        .await;
+        assert_eq!(pending(&registry, "batch"), 1);
+
+        drop(permit);
+        let permit = waiter_interactive
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+
+        drop(permit);
+        let permit = waiter_batch
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(pending(&registry, "batch"), 0);
+
+        drop(permit);
+        assert_eq!(queue.semaphore().holders_acquired(), 0);
+
+        // the queue is free again
+        queue
+            .acquire_owned(WorkloadClass::Normal, None)
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_priority_admission_queue_starvation() {
+        let registry = Registry::new();
+        let semaphore_metrics = Arc::new(AsyncSemaphoreMetrics::new(
+            &registry,
+            &[("semaphore", "test")],
+        ));
+        let queue = Arc::new(PriorityAdmissionQueue::new(
+            &registry,
+            &[("semaphore", "test")],
+            Arc::new(semaphore_metrics.new_semaphore(1)),
+            Duration::ZERO,
+        ));
+
+        let permit = queue
+            .acquire_owned(WorkloadClass::Normal, None)
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        let q = Arc::clone(&queue);
+        let head = tokio::spawn(async move { q.acquire_owned(WorkloadClass::Normal, None).await });
+        async {
+            while queue.semaphore().holders_pending() < 1 {
+                tokio::task::yield_now().await;
+            }
+        }
+        .with_timeout_panic(Duration::from_secs(5))
+        .await;
+
+        let q = Arc::clone(&queue);
+        let waiter_batch =
+            tokio::spawn(async move { q.acquire_owned(WorkloadClass::Batch, None).await });
+        async {
+            while pending(&registry, "batch") < 1 {
+                tokio::task::yield_now().await;
+            }
+        }
+        .with_timeout_panic(Duration::from_secs(5))
+        .await;
+        let q = Arc::clone(&queue);
+        let waiter_interactive =
+            tokio::spawn(async move { q.acquire_owned(WorkloadClass::Interactive, None).await });
+        async {
+            while pending(&registry, "interactive") < 1 {
+                tokio::task::yield_now().await;
+            }
+        }
+        .with_timeout_panic(Duration::from_secs(5))
+        .await;
+
+        // every waiter is considered starved, so the batch waiter goes first
+        drop(permit);
+        let permit = head
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+        drop(permit);
+        let permit = waiter_batch
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(promoted(&registry, "batch"), 1);
+
+        drop(permit);
+        waiter_interactive
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_priority_admission_queue_namespace_weights() {
+        let registry = Registry::new();
+        let semaphore_metrics = Arc::new(AsyncSemaphoreMetrics::new(
+            &registry,
+            &[("semaphore", "test")],
+        ));
+        let queue = Arc::new(
+            PriorityAdmissionQueue::new(
+                &registry,
+                &[("semaphore", "test")],
+                Arc::new(semaphore_metrics.new_semaphore(1)),
+                Duration::from_secs(3600),
+            )
+            .with_namespace_weights([("other".to_owned(), NonZeroU32::MIN)]),
+        );
+        let admitted = Arc::new(Mutex::new(vec![]));
+
+        let permit = queue
+            .acquire_owned_for_namespace(WorkloadClass::Normal, "noisy", None)
+            .with_timeout_panic(Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        // every waiter records the order in which it was admitted and releases its permit right away
+        let spawn_waiter = |namespace: &'static str, id: usize| {
+            let queue = Arc::clone(&queue);
+            let admitted = Arc::clone(&admitted);
+            tokio::spawn(async move {
+                let _permit = queue
+                    .acquire_owned_for_namespace(WorkloadClass::Normal, namespace, None)
+                    .await
+                    .unwrap();
+                admitted.lock().push((namespace, id));
+            })
+        };
+
+        // the head of the queue waits for the semaphore
+        let mut waiters = vec![spawn_waiter("noisy", 0)];
+        async {
+            while queue.semaphore().holders_pending() < 1 {
+                tokio::task::yield_now().await;
+            }
+        }
+        .with_timeout_panic(Duration::from_secs(5))
+        .await;
+
+        // a burst of the noisy namespace is queued ahead of the other namespace
+        for (n, (namespace, id)) in [("noisy", 1), ("noisy", 2), ("noisy", 3), ("other", 0)]
+            .into_iter()
+            .enumerate()
+        {
+            waiters.push(spawn_waiter(namespace, id));
+            async {
+                while pending(&registry, "normal") < n as u64 + 1 {
+                    tokio::task::yield_now().await;
+                }
+            }
+            .with_timeout_panic(Duration::from_secs(5))
+            .await;
+        }
+
+        drop(permit);
+        for waiter in waiters {
+            waiter
+                .with_timeout_panic(Duration::from_secs(5))
+                .await
+                .unwrap();
+        }
+
+        // the other namespace is admitted after the first queued waiter of the noisy namespace
+        assert_eq!(
+            *admitted.lock(),
+            [
+                ("noisy", 0),
+                ("noisy", 1),
+                ("other", 0),
+                ("noisy", 2),
+                ("noisy", 3),
+            ]
+        );
+    }
+
+    fn pending(registry: &Registry, class: &'static str) -> u64 {
+        registry
+            .get_instrument::<Metric<U64Gauge>>("iox_priority_queue_holders_pending")
+            .unwrap()
+            .get_observer(&Attributes::from(&[
+                ("semaphore", "test"),
+                ("class", class),
+            ]))
+            .map(|o| o.fetch())
+            .unwrap_or_default()
+    }
+
+    fn promoted(registry: &Registry, class: &'static str) -> u64 {
+        registry
+            .get_instrument::<Metric<U64Counter>>("iox_priority_queue_holders_promoted")
+            .unwrap()
+            .get_observer(&Attributes::from(&[
+                ("semaphore", "test"),
+                ("class", class),
+            ]))
+            .map(|o| o.fetch())
+            .unwrap_or_default()
+    }
+}
@@ -81,6 +81,7 @@
 //! synchronisation to be well-formed.
 
 use iox_time::{Time, TimeProvider};
+use parking_lot::Mutex;
 use std::fmt::Formatter;
 use std::sync::{
     atomic::{AtomicUsize, Ordering},
@@ -92,12 +93,14 @@ use tokio_util::sync::CancellationToken;
 pub use future::{TrackedFuture, TrackedFutureExt};
 pub use history::TaskRegistryWithHistory;
 pub use metrics::{FAttributes, TaskRegistryWithMetrics};
+pub use progress::{ProgressReporter, TaskProgress};
 pub use registry::{AbstractTaskRegistry, TaskId, TaskRegistry};
 use tokio::sync::Notify;
 
 mod future;
 mod history;
 mod metrics;
+mod progress;
 mod registry;
 
 /// The state shared between all sibling tasks
@@ -118,6 +121,8 @@ struct TrackerState {
     err_futures: AtomicUsize,
     cancelled_futures: AtomicUsize,
 
+    progress: Mutex<TaskProgress>,
+
     notify: Notify,
 }
 
@@ -378,9 +383,10 @@ impl<T: std::fmt::Display + Send + Sync> std::fmt::Display for TaskTracker<T> {
         let status = self.state.get_status();
         write!(
             f,
-            "Task(start={},status={},metadata={})",
+            "Task(start={},status={},progress={},metadata={})",
             self.state.start_time,
             status,
+            self.state.progress.lock(),
             self.metadata.as_ref()
         )
     }
@@ -439,6 +445,17 @@ where
         self.state.get_status()
     }
 
+    /// Gets the progress most recently reported by the task
+    pub fn progress(&self) -> TaskProgress {
+        self.state.progress.lock().clone()
+    }
+
+    /// Returns a [`ProgressReporter`] that can be used to report the
+    /// progress of the task
+    pub fn progress_reporter(&self) -> ProgressReporter {
+        ProgressReporter::new(Arc::clone(&self.state))
+    }
+
     /// Returns the instant the tracker was created
     pub fn start_time(&self) -> Time {
         self.state.start_time
@@ -511,12 +528,19 @@ impl TaskRegistration {
             ok_futures: AtomicUsize::new(0),
             err_futures: AtomicUsize::new(0),
             cancelled_futures: AtomicUsize::new(0),
+            progress: Default::default(),
             notify: Notify::new(),
         });
 
         Self { state }
     }
 
+    /// Returns a [`ProgressReporter`] that can be used by the registered
+    /// futures to report the progress of the task
+    pub fn progress_reporter(&self) -> ProgressReporter {
+        ProgressReporter::new(Arc::clone(&self.state))
+    }
+
     /// Converts the registration into a tracker with id 0 and specified metadata
     pub fn into_tracker<T>(self, metadata: T) -> TaskTracker<T>
     where
@@ -614,6 +638,47 @@ mod tests {
         assert_eq!(registry.running().len(), 0);
     }
 
+    #[tokio::test]
+    async fn test_progress() {
+        let (sender, receive) = oneshot::channel();
+        let mut registry = test_registry();
+        let (tracker, registration) = registry.register(());
+        assert_eq!(tracker.progress(), TaskProgress::default());
+
+        let reporter = registration.progress_reporter();
+        tokio::spawn(receive.track(registration));
+
+        reporter.set_phase("sort");
+        reporter.set_items_total(10);
+        reporter.inc_items_done(3);
+        reporter.inc_items_done(2);
+
+        let tracked = registry.get(tracker.id()).unwrap();
+        let progress = tracked.progress();
+        assert_eq!(progress.phase.as_deref(), Some("sort"));
+        assert_eq!(progress.items_done, 5);
+        assert_eq!(progress.items_total, Some(10));
+        assert_eq!(progress.fraction(), Some(0.5));
+
+        // entering a new phase resets the counts
+        reporter.set_phase("upload");
+        assert_eq!(
+            tracked.progress(),
+            TaskProgress {
+                phase: Some("upload".into()),
+                items_done: 0,
+                items_total: None,
+            }
+        );
+
+        // the reporter does not keep the task alive
+        sender.send(()).unwrap();
+        tracker.join().await;
+        assert_eq!(registry.running().len(), 0);
+        reporter.inc_items_done(1);
+        assert_eq!(tracker.progress().items_done, 1);
+    }
+
     #[tokio::test]
     async fn test_drop() {
         let mut registry = test_registry();
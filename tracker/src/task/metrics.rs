@@ -4,7 +4,7 @@ use std::{
     time::Duration,
 };
 
-use crate::{AbstractTaskRegistry, TaskId, TaskRegistration, TaskTracker};
+use crate::{AbstractTaskRegistry, TaskId, TaskProgress, TaskRegistration, TaskTracker};
 
 /// Function that extracts metric attributes from job metadata.
 ///
@@ -84,6 +84,13 @@ where
     cpu_time_histogram: metric::Metric<metric::DurationHistogram>,
     wall_time_histogram: metric::Metric<metric::DurationHistogram>,
 
+    progress_done_gauge: metric::Metric<metric::U64Gauge>,
+    progress_total_gauge: metric::Metric<metric::U64Gauge>,
+
+    // Attributes for which progress was last emitted. These must be reset once no running job reports progress for
+    // them anymore.
+    progress_attributes: BTreeSet<metric::Attributes>,
+
     // Set of jobs for which we already accounted data but that are still tracked. We must not account these
     // jobs a second time.
     completed_but_still_tracked: BTreeSet<TaskId>,
@@ -101,6 +108,9 @@ where
             .field("completed_accu", &self.completed_accu)
             .field("cpu_time_histogram", &self.cpu_time_histogram)
             .field("wall_time_histogram", &self.wall_time_histogram)
+            .field("progress_done_gauge", &self.progress_done_gauge)
+            .field("progress_total_gauge", &self.progress_total_gauge)
+            .field("progress_attributes", &self.progress_attributes)
             .field(
                 "completed_but_still_tracked",
                 &self.completed_but_still_tracked,
@@ -128,6 +138,15 @@ where
                 "Wall time of of completed jobs",
                 Self::duration_histogram_options,
             ),
+            progress_done_gauge: metric_registry.register_metric(
+                "influxdb_iox_job_progress_items_done",
+                "Number of items processed in the current phase of running jobs",
+            ),
+            progress_total_gauge: metric_registry.register_metric(
+                "influxdb_iox_job_progress_items_total",
+                "Number of items to process in the current phase of running jobs, if known",
+            ),
+            progress_attributes: Default::default(),
             completed_but_still_tracked: Default::default(),
             f_attributes,
         }
@@ -175,12 +194,34 @@ where
 
         // scan current not-completed jobs
         let mut accumulator: BTreeMap<metric::Attributes, u64> = self.completed_accu.clone();
+        let mut progress_accumulator: BTreeMap<metric::Attributes, (u64, u64)> = BTreeMap::new();
         for job in tracked_other {
-            let attr = self.job_to_gauge_attributes(&job);
+            let mut attr = self.job_to_gauge_attributes(&job);
             accumulator
                 .entry(attr.clone())
                 .and_modify(|x| *x += 1)
                 .or_insert(1);
+
+            let progress = job.progress();
+            if progress != TaskProgress::default() {
+                attr.insert("phase", progress.phase.unwrap_or("none".into()));
+                let (done, total) = progress_accumulator.entry(attr).or_default();
+                *done += progress.items_done;
+                *total += progress.items_total.unwrap_or_default();
+            }
+        }
+
+        // reset progress of jobs that are gone or moved to another phase
+        for attr in std::mem::take(&mut self.progress_attributes) {
+            if !progress_accumulator.contains_key(&attr) {
+                self.progress_done_gauge.recorder(attr.clone()).set(0);
+                self.progress_total_gauge.recorder(attr).set(0);
+            }
+        }
+        for (attr, (done, total)) in progress_accumulator {
+            self.progress_done_gauge.recorder(attr.clone()).set(done);
+            self.progress_total_gauge.recorder(attr.clone()).set(total);
+            self.progress_attributes.insert(attr);
         }
 
         // emit metric
@@ -273,6 +314,72 @@ mod tests {
         assert_eq!(gauge, &Observation::U64Gauge(2));
     }
 
+    #[test]
+    fn test_progress_metrics() {
+        let time_provider = Arc::new(iox_time::SystemProvider::new());
+        let registry = TaskRegistry::new(time_provider);
+        let metric_registry = Arc::new(metric::Registry::new());
+        let mut reg = TaskRegistryWithMetrics::new(
+            registry,
+            Arc::clone(&metric_registry),
+            Box::new(extract_attributes),
+        );
+
+        let (tracker, registration) = reg.register(0);
+        let reporter = registration.progress_reporter();
+        reporter.set_phase("sort");
+        reporter.set_items_total(10);
+        reporter.inc_items_done(4);
+
+        // jobs without progress are not reported
+        let (_tracker2, _registration2) = reg.register(2);
+
+        reg.reclaim();
+        let attr = [
+            ("status", "Creating"),
+            ("is_even", "true"),
+            ("phase", "sort"),
+        ];
+        assert_eq!(progress(&metric_registry, &attr), (4, 10));
+
+        // the previous phase is reset
+        reporter.set_phase("upload");
+        reg.reclaim();
+        assert_eq!(progress(&metric_registry, &attr), (0, 0));
+        let attr_upload = [
+            ("status", "Creating"),
+            ("is_even", "true"),
+            ("phase", "upload"),
+        ];
+        assert_eq!(progress(&metric_registry, &attr_upload), (0, 0));
+
+        // completed jobs are reset
+        reporter.inc_items_done(1);
+        reg.reclaim();
+        assert_eq!(progress(&metric_registry, &attr_upload), (1, 0));
+        drop(registration);
+        assert!(tracker.is_complete());
+        reg.reclaim();
+        assert_eq!(progress(&metric_registry, &attr_upload), (0, 0));
+    }
+
+    fn progress(
+        metric_registry: &metric::Registry,
+        attr: &[(&'static str, &'static str); 3],
+    ) -> (u64, u64) {
+        let mut reporter = metric::RawReporter::default();
+        metric_registry.report(&mut reporter);
+
+        let get = |name| match reporter.metric(name).unwrap().observation(attr).unwrap() {
+            Observation::U64Gauge(v) => *v,
+            _ => unreachable!(),
+        };
+        (
+            get("influxdb_iox_job_progress_items_done"),
+            get("influxdb_iox_job_progress_items_total"),
+        )
+    }
+
     async fn fut() -> Result<(), ()> {
         Ok(())
     }
@@ -0,0 +1,136 @@
+use std::{borrow::Cow, fmt::Formatter, sync::Arc};
+
+use observability_deps::tracing::info;
+
+use super::TrackerState;
+
+/// Structured progress reported by a long-running task, e.g. a compaction or a
+/// garbage collection sweep.
+///
+/// Progress is reported on a best-effort basis by the task itself using a
+/// [`ProgressReporter`] and may not be reported at all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaskProgress {
+    /// The current phase of the task, if reported.
+    pub phase: Option<Cow<'static, str>>,
+
+    /// The number of items processed in the current phase.
+    pub items_done: u64,
+
+    /// The total number of items to process in the current phase, if known.
+    pub items_total: Option<u64>,
+}
+
+impl TaskProgress {
+    /// Returns the fraction of items processed in the current phase, if the
+    /// total number of items is known.
+    pub fn fraction(&self) -> Option<f64> {
+        self.items_total.map(|total| match total {
+            0 => 1.0,
+            total => (self.items_done.min(total) as f64) / (total as f64),
+        })
+    }
+}
+
+impl std::fmt::Display for TaskProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TaskProgress(phase={},done={}",
+            self.phase.as_deref().unwrap_or("none"),
+            self.items_done
+        )?;
+        if let Some(total) = self.items_total {
+            write!(f, ",total={total}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Reports the [`TaskProgress`] of a task, making it visible via the
+/// associated [`TaskTracker`](super::TaskTracker).
+///
+/// Unlike a [`TaskRegistration`](super::TaskRegistration), holding a
+/// reporter does not prevent the task from being considered complete.
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    state: Arc<TrackerState>,
+}
+
+impl ProgressReporter {
+    pub(super) fn new(state: Arc<TrackerState>) -> Self {
+        Self { state }
+    }
+
+    /// Enter a new phase, resetting the item counts.
+    pub fn set_phase(&self, phase: impl Into<Cow<'static, str>>) {
+        let phase = phase.into();
+        let mut progress = self.state.progress.lock();
+
+        info!(
+            from = progress.phase.as_deref().unwrap_or("none"),
+            to = phase.as_ref(),
+            items_done = progress.items_done,
+            items_total = progress.items_total,
+            "task phase changed",
+        );
+
+        *progress = TaskProgress {
+            phase: Some(phase),
+            items_done: 0,
+            items_total: None,
+        };
+    }
+
+    /// Set the total number of items to process in the current phase.
+    pub fn set_items_total(&self, total: u64) {
+        self.state.progress.lock().items_total = Some(total);
+    }
+
+    /// Record that `n` more items of the current phase have been processed.
+    pub fn inc_items_done(&self, n: u64) {
+        let mut progress = self.state.progress.lock();
+        progress.items_done = progress.items_done.saturating_add(n);
+    }
+
+    /// Set the number of items of the current phase that have been processed.
+    pub fn set_items_done(&self, done: u64) {
+        self.state.progress.lock().items_done = done;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fraction() {
+        let mut progress = TaskProgress::default();
+        assert_eq!(progress.fraction(), None);
+
+        progress.items_total = Some(0);
+        assert_eq!(progress.fraction(), Some(1.0));
+
+        progress.items_total = Some(4);
+        progress.items_done = 1;
+        assert_eq!(progress.fraction(), Some(0.25));
+
+        // over-reporting is capped
+        progress.items_done = 5;
+        assert_eq!(progress.fraction(), Some(1.0));
+    }
+
+    #[test]
+    fn test_display() {
+        let mut progress = TaskProgress::default();
+        assert_eq!(progress.to_string(), "TaskProgress(phase=none,done=0)");
+
+        progress.phase = Some("compact".into());
+        progress.items_done = 3;
+        progress.items_total = Some(10);
+        assert_eq!(
+            progress.to_string(),
+            "TaskProgress(phase=compact,done=3,total=10)"
+        );
+    }
+}
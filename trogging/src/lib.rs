@@ -30,10 +30,11 @@ use std::{
 };
 use thiserror::Error;
 use tracing_subscriber::{
+    filter::ParseError,
     fmt::{self, writer::BoxMakeWriter, MakeWriter},
     layer::SubscriberExt,
     registry::LookupSpan,
-    EnvFilter, Layer,
+    reload, EnvFilter, Layer, Registry,
 };
 
 /// Maximum length of a log line.
@@ -53,6 +54,12 @@ pub enum Error {
 
     #[error("Cannot set global log subscriber")]
     SetLoggerError(#[from] tracing_log::log_tracer::SetLoggerError),
+
+    #[error("Invalid log filter: {0}")]
+    InvalidLogFilter(#[from] ParseError),
+
+    #[error("Cannot change log filter: {0}")]
+    ReloadLogFilter(#[from] reload::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -199,19 +206,43 @@ where
 
     /// Returns a [`Layer`] that emits logs as specified by the configuration of
     /// `self`.
-    pub fn build<S>(self) -> Result<impl Layer<S> + 'static>
+    pub fn build<S>(mut self) -> Result<impl Layer<S> + 'static>
+    where
+        S: Subscriber,
+        for<'a> S: LookupSpan<'a>,
+    {
+        let log_filter = self.take_log_filter();
+        Ok(self.build_with_filter(log_filter))
+    }
+
+    /// Like [`build`](Self::build), but the log filter can be changed while the layer is in use via the returned
+    /// [`LogFilterHandle`].
+    pub fn build_reloadable(mut self) -> Result<(impl Layer<Registry> + 'static, LogFilterHandle)> {
+        let log_filter = self.take_log_filter();
+        let initial = log_filter.to_string();
+        let (log_filter, handle) = reload::Layer::new(log_filter);
+        let layer = self.build_with_filter(log_filter);
+        Ok((layer, LogFilterHandle { handle, initial }))
+    }
+
+    fn take_log_filter(&mut self) -> EnvFilter {
+        self.log_filter
+            .take()
+            .unwrap_or_else(|| std::mem::take(&mut self.default_log_filter))
+    }
+
+    fn build_with_filter<S, F>(self, log_filter: F) -> Box<dyn Layer<S> + Send + Sync>
     where
         S: Subscriber,
         for<'a> S: LookupSpan<'a>,
+        F: Layer<S> + Send + Sync + 'static,
     {
         let log_writer = self.make_writer;
         let log_format = self.log_format;
         let with_target = self.with_target;
         let with_ansi = self.with_ansi;
 
-        let log_filter = self.log_filter.unwrap_or(self.default_log_filter);
-
-        let res: Box<dyn Layer<S> + Send + Sync> = match log_format {
+        match log_format {
             LogFormat::Full => Box::new(
                 log_filter.and_then(
                     fmt::layer()
@@ -241,9 +272,7 @@ where
             LogFormat::Logfmt => Box::new(
                 log_filter.and_then(logfmt::LogFmtLayer::new(log_writer).with_target(with_target)),
             ),
-        };
-
-        Ok(res)
+        }
     }
 
     /// Build a tracing subscriber and install it as a global default subscriber
@@ -255,6 +284,34 @@ where
         let subscriber = tracing_subscriber::Registry::default().with(layer);
         install_global(subscriber)
     }
+
+    /// Like [`install_global`](Self::install_global), but the log filter can be changed afterwards via the returned
+    /// [`LogFilterHandle`].
+    pub fn install_global_reloadable(self) -> Result<(TroggingGuard, LogFilterHandle)> {
+        let (layer, handle) = self.build_reloadable()?;
+        let subscriber = tracing_subscriber::Registry::default().with(layer);
+        Ok((install_global(subscriber)?, handle))
+    }
+}
+
+/// Handle to change the log filter of a layer built by [`Builder::build_reloadable`] while it is in use.
+#[derive(Debug, Clone)]
+pub struct LogFilterHandle {
+    handle: reload::Handle<EnvFilter, Registry>,
+
+    /// The filter the layer was built with.
+    initial: String,
+}
+
+impl LogFilterHandle {
+    /// Replace the log filter, using the same syntax as `--log-filter`.
+    ///
+    /// `None` restores the filter the layer was built with. If `log_filter` is invalid, the current filter is kept.
+    pub fn set(&self, log_filter: Option<&str>) -> Result<()> {
+        let log_filter = EnvFilter::try_new(log_filter.unwrap_or(&self.initial))?;
+        self.handle.reload(log_filter)?;
+        Ok(())
+    }
 }
 
 /// Install a global tracing/logging subscriber.
@@ -513,6 +570,44 @@ TRACE trax
         );
     }
 
+    #[test]
+    fn test_reload_log_filter() {
+        let (writer, output) = TestWriter::new();
+        let (layer, handle) = Builder::new()
+            .with_writer(make_writer(writer))
+            .with_target(false)
+            .with_ansi(false)
+            .with_log_filter(&Some("info".to_owned()))
+            .build_reloadable()
+            .unwrap();
+
+        let subscriber = tracing_subscriber::Registry::default().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            debug!("hidden");
+            handle.set(Some("debug")).unwrap();
+            debug!("shown");
+
+            // invalid filters keep the current one
+            let err = handle.set(Some("bananas=bananas")).unwrap_err();
+            assert!(matches!(err, Error::InvalidLogFilter(_)), "{err}");
+            debug!("still shown");
+
+            handle.set(None).unwrap();
+            debug!("hidden again");
+            error!("error");
+        });
+
+        assert_eq!(
+            output.without_timestamps(),
+            r#"
+DEBUG shown
+DEBUG still shown
+ERROR error
+"#
+            .trim_start(),
+        );
+    }
+
     #[test]
     fn test_override_default_log_filter() {
         const DEFAULT_LOG_FILTER: &str = "error";
@@ -27,7 +27,11 @@ use std::{
 use hashbrown::HashMap;
 use parking_lot::Mutex;
 use snafu::prelude::*;
-use tokio::{sync::watch, task::JoinHandle};
+use tokio::{
+    sync::{watch, Notify},
+    task::JoinHandle,
+    time::Instant,
+};
 
 use data_types::{sequence_number_set::SequenceNumberSet, NamespaceId, TableId};
 use generated_types::{
@@ -39,6 +43,7 @@ use generated_types::{
 use mutable_batch::MutableBatch;
 use mutable_batch_pb::decode::decode_database_batch;
 use observability_deps::tracing::info;
+use shipping::SegmentShipper;
 use writer_thread::WriterIoThreadHandle;
 
 use crate::blocking::{
@@ -46,9 +51,48 @@ use crate::blocking::{
 };
 
 pub mod blocking;
+pub mod report;
+pub mod shipping;
 mod writer_thread;
 
-const WAL_FLUSH_INTERVAL: Duration = Duration::from_millis(10);
+/// The default maximum duration a buffered write waits for concurrent writes
+/// to join its group commit before being flushed.
+const DEFAULT_LINGER: Duration = Duration::from_millis(10);
+
+/// The default number of buffered [`SequencedWalOp`] that causes a group
+/// commit to be flushed immediately, without waiting out the linger window.
+const DEFAULT_MAX_BATCH_OPS: usize = 1_000;
+
+/// Configuration of the [`Wal`] write path.
+///
+/// Writes to the [`Wal`] are group committed: concurrent writers' ops are
+/// buffered and flushed as a single write + fsync. The first op written to an
+/// empty buffer opens a linger window of [`WalConfig::linger`], during which
+/// any other ops are added to the same batch. The batch is flushed when the
+/// linger window elapses, or as soon as it contains
+/// [`WalConfig::max_batch_ops`] ops, whichever happens first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalConfig {
+    /// The maximum duration the first op in a batch waits for other ops to
+    /// join the group commit.
+    ///
+    /// A zero duration flushes each batch as soon as the flusher observes
+    /// it, which minimises latency at the cost of more fsync calls.
+    pub linger: Duration,
+
+    /// The number of buffered ops that causes the batch to be flushed before
+    /// the linger window elapses.
+    pub max_batch_ops: usize,
+}
+
+impl Default for WalConfig {
+    fn default() -> Self {
+        Self {
+            linger: DEFAULT_LINGER,
+            max_batch_ops: DEFAULT_MAX_BATCH_OPS,
+        }
+    }
+}
 
 // TODO: Should have more variants / error types to avoid reusing these
 #[derive(Debug, Snafu)]
@@ -142,6 +186,11 @@ pub enum Error {
     UnableToCreateSegmentFile {
         source: blocking::WriterError,
     },
+
+    DeleteShippedSegment {
+        source: shipping::Error,
+        id: SegmentId,
+    },
 }
 
 /// Errors that occur when decoding internal types from a WAL file.
@@ -221,6 +270,15 @@ pub struct Wal {
     segments: Arc<Mutex<Segments>>,
     next_id_source: Arc<AtomicU64>,
     buffer: Mutex<WalBuffer>,
+    config: WalConfig,
+
+    /// An optional [`SegmentShipper`] mirroring closed segments to object
+    /// storage.
+    shipper: Option<Arc<SegmentShipper>>,
+
+    /// Signalled when the first op is added to an empty `buffer`, or when the
+    /// buffer reaches [`WalConfig::max_batch_ops`].
+    buffer_notify: Notify,
 
     /// The handle to the [`Wal::flush_buffer_background_task()`] task.
     flusher_task: Mutex<Option<JoinHandle<()>>>,
@@ -237,8 +295,48 @@ impl Wal {
     /// Similarly, editing or deleting files within a `Wal`'s root directory via some other
     /// mechanism is not supported.
     pub async fn new(root: impl Into<PathBuf> + Send) -> Result<Arc<Self>> {
-        let root = root.into();
-        info!(wal_dir=?root, "Initalizing Write Ahead Log (WAL)");
+        Self::new_with_config(root, WalConfig::default()).await
+    }
+
+    /// Creates a `Wal` instance that manages files in the specified root
+    /// directory, using the provided [`WalConfig`] for the write path.
+    ///
+    /// The same constraints as [`Wal::new()`] apply.
+    pub async fn new_with_config(
+        root: impl Into<PathBuf> + Send,
+        config: WalConfig,
+    ) -> Result<Arc<Self>> {
+        Self::new_inner(root.into(), config, None).await
+    }
+
+    /// Creates a `Wal` instance that manages files in the specified root
+    /// directory, mirroring every closed segment to object storage with
+    /// `shipper`.
+    ///
+    /// Any closed segments already present in `root` are (re-)shipped, and
+    /// the shipped copy of a segment is removed when it is
+    /// [deleted](Wal::delete) locally.
+    ///
+    /// The same constraints as [`Wal::new()`] apply.
+    pub async fn new_with_shipper(
+        root: impl Into<PathBuf> + Send,
+        config: WalConfig,
+        shipper: Arc<SegmentShipper>,
+    ) -> Result<Arc<Self>> {
+        Self::new_inner(root.into(), config, Some(shipper)).await
+    }
+
+    async fn new_inner(
+        root: PathBuf,
+        config: WalConfig,
+        shipper: Option<Arc<SegmentShipper>>,
+    ) -> Result<Arc<Self>> {
+        info!(
+            wal_dir=?root,
+            linger=?config.linger,
+            max_batch_ops=config.max_batch_ops,
+            "Initalizing Write Ahead Log (WAL)"
+        );
         tokio::fs::create_dir_all(&root)
             .await
             .context(UnableToCreateWalDirSnafu { path: &root })?;
@@ -296,6 +394,12 @@ impl Wal {
             OpenSegmentFileWriter::new_in_directory(&root, Arc::clone(&next_id_source))
                 .context(UnableToCreateSegmentFileSnafu)?;
 
+        if let Some(shipper) = &shipper {
+            for segment in closed_segments.values() {
+                shipper.enqueue(segment.clone());
+            }
+        }
+
         let buffer = WalBuffer::new(None);
 
         let wal = Self {
@@ -307,6 +411,9 @@ impl Wal {
             })),
             next_id_source,
             buffer: Mutex::new(buffer),
+            config,
+            shipper,
+            buffer_notify: Notify::new(),
             flusher_task: Default::default(),
         };
 
@@ -335,9 +442,20 @@ impl Wal {
 
     /// Writes one [`SequencedWalOp`] to the buffer and returns a watch channel
     /// for when the buffer is flushed and fsync'd to disk.
+    ///
+    /// The op is group committed alongside any other ops written concurrently
+    /// - see [`WalConfig`].
     pub fn write_op(&self, op: SequencedWalOp) -> watch::Receiver<Option<WriteResult>> {
         let mut b = self.buffer.lock();
         b.ops.push(op);
+
+        // Wake the flusher when this op opens a new batch (starting the linger
+        // window) or fills the current batch (ending it early).
+        let len = b.ops.len();
+        if len == 1 || len >= self.config.max_batch_ops {
+            self.buffer_notify.notify_one();
+        }
+
         b.flush_notification.clone()
     }
 
@@ -361,6 +479,10 @@ impl Wal {
             "should always add new closed segment entries, not replace"
         );
 
+        if let Some(shipper) = &self.shipper {
+            shipper.enqueue(closed.clone());
+        }
+
         Ok((closed, seqnum_set))
     }
 
@@ -374,15 +496,28 @@ impl Wal {
         // When this handle is dropped, the I/O thread is gracefully stopped.
         let io_thread = WriterIoThreadHandle::new(Arc::clone(&self.segments));
 
-        let mut interval = tokio::time::interval(WAL_FLUSH_INTERVAL);
-
         // Pre-allocate the WAL buffer outside of the exclusive lock, and track
         // the buffer utilisation to optimise pre-allocation.
         let mut size_hint = None;
         let mut new_buf = WalBuffer::new(size_hint);
 
         loop {
-            interval.tick().await;
+            // Wait for an op to be buffered, opening the linger window.
+            //
+            // This may be a stale notification left over from a batch that has
+            // already been flushed, in which case the buffer is empty below and
+            // no write is performed.
+            self.buffer_notify.notified().await;
+
+            // Linger, allowing concurrent writers to join this group commit,
+            // unless the batch fills up first.
+            let deadline = Instant::now() + self.config.linger;
+            while self.buffer.lock().ops.len() < self.config.max_batch_ops {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(deadline) => break,
+                    _ = self.buffer_notify.notified() => {},
+                }
+            }
 
             // Rust's move properties ensure we never accidentally reuse a
             // buffer, but make it clear the buffer is always fresh before use.
@@ -414,15 +549,36 @@ impl Wal {
         }
     }
 
-    /// Deletes the specified segment from disk.
+    /// Deletes the specified segment from disk, and the shipped copy of it
+    /// from object storage if a [`SegmentShipper`] is configured.
+    ///
+    /// The shipped copy is deleted first, so a segment that is gone locally
+    /// can never be brought back by [`SegmentShipper::restore()`]. If the
+    /// shipped copy cannot be deleted, an error is returned and the local
+    /// segment is kept.
     pub async fn delete(&self, id: SegmentId) -> Result<()> {
+        ensure!(
+            self.segments.lock().closed_segments.contains_key(&id),
+            SegmentNotFoundSnafu { id }
+        );
+
+        if let Some(shipper) = &self.shipper {
+            shipper
+                .remove(id)
+                .await
+                .context(DeleteShippedSegmentSnafu { id })?;
+        }
+
         let closed = self
             .segments
             .lock()
             .closed_segments
             .remove(&id)
             .context(SegmentNotFoundSnafu { id })?;
-        std::fs::remove_file(&closed.path).context(DeleteClosedSegmentSnafu { path: closed.path })
+        std::fs::remove_file(&closed.path)
+            .context(DeleteClosedSegmentSnafu { path: closed.path })?;
+
+        Ok(())
     }
 }
 
@@ -440,6 +596,8 @@ impl std::fmt::Debug for Wal {
         f.debug_struct("Wal")
             .field("root", &self.root)
             .field("next_id_source", &self.next_id_source)
+            .field("config", &self.config)
+            .field("shipper", &self.shipper)
             .finish()
     }
 }
@@ -778,6 +936,85 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn group_commit_concurrent_writes() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let wal = Wal::new_with_config(
+            dir.path(),
+            WalConfig {
+                linger: Duration::from_millis(100),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        // Write several ops within the linger window.
+        let ops = (0..5)
+            .map(|i| SequencedWalOp {
+                table_write_sequence_numbers: [(TableId::new(0), i)].into_iter().collect(),
+                op: WalOp::Write(test_data("m1,t=foo v=1i 1")),
+            })
+            .collect::<Vec<_>>();
+        let mut notifications = ops
+            .iter()
+            .map(|op| wal.write_op(op.clone()))
+            .collect::<Vec<_>>();
+        for rx in &mut notifications {
+            rx.changed().await.unwrap();
+        }
+
+        // All writers must observe the same flush of the same segment write.
+        let total_bytes = notifications
+            .iter()
+            .map(|rx| match rx.borrow().clone() {
+                Some(WriteResult::Ok(summary)) => summary.total_bytes,
+                v => panic!("unexpected write result {v:?}"),
+            })
+            .collect::<BTreeSet<_>>();
+        assert_eq!(total_bytes.len(), 1);
+
+        // And the ops must have been written as a single entry batch.
+        let (closed, _) = wal.rotate().unwrap();
+        let batches = wal
+            .reader_for_segment(closed.id())
+            .unwrap()
+            .map(|batch| batch.expect("failed to read WAL op batch").0)
+            .collect::<Vec<_>>();
+        assert_eq!(batches, vec![ops]);
+    }
+
+    #[tokio::test]
+    async fn group_commit_flushes_full_batch_before_linger() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let wal = Wal::new_with_config(
+            dir.path(),
+            WalConfig {
+                // Far longer than the test timeout.
+                linger: Duration::from_secs(3600),
+                max_batch_ops: 2,
+            },
+        )
+        .await
+        .unwrap();
+
+        let op = SequencedWalOp {
+            table_write_sequence_numbers: [(TableId::new(0), 0)].into_iter().collect(),
+            op: WalOp::Write(test_data("m1,t=foo v=1i 1")),
+        };
+
+        let mut rx = wal.write_op(op.clone());
+        wal.write_op(op);
+
+        // Filling the batch must flush it without waiting for the linger
+        // window to elapse.
+        tokio::time::timeout(Duration::from_secs(5), rx.changed())
+            .await
+            .expect("full batch was not flushed")
+            .unwrap();
+        assert_matches!(*rx.borrow(), Some(WriteResult::Ok(_)));
+    }
+
     // open wal with files that aren't segments (should log and skip)
 
     // read segment works even if last entry is truncated
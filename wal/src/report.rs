@@ -0,0 +1,308 @@
+//! Read-only inspection of the contents of a WAL directory.
+//!
+//! A [`WalReport`] summarises every segment file in a WAL directory without
+//! modifying it, making it safe to generate against the directory of a
+//! running [`Wal`] for debugging and operational tooling.
+//!
+//! [`Wal`]: crate::Wal
+
+use std::{collections::BTreeMap, path::Path};
+
+use data_types::{PartitionKey, SequenceNumber, TableId};
+use generated_types::influxdata::iox::wal::v1::sequenced_wal_op::Op as WalOp;
+use mutable_batch_pb::decode::decode_database_batch;
+use snafu::prelude::*;
+
+use crate::{
+    ClosedSegmentFileReader, InvalidIdSnafu, Result, SegmentId, UnableToReadDirectoryContentsSnafu,
+    UnableToReadFileMetadataSnafu, SEGMENT_FILE_EXTENSION,
+};
+
+/// A summary of all the segment files within a WAL directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WalReport {
+    /// Per-segment summaries, ordered by [`SegmentId`].
+    pub segments: Vec<SegmentReport>,
+}
+
+impl WalReport {
+    /// Summarise the segment files in the WAL directory `dir`.
+    ///
+    /// Files that are not WAL segment files are ignored. A segment that
+    /// cannot be fully read (such as one with a truncated tail) is summarised
+    /// up to the first unreadable entry, with the failure recorded in
+    /// [`SegmentReport::error`].
+    pub fn from_directory(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+
+        let mut paths = BTreeMap::new();
+        for child in
+            std::fs::read_dir(dir).context(UnableToReadDirectoryContentsSnafu { path: dir })?
+        {
+            let child = child.context(UnableToReadDirectoryContentsSnafu { path: dir })?;
+            let path = child.path();
+
+            let metadata = child.metadata().context(UnableToReadFileMetadataSnafu)?;
+            if !metadata.is_file()
+                || path.extension().and_then(|v| v.to_str()) != Some(SEGMENT_FILE_EXTENSION)
+            {
+                continue;
+            }
+
+            let Some(filename) = path.file_stem().and_then(|v| v.to_str()) else {
+                continue;
+            };
+            let id = SegmentId::new(filename.parse().context(InvalidIdSnafu { filename })?);
+            paths.insert(id, (path, metadata.len()));
+        }
+
+        let segments = paths
+            .into_values()
+            .map(|(path, size)| SegmentReport::from_path(path, size))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { segments })
+    }
+
+    /// The total size of all segment files, in bytes.
+    pub fn total_bytes(&self) -> u64 {
+        self.segments.iter().map(|s| s.size).sum()
+    }
+
+    /// The minimum and maximum [`SequenceNumber`] across all segments, if
+    /// any ops were read.
+    pub fn sequence_number_range(&self) -> Option<(SequenceNumber, SequenceNumber)> {
+        let min = self
+            .segments
+            .iter()
+            .filter_map(|s| s.min_sequence_number)
+            .min()?;
+        let max = self
+            .segments
+            .iter()
+            .filter_map(|s| s.max_sequence_number)
+            .max()?;
+        Some((min, max))
+    }
+
+    /// The number of write ops per table across all segments.
+    pub fn table_write_counts(&self) -> BTreeMap<TableId, usize> {
+        let mut counts = BTreeMap::new();
+        for (&table_id, &n) in self.segments.iter().flat_map(|s| &s.table_write_counts) {
+            *counts.entry(table_id).or_default() += n;
+        }
+        counts
+    }
+
+    /// The oldest data timestamp (in nanoseconds since the epoch) written to
+    /// any segment.
+    ///
+    /// Segments are deleted once their contents have been persisted, making
+    /// this the oldest timestamp of any un-persisted data.
+    pub fn oldest_timestamp_nanos(&self) -> Option<i64> {
+        self.segments
+            .iter()
+            .filter_map(|s| s.oldest_timestamp_nanos)
+            .min()
+    }
+}
+
+/// A summary of a single WAL segment file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentReport {
+    /// The ID of the segment.
+    pub id: SegmentId,
+    /// The size of the segment file, in bytes.
+    pub size: u64,
+    /// The number of entry batches in the segment.
+    pub n_batches: usize,
+    /// The number of write ops in the segment.
+    pub n_write_ops: usize,
+    /// The number of delete ops in the segment.
+    pub n_delete_ops: usize,
+    /// The number of persist ops in the segment.
+    pub n_persist_ops: usize,
+    /// The smallest [`SequenceNumber`] of an op in the segment.
+    pub min_sequence_number: Option<SequenceNumber>,
+    /// The largest [`SequenceNumber`] of an op in the segment.
+    pub max_sequence_number: Option<SequenceNumber>,
+    /// The number of write ops containing data for each table.
+    pub table_write_counts: BTreeMap<TableId, usize>,
+    /// The number of write ops containing data for each table and partition,
+    /// for writes that specify a partition key.
+    pub partition_write_counts: BTreeMap<(TableId, PartitionKey), usize>,
+    /// The oldest data timestamp (in nanoseconds since the epoch) of any
+    /// write in the segment.
+    pub oldest_timestamp_nanos: Option<i64>,
+    /// The reason the segment could not be read in full, if any.
+    pub error: Option<String>,
+}
+
+impl SegmentReport {
+    fn from_path(path: impl AsRef<Path>, size: u64) -> Result<Self> {
+        let reader = ClosedSegmentFileReader::from_path(path)?;
+
+        let mut report = Self {
+            id: reader.id(),
+            size,
+            n_batches: 0,
+            n_write_ops: 0,
+            n_delete_ops: 0,
+            n_persist_ops: 0,
+            min_sequence_number: None,
+            max_sequence_number: None,
+            table_write_counts: Default::default(),
+            partition_write_counts: Default::default(),
+            oldest_timestamp_nanos: None,
+            error: None,
+        };
+
+        for batch in reader {
+            let batch = match batch {
+                Ok((batch, _)) => batch,
+                Err(e) => {
+                    report.error = Some(e.to_string());
+                    break;
+                }
+            };
+
+            report.n_batches += 1;
+            for op in batch {
+                for &id in op.table_write_sequence_numbers.values() {
+                    let id = SequenceNumber::new(id);
+                    report.min_sequence_number =
+                        Some(report.min_sequence_number.map_or(id, |v| v.min(id)));
+                    report.max_sequence_number =
+                        Some(report.max_sequence_number.map_or(id, |v| v.max(id)));
+                }
+
+                match op.op {
+                    WalOp::Write(w) => {
+                        report.n_write_ops += 1;
+
+                        let table_batches = match decode_database_batch(&w) {
+                            Ok(v) => v,
+                            Err(e) => {
+                                report.error = Some(e.to_string());
+                                return Ok(report);
+                            }
+                        };
+
+                        for (table_id, batch) in table_batches {
+                            let table_id = TableId::new(table_id);
+                            *report.table_write_counts.entry(table_id).or_default() += 1;
+
+                            if !w.partition_key.is_empty() {
+                                *report
+                                    .partition_write_counts
+                                    .entry((table_id, PartitionKey::from(w.partition_key.as_str())))
+                                    .or_default() += 1;
+                            }
+
+                            if let Some(min) = batch.timestamp_summary().and_then(|v| v.stats.min) {
+                                report.oldest_timestamp_nanos =
+                                    Some(report.oldest_timestamp_nanos.map_or(min, |v| v.min(min)));
+                            }
+                        }
+                    }
+                    WalOp::Delete(_) => report.n_delete_ops += 1,
+                    WalOp::Persist(_) => report.n_persist_ops += 1,
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dml::DmlWrite;
+    use generated_types::influxdata::iox::wal::v1::PersistOp;
+    use mutable_batch_lp::lines_to_batches;
+
+    use super::*;
+    use crate::{SequencedWalOp, Wal};
+
+    #[tokio::test]
+    async fn test_report() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let wal = Wal::new(dir.path()).await.unwrap();
+
+        wal.write_op(SequencedWalOp {
+            table_write_sequence_numbers: [(TableId::new(0), 4), (TableId::new(1), 5)]
+                .into_iter()
+                .collect(),
+            op: WalOp::Write(test_data("m1,t=foo v=1i 42\nm2,t=bar v=2i 24")),
+        });
+        wal.write_op(SequencedWalOp {
+            table_write_sequence_numbers: [(TableId::new(0), 7)].into_iter().collect(),
+            op: WalOp::Persist(PersistOp {
+                namespace_id: 1,
+                parquet_file_uuid: "b4N4N4Z".into(),
+                partition_id: 43,
+                table_id: 0,
+            }),
+        })
+        .changed()
+        .await
+        .unwrap();
+
+        let (closed, _) = wal.rotate().unwrap();
+
+        // Add a file that must be ignored.
+        std::fs::write(dir.path().join("bananas.txt"), b"bananas").unwrap();
+
+        let report = WalReport::from_directory(dir.path()).unwrap();
+
+        // The closed segment, and the newly opened (empty) segment.
+        assert_eq!(report.segments.len(), 2);
+        assert_eq!(report.total_bytes(), closed.size() + 16);
+
+        let segment = &report.segments[0];
+        assert_eq!(segment.id, closed.id());
+        assert_eq!(segment.size, closed.size());
+        assert_eq!(segment.n_batches, 1);
+        assert_eq!(segment.n_write_ops, 1);
+        assert_eq!(segment.n_delete_ops, 0);
+        assert_eq!(segment.n_persist_ops, 1);
+        assert_eq!(segment.min_sequence_number, Some(SequenceNumber::new(4)));
+        assert_eq!(segment.max_sequence_number, Some(SequenceNumber::new(7)));
+        assert_eq!(segment.oldest_timestamp_nanos, Some(24));
+        assert_eq!(segment.error, None);
+
+        let empty = &report.segments[1];
+        assert_eq!(empty.n_batches, 0);
+        assert_eq!(empty.min_sequence_number, None);
+
+        assert_eq!(
+            report.sequence_number_range(),
+            Some((SequenceNumber::new(4), SequenceNumber::new(7)))
+        );
+        assert_eq!(
+            report.table_write_counts(),
+            [(TableId::new(0), 1), (TableId::new(1), 1)]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(report.oldest_timestamp_nanos(), Some(24));
+    }
+
+    fn test_data(lp: &str) -> generated_types::influxdata::pbdata::v1::DatabaseBatch {
+        let batches = lines_to_batches(lp, 0).unwrap();
+        let batches = batches
+            .into_iter()
+            .enumerate()
+            .map(|(i, (_table_name, batch))| (TableId::new(i as _), batch))
+            .collect();
+
+        let write = DmlWrite::new(
+            data_types::NamespaceId::new(1),
+            batches,
+            "bananas".into(),
+            Default::default(),
+        );
+
+        mutable_batch_pb::encode::encode_write(1, &write)
+    }
+}
@@ -0,0 +1,484 @@
+//! Mirroring of closed WAL segments to object storage.
+//!
+//! A [`SegmentShipper`] uploads each closed segment file of a [`Wal`] to an
+//! object store prefix in the background, and removes the uploaded copy once
+//! the segment has been persisted and deleted locally. Should the local disk
+//! of a node be lost, the un-persisted segments can be recovered by calling
+//! [`SegmentShipper::restore()`] before initialising a new [`Wal`].
+//!
+//! [`Wal`]: crate::Wal
+
+use std::{
+    ops::ControlFlow,
+    path::{Path as StdPath, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use backoff::{Backoff, BackoffConfig};
+use futures::{TryFutureExt, TryStreamExt};
+use object_store::{path::Path, ObjectStore};
+use observability_deps::tracing::{debug, info, warn};
+use snafu::prelude::*;
+use tokio::{
+    io::AsyncWriteExt,
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+};
+
+use crate::{build_segment_path, ClosedSegment, SegmentId, SEGMENT_FILE_EXTENSION};
+
+/// Errors that occur when shipping segments to, or restoring segments from,
+/// object storage.
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("unable to read segment file {}: {source}", path.display()))]
+    ReadSegment {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("unable to upload segment file {} to {object}: {source}", path.display()))]
+    UploadSegment {
+        source: std::io::Error,
+        path: PathBuf,
+        object: Path,
+    },
+
+    #[snafu(display("unable to write restored segment file {}: {source}", path.display()))]
+    WriteSegment {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("object store request for {path} failed: {source}"))]
+    ObjectStore {
+        source: object_store::Error,
+        path: Path,
+    },
+
+    #[snafu(display("unexpected object {path} in WAL shipping prefix"))]
+    UnexpectedObject { path: Path },
+}
+
+/// A specialized `Result` for WAL shipping errors.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An operation processed by the background uploader task.
+#[derive(Debug)]
+enum Op {
+    /// Upload the closed segment.
+    Upload(ClosedSegment),
+
+    /// Delete the shipped copy of a segment, reporting the outcome on `done`.
+    Remove {
+        id: SegmentId,
+        done: oneshot::Sender<Result<()>>,
+    },
+}
+
+/// Uploads closed WAL segments to an object store prefix in the background.
+///
+/// Segments are enqueued for upload with [`SegmentShipper::enqueue()`] and
+/// uploaded by a background task in the order they were enqueued, streaming
+/// each segment file to the object store. Failed uploads and deletions are
+/// retried with backoff for at most the configured retry deadline, after
+/// which an upload is abandoned and a deletion reports its error to the
+/// caller. Dropping the [`SegmentShipper`] stops the background task,
+/// discarding any segments not yet uploaded.
+pub struct SegmentShipper {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    backoff_config: BackoffConfig,
+
+    queue_tx: mpsc::UnboundedSender<Op>,
+    uploader_task: JoinHandle<()>,
+}
+
+impl SegmentShipper {
+    /// Construct a new [`SegmentShipper`] that mirrors segments to `store`
+    /// under `prefix`, spawning the background uploader task.
+    ///
+    /// Each failed upload or deletion is retried for at most
+    /// `retry_deadline`.
+    pub fn new(store: Arc<dyn ObjectStore>, prefix: Path, retry_deadline: Duration) -> Self {
+        let backoff_config = BackoffConfig {
+            deadline: Some(retry_deadline),
+            ..Default::default()
+        };
+
+        let (queue_tx, queue_rx) = mpsc::unbounded_channel();
+
+        let uploader_task = tokio::spawn(upload_task(
+            Arc::clone(&store),
+            prefix.clone(),
+            backoff_config.clone(),
+            queue_rx,
+        ));
+
+        Self {
+            store,
+            prefix,
+            backoff_config,
+            queue_tx,
+            uploader_task,
+        }
+    }
+
+    /// Enqueue the closed `segment` for upload by the background task.
+    ///
+    /// Uploading an already-shipped segment overwrites the existing copy.
+    pub fn enqueue(&self, segment: ClosedSegment) {
+        // The receiver is only dropped when the uploader task is aborted in
+        // the Drop impl, after which no further segments can be enqueued.
+        let _ = self.queue_tx.send(Op::Upload(segment));
+    }
+
+    /// Delete the shipped copy of the segment identified by `id`, if any.
+    ///
+    /// This should be called once the segment data has been persisted and is
+    /// no longer needed for recovery.
+    ///
+    /// The deletion is processed by the background task after all previously
+    /// enqueued uploads, so an in-progress upload of the segment cannot
+    /// recreate the object after it was deleted. As uploads and deletions are
+    /// retried for a bounded time only, this returns an error rather than
+    /// waiting indefinitely while the object store is unavailable.
+    pub async fn remove(&self, id: SegmentId) -> Result<()> {
+        let (done, rx) = oneshot::channel();
+        if self.queue_tx.send(Op::Remove { id, done }).is_ok() {
+            if let Ok(res) = rx.await {
+                return res;
+            }
+        }
+
+        // The uploader task is gone, so no upload can race with this delete.
+        delete_with_retry(&*self.store, &self.prefix, &self.backoff_config, id).await
+    }
+
+    /// List the IDs of all segments shipped to the object store, in ascending
+    /// order.
+    pub async fn shipped_segments(&self) -> Result<Vec<SegmentId>> {
+        let mut ids = self
+            .store
+            .list(Some(&self.prefix))
+            .map_err(|source| Error::ObjectStore {
+                source,
+                path: self.prefix.clone(),
+            })
+            .and_then(|meta| async move { segment_id(&meta.location) })
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    /// Download all shipped segments into the WAL directory `dir`, returning
+    /// the IDs of the restored segments.
+    ///
+    /// This must be called before a [`Wal`] is initialised in `dir`, so the
+    /// restored segments are replayed as closed segments.
+    ///
+    /// [`Wal`]: crate::Wal
+    pub async fn restore(&self, dir: impl AsRef<StdPath> + Send) -> Result<Vec<SegmentId>> {
+        let dir = dir.as_ref();
+        let ids = self.shipped_segments().await?;
+
+        for &id in &ids {
+            let object = object_path(&self.prefix, id);
+            let data = self
+                .store
+                .get(&object)
+                .and_then(|v| v.bytes())
+                .await
+                .context(ObjectStoreSnafu { path: object })?;
+
+            let path = build_segment_path(dir, id);
+            tokio::fs::write(&path, data)
+                .await
+                .context(WriteSegmentSnafu { path })?;
+        }
+
+        info!(n_segments=ids.len(), wal_dir=?dir, "restored shipped WAL segments");
+
+        Ok(ids)
+    }
+}
+
+impl Drop for SegmentShipper {
+    fn drop(&mut self) {
+        self.uploader_task.abort();
+    }
+}
+
+impl std::fmt::Debug for SegmentShipper {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SegmentShipper")
+            .field("store", &self.store)
+            .field("prefix", &self.prefix)
+            .field("backoff_config", &self.backoff_config)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Process each operation received over `queue_rx` in order, retrying failed
+/// uploads and deletions with backoff until the deadline of `backoff_config`.
+///
+/// An upload that does not succeed within the deadline is abandoned, so a
+/// persistent object store outage cannot block the deletions queued behind
+/// it. The abandoned segment is missing from a later
+/// [`SegmentShipper::restore()`] until it is shipped again.
+async fn upload_task(
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    backoff_config: BackoffConfig,
+    mut queue_rx: mpsc::UnboundedReceiver<Op>,
+) {
+    while let Some(op) = queue_rx.recv().await {
+        match op {
+            Op::Upload(segment) => {
+                let (store, prefix, segment) = (&*store, &prefix, &segment);
+
+                let res = Backoff::new(&backoff_config)
+                    .retry_with_backoff("ship WAL segment", || async move {
+                        match upload(store, prefix, segment).await {
+                            Ok(()) => {
+                                debug!(segment_id=%segment.id(), "shipped closed WAL segment");
+                                ControlFlow::Break(())
+                            }
+                            Err(Error::ReadSegment { source, .. })
+                                if source.kind() == std::io::ErrorKind::NotFound =>
+                            {
+                                // The segment was persisted and deleted before
+                                // it was shipped, so there is nothing left to
+                                // recover.
+                                debug!(segment_id=%segment.id(), "skipping shipping of deleted WAL segment");
+                                ControlFlow::Break(())
+                            }
+                            Err(e) => ControlFlow::Continue(e),
+                        }
+                    })
+                    .await;
+
+                if let Err(e) = res {
+                    warn!(segment_id=%segment.id(), error=%e, "giving up shipping WAL segment");
+                }
+            }
+            Op::Remove { id, done } => {
+                let res = delete_with_retry(&*store, &prefix, &backoff_config, id).await;
+
+                // The caller may have stopped waiting, which is fine.
+                let _ = done.send(res);
+            }
+        }
+    }
+}
+
+/// Stream the file of `segment` to the object store, without buffering the
+/// whole segment in memory.
+async fn upload(store: &dyn ObjectStore, prefix: &Path, segment: &ClosedSegment) -> Result<()> {
+    let mut file = tokio::fs::File::open(&segment.path)
+        .await
+        .context(ReadSegmentSnafu {
+            path: &segment.path,
+        })?;
+
+    let object = object_path(prefix, segment.id());
+    let (upload_id, mut writer) = store
+        .put_multipart(&object)
+        .await
+        .context(ObjectStoreSnafu {
+            path: object.clone(),
+        })?;
+
+    let res = async {
+        tokio::io::copy(&mut file, &mut writer).await?;
+        writer.shutdown().await
+    }
+    .await;
+
+    if let Err(source) = res {
+        // Clean up the parts uploaded so far, on a best-effort basis.
+        let _ = store.abort_multipart(&object, &upload_id).await;
+        return Err(Error::UploadSegment {
+            source,
+            path: segment.path.clone(),
+            object,
+        });
+    }
+
+    Ok(())
+}
+
+/// Delete the shipped copy of the segment identified by `id`, returning the
+/// last error once the deadline of `backoff_config` has passed.
+async fn delete_with_retry(
+    store: &dyn ObjectStore,
+    prefix: &Path,
+    backoff_config: &BackoffConfig,
+    id: SegmentId,
+) -> Result<()> {
+    Backoff::new(backoff_config)
+        .retry_all_errors("delete shipped WAL segment", || delete(store, prefix, id))
+        .await
+        .map_err(|backoff::BackoffError::DeadlineExceeded { source, .. }| source)
+}
+
+async fn delete(store: &dyn ObjectStore, prefix: &Path, id: SegmentId) -> Result<()> {
+    let path = object_path(prefix, id);
+    match store.delete(&path).await {
+        Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+        Err(source) => Err(Error::ObjectStore { source, path }),
+    }
+}
+
+fn object_path(prefix: &Path, id: SegmentId) -> Path {
+    prefix.child(format!("{id}.{SEGMENT_FILE_EXTENSION}"))
+}
+
+fn segment_id(path: &Path) -> Result<SegmentId> {
+    path.filename()
+        .and_then(|name| name.strip_suffix(SEGMENT_FILE_EXTENSION))
+        .and_then(|name| name.strip_suffix('.'))
+        .and_then(|id| id.parse().ok())
+        .map(SegmentId::new)
+        .context(UnexpectedObjectSnafu { path: path.clone() })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+    use object_store::{local::LocalFileSystem, memory::InMemory};
+
+    use super::*;
+
+    #[test]
+    fn test_segment_id_round_trip() {
+        let prefix = Path::from("wal/ingester-0");
+        let path = object_path(&prefix, SegmentId::new(42));
+        assert_eq!(path.as_ref(), "wal/ingester-0/42.dat");
+        assert_eq!(segment_id(&path).unwrap(), SegmentId::new(42));
+
+        assert!(segment_id(&Path::from("wal/bananas.dat")).is_err());
+        assert!(segment_id(&Path::from("wal/42.parquet")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ship_restore_remove() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let prefix = Path::from("wal");
+
+        let shipper = Arc::new(SegmentShipper::new(
+            Arc::clone(&store),
+            prefix,
+            Duration::from_secs(5),
+        ));
+
+        let wal = crate::Wal::new_with_shipper(
+            dir.path(),
+            crate::WalConfig::default(),
+            Arc::clone(&shipper),
+        )
+        .await
+        .unwrap();
+
+        let (first, _) = wal.rotate().unwrap();
+        let (second, _) = wal.rotate().unwrap();
+
+        // Wait for the background task to ship both segments.
+        let want = vec![first.id(), second.id()];
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while shipper.shipped_segments().await.unwrap() != want {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("segments were not shipped");
+
+        // Deleting a (persisted) segment removes the shipped copy.
+        wal.delete(first.id()).await.unwrap();
+        assert_eq!(shipper.shipped_segments().await.unwrap(), [second.id()]);
+
+        // The remaining segment can be restored into an empty WAL directory.
+        let restore_dir = test_helpers::tmp_dir().unwrap();
+        let restored = shipper.restore(restore_dir.path()).await.unwrap();
+        assert_eq!(restored, [second.id()]);
+        assert_eq!(
+            std::fs::read(build_segment_path(restore_dir.path(), second.id())).unwrap(),
+            std::fs::read(build_segment_path(dir.path(), second.id())).unwrap(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_after_enqueue() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let store: Arc<dyn ObjectStore> = Arc::new(InMemory::new());
+        let prefix = Path::from("wal");
+
+        let shipper = Arc::new(SegmentShipper::new(
+            Arc::clone(&store),
+            prefix,
+            Duration::from_secs(5),
+        ));
+
+        let wal = crate::Wal::new_with_shipper(
+            dir.path(),
+            crate::WalConfig::default(),
+            Arc::clone(&shipper),
+        )
+        .await
+        .unwrap();
+
+        // Remove the shipped copy while the upload may still be queued or in
+        // progress, without deleting the local file.
+        let (segment, _) = wal.rotate().unwrap();
+        shipper.remove(segment.id()).await.unwrap();
+
+        // The upload must not recreate the object after its removal.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(shipper.shipped_segments().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_store_unavailable() {
+        let dir = test_helpers::tmp_dir().unwrap();
+
+        // Rooting the store at a regular file makes every request fail.
+        let store_root = test_helpers::tmp_file().unwrap();
+        let store: Arc<dyn ObjectStore> =
+            Arc::new(LocalFileSystem::new_with_prefix(store_root.path()).unwrap());
+
+        let shipper = Arc::new(SegmentShipper::new(
+            store,
+            Path::from("wal"),
+            Duration::from_millis(200),
+        ));
+
+        let wal = crate::Wal::new_with_shipper(
+            dir.path(),
+            crate::WalConfig::default(),
+            Arc::clone(&shipper),
+        )
+        .await
+        .unwrap();
+
+        let (segment, _) = wal.rotate().unwrap();
+
+        // The failing upload and deletion are given up on, instead of blocking
+        // the delete forever.
+        let err = tokio::time::timeout(Duration::from_secs(5), wal.delete(segment.id()))
+            .await
+            .expect("delete did not complete")
+            .unwrap_err();
+        assert_matches!(err, crate::Error::DeleteShippedSegment { .. });
+
+        // The local segment is kept, as its shipped copy may still exist.
+        assert!(segment.path.exists());
+        assert_eq!(wal.closed_segments().len(), 1);
+    }
+}